@@ -0,0 +1,289 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::Path,
+    process::{Command, Output},
+};
+
+use reader::descriptor::ClassName;
+
+use crate::Opts;
+
+/// Number of lines printed around the first difference of the outputs.
+const CONTEXT_LINES: usize = 3;
+
+/// Run the program on BlazeVM and on a host JVM, and compare their standard
+/// output and exit status.
+///
+/// Only the first difference of the outputs is reported, the following lines
+/// being usually a consequence of it. The arguments of the main method are then
+/// minimized, to the fewest of them still making the runs differ.
+/// Returns the exit code of the launcher: 0 if the runs match, 1 otherwise.
+///
+/// The host JVM runs the main class of a jar from its classpath, as java ignores
+/// the `-cp` option given with `-jar`.
+pub fn run(
+    opts: &Opts,
+    java: &Path,
+    host_classpath: Option<&str>,
+    jar_main_class: Option<&ClassName>,
+) -> Result<i32, String> {
+    let host_args = host_args(opts, host_classpath, jar_main_class)?;
+    let blazevm = run_blazevm(opts, &opts.main_args)?;
+    let host = run_host(java, &host_args, &opts.main_args)?;
+
+    let mut matching = true;
+    let (status, host_status) = (blazevm.status.code(), host.status.code());
+    if status != host_status {
+        matching = false;
+        println!(
+            "Exit status differs: blazevm {}, {} {}",
+            describe_status(status),
+            java.display(),
+            describe_status(host_status)
+        );
+    }
+    let stdout = String::from_utf8_lossy(&blazevm.stdout);
+    let host_stdout = String::from_utf8_lossy(&host.stdout);
+    if let Some(report) = first_difference(&stdout, &host_stdout) {
+        matching = false;
+        print!("{}", report);
+    }
+    if matching {
+        println!("Same output and exit status.");
+        return Ok(0);
+    }
+    // The failures of the VM are explained by its logs.
+    if !blazevm.stderr.is_empty() {
+        println!("BlazeVM standard error:");
+        print!("{}", String::from_utf8_lossy(&blazevm.stderr));
+    }
+    if !opts.main_args.is_empty() {
+        let minimal = minimize_args(&opts.main_args, |args| {
+            let blazevm = run_blazevm(opts, args)?;
+            let host = run_host(java, &host_args, args)?;
+            Ok(differs(&blazevm, &host))
+        })?;
+        if minimal.len() < opts.main_args.len() {
+            println!("Minimal arguments still differing: {:?}", minimal);
+        }
+    }
+    Ok(1)
+}
+
+/// Whether two runs differ, in their exit status or standard output.
+fn differs(output: &Output, expected: &Output) -> bool {
+    output.status.code() != expected.status.code() || output.stdout != expected.stdout
+}
+
+/// Remove the arguments of the main method one at a time, as long as the runs
+/// still differ without it.
+///
+/// The result is 1-minimal: removing any other argument makes the runs match.
+fn minimize_args<F>(args: &[String], mut differs: F) -> Result<Vec<String>, String>
+where
+    F: FnMut(&[String]) -> Result<bool, String>,
+{
+    let mut args = args.to_vec();
+    let mut index = 0;
+    while index < args.len() {
+        let mut candidate = args.clone();
+        candidate.remove(index);
+        if differs(&candidate)? {
+            args = candidate;
+        } else {
+            index += 1;
+        }
+    }
+    Ok(args)
+}
+
+/// Run the program with this launcher, without the logs of the VM but its errors.
+fn run_blazevm(opts: &Opts, main_args: &[String]) -> Result<Output, String> {
+    let launcher = env::current_exe().map_err(|e| format!("cannot find the launcher: {}", e))?;
+    let mut command = Command::new(launcher);
+    for classpath in opts.classpath.iter() {
+        command.arg("--classpath").arg(classpath);
+    }
+    command.args(assertion_args(opts));
+    if let Some(jar) = opts.jar.as_deref() {
+        command.arg("--jar").arg(jar);
+    }
+    if let Some(main_class) = opts.main_class.as_ref() {
+        command.arg(main_class.as_binary_name());
+    }
+    if !main_args.is_empty() {
+        command.arg("--main-args").args(main_args);
+    }
+    command
+        .env("RUST_LOG", "error")
+        .output()
+        .map_err(|e| format!("cannot run blazevm: {}", e))
+}
+
+/// Run the program on the host JVM, with the options given by [host_args].
+fn run_host(java: &Path, host_args: &[OsString], main_args: &[String]) -> Result<Output, String> {
+    Command::new(java)
+        .args(host_args)
+        .args(main_args)
+        .output()
+        .map_err(|e| format!("cannot run {}: {}", java.display(), e))
+}
+
+/// The options of the host JVM, up to the main class, with the classpath of
+/// BlazeVM by default.
+///
+/// A jar is appended to the classpath, after its entries as in BlazeVM, and its
+/// main class given explicitly.
+fn host_args(
+    opts: &Opts,
+    host_classpath: Option<&str>,
+    jar_main_class: Option<&ClassName>,
+) -> Result<Vec<OsString>, String> {
+    let mut paths = match host_classpath {
+        Some(classpath) => env::split_paths(classpath).collect::<Vec<_>>(),
+        // The wildcards are left to the host JVM.
+        None => opts.classpath.iter().flat_map(env::split_paths).collect(),
+    };
+    paths.extend(opts.jar.clone());
+    let classpath = env::join_paths(paths).map_err(|e| format!("invalid classpath: {}", e))?;
+
+    let mut args = vec![OsString::from("-cp"), classpath];
+    args.extend(assertion_args(opts).into_iter().map(OsString::from));
+    let main_class = opts.main_class.as_ref().or(jar_main_class);
+    if let Some(main_class) = main_class {
+        args.push(main_class.as_binary_name().replace('/', ".").into());
+    }
+    Ok(args)
+}
+
+/// The assertion options, as given to java (e.g. `-ea:com.example...`).
+fn assertion_args(opts: &Opts) -> Vec<String> {
+    opts.assertions
+        .iter()
+        .map(|(enabled, scope)| format!("-{}{}", if *enabled { "ea" } else { "da" }, scope))
+        .collect()
+}
+
+fn describe_status(status: Option<i32>) -> String {
+    match status {
+        Some(code) => code.to_string(),
+        None => "killed by a signal".to_string(),
+    }
+}
+
+/// Describe the first line that differs between two outputs, with the lines
+/// preceding it, or None if the outputs are the same.
+fn first_difference(output: &str, expected: &str) -> Option<String> {
+    if output == expected {
+        return None;
+    }
+    let lines: Vec<&str> = output.split_inclusive('\n').collect();
+    let expected_lines: Vec<&str> = expected.split_inclusive('\n').collect();
+    let line = lines
+        .iter()
+        .zip(expected_lines.iter())
+        .position(|(line, expected)| line != expected)
+        .unwrap_or(lines.len().min(expected_lines.len()));
+
+    let mut report = format!("Standard output differs at line {}:\n", line + 1);
+    for context in &lines[line.saturating_sub(CONTEXT_LINES)..line] {
+        report.push_str(&format!("  {}", with_newline(context)));
+    }
+    match lines.get(line) {
+        Some(found) => report.push_str(&format!("- {}", with_newline(found))),
+        None => report.push_str("- (end of output)\n"),
+    }
+    match expected_lines.get(line) {
+        Some(expected) => report.push_str(&format!("+ {}", with_newline(expected))),
+        None => report.push_str("+ (end of output)\n"),
+    }
+    Some(report)
+}
+
+/// Terminate a line, the last line of an output possibly lacking its newline.
+fn with_newline(line: &str) -> String {
+    if line.ends_with('\n') {
+        line.to_string()
+    } else {
+        format!("{}\n", line)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn minimized_args() {
+        let args: Vec<String> = ["-v", "a", "b", "-q", "c"].map(String::from).to_vec();
+        // The runs differ when both "b" and "c" are given.
+        let mut runs = 0;
+        let minimal = minimize_args(&args, |args| {
+            runs += 1;
+            Ok(args.contains(&"b".to_string()) && args.contains(&"c".to_string()))
+        });
+        assert_eq!(minimal.unwrap(), ["b", "c"]);
+        assert_eq!(runs, 5);
+
+        let minimal = minimize_args(&args, |_| Ok(false));
+        assert_eq!(minimal.unwrap(), args);
+        let minimal = minimize_args(&args, |_| Err("cannot run java".to_string()));
+        assert_eq!(minimal.unwrap_err(), "cannot run java");
+    }
+
+    #[test]
+    fn first_differences() {
+        assert_eq!(first_difference("a\nb\n", "a\nb\n"), None);
+        assert_eq!(
+            first_difference("1\n2\n3\n4\n5\n", "1\n2\n3\n4\nfive\n").unwrap(),
+            "Standard output differs at line 5:\n  2\n  3\n  4\n- 5\n+ five\n"
+        );
+        assert_eq!(
+            first_difference("a\n", "a\nb").unwrap(),
+            "Standard output differs at line 2:\n  a\n- (end of output)\n+ b\n"
+        );
+    }
+
+    #[test]
+    fn host_jvm_args() {
+        let args = |opts: &[&str], host_classpath, jar_main_class: Option<&str>| {
+            let opts = Opts::parse_from(["blazevm"].iter().chain(opts));
+            let jar_main_class = jar_main_class.map(|name| crate::parse_main_class(name).unwrap());
+            host_args(&opts, host_classpath, jar_main_class.as_ref()).unwrap()
+        };
+        let classpath = |paths: &[&str]| env::join_paths(paths).unwrap();
+
+        assert_eq!(
+            args(
+                &["-c", "classes", "-c", "lib/*", "com/example/Main"],
+                None,
+                None
+            ),
+            [
+                "-cp".into(),
+                classpath(&["classes", "lib/*"]),
+                "com.example.Main".into()
+            ]
+        );
+        assert_eq!(
+            args(&["-c", "classes", "Main"], Some("host"), None),
+            ["-cp".into(), classpath(&["host"]), "Main".into()]
+        );
+        // The jar is run from the classpath, java ignoring -cp with -jar.
+        assert_eq!(
+            args(
+                &["-c", "classes", "--jar", "app.jar"],
+                None,
+                Some("app.Main")
+            ),
+            [
+                "-cp".into(),
+                classpath(&["classes", "app.jar"]),
+                "app.Main".into()
+            ]
+        );
+    }
+}
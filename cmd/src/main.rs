@@ -1,80 +1,589 @@
-use std::{path::Path, process::exit};
+use std::{
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::exit,
+};
 
-use clap::Parser;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use pretty_env_logger::env_logger::{Builder, Env};
-use reader::descriptor::{self, ClassName, MethodDescriptor};
+use reader::descriptor::{self, ClassName};
 use vm::{
-    class_loader::{ClassLoader, ClassPathDirEntry},
-    class_manager::LoadedClass,
-    Vm,
+    assertions::{AssertionScope, AssertionStatus},
+    breakpoint::{Breakpoint, BreakpointAction},
+    call_log::{CallFilter, CALL_LOG_TARGET},
+    class_loader::{
+        expand_class_path, ClassLoader, ClassPathDirEntry, ClassPathEntry, ClassPathJarEntry,
+    },
+    class_manager::ClassManager,
+    debug::display_slot,
+    event::{JsonLinesSink, JsonObject},
+    harness::{self, parse_bytecode, parse_local},
+    slot::Slot,
+    thread::Thread,
+    thread_dump::ThreadStack,
+    Vm, VmBuilder, VmError,
 };
 
-const MAIN_METHOD_DESCRIPTOR: MethodDescriptor = MethodDescriptor {
-    return_type: None,
-    parameters: vec![],
-};
+mod diff;
+mod repl;
+
+/// Exit code when the main thread fails, as for an uncaught exception.
+const FAILURE_EXIT_CODE: i32 = 1;
 
 #[derive(Parser, Debug)]
-#[clap(name = "blazevm-cli", version, author, about)]
+#[clap(
+    name = "blazevm-cli",
+    version,
+    author,
+    about,
+    subcommand_negates_reqs = true
+)]
 pub struct Opts {
-    /// The classpath to use
-    #[clap(short, long, default_value = "./classpath")]
+    /// The classpath to use, the CLASSPATH environment variable or ./classpath by default
+    ///
+    /// Each value may list several entries, separated as in the PATH environment
+    /// variable. The entry `dir/*` stands for all the jars of the directory dir.
+    #[clap(short, long, global = true)]
     pub classpath: Vec<String>,
 
+    /// Cache the classfiles read from the classpath in memory, up to this size (in bytes)
+    #[clap(long, value_name = "BYTES")]
+    pub class_cache: Option<usize>,
+
+    /// Limit the memory used by the arrays to this size (in bytes)
+    #[clap(long, value_name = "BYTES")]
+    pub max_heap: Option<usize>,
+
+    /// Collect the garbage after every allocation, to debug the VM (very slow)
+    #[clap(long)]
+    pub gc_stress: bool,
+
+    /// Print the time spent in each phase of the execution at exit
+    #[clap(long)]
+    pub time: bool,
+
+    /// Print the size of the loaded classes and the allocations of their instances at exit
+    #[clap(long)]
+    pub class_stats: bool,
+
+    /// Print the number of executions of each instruction at exit
+    #[clap(long)]
+    pub opcode_stats: bool,
+
+    /// Reject the classes compiled for a Java release newer than this one (e.g. 17)
+    #[clap(long, value_name = "RELEASE")]
+    pub release_compat: Option<u16>,
+
+    /// Accept the classes compiled with the preview features of the newest accepted release
+    #[clap(long)]
+    pub enable_preview: bool,
+
+    /// Make the run reproducible, with identity hash codes seeded by SEED and a simulated clock
+    #[clap(long, value_name = "SEED")]
+    pub deterministic: Option<u64>,
+
+    /// Format of the logs and of the events of the VM, written on the standard error
+    #[clap(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    pub log_format: LogFormat,
+
+    /// Print the stacks of the threads when the main thread fails
+    #[clap(long)]
+    pub dump_on_error: bool,
+
+    /// Enable the assertions of all the classes, of a package and its subpackages
+    /// (PACKAGE...) or of a class, like the -ea[:SCOPE] option of java
+    #[clap(
+        long,
+        value_name = "SCOPE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub enable_assertions: Vec<String>,
+
+    /// Disable the assertions of all the classes, of a package and its subpackages
+    /// (PACKAGE...) or of a class, like the -da[:SCOPE] option of java
+    #[clap(
+        long,
+        value_name = "SCOPE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub disable_assertions: Vec<String>,
+
+    /// The assertion options, enabling or disabling a scope, in the order given
+    #[clap(skip)]
+    pub assertions: Vec<(bool, AssertionScope)>,
+
+    /// Pause on an instruction, given as CLASS.METHOD[(DESCRIPTOR)][:PC] (pc 0 by default),
+    /// and print the stack of the thread
+    #[clap(long = "break", value_name = "LOCATION", value_parser = parse_breakpoint)]
+    pub breakpoints: Vec<BreakpointLocation>,
+
+    /// Log the entries and exits of the methods of the classes matching GLOB,
+    /// e.g. 'com/example/*' (`*` matches any characters, subpackages included)
+    #[clap(long, value_name = "GLOB")]
+    pub log_calls: Vec<String>,
+
+    /// Do not log the calls of the classes matching GLOB, e.g. 'java/*'
+    #[clap(long, value_name = "GLOB")]
+    pub log_calls_exclude: Vec<String>,
+
     /// The class to run
-    #[clap(value_parser=parse_main_class, required = true)]
-    pub main_class: ClassName,
+    #[clap(value_parser=parse_main_class, required_unless_present = "jar")]
+    pub main_class: Option<ClassName>,
+
+    /// Run the Main-Class of a jar, its manifest Class-Path being added to the classpath
+    #[clap(long, value_name = "JAR", conflicts_with = "main_class")]
+    pub jar: Option<PathBuf>,
+
+    /// The arguments given to the main method
+    #[clap(long, num_args = 1.., allow_hyphen_values = true, value_name = "ARGS")]
+    pub main_args: Vec<String>,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable logs
+    Text,
+    /// One JSON object per line, for the tools consuming the VM telemetry
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List the classes available on the classpath
+    List,
+    /// Start an interactive session to load classes and call their static methods
+    Repl,
+    /// Execute raw bytecode, and print its operand stack and local variables
+    Bytecode {
+        /// The bytecode in hexadecimal, e.g. "1a 1b 60" for iload_0, iload_1, iadd
+        code: String,
+
+        /// Number of local variables of the bytecode
+        #[clap(long, default_value_t = 4)]
+        max_locals: usize,
+
+        /// Initial value of the next local variable, a Java literal (42, -1L, 1.5f, 2.0, null, ...)
+        #[clap(long = "local", value_name = "LITERAL", allow_hyphen_values = true)]
+        locals: Vec<String>,
+    },
+    /// Run the program on BlazeVM and on a host JVM, and compare their outputs
+    #[clap(hide = true)]
+    Diff {
+        /// The java launcher of the host JVM
+        #[clap(long, default_value = "java")]
+        java: PathBuf,
+
+        /// The classpath of the host JVM, the classpath of BlazeVM by default
+        #[clap(long, value_name = "CLASSPATH")]
+        host_classpath: Option<String>,
+    },
+}
+
+/// Location of a breakpoint given on the command line.
+#[derive(Debug, Clone)]
+pub struct BreakpointLocation {
+    pub class_name: String,
+    pub method_name: String,
+    pub descriptor: Option<String>,
+    pub pc: usize,
+}
+
+fn parse_breakpoint(input: &str) -> Result<BreakpointLocation, String> {
+    let (method, pc) = match input.rsplit_once(':') {
+        Some((method, pc)) => (
+            method,
+            pc.parse()
+                .map_err(|e| format!("invalid pc {}: {}", pc, e))?,
+        ),
+        None => (input, 0),
+    };
+    let (method, descriptor) = match method.find('(') {
+        Some(start) => (&method[..start], Some(method[start..].to_string())),
+        None => (method, None),
+    };
+    let Some((class_name, method_name)) = method.rsplit_once('.') else {
+        return Err(format!("expected CLASS.METHOD, got {}", method));
+    };
+    Ok(BreakpointLocation {
+        class_name: class_name.replace('.', "/"),
+        method_name: method_name.to_string(),
+        descriptor,
+        pc,
+    })
+}
+
+/// Print the location and the stack of the threads reaching a breakpoint.
+fn print_breakpoint(
+    breakpoint: &Breakpoint,
+    thread: &Thread,
+    cm: &ClassManager,
+) -> BreakpointAction {
+    let stack = ThreadStack::of(cm, cm.current_thread, thread);
+    eprintln!("Breakpoint reached at pc {}", breakpoint.pc);
+    eprint!("{}", stack);
+    BreakpointAction::Continue
 }
 
 fn parse_main_class(input: &str) -> Result<ClassName, descriptor::DescriptorError> {
     descriptor::parse_class_name(input.trim())
 }
 
+/// Accept the `-jar`, `-ea` and `-da` options of the java launcher as aliases
+/// of `--jar`, `--enable-assertions` and `--disable-assertions`.
+///
+/// The arguments of the main method are left untouched.
+fn launcher_args() -> Vec<String> {
+    let mut main_args = false;
+    std::env::args()
+        .map(|arg| {
+            main_args |= arg == "--main-args";
+            if main_args {
+                return arg;
+            }
+            let (option, scope) = match arg.split_once(':') {
+                Some((option, scope)) => (option, Some(scope)),
+                None => (arg.as_str(), None),
+            };
+            let alias = match option {
+                "-jar" if scope.is_none() => "--jar",
+                "-ea" | "-enableassertions" => "--enable-assertions",
+                "-da" | "-disableassertions" => "--disable-assertions",
+                _ => return arg,
+            };
+            match scope {
+                Some(scope) => format!("{}={}", alias, scope),
+                None => alias.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Get the assertion options in the order they were given, as java applies them.
+fn assertion_options(matches: &ArgMatches) -> Vec<(bool, AssertionScope)> {
+    let mut options = Vec::new();
+    for (id, enabled) in [("enable_assertions", true), ("disable_assertions", false)] {
+        let (Some(scopes), Some(indices)) =
+            (matches.get_many::<String>(id), matches.indices_of(id))
+        else {
+            continue;
+        };
+        options.extend(indices.zip(scopes.map(|scope| (enabled, AssertionScope::parse(scope)))));
+    }
+    options.sort_by_key(|(index, _)| *index);
+    options.into_iter().map(|(_, option)| option).collect()
+}
+
+/// Create the class path entry of a directory or a jar archive.
+fn class_path_entry(path: &Path) -> Result<Box<dyn ClassPathEntry>, String> {
+    if path.is_file() && path.extension().is_some_and(|ext| ext == "jar") {
+        let jar = ClassPathJarEntry::open(path)
+            .map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+        Ok(Box::new(jar))
+    } else {
+        Ok(Box::new(ClassPathDirEntry::new(path)))
+    }
+}
+
+/// Get the classpath used without `--classpath`: the CLASSPATH environment
+/// variable, or `./classpath` if it is not set.
+fn default_class_path() -> Vec<String> {
+    match env::var("CLASSPATH") {
+        Ok(classpath) if !classpath.is_empty() => vec![classpath],
+        _ => vec!["./classpath".to_string()],
+    }
+}
+
+/// Add a jar and the entries of its manifest Class-Path to the classpath.
+///
+/// Returns the Main-Class of the jar.
+fn add_jar(class_loader: &mut ClassLoader, path: &Path) -> Result<ClassName, String> {
+    let jar = ClassPathJarEntry::open(path)
+        .map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+    let manifest = jar
+        .manifest()
+        .map_err(|e| format!("cannot read the manifest of {}: {}", path.display(), e))?
+        .unwrap_or_default();
+    let Some(main_class) = manifest.main_class else {
+        return Err(format!("no main manifest attribute, in {}", path.display()));
+    };
+    let main_class = parse_main_class(&main_class)
+        .map_err(|e| format!("invalid Main-Class {}: {}", main_class, e))?;
+    log::info!("Adding classpath: {}", path.display());
+    class_loader.add_class_path_entry(Box::new(jar));
+    // The Class-Path entries are relative to the directory of the jar.
+    let base = path.parent().unwrap_or(Path::new(""));
+    for entry in manifest.class_path.iter() {
+        let entry_path = base.join(entry);
+        match class_path_entry(&entry_path) {
+            Ok(entry) => {
+                log::info!("Adding classpath: {}", entry_path.display());
+                class_loader.add_class_path_entry(entry);
+            }
+            Err(e) => log::warn!("Ignoring the manifest Class-Path entry {}: {}", entry, e),
+        }
+    }
+    Ok(main_class)
+}
+
+fn list_classes(class_loader: &ClassLoader) -> ! {
+    match class_loader.list_classes() {
+        Ok(classes) => {
+            for class in classes.iter() {
+                println!("{}", class);
+            }
+            exit(0);
+        }
+        Err(e) => {
+            log::error!("Error listing the classpath, cause:\n{}", e);
+            exit(-1);
+        }
+    }
+}
+
+/// Execute raw bytecode, printing the operand stack (the top last) and the
+/// local variables it ends with, or the value it returns.
+fn run_bytecode(cm: &mut ClassManager, code: &str, max_locals: usize, locals: &[String]) -> ! {
+    let outcome = parse_bytecode(code).and_then(|code| {
+        let locals = locals
+            .iter()
+            .map(|local| parse_local(cm, local))
+            .collect::<Result<Vec<_>, _>>()?;
+        harness::run_bytecode(cm, max_locals, locals, &code)
+    });
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            log::error!("Error executing the bytecode, cause:\n{}", e);
+            exit(FAILURE_EXIT_CODE);
+        }
+    };
+    let describe = |slots: &[Slot]| {
+        slots
+            .iter()
+            .map(|slot| display_slot(cm, slot).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if outcome.returned {
+        match outcome.result {
+            Some(result) => println!("returned: {}", display_slot(cm, &result)),
+            None => println!("returned"),
+        }
+    } else {
+        println!("stack: [{}]", describe(&outcome.stack));
+        println!("locals: [{}]", describe(&outcome.locals));
+    }
+    exit(0);
+}
+
+/// Print a thread dump on SIGQUIT (Ctrl+\\), as the JVM does.
+#[cfg(unix)]
+fn dump_on_sigquit(vm: &mut Vm) {
+    vm.set_thread_dump_hook(Box::new(|dump| eprint!("{}", dump)));
+    if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGQUIT, vm.dump_request()) {
+        log::warn!("Cannot handle SIGQUIT, no thread dump available: {}", e);
+    }
+}
+
+/// Initialize the logger, the method calls being logged whatever the RUST_LOG
+/// filter if their logging is enabled.
+fn init_logger(format: LogFormat, log_calls: bool) {
+    let env = Env::default().default_filter_or("info,vm=trace,reader=trace");
+    let mut builder = match format {
+        LogFormat::Text => pretty_env_logger::formatted_builder(),
+        LogFormat::Json => {
+            let mut builder = Builder::new();
+            builder.format(|buf, record| {
+                let mut json = JsonObject::new();
+                json.string("event", "log");
+                json.string("level", record.level().as_str());
+                json.string("target", record.target());
+                json.string("message", &record.args().to_string());
+                writeln!(buf, "{}", json.finish())
+            });
+            builder
+        }
+    };
+    builder.parse_env(env);
+    if log_calls {
+        builder.filter_module(CALL_LOG_TARGET, log::LevelFilter::Info);
+    }
+    builder.init();
+}
+
 fn main() {
-    pretty_env_logger::formatted_builder()
-        .parse_env(Env::default().default_filter_or("info,vm=trace,reader=trace"))
-        .init();
-    let opts: Opts = Opts::parse();
+    let matches = Opts::command().get_matches_from(launcher_args());
+    let mut opts = Opts::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    opts.assertions = assertion_options(&matches);
+    let log_calls = !opts.log_calls.is_empty() || !opts.log_calls_exclude.is_empty();
+    init_logger(opts.log_format, log_calls);
     log::info!("BlazeVM starting up...");
     let mut class_loader = ClassLoader::new();
+    if opts.classpath.is_empty() {
+        opts.classpath = default_class_path();
+    }
     for classpath in opts.classpath.iter() {
-        log::info!("Adding classpath: {}", classpath);
-        let class_path = ClassPathDirEntry::new(classpath);
-        class_loader.add_class_path_entry(Box::new(class_path));
-    }
-    log::info!("Loading Main class: {}", opts.main_class);
-    let mut vm = Vm::new(class_loader);
-    let main_name: String = opts.main_class.as_binary_name();
-    let thread_id = match vm.class_manager_mut().get_or_resolve_class(&main_name) {
-        Ok(main_class) => {
-            log::info!("Main class loaded: {:?}", main_class.id());
-            let LoadedClass::Loaded(main_class) = main_class else {
-                log::error!(
-                    "Main class is not correctly initialized: {:?}",
-                    main_class.id()
-                );
+        let paths = match expand_class_path(classpath) {
+            Ok(paths) => paths,
+            Err(e) => {
+                log::error!("Error expanding the classpath {}, cause:\n{}", classpath, e);
                 exit(-1);
-            };
-            let class_id = main_class.id;
-            let Some((main_method, _)) = main_class.get_method("main", &MAIN_METHOD_DESCRIPTOR)
-            else {
-                log::error!("Main method not found in class: {:?}", &main_class.id);
-                exit(-2);
-            };
-            log::info!("Main method loaded.");
-            let args = vec![];
-            vm.create_thread(&class_id, main_method, args)
+            }
+        };
+        for path in paths.iter() {
+            log::info!("Adding classpath: {}", path.display());
+            match class_path_entry(path) {
+                Ok(entry) => class_loader.add_class_path_entry(entry),
+                Err(e) => {
+                    log::error!("Error adding the classpath, cause:\n{}", e);
+                    exit(-1);
+                }
+            }
+        }
+    }
+    let jar_main_class = match opts.jar.as_deref() {
+        Some(jar) => match add_jar(&mut class_loader, jar) {
+            Ok(main_class) => Some(main_class),
+            Err(e) => {
+                log::error!("Error loading the jar, cause:\n{}", e);
+                exit(-1);
+            }
+        },
+        None => None,
+    };
+    if let Some(max_bytes) = opts.class_cache {
+        class_loader.enable_bytes_cache(max_bytes);
+    }
+    if let Some(Command::List) = opts.command {
+        list_classes(&class_loader);
+    }
+    if let Some(Command::Diff {
+        java,
+        host_classpath,
+    }) = &opts.command
+    {
+        if opts.main_class.is_none() && opts.jar.is_none() {
+            log::error!("A main class or a jar is required to compare the runs");
+            exit(-1);
+        }
+        let host_classpath = host_classpath.as_deref();
+        match diff::run(&opts, java, host_classpath, jar_main_class.as_ref()) {
+            Ok(status) => exit(status),
+            Err(e) => {
+                log::error!("Error comparing the runs, cause:\n{}", e);
+                exit(-1);
+            }
+        }
+    }
+    let mut builder = VmBuilder::new(class_loader)
+        .enable_preview(opts.enable_preview)
+        .gc_stress(opts.gc_stress)
+        .opcode_histogram(opts.opcode_stats);
+    if let Some(release) = opts.release_compat {
+        builder = builder.release_compat(release);
+    }
+    if let Some(max_bytes) = opts.max_heap {
+        builder = builder.max_heap(max_bytes);
+    }
+    if let Some(seed) = opts.deterministic {
+        builder = builder.deterministic(seed);
+    }
+    if !opts.assertions.is_empty() {
+        let mut assertions = AssertionStatus::new();
+        for (enabled, scope) in opts.assertions.iter() {
+            assertions.set(scope, *enabled);
+        }
+        builder = builder.assertions(assertions);
+    }
+    if log_calls {
+        let mut filter = CallFilter::new();
+        for glob in opts.log_calls.iter() {
+            filter = filter.include(glob);
+        }
+        for glob in opts.log_calls_exclude.iter() {
+            filter = filter.exclude(glob);
+        }
+        builder = builder.log_calls(filter);
+    }
+    if opts.log_format == LogFormat::Json {
+        builder = builder.event_sink(Box::new(JsonLinesSink::new(io::stderr())));
+    }
+    let mut vm = builder.build();
+    #[cfg(unix)]
+    dump_on_sigquit(&mut vm);
+    if let Some(Command::Repl) = opts.command {
+        repl::run(&mut vm);
+        exit(0);
+    }
+    if let Some(Command::Bytecode {
+        code,
+        max_locals,
+        locals,
+    }) = &opts.command
+    {
+        run_bytecode(vm.class_manager_mut(), code, *max_locals, locals);
+    }
+    let main_class = opts
+        .main_class
+        .or(jar_main_class)
+        .expect("main class or jar is required without subcommand");
+    for location in opts.breakpoints.iter() {
+        if let Err(e) = vm.set_breakpoint(
+            &location.class_name,
+            &location.method_name,
+            location.descriptor.as_deref(),
+            location.pc,
+        ) {
+            log::error!("Error setting the breakpoint, cause:\n{}", e);
+            exit(-1);
+        }
+    }
+    if !opts.breakpoints.is_empty() {
+        vm.set_breakpoint_hook(Box::new(print_breakpoint));
+    }
+    log::info!("Loading Main class: {}", main_class);
+    let status = match vm.run_main(&main_class.as_binary_name(), &opts.main_args) {
+        Ok(status) => {
+            log::info!("Main thread completed, exit status {}.", status);
+            status
+        }
+        Err(VmError::Execution { source }) => {
+            // The failure itself has been reported by the VM.
+            log::debug!("Main thread failed: {}", source);
+            if opts.dump_on_error {
+                eprint!("{:#}", vm.thread_dump());
+            }
+            FAILURE_EXIT_CODE
+        }
+        Err(e @ VmError::MethodNotFound { .. }) => {
+            log::error!("Main method not found, cause:\n{}", e);
+            exit(-2);
         }
         Err(e) => {
             log::error!("Error loading main class, cause:\n{}", e);
             exit(-1);
         }
     };
-    log::info!("Starting main thread: {}", thread_id);
-    match vm.execute_thread(thread_id) {
-        Ok(()) => log::info!("Main thread finished."),
-        Err(e) => log::error!("Main thread failed: {}", e),
+    if opts.time {
+        eprintln!("{}", vm.timing_report());
+    }
+    if opts.class_stats {
+        eprintln!("{}", vm.class_stats());
+    }
+    if let Some(histogram) = vm.opcode_histogram() {
+        eprintln!("{}", histogram);
+    }
+    if let Some(stats) = vm.class_manager().class_loader.bytes_cache_stats() {
+        log::info!("Classfile cache: {}", stats);
     }
     log::info!("BlazeVM shutting down...");
-    exit(0);
+    exit(status);
 }
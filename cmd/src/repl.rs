@@ -0,0 +1,269 @@
+use std::io::{self, BufRead, Write};
+
+use reader::descriptor::{self, MethodDescriptor};
+use vm::{
+    class::{ClassId, Field},
+    class_manager::{ClassManager, LoadedClass},
+    value::{format_value, parse_literal},
+    Vm,
+};
+
+const HELP: &str = "\
+Commands:
+  load <class>                                Load and initialize a class
+  call <class>.<method>[<descriptor>] [args]  Call a static method, e.g. call Main.add(II)I 1 2
+  get <class>.<field>                         Print the value of a static field
+  describe <class>                            Print the fields and methods of a class
+  help                                        Print this help
+  quit                                        Exit the REPL
+
+Arguments are Java literals: 42, -1L, 1.5f, 2.0, 'c', true, \"text\" or null.
+The descriptor can be omitted if the method name is not overloaded.";
+
+/// Interactive loop, reading the commands from the standard input.
+pub fn run(vm: &mut Vm) {
+    println!("BlazeVM REPL, type `help` for the list of commands.");
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(e) => {
+                eprintln!("error: cannot read the command: {}", e);
+                break;
+            }
+        }
+        let tokens = match tokenize(&line) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                continue;
+            }
+        };
+        let Some((command, args)) = tokens.split_first() else {
+            continue;
+        };
+        let result = match (command.as_str(), args) {
+            ("quit" | "exit", _) => break,
+            ("help", _) => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            ("load", [class_name]) => load(vm, class_name),
+            ("call", [target, args @ ..]) => call(vm, target, args),
+            ("get", [target]) => get(vm, target),
+            ("describe", [class_name]) => describe(vm, class_name),
+            _ => Err(format!("invalid command `{}`, see `help`", line.trim())),
+        };
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+        }
+    }
+}
+
+fn load(vm: &mut Vm, class_name: &str) -> Result<(), String> {
+    let class_name = binary_name(class_name);
+    let class = vm
+        .class_manager_mut()
+        .get_or_resolve_class(&class_name)
+        .map_err(|e| format!("cannot load {}: {}", class_name, e))?;
+    println!("Loaded {} (ClassId({}))", class.name(), class.id().0);
+    Ok(())
+}
+
+fn describe(vm: &mut Vm, class_name: &str) -> Result<(), String> {
+    let summary = vm
+        .describe_class(&binary_name(class_name))
+        .map_err(|e| e.to_string())?;
+    println!("{}", summary);
+    Ok(())
+}
+
+fn call(vm: &mut Vm, target: &str, args: &[String]) -> Result<(), String> {
+    let (member, descriptor) = match target.find('(') {
+        Some(index) => (&target[..index], Some(&target[index..])),
+        None => (target, None),
+    };
+    let (class_name, method_name) = split_member(member)?;
+    let descriptor = match descriptor {
+        Some(descriptor) => descriptor.to_string(),
+        None => find_descriptor(vm, &class_name, method_name)?,
+    };
+    let parsed = descriptor::parse_method_descriptor(&descriptor).map_err(|e| e.to_string())?;
+    if parsed.args_count() != args.len() {
+        return Err(format!(
+            "{}{} expects {} argument(s), got {}",
+            method_name,
+            parsed.java_syntax(),
+            parsed.args_count(),
+            args.len()
+        ));
+    }
+    let names = parameter_names(vm, &class_name, method_name, &descriptor);
+    let mut values = Vec::new();
+    for (index, (field_type, arg)) in parsed.parameters.iter().zip(args).enumerate() {
+        let value =
+            parse_literal(vm.class_manager_mut(), field_type, arg).map_err(|e| {
+                match names.get(index).and_then(|name| name.as_deref()) {
+                    Some(name) => format!("parameter `{}`: {}", name, e),
+                    None => format!("parameter {}: {}", index + 1, e),
+                }
+            })?;
+        values.push(value);
+    }
+    let result = vm
+        .invoke_static(&class_name, method_name, &descriptor, values)
+        .map_err(|e| e.to_string())?;
+    print_result(vm, &parsed, result);
+    Ok(())
+}
+
+fn print_result(vm: &Vm, descriptor: &MethodDescriptor, result: Option<vm::slot::Slot>) {
+    match (&descriptor.return_type, result) {
+        (Some(return_type), Some(value)) => {
+            println!("{}", format_value(vm.class_manager(), return_type, &value))
+        }
+        (Some(_), None) => println!("(no value returned)"),
+        (None, _) => println!("(void)"),
+    }
+}
+
+fn get(vm: &mut Vm, target: &str) -> Result<(), String> {
+    let (class_name, field_name) = split_member(target)?;
+    let class_id = vm
+        .class_manager_mut()
+        .get_or_resolve_class(&class_name)
+        .map_err(|e| format!("cannot load {}: {}", class_name, e))?
+        .id();
+    let cm = vm.class_manager();
+    let field = static_field(cm, class_id, field_name)
+        .ok_or_else(|| format!("static field not found: {}.{}", class_name, field_name))?;
+    let value = field
+        .get_value()
+        .ok_or_else(|| format!("field {}.{} has no value", class_name, field_name))?;
+    println!("{}", format_value(cm, field.descriptor.field_type(), value));
+    Ok(())
+}
+
+/// Look up a static field by its name, in the class then in its superinterfaces and superclass.
+fn static_field<'a>(cm: &'a ClassManager, class_id: ClassId, name: &str) -> Option<&'a Field> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return None;
+    };
+    if let Some(field) = class.get_field(name).filter(|field| field.is_static()) {
+        return Some(field);
+    }
+    class
+        .interfaces
+        .iter()
+        .find_map(|interface| static_field(cm, *interface, name))
+        .or_else(|| static_field(cm, class.superclass?, name))
+}
+
+/// Get the names of the parameters of a method, if the class has been compiled with them.
+fn parameter_names(
+    vm: &mut Vm,
+    class_name: &str,
+    method_name: &str,
+    descriptor: &str,
+) -> Vec<Option<String>> {
+    let cm = vm.class_manager_mut();
+    let Ok(descriptor) = cm.intern_method_descriptor(descriptor) else {
+        return Vec::new();
+    };
+    let Ok(LoadedClass::Loaded(class)) = cm.get_or_resolve_class(class_name) else {
+        return Vec::new();
+    };
+    let Some((_, method)) = class.get_method(method_name, &descriptor) else {
+        return Vec::new();
+    };
+    (0..descriptor.parameters.len())
+        .map(|index| method.parameter_name(index).map(str::to_string))
+        .collect()
+}
+
+/// Find the descriptor of the only static method of a class with the given name.
+fn find_descriptor(vm: &mut Vm, class_name: &str, method_name: &str) -> Result<String, String> {
+    let class = vm
+        .class_manager_mut()
+        .get_or_resolve_class(class_name)
+        .map_err(|e| format!("cannot load {}: {}", class_name, e))?;
+    let LoadedClass::Loaded(class) = class else {
+        return Err(format!("class {} is not loaded", class_name));
+    };
+    let candidates: Vec<_> = class
+        .methods
+        .iter()
+        .filter(|method| method.name == method_name && method.is_static())
+        .map(|method| method.descriptor.as_str().to_string())
+        .collect();
+    match candidates.as_slice() {
+        [descriptor] => Ok(descriptor.clone()),
+        [] => Err(format!(
+            "no static method {} in {}",
+            method_name, class_name
+        )),
+        _ => Err(format!(
+            "{} is overloaded, add one of the descriptors: {}",
+            method_name,
+            candidates.join(", ")
+        )),
+    }
+}
+
+/// Split `<class>.<member>`, the class name being either a binary or a source name.
+fn split_member(target: &str) -> Result<(String, &str), String> {
+    match target.rsplit_once('.') {
+        Some((class_name, member)) if !class_name.is_empty() && !member.is_empty() => {
+            Ok((binary_name(class_name), member))
+        }
+        _ => Err(format!("expected <class>.<member>, got `{}`", target)),
+    }
+}
+
+fn binary_name(class_name: &str) -> String {
+    class_name.replace('.', "/")
+}
+
+/// Split a command line on whitespaces, keeping the quoted literals together.
+///
+/// The quotes and the escape sequences are kept, to be parsed with the literal.
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.trim().chars();
+    let mut token = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                token.push(c);
+                loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            token.push('\\');
+                            token.extend(chars.next());
+                        }
+                        Some(end) if end == c => {
+                            token.push(end);
+                            break;
+                        }
+                        Some(other) => token.push(other),
+                        None => return Err(format!("unterminated literal: {}", token)),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,112 @@
+//! Conformance tests, running Java programs with the `cmd` launcher.
+//!
+//! The fixtures are grouped by feature area in `tests/conformance/<area>`. Each
+//! fixture is a class with a `main` method, along with:
+//! - `<Name>.expected`, the expected standard output,
+//! - `<Name>.status`, the expected exit status, if it is not 0.
+//!
+//! The classes are compiled against the minimal runtime of `tests/conformance/runtime`,
+//! so they run without a JDK:
+//!
+//! ```shell
+//! cd tests/conformance/<area>
+//! javac -source 8 -target 8 -bootclasspath ../runtime -implicit:none <Name>.java
+//! ```
+//!
+//! The expected files come from a reference JVM, e.g. `java -cp . <Name> > <Name>.expected`,
+//! the fixtures being valid programs against the JDK as well.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The feature areas, each one being a directory of fixtures.
+const AREAS: &[&str] = &["arithmetic", "arrays", "inheritance", "exceptions"];
+
+fn conformance_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance")
+}
+
+/// Get the names of the fixtures of an area, in order.
+fn fixtures(area_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(area_dir)
+        .unwrap_or_else(|err| panic!("Cannot read {}: {}", area_dir.display(), err))
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "expected" {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Run a fixture, and describe how its outcome differs from the expected one.
+fn check(area: &str, name: &str) -> Option<String> {
+    let root = conformance_dir();
+    let area_dir = root.join(area);
+    let expected_output = fs::read_to_string(area_dir.join(format!("{}.expected", name)))
+        .expect("the expected output should be readable");
+    let expected_status = match fs::read_to_string(area_dir.join(format!("{}.status", name))) {
+        Ok(status) => status
+            .trim()
+            .parse()
+            .expect("the status should be an integer"),
+        Err(_) => 0,
+    };
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cmd"))
+        .arg("-c")
+        .arg(&area_dir)
+        .arg("-c")
+        .arg(root.join("runtime"))
+        .arg(name)
+        .env("RUST_LOG", "off")
+        .output()
+        .expect("the launcher should start");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let status = output.status.code();
+
+    let mut mismatches = Vec::new();
+    if status != Some(expected_status) {
+        mismatches.push(format!(
+            "exit status {:?}, expected {}",
+            status, expected_status
+        ));
+    }
+    if stdout != expected_output {
+        mismatches.push(format!(
+            "standard output:\n{}\nexpected:\n{}",
+            stdout, expected_output
+        ));
+    }
+    if mismatches.is_empty() {
+        None
+    } else {
+        Some(format!("{}/{}: {}", area, name, mismatches.join("\n")))
+    }
+}
+
+#[test]
+fn conformance() {
+    let mut count = 0;
+    let mut failures = Vec::new();
+    for area in AREAS {
+        for name in fixtures(&conformance_dir().join(area)) {
+            count += 1;
+            failures.extend(check(area, &name));
+        }
+    }
+    assert!(count > 0, "no conformance fixture found");
+    assert!(
+        failures.is_empty(),
+        "{} of {} conformance fixtures failed:\n\n{}",
+        failures.len(),
+        count,
+        failures.join("\n\n")
+    );
+}
@@ -0,0 +1,17 @@
+public class FloatingPoint {
+    public static void main(String[] args) {
+        double x = 2.5;
+        double y = 0.5;
+        // The values are printed as integers, the conversions truncating toward zero.
+        System.out.println((int) (x + y));
+        System.out.println((int) (x * 4));
+        System.out.println((int) (x / y));
+        System.out.println((int) -x);
+        System.out.println((long) (x * 1e10));
+        float f = 7.75f;
+        System.out.println((int) (f - 0.25f));
+        System.out.println(x > y);
+        System.out.println(x < y);
+        System.out.println((int) (float) 3);
+    }
+}
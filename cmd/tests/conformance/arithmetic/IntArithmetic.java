@@ -0,0 +1,42 @@
+public class IntArithmetic {
+    static int factorial(int n) {
+        int result = 1;
+        for (int i = 2; i <= n; i++) {
+            result *= i;
+        }
+        return result;
+    }
+
+    static long factorial(long n) {
+        return n <= 1 ? 1 : n * factorial(n - 1);
+    }
+
+    public static void main(String[] args) {
+        int a = 17;
+        int b = 5;
+        System.out.println(a + b);
+        System.out.println(a - b);
+        System.out.println(b - a);
+        System.out.println(a * b);
+        System.out.println(a / b);
+        System.out.println(a % b);
+        // The division truncates toward zero, the remainder has the sign of the dividend.
+        System.out.println(-a / b);
+        System.out.println(-a % b);
+        System.out.println(a % -b);
+        System.out.println(-a);
+        System.out.println(a & b);
+        System.out.println(a | b);
+        System.out.println(a ^ b);
+        System.out.println(factorial(10));
+        System.out.println(factorial(20L));
+        System.out.println(3000000000L / 7);
+        System.out.println(-2147483648);
+        System.out.println(-9223372036854775808L);
+        char c = 'a';
+        c += 2;
+        System.out.println(c);
+        System.out.println(a > b);
+        System.out.println(a == b);
+    }
+}
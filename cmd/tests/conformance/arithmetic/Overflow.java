@@ -0,0 +1,30 @@
+public class Overflow {
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    static int multiply(int a, int b) {
+        return a * b;
+    }
+
+    static int negate(int a) {
+        return -a;
+    }
+
+    static long subtract(long a, long b) {
+        return a - b;
+    }
+
+    public static void main(String[] args) {
+        // The integer arithmetic wraps around, in two's complement.
+        System.out.println(add(2147483647, 1));
+        System.out.println(multiply(65536, 65536));
+        System.out.println(multiply(2147483647, 3));
+        System.out.println(negate(-2147483648));
+        System.out.println(subtract(-9223372036854775808L, 1L));
+        int counter = 2147483646;
+        counter++;
+        counter++;
+        System.out.println(counter);
+    }
+}
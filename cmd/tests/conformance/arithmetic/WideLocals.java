@@ -0,0 +1,144 @@
+public class WideLocals {
+    public static void main(String[] args) {
+        // The long locals take 260 slots, so the last ones need wide loads and stores.
+        long l0 = 0L;
+        long l1 = 1L;
+        long l2 = 2L;
+        long l3 = 3L;
+        long l4 = 4L;
+        long l5 = 5L;
+        long l6 = 6L;
+        long l7 = 7L;
+        long l8 = 8L;
+        long l9 = 9L;
+        long l10 = 10L;
+        long l11 = 11L;
+        long l12 = 12L;
+        long l13 = 13L;
+        long l14 = 14L;
+        long l15 = 15L;
+        long l16 = 16L;
+        long l17 = 17L;
+        long l18 = 18L;
+        long l19 = 19L;
+        long l20 = 20L;
+        long l21 = 21L;
+        long l22 = 22L;
+        long l23 = 23L;
+        long l24 = 24L;
+        long l25 = 25L;
+        long l26 = 26L;
+        long l27 = 27L;
+        long l28 = 28L;
+        long l29 = 29L;
+        long l30 = 30L;
+        long l31 = 31L;
+        long l32 = 32L;
+        long l33 = 33L;
+        long l34 = 34L;
+        long l35 = 35L;
+        long l36 = 36L;
+        long l37 = 37L;
+        long l38 = 38L;
+        long l39 = 39L;
+        long l40 = 40L;
+        long l41 = 41L;
+        long l42 = 42L;
+        long l43 = 43L;
+        long l44 = 44L;
+        long l45 = 45L;
+        long l46 = 46L;
+        long l47 = 47L;
+        long l48 = 48L;
+        long l49 = 49L;
+        long l50 = 50L;
+        long l51 = 51L;
+        long l52 = 52L;
+        long l53 = 53L;
+        long l54 = 54L;
+        long l55 = 55L;
+        long l56 = 56L;
+        long l57 = 57L;
+        long l58 = 58L;
+        long l59 = 59L;
+        long l60 = 60L;
+        long l61 = 61L;
+        long l62 = 62L;
+        long l63 = 63L;
+        long l64 = 64L;
+        long l65 = 65L;
+        long l66 = 66L;
+        long l67 = 67L;
+        long l68 = 68L;
+        long l69 = 69L;
+        long l70 = 70L;
+        long l71 = 71L;
+        long l72 = 72L;
+        long l73 = 73L;
+        long l74 = 74L;
+        long l75 = 75L;
+        long l76 = 76L;
+        long l77 = 77L;
+        long l78 = 78L;
+        long l79 = 79L;
+        long l80 = 80L;
+        long l81 = 81L;
+        long l82 = 82L;
+        long l83 = 83L;
+        long l84 = 84L;
+        long l85 = 85L;
+        long l86 = 86L;
+        long l87 = 87L;
+        long l88 = 88L;
+        long l89 = 89L;
+        long l90 = 90L;
+        long l91 = 91L;
+        long l92 = 92L;
+        long l93 = 93L;
+        long l94 = 94L;
+        long l95 = 95L;
+        long l96 = 96L;
+        long l97 = 97L;
+        long l98 = 98L;
+        long l99 = 99L;
+        long l100 = 100L;
+        long l101 = 101L;
+        long l102 = 102L;
+        long l103 = 103L;
+        long l104 = 104L;
+        long l105 = 105L;
+        long l106 = 106L;
+        long l107 = 107L;
+        long l108 = 108L;
+        long l109 = 109L;
+        long l110 = 110L;
+        long l111 = 111L;
+        long l112 = 112L;
+        long l113 = 113L;
+        long l114 = 114L;
+        long l115 = 115L;
+        long l116 = 116L;
+        long l117 = 117L;
+        long l118 = 118L;
+        long l119 = 119L;
+        long l120 = 120L;
+        long l121 = 121L;
+        long l122 = 122L;
+        long l123 = 123L;
+        long l124 = 124L;
+        long l125 = 125L;
+        long l126 = 126L;
+        long l127 = 127L;
+        long l128 = 128L;
+        long l129 = 129L;
+        int last = (int) l129;
+        last += 1000;
+        System.out.println(last);
+        l128 = l127 + l129;
+        System.out.println(l128);
+        int counter = 2147483000;
+        counter += 1000;
+        System.out.println(counter);
+        System.out.println(l0 + l1);
+    }
+}
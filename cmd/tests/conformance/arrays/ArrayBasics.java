@@ -0,0 +1,47 @@
+public class ArrayBasics {
+    static int sum(int[] values) {
+        int total = 0;
+        for (int value : values) {
+            total += value;
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        int[] primes = {2, 3, 5, 7, 11, 13};
+        System.out.println(primes.length);
+        System.out.println(sum(primes));
+
+        int[][] grid = new int[3][];
+        for (int i = 0; i < grid.length; i++) {
+            grid[i] = new int[4];
+            for (int j = 0; j < grid[i].length; j++) {
+                grid[i][j] = i * j;
+            }
+        }
+        System.out.println(grid[2][3]);
+        System.out.println(sum(grid[1]));
+
+        long[] longs = new long[2];
+        longs[1] = 1L + 1234567890123L;
+        System.out.println(longs[0]);
+        System.out.println(longs[1]);
+
+        char[] letters = new char[3];
+        for (int i = 0; i < letters.length; i++) {
+            letters[i] = (char) ('x' + i);
+        }
+        System.out.println(new String(letters));
+
+        String[] words = {"alpha", "beta", null};
+        System.out.println(words[1]);
+        System.out.println(words[2]);
+        Object[] objects = words;
+        System.out.println(objects.length);
+
+        boolean[] flags = new boolean[2];
+        flags[1] = true;
+        System.out.println(flags[0]);
+        System.out.println(flags[1]);
+    }
+}
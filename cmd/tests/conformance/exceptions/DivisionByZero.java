@@ -0,0 +1,20 @@
+public class DivisionByZero {
+    static int divide(int dividend, int divisor) {
+        return dividend / divisor;
+    }
+
+    static long remainder(long dividend, long divisor) {
+        return dividend % divisor;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(divide(7, -2));
+        System.out.println(remainder(-7L, 2L));
+        // The overflowing division gives the smallest value back.
+        System.out.println(divide(-2147483648, -1));
+        System.out.println(remainder(-9223372036854775808L, -1L));
+        // The division by zero throws an ArithmeticException, uncaught.
+        System.out.println(divide(1, 0));
+        System.out.println("after");
+    }
+}
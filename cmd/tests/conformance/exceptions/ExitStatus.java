@@ -0,0 +1,7 @@
+public class ExitStatus {
+    public static void main(String[] args) {
+        System.out.println("exiting");
+        System.exit(3);
+        System.out.println("unreachable");
+    }
+}
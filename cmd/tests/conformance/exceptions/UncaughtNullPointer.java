@@ -0,0 +1,11 @@
+public class UncaughtNullPointer {
+    int value;
+
+    public static void main(String[] args) {
+        UncaughtNullPointer missing = null;
+        System.out.println("before");
+        // An uncaught exception ends the program with the status 1.
+        System.out.println(missing.value);
+        System.out.println("after");
+    }
+}
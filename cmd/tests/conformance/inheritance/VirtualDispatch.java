@@ -0,0 +1,83 @@
+public class VirtualDispatch {
+    interface Named {
+        String name();
+
+        default String greeting() {
+            return "Hello, " + name();
+        }
+    }
+
+    abstract static class Shape implements Named {
+        abstract int area();
+
+        public String toString() {
+            return name() + " of area " + area();
+        }
+    }
+
+    static class Rectangle extends Shape {
+        final int width;
+        final int height;
+
+        Rectangle(int width, int height) {
+            this.width = width;
+            this.height = height;
+        }
+
+        int area() {
+            return width * height;
+        }
+
+        public String name() {
+            return "rectangle";
+        }
+    }
+
+    static class Square extends Rectangle {
+        Square(int side) {
+            super(side, side);
+        }
+
+        public String name() {
+            return "square";
+        }
+
+        public String greeting() {
+            return super.greeting() + "!";
+        }
+    }
+
+    static class Counter {
+        static int instances;
+        int value = 10;
+
+        Counter() {
+            instances++;
+        }
+    }
+
+    static class ShadowingCounter extends Counter {
+        int value = 20;
+
+        int parentValue() {
+            return super.value;
+        }
+    }
+
+    public static void main(String[] args) {
+        Shape[] shapes = {new Rectangle(2, 3), new Square(4)};
+        for (Shape shape : shapes) {
+            System.out.println(shape);
+            System.out.println(shape.greeting());
+        }
+        System.out.println(shapes[1] instanceof Rectangle);
+        System.out.println(shapes[0] instanceof Square);
+
+        ShadowingCounter counter = new ShadowingCounter();
+        new Counter();
+        System.out.println(counter.value);
+        System.out.println(((Counter) counter).value);
+        System.out.println(counter.parentValue());
+        System.out.println(Counter.instances);
+    }
+}
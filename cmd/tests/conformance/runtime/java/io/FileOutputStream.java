@@ -0,0 +1,16 @@
+package java.io;
+
+// Minimal java/io/FileOutputStream, writing to a file descriptor of the VM.
+public class FileOutputStream {
+    private final int fd;
+
+    public FileOutputStream(int fd) {
+        this.fd = fd;
+    }
+
+    public void write(byte[] b, int off, int len) {
+        writeBytes(b, off, len, false);
+    }
+
+    private native void writeBytes(byte[] b, int off, int len, boolean append);
+}
@@ -0,0 +1,63 @@
+package java.io;
+
+// Minimal java/io/PrintStream, printing the Latin-1 characters of the strings.
+public class PrintStream {
+    private final FileOutputStream out;
+
+    public PrintStream(FileOutputStream out) {
+        this.out = out;
+    }
+
+    public void print(String s) {
+        if (s == null) {
+            s = "null";
+        }
+        byte[] bytes = new byte[s.length()];
+        for (int i = 0; i < bytes.length; i++) {
+            bytes[i] = (byte) s.charAt(i);
+        }
+        out.write(bytes, 0, bytes.length);
+    }
+
+    public void print(Object obj) {
+        print(String.valueOf(obj));
+    }
+
+    public void print(int i) {
+        print(new StringBuilder().append(i).toString());
+    }
+
+    public void println() {
+        print("\n");
+    }
+
+    public void println(String s) {
+        print(s);
+        println();
+    }
+
+    public void println(Object obj) {
+        print(obj);
+        println();
+    }
+
+    public void println(int i) {
+        print(i);
+        println();
+    }
+
+    public void println(long l) {
+        print(new StringBuilder().append(l).toString());
+        println();
+    }
+
+    public void println(char c) {
+        print(new StringBuilder().append(c).toString());
+        println();
+    }
+
+    public void println(boolean b) {
+        print(b ? "true" : "false");
+        println();
+    }
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Byte, for the boxing conversions.
+public final class Byte {
+    private final byte value;
+
+    public Byte(byte value) {
+        this.value = value;
+    }
+
+    public static Byte valueOf(byte value) {
+        return new Byte(value);
+    }
+
+    public byte byteValue() {
+        return value;
+    }
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Character, for the boxing conversions.
+public final class Character {
+    private final char value;
+
+    public Character(char value) {
+        this.value = value;
+    }
+
+    public static Character valueOf(char value) {
+        return new Character(value);
+    }
+
+    public char charValue() {
+        return value;
+    }
+}
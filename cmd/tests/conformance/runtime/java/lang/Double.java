@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Double, for the boxing conversions.
+public final class Double {
+    private final double value;
+
+    public Double(double value) {
+        this.value = value;
+    }
+
+    public static Double valueOf(double value) {
+        return new Double(value);
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+}
@@ -0,0 +1,10 @@
+package java.lang;
+
+// Minimal java/lang/Exception, to run the conformance fixtures without a JDK.
+public class Exception extends Throwable {
+    public Exception() {}
+
+    public Exception(String message) {
+        super(message);
+    }
+}
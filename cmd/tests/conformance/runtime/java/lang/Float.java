@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Float, for the boxing conversions.
+public final class Float {
+    private final float value;
+
+    public Float(float value) {
+        this.value = value;
+    }
+
+    public static Float valueOf(float value) {
+        return new Float(value);
+    }
+
+    public float floatValue() {
+        return value;
+    }
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Integer, for the boxing conversions.
+public final class Integer {
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int value) {
+        return new Integer(value);
+    }
+
+    public int intValue() {
+        return value;
+    }
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Long, for the boxing conversions.
+public final class Long {
+    private final long value;
+
+    public Long(long value) {
+        this.value = value;
+    }
+
+    public static Long valueOf(long value) {
+        return new Long(value);
+    }
+
+    public long longValue() {
+        return value;
+    }
+}
@@ -0,0 +1,10 @@
+package java.lang;
+
+// Minimal java/lang/NullPointerException, to run the conformance fixtures without a JDK.
+public class NullPointerException extends RuntimeException {
+    public NullPointerException() {}
+
+    public NullPointerException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,17 @@
+package java.lang;
+
+// Minimal java/lang/Object, to run the conformance fixtures without a JDK.
+public class Object {
+    public Object() {}
+
+    public native int hashCode();
+
+    public boolean equals(Object obj) {
+        return this == obj;
+    }
+
+    // The class name is not available without reflection.
+    public String toString() {
+        return "java.lang.Object";
+    }
+}
@@ -0,0 +1,10 @@
+package java.lang;
+
+// Minimal java/lang/RuntimeException, to run the conformance fixtures without a JDK.
+public class RuntimeException extends Exception {
+    public RuntimeException() {}
+
+    public RuntimeException(String message) {
+        super(message);
+    }
+}
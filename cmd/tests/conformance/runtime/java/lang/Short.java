@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Short, for the boxing conversions.
+public final class Short {
+    private final short value;
+
+    public Short(short value) {
+        this.value = value;
+    }
+
+    public static Short valueOf(short value) {
+        return new Short(value);
+    }
+
+    public short shortValue() {
+        return value;
+    }
+}
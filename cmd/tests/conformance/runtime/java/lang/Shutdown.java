@@ -0,0 +1,6 @@
+package java.lang;
+
+// Minimal java/lang/Shutdown, the end of System.exit.
+class Shutdown {
+    static native void halt0(int status);
+}
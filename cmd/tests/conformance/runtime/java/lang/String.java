@@ -0,0 +1,25 @@
+package java.lang;
+
+// Minimal java/lang/String, to run the conformance fixtures without a JDK.
+public final class String {
+    private final char[] value;
+    private int hash;
+
+    public String(char[] value) {
+        this.value = value;
+    }
+
+    // Implemented by the intrinsics of the VM.
+    public native boolean equals(Object anObject);
+    public native int length();
+    public native char charAt(int index);
+    public native int hashCode();
+
+    public String toString() {
+        return this;
+    }
+
+    public static String valueOf(Object obj) {
+        return obj == null ? "null" : obj.toString();
+    }
+}
@@ -0,0 +1,81 @@
+package java.lang;
+
+// Minimal java/lang/StringBuilder, used by javac for the string concatenations.
+public final class StringBuilder {
+    private char[] value = new char[16];
+    private int count;
+
+    public StringBuilder() {}
+
+    public StringBuilder(String str) {
+        append(str);
+    }
+
+    public StringBuilder append(String str) {
+        if (str == null) {
+            str = "null";
+        }
+        for (int i = 0; i < str.length(); i++) {
+            append(str.charAt(i));
+        }
+        return this;
+    }
+
+    public StringBuilder append(Object obj) {
+        return append(String.valueOf(obj));
+    }
+
+    public StringBuilder append(boolean b) {
+        return append(b ? "true" : "false");
+    }
+
+    public StringBuilder append(char c) {
+        if (count == value.length) {
+            char[] grown = new char[value.length * 2];
+            for (int i = 0; i < count; i++) {
+                grown[i] = value[i];
+            }
+            value = grown;
+        }
+        value[count++] = c;
+        return this;
+    }
+
+    public StringBuilder append(int i) {
+        return append((long) i);
+    }
+
+    public StringBuilder append(long l) {
+        if (l == 0) {
+            return append('0');
+        }
+        // The digits are computed on the negative value, as Long.MIN_VALUE has no positive counterpart.
+        if (l > 0) {
+            l = -l;
+        } else {
+            append('-');
+        }
+        char[] digits = new char[20];
+        int length = 0;
+        while (l != 0) {
+            digits[length++] = (char) ('0' - l % 10);
+            l = l / 10;
+        }
+        while (length > 0) {
+            append(digits[--length]);
+        }
+        return this;
+    }
+
+    public int length() {
+        return count;
+    }
+
+    public String toString() {
+        char[] chars = new char[count];
+        for (int i = 0; i < count; i++) {
+            chars[i] = value[i];
+        }
+        return new String(chars);
+    }
+}
@@ -0,0 +1,16 @@
+package java.lang;
+
+import java.io.FileOutputStream;
+import java.io.PrintStream;
+
+// Minimal java/lang/System, to run the conformance fixtures without a JDK.
+public final class System {
+    public static final PrintStream out = new PrintStream(new FileOutputStream(1));
+    public static final PrintStream err = new PrintStream(new FileOutputStream(2));
+
+    private System() {}
+
+    public static void exit(int status) {
+        Shutdown.halt0(status);
+    }
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/Throwable, to run the conformance fixtures without a JDK.
+public class Throwable {
+    private final String message;
+
+    public Throwable() {
+        this(null);
+    }
+
+    public Throwable(String message) {
+        this.message = message;
+    }
+
+    public String getMessage() {
+        return message;
+    }
+}
@@ -0,0 +1,70 @@
+//! Signal handling of the `cmd` launcher.
+//!
+//! The fixtures of `tests/signals` are compiled against the minimal runtime of
+//! the conformance tests:
+//!
+//! ```shell
+//! cd tests/signals
+//! javac -source 8 -target 8 -bootclasspath ../conformance/runtime -implicit:none <Name>.java
+//! ```
+#![cfg(unix)]
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn thread_dump_on_sigquit() {
+    let tests_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cmd"))
+        .arg("-c")
+        .arg(tests_dir.join("signals"))
+        .arg("-c")
+        .arg(tests_dir.join("conformance/runtime"))
+        .arg("Spin")
+        .env("RUST_LOG", "off")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the launcher should start");
+
+    // The handler is installed before the main class runs.
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "started\n");
+    let status = Command::new("kill")
+        .arg("-QUIT")
+        .arg(child.id().to_string())
+        .status()
+        .expect("kill should run");
+    assert!(status.success());
+
+    // The main thread may still be printing, or already spinning.
+    // The launcher keeps running after the dump, until it is killed.
+    let mut stderr = child.stderr.take().unwrap();
+    let mut dump = Vec::new();
+    let mut buf = [0; 256];
+    let main_frame = "\tat Spin.main([Ljava/lang/String;)V (pc ";
+    while !String::from_utf8_lossy(&dump).contains(main_frame) {
+        let read = stderr.read(&mut buf).unwrap();
+        assert!(
+            read > 0,
+            "no thread dump: {}",
+            String::from_utf8_lossy(&dump)
+        );
+        dump.extend_from_slice(&buf[..read]);
+    }
+    assert!(
+        child.try_wait().unwrap().is_none(),
+        "the launcher has exited"
+    );
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let dump = String::from_utf8_lossy(&dump);
+    assert!(dump.starts_with("Full thread dump:"), "{}", dump);
+    assert!(dump.contains("\"main\" #0\n"), "{}", dump);
+}
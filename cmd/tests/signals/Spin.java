@@ -0,0 +1,14 @@
+// Announces its start, then loops until the launcher is killed.
+public class Spin {
+    public static void main(String[] args) {
+        System.out.println("started");
+        spin();
+    }
+
+    static void spin() {
+        int turns = 0;
+        while (true) {
+            turns++;
+        }
+    }
+}
@@ -0,0 +1,37 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use reader::base::attribute_info::{CodeAttribute, StackMapTableAttribute};
+use reader::base::ClassFile;
+use reader::descriptor;
+use reader::BinRead;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(classfile) = ClassFile::from_bytes(data) else {
+        return;
+    };
+    let _ = classfile.class_name();
+    let _ = classfile.super_class_name();
+    let _ = classfile.super_interfaces_names();
+    let cp = classfile.constant_pool();
+    for method in classfile.methods() {
+        if let Some(descriptor) = cp.get_utf8_string(method.descriptor_index as usize) {
+            let _ = descriptor::parse_method_descriptor(&descriptor);
+        }
+        for attribute in method.attributes.iter() {
+            let Ok(code) = CodeAttribute::read(&mut Cursor::new(attribute.info.as_slice())) else {
+                continue;
+            };
+            for attribute in code.attributes.iter() {
+                let _ = StackMapTableAttribute::read(&mut Cursor::new(attribute.info.as_slice()));
+            }
+        }
+    }
+    for field in classfile.fields() {
+        if let Some(descriptor) = cp.get_utf8_string(field.descriptor_index as usize) {
+            let _ = descriptor::parse_field_descriptor(&descriptor);
+        }
+    }
+});
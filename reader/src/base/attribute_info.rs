@@ -73,6 +73,32 @@ pub struct ExceptionTableEntry {
     catch_type: U2,
 }
 
+impl ExceptionTableEntry {
+    /// Get the start of the code range where the exception handler is active.
+    pub fn start_pc(&self) -> u16 {
+        self.start_pc
+    }
+
+    /// Get the end of the code range where the exception handler is active, exclusive.
+    pub fn end_pc(&self) -> u16 {
+        self.end_pc
+    }
+
+    /// Get the first instruction of the exception handler.
+    pub fn handler_pc(&self) -> u16 {
+        self.handler_pc
+    }
+
+    /// Get the index of the [ClassInfo] of the exceptions handled in the constant pool,
+    /// None if the handler handles all types of exceptions.
+    pub fn catch_type(&self) -> Option<usize> {
+        match self.catch_type {
+            0 => None,
+            index => Some(index as usize),
+        }
+    }
+}
+
 /// Atribute StackMapTable, a member of [AttributeInfo].
 ///
 /// Represents the stack map table of a method.
@@ -80,10 +106,10 @@ pub struct ExceptionTableEntry {
 #[br(big)]
 pub struct StackMapTableAttribute {
     /// The number of entries in the stack map table.
-    number_of_entries: U2,
+    pub number_of_entries: U2,
     /// The stack map table.
     #[br(parse_with=parse_stack_map_entries, args(number_of_entries as usize))]
-    entries: Vec<StackMapFrame>,
+    pub entries: Vec<StackMapFrame>,
 }
 
 #[binrw::parser(reader, endian)]
@@ -188,16 +214,60 @@ pub struct ExceptionsAttribute {
     pub exception_index_table: Vec<U2>,
 }
 
+/// Attribute MethodParameters, a member of [AttributeInfo].
+///
+/// This attribute records the names and access flags of the formal parameters of a method.
+///
+/// Ref: <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.24>
+#[derive(BinRead)]
+#[br(big)]
+pub struct MethodParametersAttribute {
+    /// The number of entries in the parameters array.
+    pub parameters_count: U1,
+    /// The formal parameters, in the order of the method descriptor.
+    #[br(count=parameters_count)]
+    pub parameters: Vec<MethodParameter>,
+}
+
+/// A formal parameter, a structure part of [MethodParametersAttribute].
+#[derive(BinRead)]
+#[br(big)]
+pub struct MethodParameter {
+    /// A reference to a [Utf8Info](super::constant_pool::Utf8Info) in the constant pool.
+    ///
+    /// The name of the parameter, or zero if the parameter has no name.
+    pub name_index: U2,
+    /// The access flags of the parameter.
+    #[br(map= |x: U2| FlagSet::<MethodParameterAccessFlags>::new_truncated(x))]
+    pub access_flags: FlagSet<MethodParameterAccessFlags>,
+}
+
+flags! {
+    /// Access flags of the formal parameters of a method.
+    /// See <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.24>.
+    pub enum MethodParameterAccessFlags: U2 {
+        /// Declared final.
+        Final = 0x0010,
+        /// Not present in the source code, neither explicitly nor implicitly.
+        Synthetic = 0x1000,
+        /// Implicitly declared in the source code (e.g. the outer instance of an inner class).
+        Mandated = 0x8000,
+    }
+}
+
 /// Attribute InnerClasses, a member of [AttributeInfo].
 ///
 /// This attribute records the inner classes of a class or interface.
 ///
 /// Ref: <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.6>
+#[derive(BinRead)]
+#[br(big)]
 pub struct InnerClassesAttribute {
     /// The number of entries in the classes array.
     pub number_of_classes: U2,
     /// References all the class/interface that are represented as a [ClassInfo](super::constant_pool::ClassInfo)
     /// in the constant pool, but that are not a member of a package.
+    #[br(count=number_of_classes)]
     pub classes: Vec<InnerClass>,
 }
 
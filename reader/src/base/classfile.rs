@@ -15,6 +15,7 @@ pub struct ClassFile {
     /// Magic number identifying the class file format
     /// Value should be 0xCAFEBABE for a valid class file for
     /// Java SE 21 and under.
+    #[br(assert(magic == 0xCAFEBABE, "not a class file, magic: {:#x}", magic))]
     magic: U4,
     /// Minor version of the class file format
     /// Should be 0 for Java 5 and above.
@@ -28,7 +29,7 @@ pub struct ClassFile {
     // This is because the constant pool is indexed from 1 to n-1.
     constant_pool_count: U2,
     /// Constant pool, see [crate::base::constant_pool::ConstantPool].
-    #[br(args(constant_pool_count - 1))]
+    #[br(args(constant_pool_count.saturating_sub(1)))]
     constant_pool: ConstantPool,
     /// Access flags
     /// Flags indicating access permissions to and properties of this class,
@@ -144,10 +145,25 @@ impl ClassFile {
         Ok(names)
     }
 
+    /// Get the attributes of this class.
+    pub fn attributes(&self) -> &Vec<AttributeInfo> {
+        &self.attributes
+    }
+
     /// Get the access flags of this class.
     pub fn access_flags(&self) -> FlagSet<ClassAccessFlags> {
         self.access_flags
     }
+
+    /// Get the major version of the class file format.
+    pub fn major_version(&self) -> u16 {
+        self.major_version
+    }
+
+    /// Get the minor version of the class file format.
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version
+    }
 }
 
 #[derive(BinRead, Debug, Clone)]
@@ -339,4 +355,19 @@ mod test {
         assert_eq!(source_file_attribute.attribute_length, 2);
         assert_eq!(source_file_attribute.info.len(), 2);
     }
+
+    #[test]
+    fn read_malformed_class() {
+        let bytecode = include_bytes!("../../res/test/MinimalClass.class");
+        // Every truncation is an error, not a panic.
+        for len in 0..bytecode.len() {
+            assert!(ClassFile::from_bytes(&bytecode[..len]).is_err());
+        }
+        let mut bytes = bytecode.to_vec();
+        bytes[0] = 0;
+        assert!(ClassFile::from_bytes(&bytes).is_err());
+        // No constant pool at all
+        let bytes = [0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 65, 0, 0];
+        assert!(ClassFile::from_bytes(&bytes).is_err());
+    }
 }
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use super::{U1, U2, U4};
+use super::{DecodingError, U1, U2, U4};
 use binrw::{BinRead, BinResult};
 use cesu8::from_java_cesu8;
 
@@ -140,6 +140,31 @@ pub enum ConstantPoolInfo {
     PackageInfo(PackageInfo),
 }
 
+impl ConstantPoolInfo {
+    /// Get the name of the tag of this constant, e.g. `CONSTANT_Utf8`.
+    pub fn tag_name(&self) -> &'static str {
+        match self {
+            ConstantPoolInfo::ClassInfo(_) => "CONSTANT_Class",
+            ConstantPoolInfo::FieldRefInfo(_) => "CONSTANT_Fieldref",
+            ConstantPoolInfo::MethodRefInfo(_) => "CONSTANT_Methodref",
+            ConstantPoolInfo::InterfaceMethodRefInfo(_) => "CONSTANT_InterfaceMethodref",
+            ConstantPoolInfo::StringInfo(_) => "CONSTANT_String",
+            ConstantPoolInfo::IntegerInfo(_) => "CONSTANT_Integer",
+            ConstantPoolInfo::FloatInfo(_) => "CONSTANT_Float",
+            ConstantPoolInfo::LongInfo(_) => "CONSTANT_Long",
+            ConstantPoolInfo::DoubleInfo(_) => "CONSTANT_Double",
+            ConstantPoolInfo::NameAndTypeInfo(_) => "CONSTANT_NameAndType",
+            ConstantPoolInfo::Utf8Info(_) => "CONSTANT_Utf8",
+            ConstantPoolInfo::MethodHandleInfo(_) => "CONSTANT_MethodHandle",
+            ConstantPoolInfo::MethodTypeInfo(_) => "CONSTANT_MethodType",
+            ConstantPoolInfo::DynamicInfo(_) => "CONSTANT_Dynamic",
+            ConstantPoolInfo::InvokeDynamicInfo(_) => "CONSTANT_InvokeDynamic",
+            ConstantPoolInfo::ModuleInfo(_) => "CONSTANT_Module",
+            ConstantPoolInfo::PackageInfo(_) => "CONSTANT_Package",
+        }
+    }
+}
+
 /// ClassInfo is a [ConstantPool] entry.
 ///
 /// It gives the index in the [ConstantPool] of a [Utf8Info] entry,
@@ -181,7 +206,7 @@ impl Utf8Info {
 impl std::fmt::Debug for Utf8Info {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.to_string() {
-            Some(s) => write!(f, "Utf8Info(\"{}\")", s.to_string()),
+            Some(s) => write!(f, "Utf8Info(\"{}\")", s),
             None => write!(f, "Utf8Info({:?})", self.bytes),
         }
     }
@@ -409,6 +434,7 @@ fn parse_constant_pool(count: U2) -> BinResult<Vec<ConstantPoolEntry>> {
     let mut entries = Vec::with_capacity(count);
     let mut i = 0;
     while i < count {
+        let offset = reader.stream_position()?;
         let tag = U1::read_be(reader)?;
         let (entry, tombstone) = match tag {
             1 => (
@@ -493,7 +519,12 @@ fn parse_constant_pool(count: U2) -> BinResult<Vec<ConstantPoolEntry>> {
                 ConstantPoolEntry::Entry(ConstantPoolInfo::PackageInfo(PackageInfo::read(reader)?)),
                 false,
             ),
-            x => unimplemented!("Constant pool tag {} not implemented", x),
+            tag => {
+                return Err(binrw::Error::Custom {
+                    pos: offset,
+                    err: Box::new(DecodingError::UnknownConstantPoolTag { tag, offset }),
+                })
+            }
         };
         entries.push(entry);
         i += 1;
@@ -537,4 +568,24 @@ mod test {
             ConstantPoolEntry::Entry(ConstantPoolInfo::Utf8Info(_))
         ));
     }
+
+    #[test]
+    fn unknown_tag_in_constant_pool() {
+        // An integer, then the unassigned tag 2.
+        let data = [0x03, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00];
+        let mut reader = Cursor::new(&data);
+        let err = ConstantPool::read_args(&mut reader, (2,)).unwrap_err();
+        let binrw::Error::Custom { pos, err } = err.root_cause() else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!(*pos, 5);
+        assert!(
+            matches!(
+                err.downcast_ref::<DecodingError>(),
+                Some(DecodingError::UnknownConstantPoolTag { tag: 2, offset: 5 })
+            ),
+            "{:?}",
+            err
+        );
+    }
 }
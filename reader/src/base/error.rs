@@ -23,6 +23,9 @@ pub enum DecodingError {
         message: Option<String>,
     },
 
+    #[snafu(display("Unknown constant pool tag {}, at offset {:#x}", tag, offset))]
+    UnknownConstantPoolTag { tag: u8, offset: u64 },
+
     #[snafu(display("Unexpected error, causes:\n{:?}", context.as_deref().unwrap_or("<no context provided>")))]
     Unknown { context: Option<String> },
 }
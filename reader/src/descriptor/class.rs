@@ -1,5 +1,8 @@
+use super::ParseResult;
 use dumpster::Collectable;
-use nom::{branch::alt, bytes::complete::tag, character::complete::none_of, multi::many1, IResult};
+use nom::{
+    branch::alt, bytes::complete::tag, character::complete::none_of, error::context, multi::many1,
+};
 use std::{fmt::Display, str::FromStr};
 
 /// Classname representation
@@ -13,9 +16,11 @@ impl ClassName {
         Self { parts }
     }
 
-    pub fn parse(input: &str) -> IResult<&str, Self> {
-        let (input, parts) =
-            nom::multi::separated_list1(alt((tag("/"), tag("."))), UnqualifiedName::parse)(input)?;
+    pub fn parse(input: &str) -> ParseResult<'_, Self> {
+        let (input, parts) = context(
+            "class name",
+            nom::multi::separated_list1(alt((tag("/"), tag("."))), UnqualifiedName::parse),
+        )(input)?;
         Ok((input, Self { parts }))
     }
 
@@ -38,6 +43,11 @@ impl ClassName {
     pub fn parts(&self) -> &[UnqualifiedName] {
         &self.parts
     }
+
+    /// Get the name of the class without its package, e.g. `String`.
+    pub fn simple_name(&self) -> &str {
+        self.parts.last().map_or("", |part| part.as_str())
+    }
 }
 
 impl Display for ClassName {
@@ -63,7 +73,7 @@ impl UnqualifiedName {
         Self(name.into())
     }
 
-    pub fn parse(input: &str) -> IResult<&str, Self> {
+    pub fn parse(input: &str) -> ParseResult<'_, Self> {
         let (input, name) = many1(none_of("./[;"))(input)?;
         Ok((input, Self(name.into_iter().collect())))
     }
@@ -1,13 +1,19 @@
 use super::class::ClassName;
+use super::ParseResult;
 use dumpster::Collectable;
-use nom::{branch::alt, bytes::complete::tag, combinator::map, IResult};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{cut, map},
+    error::context,
+};
 
 /// Field descriptor representation
 #[derive(Debug, Clone, Eq, PartialEq, Collectable)]
 pub struct FieldDescriptor(FieldType);
 
 impl FieldDescriptor {
-    pub fn parse(input: &str) -> IResult<&str, Self> {
+    pub fn parse(input: &str) -> ParseResult<'_, Self> {
         let (input, field_type) = FieldType::parse_field_type(input)?;
         Ok((input, Self(field_type)))
     }
@@ -45,12 +51,15 @@ pub enum FieldType {
 }
 
 impl FieldType {
-    pub fn parse_field_type(input: &str) -> IResult<&str, Self> {
-        alt((
-            map(BaseType::parse, Self::BaseType),
-            map(ObjectType::parse, Self::ObjectType),
-            map(ArrayType::parse, Self::ArrayType),
-        ))(input)
+    pub fn parse_field_type(input: &str) -> ParseResult<'_, Self> {
+        context(
+            "field type",
+            alt((
+                map(BaseType::parse, Self::BaseType),
+                map(ObjectType::parse, Self::ObjectType),
+                map(ArrayType::parse, Self::ArrayType),
+            )),
+        )(input)
     }
 
     pub fn is_primitive(&self) -> bool {
@@ -73,6 +82,25 @@ impl FieldType {
             _ => false,
         }
     }
+
+    /// Get the number of local variables (or units of the operand stack) taken
+    /// by a value of this type: 2 for a long or a double, 1 otherwise.
+    pub fn width(&self) -> usize {
+        match self {
+            Self::BaseType(BaseType::Long) | Self::BaseType(BaseType::Double) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Get the type in Java syntax, with the simple names of the classes,
+    /// e.g. `int`, `String` or `long[][]`.
+    pub fn java_syntax(&self) -> String {
+        match self {
+            Self::BaseType(base) => base.java_syntax().to_string(),
+            Self::ObjectType(object) => object.class_name.simple_name().to_string(),
+            Self::ArrayType(array) => format!("{}[]", array.item.java_syntax()),
+        }
+    }
 }
 
 /// Primitive type representation
@@ -89,7 +117,7 @@ pub enum BaseType {
 }
 
 impl BaseType {
-    pub fn parse(input: &str) -> IResult<&str, Self> {
+    pub fn parse(input: &str) -> ParseResult<'_, Self> {
         alt((
             map(tag("B"), |_| Self::Byte),
             map(tag("C"), |_| Self::Char),
@@ -101,6 +129,20 @@ impl BaseType {
             map(tag("Z"), |_| Self::Boolean),
         ))(input)
     }
+
+    /// Get the keyword of the type, e.g. `int`.
+    pub fn java_syntax(&self) -> &'static str {
+        match self {
+            Self::Byte => "byte",
+            Self::Char => "char",
+            Self::Double => "double",
+            Self::Float => "float",
+            Self::Int => "int",
+            Self::Long => "long",
+            Self::Short => "short",
+            Self::Boolean => "boolean",
+        }
+    }
 }
 
 /// Object type representation
@@ -116,10 +158,11 @@ impl ObjectType {
         Self { class_name }
     }
 
-    pub fn parse(input: &str) -> IResult<&str, Self> {
+    pub fn parse(input: &str) -> ParseResult<'_, Self> {
         let (input, _) = tag("L")(input)?;
-        let (input, class_name) = ClassName::parse(input)?;
-        let (input, _) = tag(";")(input)?;
+        // Past the `L`, the descriptor can only be an object type.
+        let (input, class_name) = cut(ClassName::parse)(input)?;
+        let (input, _) = cut(context("';'", tag(";")))(input)?;
         Ok((input, Self { class_name }))
     }
 }
@@ -137,9 +180,9 @@ impl ArrayType {
         }
     }
 
-    pub fn parse(input: &str) -> IResult<&str, Self> {
+    pub fn parse(input: &str) -> ParseResult<'_, Self> {
         let (input, _) = tag("[")(input)?;
-        let (input, item) = FieldType::parse_field_type(input)?;
+        let (input, item) = cut(FieldType::parse_field_type)(input)?;
         Ok((
             input,
             Self {
@@ -1,6 +1,7 @@
 use super::field::FieldType;
+use super::ParseResult;
 use dumpster::Collectable;
-use nom::{branch::alt, bytes::complete::tag, combinator::map, IResult};
+use nom::{branch::alt, bytes::complete::tag, combinator::map, error::context};
 
 /// Method descriptor representation
 #[derive(Debug, Clone, Eq, PartialEq, Collectable)]
@@ -10,7 +11,7 @@ pub struct MethodDescriptor {
 }
 
 impl MethodDescriptor {
-    pub fn parse(input: &str) -> IResult<&str, Self> {
+    pub fn parse(input: &str) -> ParseResult<'_, Self> {
         let (input, parameters) = parse_parameters(input)?;
         let (input, return_type) = parse_return_type(input)?;
         Ok((
@@ -22,22 +23,49 @@ impl MethodDescriptor {
         ))
     }
 
+    /// Get the number of parameters, a long or a double counting once.
     pub fn args_count(&self) -> usize {
         self.parameters.len()
     }
+
+    /// Get the number of local variables taken by the parameters, a long or a
+    /// double taking two. The receiver of an instance method is not counted.
+    pub fn slot_count(&self) -> usize {
+        self.parameters.iter().map(FieldType::width).sum()
+    }
+
+    /// Get the number of units of the operand stack taken by the returned
+    /// value: 0 for void, 2 for a long or a double, 1 otherwise.
+    pub fn return_width(&self) -> usize {
+        self.return_type.as_ref().map_or(0, FieldType::width)
+    }
+
+    /// Get the descriptor in Java syntax, with the simple names of the classes,
+    /// e.g. `(int, long) -> String` for `(IJ)Ljava/lang/String;`.
+    pub fn java_syntax(&self) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(FieldType::java_syntax).collect();
+        let return_type = match &self.return_type {
+            Some(return_type) => return_type.java_syntax(),
+            None => "void".to_string(),
+        };
+        format!("({}) -> {}", parameters.join(", "), return_type)
+    }
 }
 
-fn parse_parameters(input: &str) -> IResult<&str, Vec<FieldType>> {
-    let (input, _) = tag("(")(input)?;
+fn parse_parameters(input: &str) -> ParseResult<'_, Vec<FieldType>> {
+    let (input, _) = context("'('", tag("("))(input)?;
     let (input, parameters) = nom::multi::many0(FieldType::parse_field_type)(input)?;
-    let (input, _) = tag(")")(input)?;
+    let (input, _) = context("field type or ')'", tag(")"))(input)?;
     Ok((input, parameters))
 }
 
-fn parse_return_type(input: &str) -> IResult<&str, Option<FieldType>> {
-    let (input, return_type) = alt((
-        map(FieldType::parse_field_type, Some),
-        map(tag("V"), |_| None),
-    ))(input)?;
+fn parse_return_type(input: &str) -> ParseResult<'_, Option<FieldType>> {
+    let (input, return_type) = context(
+        "return type",
+        alt((
+            map(FieldType::parse_field_type, Some),
+            map(tag("V"), |_| None),
+        )),
+    )(input)?;
     Ok((input, return_type))
 }
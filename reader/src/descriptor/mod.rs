@@ -1,3 +1,4 @@
+use nom::error::{VerboseError, VerboseErrorKind};
 use snafu::Snafu;
 
 pub use self::class::*;
@@ -8,65 +9,111 @@ pub mod class;
 pub mod field;
 pub mod method;
 
+/// Result of the descriptor parsers, the errors keeping the context of the
+/// failure (see [DescriptorError::UndecodableDescriptor]).
+pub type ParseResult<'a, T> = nom::IResult<&'a str, T, VerboseError<&'a str>>;
+
 #[derive(Debug, Snafu)]
 pub enum DescriptorError {
-    #[snafu(display("Undecodable descriptor: {}", input))]
+    #[snafu(display(
+        "Undecodable descriptor: '{}', expected {} at offset {}",
+        input,
+        expected,
+        offset
+    ))]
     UndecodableDescriptor {
         input: String,
-        //source: nom::Err<nom::error::Error<&'static str>>,
+        /// Offset (in bytes) of the input where the parsing failed.
+        offset: usize,
+        /// What the parser expected at this offset, e.g. `';'` or `return type`.
+        expected: String,
     },
 
     #[snafu(display(
-        "Badly formated descriptor as it is longer than the parser decoded it, input: '{}'",
-        input
+        "Badly formated descriptor as it is longer than the parser decoded it, input: '{}', unexpected trailing input at offset {}",
+        input,
+        offset
     ))]
-    TooLongDescriptor { input: String },
+    TooLongDescriptor { input: String, offset: usize },
 }
 
-/// Parse a field descriptor
-pub fn parse_field_descriptor(input: &str) -> Result<FieldDescriptor, DescriptorError> {
-    let (rem, fty) = field::FieldDescriptor::parse(input).map_err(|_| {
+impl DescriptorError {
+    /// Build the error of a parser that failed on the given input.
+    fn undecodable(input: &str, err: nom::Err<VerboseError<&str>>) -> Self {
+        let (offset, expected) = match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => {
+                // The first error is the innermost one, where the parsing
+                // stopped, the contexts are added while going back up.
+                let offset = err
+                    .errors
+                    .first()
+                    .map(|(rem, _)| input.len() - rem.len())
+                    .unwrap_or_default();
+                let expected = err
+                    .errors
+                    .iter()
+                    .find_map(|(_, kind)| match kind {
+                        VerboseErrorKind::Context(context) => Some(context.to_string()),
+                        _ => None,
+                    })
+                    .or_else(|| {
+                        err.errors.first().map(|(_, kind)| match kind {
+                            VerboseErrorKind::Char(c) => format!("'{}'", c),
+                            VerboseErrorKind::Nom(kind) => kind.description().to_lowercase(),
+                            VerboseErrorKind::Context(context) => context.to_string(),
+                        })
+                    })
+                    .unwrap_or_else(|| "a descriptor".into());
+                (offset, expected)
+            }
+            nom::Err::Incomplete(_) => (input.len(), "more input".into()),
+        };
         DescriptorError::UndecodableDescriptor {
             input: input.into(),
+            offset,
+            expected,
+        }
+    }
+
+    /// Build the error of a parser that did not consume all the input.
+    fn too_long(input: &str, rem: &str) -> Self {
+        DescriptorError::TooLongDescriptor {
+            input: input.into(),
+            offset: input.len() - rem.len(),
         }
-    })?;
+    }
+}
+
+/// Parse a field descriptor
+pub fn parse_field_descriptor(input: &str) -> Result<FieldDescriptor, DescriptorError> {
+    let (rem, fty) = field::FieldDescriptor::parse(input)
+        .map_err(|err| DescriptorError::undecodable(input, err))?;
     if rem.is_empty() {
         Ok(fty)
     } else {
-        Err(DescriptorError::TooLongDescriptor {
-            input: input.into(),
-        })
+        Err(DescriptorError::too_long(input, rem))
     }
 }
 
 /// Parse a method descriptor
 pub fn parse_method_descriptor(input: &str) -> Result<MethodDescriptor, DescriptorError> {
-    let (rem, mty) = method::MethodDescriptor::parse(input).map_err(|_| {
-        DescriptorError::UndecodableDescriptor {
-            input: input.into(),
-        }
-    })?;
+    let (rem, mty) = method::MethodDescriptor::parse(input)
+        .map_err(|err| DescriptorError::undecodable(input, err))?;
     if rem.is_empty() {
         Ok(mty)
     } else {
-        Err(DescriptorError::TooLongDescriptor {
-            input: input.into(),
-        })
+        Err(DescriptorError::too_long(input, rem))
     }
 }
 
 /// Parse a class name
 pub fn parse_class_name(input: &str) -> Result<ClassName, DescriptorError> {
     let (rem, class) =
-        class::ClassName::parse(input).map_err(|_| DescriptorError::UndecodableDescriptor {
-            input: input.into(),
-        })?;
+        class::ClassName::parse(input).map_err(|err| DescriptorError::undecodable(input, err))?;
     if rem.is_empty() {
         Ok(class)
     } else {
-        Err(DescriptorError::TooLongDescriptor {
-            input: input.into(),
-        })
+        Err(DescriptorError::too_long(input, rem))
     }
 }
 
@@ -170,4 +217,50 @@ mod tests {
         assert!(parse_field_descriptor("[[[B").is_ok());
         assert!(parse_field_descriptor("[[[").is_err());
     }
+
+    #[test]
+    fn method_descriptor_layout() {
+        let method =
+            parse_method_descriptor("(IJ[DLjava/lang/String;D)Ljava/lang/String;").unwrap();
+        assert_eq!(method.args_count(), 5);
+        assert_eq!(method.slot_count(), 7);
+        assert_eq!(method.return_width(), 1);
+        assert_eq!(
+            method.java_syntax(),
+            "(int, long, double[], String, double) -> String"
+        );
+
+        let method = parse_method_descriptor("()V").unwrap();
+        assert_eq!((method.slot_count(), method.return_width()), (0, 0));
+        assert_eq!(method.java_syntax(), "() -> void");
+        assert_eq!(parse_method_descriptor("(Z)J").unwrap().return_width(), 2);
+    }
+
+    #[test]
+    fn error_positions() {
+        let position = |err: DescriptorError| match err {
+            DescriptorError::UndecodableDescriptor {
+                offset, expected, ..
+            } => (offset, expected),
+            DescriptorError::TooLongDescriptor { offset, .. } => (offset, "end".into()),
+        };
+        let field = |input| position(parse_field_descriptor(input).unwrap_err());
+        let method = |input| position(parse_method_descriptor(input).unwrap_err());
+
+        assert_eq!(field("Ljava/lang/Object"), (17, "';'".into()));
+        assert_eq!(field("L[java/lang/Object;"), (1, "class name".into()));
+        assert_eq!(field("[[["), (3, "field type".into()));
+        assert_eq!(field("X"), (0, "field type".into()));
+        assert_eq!(field("Ljava/lang/Object;;"), (18, "end".into()));
+        assert_eq!(method("II)V"), (0, "'('".into()));
+        assert_eq!(method("(IX)V"), (2, "field type or ')'".into()));
+        assert_eq!(method("(I[Ljava/lang/String)V"), (22, "';'".into()));
+        assert_eq!(method("(I)"), (3, "return type".into()));
+        assert_eq!(method("(I)VV"), (4, "end".into()));
+
+        assert_eq!(
+            parse_method_descriptor("(I)Q").unwrap_err().to_string(),
+            "Undecodable descriptor: '(I)Q', expected return type at offset 3"
+        );
+    }
 }
@@ -0,0 +1,51 @@
+//! Micro-benchmark of the inline cache of the getters run without a frame.
+//!
+//! Compares a loop calling a getter on receivers of a single class, which
+//! hit the inline cache of the getter, with a loop alternating the receivers
+//! of two classes, the field being resolved again on each call.
+//!
+//! Run with `cargo bench -p vm --bench accessors`.
+
+use std::time::{Duration, Instant};
+
+use vm::class_loader::{ClassLoader, ClassPathDirEntry};
+use vm::slot::Slot;
+use vm::Vm;
+
+const CALLS: i32 = 1_000_000;
+
+fn bench(vm: &mut Vm, method: &str) -> Duration {
+    let mut call = |count: i32| {
+        let value = vm
+            .invoke_static("Accessors", method, "(I)I", vec![Slot::Int(count)])
+            .unwrap();
+        // Every getter returns 1.
+        assert!(matches!(value, Some(Slot::Int(sum)) if sum == count));
+    };
+    // Warm up
+    call(1000);
+    let start = Instant::now();
+    call(CALLS);
+    let elapsed = start.elapsed();
+    println!(
+        "{:<16}{:>10.2} ns/call ({} calls)",
+        method,
+        elapsed.as_nanos() as f64 / CALLS as f64,
+        CALLS
+    );
+    elapsed
+}
+
+fn main() {
+    let mut class_loader = ClassLoader::new();
+    class_loader.add_class_path_entry(Box::new(ClassPathDirEntry::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/classes"
+    ))));
+    let mut vm = Vm::new(class_loader);
+
+    let cached = bench(&mut vm, "monomorphic");
+    let resolved = bench(&mut vm, "polymorphic");
+
+    println!("speedup: {:.2}x", resolved.as_secs_f64() / cached.as_secs_f64());
+}
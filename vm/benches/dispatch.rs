@@ -0,0 +1,81 @@
+//! Micro-benchmark of the instruction fetch of the interpreter.
+//!
+//! Compares the decoding of each instruction from the bytecode (through a
+//! Cursor) with the fetch from the flat decoded program of the instruction cache.
+//!
+//! Run with `cargo bench -p vm --bench dispatch`.
+
+use std::hint::black_box;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use vm::opcode::{read_instruction, InstructionCache, Opcode};
+
+const ITERATIONS: usize = 200_000;
+
+/// A loop body of arithmetic on locals, followed by a `goto` to its start.
+fn loop_bytecode() -> Vec<u8> {
+    let mut code = Vec::new();
+    for _ in 0..16 {
+        // iload_1; iload_2; iadd; istore_1; iinc 2 1
+        code.extend_from_slice(&[0x1b, 0x1c, 0x60, 0x3c, 0x84, 0x02, 0x01]);
+    }
+    let offset = -(code.len() as i16);
+    code.push(0xa7);
+    code.extend_from_slice(&offset.to_be_bytes());
+    code
+}
+
+fn bench(name: &str, mut f: impl FnMut() -> usize) -> Duration {
+    // Warm up
+    black_box(f());
+    let start = Instant::now();
+    let mut count = 0;
+    for _ in 0..ITERATIONS {
+        count += black_box(f());
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<24}{:>10.2} ns/instruction ({} instructions)",
+        name,
+        elapsed.as_nanos() as f64 / count as f64,
+        count
+    );
+    elapsed
+}
+
+fn main() {
+    let bytecode = loop_bytecode();
+
+    let cursor = bench("cursor decoding", || {
+        let mut reader = Cursor::new(bytecode.as_slice());
+        let mut pc = 0;
+        let mut count = 0;
+        loop {
+            reader.set_position(pc as u64);
+            let (size, opcode) = read_instruction(&mut reader).unwrap();
+            count += 1;
+            if let Opcode::Goto(_) = black_box(opcode) {
+                return count;
+            }
+            pc += size;
+        }
+    });
+
+    let cache = InstructionCache::new(bytecode.clone());
+    let program = cache.program();
+    let flat = bench("flat program", || {
+        let mut index = 0;
+        let mut count = 0;
+        loop {
+            let opcode = program.get(index).unwrap();
+            count += 1;
+            if let Opcode::Goto(_) = black_box(opcode) {
+                return count;
+            }
+            index += 1;
+        }
+    });
+
+    println!("speedup: {:.2}x", cursor.as_secs_f64() / flat.as_secs_f64());
+}
@@ -1,9 +1,9 @@
 use crate::{class::ClassId, from_item_array, item_array};
 use dumpster::{sync::Gc, Collectable};
 use reader::descriptor::{ArrayType, BaseType, FieldType, ObjectType};
-use std::sync::RwLock;
+use std::{mem::size_of, sync::RwLock};
 
-use super::ObjectRef;
+use super::{heap::HeapCharge, IdentityHash, ObjectRef};
 
 /// Garbage collected array reference
 pub type ArrayRef = Gc<Array>;
@@ -23,14 +23,14 @@ pub enum Array {
     Boolean(BoolArray),
 }
 
-item_array!(IntArray, i32, 0);
-item_array!(LongArray, i64, 0);
-item_array!(FloatArray, f32, 0.0);
-item_array!(DoubleArray, f64, 0.0);
-item_array!(ByteArray, i8, 0);
-item_array!(BoolArray, bool, false);
-item_array!(CharArray, u16, 0);
-item_array!(ShortArray, i16, 0);
+item_array!(IntArray, Int, i32, 0);
+item_array!(LongArray, Long, i64, 0);
+item_array!(FloatArray, Float, f32, 0.0);
+item_array!(DoubleArray, Double, f64, 0.0);
+item_array!(ByteArray, Byte, i8, 0);
+item_array!(BoolArray, Boolean, bool, false);
+item_array!(CharArray, Char, u16, 0);
+item_array!(ShortArray, Short, i16, 0);
 
 from_item_array!(Int, IntArray);
 from_item_array!(Long, LongArray);
@@ -43,7 +43,75 @@ from_item_array!(Short, ShortArray);
 from_item_array!(ArrayRef, ArrayRefArray);
 from_item_array!(ObjectRef, ObjectRefArray);
 
+/// The type of the elements of an array of a primitive type, e.g. `i32` for `int[]`.
+///
+/// The elements are stored contiguously, so that they can be read and written
+/// as slices, without going through a slot per element.
+pub trait PrimitiveElement: Copy + Sized + 'static {
+    /// Get the elements of the array, None if it holds another type.
+    fn data_of(array: &Array) -> Option<&RwLock<Vec<Self>>>;
+
+    /// Create an array holding the elements.
+    fn new_array(data: Vec<Self>) -> Array;
+}
+
 impl Array {
+    /// Create an array of a primitive type, filled with zeros.
+    pub fn new_primitive(base: &BaseType, len: usize) -> Self {
+        match base {
+            BaseType::Boolean => BoolArray::new(len).into(),
+            BaseType::Byte => ByteArray::new(len).into(),
+            BaseType::Char => CharArray::new(len).into(),
+            BaseType::Short => ShortArray::new(len).into(),
+            BaseType::Int => IntArray::new(len).into(),
+            BaseType::Long => LongArray::new(len).into(),
+            BaseType::Float => FloatArray::new(len).into(),
+            BaseType::Double => DoubleArray::new(len).into(),
+        }
+    }
+
+    /// Get the size (in bytes) of an element of an array of a primitive type.
+    pub fn primitive_item_size(base: &BaseType) -> usize {
+        match base {
+            BaseType::Boolean => size_of::<bool>(),
+            BaseType::Byte => size_of::<i8>(),
+            BaseType::Char => size_of::<u16>(),
+            BaseType::Short => size_of::<i16>(),
+            BaseType::Int => size_of::<i32>(),
+            BaseType::Long => size_of::<i64>(),
+            BaseType::Float => size_of::<f32>(),
+            BaseType::Double => size_of::<f64>(),
+        }
+    }
+
+    /// Run a function on the elements of the array, read at once.
+    ///
+    /// Returns None if the elements are not of the type `T`.
+    pub fn with_slice<T: PrimitiveElement, R>(&self, f: impl FnOnce(&[T]) -> R) -> Option<R> {
+        let data = T::data_of(self)?
+            .read()
+            .expect("rwlock has been poisoned, cannot read the array elements");
+        Some(f(&data))
+    }
+
+    /// Run a function on the elements of the array, written at once.
+    ///
+    /// Returns None if the elements are not of the type `T`.
+    pub fn with_slice_mut<T: PrimitiveElement, R>(
+        &self,
+        f: impl FnOnce(&mut [T]) -> R,
+    ) -> Option<R> {
+        let mut data = T::data_of(self)?
+            .write()
+            .expect("rwlock has been poisoned, cannot write the array elements");
+        Some(f(&mut data))
+    }
+
+    /// Copy the elements of the array, None if they are not of the type `T`.
+    pub fn to_vec<T: PrimitiveElement>(&self) -> Option<Vec<T>> {
+        self.with_slice(<[T]>::to_vec)
+    }
+
     /// Get the length of the array.
     pub fn len(&self) -> usize {
         match self {
@@ -59,12 +127,68 @@ impl Array {
             Array::ArrayRef(array) => array.len(),
         }
     }
+
+    /// Estimate the size (in bytes) of the array, its elements included.
+    pub fn size_estimate(&self) -> usize {
+        size_of::<Array>() + self.data_size()
+    }
+
+    /// Get the size (in bytes) of the elements of the array.
+    pub fn data_size(&self) -> usize {
+        let item_size = match self {
+            Array::Int(_) => size_of::<i32>(),
+            Array::Long(_) => size_of::<i64>(),
+            Array::Float(_) => size_of::<f32>(),
+            Array::Double(_) => size_of::<f64>(),
+            Array::Byte(_) => size_of::<i8>(),
+            Array::Boolean(_) => size_of::<bool>(),
+            Array::Char(_) => size_of::<u16>(),
+            Array::Short(_) => size_of::<i16>(),
+            Array::ObjectRef(_) => size_of::<Option<ObjectRef>>(),
+            Array::ArrayRef(_) => size_of::<Option<ArrayRef>>(),
+        };
+        self.len() * item_size
+    }
+
+    /// Get the heap charge of the elements of the array.
+    pub fn heap_charge(&self) -> &HeapCharge {
+        match self {
+            Array::Int(array) => array.heap_charge(),
+            Array::Long(array) => array.heap_charge(),
+            Array::Float(array) => array.heap_charge(),
+            Array::Double(array) => array.heap_charge(),
+            Array::Byte(array) => array.heap_charge(),
+            Array::Boolean(array) => array.heap_charge(),
+            Array::Char(array) => array.heap_charge(),
+            Array::Short(array) => array.heap_charge(),
+            Array::ObjectRef(array) => array.heap_charge(),
+            Array::ArrayRef(array) => array.heap_charge(),
+        }
+    }
+
+    /// Get the identity hash code of the array.
+    pub fn identity_hash(&self) -> &IdentityHash {
+        match self {
+            Array::Int(array) => array.identity_hash(),
+            Array::Long(array) => array.identity_hash(),
+            Array::Float(array) => array.identity_hash(),
+            Array::Double(array) => array.identity_hash(),
+            Array::Byte(array) => array.identity_hash(),
+            Array::Boolean(array) => array.identity_hash(),
+            Array::Char(array) => array.identity_hash(),
+            Array::Short(array) => array.identity_hash(),
+            Array::ObjectRef(array) => array.identity_hash(),
+            Array::ArrayRef(array) => array.identity_hash(),
+        }
+    }
 }
 
 #[derive(Debug, Collectable)]
 pub struct ObjectRefArray {
     pub class_id: ClassId,
     pub data: RwLock<Vec<Option<ObjectRef>>>,
+    identity_hash: IdentityHash,
+    charge: HeapCharge,
 }
 
 impl ObjectRefArray {
@@ -73,6 +197,8 @@ impl ObjectRefArray {
         Self {
             class_id,
             data: RwLock::new(vec![None; size]),
+            identity_hash: IdentityHash::default(),
+            charge: HeapCharge::default(),
         }
     }
 
@@ -105,12 +231,24 @@ impl ObjectRefArray {
     pub fn class_id(&self) -> ClassId {
         self.class_id
     }
+
+    /// Get the identity hash code of the array
+    pub fn identity_hash(&self) -> &IdentityHash {
+        &self.identity_hash
+    }
+
+    /// Get the heap charge of the array elements
+    pub fn heap_charge(&self) -> &HeapCharge {
+        &self.charge
+    }
 }
 
 #[derive(Debug, Collectable)]
 pub struct ArrayRefArray {
     pub item_ty: ArrayType,
     pub data: RwLock<Vec<Option<ArrayRef>>>,
+    identity_hash: IdentityHash,
+    charge: HeapCharge,
 }
 
 impl ArrayRefArray {
@@ -119,6 +257,8 @@ impl ArrayRefArray {
         Self {
             item_ty,
             data: RwLock::new(vec![None; size]),
+            identity_hash: IdentityHash::default(),
+            charge: HeapCharge::default(),
         }
     }
 
@@ -151,26 +291,35 @@ impl ArrayRefArray {
     pub fn item_type(&self) -> &ArrayType {
         &self.item_ty
     }
+
+    /// Get the identity hash code of the array
+    pub fn identity_hash(&self) -> &IdentityHash {
+        &self.identity_hash
+    }
+
+    /// Get the heap charge of the array elements
+    pub fn heap_charge(&self) -> &HeapCharge {
+        &self.charge
+    }
 }
 
 impl CharArray {
     /// Create a Char Array from a rust string
     pub fn from_string(string: &str) -> Self {
-        let data = string.encode_utf16().collect();
-        Self {
-            data: RwLock::new(data),
-        }
+        string.encode_utf16().collect::<Vec<_>>().into()
     }
 }
 
 mod macros {
     #[macro_export]
     macro_rules! item_array {
-        ($name:ident, $ty:ty, $default_value:expr) => {
+        ($name:ident, $variant:ident, $ty:ty, $default_value:expr) => {
             /// JVM representation of an array of such type
             #[derive(Debug, Collectable)]
             pub struct $name {
                 pub data: RwLock<Vec<$ty>>,
+                identity_hash: $crate::alloc::IdentityHash,
+                charge: $crate::alloc::heap::HeapCharge,
             }
 
             impl $name {
@@ -178,6 +327,8 @@ mod macros {
                 pub fn new(size: usize) -> Self {
                     Self {
                         data: RwLock::new(vec![$default_value; size]),
+                        identity_hash: Default::default(),
+                        charge: Default::default(),
                     }
                 }
 
@@ -207,21 +358,67 @@ mod macros {
                         .expect("rwlock has been poisoned, cannot get length to array element")
                         .len()
                 }
+
+                /// Get the identity hash code of the array
+                pub fn identity_hash(&self) -> &$crate::alloc::IdentityHash {
+                    &self.identity_hash
+                }
+
+                /// Get the heap charge of the array elements
+                pub fn heap_charge(&self) -> &$crate::alloc::heap::HeapCharge {
+                    &self.charge
+                }
+
+                /// Run a function on the elements of the array, read at once.
+                pub fn with_slice<R>(&self, f: impl FnOnce(&[$ty]) -> R) -> R {
+                    f(&self
+                        .data
+                        .read()
+                        .expect("rwlock has been poisoned, cannot read the array elements"))
+                }
+
+                /// Run a function on the elements of the array, written at once.
+                pub fn with_slice_mut<R>(&self, f: impl FnOnce(&mut [$ty]) -> R) -> R {
+                    f(&mut self
+                        .data
+                        .write()
+                        .expect("rwlock has been poisoned, cannot write the array elements"))
+                }
+
+                /// Copy the elements of the array.
+                pub fn to_vec(&self) -> Vec<$ty> {
+                    self.with_slice(<[$ty]>::to_vec)
+                }
+            }
+
+            impl $crate::alloc::array::PrimitiveElement for $ty {
+                fn data_of(array: &Array) -> Option<&RwLock<Vec<Self>>> {
+                    match array {
+                        Array::$variant(array) => Some(&array.data),
+                        _ => None,
+                    }
+                }
+
+                fn new_array(data: Vec<Self>) -> Array {
+                    Array::$variant($name::from(data))
+                }
             }
 
             impl From<Vec<$ty>> for $name {
                 fn from(data: Vec<$ty>) -> Self {
                     Self {
                         data: RwLock::new(data),
+                        identity_hash: Default::default(),
+                        charge: Default::default(),
                     }
                 }
             }
 
             impl From<$name> for Vec<$ty> {
-                fn from(array: $name) -> Self {
-                    array.data.into_inner().expect(
+                fn from(mut array: $name) -> Self {
+                    std::mem::take(array.data.get_mut().expect(
                         "rwlock has been poisoned, cannot consume it to access the array data",
-                    )
+                    ))
                 }
             }
         };
@@ -238,3 +435,34 @@ mod macros {
         };
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{slot::Slot, test_util::test_vm};
+
+    #[test]
+    fn array_slices() {
+        let array = Array::from(IntArray::from(vec![1, 2, 3]));
+        assert_eq!(
+            array.with_slice(|data: &[i32]| data.iter().sum::<i32>()),
+            Some(6)
+        );
+        assert_eq!(array.with_slice(|data: &[i64]| data.len()), None);
+        array.with_slice_mut(|data: &mut [i32]| data.reverse());
+        assert_eq!(array.to_vec::<i32>(), Some(vec![3, 2, 1]));
+
+        let mut vm = test_vm();
+        let values = Slot::ArrayReference(vm.new_array(vec![4, 5, 6, 7]).unwrap());
+        assert!(vm.write_array(&values, &[8, 9]));
+        assert!(!vm.write_array(&values, &[0; 5]));
+        assert!(!vm.write_array(&values, &[0i8]));
+        let reversed = vm
+            .invoke_static("ArrayBulk", "reversed", "([I)[I", vec![values])
+            .unwrap()
+            .unwrap();
+        assert_eq!(vm.read_array::<i32>(&reversed), Some(vec![7, 6, 9, 8]));
+        assert_eq!(vm.read_array::<u16>(&reversed), None);
+        assert_eq!(vm.read_array::<i32>(&Slot::UndefinedReference), None);
+    }
+}
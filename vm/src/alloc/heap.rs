@@ -0,0 +1,205 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, OnceLock,
+};
+
+use dumpster::{Collectable, Visitor};
+use snafu::Snafu;
+
+/// Bytes used by the elements of the live arrays of a VM.
+///
+/// Each array allocated by the VM is charged to it, until the array is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct HeapUsage {
+    bytes: Arc<AtomicUsize>,
+}
+
+impl HeapUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of bytes used by the elements of the live arrays.
+    pub fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// The bytes of an array charged to a [HeapUsage], released when the array is dropped.
+#[derive(Debug, Default)]
+pub struct HeapCharge {
+    charged: OnceLock<(HeapUsage, usize)>,
+}
+
+impl HeapCharge {
+    /// Charge `bytes` to the usage, once: an array is allocated by a single VM.
+    pub(crate) fn charge(&self, usage: &HeapUsage, bytes: usize) {
+        if self.charged.set((usage.clone(), bytes)).is_ok() {
+            usage.bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for HeapCharge {
+    fn drop(&mut self) {
+        if let Some((usage, bytes)) = self.charged.get() {
+            usage.bytes.fetch_sub(*bytes, Ordering::Relaxed);
+        }
+    }
+}
+
+// SAFETY: the charge does not hold any Gc pointer.
+unsafe impl Collectable for HeapCharge {
+    fn accept<V: Visitor>(&self, _visitor: &mut V) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum AllocError {
+    #[snafu(display(
+        "cannot allocate {} bytes, {} bytes already used out of {}",
+        requested,
+        used,
+        max
+    ))]
+    OutOfMemory {
+        requested: usize,
+        used: usize,
+        max: usize,
+    },
+
+    #[snafu(display("array too large: {} elements of {} bytes", count, item_size))]
+    SizeOverflow { count: usize, item_size: usize },
+}
+
+/// Upper bound of the memory used by the arrays.
+///
+/// Allocating an array beyond the budget fails with an OutOfMemory error,
+/// instead of aborting the process when the host runs out of memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapBudget {
+    max_bytes: Option<usize>,
+}
+
+impl HeapBudget {
+    /// A budget without any limit.
+    pub fn unlimited() -> Self {
+        Self { max_bytes: None }
+    }
+
+    /// A budget of `max_bytes` for the array elements.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes: Some(max_bytes),
+        }
+    }
+
+    pub fn max_bytes(&self) -> Option<usize> {
+        self.max_bytes
+    }
+
+    /// Check that an array of `count` elements of `item_size` bytes can be
+    /// allocated, `used` bytes being already used by the live arrays.
+    pub fn check_array(
+        &self,
+        used: usize,
+        count: usize,
+        item_size: usize,
+    ) -> Result<(), AllocError> {
+        let requested = count
+            .checked_mul(item_size)
+            .ok_or(AllocError::SizeOverflow { count, item_size })?;
+        self.check(used, requested)
+    }
+
+    fn check(&self, used: usize, requested: usize) -> Result<(), AllocError> {
+        let Some(max) = self.max_bytes else {
+            return Ok(());
+        };
+        match used.checked_add(requested) {
+            Some(total) if total <= max => Ok(()),
+            _ => Err(AllocError::OutOfMemory {
+                requested,
+                used,
+                max,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        slot::Slot, test_util::test_class_loader, thread::ExecutionError, VmBuilder, VmError,
+    };
+
+    #[test]
+    fn budget_check() {
+        let budget = HeapBudget::new(100);
+        assert!(budget.check(0, 100).is_ok());
+        assert!(budget.check(60, 40).is_ok());
+        assert!(matches!(
+            budget.check(60, 41),
+            Err(AllocError::OutOfMemory { requested: 41, .. })
+        ));
+        assert!(HeapBudget::unlimited().check(usize::MAX, 1).is_ok());
+        assert!(matches!(
+            budget.check_array(0, usize::MAX, 8),
+            Err(AllocError::SizeOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn usage_per_vm() {
+        let (first, second) = (HeapUsage::new(), HeapUsage::new());
+        let charge = HeapCharge::default();
+        charge.charge(&first, 64);
+        // Charged once, to the first usage.
+        charge.charge(&second, 32);
+        assert_eq!((first.bytes(), second.bytes()), (64, 0));
+        drop(charge);
+        assert_eq!(first.bytes(), 0);
+    }
+
+    #[test]
+    fn multi_array_budget() {
+        let mut vm = VmBuilder::new(test_class_loader())
+            .max_heap(64 * 1024)
+            .build();
+        let grid = vm.invoke_static(
+            "MultiArrays",
+            "grid",
+            "(II)I",
+            vec![Slot::Int(3), Slot::Int(4)],
+        );
+        assert!(matches!(grid, Ok(Some(Slot::Int(347)))));
+        let partial = vm.invoke_static("MultiArrays", "partial", "()Z", vec![]);
+        assert!(matches!(partial, Ok(Some(Slot::Int(1)))));
+
+        // 64 arrays of 64 longs fit, 128 arrays of 128 longs do not.
+        let large = |vm: &mut crate::Vm, count| {
+            vm.invoke_static(
+                "MultiArrays",
+                "large",
+                "(I)Ljava/lang/Object;",
+                vec![Slot::Int(count)],
+            )
+        };
+        let array = large(&mut vm, 64).unwrap();
+        let used = vm.class_manager().allocations.live_array_bytes();
+        assert!(used >= 64 * 64 * 8, "{} bytes used", used);
+        drop(array);
+        assert!(vm.class_manager().allocations.live_array_bytes() < used);
+        match large(&mut vm, 128) {
+            Err(VmError::Execution {
+                source: ExecutionError::InstructionExecutionError { source },
+            }) => assert_eq!(source.java_exception(), Some("java/lang/OutOfMemoryError")),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        // Each VM has its own heap.
+        let other = VmBuilder::new(test_class_loader()).build();
+        assert_eq!(other.class_manager().allocations.live_array_bytes(), 0);
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::RwLock,
+};
+
+use dumpster::Collectable;
+
+use crate::{class_manager::ClassManager, slot::Slot};
+
+/// Identity hash code of an object or an array (`System.identityHashCode`).
+///
+/// The hash is assigned on the first request and then never changes, 0 meaning
+/// that no hash has been assigned yet.
+#[derive(Debug, Default, Collectable)]
+pub struct IdentityHash {
+    hash: RwLock<i32>,
+}
+
+impl IdentityHash {
+    /// Get the identity hash, assigning the next one of the generator if needed.
+    pub fn get_or_assign(&self, generator: &mut IdentityHashGenerator) -> i32 {
+        let mut hash = self
+            .hash
+            .write()
+            .expect("rwlock has been poisoned, cannot get the identity hash");
+        if *hash == 0 {
+            *hash = generator.next_hash();
+        }
+        *hash
+    }
+}
+
+/// Source of the identity hash codes.
+///
+/// The hash codes are drawn from a pseudo-random sequence, randomly seeded
+/// unless a seed is given to make the runs reproducible.
+#[derive(Debug, Clone)]
+pub struct IdentityHashGenerator {
+    state: u64,
+}
+
+impl IdentityHashGenerator {
+    /// Create a randomly seeded generator.
+    pub fn new() -> Self {
+        Self::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    /// Create a generator always producing the same sequence for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Get the next hash code, a non-zero positive 31-bit integer.
+    pub fn next_hash(&mut self) -> i32 {
+        loop {
+            // SplitMix64
+            self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = self.state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            let hash = (z & 0x7FFF_FFFF) as i32;
+            if hash != 0 {
+                return hash;
+            }
+        }
+    }
+}
+
+impl Default for IdentityHashGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the identity hash code of a reference, 0 for `null`.
+///
+/// Returns None if the slot is not a reference.
+pub fn identity_hash(cm: &mut ClassManager, slot: &Slot) -> Option<i32> {
+    match slot {
+        Slot::UndefinedReference => Some(0),
+        Slot::ObjectReference(obj) => {
+            Some(obj.identity_hash().get_or_assign(&mut cm.identity_hashes))
+        }
+        Slot::ArrayReference(array) => {
+            Some(array.identity_hash().get_or_assign(&mut cm.identity_hashes))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seeded_identity_hashes() {
+        let mut first = IdentityHashGenerator::seeded(42);
+        let mut second = IdentityHashGenerator::seeded(42);
+        let hashes: Vec<i32> = (0..16).map(|_| first.next_hash()).collect();
+        assert!(hashes.iter().all(|hash| *hash > 0));
+        assert_eq!(
+            hashes,
+            (0..16).map(|_| second.next_hash()).collect::<Vec<_>>()
+        );
+
+        let identity = IdentityHash::default();
+        let hash = identity.get_or_assign(&mut first);
+        assert_eq!(identity.get_or_assign(&mut first), hash);
+    }
+}
@@ -1,8 +1,20 @@
 pub mod array;
+pub mod heap;
+pub mod identity;
 pub mod object;
+pub mod roots;
+pub mod stats;
+pub mod string;
+pub mod weak;
 
 pub use array::{
     Array, ArrayRef, ArrayRefArray, ByteArray, CharArray, DoubleArray, FloatArray, IntArray,
-    LongArray, ShortArray,
+    LongArray, ObjectRefArray, PrimitiveElement, ShortArray,
 };
+pub use heap::{AllocError, HeapBudget, HeapUsage};
+pub use identity::{identity_hash, IdentityHash, IdentityHashGenerator};
 pub use object::{Object, ObjectRef};
+pub use roots::{Collector, RootProvider, RootSet};
+pub use stats::{AllocationCount, AllocationStats};
+pub use string::{new_string, new_string_array, read_string};
+pub use weak::{WeakRef, WeakRegistry};
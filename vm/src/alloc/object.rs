@@ -1,16 +1,12 @@
-use std::sync::RwLock;
+use std::{mem::size_of, sync::RwLock};
 
 use dumpster::{sync::Gc, Collectable};
-use reader::{
-    base::{classfile::FieldAccessFlags, ClassFile},
-    descriptor,
-};
 
+use super::{weak::WeakAnchor, IdentityHash};
 use crate::{
     class::ClassId,
     class_loader::ClassLoadingError,
     class_manager::{ClassManager, LoadedClass},
-    constant_pool::ConstantPoolError,
     slot::Slot,
 };
 
@@ -23,6 +19,13 @@ pub struct Object {
     // A better solution would have been to use Once but unfortunately it does not
     // implement Collectable.
     initialized: RwLock<ObjectInitState>,
+    /// Recursion count of the object monitor.
+    ///
+    /// Only one thread is executed at a time for now, hence the owner of the
+    /// monitor is not tracked.
+    monitor: RwLock<u32>,
+    identity_hash: IdentityHash,
+    weak_anchor: WeakAnchor,
 }
 
 impl Object {
@@ -35,6 +38,9 @@ impl Object {
             class_id,
             fields: RwLock::new(fields),
             initialized: RwLock::new(ObjectInitState::Uninitialized),
+            monitor: RwLock::new(0),
+            identity_hash: IdentityHash::default(),
+            weak_anchor: WeakAnchor::default(),
         }
     }
 
@@ -52,8 +58,17 @@ impl Object {
             log::debug!("Class not loaded: {:?}", class_id);
             return Err(ClassLoadingError::Unknown);
         };
+        // The fields of the superclasses come first, see Class::field_offset.
+        let mut hierarchy = vec![class];
+        while let Some(superclass) = hierarchy.last().and_then(|class| class.superclass) {
+            let Some(LoadedClass::Loaded(superclass)) = cm.get_class_by_id(superclass) else {
+                log::debug!("Superclass not loaded: {:?}", superclass);
+                return Err(ClassLoadingError::Unknown);
+            };
+            hierarchy.push(superclass);
+        }
         let mut fields = vec![];
-        for f in class.fields.iter() {
+        for f in hierarchy.iter().rev().flat_map(|class| class.fields.iter()) {
             if f.is_static() {
                 fields.push(Slot::Tombstone);
             } else {
@@ -64,40 +79,14 @@ impl Object {
         Ok(Self::new(class_id, fields))
     }
 
-    pub(crate) fn new_with_classfile(
-        class_id: ClassId,
-        classfile: &ClassFile,
-    ) -> Result<Self, ClassLoadingError> {
-        let mut fields = vec![];
-        for f in classfile.fields().iter() {
-            if f.access_flags.contains(FieldAccessFlags::Static) {
-                fields.push(Slot::Tombstone);
-            } else {
-                let Some(descriptor) = classfile
-                    .constant_pool()
-                    .get_utf8_string(f.descriptor_index as usize)
-                else {
-                    log::error!("alloc::object::new_with_classfile - Failed to get field descriptor from constant pool");
-                    return Err(ClassLoadingError::ConstantPoolLoadingError {
-                        source: ConstantPoolError::InvalidUtf8StringReference {
-                            index: f.descriptor_index as usize,
-                        },
-                    });
-                };
-                let desc = descriptor::parse_field_descriptor(&descriptor.to_string()).map_err(|err| {
-                    log::error!("alloc::object::new_with_classfile - Failed to parse field descriptor: {}", &err);
-                    ClassLoadingError::BadDescriptor { source: err }
-                })?;
-                fields.push(Slot::default_for(desc.field_type()));
-            }
-        }
-
-        Ok(Self::new(class_id, fields))
+    /// Estimate the size (in bytes) of an object holding `field_count` fields.
+    pub fn size_estimate(field_count: usize) -> usize {
+        size_of::<Object>() + field_count * size_of::<Slot>()
     }
 
     /// Get the class id of the object
-    pub fn class_id(&self) -> &ClassId {
-        &self.class_id
+    pub fn class_id(&self) -> ClassId {
+        self.class_id
     }
 
     /// Check if the object has been initialized
@@ -126,6 +115,86 @@ impl Object {
             .expect("rwlock has been poisoned, cannot set initialized flag") = state;
     }
 
+    /// Enter the monitor associated with the object
+    pub fn monitor_enter(&self) {
+        *self
+            .monitor
+            .write()
+            .expect("rwlock has been poisoned, cannot enter monitor") += 1;
+    }
+
+    /// Exit the monitor associated with the object
+    ///
+    /// Returns false if the monitor was not held.
+    pub fn monitor_exit(&self) -> bool {
+        let mut count = self
+            .monitor
+            .write()
+            .expect("rwlock has been poisoned, cannot exit monitor");
+        if *count == 0 {
+            return false;
+        }
+        *count -= 1;
+        true
+    }
+
+    /// Get the number of times the monitor has been entered
+    pub fn monitor_count(&self) -> u32 {
+        *self
+            .monitor
+            .read()
+            .expect("rwlock has been poisoned, cannot read monitor")
+    }
+
+    /// Get the identity hash code of the object, see [crate::alloc::identity_hash]
+    pub fn identity_hash(&self) -> &IdentityHash {
+        &self.identity_hash
+    }
+
+    /// Get the liveness token of the object, observed by its weak references
+    pub fn weak_anchor(&self) -> &WeakAnchor {
+        &self.weak_anchor
+    }
+
+    /// Get the index of an instance field of the object by its name.
+    ///
+    /// The field can be declared by the class of the object or one of its
+    /// superclasses, the most derived declaration wins.
+    pub fn field_index(&self, cm: &ClassManager, name: &str) -> Option<usize> {
+        let mut class_id = Some(self.class_id);
+        while let Some(id) = class_id {
+            let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(id) else {
+                return None;
+            };
+            if let Some(index) = class
+                .index_of_field(name)
+                .filter(|index| !class.fields[*index].is_static())
+            {
+                return Some(class.field_offset + index);
+            }
+            class_id = class.superclass;
+        }
+        None
+    }
+
+    /// Get the value of an instance field by its name.
+    pub fn get_field_by_name(&self, cm: &ClassManager, name: &str) -> Option<Slot> {
+        self.get_field(self.field_index(cm, name)?)
+    }
+
+    /// Set the value of an instance field by its name.
+    ///
+    /// Returns false if the object has no such field.
+    pub fn set_field_by_name(&self, cm: &ClassManager, name: &str, value: Slot) -> bool {
+        match self.field_index(cm, name) {
+            Some(index) => {
+                self.set_field(index, value);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get the value at the given index
     pub fn get_field(&self, index: usize) -> Option<Slot> {
         self.fields
@@ -135,6 +204,14 @@ impl Object {
             .cloned()
     }
 
+    /// Get the number of fields of the object, static ones included.
+    pub fn field_count(&self) -> usize {
+        self.fields
+            .read()
+            .expect("rwlock has been poisoned, cannot get fields of object")
+            .len()
+    }
+
     /// Set the value at the given index
     pub fn set_field(&self, index: usize, value: Slot) {
         self.fields
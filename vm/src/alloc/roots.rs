@@ -0,0 +1,196 @@
+//! The roots of the heap: the references held by the VM itself, outside of the
+//! objects and arrays.
+//!
+//! The collector frees the cycles of objects only referenced by each other, so
+//! every reference used by the interpreter must be held by a root: the local
+//! variables and operand stacks of the frames, the static fields of the
+//! classes, their java/lang/Class objects... The [RootProvider]s enumerate
+//! them (see [crate::Vm::gc_roots]).
+//!
+//! The stress mode of the [Collector] collects after every allocation, so that
+//! a reference kept out of the roots is freed as early as possible. After each
+//! of these collections, the roots of the running thread and of the class
+//! manager are checked: a root freed by the collection is reported as dangling.
+
+use dumpster::sync::{self as gc, Gc};
+
+use super::{ArrayRef, ObjectRef};
+use crate::slot::Slot;
+
+/// A holder of references, such as a frame or a class.
+pub trait RootProvider {
+    /// Add the references held by the provider to the set.
+    fn add_roots(&self, roots: &mut RootSet);
+}
+
+/// The references held by some root providers.
+#[derive(Debug, Clone, Default)]
+pub struct RootSet {
+    objects: Vec<ObjectRef>,
+    arrays: Vec<ArrayRef>,
+    /// Number of references to objects or arrays already freed.
+    dangling: usize,
+}
+
+impl RootSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the reference held by a slot, if any.
+    pub fn add_slot(&mut self, slot: &Slot) {
+        match slot {
+            Slot::ObjectReference(obj) => self.add_object(obj),
+            Slot::ArrayReference(array) => self.add_array(array),
+            _ => {}
+        }
+    }
+
+    /// Add a reference to an object, counted as dangling if the object has
+    /// been freed by the collector.
+    pub fn add_object(&mut self, obj: &ObjectRef) {
+        match Gc::try_clone(obj) {
+            Some(obj) => self.objects.push(obj),
+            None => self.dangling += 1,
+        }
+    }
+
+    /// Add a reference to an array, counted as dangling if the array has
+    /// been freed by the collector.
+    pub fn add_array(&mut self, array: &ArrayRef) {
+        match Gc::try_clone(array) {
+            Some(array) => self.arrays.push(array),
+            None => self.dangling += 1,
+        }
+    }
+
+    /// Add the references of a provider.
+    pub fn add_provider(&mut self, provider: &impl RootProvider) {
+        provider.add_roots(self);
+    }
+
+    pub fn objects(&self) -> &[ObjectRef] {
+        &self.objects
+    }
+
+    pub fn arrays(&self) -> &[ArrayRef] {
+        &self.arrays
+    }
+
+    /// Number of roots, a reference held twice counting twice.
+    pub fn len(&self) -> usize {
+        self.objects.len() + self.arrays.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of references to freed objects or arrays, which are not part of
+    /// the set.
+    pub fn dangling(&self) -> usize {
+        self.dangling
+    }
+}
+
+/// The collector settings of a VM.
+///
+/// The collector of dumpster is global to the process: the collections of the
+/// stress mode are requested by the allocations of the VM, and run by its
+/// thread before its next instruction, see [crate::thread::Thread].
+#[derive(Debug, Clone, Default)]
+pub struct Collector {
+    stress: bool,
+    /// An allocation happened since the last collection of the stress mode.
+    requested: bool,
+    collections: u64,
+    dangling_roots: u64,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a collector running after every allocation, to find the
+    /// references missing from the roots.
+    pub fn stress() -> Self {
+        Self {
+            stress: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_stress(&self) -> bool {
+        self.stress
+    }
+
+    /// Number of collections run by the stress mode.
+    pub fn collections(&self) -> u64 {
+        self.collections
+    }
+
+    /// Number of dangling roots found after the collections of the stress mode.
+    pub fn dangling_roots(&self) -> u64 {
+        self.dangling_roots
+    }
+
+    /// Notify the collector of an allocation, requesting a collection in
+    /// stress mode.
+    pub fn after_allocation(&mut self) {
+        self.requested |= self.stress;
+    }
+
+    /// Check if a collection of the stress mode has been requested.
+    pub fn is_requested(&self) -> bool {
+        self.requested
+    }
+
+    /// Collect the unreachable cycles, the roots of the VM being then checked
+    /// with [Collector::check_roots].
+    pub fn collect(&mut self) {
+        gc::collect();
+        self.requested = false;
+        self.collections += 1;
+    }
+
+    /// Check the roots gathered after a collection, reporting the dangling ones.
+    pub fn check_roots(&mut self, roots: &RootSet) {
+        if roots.dangling() > 0 {
+            log::error!(
+                "{} root(s) freed by the collection #{}",
+                roots.dangling(),
+                self.collections
+            );
+            self.dangling_roots += roots.dangling() as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{slot::Slot, test_util::test_class_loader, VmBuilder};
+
+    #[test]
+    fn stress_collections() {
+        let mut vm = VmBuilder::new(test_class_loader()).gc_stress(true).build();
+        let value = vm.invoke_static("GcStress", "run", "()I", vec![]).unwrap();
+        assert!(matches!(value, Some(Slot::Int(4995))));
+        let cm = vm.class_manager();
+        assert!(cm.collector.collections() > 0);
+        assert_eq!(cm.collector.dangling_roots(), 0);
+
+        // The ring is only held by the static field of its class.
+        let roots = vm.gc_roots();
+        let node_class = cm.id_of_class("GcStress$Node").unwrap();
+        assert_eq!(
+            roots
+                .objects()
+                .iter()
+                .filter(|obj| obj.class_id() == node_class)
+                .count(),
+            1
+        );
+        assert_eq!(roots.dangling(), 0);
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use super::{heap::HeapUsage, Array};
+use crate::class::ClassId;
+
+/// Number and estimated size of some allocations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationCount {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+impl AllocationCount {
+    fn record(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes as u64;
+    }
+}
+
+/// Counters of the allocations made since the start of the VM.
+///
+/// The objects are counted by class, the arrays all together. The bytes of
+/// the elements of the live arrays are also tracked, for the heap budget.
+#[derive(Debug, Default)]
+pub struct AllocationStats {
+    objects: HashMap<ClassId, AllocationCount>,
+    arrays: AllocationCount,
+    live_arrays: HeapUsage,
+}
+
+impl AllocationStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the allocation of an instance of a class.
+    pub fn record_object(&mut self, class_id: ClassId, bytes: usize) {
+        self.objects.entry(class_id).or_default().record(bytes);
+    }
+
+    /// Count the allocation of an array, its elements being charged to the
+    /// live arrays until it is dropped.
+    pub fn record_array(&mut self, array: &Array) {
+        self.arrays.record(array.size_estimate());
+        array
+            .heap_charge()
+            .charge(&self.live_arrays, array.data_size());
+    }
+
+    /// Get the allocations of instances of a class.
+    pub fn objects(&self, class_id: ClassId) -> AllocationCount {
+        self.objects.get(&class_id).copied().unwrap_or_default()
+    }
+
+    /// Get the allocations of arrays.
+    pub fn arrays(&self) -> AllocationCount {
+        self.arrays
+    }
+
+    /// Get the number of bytes used by the elements of the live arrays.
+    pub fn live_array_bytes(&self) -> usize {
+        self.live_arrays.bytes()
+    }
+}
@@ -0,0 +1,46 @@
+use super::{Array, ArrayRef, CharArray, Object, ObjectRef, ObjectRefArray};
+use crate::{class_loader::ClassLoadingError, class_manager::ClassManager, slot::Slot};
+
+/// Index of the `value` field (char array) of java/lang/String objects.
+pub const STRING_VALUE_FIELD: usize = 0;
+
+/// Create a new java/lang/String object from a rust string.
+pub fn new_string(cm: &mut ClassManager, value: &str) -> Result<ObjectRef, ClassLoadingError> {
+    let class_id = cm.get_or_resolve_class("java/lang/String")?.id();
+    let obj = Object::new_with_classmanager(cm, class_id)?;
+    obj.set_field(
+        STRING_VALUE_FIELD,
+        Slot::ArrayReference(cm.alloc_array(Array::Char(CharArray::from_string(value)))),
+    );
+    Ok(cm.alloc_object(obj))
+}
+
+/// Create a new java/lang/String array from rust strings.
+pub fn new_string_array(
+    cm: &mut ClassManager,
+    values: &[String],
+) -> Result<ArrayRef, ClassLoadingError> {
+    let class_id = cm.get_or_resolve_class("java/lang/String")?.id();
+    let array = ObjectRefArray::new(class_id, values.len());
+    for (index, value) in values.iter().enumerate() {
+        array.set(index, Some(new_string(cm, value)?));
+    }
+    Ok(cm.alloc_array(Array::ObjectRef(array)))
+}
+
+/// Read the content of a java/lang/String object as a rust string.
+///
+/// Returns None if the object does not look like a string.
+pub fn read_string(obj: &Object) -> Option<String> {
+    let Some(Slot::ArrayReference(array)) = obj.get_field(STRING_VALUE_FIELD) else {
+        return None;
+    };
+    let Array::Char(chars) = array.as_ref() else {
+        return None;
+    };
+    let data = chars
+        .data
+        .read()
+        .expect("rwlock has been poisoned, cannot read string value");
+    Some(String::from_utf16_lossy(&data))
+}
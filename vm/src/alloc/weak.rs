@@ -0,0 +1,311 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, OnceLock, Weak},
+};
+
+use dumpster::{Collectable, Visitor};
+
+use super::{Array, ArrayRef, Object, ObjectRef};
+use crate::{class::ClassId, class_manager::ClassManager, slot::Slot, types};
+
+/// Liveness token of an object, dropped along with it.
+///
+/// The weak references observe the collection of their referent through it,
+/// without holding any Gc pointer to the referent.
+#[derive(Debug, Default)]
+pub struct WeakAnchor {
+    token: OnceLock<Arc<()>>,
+}
+
+impl WeakAnchor {
+    fn token(&self) -> Weak<()> {
+        Arc::downgrade(self.token.get_or_init(|| Arc::new(())))
+    }
+}
+
+// SAFETY: the anchor does not hold any Gc pointer.
+unsafe impl Collectable for WeakAnchor {
+    fn accept<V: Visitor>(&self, _visitor: &mut V) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+/// Handle to a weakly referenced object.
+///
+/// Neither the handle nor the [WeakRegistry] own the referent: the handle is
+/// cleared once the referent is collected, or once a weak reference sweep
+/// finds it unreachable from the VM roots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WeakRef(usize);
+
+/// Hook called for every java/lang/ref/Reference object cleared by a sweep.
+pub type ReferenceClearedHook = fn(&mut ClassManager, &ObjectRef);
+
+/// A weakly referenced object, identified by its address while it is alive.
+#[derive(Debug)]
+struct WeakEntry {
+    key: usize,
+    token: Weak<()>,
+}
+
+impl WeakEntry {
+    fn is_alive(&self) -> bool {
+        self.token.strong_count() > 0
+    }
+}
+
+/// Registry of the weak references of the VM.
+#[derive(Debug, Default)]
+pub struct WeakRegistry {
+    entries: HashMap<usize, WeakEntry>,
+    next_id: usize,
+    hooks: Vec<ReferenceClearedHook>,
+}
+
+impl WeakRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a weak reference to the given object.
+    pub fn register(&mut self, obj: &ObjectRef) -> WeakRef {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = WeakEntry {
+            key: object_key(obj),
+            token: obj.weak_anchor().token(),
+        };
+        self.entries.insert(id, entry);
+        WeakRef(id)
+    }
+
+    /// Check if a weak reference has been cleared.
+    pub fn is_cleared(&self, weak: WeakRef) -> bool {
+        !self.entries.get(&weak.0).is_some_and(WeakEntry::is_alive)
+    }
+
+    /// Check if a weak reference refers to the given object, as
+    /// `Reference.refersTo`.
+    pub fn refers_to(&self, weak: WeakRef, obj: &ObjectRef) -> bool {
+        self.entries
+            .get(&weak.0)
+            .is_some_and(|entry| entry.is_alive() && entry.key == object_key(obj))
+    }
+
+    /// Clear a weak reference.
+    pub fn clear(&mut self, weak: WeakRef) {
+        self.entries.remove(&weak.0);
+    }
+
+    /// Register a hook called for each java/lang/ref/Reference object cleared by a sweep.
+    pub fn add_cleared_hook(&mut self, hook: ReferenceClearedHook) {
+        self.hooks.push(hook);
+    }
+
+    /// Number of live weak references.
+    pub fn len(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|entry| entry.is_alive())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Check if a class is a subclass of java/lang/ref/Reference.
+pub fn is_reference_class(cm: &ClassManager, class_id: ClassId) -> bool {
+    match cm.id_of_class("java/lang/ref/Reference") {
+        Some(reference) => types::is_subtype_of(cm, class_id, reference),
+        None => false,
+    }
+}
+
+/// Index of the `referent` field of a java/lang/ref/Reference object, if any.
+fn referent_index(cm: &ClassManager, obj: &Object) -> Option<usize> {
+    obj.field_index(cm, "referent")
+}
+
+/// Reachability analysis from a set of roots.
+///
+/// The `referent` of java/lang/ref/Reference objects is not followed, as it is
+/// a weak edge.
+#[derive(Debug, Default)]
+pub struct Reachability {
+    objects: HashSet<usize>,
+    arrays: HashSet<usize>,
+    /// The java/lang/ref/Reference objects marked.
+    references: Vec<ObjectRef>,
+}
+
+impl Reachability {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark everything reachable from the given slots.
+    pub fn mark<'a>(&mut self, cm: &ClassManager, roots: impl IntoIterator<Item = &'a Slot>) {
+        let mut stack: Vec<Slot> = roots.into_iter().cloned().collect();
+        while let Some(slot) = stack.pop() {
+            match slot {
+                Slot::ObjectReference(obj) => {
+                    if !self.objects.insert(object_key(&obj)) {
+                        continue;
+                    }
+                    let referent = if is_reference_class(cm, obj.class_id()) {
+                        self.references.push(obj.clone());
+                        referent_index(cm, &obj)
+                    } else {
+                        None
+                    };
+                    let mut index = 0;
+                    while let Some(field) = obj.get_field(index) {
+                        if Some(index) != referent {
+                            stack.push(field);
+                        }
+                        index += 1;
+                    }
+                }
+                Slot::ArrayReference(array) => {
+                    if !self.arrays.insert(array_key(&array)) {
+                        continue;
+                    }
+                    match array.as_ref() {
+                        Array::ObjectRef(array) => stack.extend(
+                            (0..array.len())
+                                .filter_map(|i| array.get(i).flatten())
+                                .map(Slot::ObjectReference),
+                        ),
+                        Array::ArrayRef(array) => stack.extend(
+                            (0..array.len())
+                                .filter_map(|i| array.get(i).flatten())
+                                .map(Slot::ArrayReference),
+                        ),
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Check if an object has been marked as reachable.
+    pub fn is_reachable(&self, obj: &ObjectRef) -> bool {
+        self.objects.contains(&object_key(obj))
+    }
+}
+
+fn object_key(obj: &ObjectRef) -> usize {
+    &**obj as *const Object as usize
+}
+
+fn array_key(array: &ArrayRef) -> usize {
+    &**array as *const Array as usize
+}
+
+/// Clear the weak references whose referent is not reachable.
+///
+/// The referent of the java/lang/ref/Reference objects marked is also set to
+/// null, and the cleared hooks are called for each of them.
+/// Returns the number of cleared weak references.
+pub fn sweep(cm: &mut ClassManager, reachability: &Reachability) -> usize {
+    let registry = &mut cm.weak_refs;
+    let handles = registry.entries.len();
+    registry
+        .entries
+        .retain(|_, entry| entry.is_alive() && reachability.objects.contains(&entry.key));
+    let dead = handles - registry.entries.len();
+
+    let mut cleared = Vec::new();
+    for reference in reachability.references.iter() {
+        let Some(index) = referent_index(cm, reference) else {
+            continue;
+        };
+        if let Some(Slot::ObjectReference(referent)) = reference.get_field(index) {
+            if !reachability.is_reachable(&referent) {
+                reference.set_field(index, Slot::UndefinedReference);
+                cleared.push(reference.clone());
+            }
+        }
+    }
+
+    let hooks = cm.weak_refs.hooks.clone();
+    for reference in cleared.iter() {
+        for hook in hooks.iter() {
+            hook(cm, reference);
+        }
+    }
+    log::debug!(
+        "Weak reference sweep: {} handle(s) and {} reference object(s) cleared",
+        dead,
+        cleared.len()
+    );
+    dead + cleared.len()
+}
+
+#[cfg(test)]
+mod test {
+    use dumpster::sync::Gc;
+
+    use super::*;
+    use crate::test_util::{test_class_manager, test_vm};
+
+    #[test]
+    fn register_and_clear() {
+        let mut registry = WeakRegistry::new();
+        let obj: ObjectRef = Gc::new(Object::new(ClassId(0), vec![]));
+        let other: ObjectRef = Gc::new(Object::new(ClassId(0), vec![]));
+        let weak = registry.register(&obj);
+        assert!(registry.refers_to(weak, &obj));
+        assert!(!registry.refers_to(weak, &other));
+        assert!(!registry.is_cleared(weak));
+
+        registry.clear(weak);
+        assert!(registry.is_cleared(weak));
+        assert!(!registry.refers_to(weak, &obj));
+        assert!(registry.is_empty());
+
+        // The registry does not keep the referent alive.
+        let weak = registry.register(&obj);
+        drop(obj);
+        assert!(registry.is_cleared(weak));
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn sweep_unreachable_referents() {
+        let mut cm = test_class_manager();
+        let reachable: ObjectRef = Gc::new(Object::new(ClassId(0), vec![]));
+        let unreachable: ObjectRef = Gc::new(Object::new(ClassId(0), vec![]));
+        let holder = Gc::new(Object::new(
+            ClassId(0),
+            vec![Slot::ObjectReference(reachable.clone())],
+        ));
+        let kept = cm.weak_refs.register(&reachable);
+        let dropped = cm.weak_refs.register(&unreachable);
+
+        let mut reachability = Reachability::new();
+        reachability.mark(&cm, [Slot::ObjectReference(holder)].iter());
+        assert_eq!(sweep(&mut cm, &reachability), 1);
+        assert!(cm.weak_refs.refers_to(kept, &reachable));
+        assert!(cm.weak_refs.is_cleared(dropped));
+        assert_eq!(cm.weak_refs.len(), 1);
+    }
+
+    #[test]
+    fn clear_reference_objects() {
+        let mut vm = test_vm();
+        fn call(vm: &mut crate::Vm, method: &str) -> bool {
+            match vm.invoke_static("WeakRefs", method, "()Z", vec![]) {
+                Ok(Some(Slot::Int(value))) => value != 0,
+                result => panic!("Unexpected result of {}: {:?}", method, result),
+            }
+        }
+        assert!(!call(&mut vm, "cleared"));
+        assert!(vm.collect_weak_refs() >= 1);
+        assert!(call(&mut vm, "cleared"));
+        assert!(call(&mut vm, "alive"));
+    }
+}
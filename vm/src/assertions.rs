@@ -0,0 +1,164 @@
+//! The assertion status of the classes, set by the `-ea` and `-da` options of
+//! the java launcher.
+//!
+//! javac compiles the `assert` statements of a class to a check of its static
+//! `$assertionsDisabled` field, initialized with `Class.desiredAssertionStatus()`.
+
+use std::{collections::HashMap, fmt};
+
+/// Packages of the class library, whose assertions are not enabled by a plain
+/// `-ea`, as the JVM only enables them with `-esa`.
+const SYSTEM_PACKAGES: [&str; 4] = ["java/", "javax/", "jdk/", "sun/"];
+
+/// The classes affected by a `-ea` or `-da` option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssertionScope {
+    /// All the classes but the system ones, `-ea`.
+    All,
+    /// A package and its subpackages by binary name, e.g. `-ea:com.example...`.
+    ///
+    /// The unnamed package (`-ea:...`) has an empty name.
+    Package(String),
+    /// A class by binary name, e.g. `-ea:com.example.Main`.
+    Class(String),
+}
+
+impl AssertionScope {
+    /// Parse the argument of a `-ea` or `-da` option, without the colon.
+    pub fn parse(input: &str) -> Self {
+        if input.is_empty() {
+            AssertionScope::All
+        } else if let Some(package) = input.strip_suffix("...") {
+            AssertionScope::Package(package.replace('.', "/"))
+        } else {
+            AssertionScope::Class(input.replace('.', "/"))
+        }
+    }
+}
+
+/// Display the scope as the argument of a `-ea` option, e.g. `:com.example...`.
+impl fmt::Display for AssertionScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssertionScope::All => Ok(()),
+            AssertionScope::Package(package) => write!(f, ":{}...", package.replace('/', ".")),
+            AssertionScope::Class(class) => write!(f, ":{}", class.replace('/', ".")),
+        }
+    }
+}
+
+/// The desired assertion status of the classes.
+///
+/// A class directive wins over the package ones, the directive of the closest
+/// package winning over the ones of its parents, as in the JVM. The last
+/// directive of a scope replaces the previous ones.
+#[derive(Debug, Clone, Default)]
+pub struct AssertionStatus {
+    default: bool,
+    packages: HashMap<String, bool>,
+    classes: HashMap<String, bool>,
+}
+
+impl AssertionStatus {
+    /// Create a status with the assertions disabled everywhere.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable the assertions of the given classes.
+    pub fn set(&mut self, scope: &AssertionScope, enabled: bool) {
+        match scope {
+            AssertionScope::All => self.default = enabled,
+            AssertionScope::Package(package) => {
+                self.packages.insert(package.clone(), enabled);
+            }
+            AssertionScope::Class(class) => {
+                self.classes.insert(class.clone(), enabled);
+            }
+        }
+    }
+
+    /// Get whether the assertions of a class, by binary name, are enabled.
+    pub fn desired(&self, class_name: &str) -> bool {
+        if let Some(enabled) = self.classes.get(class_name) {
+            return *enabled;
+        }
+        let mut package = class_name
+            .rsplit_once('/')
+            .map_or("", |(package, _)| package);
+        loop {
+            if let Some(enabled) = self.packages.get(package) {
+                return *enabled;
+            }
+            match package.rsplit_once('/') {
+                Some((parent, _)) => package = parent,
+                // The unnamed package is not the parent of the top-level packages.
+                None => break,
+            }
+        }
+        let is_system = SYSTEM_PACKAGES
+            .iter()
+            .any(|prefix| class_name.starts_with(prefix));
+        self.default && !is_system
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assertion_directives() {
+        let mut status = AssertionStatus::new();
+        assert!(!status.desired("Main"));
+
+        status.set(&AssertionScope::parse(""), true);
+        status.set(&AssertionScope::parse("com.example..."), false);
+        status.set(&AssertionScope::parse("com.example.debug..."), true);
+        status.set(&AssertionScope::parse("com.example.Checked"), true);
+        assert!(status.desired("Main"));
+        assert!(status.desired("org/other/Main"));
+        assert!(!status.desired("java/util/HashMap"));
+        assert!(!status.desired("com/example/Main"));
+        assert!(!status.desired("com/example/sub/Main"));
+        assert!(status.desired("com/example/debug/Tracer"));
+        assert!(status.desired("com/example/Checked"));
+
+        status.set(&AssertionScope::parse("..."), false);
+        status.set(&AssertionScope::parse("com.example..."), true);
+        assert!(!status.desired("Main"));
+        assert!(status.desired("com/example/Main"));
+        assert!(status.desired("org/other/Main"));
+
+        assert_eq!(
+            AssertionScope::parse("com.example...").to_string(),
+            ":com.example..."
+        );
+        assert_eq!(AssertionScope::parse("...").to_string(), ":...");
+        assert_eq!(AssertionScope::parse("a.B").to_string(), ":a.B");
+    }
+
+    #[test]
+    fn desired_assertion_status() {
+        use crate::{slot::Slot, test_util::test_class_loader, VmBuilder};
+
+        let enabled = |scope: Option<&str>| {
+            let mut assertions = AssertionStatus::new();
+            if let Some(scope) = scope {
+                assertions.set(&AssertionScope::parse(scope), true);
+            }
+            let mut vm = VmBuilder::new(test_class_loader())
+                .assertions(assertions)
+                .build();
+            match vm.invoke_static("Assertions", "enabled", "()Z", vec![]) {
+                Ok(Some(Slot::Int(enabled))) => enabled != 0,
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        };
+        assert!(!enabled(None));
+        assert!(enabled(Some("")));
+        assert!(enabled(Some("Assertions")));
+        assert!(enabled(Some("...")));
+        assert!(!enabled(Some("Other")));
+    }
+}
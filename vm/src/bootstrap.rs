@@ -0,0 +1,304 @@
+use reader::{base::constant_pool::ReferenceKind, descriptor::FieldType};
+
+use crate::{
+    alloc::new_string,
+    class::ClassId,
+    class_manager::{ClassManager, LoadedClass},
+    constant_pool::{resolve_string_constant, ConstantPoolEntry, DynamicCallSite, DynamicConstant},
+    lambda,
+    opcode::InstructionError,
+    slot::Slot,
+    value::type_name,
+};
+
+/// Number of arguments given to a bootstrap method before its static arguments:
+/// the lookup, the name and the type of the dynamic constant.
+const BOOTSTRAP_LEADING_ARGS: usize = 3;
+
+/// Resolve the dynamically-computed constant at the given index of the constant
+/// pool of a class (JVMS 5.4.3.6).
+///
+/// The bootstrap method is only invoked on the first resolution, the produced
+/// value is then cached in the runtime constant pool.
+///
+/// Only the static bootstrap methods (REF_invokeStatic handles) with exactly one
+/// parameter per argument are supported. As method handles are not implemented,
+/// the lookup argument is `null`, and so is the type argument of the primitive
+/// and array constants.
+pub fn resolve_dynamic_constant(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    index: u16,
+) -> Result<Slot, InstructionError> {
+    let constant = dynamic_constant(cm, class_id, index)?;
+    if let Some(value) = constant.value.get() {
+        return Ok(value.clone());
+    }
+    let constant = constant.clone();
+    let value = invoke_bootstrap_method(cm, class_id, &constant)?;
+    if !value.matches_type(constant.descriptor.field_type()) {
+        return Err(InstructionError::BootstrapMethod {
+            context: format!(
+                "the value of the dynamic constant {}, {:?}, is not of type {}",
+                constant.name,
+                value,
+                type_name(constant.descriptor.field_type())
+            ),
+        });
+    }
+    // A nested resolution of the same constant may already have set the value,
+    // which is then the one to keep.
+    let constant = dynamic_constant(cm, class_id, index)?;
+    Ok(constant.value.get_or_init(|| value).clone())
+}
+
+fn dynamic_constant(
+    cm: &ClassManager,
+    class_id: ClassId,
+    index: u16,
+) -> Result<&DynamicConstant, InstructionError> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found (or not loaded): ClassId({})", class_id.0),
+        });
+    };
+    match class.constant_pool.get(index as usize) {
+        Some(ConstantPoolEntry::DynamicConstant(constant)) => Ok(constant),
+        _ => Err(InstructionError::InvalidState {
+            context: format!(
+                "DynamicConstant not found: ClassId({}), constant pool index {}",
+                class_id.0, index
+            ),
+        }),
+    }
+}
+
+/// Link the dynamically-computed call site at the given index of the constant
+/// pool of a class (JVMS 5.4.3.6), and get the class of the objects it produces.
+///
+/// Only the call sites bootstrapped by the lambda metafactory are supported,
+/// the VM spinning the lambda classes itself (see [crate::lambda]). The call
+/// site is linked on its first resolution, and then cached in the runtime
+/// constant pool.
+pub fn resolve_call_site(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    index: u16,
+) -> Result<ClassId, InstructionError> {
+    let call_site = call_site(cm, class_id, index)?;
+    if let Some(target) = call_site.target.get() {
+        return Ok(*target);
+    }
+    let call_site = call_site.clone();
+    let bootstrap_method = {
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+            return Err(InstructionError::InvalidState {
+                context: format!("Class not found (or not loaded): ClassId({})", class_id.0),
+            });
+        };
+        match class.constant_pool.get(call_site.method_handle) {
+            Some(ConstantPoolEntry::MethodHandleReference(ReferenceKind::InvokeStatic, index)) => {
+                class
+                    .constant_pool
+                    .any_method_ref(*index)
+                    .map(|method_ref| {
+                        (
+                            cm.get_class_by_id(method_ref.implementor)
+                                .map(|class| class.name().to_string())
+                                .unwrap_or_default(),
+                            method_ref.name.to_string(),
+                        )
+                    })
+            }
+            _ => None,
+        }
+    };
+    let Some((bootstrap_class, bootstrap_name)) = bootstrap_method else {
+        return Err(InstructionError::BootstrapMethod {
+            context: format!(
+                "the bootstrap method of the call site {} is not a static method handle",
+                call_site.name
+            ),
+        });
+    };
+    if !lambda::is_metafactory(&bootstrap_class, &bootstrap_name) {
+        return Err(InstructionError::BootstrapMethod {
+            context: format!(
+                "unsupported bootstrap method {}.{} for the call site {}",
+                bootstrap_class, bootstrap_name, call_site.name
+            ),
+        });
+    }
+    let target = lambda::define_lambda_class(cm, class_id, &call_site)?;
+    // A nested resolution of the same call site may already have linked it,
+    // its class is then the one to keep.
+    let call_site = self::call_site(cm, class_id, index)?;
+    Ok(*call_site.target.get_or_init(|| target))
+}
+
+fn call_site(
+    cm: &ClassManager,
+    class_id: ClassId,
+    index: u16,
+) -> Result<&DynamicCallSite, InstructionError> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found (or not loaded): ClassId({})", class_id.0),
+        });
+    };
+    match class.constant_pool.get(index as usize) {
+        Some(ConstantPoolEntry::DynamicCCallSite(call_site)) => Ok(call_site),
+        _ => Err(InstructionError::InvalidState {
+            context: format!(
+                "DynamicCallSite not found: ClassId({}), constant pool index {}",
+                class_id.0, index
+            ),
+        }),
+    }
+}
+
+fn invoke_bootstrap_method(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    constant: &DynamicConstant,
+) -> Result<Slot, InstructionError> {
+    let bootstrap_error = |context: String| InstructionError::BootstrapMethod { context };
+    let (name, descriptor, implementor) = {
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+            return Err(InstructionError::InvalidState {
+                context: format!("Class not found (or not loaded): ClassId({})", class_id.0),
+            });
+        };
+        let method_ref = match class.constant_pool.get(constant.method_handle) {
+            Some(ConstantPoolEntry::MethodHandleReference(ReferenceKind::InvokeStatic, index)) => {
+                class.constant_pool.any_method_ref(*index)
+            }
+            _ => None,
+        };
+        let Some(method_ref) = method_ref else {
+            return Err(bootstrap_error(format!(
+                "the bootstrap method of {} is not a static method handle",
+                constant.name
+            )));
+        };
+        (
+            method_ref.name.to_string(),
+            method_ref.descriptor.clone(),
+            method_ref.implementor,
+        )
+    };
+    let expected = BOOTSTRAP_LEADING_ARGS + constant.arguments_ref.len();
+    if descriptor.parameters.len() != expected {
+        return Err(bootstrap_error(format!(
+            "{}{} takes {} parameter(s), {} expected",
+            name,
+            descriptor.as_str(),
+            descriptor.parameters.len(),
+            expected
+        )));
+    }
+
+    let class_loading_error =
+        |cm: &ClassManager, class_id: ClassId, err| InstructionError::ClassLoadingError {
+            class_name: cm
+                .get_class_by_id(class_id)
+                .map(|class| class.name().to_string())
+                .unwrap_or_default(),
+            source: Box::new(err),
+        };
+    cm.request_class_load(implementor)
+        .map_err(|err| class_loading_error(cm, implementor, err))?;
+    let Some((declaring, method)) = cm
+        .resolve_method(class_id, implementor, &name, &descriptor, false)
+        .map_err(|err| class_loading_error(cm, implementor, err))?
+    else {
+        return Err(bootstrap_error(format!(
+            "bootstrap method not found: {}{}",
+            name,
+            descriptor.as_str()
+        )));
+    };
+
+    let mut args = Vec::with_capacity(expected);
+    args.push(Slot::UndefinedReference);
+    let constant_name = new_string(cm, constant.name.as_str())
+        .map_err(|err| class_loading_error(cm, class_id, err))?;
+    args.push(Slot::ObjectReference(constant_name));
+    args.push(type_argument(cm, constant.descriptor.field_type())?);
+    for index in constant.arguments_ref.iter() {
+        args.push(static_argument(cm, class_id, *index)?);
+    }
+
+    match cm.run_method(declaring, method, args) {
+        Ok(Some(value)) => Ok(value),
+        Ok(None) => Err(bootstrap_error(format!(
+            "the bootstrap method {} of {} returned no value",
+            name, constant.name
+        ))),
+        Err(err) => Err(bootstrap_error(format!(
+            "the bootstrap method {} of {} failed: {}",
+            name, constant.name, err
+        ))),
+    }
+}
+
+/// Get the Class object given as the type of the constant to the bootstrap method.
+fn type_argument(cm: &mut ClassManager, field_type: &FieldType) -> Result<Slot, InstructionError> {
+    let FieldType::ObjectType(object) = field_type else {
+        return Ok(Slot::UndefinedReference);
+    };
+    let class_name = object.class_name.as_binary_name();
+    let class_object = cm
+        .get_or_resolve_class(&class_name)
+        .map(|class| class.id())
+        .and_then(|class_id| cm.get_class_object(class_id))
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name,
+            source: Box::new(err),
+        })?;
+    Ok(Slot::ObjectReference(class_object))
+}
+
+/// Get the value of a static argument of a bootstrap method.
+fn static_argument(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    index: usize,
+) -> Result<Slot, InstructionError> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found (or not loaded): ClassId({})", class_id.0),
+        });
+    };
+    let slot = match class.constant_pool.get(index) {
+        Some(ConstantPoolEntry::IntegerConstant(value)) => Slot::Int(*value),
+        Some(ConstantPoolEntry::FloatConstant(value)) => Slot::Float(*value),
+        Some(ConstantPoolEntry::LongConstant(value)) => Slot::Long(*value),
+        Some(ConstantPoolEntry::DoubleConstant(value)) => Slot::Double(*value),
+        Some(ConstantPoolEntry::StringReference(_)) => {
+            Slot::ObjectReference(resolve_string_constant(cm, class_id, index)?)
+        }
+        Some(ConstantPoolEntry::ClassReference(referenced)) => {
+            let referenced = *referenced;
+            let class_object = cm.get_class_object(referenced).map_err(|err| {
+                InstructionError::ClassLoadingError {
+                    class_name: "java/lang/Class".into(),
+                    source: Box::new(err),
+                }
+            })?;
+            Slot::ObjectReference(class_object)
+        }
+        Some(ConstantPoolEntry::DynamicConstant(_)) => {
+            resolve_dynamic_constant(cm, class_id, index as u16)?
+        }
+        constant => {
+            return Err(InstructionError::BootstrapMethod {
+                context: format!(
+                    "unsupported static argument at index {}: {:?}",
+                    index, constant
+                ),
+            });
+        }
+    };
+    Ok(slot)
+}
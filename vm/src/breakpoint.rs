@@ -0,0 +1,164 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::{class::ClassId, class_manager::ClassManager, thread::Thread};
+
+/// Location of a breakpoint: an instruction of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Breakpoint {
+    pub class: ClassId,
+    /// Index of the method in the methods of its class.
+    pub method: usize,
+    pub pc: usize,
+}
+
+/// What to do after a breakpoint hook has been called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointAction {
+    /// Resume the execution of the thread.
+    Continue,
+    /// Remove the breakpoint, and resume the execution of the thread.
+    Remove,
+}
+
+/// Hook called when a thread reaches a breakpoint, before the instruction is executed.
+///
+/// The thread is paused until the hook returns.
+pub type BreakpointHook =
+    Box<dyn FnMut(&Breakpoint, &Thread, &ClassManager) -> BreakpointAction + Send>;
+
+/// Breakpoints set on the methods of the loaded classes.
+///
+/// The interpreter looks up the breakpoints of a method when entering it, so a
+/// method without breakpoint is executed at full speed.
+#[derive(Default)]
+pub struct BreakpointManager {
+    methods: HashMap<(ClassId, usize), BTreeSet<usize>>,
+    hook: Option<BreakpointHook>,
+}
+
+impl fmt::Debug for BreakpointManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BreakpointManager")
+            .field("methods", &self.methods)
+            .field("hook", &self.hook.is_some())
+            .finish()
+    }
+}
+
+impl BreakpointManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the hook called when a breakpoint is reached, the breakpoints being
+    /// logged by default.
+    pub fn set_hook(&mut self, hook: BreakpointHook) {
+        self.hook = Some(hook);
+    }
+
+    /// Take the hook out of the manager, to call it while the class manager
+    /// is borrowed. It must then be put back with [BreakpointManager::set_hook].
+    pub(crate) fn take_hook(&mut self) -> Option<BreakpointHook> {
+        self.hook.take()
+    }
+
+    /// Set a breakpoint, returns false if it was already set.
+    pub fn insert(&mut self, breakpoint: Breakpoint) -> bool {
+        self.methods
+            .entry((breakpoint.class, breakpoint.method))
+            .or_default()
+            .insert(breakpoint.pc)
+    }
+
+    /// Remove a breakpoint, returns false if it was not set.
+    pub fn remove(&mut self, breakpoint: &Breakpoint) -> bool {
+        let key = (breakpoint.class, breakpoint.method);
+        let Some(pcs) = self.methods.get_mut(&key) else {
+            return false;
+        };
+        let removed = pcs.remove(&breakpoint.pc);
+        if pcs.is_empty() {
+            self.methods.remove(&key);
+        }
+        removed
+    }
+
+    /// Remove all the breakpoints.
+    pub fn clear(&mut self) {
+        self.methods.clear();
+    }
+
+    pub fn contains(&self, breakpoint: &Breakpoint) -> bool {
+        self.methods
+            .get(&(breakpoint.class, breakpoint.method))
+            .is_some_and(|pcs| pcs.contains(&breakpoint.pc))
+    }
+
+    /// Check if a method has at least one breakpoint.
+    pub fn in_method(&self, class: ClassId, method: usize) -> bool {
+        self.methods.contains_key(&(class, method))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.methods.values().map(BTreeSet::len).sum()
+    }
+
+    /// Iterate over the breakpoints, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = Breakpoint> + '_ {
+        self.methods.iter().flat_map(|((class, method), pcs)| {
+            pcs.iter().map(|pc| Breakpoint {
+                class: *class,
+                method: *method,
+                pc: *pc,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::{slot::Slot, test_util::test_vm, VmError};
+
+    #[test]
+    fn breakpoint_hook() {
+        let mut vm = test_vm();
+        assert!(matches!(
+            vm.set_breakpoint("Parameters", "add", None, 4),
+            Err(VmError::InvalidPc { pc: 4, .. })
+        ));
+        // iload_0, iload_1, then the breakpoint on iadd.
+        let breakpoint = vm.set_breakpoint("Parameters", "add", None, 2).unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let hook_hits = hits.clone();
+        vm.set_breakpoint_hook(Box::new(move |_, thread, _| {
+            let frame = thread.stack.last().unwrap();
+            assert!(matches!(
+                frame.operand_stack[..],
+                [Slot::Int(2), Slot::Int(3)]
+            ));
+            hook_hits.fetch_add(1, Ordering::Relaxed);
+            BreakpointAction::Remove
+        }));
+
+        let args = vec![Slot::Int(2), Slot::Int(3)];
+        let result = vm.invoke_static("Parameters", "add", "(II)I", args.clone());
+        assert!(matches!(result, Ok(Some(Slot::Int(5)))));
+        assert!(!vm.class_manager().breakpoints.contains(&breakpoint));
+        vm.invoke_static("Parameters", "add", "(II)I", args)
+            .unwrap();
+        assert_eq!(hits.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,203 @@
+//! Logging of the method calls, for the classes selected by globs.
+//!
+//! The entries and exits of the methods are logged at the info level with the
+//! `vm::calls` target, e.g. `-> com.example.Main.add(II)I (int 1, int 2)` and
+//! `<- com.example.Main.add(II)I = int 3`, indented by the depth of the stack.
+
+use crate::{
+    class::ClassId,
+    class_manager::{ClassManager, LoadedClass},
+    debug::display_slot,
+    slot::Slot,
+};
+
+/// Target of the logs of the method calls.
+pub const CALL_LOG_TARGET: &str = "vm::calls";
+
+/// A glob over the binary names of the classes, e.g. `com/example/*`.
+///
+/// `*` matches any sequence of characters (`/` included, hence the subpackages)
+/// and `?` a single character. The dots are read as slashes, so
+/// `com.example.*` is the same glob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassGlob {
+    pattern: Vec<char>,
+}
+
+impl ClassGlob {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.replace('.', "/").chars().collect(),
+        }
+    }
+
+    /// Check if a class, by binary name, matches the glob.
+    pub fn matches(&self, class_name: &str) -> bool {
+        let name: Vec<char> = class_name.chars().collect();
+        // Iterative matching, backtracking to the last star on a mismatch.
+        let (mut p, mut n) = (0, 0);
+        let mut star: Option<(usize, usize)> = None;
+        while n < name.len() {
+            match self.pattern.get(p) {
+                Some('*') => {
+                    star = Some((p, n));
+                    p += 1;
+                }
+                Some('?') => {
+                    p += 1;
+                    n += 1;
+                }
+                Some(c) if *c == name[n] => {
+                    p += 1;
+                    n += 1;
+                }
+                _ => match star {
+                    Some((star_p, star_n)) => {
+                        p = star_p + 1;
+                        n = star_n + 1;
+                        star = Some((star_p, star_n + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+        self.pattern[p..].iter().all(|c| *c == '*')
+    }
+}
+
+/// The classes whose method calls are logged.
+///
+/// A class is selected if it matches one of the included globs (or if there is
+/// none), and none of the excluded ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallFilter {
+    include: Vec<ClassGlob>,
+    exclude: Vec<ClassGlob>,
+}
+
+impl CallFilter {
+    /// Create a filter selecting all the classes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Log the calls of the classes matching the glob.
+    pub fn include(mut self, glob: &str) -> Self {
+        self.include.push(ClassGlob::new(glob));
+        self
+    }
+
+    /// Do not log the calls of the classes matching the glob.
+    pub fn exclude(mut self, glob: &str) -> Self {
+        self.exclude.push(ClassGlob::new(glob));
+        self
+    }
+
+    /// Check if the calls of a class, by binary name, are logged.
+    pub fn matches(&self, class_name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(class_name)))
+            && !self.exclude.iter().any(|glob| glob.matches(class_name))
+    }
+}
+
+/// Log the entry of a method, with its arguments, if the call logging selects its class.
+pub(crate) fn log_entry(
+    cm: &ClassManager,
+    class_id: ClassId,
+    method_index: usize,
+    args: &[Slot],
+    depth: usize,
+) {
+    let Some(method) = logged_method(cm, class_id, method_index) else {
+        return;
+    };
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| display_slot(cm, arg).to_string())
+        .collect();
+    log::info!(
+        target: CALL_LOG_TARGET,
+        "{:indent$}-> {} ({})",
+        "",
+        method,
+        args.join(", "),
+        indent = 2 * depth
+    );
+}
+
+/// Log the normal completion of a method, with its returned value if any, if
+/// the call logging selects its class.
+pub(crate) fn log_exit(
+    cm: &ClassManager,
+    class_id: ClassId,
+    method_index: usize,
+    value: Option<&Slot>,
+    depth: usize,
+) {
+    let Some(method) = logged_method(cm, class_id, method_index) else {
+        return;
+    };
+    match value {
+        Some(value) => log::info!(
+            target: CALL_LOG_TARGET,
+            "{:indent$}<- {} = {}",
+            "",
+            method,
+            display_slot(cm, value),
+            indent = 2 * depth
+        ),
+        None => log::info!(
+            target: CALL_LOG_TARGET,
+            "{:indent$}<- {}",
+            "",
+            method,
+            indent = 2 * depth
+        ),
+    }
+}
+
+/// Get the name of a method whose calls are logged, e.g. `com.example.Main.add(II)I`.
+fn logged_method(cm: &ClassManager, class_id: ClassId, method_index: usize) -> Option<String> {
+    let filter = cm.call_log.as_ref()?;
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return None;
+    };
+    if !filter.matches(&class.name) {
+        return None;
+    }
+    let method = class.get_method_by_index(method_index)?;
+    Some(format!(
+        "{}.{}{}",
+        class.name.replace('/', "."),
+        method.name,
+        method.descriptor.as_str()
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn class_globs() {
+        let glob = ClassGlob::new("com/example/*");
+        assert!(glob.matches("com/example/Main"));
+        assert!(glob.matches("com/example/sub/Helper"));
+        assert!(!glob.matches("com/other/Main"));
+        assert!(!glob.matches("com/example"));
+        assert!(ClassGlob::new("com.example.Ma?n").matches("com/example/Main"));
+        assert!(ClassGlob::new("*Test*").matches("org/FooTests"));
+        assert!(!ClassGlob::new("*Test").matches("org/FooTests"));
+
+        let filter = CallFilter::new()
+            .include("com/example/*")
+            .exclude("com/example/generated/*");
+        assert!(filter.matches("com/example/Main"));
+        assert!(!filter.matches("com/example/generated/Parser"));
+        assert!(!filter.matches("java/lang/String"));
+        assert!(CallFilter::new().exclude("java/*").matches("Main"));
+        assert!(!CallFilter::new()
+            .exclude("java/*")
+            .matches("java/lang/Object"));
+    }
+}
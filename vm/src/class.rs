@@ -1,13 +1,24 @@
 use crate::{
-    alloc::{Object, ObjectRef},
+    alloc::{Object, ObjectRef, RootProvider, RootSet},
     slot::Slot,
 };
-use std::{cell::OnceCell, io::Cursor, sync::Once};
+use std::{
+    borrow::Cow,
+    cell::OnceCell,
+    io::Cursor,
+    mem::size_of,
+    sync::{Arc, OnceLock},
+};
 
 use crate::{
     class_loader::ClassLoadingError,
     class_manager::ClassManager,
-    constant_pool::{ConstantPool, ConstantPoolError},
+    constant_pool::{ConstantPool, ConstantPoolEntry, ConstantPoolError},
+    descriptor_table::InternedMethodDescriptor,
+    intrinsic::{Intrinsic, IntrinsicRegistry},
+    opcode::{InstructionCache, InstructionError},
+    types,
+    value::type_name,
 };
 use dumpster::Collectable;
 use flagset::FlagSet;
@@ -18,12 +29,19 @@ use reader::{
 };
 use reader::{
     base::{
-        attribute_info::{CodeAttribute, ConstantValueAttribute},
+        attribute_info::{
+            CodeAttribute, ConstantValueAttribute, EnclosingMethodAttribute, ExceptionsAttribute,
+            InnerClassAccessFlags, InnerClassesAttribute, LineNumberTableAttribute,
+            LocalVariableTableAttribute, LocalVariableTypeTableAttribute,
+            MethodParameterAccessFlags, MethodParametersAttribute, NestHostAttribute,
+            NestMembersAttribute, StackMapTableAttribute,
+        },
         classfile,
         constant_pool::ConstantPoolInfo as ClassfileConstantPoolInfo,
-        AttributeInfo, ConstantPool as ClassfileConstantPool,
+        stack_frame::StackMapFrame as ClassfileStackMapFrame,
+        AttributeInfo, ConstantPool as ClassfileConstantPool, VerificationTypeInfo,
     },
-    descriptor::{self, FieldDescriptor, MethodDescriptor},
+    descriptor::{self, BaseType, ClassName, FieldDescriptor, FieldType},
 };
 
 /// Runtime identifier for a class.
@@ -45,7 +63,14 @@ pub struct Class {
     pub superclass: Option<ClassId>,
     pub interfaces: Vec<ClassId>,
     pub flags: FlagSet<ClassAccessFlags>,
+    /// Major version of the classfile of the class.
+    pub major_version: u16,
     pub fields: Vec<Field>,
+    /// Index of the first field of this class in its instances.
+    ///
+    /// The instances hold the fields of their superclasses first, the fields
+    /// declared by this class start right after them.
+    pub field_offset: usize,
     pub methods: Vec<Method>,
     /// Whether the class has been initialized.
     ///
@@ -53,13 +78,14 @@ pub struct Class {
     /// This is particularly useful for ensuring final static fields are set only once.
     pub initialized: OnceCell<bool>,
     pub class_object: OnceCell<ObjectRef>,
+    pub attributes: Vec<ClassAttribute>,
 }
 
 impl Class {
     pub fn get_method(
         &self,
         name: &str,
-        descriptor: &MethodDescriptor,
+        descriptor: &InternedMethodDescriptor,
     ) -> Option<(usize, &Method)> {
         self.methods
             .iter()
@@ -83,7 +109,11 @@ impl Class {
         self.methods.get(index)
     }
 
-    pub fn index_of_method(&self, name: &str, descriptor: &MethodDescriptor) -> Option<usize> {
+    pub fn index_of_method(
+        &self,
+        name: &str,
+        descriptor: &InternedMethodDescriptor,
+    ) -> Option<usize> {
         self.methods
             .iter()
             .position(|method| method.name == name && method.descriptor == *descriptor)
@@ -96,6 +126,188 @@ impl Class {
     pub fn is_array_class(&self) -> bool {
         self.name.starts_with('[')
     }
+
+    pub fn is_interface(&self) -> bool {
+        self.flags.contains(ClassAccessFlags::Interface)
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.flags.contains(ClassAccessFlags::Abstract)
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.flags.contains(ClassAccessFlags::Final)
+    }
+
+    /// Check if the ACC_SUPER flag is set, changing the `invokespecial` semantics.
+    pub fn is_super(&self) -> bool {
+        self.flags.contains(ClassAccessFlags::Super)
+    }
+
+    /// Get the name of the runtime package of this class, empty for the
+    /// unnamed package.
+    ///
+    /// The package of an array class is the one of its element class.
+    pub fn package_name(&self) -> &str {
+        let name = self.name.trim_start_matches('[');
+        let name = name.strip_prefix('L').unwrap_or(name);
+        match name.rsplit_once('/') {
+            Some((package, _)) => package,
+            None => "",
+        }
+    }
+
+    /// Get the name of the nest host of this class.
+    ///
+    /// A class without NestHost attribute is the host of its own nest.
+    pub fn nest_host(&self) -> &str {
+        self.attributes
+            .iter()
+            .find_map(|attr| match attr {
+                ClassAttribute::NestHost(host) => Some(host.as_str()),
+                _ => None,
+            })
+            .unwrap_or(&self.name)
+    }
+
+    /// Check if this is a hidden class, defined by the VM.
+    pub fn is_hidden(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attr| matches!(attr, ClassAttribute::Hidden))
+    }
+
+    /// Get the names of the members of the nest hosted by this class.
+    pub fn nest_members(&self) -> &[String] {
+        self.attributes
+            .iter()
+            .find_map(|attr| match attr {
+                ClassAttribute::NestMembers(members) => Some(members.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// Get the entries of the InnerClasses attribute of this class.
+    ///
+    /// They describe every nested class referenced by this class, including
+    /// this class itself if it is nested.
+    pub fn inner_classes(&self) -> &[InnerClassInfo] {
+        self.attributes
+            .iter()
+            .find_map(|attr| match attr {
+                ClassAttribute::InnerClasses(classes) => Some(classes.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// Get the InnerClasses entry describing this class, if it is a nested class.
+    pub fn inner_class_info(&self) -> Option<&InnerClassInfo> {
+        self.inner_classes()
+            .iter()
+            .find(|info| info.inner_class == self.name)
+    }
+
+    /// Get the name of the class this class is a member of.
+    ///
+    /// Local and anonymous classes are not members of any class.
+    pub fn declaring_class(&self) -> Option<&str> {
+        self.inner_class_info()
+            .and_then(|info| info.outer_class.as_deref())
+    }
+
+    /// Get the name of the class and method enclosing this local or anonymous
+    /// class, the method being None if the class is declared in an initializer.
+    pub fn enclosing_method(&self) -> Option<(&str, Option<&(String, String)>)> {
+        self.attributes.iter().find_map(|attr| match attr {
+            ClassAttribute::EnclosingMethod { class, method } => {
+                Some((class.as_str(), method.as_ref()))
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the name of the class immediately enclosing this class.
+    pub fn enclosing_class(&self) -> Option<&str> {
+        self.enclosing_method()
+            .map(|(class, _)| class)
+            .or_else(|| self.declaring_class())
+    }
+
+    /// Get the names of the classes declared as members of this class.
+    pub fn declared_inner_classes(&self) -> Vec<&str> {
+        self.inner_classes()
+            .iter()
+            .filter(|info| info.outer_class.as_deref() == Some(self.name.as_str()))
+            .map(|info| info.inner_class.as_str())
+            .collect()
+    }
+
+    /// Get the simple name of this class, as written in the source code.
+    ///
+    /// The simple name of an anonymous class is empty.
+    pub fn simple_name(&self) -> &str {
+        match self.inner_class_info() {
+            Some(info) => info.inner_name.as_deref().unwrap_or(""),
+            None => match self.name.rsplit_once('/') {
+                Some((_, name)) => name,
+                None => &self.name,
+            },
+        }
+    }
+
+    /// Get the access flags of this class, as declared in the source code.
+    ///
+    /// The flags of a nested class come from its InnerClasses entry, the
+    /// class file flags lacking `private`, `protected` and `static`.
+    pub fn modifiers(&self) -> u16 {
+        match self.inner_class_info() {
+            Some(info) => info.flags.bits(),
+            None => self.flags.bits(),
+        }
+    }
+
+    /// Estimate the size (in bytes) of an instance of this class.
+    ///
+    /// An instance holds a slot for each field of the class and of its
+    /// superclasses, see [Object::new_with_classmanager].
+    pub fn instance_size_estimate(&self) -> usize {
+        Object::size_estimate(self.field_offset + self.fields.len())
+    }
+
+    /// Estimate the size (in bytes) of the static fields of this class.
+    pub fn static_size_estimate(&self) -> usize {
+        self.fields.iter().filter(|field| field.is_static()).count() * size_of::<Slot>()
+    }
+}
+
+/// The static fields of a class, its java/lang/Class object and the objects
+/// resolved by its constant pool.
+impl RootProvider for Class {
+    fn add_roots(&self, roots: &mut RootSet) {
+        for field in self.fields.iter().filter(|field| field.is_static()) {
+            roots.add_slot(&field.value);
+        }
+        if let Some(class_object) = self.class_object.get() {
+            roots.add_object(class_object);
+        }
+        for (_, entry) in self.constant_pool.iter() {
+            match entry {
+                ConstantPoolEntry::StringReference(string) => {
+                    if let Some(obj) = string.object.get() {
+                        roots.add_object(obj);
+                    }
+                }
+                ConstantPoolEntry::DynamicConstant(constant) => {
+                    if let Some(value) = constant.value.get() {
+                        roots.add_slot(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -124,9 +336,11 @@ impl Field {
                 index: fi.descriptor_index as usize,
             })?;
 
-        let descriptor = descriptor::parse_field_descriptor(&descriptor.to_string())?;
+        let descriptor = descriptor::parse_field_descriptor(&descriptor)?;
 
-        let attributes: Vec<FieldAttribute> = fi
+        let flags = fi.access_flags;
+
+        let mut attributes: Vec<FieldAttribute> = fi
             .attributes
             .iter()
             .map(|attr| parse_field_attribute(cm, cp, attr))
@@ -134,21 +348,37 @@ impl Field {
             .into_iter()
             .flatten()
             .collect();
+        if flags.contains(FieldAccessFlags::Static) {
+            for attr in attributes.iter_mut() {
+                if let FieldAttribute::ConstantValue { value } = attr {
+                    let Some(converted) = value.clone().for_field_type(descriptor.field_type())
+                    else {
+                        return Err(ClassLoadingError::InvalidConstantValue {
+                            field_name: name.to_string(),
+                            field_type: type_name(descriptor.field_type()),
+                        });
+                    };
+                    *value = converted;
+                }
+            }
+        }
 
+        // Static fields hold their value, starting with the default value of
+        // their type unless a ConstantValue attribute is given.
+        // Instance fields values are stored in the objects.
         let mut value = Slot::Tombstone;
-        if let Some(cv) = attributes
-            .iter()
-            .find(|x| matches!(x, &&FieldAttribute::ConstantValue { .. }))
-        {
-            if let FieldAttribute::ConstantValue { value: cv } = cv {
+        if flags.contains(FieldAccessFlags::Static) {
+            value = Slot::default_for(descriptor.field_type());
+            if let Some(FieldAttribute::ConstantValue { value: cv }) = attributes
+                .iter()
+                .find(|x| matches!(x, FieldAttribute::ConstantValue { .. }))
+            {
                 value = cv.clone().into();
             }
         }
 
-        let flags = fi.access_flags.clone();
-
         Ok(Self {
-            name: name.to_string(),
+            name: name.into_owned(),
             value,
             descriptor: descriptor,
             attributes,
@@ -156,9 +386,14 @@ impl Field {
         })
     }
 
-    /// Get the value of the field.
+    /// Get the value of a static field.
+    ///
+    /// Instance fields do not have any value here, their values are stored in
+    /// the objects.
     pub fn get_value(&self) -> Option<&Slot> {
-        // TODO: Check if the field is static
+        if !self.is_static() {
+            return None;
+        }
         Some(&self.value)
     }
 
@@ -172,18 +407,42 @@ impl Field {
         self.flags.contains(FieldAccessFlags::Static)
     }
 
+    /// Narrow an int value stored in a boolean, byte, char or short field, as
+    /// `putfield` and `putstatic` do. The other values are stored as is.
+    pub fn narrow(&self, value: Slot) -> Slot {
+        match (value, self.descriptor.field_type()) {
+            (Slot::Int(value), FieldType::BaseType(base)) => Slot::Int(narrow_int(value, base)),
+            (value, _) => value,
+        }
+    }
+
     /// Check if the field is final.
     pub fn is_final(&self) -> bool {
         self.flags.contains(FieldAccessFlags::Final)
     }
+
+    /// Get the access level of the field.
+    pub fn access(&self) -> MemberAccess {
+        if self.flags.contains(FieldAccessFlags::Public) {
+            MemberAccess::Public
+        } else if self.flags.contains(FieldAccessFlags::Protected) {
+            MemberAccess::Protected
+        } else if self.flags.contains(FieldAccessFlags::Private) {
+            MemberAccess::Private
+        } else {
+            MemberAccess::Package
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Method {
     pub name: String,
-    pub descriptor: MethodDescriptor,
+    pub descriptor: InternedMethodDescriptor,
     pub flags: FlagSet<MethodAccessFlags>,
     pub attributes: Vec<MethodAttribute>,
+    /// The intrinsic replacing the method, looked up on its first invocation.
+    pub(crate) intrinsic: OnceLock<Option<Intrinsic>>,
 }
 
 impl Method {
@@ -203,7 +462,7 @@ impl Method {
                 index: mi.descriptor_index as usize,
             })?;
 
-        let descriptor = descriptor::parse_method_descriptor(&descriptor.to_string())?;
+        let descriptor = cm.intern_method_descriptor(&descriptor)?;
 
         let attributes: Vec<MethodAttribute> = mi
             .attributes
@@ -214,16 +473,27 @@ impl Method {
             .flatten()
             .collect();
 
-        let flags = mi.access_flags.clone();
+        let flags = mi.access_flags;
 
         Ok(Self {
-            name: name.to_string(),
+            name: name.into_owned(),
             descriptor: descriptor,
             attributes,
             flags,
+            intrinsic: OnceLock::new(),
         })
     }
 
+    /// Get the intrinsic replacing this method of the given class, if any.
+    ///
+    /// The registry is only looked up once, the intrinsics must then be
+    /// registered before the method is invoked.
+    pub fn intrinsic(&self, registry: &IntrinsicRegistry, class_name: &str) -> Option<Intrinsic> {
+        *self
+            .intrinsic
+            .get_or_init(|| registry.get(class_name, &self.name, self.descriptor.as_str()))
+    }
+
     pub fn get_code(&self) -> Option<&MethodCode> {
         self.attributes.iter().find_map(|attr| match attr {
             MethodAttribute::Code(code) => Some(code),
@@ -235,6 +505,34 @@ impl Method {
         &self.flags
     }
 
+    /// Get the formal parameters of the method, if the class has been compiled
+    /// with their names (`javac -parameters`).
+    pub fn parameters(&self) -> &[MethodParameter] {
+        self.attributes
+            .iter()
+            .find_map(|attr| match attr {
+                MethodAttribute::MethodParameters(parameters) => Some(parameters.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the checked exceptions the method is declared to throw (its throws clause).
+    pub fn declared_exceptions(&self) -> &[ClassName] {
+        self.attributes
+            .iter()
+            .find_map(|attr| match attr {
+                MethodAttribute::Exceptions(exceptions) => Some(exceptions.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the name of the parameter at the given position, if known.
+    pub fn parameter_name(&self, index: usize) -> Option<&str> {
+        self.parameters().get(index)?.name.as_deref()
+    }
+
     pub fn is_static(&self) -> bool {
         self.flags.contains(MethodAccessFlags::Static)
     }
@@ -266,6 +564,47 @@ impl Method {
     pub fn is_protected(&self) -> bool {
         self.flags.contains(MethodAccessFlags::Protected)
     }
+
+    /// Check if the ACC_STRICT flag is set.
+    ///
+    /// See [crate::fp::FpStrictness] for the actual floating-point semantics.
+    pub fn is_strict(&self) -> bool {
+        self.flags.contains(MethodAccessFlags::Strict)
+    }
+
+    /// Get the access level of the method.
+    pub fn access(&self) -> MemberAccess {
+        if self.is_public() {
+            MemberAccess::Public
+        } else if self.is_protected() {
+            MemberAccess::Protected
+        } else if self.is_private() {
+            MemberAccess::Private
+        } else {
+            MemberAccess::Package
+        }
+    }
+}
+
+/// Access level of a field or a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberAccess {
+    Public,
+    Protected,
+    /// Default access, only from the same runtime package.
+    Package,
+    Private,
+}
+
+impl std::fmt::Display for MemberAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemberAccess::Public => write!(f, "public"),
+            MemberAccess::Protected => write!(f, "protected"),
+            MemberAccess::Package => write!(f, "package-private"),
+            MemberAccess::Private => write!(f, "private"),
+        }
+    }
 }
 
 #[derive(Debug, Collectable, Clone)]
@@ -275,20 +614,318 @@ pub enum FieldAttribute {
     Deprecated,
 }
 
-#[derive(Debug, Collectable, Clone)]
+#[derive(Debug, Clone)]
+pub enum ClassAttribute {
+    /// Name of the host of the nest the class belongs to.
+    NestHost(String),
+    /// Names of the members of the nest hosted by the class.
+    NestMembers(Vec<String>),
+    /// The class is a hidden class defined by the VM (e.g. the class of a
+    /// lambda), member of the nest of its NestHost without being listed in
+    /// its NestMembers.
+    Hidden,
+    /// The nested classes referenced by the class.
+    InnerClasses(Vec<InnerClassInfo>),
+    /// The class and method enclosing a local or anonymous class.
+    EnclosingMethod {
+        class: String,
+        /// Name and descriptor of the method, None for a class declared in
+        /// an initializer.
+        method: Option<(String, String)>,
+    },
+}
+
+/// An entry of the InnerClasses attribute of a class.
+#[derive(Debug, Clone)]
+pub struct InnerClassInfo {
+    pub inner_class: String,
+    /// The class this class is a member of, None for local and anonymous classes.
+    pub outer_class: Option<String>,
+    /// The simple name of the class, None for anonymous classes.
+    pub inner_name: Option<String>,
+    pub flags: FlagSet<InnerClassAccessFlags>,
+}
+
+#[derive(Debug, Clone)]
 pub enum MethodAttribute {
     Code(MethodCode),
+    /// The formal parameters, in the order of the method descriptor.
+    MethodParameters(Vec<MethodParameter>),
+    /// The exceptions the method is declared to throw.
+    Exceptions(Vec<ClassName>),
     Synthetic,
     Deprecated,
 }
 
-#[derive(Debug, Collectable, Clone)]
+/// A formal parameter of a method.
+#[derive(Debug, Clone)]
+pub struct MethodParameter {
+    /// The name of the parameter, none if it is unnamed (e.g. synthetic).
+    pub name: Option<String>,
+    pub flags: FlagSet<MethodParameterAccessFlags>,
+}
+
+impl MethodParameter {
+    pub fn is_final(&self) -> bool {
+        self.flags.contains(MethodParameterAccessFlags::Final)
+    }
+
+    pub fn is_synthetic(&self) -> bool {
+        self.flags.contains(MethodParameterAccessFlags::Synthetic)
+    }
+
+    pub fn is_mandated(&self) -> bool {
+        self.flags.contains(MethodParameterAccessFlags::Mandated)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MethodCode {
     pub max_stack: u16,
     pub max_locals: u16,
     pub instructions: Vec<u8>,
-    // TODO: exception_table: Vec<ExceptionTableEntry>,
-    // TODO: attributes: Vec<CodeAttribute>,
+    /// Decoded (and quickened) instructions, shared between the clones of the class
+    pub cache: Arc<InstructionCache>,
+    /// Start pc and source line of each line of the method, sorted by pc
+    pub line_numbers: Vec<(u16, u16)>,
+    /// The local variables described by the LocalVariableTable
+    pub local_variables: Vec<LocalVariable>,
+    /// The frames of the StackMapTable, sorted by pc
+    pub stack_map: Vec<StackMapFrame>,
+    pub exception_table: ExceptionTable,
+}
+
+impl MethodCode {
+    /// Get the source line of the instruction at the given pc, if known.
+    pub fn line_number(&self, pc: usize) -> Option<u16> {
+        self.line_numbers
+            .iter()
+            .take_while(|(start_pc, _)| *start_pc as usize <= pc)
+            .last()
+            .map(|(_, line)| *line)
+    }
+
+    /// Get the local variable stored at the given index at the given pc, if known.
+    pub fn local_variable(&self, index: usize, pc: usize) -> Option<&LocalVariable> {
+        self.local_variables
+            .iter()
+            .find(|variable| variable.index as usize == index && variable.is_live_at(pc))
+    }
+
+    /// Get the stack map frame at the given pc, if any.
+    pub fn stack_map_frame(&self, pc: usize) -> Option<&StackMapFrame> {
+        self.stack_map
+            .binary_search_by_key(&pc, |frame| frame.pc as usize)
+            .ok()
+            .map(|index| &self.stack_map[index])
+    }
+}
+
+/// An exception handler of a method, from the exception table of its Code attribute.
+#[derive(Debug, Clone)]
+pub struct ExceptionHandler {
+    /// The pc from which the handler is active.
+    pub start_pc: u16,
+    /// The pc from which the handler is no longer active, exclusive.
+    pub end_pc: u16,
+    /// The pc of the first instruction of the handler.
+    pub handler_pc: u16,
+    /// Binary name of the class of the exceptions handled, None if the handler
+    /// handles all of them (e.g. a `finally` block).
+    pub catch_type: Option<String>,
+    /// The class of the exceptions handled, once resolved.
+    catch_class: OnceLock<ClassId>,
+}
+
+impl ExceptionHandler {
+    pub fn new(start_pc: u16, end_pc: u16, handler_pc: u16, catch_type: Option<String>) -> Self {
+        Self {
+            start_pc,
+            end_pc,
+            handler_pc,
+            catch_type,
+            catch_class: OnceLock::new(),
+        }
+    }
+
+    /// Check if the handler is active at the given pc.
+    pub fn is_active_at(&self, pc: usize) -> bool {
+        self.start_pc as usize <= pc && pc < self.end_pc as usize
+    }
+
+    /// Check if the handler handles the exceptions of the given class.
+    ///
+    /// The catch type is resolved on the first call, and cached for the next ones.
+    pub fn handles(
+        &self,
+        cm: &mut ClassManager,
+        exception: ClassId,
+    ) -> Result<bool, ClassLoadingError> {
+        let Some(catch_type) = &self.catch_type else {
+            return Ok(true);
+        };
+        let catch_class = match self.catch_class.get() {
+            Some(class_id) => *class_id,
+            None => {
+                let class_id = cm.get_or_resolve_class(catch_type)?.id();
+                *self.catch_class.get_or_init(|| class_id)
+            }
+        };
+        Ok(types::is_subtype_of(cm, exception, catch_class))
+    }
+}
+
+/// The exception table of a method, indexed by the code ranges of the handlers.
+///
+/// The bounds of the handlers split the code into intervals, sorted by pc,
+/// where the same handlers are active: looking up the handlers active at a pc
+/// is a binary search of its interval. The table is shared between its clones,
+/// so a thread can search it while resolving the catch types.
+#[derive(Debug, Clone, Default)]
+pub struct ExceptionTable(Arc<HandlerIntervals>);
+
+#[derive(Debug, Default)]
+struct HandlerIntervals {
+    /// The handlers, in the order of the table, which is the order in which
+    /// they are tried.
+    handlers: Vec<ExceptionHandler>,
+    /// The start pc of the intervals, sorted.
+    starts: Vec<u16>,
+    /// Indices of the handlers active in each interval, in the order of the table.
+    active: Vec<Vec<usize>>,
+}
+
+impl ExceptionTable {
+    pub fn new(handlers: Vec<ExceptionHandler>) -> Self {
+        let mut starts: Vec<u16> = handlers
+            .iter()
+            .flat_map(|handler| [handler.start_pc, handler.end_pc])
+            .collect();
+        starts.sort_unstable();
+        starts.dedup();
+        let active = starts
+            .iter()
+            .map(|start| {
+                (0..handlers.len())
+                    .filter(|index| handlers[*index].is_active_at(*start as usize))
+                    .collect()
+            })
+            .collect();
+        Self(Arc::new(HandlerIntervals {
+            handlers,
+            starts,
+            active,
+        }))
+    }
+
+    /// Get the handlers, in the order of the table.
+    pub fn handlers(&self) -> &[ExceptionHandler] {
+        &self.0.handlers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.handlers.is_empty()
+    }
+
+    /// Get the handlers active at the given pc, in the order of the table.
+    pub fn handlers_at(&self, pc: usize) -> impl Iterator<Item = &ExceptionHandler> {
+        let interval = self
+            .0
+            .starts
+            .partition_point(|start| *start as usize <= pc)
+            .checked_sub(1);
+        interval
+            .map_or(&[][..], |interval| &self.0.active[interval][..])
+            .iter()
+            .map(|index| &self.0.handlers[*index])
+    }
+
+    /// Find the handler of an exception thrown at the given pc: the first
+    /// handler of the table active at the pc and handling its class.
+    pub fn find_handler(
+        &self,
+        cm: &mut ClassManager,
+        pc: usize,
+        exception: ClassId,
+    ) -> Result<Option<&ExceptionHandler>, InstructionError> {
+        for handler in self.handlers_at(pc) {
+            let handles = handler.handles(cm, exception).map_err(|source| {
+                InstructionError::ClassLoadingError {
+                    class_name: handler.catch_type.clone().unwrap_or_default(),
+                    source: Box::new(source),
+                }
+            })?;
+            if handles {
+                return Ok(Some(handler));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A local variable of a method, from the LocalVariableTable attribute.
+#[derive(Debug, Clone)]
+pub struct LocalVariable {
+    /// The pc from which the variable has a value.
+    pub start_pc: u16,
+    /// The length of the code range where the variable has a value.
+    pub length: u16,
+    pub name: String,
+    pub descriptor: FieldDescriptor,
+    /// The generic signature of the variable, from the LocalVariableTypeTable attribute.
+    pub signature: Option<String>,
+    /// The index of the variable in the local variables of the frame.
+    pub index: u16,
+}
+
+impl LocalVariable {
+    /// Check if the variable has a value at the given pc.
+    pub fn is_live_at(&self, pc: usize) -> bool {
+        let start_pc = self.start_pc as usize;
+        start_pc <= pc && pc < start_pc + self.length as usize
+    }
+}
+
+/// A frame of the StackMapTable attribute.
+///
+/// The frames are kept as described by the attribute, relative to the
+/// previous frame, but located by their absolute pc.
+#[derive(Debug, Clone)]
+pub struct StackMapFrame {
+    pub pc: u16,
+    pub kind: StackMapFrameKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum StackMapFrameKind {
+    /// Same locals as the previous frame, and an empty stack.
+    Same,
+    /// Same locals as the previous frame, and a single stack item.
+    SameLocals1StackItem(VerificationType),
+    /// The last locals of the previous frame are absent, and the stack is empty.
+    Chop(u8),
+    /// The previous locals and additional ones, and an empty stack.
+    Append(Vec<VerificationType>),
+    Full {
+        locals: Vec<VerificationType>,
+        stack: Vec<VerificationType>,
+    },
+}
+
+/// Type of a local variable or a stack item in a stack map frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    /// An instance of the class, by binary name (or the descriptor of an array class)
+    Object(String),
+    /// An object created by the `new` instruction at the given pc, not yet initialized
+    Uninitialized(u16),
 }
 
 #[derive(Debug, Collectable, Clone)]
@@ -297,6 +934,49 @@ pub enum ConstantValue {
     Long(i64),
     Float(f32),
     Double(f64),
+    /// A String constant, interned when the class is initialized.
+    String(String),
+}
+
+/// Narrow an int to a boolean, byte, char or short, the other types keeping it.
+pub(crate) fn narrow_int(value: i32, base: &BaseType) -> i32 {
+    match base {
+        BaseType::Boolean => value & 1,
+        BaseType::Byte => value as i8 as i32,
+        BaseType::Char => value as u16 as i32,
+        BaseType::Short => value as i16 as i32,
+        _ => value,
+    }
+}
+
+impl ConstantValue {
+    /// Convert a constant to the type of the static field it initializes (JVMS 4.7.2),
+    /// None if the field cannot hold it.
+    ///
+    /// The boolean, byte, char and short constants are given as int constants,
+    /// they are narrowed as by `putstatic`.
+    pub fn for_field_type(self, field_type: &FieldType) -> Option<Self> {
+        match (self, field_type) {
+            (ConstantValue::Integer(value), FieldType::BaseType(base)) => match base {
+                BaseType::Boolean | BaseType::Byte | BaseType::Char | BaseType::Short => {
+                    Some(ConstantValue::Integer(narrow_int(value, base)))
+                }
+                BaseType::Int => Some(ConstantValue::Integer(value)),
+                _ => None,
+            },
+            (value @ ConstantValue::Long(_), FieldType::BaseType(BaseType::Long))
+            | (value @ ConstantValue::Float(_), FieldType::BaseType(BaseType::Float))
+            | (value @ ConstantValue::Double(_), FieldType::BaseType(BaseType::Double)) => {
+                Some(value)
+            }
+            (value @ ConstantValue::String(_), FieldType::ObjectType(object))
+                if object.class_name.as_binary_name() == "java/lang/String" =>
+            {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
 }
 
 pub fn parse_field_attribute(
@@ -339,7 +1019,20 @@ pub fn parse_field_attribute(
                         value: ConstantValue::Double(info.value()),
                     }))
                 }
-                _ => unimplemented!("ConstantValue attribute with type: {:?}", value),
+                ClassfileConstantPoolInfo::StringInfo(info) => {
+                    let value = cp.get_utf8_string(info.string_index as usize).ok_or(
+                        ConstantPoolError::InvalidUtf8StringReference {
+                            index: info.string_index as usize,
+                        },
+                    )?;
+                    Ok(Some(FieldAttribute::ConstantValue {
+                        value: ConstantValue::String(value.into_owned()),
+                    }))
+                }
+                _ => Err(ConstantPoolError::InvalidConstantReference {
+                    index: cvattr.constant_value_index as usize,
+                }
+                .into()),
             }
         }
         "Synthetic" => Ok(Some(FieldAttribute::Synthetic)),
@@ -368,12 +1061,87 @@ pub fn parse_method_attribute(
         "Code" => {
             let mut reader = Cursor::new(attribute.info.as_slice());
             let codeattr = CodeAttribute::read(&mut reader)?;
-            // TODO: let attributes = codeattr.attributes.iter().map(|attr| parse_code_attribute(cm, cp, attr)).collect::<Result<Vec<_>, _>>()?.into_iter().flatten().collect();
-            Ok(Some(MethodAttribute::Code(MethodCode {
+            let mut code = MethodCode {
                 max_stack: codeattr.max_stack,
                 max_locals: codeattr.max_locals,
+                cache: Arc::new(InstructionCache::new(codeattr.code.clone())),
                 instructions: codeattr.code,
-            })))
+                line_numbers: Vec::new(),
+                local_variables: Vec::new(),
+                stack_map: Vec::new(),
+                exception_table: ExceptionTable::default(),
+            };
+            if let Err((pc, target)) = code.cache.program().check_branches() {
+                return Err(ClassLoadingError::InvalidBranchTarget { pc, target });
+            }
+            for attr in codeattr.attributes.iter() {
+                parse_code_attribute(cp, attr, &mut code)?;
+            }
+            code.line_numbers.sort();
+            let handlers = codeattr
+                .exception_table
+                .iter()
+                .map(|entry| {
+                    let catch_type = match entry.catch_type() {
+                        None => None,
+                        Some(index) => Some(
+                            cp.get_class_name(index)
+                                .ok_or(ConstantPoolError::InvalidClassNameReference { index })?
+                                .into_owned(),
+                        ),
+                    };
+                    Ok(ExceptionHandler::new(
+                        entry.start_pc(),
+                        entry.end_pc(),
+                        entry.handler_pc(),
+                        catch_type,
+                    ))
+                })
+                .collect::<Result<Vec<_>, ConstantPoolError>>()?;
+            code.exception_table = ExceptionTable::new(handlers);
+            Ok(Some(MethodAttribute::Code(code)))
+        }
+        "MethodParameters" => {
+            let mut reader = Cursor::new(attribute.info.as_slice());
+            let attr = MethodParametersAttribute::read(&mut reader)?;
+            let parameters = attr
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    let name = match parameter.name_index {
+                        0 => None,
+                        index => Some(
+                            cp.get_utf8_string(index as usize)
+                                .ok_or(ConstantPoolError::InvalidUtf8StringReference {
+                                    index: index as usize,
+                                })?
+                                .to_string(),
+                        ),
+                    };
+                    Ok(MethodParameter {
+                        name,
+                        flags: parameter.access_flags,
+                    })
+                })
+                .collect::<Result<Vec<_>, ConstantPoolError>>()?;
+            Ok(Some(MethodAttribute::MethodParameters(parameters)))
+        }
+        "Exceptions" => {
+            let mut reader = Cursor::new(attribute.info.as_slice());
+            let attr = ExceptionsAttribute::read(&mut reader)?;
+            let exceptions = attr
+                .exception_index_table
+                .iter()
+                .map(|index| {
+                    let index = *index as usize;
+                    let name = cp
+                        .get_class_name(index)
+                        .ok_or(ConstantPoolError::InvalidClassNameReference { index })?;
+                    name.parse::<ClassName>()
+                        .map_err(|source| ConstantPoolError::InvalidDescriptor { index, source })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Some(MethodAttribute::Exceptions(exceptions)))
         }
         "Synthetic" => Ok(Some(MethodAttribute::Synthetic)),
         "Deprecated" => Ok(Some(MethodAttribute::Deprecated)),
@@ -386,3 +1154,245 @@ pub fn parse_method_attribute(
         }
     }
 }
+
+/// Parse an attribute of a Code attribute into the method code.
+///
+/// The LocalVariableTypeTable must follow the LocalVariableTable, as javac
+/// writes them, to complete its variables.
+fn parse_code_attribute(
+    cp: &ClassfileConstantPool,
+    attribute: &AttributeInfo,
+    code: &mut MethodCode,
+) -> Result<(), ClassLoadingError> {
+    let name = cp
+        .get_utf8_string(attribute.attribute_name_index as usize)
+        .ok_or(ConstantPoolError::InvalidUtf8StringReference {
+            index: attribute.attribute_name_index as usize,
+        })?;
+    let utf8 = |index: u16| {
+        cp.get_utf8_string(index as usize)
+            .map(Cow::into_owned)
+            .ok_or(ConstantPoolError::InvalidUtf8StringReference {
+                index: index as usize,
+            })
+    };
+    let mut reader = Cursor::new(attribute.info.as_slice());
+    match name.as_ref() {
+        "LineNumberTable" => {
+            let table = LineNumberTableAttribute::read(&mut reader)?;
+            code.line_numbers.extend(
+                table
+                    .line_number_table
+                    .iter()
+                    .map(|entry| (entry.start_pc, entry.line_number)),
+            );
+        }
+        "LocalVariableTable" => {
+            let table = LocalVariableTableAttribute::read(&mut reader)?;
+            for entry in table.local_variable_table.iter() {
+                let descriptor = descriptor::parse_field_descriptor(&utf8(entry.descriptor_index)?)
+                    .map_err(|source| ConstantPoolError::InvalidDescriptor {
+                        index: entry.descriptor_index as usize,
+                        source,
+                    })?;
+                code.local_variables.push(LocalVariable {
+                    start_pc: entry.start_pc,
+                    length: entry.length,
+                    name: utf8(entry.name_index)?,
+                    descriptor,
+                    signature: None,
+                    index: entry.index,
+                });
+            }
+        }
+        "LocalVariableTypeTable" => {
+            let table = LocalVariableTypeTableAttribute::read(&mut reader)?;
+            for entry in table.local_variable_type_table.iter() {
+                let variable = code.local_variables.iter_mut().find(|variable| {
+                    variable.start_pc == entry.start_pc
+                        && variable.length == entry.length
+                        && variable.index == entry.index
+                });
+                if let Some(variable) = variable {
+                    variable.signature = Some(utf8(entry.signature_index)?);
+                }
+            }
+        }
+        "StackMapTable" => {
+            let table = StackMapTableAttribute::read(&mut reader)?;
+            let verification_type = |info: &VerificationTypeInfo| {
+                Ok::<_, ConstantPoolError>(match info {
+                    VerificationTypeInfo::TopVariableInfo => VerificationType::Top,
+                    VerificationTypeInfo::IntegerVariableInfo => VerificationType::Integer,
+                    VerificationTypeInfo::FloatVariableInfo => VerificationType::Float,
+                    VerificationTypeInfo::DoubleVariableInfo => VerificationType::Double,
+                    VerificationTypeInfo::LongVariableInfo => VerificationType::Long,
+                    VerificationTypeInfo::NullVariableInfo => VerificationType::Null,
+                    VerificationTypeInfo::UninitializedThisVariableInfo => {
+                        VerificationType::UninitializedThis
+                    }
+                    VerificationTypeInfo::ObjectVariableInfo { cpool_index } => {
+                        let name = cp.get_class_name(*cpool_index as usize).ok_or(
+                            ConstantPoolError::InvalidClassNameReference {
+                                index: *cpool_index as usize,
+                            },
+                        )?;
+                        VerificationType::Object(name.into_owned())
+                    }
+                    VerificationTypeInfo::UninitializedVariableInfo { offset } => {
+                        VerificationType::Uninitialized(*offset)
+                    }
+                })
+            };
+            let verification_types = |infos: &[VerificationTypeInfo]| {
+                infos
+                    .iter()
+                    .map(verification_type)
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            // The first frame is at offset_delta, the next ones at offset_delta + 1
+            // from the previous frame.
+            let mut pc: Option<u16> = None;
+            for frame in table.entries.iter() {
+                let (offset_delta, kind) = match frame {
+                    ClassfileStackMapFrame::SameFrame(frame) => {
+                        (frame.offset_delta as u16, StackMapFrameKind::Same)
+                    }
+                    ClassfileStackMapFrame::SameLocals1StackItemFrame(frame) => (
+                        frame.offset_delta as u16,
+                        StackMapFrameKind::SameLocals1StackItem(verification_type(&frame.stack)?),
+                    ),
+                    ClassfileStackMapFrame::SameLocals1StackItemFrameExtended(frame) => (
+                        frame.offset_delta,
+                        StackMapFrameKind::SameLocals1StackItem(verification_type(&frame.stack)?),
+                    ),
+                    ClassfileStackMapFrame::ChopFrame(frame) => {
+                        (frame.offset_delta, StackMapFrameKind::Chop(frame.k))
+                    }
+                    ClassfileStackMapFrame::SameFrameExtended(frame) => {
+                        (frame.offset_delta, StackMapFrameKind::Same)
+                    }
+                    ClassfileStackMapFrame::AppendFrame(frame) => (
+                        frame.offset_delta,
+                        StackMapFrameKind::Append(verification_types(&frame.locals)?),
+                    ),
+                    ClassfileStackMapFrame::FullFrame(frame) => (
+                        frame.offset_delta,
+                        StackMapFrameKind::Full {
+                            locals: verification_types(&frame.locals)?,
+                            stack: verification_types(&frame.stack)?,
+                        },
+                    ),
+                };
+                let frame_pc = match pc {
+                    Some(pc) => pc.wrapping_add(offset_delta).wrapping_add(1),
+                    None => offset_delta,
+                };
+                pc = Some(frame_pc);
+                code.stack_map.push(StackMapFrame { pc: frame_pc, kind });
+            }
+        }
+        _ => {
+            log::debug!(
+                "Code attribute not implemented/unknown, ignored: {:?}",
+                &name
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_class_attribute(
+    _cm: &mut ClassManager,
+    cp: &ClassfileConstantPool,
+    attribute: &AttributeInfo,
+) -> Result<Option<ClassAttribute>, ClassLoadingError> {
+    let name = cp
+        .get_utf8_string(attribute.attribute_name_index as usize)
+        .ok_or(ConstantPoolError::InvalidUtf8StringReference {
+            index: attribute.attribute_name_index as usize,
+        })?;
+    let class_name = |index: u16| {
+        cp.get_class_name(index as usize)
+            .map(Cow::into_owned)
+            .ok_or(ConstantPoolError::InvalidClassNameReference {
+                index: index as usize,
+            })
+    };
+    match name.as_ref() {
+        "NestHost" => {
+            let mut reader = Cursor::new(attribute.info.as_slice());
+            let attr = NestHostAttribute::read(&mut reader)?;
+            Ok(Some(ClassAttribute::NestHost(class_name(
+                attr.host_class_index,
+            )?)))
+        }
+        "NestMembers" => {
+            let mut reader = Cursor::new(attribute.info.as_slice());
+            let attr = NestMembersAttribute::read(&mut reader)?;
+            let members = attr
+                .classes
+                .iter()
+                .map(|index| class_name(*index))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Some(ClassAttribute::NestMembers(members)))
+        }
+        "InnerClasses" => {
+            let mut reader = Cursor::new(attribute.info.as_slice());
+            let attr = InnerClassesAttribute::read(&mut reader)?;
+            let classes = attr
+                .classes
+                .iter()
+                .map(|inner| {
+                    let outer_class = match inner.outer_class_info_index {
+                        0 => None,
+                        index => Some(class_name(index)?),
+                    };
+                    let inner_name = match inner.inner_name_index {
+                        0 => None,
+                        index => Some(
+                            cp.get_utf8_string(index as usize)
+                                .ok_or(ConstantPoolError::InvalidUtf8StringReference {
+                                    index: index as usize,
+                                })?
+                                .to_string(),
+                        ),
+                    };
+                    Ok(InnerClassInfo {
+                        inner_class: class_name(inner.inner_class_info_index)?,
+                        outer_class,
+                        inner_name,
+                        flags: inner.inner_class_access_flags,
+                    })
+                })
+                .collect::<Result<Vec<_>, ConstantPoolError>>()?;
+            Ok(Some(ClassAttribute::InnerClasses(classes)))
+        }
+        "EnclosingMethod" => {
+            let mut reader = Cursor::new(attribute.info.as_slice());
+            let attr = EnclosingMethodAttribute::read(&mut reader)?;
+            let method = match attr.method_index {
+                0 => None,
+                index => {
+                    let (name, descriptor) = cp.get_name_and_type(index as usize).ok_or(
+                        ConstantPoolError::InvalidConstantReference {
+                            index: index as usize,
+                        },
+                    )?;
+                    Some((name.into_owned(), descriptor.into_owned()))
+                }
+            };
+            Ok(Some(ClassAttribute::EnclosingMethod {
+                class: class_name(attr.class_index)?,
+                method,
+            }))
+        }
+        _ => {
+            log::debug!(
+                "Class attribute not implemented/unknown, ignored: {:?}",
+                &name
+            );
+            Ok(None)
+        }
+    }
+}
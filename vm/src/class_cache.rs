@@ -0,0 +1,142 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// Bounded in-memory cache of classfile bytes, with a least-recently-used eviction.
+///
+/// The bound is the total size of the cached classfiles, in bytes. A classfile
+/// larger than the bound is never cached.
+#[derive(Debug)]
+pub struct ClassBytesCache {
+    max_bytes: usize,
+    size: usize,
+    /// Cached bytes and last access tick, by class name.
+    entries: HashMap<String, (Vec<u8>, u64)>,
+    /// Class names by last access tick, the oldest first.
+    lru: BTreeMap<u64, String>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+/// Statistics of a [ClassBytesCache].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Number of classfiles currently cached
+    pub entries: usize,
+    /// Total size of the classfiles currently cached
+    pub bytes: usize,
+}
+
+impl ClassBytesCache {
+    /// Create an empty cache holding at most `max_bytes` of classfiles.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            size: 0,
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+            tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Get the cached bytes of a class, marking them as recently used.
+    pub fn get(&mut self, class_name: &str) -> Option<Vec<u8>> {
+        self.tick += 1;
+        let Some((bytes, last)) = self.entries.get_mut(class_name) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.lru.remove(last);
+        *last = self.tick;
+        self.lru.insert(self.tick, class_name.to_string());
+        self.stats.hits += 1;
+        Some(bytes.clone())
+    }
+
+    /// Cache the bytes of a class, evicting the least recently used ones if needed.
+    pub fn insert(&mut self, class_name: &str, bytes: Vec<u8>) {
+        if bytes.len() > self.max_bytes {
+            return;
+        }
+        self.remove(class_name);
+        while self.size + bytes.len() > self.max_bytes {
+            let Some((_, oldest)) = self.lru.pop_first() else {
+                break;
+            };
+            if let Some((evicted, _)) = self.entries.remove(&oldest) {
+                self.size -= evicted.len();
+                self.stats.evictions += 1;
+            }
+        }
+        self.tick += 1;
+        self.size += bytes.len();
+        self.lru.insert(self.tick, class_name.to_string());
+        self.entries
+            .insert(class_name.to_string(), (bytes, self.tick));
+    }
+
+    /// Remove a class from the cache.
+    pub fn remove(&mut self, class_name: &str) {
+        if let Some((bytes, last)) = self.entries.remove(class_name) {
+            self.lru.remove(&last);
+            self.size -= bytes.len();
+        }
+    }
+
+    /// Get the size bound of the cache, in bytes.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Get the hit/miss statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            bytes: self.size,
+            ..self.stats
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hit(s), {} miss(es), {} eviction(s), {} classfile(s) cached ({} bytes)",
+            self.hits, self.misses, self.evictions, self.entries, self.bytes
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lru_eviction() {
+        let mut cache = ClassBytesCache::new(10);
+        cache.insert("A", vec![0; 4]);
+        cache.insert("B", vec![0; 4]);
+        assert!(cache.get("A").is_some());
+        // B is the least recently used
+        cache.insert("C", vec![0; 4]);
+        assert!(cache.get("B").is_none());
+        assert!(cache.get("A").is_some());
+        assert!(cache.get("C").is_some());
+        // Too large to be cached
+        cache.insert("D", vec![0; 11]);
+        assert!(cache.get("D").is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 2);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.bytes, 8);
+    }
+}
@@ -1,10 +1,23 @@
-use crate::{constant_pool::ConstantPoolError, thread::ExecutionError};
+use crate::{
+    class_cache::{CacheStats, ClassBytesCache},
+    class_version::ClassVersionPolicy,
+    constant_pool::ConstantPoolError,
+    thread::ExecutionError,
+};
 use reader::{
     base::{ClassFile, DecodingError, ParsingError},
     descriptor::{self, ClassName},
 };
 use snafu::Snafu;
-use std::fmt::Debug;
+use std::{
+    ffi::OsStr,
+    fmt::Debug,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use zip::{result::ZipError, ZipArchive};
 
 /// Runtime representation of a class loader.
 ///
@@ -13,6 +26,10 @@ use std::fmt::Debug;
 #[derive(Debug)]
 pub struct ClassLoader {
     pub class_path: ClassPath,
+    /// Optional cache of the classfile bytes read from the class path.
+    bytes_cache: Option<ClassBytesCache>,
+    /// The hooks rewriting the classfiles before they are parsed, in order.
+    transformers: Vec<Box<dyn ClassFileTransformer>>,
 }
 
 impl ClassLoader {
@@ -20,6 +37,46 @@ impl ClassLoader {
     pub fn new() -> Self {
         Self {
             class_path: ClassPath::new(),
+            bytes_cache: None,
+            transformers: Vec::new(),
+        }
+    }
+
+    /// Cache the classfiles read from the class path in memory, up to `max_bytes`.
+    pub fn enable_bytes_cache(&mut self, max_bytes: usize) {
+        self.bytes_cache = Some(ClassBytesCache::new(max_bytes));
+    }
+
+    /// List the binary names of all the classes available on the class path.
+    ///
+    /// See [ClassPath::list_classes].
+    pub fn list_classes(&self) -> Result<Vec<String>, ClassLoadingError> {
+        self.class_path.list_classes()
+    }
+
+    /// Get the statistics of the classfile bytes cache, if enabled.
+    pub fn bytes_cache_stats(&self) -> Option<CacheStats> {
+        self.bytes_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Select the versioned classes of the multi-release jars for the newest
+    /// release accepted by the policy.
+    pub fn set_version_policy(&mut self, policy: &ClassVersionPolicy) {
+        self.class_path.set_release(policy.release());
+    }
+
+    /// Get the content of a resource of the class path, given its path
+    /// relative to the class path entries, e.g. `com/example/app.properties`.
+    ///
+    /// Returns None if no entry has the resource or if it cannot be read.
+    pub fn get_resource(&self, name: &str) -> Option<Vec<u8>> {
+        match self.class_path.read_resource(name) {
+            Ok(bytes) => Some(bytes),
+            Err(ClassLoadingError::NotFound) => None,
+            Err(err) => {
+                log::warn!("Cannot read the resource {}: {}", name, err);
+                None
+            }
         }
     }
 
@@ -28,11 +85,42 @@ impl ClassLoader {
         self.class_path.add_entry(entry);
     }
 
+    /// Register a hook rewriting the classfiles read, after the ones already registered.
+    ///
+    /// The classes loaded before are not transformed.
+    pub fn add_transformer(&mut self, transformer: Box<dyn ClassFileTransformer>) {
+        self.transformers.push(transformer);
+    }
+
     /// Load a class from this class loader.
+    ///
+    /// The bytes read are given to the transformers before being parsed, the
+    /// cache keeping the bytes of the class path.
     pub fn load_classfile(&mut self, class_name: &str) -> Result<ClassFile, ClassLoadingError> {
         let parsed_name = descriptor::parse_class_name(class_name)?;
-        let bytes = self.class_path.read_class(&parsed_name)?;
-        match ClassFile::from_bytes(&bytes) {
+        let bytes = match self
+            .bytes_cache
+            .as_mut()
+            .and_then(|cache| cache.get(class_name))
+        {
+            Some(bytes) => bytes,
+            None => {
+                let bytes = self.class_path.read_class(&parsed_name)?;
+                if let Some(cache) = self.bytes_cache.as_mut() {
+                    cache.insert(class_name, bytes.clone());
+                }
+                bytes
+            }
+        };
+        let mut transformed: Option<Vec<u8>> = None;
+        for transformer in &self.transformers {
+            let current = transformed.as_deref().unwrap_or(&bytes);
+            if let Some(bytes) = transformer.transform(class_name, current) {
+                transformed = Some(bytes);
+            }
+        }
+        let bytes = transformed.as_deref().unwrap_or(&bytes);
+        match ClassFile::from_bytes(bytes) {
             Ok(classfile) => Ok(classfile),
             Err(e) => Err(e.into()),
         }
@@ -61,6 +149,13 @@ impl ClassPath {
         self.entries.push(entry);
     }
 
+    /// Select the Java release of the versioned classes of the entries.
+    pub fn set_release(&mut self, release: u16) {
+        for entry in self.entries.iter_mut() {
+            entry.set_release(release);
+        }
+    }
+
     /// Read a classfile from this class path.
     ///
     /// Returns the bytes of the classfile, or an error if the classfile could not be found or loaded.
@@ -74,17 +169,91 @@ impl ClassPath {
         }
         Err(ClassLoadingError::NotFound)
     }
+
+    /// Read a resource from this class path, the first entry having it winning.
+    pub fn read_resource(&self, name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        for entry in &self.entries {
+            match entry.read_resource(name) {
+                Ok(bytes) => return Ok(bytes),
+                Err(ClassLoadingError::NotFound) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(ClassLoadingError::NotFound)
+    }
+
+    /// List the binary names of all the classes available on this class path.
+    ///
+    /// The entries are scanned concurrently, the result is sorted and without
+    /// duplicates (a class shadowed by a previous entry is listed once).
+    pub fn list_classes(&self) -> Result<Vec<String>, ClassLoadingError> {
+        let lists = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .entries
+                .iter()
+                .map(|entry| scope.spawn(|| entry.list_classes()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("class path scanning thread panicked"))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        let mut classes: Vec<String> = lists.into_iter().flatten().collect();
+        classes.sort();
+        classes.dedup();
+        Ok(classes)
+    }
+}
+
+/// Hook rewriting the classfiles at load time, before they are parsed, e.g. to
+/// instrument the methods for coverage or profiling.
+///
+/// Any `Fn(&str, &[u8]) -> Option<Vec<u8>>` closure is a transformer.
+pub trait ClassFileTransformer: Send + Sync {
+    /// Get the new bytes of the classfile of a class, given its binary name
+    /// (e.g. `java/lang/Object`), or None to keep the bytes unchanged.
+    fn transform(&self, class_name: &str, bytes: &[u8]) -> Option<Vec<u8>>;
+}
+
+impl<F> ClassFileTransformer for F
+where
+    F: Fn(&str, &[u8]) -> Option<Vec<u8>> + Send + Sync,
+{
+    fn transform(&self, class_name: &str, bytes: &[u8]) -> Option<Vec<u8>> {
+        self(class_name, bytes)
+    }
+}
+
+impl Debug for dyn ClassFileTransformer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ClassFileTransformer")
+    }
 }
 
 /// Class path entry trait.
 ///
 /// This trait is used to represent a class path entry, which is a way to
 /// register a loader that can load classes from a specific location (from File, from Jar Archive, ...).
-pub trait ClassPathEntry: Debug {
+pub trait ClassPathEntry: Debug + Send + Sync {
     /// Read a classfile from this class path entry.
     ///
     /// Returns the bytes of the classfile, or an error if the classfile could not be found or loaded.
     fn read_class(&self, name: &ClassName) -> Result<Vec<u8>, ClassLoadingError>;
+
+    /// List the binary names (e.g. `java/lang/Object`) of the classes of this class path entry.
+    fn list_classes(&self) -> Result<Vec<String>, ClassLoadingError>;
+
+    /// Read a resource (any file, e.g. `com/example/app.properties`) from this
+    /// class path entry, given its `/` separated path relative to the entry.
+    ///
+    /// The entries without resources find none.
+    fn read_resource(&self, _name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        Err(ClassLoadingError::NotFound)
+    }
+
+    /// Select the Java release (e.g. 17) of the classes read, for the entries
+    /// with versioned classes (multi-release jars).
+    fn set_release(&mut self, _release: u16) {}
 }
 
 /// Class loading error.
@@ -100,6 +269,10 @@ pub enum ClassLoadingError {
     #[snafu(display("IO error: {}", source))]
     IOError { source: std::io::Error },
 
+    #[snafu(context(false))]
+    #[snafu(display("Archive error: {}", source))]
+    ArchiveError { source: ZipError },
+
     #[snafu(context(false))]
     #[snafu(display("Parsing error: {}", source))]
     ParsingError { source: ParsingError },
@@ -122,9 +295,47 @@ pub enum ClassLoadingError {
         source: reader::descriptor::DescriptorError,
     },
 
+    #[snafu(display(
+        "UnsupportedClassVersionError: {} has class file version {}.{}, this VM only supports versions up to {}.0",
+        class_name, major, minor, max_major
+    ))]
+    UnsupportedClassVersion {
+        class_name: String,
+        major: u16,
+        minor: u16,
+        max_major: u16,
+    },
+
+    #[snafu(display("IllegalAccessError: {}", context))]
+    IllegalAccess { context: String },
+
+    #[snafu(display(
+        "VerifyError: the branch at pc {} targets {}, which is not an instruction of the method",
+        pc,
+        target
+    ))]
+    InvalidBranchTarget { pc: usize, target: i64 },
+
+    #[snafu(display(
+        "ClassFormatError: the ConstantValue of the field {} does not match its type {}",
+        field_name,
+        field_type
+    ))]
+    InvalidConstantValue {
+        field_name: String,
+        field_type: String,
+    },
+
     #[snafu(display("The class initializer failed: {}", source))]
     InitializerError { source: ExecutionError },
 
+    #[snafu(display(
+        "Class {} is being initialized by thread #{}, which cannot complete while waiting for it",
+        class_name,
+        thread
+    ))]
+    InitializationInProgress { class_name: String, thread: usize },
+
     #[snafu(display("Unknown error"))]
     Unknown,
 }
@@ -139,6 +350,48 @@ pub enum DerivingError {
 
     #[snafu(display("Circular dependency (class {} is dependent of itself)", class_name))]
     CircularDependency { class_name: String },
+
+    #[snafu(display(
+        "VerifyError: class {} cannot inherit from final class {}",
+        class_name,
+        super_class
+    ))]
+    FinalSuperClass {
+        class_name: String,
+        super_class: String,
+    },
+
+    #[snafu(display(
+        "IncompatibleClassChangeError: class {} has interface {} as super class",
+        class_name,
+        super_class
+    ))]
+    InterfaceSuperClass {
+        class_name: String,
+        super_class: String,
+    },
+
+    #[snafu(display(
+        "IncompatibleClassChangeError: class {} cannot implement class {}, which is not an interface",
+        class_name,
+        interface_name
+    ))]
+    NotAnInterface {
+        class_name: String,
+        interface_name: String,
+    },
+
+    #[snafu(display(
+        "VerifyError: class {} overrides final method {}.{}",
+        class_name,
+        super_class,
+        method
+    ))]
+    FinalMethodOverride {
+        class_name: String,
+        super_class: String,
+        method: String,
+    },
 }
 
 /// Class path entry for a directory.
@@ -172,4 +425,507 @@ impl ClassPathEntry for ClassPathDirEntry {
             },
         }
     }
+
+    fn list_classes(&self) -> Result<Vec<String>, ClassLoadingError> {
+        let mut classes = Vec::new();
+        let mut stack = vec![self.path.clone()];
+        while let Some(dir) = stack.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    stack.push(path);
+                    continue;
+                }
+                if path.extension() != Some("class".as_ref()) {
+                    continue;
+                }
+                let Ok(relative) = path.with_extension("").strip_prefix(&self.path).map(|p| {
+                    p.components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                }) else {
+                    continue;
+                };
+                classes.push(relative);
+            }
+        }
+        Ok(classes)
+    }
+
+    fn read_resource(&self, name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        let mut path = self.path.clone();
+        for part in name.split('/') {
+            // The resource must not escape the directory.
+            if part.is_empty() || part == "." || part == ".." {
+                return Err(ClassLoadingError::NotFound);
+            }
+            path.push(part);
+        }
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(bytes),
+            Err(e) => match e.kind() {
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::IsADirectory => {
+                    Err(ClassLoadingError::NotFound)
+                }
+                _ => Err(e.into()),
+            },
+        }
+    }
+}
+
+/// Path of the manifest in a jar archive.
+const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+/// Directory of the versioned classes of a multi-release jar archive.
+const VERSIONS_DIR: &str = "META-INF/versions/";
+
+/// Class path entry for a jar archive.
+///
+/// This is a class path entry that will load classes from the .class files of a jar (zip) archive.
+///
+/// In a multi-release archive, a class or resource of `META-INF/versions/N/`
+/// replaces the base one for the release N and the newer ones, the newest
+/// release not above the one selected (see [ClassPathEntry::set_release]) being preferred.
+#[derive(Debug)]
+pub struct ClassPathJarEntry {
+    /// The path of the archive.
+    path: PathBuf,
+    /// The opened archive, its reader being shared by the loading threads.
+    archive: Mutex<ZipArchive<File>>,
+    /// The releases with versioned classes, the newest first, empty if the
+    /// archive is not multi-release.
+    versions: Vec<u16>,
+    /// The release of the classes read.
+    release: u16,
+}
+
+impl ClassPathJarEntry {
+    /// Open a jar archive as a class path entry.
+    ///
+    /// The versioned classes of a multi-release archive are selected for the
+    /// newest release supported by the VM, until [ClassPathEntry::set_release].
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, ClassLoadingError> {
+        let path = path.into();
+        let archive = ZipArchive::new(File::open(&path)?)?;
+        let mut entry = Self {
+            path,
+            archive: Mutex::new(archive),
+            versions: Vec::new(),
+            release: ClassVersionPolicy::default().release(),
+        };
+        if entry
+            .manifest()?
+            .is_some_and(|manifest| manifest.multi_release)
+        {
+            entry.versions = entry.versions();
+        }
+        Ok(entry)
+    }
+
+    /// Get the releases of the versioned directories of the archive, the newest first.
+    fn versions(&self) -> Vec<u16> {
+        let archive = self.archive.lock().expect("jar archive lock poisoned");
+        let mut versions: Vec<u16> = archive
+            .file_names()
+            .filter_map(|name| name.strip_prefix(VERSIONS_DIR))
+            .filter_map(|name| name.split_once('/'))
+            .filter_map(|(version, _)| version.parse().ok())
+            // Only the releases since Java 9 are versioned.
+            .filter(|version| *version >= 9)
+            .collect();
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+        versions.dedup();
+        versions
+    }
+
+    /// Get the path of the archive.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the manifest of the archive, if it has one.
+    pub fn manifest(&self) -> Result<Option<JarManifest>, ClassLoadingError> {
+        match self.read_file(MANIFEST_PATH) {
+            Ok(bytes) => Ok(Some(JarManifest::parse(&String::from_utf8_lossy(&bytes)))),
+            Err(ClassLoadingError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read a file of the archive, given its path in the archive.
+    fn read_file(&self, name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        let mut archive = self.archive.lock().expect("jar archive lock poisoned");
+        let mut file = match archive.by_name(name) {
+            Ok(file) => file,
+            Err(ZipError::FileNotFound) => return Err(ClassLoadingError::NotFound),
+            Err(e) => return Err(e.into()),
+        };
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Read a file of the archive, preferring its versioned copy for the selected release.
+    fn read_versioned_file(&self, name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        for version in self.versions.iter().filter(|v| **v <= self.release) {
+            match self.read_file(&format!("{}{}/{}", VERSIONS_DIR, version, name)) {
+                Err(ClassLoadingError::NotFound) => continue,
+                result => return result,
+            }
+        }
+        self.read_file(name)
+    }
+}
+
+impl ClassPathEntry for ClassPathJarEntry {
+    fn read_class(&self, name: &ClassName) -> Result<Vec<u8>, ClassLoadingError> {
+        self.read_versioned_file(&format!("{}.class", name.as_binary_name()))
+    }
+
+    fn list_classes(&self) -> Result<Vec<String>, ClassLoadingError> {
+        let archive = self.archive.lock().expect("jar archive lock poisoned");
+        Ok(archive
+            .file_names()
+            .filter(|name| !name.starts_with("META-INF/"))
+            .filter_map(|name| name.strip_suffix(".class"))
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn read_resource(&self, name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        // The directories are entries of the archive too, but not resources.
+        if name.is_empty() || name.ends_with('/') {
+            return Err(ClassLoadingError::NotFound);
+        }
+        self.read_versioned_file(name)
+    }
+
+    fn set_release(&mut self, release: u16) {
+        self.release = release;
+    }
+}
+
+/// Main attributes of the manifest of a jar archive.
+///
+/// See the [JAR File Specification](https://docs.oracle.com/en/java/javase/21/docs/specs/jar/jar.html#jar-manifest).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JarManifest {
+    /// The class to run, as given by the `Main-Class` attribute (e.g. `com.example.Main`).
+    pub main_class: Option<String>,
+    /// The relative URLs of the dependencies of the archive, given by the `Class-Path` attribute.
+    pub class_path: Vec<String>,
+    /// Whether the archive has versioned classes, given by the `Multi-Release` attribute.
+    pub multi_release: bool,
+}
+
+impl JarManifest {
+    /// Parse the main section of a manifest, the other attributes are ignored.
+    pub fn parse(input: &str) -> Self {
+        let mut lines: Vec<String> = Vec::new();
+        for line in input.lines() {
+            if line.is_empty() {
+                // The main section ends at the first empty line.
+                break;
+            }
+            match (line.strip_prefix(' '), lines.last_mut()) {
+                (Some(continuation), Some(last)) => last.push_str(continuation),
+                _ => lines.push(line.to_string()),
+            }
+        }
+        let mut manifest = Self::default();
+        for line in lines.iter() {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("Main-Class") {
+                manifest.main_class = Some(value.to_string()).filter(|value| !value.is_empty());
+            } else if name.eq_ignore_ascii_case("Class-Path") {
+                manifest.class_path = value.split_whitespace().map(str::to_string).collect();
+            } else if name.eq_ignore_ascii_case("Multi-Release") {
+                manifest.multi_release = value.eq_ignore_ascii_case("true");
+            }
+        }
+        manifest
+    }
+}
+
+/// Expand a class path as the `java` launcher does, into the paths of its entries.
+///
+/// The entries are separated by the platform separator (`:`, or `;` on
+/// Windows), and the entry `dir/*` stands for the jars of the directory `dir`
+/// (not its subdirectories), sorted by name. The empty entries, and the
+/// wildcards of missing directories, are skipped.
+pub fn expand_class_path(class_path: &str) -> Result<Vec<PathBuf>, ClassLoadingError> {
+    let mut paths = Vec::new();
+    for entry in std::env::split_paths(class_path) {
+        if entry.as_os_str().is_empty() {
+            continue;
+        }
+        if entry.file_name() != Some(OsStr::new("*")) {
+            paths.push(entry);
+            continue;
+        }
+        let dir = match entry.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let files = match std::fs::read_dir(dir) {
+            Ok(files) => files,
+            // A missing directory has no jar, as for the java launcher.
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                log::debug!("Skipping the wildcard of the missing directory {:?}", dir);
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let mut jars = Vec::new();
+        for file in files {
+            let path = file?.path();
+            let is_jar = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("jar"));
+            if is_jar && path.is_file() {
+                jars.push(path);
+            }
+        }
+        jars.sort();
+        paths.extend(jars);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    /// A directory of the temporary directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("blazevm-{}-test-{}", name, std::process::id()));
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn jar_manifest() {
+        let manifest = JarManifest::parse(
+            "Manifest-Version: 1.0\r\nMain-Class: com.example.Main\r\nClass-Path: lib/a.jar lib/b\r\n .jar classes/\r\n\r\nName: com/example/\r\nMain-Class: Ignored\r\n",
+        );
+        assert_eq!(manifest.main_class.as_deref(), Some("com.example.Main"));
+        assert_eq!(
+            manifest.class_path,
+            vec!["lib/a.jar", "lib/b.jar", "classes/"]
+        );
+        assert!(!manifest.multi_release);
+        assert!(JarManifest::parse("Multi-Release: TRUE\n").multi_release);
+        assert_eq!(JarManifest::parse(""), JarManifest::default());
+    }
+
+    #[test]
+    fn jar_entry() {
+        let dir = TempDir::new("jar");
+        let path = dir.path().join("test.jar");
+        let mut writer = ZipWriter::new(File::create(&path).unwrap());
+        writer
+            .start_file(MANIFEST_PATH, FileOptions::default())
+            .unwrap();
+        writer.write_all(b"Main-Class: pkg.Main\n").unwrap();
+        writer
+            .start_file("pkg/Main.class", FileOptions::default())
+            .unwrap();
+        writer.write_all(&[0xCA, 0xFE, 0xBA, 0xBE]).unwrap();
+        writer.finish().unwrap();
+
+        let entry = ClassPathJarEntry::open(&path).unwrap();
+        let manifest = entry.manifest().unwrap().unwrap();
+        assert_eq!(manifest.main_class.as_deref(), Some("pkg.Main"));
+        let name = descriptor::parse_class_name("pkg/Main").unwrap();
+        assert_eq!(
+            entry.read_class(&name).unwrap(),
+            vec![0xCA, 0xFE, 0xBA, 0xBE]
+        );
+        let missing = descriptor::parse_class_name("pkg/Missing").unwrap();
+        assert!(matches!(
+            entry.read_class(&missing),
+            Err(ClassLoadingError::NotFound)
+        ));
+        assert_eq!(entry.list_classes().unwrap(), vec!["pkg/Main"]);
+    }
+
+    #[test]
+    fn multi_release_jar_entry() {
+        let dir = TempDir::new("mr");
+        let path = dir.path().join("test.jar");
+        let mut writer = ZipWriter::new(File::create(&path).unwrap());
+        let files: &[(&str, &[u8])] = &[
+            (MANIFEST_PATH, b"Multi-Release: true\n"),
+            ("pkg/Main.class", &[8]),
+            ("pkg/Base.class", &[8]),
+            ("META-INF/versions/11/pkg/Main.class", &[11]),
+            ("META-INF/versions/17/pkg/Main.class", &[17]),
+            ("META-INF/versions/21/pkg/Main.class", &[21]),
+            ("pkg/app.properties", &[8]),
+            ("META-INF/versions/11/pkg/app.properties", &[11]),
+        ];
+        for (name, bytes) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut entry = ClassPathJarEntry::open(&path).unwrap();
+        let main = descriptor::parse_class_name("pkg/Main").unwrap();
+        let base = descriptor::parse_class_name("pkg/Base").unwrap();
+        assert_eq!(entry.read_class(&main).unwrap(), vec![21]);
+        for (release, expected) in [(8, 8), (11, 11), (16, 11), (17, 17), (21, 21)] {
+            entry.set_release(release);
+            assert_eq!(entry.read_class(&main).unwrap(), vec![expected]);
+            assert_eq!(entry.read_class(&base).unwrap(), vec![8]);
+            let resource = entry.read_resource("pkg/app.properties").unwrap();
+            assert_eq!(resource, vec![expected.min(11)]);
+        }
+        assert!(matches!(
+            entry.read_resource("pkg/"),
+            Err(ClassLoadingError::NotFound)
+        ));
+        let mut classes = entry.list_classes().unwrap();
+        classes.sort();
+        assert_eq!(classes, vec!["pkg/Base", "pkg/Main"]);
+    }
+
+    #[test]
+    fn dir_entry() {
+        let dir = TempDir::new("dir");
+        let path = dir.path();
+        std::fs::create_dir_all(path.join("pkg")).unwrap();
+        std::fs::write(path.join("pkg/Main.class"), [0xCA, 0xFE, 0xBA, 0xBE]).unwrap();
+
+        let entry = ClassPathDirEntry::new(path);
+        let name = descriptor::parse_class_name("pkg/Main").unwrap();
+        assert_eq!(
+            entry.read_class(&name).unwrap(),
+            vec![0xCA, 0xFE, 0xBA, 0xBE]
+        );
+        let missing = descriptor::parse_class_name("pkg/Missing").unwrap();
+        assert!(matches!(
+            entry.read_class(&missing),
+            Err(ClassLoadingError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn default_resources() {
+        /// An entry of generated classes, without resources.
+        #[derive(Debug)]
+        struct Generated;
+
+        impl ClassPathEntry for Generated {
+            fn read_class(&self, _name: &ClassName) -> Result<Vec<u8>, ClassLoadingError> {
+                Ok(vec![0xCA, 0xFE, 0xBA, 0xBE])
+            }
+
+            fn list_classes(&self) -> Result<Vec<String>, ClassLoadingError> {
+                Ok(vec![])
+            }
+        }
+
+        let dir = TempDir::new("res");
+        let path = dir.path();
+        std::fs::create_dir_all(path.join("pkg")).unwrap();
+        std::fs::write(path.join("pkg/app.properties"), b"a=1").unwrap();
+
+        let mut class_loader = ClassLoader::new();
+        class_loader.add_class_path_entry(Box::new(Generated));
+        assert_eq!(class_loader.get_resource("pkg/app.properties"), None);
+        // The next entries are searched for the resource.
+        class_loader.add_class_path_entry(Box::new(ClassPathDirEntry::new(path)));
+        assert_eq!(
+            class_loader.get_resource("pkg/app.properties"),
+            Some(b"a=1".to_vec())
+        );
+    }
+
+    #[test]
+    fn class_path_listing() {
+        let dir = TempDir::new("list");
+        let path = dir.path();
+        for (dir, files) in [
+            (
+                "first",
+                &["pkg/Main.class", "pkg/Util.class", "pkg/notes.txt"][..],
+            ),
+            ("second", &["pkg/Main.class", "Other.class"][..]),
+        ] {
+            for file in files {
+                let file = path.join(dir).join(file);
+                std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+                std::fs::write(file, []).unwrap();
+            }
+        }
+
+        let mut class_path = ClassPath::new();
+        class_path.add_entry(Box::new(ClassPathDirEntry::new(path.join("first"))));
+        class_path.add_entry(Box::new(ClassPathDirEntry::new(path.join("second"))));
+        // Sorted, the shadowed pkg/Main being listed once.
+        assert_eq!(
+            class_path.list_classes().unwrap(),
+            vec!["Other", "pkg/Main", "pkg/Util"]
+        );
+        class_path.add_entry(Box::new(ClassPathDirEntry::new(path.join("missing"))));
+        assert!(class_path.list_classes().is_err());
+    }
+
+    #[test]
+    fn class_path_wildcards() {
+        let dir = TempDir::new("cp");
+        let path = dir.path();
+        std::fs::create_dir_all(path.join("lib/nested")).unwrap();
+        for file in [
+            "lib/b.jar",
+            "lib/a.JAR",
+            "lib/notes.txt",
+            "lib/nested/c.jar",
+        ] {
+            std::fs::write(path.join(file), []).unwrap();
+        }
+
+        let class_path =
+            std::env::join_paths([path.join("classes"), path.join("lib/*"), PathBuf::new()])
+                .unwrap();
+        let expanded = expand_class_path(class_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            expanded,
+            vec![
+                path.join("classes"),
+                path.join("lib/a.JAR"),
+                path.join("lib/b.jar")
+            ]
+        );
+        // The wildcard of a missing directory expands to no jar.
+        let missing = std::env::join_paths([path.join("missing/*"), path.join("missing")]).unwrap();
+        assert_eq!(
+            expand_class_path(missing.to_str().unwrap()).unwrap(),
+            vec![path.join("missing")]
+        );
+    }
 }
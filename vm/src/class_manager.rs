@@ -1,4 +1,7 @@
-use std::{cell::OnceCell, collections::HashMap};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+};
 
 use dumpster::sync::Gc;
 use flagset::FlagSet;
@@ -8,21 +11,43 @@ use reader::{
         constant_pool::{ConstantPoolEntry, ConstantPoolInfo},
         ClassFile,
     },
-    descriptor::{self, MethodDescriptor},
+    descriptor::{DescriptorError, FieldDescriptor, FieldType},
 };
 
 use crate::{
-    alloc::{Object, ObjectRef},
-    class::{self, Class, ClassId, Method},
-    class_loader::{ClassLoader, ClassLoadingError, DerivingError},
+    alloc::{
+        new_string, AllocError, AllocationStats, Array, ArrayRef, Collector, HeapBudget,
+        IdentityHashGenerator, Object, ObjectRef, RootProvider, RootSet, WeakRegistry,
+    },
+    assertions::AssertionStatus,
+    breakpoint::BreakpointManager,
+    call_log::CallFilter,
+    class::{self, Class, ClassId, ConstantValue, FieldAttribute, MemberAccess},
+    class_loader::{ClassFileTransformer, ClassLoader, ClassLoadingError, DerivingError},
+    class_stats::{ClassStat, ClassStats},
+    class_version::{self, ClassVersionPolicy},
+    clock::Clock,
     constant_pool::{ConstantPool, ConstantPoolError},
-    thread::{ExecutionError, Frame, Thread},
+    descriptor_table::{InternedMethodDescriptor, MethodDescriptorTable},
+    event::{EventSink, LogSink, VmEvent},
+    fp::FpStrictness,
+    intrinsic::IntrinsicRegistry,
+    native::{FileTable, NativeRegistry},
+    opcode::InstructionError,
+    opcode_stats::OpcodeHistogram,
+    thread::{ExecutionError, Frame, Slot, Thread},
+    thread_manager::ThreadId,
+    timing::{Phase, Timings},
+    types::{self, SubtypeCache},
+    value::type_descriptor,
 };
 
-const CLINIT_DESCRIPTOR: MethodDescriptor = MethodDescriptor {
-    return_type: None,
-    parameters: vec![],
-};
+const CLINIT_DESCRIPTOR: &str = "()V";
+
+/// The primitive types having a java/lang/Class mirror, e.g. `int.class`, `void` included.
+pub const PRIMITIVE_TYPE_NAMES: [&str; 9] = [
+    "boolean", "byte", "char", "short", "int", "long", "float", "double", "void",
+];
 
 /// Representation of the class manager.
 ///
@@ -38,46 +63,280 @@ pub struct ClassManager {
     /// The mapping between class names and their ID.
     pub name_map: HashMap<String, ClassId>,
 
+    /// The interned method descriptors.
+    pub method_descriptors: MethodDescriptorTable,
+
+    /// The string constants of the loaded classes, by value.
+    pub string_constants: HashMap<String, ObjectRef>,
+
+    /// The native methods implemented by the VM.
+    pub natives: NativeRegistry,
+
+    /// The methods of the class library implemented by the VM.
+    ///
+    /// The intrinsic of a method is looked up on its first invocation, so the
+    /// registry must be set up before the classes are loaded.
+    pub intrinsics: IntrinsicRegistry,
+
+    /// The files opened by the java.io natives.
+    pub files: FileTable,
+
+    /// Time spent loading, linking and initializing classes.
+    pub timings: Timings,
+
+    /// Executions of each instruction, only counted when enabled.
+    pub opcode_histogram: Option<OpcodeHistogram>,
+
+    /// The classes whose method calls are logged, if the call logging is enabled.
+    pub call_log: Option<CallFilter>,
+
+    /// The class file versions accepted when resolving classes.
+    pub version_policy: ClassVersionPolicy,
+
+    /// The desired assertion status of the classes, see `Class.desiredAssertionStatus`.
+    pub assertions: AssertionStatus,
+
+    /// The weak references and java/lang/ref/Reference objects of the heap.
+    pub weak_refs: WeakRegistry,
+
+    /// The memory budget of the array allocations.
+    pub heap_budget: HeapBudget,
+
+    /// The collector settings, see [Collector::stress].
+    pub collector: Collector,
+
+    /// The results of the subtype checks, see [types::is_subtype_of].
+    pub subtype_cache: SubtypeCache,
+
+    /// The counters of the allocations, by class.
+    pub allocations: AllocationStats,
+
+    /// The source of the identity hash codes of the objects and arrays.
+    pub identity_hashes: IdentityHashGenerator,
+
+    /// The time given to the programs.
+    pub clock: Clock,
+
+    /// The receiver of the events of the VM (class loading, failures...).
+    pub events: Box<dyn EventSink>,
+
+    /// The thread on behalf of which the classes are loaded and initialized.
+    pub current_thread: ThreadId,
+
+    /// The breakpoints set by the debuggers.
+    pub breakpoints: BreakpointManager,
+
+    /// The threads started by `Thread.start`, until the VM adds them to its threads.
+    pub started_threads: Vec<Thread>,
+
+    /// The java/lang/Thread objects registered by `Runtime.addShutdownHook`.
+    pub shutdown_hooks: Vec<ObjectRef>,
+
+    /// The classes whose initializer is running, with the thread requesting
+    /// their initialization.
+    initializing: HashMap<ClassId, ThreadId>,
+
+    /// The loaded interfaces not initialized yet, waiting for their first use.
+    pending_interfaces: HashSet<ClassId>,
+
+    /// Idle threads used to run the class initializers and the other methods
+    /// called by the VM itself (see [ClassManager::run_method]), reused between
+    /// the calls.
+    ///
+    /// A nested call (an initializer requiring another class) takes another
+    /// thread, so the calls never share their frames.
+    init_threads: Vec<Thread>,
+
     /// The next class ID to use.
     next_class_id: ClassId,
+
+    /// The number of classes unloaded, see [ClassManager::unload_class].
+    unloaded_classes: usize,
+
+    /// The java/lang/Class objects of the primitive types, all created on the first request.
+    primitive_mirrors: Vec<(&'static str, ObjectRef)>,
 }
 
 impl ClassManager {
     /// Create a new class manager.
     pub fn new(class_loader: ClassLoader) -> Self {
-        let mut s = Self {
+        Self::with_version_policy(class_loader, ClassVersionPolicy::default())
+    }
+
+    /// Create a new class manager accepting the class file versions of the given policy.
+    pub fn with_version_policy(
+        class_loader: ClassLoader,
+        version_policy: ClassVersionPolicy,
+    ) -> Self {
+        let mut s = Self::without_preloading(class_loader, version_policy);
+        s.preload();
+        s
+    }
+
+    /// Create a new class manager, without any class loaded.
+    ///
+    /// The class manager must be configured, then [ClassManager::preload] called
+    /// before using it.
+    pub(crate) fn without_preloading(
+        mut class_loader: ClassLoader,
+        version_policy: ClassVersionPolicy,
+    ) -> Self {
+        class_loader.set_version_policy(&version_policy);
+        Self {
             class_loader,
             classes_by_id: HashMap::new(),
             name_map: HashMap::new(),
+            method_descriptors: MethodDescriptorTable::new(),
+            string_constants: HashMap::new(),
+            natives: NativeRegistry::new(),
+            intrinsics: IntrinsicRegistry::new(),
+            files: FileTable::new(),
+            timings: Timings::new(),
+            opcode_histogram: None,
+            call_log: None,
+            version_policy,
+            assertions: AssertionStatus::new(),
+            weak_refs: WeakRegistry::new(),
+            heap_budget: HeapBudget::unlimited(),
+            collector: Collector::new(),
+            subtype_cache: SubtypeCache::new(),
+            allocations: AllocationStats::new(),
+            identity_hashes: IdentityHashGenerator::new(),
+            clock: Clock::system(),
+            events: Box::new(LogSink),
+            current_thread: 0,
+            breakpoints: BreakpointManager::new(),
+            started_threads: Vec::new(),
+            shutdown_hooks: Vec::new(),
+            initializing: HashMap::new(),
+            pending_interfaces: HashSet::new(),
+            init_threads: Vec::new(),
             next_class_id: ClassId(0),
-        };
-        // Preload java/lang/Object and java/lang/String.
-        s.get_or_resolve_class("java/lang/String")
+            unloaded_classes: 0,
+            primitive_mirrors: Vec::new(),
+        }
+    }
+
+    /// Preload java/lang/Object and java/lang/String.
+    pub(crate) fn preload(&mut self) {
+        self.get_or_resolve_class("java/lang/String")
             .expect("Failed to preload java/lang/String");
-        s.get_or_resolve_class("java/lang/Object")
+        self.get_or_resolve_class("java/lang/Object")
             .expect("Failed to preload java/lang/Object");
-        s
     }
 
-    /// Execute the class initializer
-    fn execute_class_init(
+    /// Get the java/lang/Class object of a primitive type by name, e.g. `int` or
+    /// `void`, or None if the name is not the one of a primitive type.
+    ///
+    /// The objects of all the primitive types are created together, the first time.
+    pub fn get_primitive_class_object(
         &mut self,
-        thread: &mut Thread,
-        class_id: &ClassId,
-    ) -> Result<(), ExecutionError> {
-        thread.reset();
-        let clid = {
-            let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(class_id) else {
+        name: &str,
+    ) -> Result<Option<ObjectRef>, ClassLoadingError> {
+        if self.primitive_mirrors.is_empty() {
+            self.create_primitive_mirrors()?;
+        }
+        Ok(self
+            .primitive_mirrors
+            .iter()
+            .find(|(primitive, _)| *primitive == name)
+            .map(|(_, obj)| obj.clone()))
+    }
+
+    /// Create the java/lang/Class objects of the primitive types.
+    fn create_primitive_mirrors(&mut self) -> Result<(), ClassLoadingError> {
+        let class_id = self.get_or_resolve_class("java/lang/Class")?.id();
+        for name in PRIMITIVE_TYPE_NAMES {
+            let obj = Object::new_with_classmanager(self, class_id)?;
+            let obj = self.alloc_object(obj);
+            self.primitive_mirrors.push((name, obj));
+        }
+        Ok(())
+    }
+
+    /// Get the name of the primitive type whose java/lang/Class object is the given mirror.
+    pub fn primitive_of_mirror(&self, mirror: &Object) -> Option<&'static str> {
+        self.primitive_mirrors
+            .iter()
+            .find(|(_, obj)| std::ptr::eq::<Object>(&**obj, mirror))
+            .map(|(name, _)| *name)
+    }
+
+    /// Register a hook rewriting the classfiles of the classes loaded from now
+    /// on, see [ClassLoader::add_transformer].
+    pub fn add_transformer(&mut self, transformer: Box<dyn ClassFileTransformer>) {
+        self.class_loader.add_transformer(transformer);
+    }
+
+    /// Get the interned java/lang/String object of a string constant, creating it if needed.
+    pub fn intern_string(&mut self, value: &str) -> Result<ObjectRef, ClassLoadingError> {
+        if let Some(obj) = self.string_constants.get(value) {
+            return Ok(obj.clone());
+        }
+        let obj = new_string(self, value)?;
+        self.string_constants.insert(value.to_string(), obj.clone());
+        Ok(obj)
+    }
+
+    /// Set the static fields of a class whose ConstantValue is a String, before
+    /// running its initializer (JVMS 5.5, step 6).
+    fn init_string_constants(&mut self, class_id: ClassId) -> Result<(), ClassLoadingError> {
+        let constants: Vec<(usize, String)> = {
+            let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&class_id) else {
+                return Err(ClassLoadingError::NotFound);
+            };
+            class
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| field.is_static())
+                .filter_map(|(index, field)| {
+                    field.attributes.iter().find_map(|attr| match attr {
+                        FieldAttribute::ConstantValue {
+                            value: ConstantValue::String(value),
+                        } => Some((index, value.clone())),
+                        _ => None,
+                    })
+                })
+                .collect()
+        };
+        for (index, value) in constants {
+            let obj = self.intern_string(&value)?;
+            if let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get_mut(&class_id) {
+                class.fields[index].value = Slot::ObjectReference(obj);
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute the class initializer of a class, in its own thread.
+    fn execute_class_init(&mut self, class_id: ClassId) -> Result<(), ExecutionError> {
+        let clinit_descriptor = self
+            .intern_method_descriptor(CLINIT_DESCRIPTOR)
+            .expect("<clinit> descriptor should be valid");
+        let clinit = {
+            let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&class_id) else {
                 return Err(ExecutionError::ClassNotLoaded);
             };
-            class.index_of_method("<clinit>", &CLINIT_DESCRIPTOR)
+            class
+                .get_method("<clinit>", &clinit_descriptor)
+                .map(|(index, _)| index)
         };
-        if let Some(clid) = clid {
-            let frame = Frame::new(*class_id, clid, 0);
-            thread.push_frame(frame);
-            thread.execute(self)?;
+        self.init_string_constants(class_id).map_err(|err| {
+            ExecutionError::InstructionExecutionError {
+                source: InstructionError::ClassLoadingError {
+                    class_name: "java/lang/String".into(),
+                    source: Box::new(err),
+                },
+            }
+        })?;
+        if let Some(clid) = clinit {
+            self.initializing.insert(class_id, self.current_thread);
+            let result = self.run_method(class_id, clid, vec![]);
+            self.initializing.remove(&class_id);
+            result?;
         }
-        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get_mut(class_id) else {
+        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get_mut(&class_id) else {
             return Err(ExecutionError::ClassNotLoaded);
         };
         class.initialized = OnceCell::new();
@@ -85,6 +344,121 @@ impl ClassManager {
         Ok(())
     }
 
+    /// Initialize an interface on its first use, if it is still waiting for its
+    /// initialization (JVMS 5.5).
+    ///
+    /// The classes are initialized as soon as they are loaded, so this does nothing
+    /// for them. The superinterfaces of an interface are not initialized with it.
+    pub fn initialize_class(&mut self, class_id: ClassId) -> Result<(), ClassLoadingError> {
+        // Removed first, so a recursive request completes immediately.
+        if !self.pending_interfaces.remove(&class_id) {
+            return Ok(());
+        }
+        log::debug!(
+            "Invoking interface initializer for {}",
+            self.get_class_by_id(class_id)
+                .map(|class| class.name())
+                .unwrap_or_default()
+        );
+        self.timings.enter(Phase::ClassInit);
+        let init = self.execute_class_init(class_id);
+        self.timings.exit(Phase::ClassInit);
+        init.map_err(|source| ClassLoadingError::InitializerError { source })
+    }
+
+    /// Initialize the superinterfaces of a class which declare a default method,
+    /// before the class itself (JVMS 5.5, step 7).
+    ///
+    /// They are enumerated recursively in the order of the interfaces of the class,
+    /// each interface after its own superinterfaces.
+    fn initialize_superinterfaces(&mut self, class_id: ClassId) -> Result<(), ClassLoadingError> {
+        fn collect(cm: &ClassManager, class_id: ClassId, found: &mut Vec<ClassId>) {
+            let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+                return;
+            };
+            for interface_id in class.interfaces.iter() {
+                collect(cm, *interface_id, found);
+                let Some(LoadedClass::Loaded(interface)) = cm.get_class_by_id(*interface_id) else {
+                    continue;
+                };
+                let has_default = interface
+                    .methods
+                    .iter()
+                    .any(|method| !method.is_abstract() && !method.is_static());
+                if has_default && !found.contains(interface_id) {
+                    found.push(*interface_id);
+                }
+            }
+        }
+
+        let mut interfaces = Vec::new();
+        collect(self, class_id, &mut interfaces);
+        for interface_id in interfaces {
+            self.initialize_class(interface_id)?;
+        }
+        Ok(())
+    }
+
+    /// Run a method to completion with the given arguments, and get its return value.
+    ///
+    /// The method runs on an idle thread, without touching the stack of the
+    /// current thread, e.g. for the class initializers and the bootstrap methods.
+    pub fn run_method(
+        &mut self,
+        class_id: ClassId,
+        method: usize,
+        args: Vec<Slot>,
+    ) -> Result<Option<Slot>, ExecutionError> {
+        let mut frame = {
+            let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&class_id) else {
+                return Err(ExecutionError::ClassNotLoaded);
+            };
+            let Some(code) = class.get_method_by_index(method).and_then(|m| m.get_code()) else {
+                return Err(ExecutionError::MethodNotLoaded);
+            };
+            let mut frame = Frame::new(
+                class_id,
+                method,
+                code.max_locals as usize,
+                code.max_stack as usize,
+            );
+            frame.fp_strictness = FpStrictness::of_method(class, &class.methods[method]);
+            frame
+        };
+        frame
+            .set_arguments(args)
+            .map_err(|source| ExecutionError::InstructionExecutionError { source })?;
+        let mut thread = self.init_threads.pop().unwrap_or_else(Thread::new);
+        thread.push_frame(frame);
+        let result = thread.execute(self);
+        let value = thread.result.take();
+        thread.reset();
+        self.init_threads.push(thread);
+        result.map(|_| value)
+    }
+
+    /// Check a loaded class can be used by the current thread.
+    ///
+    /// While its initializer runs, a class can only be used by the thread which
+    /// requested its initialization, recursive requests completing immediately
+    /// (JVMS 5.5, step 3). Another thread would have to wait for the end of the
+    /// initialization (step 2), which never comes as the threads do not run
+    /// concurrently: this is reported as an error instead.
+    fn check_initialization(&self, class_id: ClassId) -> Result<(), ClassLoadingError> {
+        match self.initializing.get(&class_id) {
+            Some(thread) if *thread != self.current_thread => {
+                Err(ClassLoadingError::InitializationInProgress {
+                    class_name: self
+                        .get_class_by_id(class_id)
+                        .map(|class| class.name().to_string())
+                        .unwrap_or_default(),
+                    thread: *thread,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Get a class by its ID.
     pub fn get_class_by_id(&self, id: ClassId) -> Option<&LoadedClass> {
         self.classes_by_id.get(&id)
@@ -107,6 +481,14 @@ impl ClassManager {
         self.name_map.get(name).cloned()
     }
 
+    /// Intern a method descriptor, so it is parsed only once.
+    pub fn intern_method_descriptor(
+        &mut self,
+        descriptor: &str,
+    ) -> Result<InternedMethodDescriptor, DescriptorError> {
+        self.method_descriptors.intern(descriptor)
+    }
+
     /// Acquire a new class ID.
     pub fn acquire_class_id(&mut self) -> ClassId {
         let id = self.next_class_id;
@@ -117,11 +499,14 @@ impl ClassManager {
     /// Request the loading of a class by its ID, meaning the class has already been resolved beforehand.
     pub fn request_class_load(&mut self, class_id: ClassId) -> Result<ClassId, ClassLoadingError> {
         match self.classes_by_id.get(&class_id) {
-            Some(LoadedClass::Loaded(class)) => Ok(class.id.clone()),
+            Some(LoadedClass::Loaded(class)) => {
+                self.check_initialization(class.id)?;
+                Ok(class.id)
+            }
             Some(x) => {
                 let name = x.name().to_string();
                 match self.get_or_resolve_class(&name) {
-                    Ok(LoadedClass::Loaded(class)) => Ok(class.id.clone()),
+                    Ok(LoadedClass::Loaded(class)) => Ok(class.id),
                     Ok(_) => Err(ClassLoadingError::Unknown),
                     Err(err) => Err(err),
                 }
@@ -135,14 +520,13 @@ impl ClassManager {
         &mut self,
         class_name: &str,
     ) -> Result<&LoadedClass, ClassLoadingError> {
-        let mut init_thread = Thread::new();
         let mut stack: Vec<String> = Vec::new();
         stack.push(class_name.to_string());
         while let Some(class_name) = stack.pop() {
             if let Some(class) = self.get_class_by_name(&class_name) {
                 let class = class.clone();
                 match class {
-                    LoadedClass::Loaded(_) => (),
+                    LoadedClass::Loaded(class) => self.check_initialization(class.id)?,
                     LoadedClass::Resolved(resolved) => {
                         log::debug!(
                             "Resolving/Loading class dependencies for {}...",
@@ -165,8 +549,7 @@ impl ClassManager {
                                     // This is an array class
                                     let _ = self.create_array_class(&dependency)?;
                                 } else {
-                                    let classfile =
-                                        self.class_loader.load_classfile(&dependency)?;
+                                    let classfile = self.load_classfile(&dependency)?;
                                     self.resolve_class(classfile)?;
                                 }
                             }
@@ -179,39 +562,10 @@ impl ClassManager {
 
                         // Once the dependencies are resolved (all of them has at least a ClassId),
                         // we can create the LoadingClass, and construct the constantpool, fields and methods.
-                        let loaded_class = LoadedClass::Loading(LoadingClass {
-                            class_id: resolved.class_id,
-                            class_name: class_name.to_string(),
-                            super_class: resolved.super_class,
-                            interfaces: resolved.interfaces,
-                            flags: resolved.classfile.access_flags().clone(),
-                            constant_pool: ConstantPool::from_classfile(self, &resolved.classfile)?,
-                            fields: resolved
-                                .classfile
-                                .fields()
-                                .iter()
-                                .map(|field| {
-                                    class::Field::try_from_classfile(
-                                        self,
-                                        resolved.classfile.constant_pool(),
-                                        field,
-                                    )
-                                })
-                                .collect::<Result<Vec<_>, _>>()?,
-                            methods: resolved
-                                .classfile
-                                .methods()
-                                .iter()
-                                .map(|method| {
-                                    class::Method::try_from_classfile(
-                                        self,
-                                        resolved.classfile.constant_pool(),
-                                        method,
-                                    )
-                                })
-                                .collect::<Result<Vec<_>, _>>()?,
-                            classfile: Some(resolved.classfile.clone()),
-                        });
+                        self.timings.enter(Phase::Linking);
+                        let loading = self.link_class(&class_name, resolved);
+                        self.timings.exit(Phase::Linking);
+                        let loaded_class = LoadedClass::Loading(loading?);
 
                         // Update the class manager with the loading class.
                         self.classes_by_id.insert(loaded_class.id(), loaded_class);
@@ -263,18 +617,25 @@ impl ClassManager {
                             }
                         }
 
+                        let field_offset = superclass
+                            .as_ref()
+                            .map_or(0, |x| x.field_offset + x.fields.len());
                         let class = Class {
                             id: loading.class_id,
                             name: loading.class_name.clone(),
                             superclass: superclass.map(|x| x.id),
                             interfaces: interfaces.iter().map(|x| x.id).collect(),
                             flags: loading.flags,
+                            major_version: loading.major_version,
                             constant_pool: loading.constant_pool.clone(),
                             fields: loading.fields.clone(),
+                            field_offset,
                             methods: loading.methods.clone(),
                             initialized: OnceCell::new(),
                             class_object: OnceCell::new(),
+                            attributes: loading.attributes.clone(),
                         };
+                        self.check_derivation(&class)?;
                         class.initialized.set(false).unwrap();
 
                         let loaded_class = LoadedClass::Loaded(class);
@@ -284,12 +645,25 @@ impl ClassManager {
                         let _ = self
                             .classes_by_id
                             .insert(loading.class_id, loaded_class.clone());
+                        self.events.emit(&VmEvent::ClassLoaded {
+                            class_name: class_name.clone(),
+                            class_id: loading.class_id.0,
+                        });
+
+                        // An interface is only initialized on its first use, e.g. of one
+                        // of its static fields or methods (JVMS 5.5).
+                        if loading.flags.contains(ClassAccessFlags::Interface) {
+                            self.pending_interfaces.insert(loading.class_id);
+                            continue;
+                        }
+                        self.initialize_superinterfaces(loading.class_id)?;
 
                         // Invoke the class initializer.
                         log::debug!("Invoking class initializer for {}", &loading.class_name);
-                        if let Err(err) =
-                            self.execute_class_init(&mut init_thread, &loading.class_id)
-                        {
+                        self.timings.enter(Phase::ClassInit);
+                        let init = self.execute_class_init(loading.class_id);
+                        self.timings.exit(Phase::ClassInit);
+                        if let Err(err) = init {
                             return Err(ClassLoadingError::InitializerError { source: err });
                         }
                     }
@@ -300,7 +674,7 @@ impl ClassManager {
                     let _ = self.create_array_class(&class_name)?;
                 } else {
                     // Standard class, just load it from its classfile
-                    let classfile = self.class_loader.load_classfile(&class_name)?;
+                    let classfile = self.load_classfile(&class_name)?;
                     self.resolve_class(classfile)?;
                 }
                 stack.push(class_name);
@@ -310,11 +684,151 @@ impl ClassManager {
         Ok(self.get_class_by_name(class_name).unwrap())
     }
 
+    /// Check that a class being loaded can derive from its superclass and its
+    /// superinterfaces, which are loaded (JVMS 5.3.5 and 5.4.5).
+    ///
+    /// The superclass must be neither final nor an interface, the superinterfaces
+    /// must be interfaces, and the methods must not override a final method.
+    fn check_derivation(&self, class: &Class) -> Result<(), DerivingError> {
+        if let Some(LoadedClass::Loaded(superclass)) =
+            class.superclass.and_then(|id| self.classes_by_id.get(&id))
+        {
+            if superclass.is_final() {
+                return Err(DerivingError::FinalSuperClass {
+                    class_name: class.name.clone(),
+                    super_class: superclass.name.clone(),
+                });
+            }
+            if superclass.is_interface() {
+                return Err(DerivingError::InterfaceSuperClass {
+                    class_name: class.name.clone(),
+                    super_class: superclass.name.clone(),
+                });
+            }
+        }
+        for interface_id in class.interfaces.iter() {
+            if let Some(LoadedClass::Loaded(interface)) = self.classes_by_id.get(interface_id) {
+                if !interface.is_interface() {
+                    return Err(DerivingError::NotAnInterface {
+                        class_name: class.name.clone(),
+                        interface_name: interface.name.clone(),
+                    });
+                }
+            }
+        }
+
+        for method in class.methods.iter() {
+            if method.is_static() || method.is_private() || method.name == "<init>" {
+                continue;
+            }
+            let mut cur = class.superclass;
+            while let Some(Some(LoadedClass::Loaded(superclass))) =
+                cur.map(|id| self.classes_by_id.get(&id))
+            {
+                let overridden = superclass
+                    .index_of_method(&method.name, &method.descriptor)
+                    .map(|index| &superclass.methods[index])
+                    .filter(|overridden| {
+                        !overridden.is_static()
+                            && !overridden.is_private()
+                            && (overridden.access() != MemberAccess::Package
+                                || superclass.package_name() == class.package_name())
+                    });
+                if overridden.is_some_and(|overridden| overridden.is_final()) {
+                    return Err(DerivingError::FinalMethodOverride {
+                        class_name: class.name.clone(),
+                        super_class: superclass.name.clone(),
+                        method: format!("{}{}", method.name, method.descriptor.as_str()),
+                    });
+                }
+                cur = superclass.superclass;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a classfile from the class loader.
+    fn load_classfile(&mut self, class_name: &str) -> Result<ClassFile, ClassLoadingError> {
+        self.timings.enter(Phase::ClassRead);
+        let classfile = self.class_loader.load_classfile(class_name);
+        self.timings.exit(Phase::ClassRead);
+        classfile
+    }
+
+    /// Create the LoadingClass of a resolved class, constructing its constant pool,
+    /// fields and methods.
+    fn link_class(
+        &mut self,
+        class_name: &str,
+        resolved: ResovedClass,
+    ) -> Result<LoadingClass, ClassLoadingError> {
+        Ok(LoadingClass {
+            class_id: resolved.class_id,
+            class_name: class_name.to_string(),
+            super_class: resolved.super_class,
+            interfaces: resolved.interfaces,
+            flags: resolved.classfile.access_flags(),
+            major_version: resolved.classfile.major_version(),
+            constant_pool: ConstantPool::from_classfile(self, &resolved.classfile)?,
+            fields: resolved
+                .classfile
+                .fields()
+                .iter()
+                .map(|field| {
+                    class::Field::try_from_classfile(
+                        self,
+                        resolved.classfile.constant_pool(),
+                        field,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            methods: resolved
+                .classfile
+                .methods()
+                .iter()
+                .map(|method| {
+                    class::Method::try_from_classfile(
+                        self,
+                        resolved.classfile.constant_pool(),
+                        method,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            attributes: resolved
+                .classfile
+                .attributes()
+                .iter()
+                .map(|attr| {
+                    class::parse_class_attribute(self, resolved.classfile.constant_pool(), attr)
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .collect(),
+            classfile: Some(resolved.classfile.clone()),
+        })
+    }
+
     /// Load a class from a classfile, and resolve its dependencies.
     ///
     /// This method will produces a ResolvedClass, with all its dependencies calculated.
     pub fn resolve_class(&mut self, classfile: ClassFile) -> Result<ClassId, ClassLoadingError> {
+        self.timings.enter(Phase::Resolution);
+        let class_id = self.resolve_class_dependencies(classfile);
+        self.timings.exit(Phase::Resolution);
+        class_id
+    }
+
+    fn resolve_class_dependencies(
+        &mut self,
+        classfile: ClassFile,
+    ) -> Result<ClassId, ClassLoadingError> {
         let class_name = classfile.class_name()?.to_string();
+        self.version_policy.check(
+            &class_name,
+            classfile.major_version(),
+            classfile.minor_version(),
+        )?;
         let class_id = self.acquire_class_id();
         let super_name = classfile.super_class_name()?.map(|x| x.to_string());
         //let flags = classfile.access_flags();
@@ -400,14 +914,14 @@ impl ClassManager {
     }
 
     /// Determine if this the given class is a superclass of the other class.
-    pub fn is_superclass_of(&self, class_id: &ClassId, other: &ClassId) -> bool {
-        let mut cur = class_id.clone();
-        while &cur != other {
+    pub fn is_superclass_of(&self, class_id: ClassId, other: ClassId) -> bool {
+        let mut cur = class_id;
+        while cur != other {
             let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&cur) else {
                 return false;
             };
             if let Some(super_class) = class.superclass {
-                cur = super_class.clone();
+                cur = super_class;
             } else {
                 return false;
             }
@@ -415,28 +929,260 @@ impl ClassManager {
         return true;
     }
 
+    /// Resolve a field by its name and descriptor, from the given class (JVMS 5.4.3.2).
+    ///
+    /// The field is looked up in the class itself, then in its superinterfaces,
+    /// then in its superclass, recursively.
+    /// Returns the declaring class and the index of the field in it.
+    pub fn resolve_field(
+        &self,
+        class_id: ClassId,
+        name: &str,
+        descriptor: &FieldDescriptor,
+    ) -> Option<(ClassId, usize)> {
+        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&class_id) else {
+            return None;
+        };
+        if let Some(index) = class
+            .fields
+            .iter()
+            .position(|field| field.name == name && field.descriptor == *descriptor)
+        {
+            return Some((class_id, index));
+        }
+        for interface in &class.interfaces {
+            if let Some(resolved) = self.resolve_field(*interface, name, descriptor) {
+                return Some(resolved);
+            }
+        }
+        self.resolve_field(class.superclass?, name, descriptor)
+    }
+
+    /// Check if two classes belong to the same nest (JVMS 5.4.4).
+    ///
+    /// Classes are nestmates if they have the same nest host, and the host lists
+    /// them in its NestMembers attribute (when it is loaded). The hidden classes
+    /// are members of the nest of their host without being listed.
+    pub fn are_nestmates(&self, class_id: ClassId, other: ClassId) -> bool {
+        if class_id == other {
+            return true;
+        }
+        let (Some(LoadedClass::Loaded(class)), Some(LoadedClass::Loaded(other))) = (
+            self.classes_by_id.get(&class_id),
+            self.classes_by_id.get(&other),
+        ) else {
+            return false;
+        };
+        let host_name = class.nest_host();
+        if host_name != other.nest_host() {
+            return false;
+        }
+        match self.get_class_by_name(host_name) {
+            Some(LoadedClass::Loaded(host)) => [class, other].iter().all(|member| {
+                member.name == host.name
+                    || member.is_hidden()
+                    || host.nest_members().contains(&member.name)
+            }),
+            _ => true,
+        }
+    }
+
+    /// Check if a member of `declaring`, with the given access level, is
+    /// accessible from `accessor` (JVMS 5.4.4).
+    ///
+    /// - public members are accessible from everywhere,
+    /// - protected members from the subclasses of `declaring` and its package,
+    /// - package-private members from the package of `declaring`,
+    /// - private members from the nestmates of `declaring`.
+    pub fn is_member_accessible(
+        &self,
+        accessor: ClassId,
+        declaring: ClassId,
+        access: MemberAccess,
+    ) -> bool {
+        match access {
+            MemberAccess::Public => true,
+            MemberAccess::Protected => {
+                self.is_superclass_of(accessor, declaring) || self.same_package(accessor, declaring)
+            }
+            MemberAccess::Package => self.same_package(accessor, declaring),
+            MemberAccess::Private => self.are_nestmates(accessor, declaring),
+        }
+    }
+
+    /// Check if two classes belong to the same runtime package.
+    ///
+    /// There is only one class loader, so the runtime package is given by the
+    /// package name.
+    pub fn same_package(&self, class_id: ClassId, other: ClassId) -> bool {
+        if class_id == other {
+            return true;
+        }
+        match (
+            self.classes_by_id.get(&class_id),
+            self.classes_by_id.get(&other),
+        ) {
+            (Some(LoadedClass::Loaded(class)), Some(LoadedClass::Loaded(other))) => {
+                class.package_name() == other.package_name()
+            }
+            _ => false,
+        }
+    }
+
     /// Resolve method reference
+    ///
+    /// For `invokespecial` (`special`), if the current class has the ACC_SUPER flag
+    /// and the referenced class is one of its superclasses, the lookup starts from
+    /// the direct superclass of the current class instead (JVMS 6.5 invokespecial).
+    ///
+    /// The resolved method must be accessible from the current class, see
+    /// [ClassManager::is_member_accessible].
     pub fn resolve_method(
         &mut self,
-        this_class: &ClassId,
-        impl_class: &ClassId,
+        this_class: ClassId,
+        impl_class: ClassId,
         name: &str,
-        descriptor: &MethodDescriptor,
+        descriptor: &InternedMethodDescriptor,
         special: bool,
     ) -> Result<Option<(ClassId, usize)>, ClassLoadingError> {
-        // `invokespecial` particular case resolution
-        if special && name != "<init>" && self.is_superclass_of(impl_class, this_class) {
-            let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(impl_class) else {
-                return Err(ClassLoadingError::NotFound);
+        let start = if special {
+            self.invokespecial_lookup_class(this_class, impl_class, name)
+        } else {
+            impl_class
+        };
+        let Some((declaring, index)) = self.lookup_method(start, name, descriptor)? else {
+            return Ok(None);
+        };
+
+        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&declaring) else {
+            return Err(ClassLoadingError::NotFound);
+        };
+        let method = class.methods.get(index).unwrap();
+        let access = method.access();
+        if !self.is_member_accessible(this_class, declaring, access) {
+            return Err(ClassLoadingError::IllegalAccess {
+                context: format!(
+                    "{} method {}.{}{} is not accessible from ClassId({})",
+                    access,
+                    class.name,
+                    name,
+                    descriptor.as_str(),
+                    this_class.0
+                ),
+            });
+        }
+        Ok(Some((declaring, index)))
+    }
+
+    /// Select the method invoked by `invokevirtual` or `invokeinterface` on an object
+    /// of the given class, from the resolved method (JVMS 5.4.6).
+    ///
+    /// Private methods are not virtual, they are invoked as resolved. Otherwise the
+    /// overriding method is searched in the class of the object and its superclasses,
+    /// then in its maximally-specific superinterfaces. The resolved method is kept
+    /// if no other method can be selected.
+    pub fn select_method(&self, class_id: ClassId, resolved: (ClassId, usize)) -> (ClassId, usize) {
+        let Some(LoadedClass::Loaded(declaring)) = self.classes_by_id.get(&resolved.0) else {
+            return resolved;
+        };
+        let Some(method) = declaring.methods.get(resolved.1) else {
+            return resolved;
+        };
+        if method.is_private() || method.is_static() {
+            return resolved;
+        }
+        let package_private = method.access() == MemberAccess::Package;
+        let (name, descriptor) = (&method.name, &method.descriptor);
+
+        // Search for an overriding method in the class and its superclasses
+        let mut interfaces = Vec::new();
+        let mut cur = Some(class_id);
+        while let Some(cid) = cur {
+            let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&cid) else {
+                return resolved;
             };
             if let Some(index) = class.index_of_method(name, descriptor) {
-                return Ok(Some((impl_class.clone(), index)));
+                let candidate = &class.methods[index];
+                if !candidate.is_private()
+                    && !candidate.is_static()
+                    && (!package_private || self.same_package(cid, resolved.0))
+                {
+                    return (cid, index);
+                }
             }
+            interfaces.extend(class.interfaces.iter().copied());
+            cur = class.superclass;
         }
 
+        // Search for the maximally-specific superinterface methods, a single
+        // non-abstract one is selected.
+        let mut candidates = Vec::new();
+        let mut visited = HashSet::new();
+        while let Some(cid) = interfaces.pop() {
+            if !visited.insert(cid) {
+                continue;
+            }
+            let Some(LoadedClass::Loaded(interface)) = self.classes_by_id.get(&cid) else {
+                continue;
+            };
+            match interface.index_of_method(name, descriptor) {
+                Some(index)
+                    if !interface.methods[index].is_private()
+                        && !interface.methods[index].is_static() =>
+                {
+                    candidates.push((cid, index, interface.methods[index].is_abstract()))
+                }
+                _ => interfaces.extend(interface.interfaces.iter().copied()),
+            }
+        }
+        let mut selected = candidates.iter().filter(|(cid, _, is_abstract)| {
+            !is_abstract
+                && !candidates
+                    .iter()
+                    .any(|(other, _, _)| other != cid && types::is_subtype_of(self, *other, *cid))
+        });
+        match (selected.next(), selected.next()) {
+            (Some((cid, index, _)), None) => (*cid, *index),
+            _ => resolved,
+        }
+    }
+
+    /// Get the class from which the `invokespecial` method lookup starts.
+    fn invokespecial_lookup_class(
+        &self,
+        this_class: ClassId,
+        impl_class: ClassId,
+        name: &str,
+    ) -> ClassId {
+        let Some(LoadedClass::Loaded(current)) = self.classes_by_id.get(&this_class) else {
+            return impl_class;
+        };
+        let Some(LoadedClass::Loaded(referenced)) = self.classes_by_id.get(&impl_class) else {
+            return impl_class;
+        };
+        if name != "<init>"
+            && !referenced.is_interface()
+            && current.is_super()
+            && this_class != impl_class
+            && self.is_superclass_of(this_class, impl_class)
+        {
+            if let Some(superclass) = current.superclass {
+                return superclass;
+            }
+        }
+        impl_class
+    }
+
+    /// Search a method in a class, its superclasses, and then its superinterfaces.
+    fn lookup_method(
+        &self,
+        class_id: ClassId,
+        name: &str,
+        descriptor: &InternedMethodDescriptor,
+    ) -> Result<Option<(ClassId, usize)>, ClassLoadingError> {
         // Search for the method in the class and its superclasses
         // In the same time, collect the superinterfaces to search for, if it fails.
-        let mut cur = Some(impl_class.clone());
+        let mut cur = Some(class_id);
         let mut superinterfaces = Vec::new();
         while let Some(cid) = cur {
             let Some(LoadedClass::Loaded(class)) = self.get_class_by_id(cid) else {
@@ -445,24 +1191,37 @@ impl ClassManager {
             if let Some(index) = class.index_of_method(name, descriptor) {
                 return Ok(Some((cid, index)));
             }
-            superinterfaces.extend(class.interfaces.iter().cloned());
+            superinterfaces.extend(class.interfaces.iter().copied());
             cur = class.superclass;
         }
 
-        // Search for the method in the superinterfaces
-        for cid in superinterfaces {
+        // Search for the method in the superinterfaces, and their own superinterfaces.
+        // A default method is preferred, but an abstract method resolves as well,
+        // the selection finding the implementation (JVMS 5.4.3.3).
+        let mut abstract_method = None;
+        let mut visited = HashSet::new();
+        let mut index = 0;
+        while let Some(cid) = superinterfaces.get(index).copied() {
+            index += 1;
+            if !visited.insert(cid) {
+                continue;
+            }
             let Some(LoadedClass::Loaded(class)) = self.get_class_by_id(cid) else {
                 return Err(ClassLoadingError::NotFound);
             };
-            if let Some(index) = class.index_of_method(name, descriptor) {
-                let method = class.methods.get(index).unwrap();
-                if !method.is_private() && !method.is_static() && !method.is_abstract() {
-                    return Ok(Some((cid, index)));
+            if let Some(method_index) = class.index_of_method(name, descriptor) {
+                let method = class.methods.get(method_index).unwrap();
+                if !method.is_private() && !method.is_static() {
+                    if !method.is_abstract() {
+                        return Ok(Some((cid, method_index)));
+                    }
+                    abstract_method.get_or_insert((cid, method_index));
                 }
             }
+            superinterfaces.extend(class.interfaces.iter().copied());
         }
 
-        Ok(None)
+        Ok(abstract_method)
     }
 
     pub fn create_array_class(&mut self, array_name: &str) -> Result<ClassId, ClassLoadingError> {
@@ -474,10 +1233,12 @@ impl ClassManager {
             super_class: Some("java/lang/Object".into()),
             interfaces: vec!["java/lang/Cloneable".into(), "java/io/Serializable".into()],
             flags: ClassAccessFlags::Public | ClassAccessFlags::Final,
+            major_version: class_version::MAX_MAJOR_VERSION,
             constant_pool: ConstantPool::new(vec![]),
             fields: vec![],
             // TODO: Add the clone method
             methods: vec![],
+            attributes: vec![],
             classfile: None,
         };
 
@@ -489,10 +1250,117 @@ impl ClassManager {
         Ok(loaded_class.id())
     }
 
+    /// Define a class built by the VM itself (e.g. the class of a lambda), and load it.
+    ///
+    /// Its superclass and interfaces must already be loaded.
+    pub fn define_class(&mut self, class: LoadingClass) -> Result<ClassId, ClassLoadingError> {
+        log::debug!("Defining class {}", class.class_name);
+        let class_id = class.class_id;
+        self.name_map.insert(class.class_name.clone(), class_id);
+        if self
+            .classes_by_id
+            .insert(class_id, LoadedClass::Loading(class))
+            .is_some()
+        {
+            // Redefined, the hierarchy of the class may have changed.
+            self.subtype_cache.invalidate();
+        }
+        self.request_class_load(class_id)
+    }
+
+    /// Remove a class defined by the VM itself, dropping its structures and its
+    /// decoded code.
+    ///
+    /// This is not an unloading driven by reachability: nothing tracks the uses
+    /// of the class, the caller must ensure nothing refers to it anymore
+    /// (instances, frames, constant pools of the other classes...). Its mirror
+    /// is only forgotten by the class manager, a program keeping a reference to
+    /// it keeps it alive.
+    ///
+    /// The classes of the class loader are never removed: they are all defined
+    /// by the bootstrap loader, which lives as long as the VM.
+    ///
+    /// Returns whether the class was loaded.
+    pub(crate) fn unload_class(&mut self, class_id: ClassId) -> bool {
+        let Some(class) = self.classes_by_id.remove(&class_id) else {
+            return false;
+        };
+        log::debug!("Unloading class {}", class.name());
+        if self.name_map.get(class.name()) == Some(&class_id) {
+            self.name_map.remove(class.name());
+        }
+        self.initializing.remove(&class_id);
+        self.pending_interfaces.remove(&class_id);
+        self.subtype_cache.invalidate();
+        self.unloaded_classes += 1;
+        true
+    }
+
+    /// Get the number of classes removed by [ClassManager::unload_class] since
+    /// the creation of the class manager.
+    pub fn unloaded_class_count(&self) -> usize {
+        self.unloaded_classes
+    }
+
+    /// Allocate an object on the heap.
+    pub fn alloc_object(&mut self, obj: Object) -> ObjectRef {
+        self.allocations
+            .record_object(obj.class_id(), Object::size_estimate(obj.field_count()));
+        let obj = Gc::new(obj);
+        self.collector.after_allocation();
+        obj
+    }
+
+    /// Check that an array of `count` elements of `item_size` bytes fits in the
+    /// heap budget, given the live arrays of the VM.
+    pub fn check_array_budget(&self, count: usize, item_size: usize) -> Result<(), AllocError> {
+        self.heap_budget
+            .check_array(self.allocations.live_array_bytes(), count, item_size)
+    }
+
+    /// Allocate an array on the heap.
+    ///
+    /// The heap budget must have been checked beforehand.
+    /// See [ClassManager::check_array_budget].
+    pub fn alloc_array(&mut self, array: Array) -> ArrayRef {
+        self.allocations.record_array(&array);
+        let array = Gc::new(array);
+        self.collector.after_allocation();
+        array
+    }
+
+    /// Get the memory statistics of the loaded classes.
+    pub fn class_stats(&self) -> ClassStats {
+        let mut classes: Vec<ClassStat> = self
+            .classes_by_id
+            .values()
+            .filter_map(|class| match class {
+                LoadedClass::Loaded(class) => Some(ClassStat {
+                    class_id: class.id,
+                    name: class.name.clone(),
+                    instance_size: class.instance_size_estimate(),
+                    static_size: class.static_size_estimate(),
+                    allocations: self.allocations.objects(class.id),
+                }),
+                _ => None,
+            })
+            .collect();
+        classes.sort_by(|a, b| {
+            b.allocations
+                .bytes
+                .cmp(&a.allocations.bytes)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        ClassStats {
+            classes,
+            arrays: self.allocations.arrays(),
+        }
+    }
+
     /// Get the Class<T> object for a given class.
-    pub fn get_class_object(&mut self, class_id: &ClassId) -> Result<ObjectRef, ClassLoadingError> {
-        let _ = self.request_class_load(class_id.clone())?;
-        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(class_id) else {
+    pub fn get_class_object(&mut self, class_id: ClassId) -> Result<ObjectRef, ClassLoadingError> {
+        let _ = self.request_class_load(class_id)?;
+        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&class_id) else {
             return Err(ClassLoadingError::NotFound);
         };
 
@@ -503,16 +1371,71 @@ impl ClassManager {
         let class_ty = self.get_or_resolve_class("java/lang/Class")?;
         let class_ty = class_ty.id();
 
-        let obj = Gc::new(Object::new_with_classmanager(self, class_ty)?);
+        let obj = Object::new_with_classmanager(self, class_ty)?;
+        let obj = self.alloc_object(obj);
 
         // TODO: Maybe init the class object, but for now, it should be OK as <init> currently only put default zero values in the fields.
 
-        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(class_id) else {
+        let Some(LoadedClass::Loaded(class)) = self.classes_by_id.get(&class_id) else {
             return Err(ClassLoadingError::NotFound);
         };
         class.class_object.set(obj.clone()).unwrap();
         Ok(obj)
     }
+
+    /// Get the java/lang/Class object of an array type, creating its class if needed.
+    pub fn get_array_class_object(
+        &mut self,
+        array_type: &FieldType,
+    ) -> Result<ObjectRef, ClassLoadingError> {
+        let name = type_descriptor(array_type);
+        // The superinterfaces of the array classes must be loaded first.
+        self.get_or_resolve_class("java/lang/Cloneable")?;
+        self.get_or_resolve_class("java/io/Serializable")?;
+        let class_id = match self.id_of_class(&name) {
+            Some(class_id) => class_id,
+            None => self.create_array_class(&name)?,
+        };
+        self.get_class_object(class_id)
+    }
+
+    /// Find the class whose java/lang/Class object is the given mirror.
+    ///
+    /// The mirrors are only created on demand, so the loaded classes are scanned.
+    pub fn class_of_mirror(&self, mirror: &Object) -> Option<ClassId> {
+        self.classes_by_id.values().find_map(|class| match class {
+            LoadedClass::Loaded(class) => class
+                .class_object
+                .get()
+                .filter(|obj| std::ptr::eq::<Object>(&***obj, mirror))
+                .map(|_| class.id),
+            _ => None,
+        })
+    }
+}
+
+/// The loaded classes, the interned strings and the threads not yet started
+/// or used for the calls of the VM.
+impl RootProvider for ClassManager {
+    fn add_roots(&self, roots: &mut RootSet) {
+        for class in self.classes_by_id.values() {
+            if let LoadedClass::Loaded(class) = class {
+                roots.add_provider(class);
+            }
+        }
+        for string in self.string_constants.values() {
+            roots.add_object(string);
+        }
+        for (_, mirror) in &self.primitive_mirrors {
+            roots.add_object(mirror);
+        }
+        for hook in &self.shutdown_hooks {
+            roots.add_object(hook);
+        }
+        for thread in self.started_threads.iter().chain(&self.init_threads) {
+            roots.add_provider(thread);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -547,9 +1470,11 @@ pub struct LoadingClass {
     pub super_class: Option<String>,
     pub interfaces: Vec<String>,
     pub flags: FlagSet<ClassAccessFlags>,
+    pub major_version: u16,
     pub constant_pool: ConstantPool,
     pub fields: Vec<class::Field>,
     pub methods: Vec<class::Method>,
+    pub attributes: Vec<class::ClassAttribute>,
     pub classfile: Option<ClassFile>,
 }
 
@@ -566,3 +1491,989 @@ pub struct ResovedClass {
     /// dependency is a super class or an interface, and therefore must be fully loaded before this class.
     pub class_dependencies: Vec<(String, bool)>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constant_pool::ConstantPoolEntry as RtConstantPoolEntry;
+    use crate::test_util::test_class_manager;
+
+    fn static_int(cm: &ClassManager, class_name: &str, field_name: &str) -> Option<i32> {
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_name(class_name) else {
+            return None;
+        };
+        match class.get_field(field_name)?.get_value()? {
+            crate::thread::Slot::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn cyclic_class_init() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("CycleA").unwrap();
+        // CycleB is initialized while CycleA is being initialized, and sees its default value.
+        assert_eq!(static_int(&cm, "CycleB", "b"), Some(10));
+        assert_eq!(static_int(&cm, "CycleA", "a"), Some(11));
+        assert!(cm.initializing.is_empty());
+        assert_eq!(cm.init_threads.len(), 2);
+    }
+
+    #[test]
+    fn interface_method_calls() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("InterfaceCalls").unwrap();
+        // invokestatic of an InterfaceMethodref
+        assert_eq!(static_int(&cm, "InterfaceCalls", "staticCall"), Some(42));
+        // invokeinterface selecting the implementation of the object class
+        assert_eq!(static_int(&cm, "InterfaceCalls", "virtualCall"), Some(7));
+        // default method, invoking a private interface method with invokeinterface
+        assert_eq!(static_int(&cm, "InterfaceCalls", "defaultCall"), Some(107));
+    }
+
+    #[test]
+    fn interface_method_ref_version() {
+        let mut cm = test_class_manager();
+        // Before Java 8, invokestatic cannot name an InterfaceMethodref.
+        cm.add_transformer(Box::new(|name: &str, bytes: &[u8]| {
+            (name == "InterfaceCalls").then(|| {
+                let mut bytes = bytes.to_vec();
+                bytes[6..8].copy_from_slice(&51u16.to_be_bytes());
+                bytes
+            })
+        }));
+        let err = cm.get_or_resolve_class("InterfaceCalls").unwrap_err();
+        let ClassLoadingError::InitializerError {
+            source: ExecutionError::InstructionExecutionError { source },
+        } = err
+        else {
+            panic!("unexpected error: {}", err);
+        };
+        assert!(
+            source.to_string().contains("MethodRef not found"),
+            "unexpected error: {}",
+            source
+        );
+    }
+
+    #[test]
+    fn final_field_initializers() {
+        fn call(cm: &mut ClassManager, name: &str) -> Result<Option<Slot>, ExecutionError> {
+            let class = cm.get_or_resolve_class("FinalFields").unwrap().id();
+            let Some(LoadedClass::Loaded(fields)) = cm.get_class_by_id(class) else {
+                panic!("FinalFields is not loaded");
+            };
+            let method = fields.methods.iter().position(|m| m.name == name);
+            cm.run_method(class, method.unwrap(), vec![])
+        }
+
+        let mut cm = test_class_manager();
+        // The constructor of Base sets its final field on an instance of Derived.
+        assert!(matches!(call(&mut cm, "derived"), Ok(Some(Slot::Int(73)))));
+        assert!(matches!(call(&mut cm, "bumped"), Ok(Some(Slot::Int(1)))));
+
+        // Once final, the counter cannot be set by the constructor of Derived,
+        // nor by a method of Base.
+        let base = cm.get_or_resolve_class("FinalFields$Base").unwrap().id();
+        let Some(LoadedClass::Loaded(class)) = cm.get_mut_class_by_id(base) else {
+            panic!("FinalFields$Base is not loaded");
+        };
+        class.get_mut_field("counter").unwrap().flags |=
+            reader::base::classfile::FieldAccessFlags::Final;
+        for name in ["derived", "bumped"] {
+            let result = call(&mut cm, name);
+            assert!(
+                matches!(
+                    result,
+                    Err(ExecutionError::InstructionExecutionError {
+                        source: InstructionError::InvalidState { .. }
+                    })
+                ),
+                "{}: {:?}",
+                name,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn superinterface_methods() {
+        let mut cm = test_class_manager();
+        let class = cm
+            .get_or_resolve_class("SuperinterfaceMethods")
+            .unwrap()
+            .id();
+        let Some(LoadedClass::Loaded(methods)) = cm.get_class_by_id(class) else {
+            panic!("SuperinterfaceMethods is not loaded");
+        };
+        let greet = methods.methods.iter().position(|m| m.name == "greet");
+        let size = methods.methods.iter().position(|m| m.name == "size");
+
+        // A default method of an indirect superinterface.
+        let result = cm.run_method(class, greet.unwrap(), vec![]);
+        assert!(matches!(result, Ok(Some(Slot::Int(5)))), "{:?}", result);
+        // An abstract method of an indirect superinterface of an abstract class,
+        // selected in the subclass.
+        let result = cm.run_method(class, size.unwrap(), vec![]);
+        assert!(matches!(result, Ok(Some(Slot::Int(4)))), "{:?}", result);
+    }
+
+    #[test]
+    fn incompatible_method_reference() {
+        let mut cm = test_class_manager();
+        // A Methodref to a class turned into an interface.
+        let err = cm.get_or_resolve_class("IncompatibleCalls").unwrap_err();
+        let ClassLoadingError::InitializerError {
+            source: ExecutionError::InstructionExecutionError { source },
+        } = err
+        else {
+            panic!("unexpected error: {}", err);
+        };
+        assert_eq!(
+            source.java_exception(),
+            Some("java/lang/IncompatibleClassChangeError")
+        );
+    }
+
+    #[test]
+    fn method_parameters() {
+        let mut cm = test_class_manager();
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("Parameters").unwrap() else {
+            panic!("Parameters not loaded");
+        };
+        let method = class.methods.iter().find(|m| m.name == "add").unwrap();
+        let names: Vec<_> = (0..2).map(|i| method.parameter_name(i)).collect();
+        assert_eq!(names, [Some("left"), Some("right")]);
+        assert!(!method.parameters()[0].is_final());
+        assert!(method.parameters()[1].is_final());
+        // The constructor is compiled without parameter.
+        let init = class.methods.iter().find(|m| m.name == "<init>").unwrap();
+        assert!(init.parameter_name(0).is_none());
+    }
+
+    #[test]
+    fn declared_exceptions() {
+        let mut cm = test_class_manager();
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("Throws").unwrap() else {
+            panic!("Throws not loaded");
+        };
+        let exceptions = |name| {
+            let method = class.methods.iter().find(|m| m.name == name).unwrap();
+            method
+                .declared_exceptions()
+                .iter()
+                .map(|class_name| class_name.as_binary_name())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(exceptions("read"), ["ReadFailure", "java/lang/Exception"]);
+        assert!(exceptions("run").is_empty());
+    }
+
+    #[test]
+    fn dynamic_constants() {
+        let mut cm = test_class_manager();
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("DynamicConstants").unwrap()
+        else {
+            panic!("DynamicConstants not loaded");
+        };
+        let class_id = class.id;
+        let method = |name, descriptor: &str| {
+            class
+                .methods
+                .iter()
+                .position(|m| m.name == name && m.descriptor.as_str() == descriptor)
+                .unwrap()
+        };
+        let (answer, wide) = (method("answer", "()I"), method("wide", "()J"));
+
+        for _ in 0..2 {
+            let value = cm.run_method(class_id, answer, vec![]).unwrap();
+            assert!(matches!(value, Some(Slot::Int(42))));
+        }
+        // The bootstrap method only runs on the first resolution.
+        assert_eq!(static_int(&cm, "DynamicConstants", "calls"), Some(1));
+
+        let value = cm.run_method(class_id, wide, vec![]).unwrap();
+        assert!(matches!(value, Some(Slot::Long(value)) if value == 1 << 40));
+    }
+
+    #[test]
+    fn class_init_by_another_thread() {
+        let mut cm = test_class_manager();
+        let class_id = cm.get_or_resolve_class("CycleB").unwrap().id();
+        cm.initializing.insert(class_id, 1);
+        assert!(matches!(
+            cm.request_class_load(class_id),
+            Err(ClassLoadingError::InitializationInProgress { thread: 1, .. })
+        ));
+        cm.current_thread = 1;
+        assert!(cm.request_class_load(class_id).is_ok());
+    }
+
+    #[test]
+    fn string_constant_identity() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("StringIdentity").unwrap();
+        assert_eq!(static_int(&cm, "StringIdentity", "sameClass"), Some(1));
+        // Equal literals of different classes are the same object.
+        assert_eq!(static_int(&cm, "StringIdentity", "otherClass"), Some(1));
+        assert_eq!(static_int(&cm, "StringIdentity", "newObject"), Some(0));
+        // The literals are only materialized by their first ldc.
+        assert!(cm.string_constants.contains_key("hello"));
+        assert!(!cm.string_constants.contains_key("unused"));
+    }
+
+    #[test]
+    fn class_stats() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("StringIdentity").unwrap();
+        let stats = cm.class_stats();
+        assert!(stats.loaded_classes() >= 3);
+        let string_stat = stats.get("java/lang/String").unwrap();
+        // The "hello" constant and the new String of the initializer.
+        assert!(string_stat.allocations.count >= 2);
+        assert_eq!(
+            string_stat.allocations.bytes,
+            string_stat.allocations.count * string_stat.instance_size as u64
+        );
+        let identity = stats.get("StringIdentity").unwrap();
+        assert_eq!(identity.allocations.count, 0);
+        assert_eq!(identity.static_size, 3 * std::mem::size_of::<Slot>());
+        assert!(stats.arrays.count >= 2);
+    }
+
+    #[test]
+    fn code_attributes() {
+        use crate::class::{StackMapFrameKind, VerificationType};
+
+        let mut cm = test_class_manager();
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("CodeAttributes").unwrap() else {
+            panic!("CodeAttributes not loaded");
+        };
+        let code = |name: &str| {
+            let method = class.methods.iter().find(|m| m.name == name).unwrap();
+            method.get_code().unwrap()
+        };
+
+        let sum = code("sum");
+        assert_eq!(sum.local_variable(1, 2).unwrap().name, "total");
+        assert!(sum.local_variable(5, 2).is_none());
+        assert_eq!(sum.local_variable(5, 22).unwrap().name, "value");
+        let pcs: Vec<_> = sum.stack_map.iter().map(|frame| frame.pc).collect();
+        assert_eq!(pcs, [10, 33]);
+        let Some(StackMapFrameKind::Full { locals, stack }) =
+            sum.stack_map_frame(10).map(|frame| &frame.kind)
+        else {
+            panic!("expected a full frame at pc 10");
+        };
+        assert_eq!(locals[0], VerificationType::Object("[I".into()));
+        assert_eq!(locals[1], VerificationType::Integer);
+        assert!(stack.is_empty());
+        assert!(matches!(
+            sum.stack_map_frame(33).unwrap().kind,
+            StackMapFrameKind::Chop(3)
+        ));
+
+        let first = code("first");
+        let value = first.local_variable(1, 4).unwrap();
+        assert_eq!(
+            crate::value::type_name(value.descriptor.field_type()),
+            "java.lang.Object"
+        );
+        assert_eq!(value.signature.as_deref(), Some("TT;"));
+
+        let table = code("guarded").exception_table.clone();
+        let targets = |pc| {
+            table
+                .handlers_at(pc)
+                .map(|handler| handler.handler_pc)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(targets(2), [14, 27, 41]);
+        assert_eq!(targets(15), [27, 41]);
+        assert_eq!(targets(42), [41]);
+        assert!(targets(5).is_empty());
+        assert_eq!(
+            table.handlers()[0].catch_type.as_deref(),
+            Some("java/lang/Error")
+        );
+        assert!(table.handlers()[3].catch_type.is_none());
+
+        let mut class_id = |name| cm.get_or_resolve_class(name).unwrap().id();
+        let error = class_id("java/lang/Error");
+        let runtime = class_id("java/lang/RuntimeException");
+        let throwable = class_id("java/lang/Throwable");
+        let mut handler = |pc, exception| {
+            table
+                .find_handler(&mut cm, pc, exception)
+                .unwrap()
+                .map(|handler| handler.handler_pc)
+        };
+        assert_eq!(handler(2, error), Some(14));
+        assert_eq!(handler(2, runtime), Some(27));
+        assert_eq!(handler(2, throwable), Some(41));
+        // Resolved once, the catch types are then reused.
+        assert_eq!(handler(3, error), Some(14));
+        assert_eq!(handler(15, error), Some(41));
+        assert_eq!(handler(5, throwable), None);
+    }
+
+    #[test]
+    fn deterministic_identity_hashes() {
+        let run = |seed| {
+            let mut cm = test_class_manager();
+            cm.identity_hashes = IdentityHashGenerator::seeded(seed);
+            cm.clock = Clock::fixed();
+            cm.get_or_resolve_class("Determinism").unwrap();
+            let field = |name| static_int(&cm, "Determinism", name).unwrap();
+            assert_eq!(field("stable"), 1);
+            assert_ne!(field("first"), field("second"));
+            assert_eq!(field("elapsed"), 1_000_000);
+            (field("first"), field("second"), field("arrayHash"))
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn lambda_metafactory() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("Lambdas").unwrap();
+        let field = |name| static_int(&cm, "Lambdas", name);
+        assert_eq!(field("sum"), Some(7));
+        assert_eq!(field("captured"), Some(13));
+        assert_eq!(field("instance"), Some(42));
+        assert_eq!(field("bound"), Some(40));
+        assert_eq!(field("constructed"), Some(42));
+        assert_eq!(field("boxed"), Some(42));
+        assert_eq!(field("adapted"), Some(-5));
+
+        let lambda = cm
+            .name_map
+            .keys()
+            .find(|name| name.starts_with("Lambdas$$Lambda$"))
+            .unwrap();
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_name(lambda) else {
+            panic!("lambda class not loaded");
+        };
+        assert!(class.is_hidden());
+        assert_eq!(class.nest_host(), "Lambdas");
+    }
+
+    #[test]
+    fn interface_initialization() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("InterfaceInit").unwrap();
+        let field = |name| static_int(&cm, "InterfaceInit", name);
+        // Only the superinterface with a default method is initialized with Impl,
+        // and an interface is initialized without its superinterfaces.
+        assert_eq!(field("afterImpl"), Some(24));
+        assert_eq!(field("afterPlain"), Some(243));
+        assert_eq!(field("afterInherited"), Some(2431));
+    }
+
+    #[test]
+    fn constant_values() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("ConstantValues").unwrap();
+        assert_eq!(static_int(&cm, "ConstantValues", "FLAG"), Some(1));
+        assert_eq!(
+            static_int(&cm, "ConstantValues", "LETTER"),
+            Some('x' as i32)
+        );
+        assert_eq!(static_int(&cm, "ConstantValues", "SMALL"), Some(-3));
+        assert_eq!(static_int(&cm, "ConstantValues", "MEDIUM"), Some(1234));
+        // The ConstantValue and the ldc of the static initializer share the interned String.
+        assert_eq!(static_int(&cm, "ConstantValues", "same"), Some(1));
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_name("ConstantValues") else {
+            panic!("ConstantValues is not loaded");
+        };
+        let value = class.get_field("GREETING").unwrap().get_value().unwrap();
+        let interned = crate::thread::Slot::ObjectReference(cm.string_constants["hello"].clone());
+        assert_eq!(value.same_reference(&interned), Some(true));
+    }
+
+    #[test]
+    fn intrinsic_lookup_once() {
+        let mut cm = test_class_manager();
+        let LoadedClass::Loaded(math) = cm.get_or_resolve_class("java/lang/Math").unwrap() else {
+            panic!("java/lang/Math is not loaded");
+        };
+        let sqrt = math.methods.iter().find(|m| m.name == "sqrt").unwrap();
+        let registry = IntrinsicRegistry::new();
+        assert!(sqrt.intrinsic(&registry, &math.name).is_some());
+        // The intrinsic is kept by the method, whatever the registry.
+        assert!(sqrt
+            .intrinsic(&IntrinsicRegistry::empty(), &math.name)
+            .is_some());
+        let abs = math.methods.iter().find(|m| m.name == "abs").unwrap();
+        assert!(abs
+            .intrinsic(&IntrinsicRegistry::empty(), &math.name)
+            .is_none());
+        assert!(abs.intrinsic(&registry, &math.name).is_none());
+    }
+
+    #[test]
+    fn math_intrinsics() {
+        let mut cm = test_class_manager();
+        // The Math stub only has native methods, implemented by the intrinsics.
+        cm.get_or_resolve_class("MathCalls").unwrap();
+        for (field, expected) in [
+            ("sine", 1000),
+            ("cosine", 1),
+            ("root", 12),
+            ("floor", -2),
+            ("ceil", -1),
+            ("power", 1024),
+            ("absMin", i32::MIN),
+            ("minLong", -5),
+            ("maxInt", 7),
+            ("negativeZero", 1),
+            ("nan", 1),
+        ] {
+            assert_eq!(
+                static_int(&cm, "MathCalls", field),
+                Some(expected),
+                "{field}"
+            );
+        }
+    }
+
+    #[test]
+    fn array_intrinsics() {
+        // The Arrays stub has plain Java loops, the intrinsics must give the same results.
+        for intrinsics in [IntrinsicRegistry::new(), IntrinsicRegistry::empty()] {
+            let mut cm = test_class_manager();
+            cm.intrinsics = intrinsics;
+            cm.get_or_resolve_class("ArrayBulk").unwrap();
+            for (field, expected) in [
+                ("filled", 6),
+                ("ranged", 373),
+                ("copied", 37),
+                ("padded", 70),
+                ("sliced", 470),
+                ("equal", 101),
+                ("nans", 1),
+                ("bytes", -4),
+            ] {
+                assert_eq!(
+                    static_int(&cm, "ArrayBulk", field),
+                    Some(expected),
+                    "{field}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn string_intrinsics() {
+        let mut cm = test_class_manager();
+        // The String stub only has native methods, implemented by the intrinsics.
+        cm.get_or_resolve_class("StringCalls").unwrap();
+        for (field, expected) in [
+            ("length", 5),
+            ("letter", 'e' as i32),
+            ("hash", 99162322),
+            ("cachedHash", 99162322),
+            ("equal", 1),
+            ("notEqual", 0),
+            ("order", 'l' as i32 - 'p' as i32),
+            ("prefix", 1),
+            ("charIndex", 2),
+            ("stringIndex", 3),
+        ] {
+            assert_eq!(
+                static_int(&cm, "StringCalls", field),
+                Some(expected),
+                "{field}"
+            );
+        }
+        let hello = &cm.string_constants["hello"];
+        assert!(matches!(
+            hello.get_field_by_name(&cm, "hash"),
+            Some(crate::thread::Slot::Int(99162322))
+        ));
+    }
+
+    #[test]
+    fn final_class_and_method_derivation() {
+        let mut cm = test_class_manager();
+        // The superclasses and superinterfaces changed after the classes were compiled.
+        assert!(matches!(
+            cm.get_or_resolve_class("FinalChild"),
+            Err(ClassLoadingError::DerivingError {
+                source: DerivingError::FinalSuperClass { .. }
+            })
+        ));
+        assert!(matches!(
+            cm.get_or_resolve_class("InterfaceChild"),
+            Err(ClassLoadingError::DerivingError {
+                source: DerivingError::InterfaceSuperClass { .. }
+            })
+        ));
+        assert!(matches!(
+            cm.get_or_resolve_class("ClassImplementor"),
+            Err(ClassLoadingError::DerivingError {
+                source: DerivingError::NotAnInterface { .. }
+            })
+        ));
+        let err = cm.get_or_resolve_class("FinalOverride").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Deriving error: VerifyError: class FinalOverride overrides final method FinalMethods.locked()I"
+        );
+
+        // A private method is not overridden.
+        cm.get_or_resolve_class("HiddenOverride").unwrap();
+        assert_eq!(static_int(&cm, "HiddenOverride", "value"), Some(2));
+    }
+
+    #[test]
+    fn static_field_defaults() {
+        let mut cm = test_class_manager();
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("StaticDefaults").unwrap() else {
+            panic!("StaticDefaults not loaded");
+        };
+        let value = |name: &str| class.get_field(name).unwrap().get_value().cloned();
+        for name in ["flag", "letter", "integer"] {
+            assert!(matches!(value(name), Some(Slot::Int(0))), "{}", name);
+        }
+        assert!(matches!(value("wide"), Some(Slot::Long(0))));
+        assert!(matches!(value("single"), Some(Slot::Float(f)) if f == 0.0));
+        assert!(matches!(value("precise"), Some(Slot::Double(d)) if d == 0.0));
+        assert!(matches!(value("reference"), Some(Slot::UndefinedReference)));
+        assert!(matches!(value("array"), Some(Slot::UndefinedReference)));
+        assert!(matches!(value("CONSTANT"), Some(Slot::Int(7))));
+        assert!(matches!(
+            value("WIDE_CONSTANT"),
+            Some(Slot::Long(0x100_0000_0000))
+        ));
+    }
+
+    #[test]
+    fn inherited_fields() {
+        let mut cm = test_class_manager();
+        let class = cm.get_or_resolve_class("InheritedFields").unwrap().id();
+        let base = cm.get_or_resolve_class("FieldBase").unwrap().id();
+        let source = cm.get_or_resolve_class("FieldSource").unwrap().id();
+        let field = |descriptor: &str| FieldDescriptor::parse(descriptor).unwrap().1;
+        let resolve = |name: &str, descriptor: &str| {
+            cm.resolve_field(class, name, &field(descriptor))
+                .map(|(declaring, _)| declaring)
+        };
+        assert_eq!(resolve("value", "J"), Some(class));
+        assert_eq!(resolve("value", "I"), Some(base));
+        assert_eq!(resolve("total", "I"), Some(base));
+        assert_eq!(resolve("MARK", "Ljava/lang/Object;"), Some(source));
+        assert_eq!(resolve("value", "D"), None);
+        assert_eq!(resolve("total", "J"), None);
+
+        let Some(LoadedClass::Loaded(fields)) = cm.get_class_by_id(class) else {
+            panic!("InheritedFields is not loaded");
+        };
+        let read = fields.methods.iter().position(|m| m.name == "read");
+        let result = cm.run_method(class, read.unwrap(), vec![]);
+        assert!(matches!(result, Ok(Some(Slot::Int(1457)))), "{:?}", result);
+    }
+
+    #[test]
+    fn run_method_arguments() {
+        let mut cm = test_class_manager();
+        let class = cm.get_or_resolve_class("Stepping").unwrap().id();
+        let Some(LoadedClass::Loaded(stepping)) = cm.get_class_by_id(class) else {
+            panic!("Stepping is not loaded");
+        };
+        let square = stepping.methods.iter().position(|m| m.name == "square");
+        let square = square.unwrap();
+        let result = cm.run_method(class, square, vec![Slot::Int(7)]);
+        assert!(matches!(result, Ok(Some(Slot::Int(49)))), "{:?}", result);
+        // square(I)I has a single local variable.
+        for args in [vec![Slot::Int(7), Slot::Int(1)], vec![Slot::Long(7)]] {
+            let result = cm.run_method(class, square, args);
+            assert!(
+                matches!(
+                    result,
+                    Err(ExecutionError::InstructionExecutionError {
+                        source: InstructionError::InvalidState { .. }
+                    })
+                ),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn abstract_classes_and_methods() {
+        fn area(cm: &mut ClassManager) -> Result<Option<Slot>, ExecutionError> {
+            let class = cm.get_or_resolve_class("Abstracts").unwrap().id();
+            let Some(LoadedClass::Loaded(abstracts)) = cm.get_class_by_id(class) else {
+                panic!("Abstracts is not loaded");
+            };
+            let method = abstracts.methods.iter().position(|m| m.name == "area");
+            cm.run_method(class, method.unwrap(), vec![])
+        }
+        fn java_exception(result: Result<Option<Slot>, ExecutionError>) -> Option<&'static str> {
+            match result {
+                Err(ExecutionError::InstructionExecutionError { source }) => {
+                    source.java_exception()
+                }
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+
+        let mut cm = test_class_manager();
+        assert!(matches!(area(&mut cm), Ok(Some(Slot::Int(4)))));
+        let square = cm.get_or_resolve_class("Abstracts$Square").unwrap().id();
+        let Some(LoadedClass::Loaded(class)) = cm.get_mut_class_by_id(square) else {
+            panic!("Abstracts$Square is not loaded");
+        };
+        class.flags |= ClassAccessFlags::Abstract;
+        assert_eq!(
+            java_exception(area(&mut cm)),
+            Some("java/lang/InstantiationError")
+        );
+
+        let mut cm = test_class_manager();
+        let square = cm.get_or_resolve_class("Abstracts$Square").unwrap().id();
+        let Some(LoadedClass::Loaded(class)) = cm.get_mut_class_by_id(square) else {
+            panic!("Abstracts$Square is not loaded");
+        };
+        for method in class.methods.iter_mut().filter(|m| m.name == "area") {
+            method.flags |= reader::base::classfile::MethodAccessFlags::Abstract;
+        }
+        assert_eq!(
+            java_exception(area(&mut cm)),
+            Some("java/lang/AbstractMethodError")
+        );
+    }
+
+    #[test]
+    fn member_access() {
+        let mut cm = test_class_manager();
+        let mut id = |name: &str| cm.get_or_resolve_class(name).unwrap().id();
+        let object = id("java/lang/Object");
+        let string = id("java/lang/String");
+        let greeter = id("LoudGreeter");
+        let outer = id("Nestmates");
+        let inner = id("Nestmates$Inner");
+
+        let accessible =
+            |accessor, declaring, access| cm.is_member_accessible(accessor, declaring, access);
+        assert!(accessible(greeter, string, MemberAccess::Public));
+        // From a subclass, or from the same package.
+        assert!(accessible(greeter, object, MemberAccess::Protected));
+        assert!(accessible(string, object, MemberAccess::Protected));
+        assert!(!accessible(object, greeter, MemberAccess::Protected));
+        assert!(accessible(greeter, outer, MemberAccess::Package));
+        assert!(!accessible(greeter, object, MemberAccess::Package));
+        // From the nestmates only.
+        assert!(accessible(inner, outer, MemberAccess::Private));
+        assert!(accessible(outer, inner, MemberAccess::Private));
+        assert!(!accessible(greeter, outer, MemberAccess::Private));
+    }
+
+    #[test]
+    fn nestmate_private_access() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("Nestmates").unwrap();
+        assert_eq!(static_int(&cm, "Nestmates", "outer"), Some(42));
+        assert_eq!(static_int(&cm, "Nestmates", "inner"), Some(42));
+
+        // Without its NestHost attribute, the inner class is not a nestmate anymore.
+        let mut cm = test_class_manager();
+        cm.add_transformer(Box::new(|name: &str, bytes: &[u8]| {
+            let pattern = b"NestHost";
+            let index = bytes.windows(pattern.len()).position(|w| w == pattern)?;
+            (name == "Nestmates$Inner").then(|| {
+                let mut bytes = bytes.to_vec();
+                bytes[index] = b'X';
+                bytes
+            })
+        }));
+        let err = cm.get_or_resolve_class("Nestmates").unwrap_err();
+        assert!(
+            err.to_string().contains("IllegalAccessError"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn inner_classes() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("Nesting").unwrap();
+        assert_eq!(static_int(&cm, "Nesting", "declaring"), Some(1));
+        assert_eq!(static_int(&cm, "Nesting", "declaredCount"), Some(3));
+        assert_eq!(static_int(&cm, "Nesting", "localDeclaring"), Some(1));
+        assert_eq!(static_int(&cm, "Nesting", "localEnclosing"), Some(1));
+        assert_eq!(static_int(&cm, "Nesting", "localName"), Some(1));
+        // private static abstract interface
+        assert_eq!(static_int(&cm, "Nesting", "hiddenModifiers"), Some(0x060a));
+        assert_eq!(static_int(&cm, "Nesting", "methodEnclosing"), Some(1));
+
+        let member = cm.get_or_resolve_class("Nesting$Member").unwrap().id();
+        let Some(LoadedClass::Loaded(member)) = cm.get_class_by_id(member) else {
+            panic!("Nesting$Member is not loaded");
+        };
+        assert_eq!(member.simple_name(), "Member");
+        assert_eq!(member.enclosing_class(), Some("Nesting"));
+        assert!(member.enclosing_method().is_none());
+
+        let anonymous = cm.get_or_resolve_class("Nesting$1").unwrap().id();
+        let Some(LoadedClass::Loaded(anonymous)) = cm.get_class_by_id(anonymous) else {
+            panic!("Nesting$1 is not loaded");
+        };
+        assert_eq!(anonymous.simple_name(), "");
+        assert_eq!(anonymous.declaring_class(), None);
+        assert_eq!(anonymous.enclosing_class(), Some("Nesting"));
+    }
+
+    #[test]
+    fn frameless_accessors() {
+        let mut cm = test_class_manager();
+        cm.opcode_histogram = Some(crate::opcode_stats::OpcodeHistogram::new());
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("Accessors").unwrap() else {
+            panic!("Accessors not loaded");
+        };
+        let class_id = class.id;
+        let run = class.methods.iter().position(|m| m.name == "run").unwrap();
+        let value = cm.run_method(class_id, run, vec![]).unwrap();
+        assert!(matches!(value, Some(Slot::Int(575))));
+        // Only the constructor sets a field in its frame.
+        let histogram = cm.opcode_histogram.as_ref().unwrap();
+        assert_eq!(histogram.count(0xb4), 0);
+        assert_eq!(histogram.count(0xb5), 1);
+    }
+
+    #[test]
+    fn accessor_inline_cache() {
+        let mut cm = test_class_manager();
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("Accessors").unwrap() else {
+            panic!("Accessors not loaded");
+        };
+        let class_id = class.id;
+        let method = |name: &str| class.methods.iter().position(|m| m.name == name).unwrap();
+        let (receivers, get_value) = (method("receivers"), method("getValue"));
+        let hits = |cm: &ClassManager| {
+            let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+                panic!("Accessors not loaded");
+            };
+            let code = class.methods[get_value].get_code().unwrap();
+            code.cache.accessor_cache().hits()
+        };
+        // The second call on the same receiver hits, the call on the subclass
+        // then replaces the cached field.
+        let value = cm.run_method(class_id, receivers, vec![]).unwrap();
+        assert!(matches!(value, Some(Slot::Int(433))));
+        assert_eq!(hits(&cm), 1);
+        let value = cm.run_method(class_id, receivers, vec![]).unwrap();
+        assert!(matches!(value, Some(Slot::Int(433))));
+        assert_eq!(hits(&cm), 2);
+    }
+
+    #[test]
+    fn narrowed_accessors() {
+        let mut cm = test_class_manager();
+        // Remove the casts of the arguments of the setters: iload_0; i2b/i2c; invokevirtual
+        cm.add_transformer(Box::new(|name: &str, bytes: &[u8]| {
+            (name == "NarrowedAccessors").then(|| {
+                let mut bytes = bytes.to_vec();
+                for index in 0..bytes.len() - 2 {
+                    if let [0x1a, 0x91 | 0x92, 0xb6] = bytes[index..index + 3] {
+                        bytes[index + 1] = 0x00;
+                    }
+                }
+                bytes
+            })
+        }));
+        let class = cm.get_or_resolve_class("NarrowedAccessors").unwrap().id();
+        let mut call = |name: &str, value: i32| {
+            let Some(LoadedClass::Loaded(accessors)) = cm.get_class_by_id(class) else {
+                panic!("NarrowedAccessors is not loaded");
+            };
+            let method = accessors.methods.iter().position(|m| m.name == name);
+            cm.run_method(class, method.unwrap(), vec![Slot::Int(value)])
+        };
+        // The setters without a frame narrow as putfield does.
+        for (name, expected) in [
+            ("narrowSmall", 0x45),
+            ("narrowStoredSmall", 0x45),
+            ("narrowLetter", 0x2345),
+        ] {
+            let result = call(name, 0x12345);
+            assert!(
+                matches!(result, Ok(Some(Slot::Int(value))) if value == expected),
+                "{}: {:?}",
+                name,
+                result
+            );
+        }
+        let result = call("narrowSmall", 0xff);
+        assert!(matches!(result, Ok(Some(Slot::Int(-1)))), "{:?}", result);
+    }
+
+    #[test]
+    fn mistyped_accessors() {
+        let mut cm = test_class_manager();
+        // The getter of an int returns a float.
+        cm.add_transformer(Box::new(|name: &str, bytes: &[u8]| {
+            (name == "Accessors").then(|| {
+                let mut bytes = bytes.to_vec();
+                let getter = bytes
+                    .windows(5)
+                    .position(|window| window[..2] == [0x2a, 0xb4] && window[4] == 0xac)
+                    .unwrap();
+                bytes[getter + 4] = 0xae;
+                bytes
+            })
+        }));
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("Accessors").unwrap() else {
+            panic!("Accessors not loaded");
+        };
+        let class_id = class.id;
+        let run = class.methods.iter().position(|m| m.name == "run").unwrap();
+        // The getter runs in a frame, its return instruction failing.
+        let result = cm.run_method(class_id, run, vec![]);
+        let Err(ExecutionError::InstructionExecutionError {
+            source: InstructionError::Verify { context },
+        }) = result
+        else {
+            panic!("unexpected result: {:?}", result);
+        };
+        assert!(
+            context.starts_with("freturn in Accessors.getValue()I"),
+            "{}",
+            context
+        );
+    }
+
+    #[test]
+    fn class_file_transformers() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let mut cm = test_class_manager();
+        // `return value * 2` becomes `return value * 3`.
+        cm.add_transformer(Box::new(|name: &str, bytes: &[u8]| {
+            let pattern = [0x1a, 0x05, 0x68, 0xac];
+            let index = bytes.windows(4).position(|window| window == pattern);
+            index.filter(|_| name == "Greeter").map(|index| {
+                let mut bytes = bytes.to_vec();
+                bytes[index + 1] = 0x06;
+                bytes
+            })
+        }));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        cm.add_transformer(Box::new(move |_: &str, _: &[u8]| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            None
+        }));
+
+        let LoadedClass::Loaded(class) = cm.get_or_resolve_class("Greeter").unwrap() else {
+            panic!("Greeter not loaded");
+        };
+        let class_id = class.id;
+        let twice = class
+            .methods
+            .iter()
+            .position(|m| m.name == "twice")
+            .unwrap();
+        let value = cm.run_method(class_id, twice, vec![Slot::Int(5)]).unwrap();
+        assert!(matches!(value, Some(Slot::Int(15))));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn reflect_arrays() {
+        let mut cm = test_class_manager();
+        cm.get_or_resolve_class("ReflectArrays").unwrap();
+        assert_eq!(static_int(&cm, "ReflectArrays", "length"), Some(3));
+        assert_eq!(static_int(&cm, "ReflectArrays", "stored"), Some(1));
+        assert_eq!(static_int(&cm, "ReflectArrays", "boxed"), Some(5));
+        assert_eq!(static_int(&cm, "ReflectArrays", "widened"), Some(7));
+        assert_eq!(static_int(&cm, "ReflectArrays", "nested"), Some(22));
+        assert_eq!(static_int(&cm, "ReflectArrays", "primitive"), Some(4));
+        let int = cm.get_primitive_class_object("int").unwrap().unwrap();
+        assert_eq!(cm.primitive_of_mirror(&int), Some("int"));
+        assert_eq!(cm.class_of_mirror(&int), None);
+        assert!(cm.get_primitive_class_object("Integer").unwrap().is_none());
+    }
+
+    #[test]
+    fn constant_pool_iteration() {
+        let mut cm = test_class_manager();
+        let class = cm.get_or_resolve_class("ConstantValues").unwrap();
+        let LoadedClass::Loaded(class) = class else {
+            panic!("ConstantValues should be loaded");
+        };
+        let constants: Vec<_> = class.constant_pool.iter().collect();
+        assert!(constants.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        for (index, entry) in constants.iter() {
+            assert!(std::ptr::eq(
+                class.constant_pool.get(*index).unwrap(),
+                *entry
+            ));
+        }
+        let long_index = constants
+            .iter()
+            .find_map(|(index, entry)| match entry {
+                RtConstantPoolEntry::LongConstant(value) if *value == 1 << 40 => Some(*index),
+                _ => None,
+            })
+            .expect("the long constant should be listed");
+        // The index following a long has no entry, like the UTF-8 strings.
+        assert!(class.constant_pool.get(long_index + 1).is_none());
+        assert!(constants.iter().any(|(_, entry)| matches!(
+            entry,
+            RtConstantPoolEntry::StringReference(string) if string.value == "hello"
+        )));
+    }
+
+    #[test]
+    fn memoized_subtype_checks() {
+        let mut cm = test_class_manager();
+        let loud = cm.get_or_resolve_class("LoudGreeter").unwrap().id();
+        let greeter = cm.id_of_class("Greeter").unwrap();
+        cm.subtype_cache.invalidate();
+
+        assert!(types::is_subtype_of(&cm, loud, greeter));
+        assert!(!types::is_subtype_of(&cm, greeter, loud));
+        assert_eq!(cm.subtype_cache.get(loud, greeter), Some(true));
+        assert_eq!(cm.subtype_cache.get(greeter, loud), Some(false));
+        // Same answers from the cache.
+        assert!(types::is_subtype_of(&cm, loud, greeter));
+        assert!(!types::is_subtype_of(&cm, greeter, loud));
+        assert_eq!(cm.subtype_cache.len(), 2);
+
+        cm.unload_class(loud);
+        assert!(cm.subtype_cache.is_empty());
+    }
+
+    #[test]
+    fn unloaded_classes() {
+        let mut cm = test_class_manager();
+        let class = cm.get_or_resolve_class("FinalParent").unwrap().id();
+        let mirror = cm.get_class_object(class).unwrap();
+        assert_eq!(cm.class_of_mirror(&mirror), Some(class));
+
+        assert!(cm.unload_class(class));
+        assert!(cm.get_class_by_id(class).is_none());
+        assert_eq!(cm.id_of_class("FinalParent"), None);
+        assert_eq!(cm.unloaded_class_count(), 1);
+        assert!(!cm.unload_class(class));
+        assert_eq!(cm.unloaded_class_count(), 1);
+
+        // The mirror outlives its class, which is loaded again by its name.
+        assert_eq!(cm.class_of_mirror(&mirror), None);
+        let reloaded = cm.get_or_resolve_class("FinalParent").unwrap().id();
+        assert_ne!(reloaded, class);
+        assert!(!std::ptr::eq::<Object>(
+            &*cm.get_class_object(reloaded).unwrap(),
+            &*mirror
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+
+use crate::{alloc::AllocationCount, class::ClassId};
+
+/// Memory statistics of a loaded class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassStat {
+    pub class_id: ClassId,
+    pub name: String,
+    /// Estimated size (in bytes) of an instance.
+    pub instance_size: usize,
+    /// Estimated size (in bytes) of the static fields.
+    pub static_size: usize,
+    /// Instances allocated since the start of the VM.
+    pub allocations: AllocationCount,
+}
+
+/// Memory statistics of the loaded classes, see [crate::Vm::class_stats].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassStats {
+    /// The loaded classes, the ones allocating the most bytes first.
+    pub classes: Vec<ClassStat>,
+    /// Arrays allocated since the start of the VM.
+    pub arrays: AllocationCount,
+}
+
+impl ClassStats {
+    /// Get the number of loaded classes.
+    pub fn loaded_classes(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Get the statistics of a class by its name.
+    pub fn get(&self, name: &str) -> Option<&ClassStat> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+
+    /// Get the total size (in bytes) of the static fields of the loaded classes.
+    pub fn static_bytes(&self) -> usize {
+        self.classes.iter().map(|class| class.static_size).sum()
+    }
+
+    /// Get all the allocations, objects and arrays.
+    pub fn total_allocations(&self) -> AllocationCount {
+        let mut total = self.arrays;
+        for class in &self.classes {
+            total.count += class.allocations.count;
+            total.bytes += class.allocations.bytes;
+        }
+        total
+    }
+}
+
+impl fmt::Display for ClassStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<40}{:>12}{:>12}{:>12}{:>14}",
+            "class", "size (B)", "static (B)", "instances", "allocated (B)"
+        )?;
+        for class in &self.classes {
+            writeln!(
+                f,
+                "{:<40}{:>12}{:>12}{:>12}{:>14}",
+                class.name,
+                class.instance_size,
+                class.static_size,
+                class.allocations.count,
+                class.allocations.bytes
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<40}{:>12}{:>12}{:>12}{:>14}",
+            "(arrays)", "", "", self.arrays.count, self.arrays.bytes
+        )?;
+        let total = self.total_allocations();
+        write!(
+            f,
+            "{:<40}{:>12}{:>12}{:>12}{:>14}",
+            format!("total ({} classes)", self.loaded_classes()),
+            "",
+            self.static_bytes(),
+            total.count,
+            total.bytes
+        )
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt;
+
+use flagset::FlagSet;
+use reader::base::classfile::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+
+use crate::{
+    class::{Class, ClassId},
+    class_manager::ClassManager,
+    value::type_descriptor,
+};
+
+/// Description of a loaded class, see [crate::Vm::describe_class].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassSummary {
+    pub class_id: ClassId,
+    /// Binary name of the class, e.g. `java/lang/String`.
+    pub name: String,
+    /// Binary name of the superclass, None for java/lang/Object and the interfaces.
+    pub superclass: Option<String>,
+    /// Binary names of the direct superinterfaces, in declaration order.
+    pub interfaces: Vec<String>,
+    pub flags: FlagSet<ClassAccessFlags>,
+    /// Major version of the classfile of the class.
+    pub major_version: u16,
+    /// The fields declared by the class, in declaration order.
+    pub fields: Vec<FieldSummary>,
+    /// The methods declared by the class, in declaration order.
+    pub methods: Vec<MethodSummary>,
+    /// Number of entries of the runtime constant pool.
+    pub constants: usize,
+}
+
+/// Description of a field declared by a class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSummary {
+    pub name: String,
+    /// Descriptor of the field, e.g. `I` or `Ljava/lang/String;`.
+    pub descriptor: String,
+    pub flags: FlagSet<FieldAccessFlags>,
+}
+
+/// Description of a method declared by a class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodSummary {
+    pub name: String,
+    /// Descriptor of the method, e.g. `(I)V`.
+    pub descriptor: String,
+    /// Descriptor of the method in Java syntax, e.g. `(int) -> void`.
+    pub signature: String,
+    pub flags: FlagSet<MethodAccessFlags>,
+}
+
+impl ClassSummary {
+    /// Describe a loaded class, the classes it references being resolved by id.
+    pub fn of(cm: &ClassManager, class: &Class) -> Self {
+        let class_name = |class_id: ClassId| match cm.get_class_by_id(class_id) {
+            Some(class) => class.name().to_string(),
+            None => format!("ClassId({})", class_id.0),
+        };
+        Self {
+            class_id: class.id,
+            name: class.name.clone(),
+            superclass: class.superclass.map(class_name),
+            interfaces: class.interfaces.iter().copied().map(class_name).collect(),
+            flags: class.flags,
+            major_version: class.major_version,
+            fields: class
+                .fields
+                .iter()
+                .map(|field| FieldSummary {
+                    name: field.name.clone(),
+                    descriptor: type_descriptor(field.descriptor.field_type()),
+                    flags: field.flags,
+                })
+                .collect(),
+            methods: class
+                .methods
+                .iter()
+                .map(|method| MethodSummary {
+                    name: method.name.clone(),
+                    descriptor: method.descriptor.as_str().to_string(),
+                    signature: method.descriptor.java_syntax(),
+                    flags: method.flags,
+                })
+                .collect(),
+            constants: class.constant_pool.iter().count(),
+        }
+    }
+
+    /// Get a field by its name.
+    pub fn field(&self, name: &str) -> Option<&FieldSummary> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Get the overloads of a method by its name.
+    pub fn methods_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a MethodSummary> + 'a {
+        self.methods
+            .iter()
+            .filter(move |method| method.name == name)
+    }
+}
+
+/// Display the class in the manner of `javap`, e.g.
+///
+/// ```text
+/// public class Point extends java.lang.Object (version 52, 18 constants)
+///   private x: I
+///   public getX: ()I  // () -> int
+/// ```
+///
+/// The members are listed with their modifiers, name and descriptor, the
+/// methods being followed by their descriptor in Java syntax.
+impl fmt::Display for ClassSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.flags.contains(ClassAccessFlags::Interface) {
+            "interface"
+        } else {
+            "class"
+        };
+        write!(
+            f,
+            "{}{} {}",
+            modifiers(self.flags, &CLASS_MODIFIERS),
+            kind,
+            self.name.replace('/', ".")
+        )?;
+        if let Some(superclass) = &self.superclass {
+            write!(f, " extends {}", superclass.replace('/', "."))?;
+        }
+        if !self.interfaces.is_empty() {
+            let interfaces: Vec<String> = self
+                .interfaces
+                .iter()
+                .map(|name| name.replace('/', "."))
+                .collect();
+            write!(f, " implements {}", interfaces.join(", "))?;
+        }
+        write!(
+            f,
+            " (version {}, {} constants)",
+            self.major_version, self.constants
+        )?;
+        for field in &self.fields {
+            write!(
+                f,
+                "\n  {}{}: {}",
+                modifiers(field.flags, &FIELD_MODIFIERS),
+                field.name,
+                field.descriptor
+            )?;
+        }
+        for method in &self.methods {
+            write!(
+                f,
+                "\n  {}{}: {}  // {}",
+                modifiers(method.flags, &METHOD_MODIFIERS),
+                method.name,
+                method.descriptor,
+                method.signature
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The modifiers of the classes, in the order of the Java sources.
+const CLASS_MODIFIERS: [(ClassAccessFlags, &str); 3] = [
+    (ClassAccessFlags::Public, "public"),
+    (ClassAccessFlags::Abstract, "abstract"),
+    (ClassAccessFlags::Final, "final"),
+];
+
+const FIELD_MODIFIERS: [(FieldAccessFlags, &str); 7] = [
+    (FieldAccessFlags::Public, "public"),
+    (FieldAccessFlags::Protected, "protected"),
+    (FieldAccessFlags::Private, "private"),
+    (FieldAccessFlags::Static, "static"),
+    (FieldAccessFlags::Final, "final"),
+    (FieldAccessFlags::Transient, "transient"),
+    (FieldAccessFlags::Volatile, "volatile"),
+];
+
+const METHOD_MODIFIERS: [(MethodAccessFlags, &str); 8] = [
+    (MethodAccessFlags::Public, "public"),
+    (MethodAccessFlags::Protected, "protected"),
+    (MethodAccessFlags::Private, "private"),
+    (MethodAccessFlags::Abstract, "abstract"),
+    (MethodAccessFlags::Static, "static"),
+    (MethodAccessFlags::Final, "final"),
+    (MethodAccessFlags::Synchronized, "synchronized"),
+    (MethodAccessFlags::Native, "native"),
+];
+
+/// Get the modifiers set by the flags, each followed by a space.
+fn modifiers<F: flagset::Flags + Copy>(flags: FlagSet<F>, names: &[(F, &str)]) -> String {
+    names
+        .iter()
+        .filter(|(flag, _)| flags.contains(*flag))
+        .map(|(_, name)| format!("{} ", name))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{test_util::test_vm, VmError};
+
+    #[test]
+    fn class_summaries() {
+        let mut vm = test_vm();
+        let summary = vm.describe_class("LoudGreeter").unwrap();
+        assert_eq!(summary.name, "LoudGreeter");
+        assert_eq!(summary.superclass.as_deref(), Some("java/lang/Object"));
+        assert_eq!(summary.interfaces, ["Greeter"]);
+        assert!(summary.constants > 0);
+        assert_eq!(
+            summary.methods_named("name").next().unwrap().descriptor,
+            "()I"
+        );
+
+        let summary = vm.describe_class("Accessors").unwrap();
+        let field = summary.field("name").unwrap();
+        assert_eq!(field.descriptor, "Ljava/lang/String;");
+        let described = summary.to_string();
+        assert!(described.starts_with("public class Accessors extends java.lang.Object (version "));
+        assert!(described.contains("\n  private final name: Ljava/lang/String;"));
+        assert!(described.contains("\n  static run: ()I  // () -> int"));
+
+        assert!(matches!(
+            vm.describe_class("Missing"),
+            Err(VmError::ClassLoading { .. })
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::class_loader::ClassLoadingError;
+
+/// Oldest supported class file major version (Java 1.1).
+pub const MIN_MAJOR_VERSION: u16 = 45;
+
+/// Newest class file major version supported by default (Java 21).
+pub const MAX_MAJOR_VERSION: u16 = 65;
+
+/// Minor version of the class files compiled with preview features enabled.
+pub const PREVIEW_MINOR_VERSION: u16 = 0xFFFF;
+
+/// Offset between a Java release number and its class file major version.
+const RELEASE_TO_MAJOR_OFFSET: u16 = 44;
+
+/// The class file versions accepted by the VM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassVersionPolicy {
+    /// Newest accepted major version.
+    pub max_major: u16,
+    /// Whether class files depending on preview features (minor version 0xFFFF)
+    /// of the newest accepted release are accepted.
+    pub enable_preview: bool,
+}
+
+impl Default for ClassVersionPolicy {
+    fn default() -> Self {
+        Self {
+            max_major: MAX_MAJOR_VERSION,
+            enable_preview: false,
+        }
+    }
+}
+
+impl ClassVersionPolicy {
+    /// Accept class files up to the given Java release (e.g. 17 for major version 61).
+    pub fn for_release(release: u16) -> Self {
+        Self {
+            max_major: release.saturating_add(RELEASE_TO_MAJOR_OFFSET),
+            enable_preview: false,
+        }
+    }
+
+    /// The newest accepted Java release (e.g. 17 for major version 61).
+    pub fn release(&self) -> u16 {
+        self.max_major.saturating_sub(RELEASE_TO_MAJOR_OFFSET)
+    }
+
+    /// Check if a class file version is accepted.
+    ///
+    /// Preview class files are only accepted if preview features are enabled
+    /// and they target exactly the newest accepted release.
+    pub fn is_supported(&self, major: u16, minor: u16) -> bool {
+        if !(MIN_MAJOR_VERSION..=self.max_major).contains(&major) {
+            return false;
+        }
+        // Before Java 12, minor versions were freely used.
+        if major < 56 || minor == 0 {
+            return true;
+        }
+        minor == PREVIEW_MINOR_VERSION && self.enable_preview && major == self.max_major
+    }
+
+    /// Check the version of a class file, returning an UnsupportedClassVersionError if rejected.
+    pub fn check(&self, class_name: &str, major: u16, minor: u16) -> Result<(), ClassLoadingError> {
+        if self.is_supported(major, minor) {
+            return Ok(());
+        }
+        Err(ClassLoadingError::UnsupportedClassVersion {
+            class_name: class_name.to_string(),
+            major,
+            minor,
+            max_major: self.max_major,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn version_range() {
+        let policy = ClassVersionPolicy::for_release(17);
+        assert_eq!(policy.max_major, 61);
+        assert_eq!(policy.release(), 17);
+        assert!(policy.is_supported(52, 0));
+        assert!(policy.is_supported(61, 0));
+        assert!(!policy.is_supported(65, 0));
+        assert!(!policy.is_supported(44, 0));
+        assert!(!policy.is_supported(61, PREVIEW_MINOR_VERSION));
+        assert!(!policy.is_supported(61, 3));
+
+        let preview = ClassVersionPolicy {
+            enable_preview: true,
+            ..policy
+        };
+        assert!(preview.is_supported(61, PREVIEW_MINOR_VERSION));
+        assert!(!preview.is_supported(60, PREVIEW_MINOR_VERSION));
+    }
+}
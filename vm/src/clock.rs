@@ -0,0 +1,70 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Start of the simulated time of a [Clock::Fixed], 2000-01-01T00:00:00Z.
+const FIXED_EPOCH_MILLIS: i64 = 946_684_800_000;
+
+/// Time elapsed in a [Clock::Fixed] at each reading, 1ms.
+const FIXED_STEP_NANOS: i64 = 1_000_000;
+
+/// Source of the time of `System.currentTimeMillis` and `System.nanoTime`.
+#[derive(Debug, Clone)]
+pub enum Clock {
+    /// The time of the host.
+    System {
+        /// Origin of `System.nanoTime`.
+        start: Instant,
+    },
+    /// A simulated time, independent of the host to make the runs reproducible.
+    ///
+    /// It starts at a fixed date and advances by a fixed step at each reading,
+    /// so the programs waiting for some time to pass still terminate.
+    Fixed {
+        /// Nanoseconds elapsed since the start.
+        elapsed: i64,
+    },
+}
+
+impl Clock {
+    /// Create a clock giving the time of the host.
+    pub fn system() -> Self {
+        Clock::System {
+            start: Instant::now(),
+        }
+    }
+
+    /// Create a simulated clock.
+    pub fn fixed() -> Self {
+        Clock::Fixed { elapsed: 0 }
+    }
+
+    /// Milliseconds since the UNIX epoch, as `System.currentTimeMillis`.
+    pub fn current_time_millis(&mut self) -> i64 {
+        match self {
+            Clock::System { .. } => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as i64)
+                .unwrap_or(0),
+            Clock::Fixed { elapsed } => {
+                *elapsed += FIXED_STEP_NANOS;
+                FIXED_EPOCH_MILLIS + *elapsed / 1_000_000
+            }
+        }
+    }
+
+    /// Nanoseconds since an arbitrary origin, as `System.nanoTime`.
+    pub fn nano_time(&mut self) -> i64 {
+        match self {
+            Clock::System { start } => start.elapsed().as_nanos() as i64,
+            Clock::Fixed { elapsed } => {
+                *elapsed += FIXED_STEP_NANOS;
+                *elapsed
+            }
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::system()
+    }
+}
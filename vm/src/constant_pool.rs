@@ -1,7 +1,10 @@
+use std::cell::OnceCell;
 use std::char;
+use std::collections::HashMap;
+use std::io::Cursor;
 
-use dumpster::sync::Gc;
 use dumpster::Collectable;
+use reader::base::attribute_info::{BootstrapMethod, BootstrapMethodsAttribute};
 use reader::base::constant_pool::ConstantPoolEntry as ClassfileConstantPoolEntry;
 use reader::base::constant_pool::ConstantPoolInfo as ClassfileConstantPoolInfo;
 use reader::base::constant_pool::ReferenceKind;
@@ -14,16 +17,15 @@ use reader::descriptor::FieldDescriptor;
 use reader::descriptor::FieldType;
 use reader::descriptor::MethodDescriptor;
 use reader::descriptor::UnqualifiedName;
+use reader::BinRead;
 use snafu::{ResultExt, Snafu};
 
-use crate::alloc::Array;
-use crate::alloc::CharArray;
-use crate::alloc::Object;
 use crate::alloc::ObjectRef;
 use crate::class::ClassId;
 use crate::class_loader::ClassLoadingError;
 use crate::class_manager::ClassManager;
 use crate::class_manager::LoadedClass;
+use crate::descriptor_table::InternedMethodDescriptor;
 use crate::opcode::InstructionError;
 use crate::thread::Slot;
 
@@ -31,22 +33,65 @@ use crate::thread::Slot;
 #[derive(Debug, Clone)]
 pub struct ConstantPool {
     /// A mapping from the constant pool index to the index of the corresponding
-    /// entry in the `entries` vector.
+    /// entry in the `entries` vector, [NO_ENTRY] if there is none.
     ///
     /// Note that the index 0 is not used, as the constant pool index starts at
     /// 1.
     pub mappings: Vec<usize>,
     pub entries: Vec<ConstantPoolEntry>,
+    /// The tag names of the class file constants not kept in `entries`, by
+    /// constant pool index.
+    ignored: HashMap<usize, &'static str>,
 }
 
+/// What a constant pool index refers to, see [ConstantPool::kind].
+#[derive(Debug, Clone, Copy)]
+pub enum RuntimeConstantKind<'a> {
+    /// An entry of the runtime constant pool.
+    Entry(&'a ConstantPoolEntry),
+    /// A class file constant only used while loading the class, with the name of
+    /// its tag, e.g. `CONSTANT_Utf8`.
+    Ignored(&'static str),
+    /// No constant at all: the index 0, the index following a long or a double,
+    /// or an index out of the constant pool.
+    Unusable,
+}
+
+/// Mapping of the constant pool indexes without an entry in the runtime constant
+/// pool: the index 0, the index following a long or a double, and the entries
+/// only used while loading the class (e.g. the UTF-8 strings).
+pub const NO_ENTRY: usize = usize::MAX;
+
 impl ConstantPool {
     pub fn new(entries: Vec<ConstantPoolEntry>) -> Self {
         Self {
-            mappings: vec![0],
+            mappings: vec![NO_ENTRY],
             entries,
+            ignored: HashMap::new(),
         }
     }
 
+    /// Get what the given constant pool index refers to.
+    pub fn kind(&self, index: usize) -> RuntimeConstantKind<'_> {
+        if let Some(entry) = self.get(index) {
+            return RuntimeConstantKind::Entry(entry);
+        }
+        match self.ignored.get(&index) {
+            Some(tag) => RuntimeConstantKind::Ignored(tag),
+            None => RuntimeConstantKind::Unusable,
+        }
+    }
+
+    /// Iterate over the entries with their constant pool index, in order.
+    ///
+    /// The indexes without an entry are skipped, see [NO_ENTRY].
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &ConstantPoolEntry)> + '_ {
+        self.mappings
+            .iter()
+            .enumerate()
+            .filter_map(|(index, map)| Some((index, self.entries.get(*map)?)))
+    }
+
     pub fn get(&self, index: usize) -> Option<&ConstantPoolEntry> {
         if index == 0 || index >= self.mappings.len() {
             return None;
@@ -87,9 +132,76 @@ impl ConstantPool {
         }
     }
 
-    fn append(&mut self, entry: ConstantPoolEntry) {
+    /// Get the field reference at the given index.
+    pub fn field_ref(&self, index: usize) -> Option<FieldRefView<'_>> {
+        match self.get(index)? {
+            ConstantPoolEntry::FieldReference {
+                field_name,
+                field_descriptor,
+                implementor,
+            } => Some(FieldRefView {
+                name: field_name,
+                descriptor: field_descriptor,
+                implementor: *implementor,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Get the method reference (of a class, not an interface) at the given index.
+    pub fn method_ref(&self, index: usize) -> Option<MethodRefView<'_>> {
+        self.any_method_ref(index).filter(|view| !view.interface)
+    }
+
+    /// Get the interface method reference at the given index.
+    pub fn interface_method_ref(&self, index: usize) -> Option<MethodRefView<'_>> {
+        self.any_method_ref(index).filter(|view| view.interface)
+    }
+
+    /// Get the method reference at the given index, either of a class or of an interface.
+    ///
+    /// Since Java 8, `invokestatic` and `invokespecial` accept both.
+    pub fn any_method_ref(&self, index: usize) -> Option<MethodRefView<'_>> {
+        let (method_name, method_descriptor, implementor, interface) = match self.get(index)? {
+            ConstantPoolEntry::MethodReference {
+                method_name,
+                method_descriptor,
+                implementor,
+            } => (method_name, method_descriptor, implementor, false),
+            ConstantPoolEntry::InterfaceMethodReference {
+                method_name,
+                method_descriptor,
+                implementor,
+            } => (method_name, method_descriptor, implementor, true),
+            _ => return None,
+        };
+        Some(MethodRefView {
+            name: method_name,
+            descriptor: method_descriptor,
+            implementor: *implementor,
+            interface,
+        })
+    }
+
+    /// Get the class referenced at the given index.
+    pub fn class_ref(&self, index: usize) -> Option<ClassId> {
+        match self.get(index)? {
+            ConstantPoolEntry::ClassReference(class_id) => Some(*class_id),
+            _ => None,
+        }
+    }
+
+    /// Append an entry, returning its constant pool index.
+    pub(crate) fn append(&mut self, entry: ConstantPoolEntry) -> usize {
         self.entries.push(entry);
         self.mappings.push(self.entries.len() - 1);
+        self.mappings.len() - 1
+    }
+
+    /// Skip a class file constant not kept in the runtime constant pool.
+    pub(crate) fn ignore(&mut self, tag: &'static str) {
+        self.ignored.insert(self.mappings.len(), tag);
+        self.mappings.push(NO_ENTRY);
     }
 
     pub fn from_classfile(
@@ -97,6 +209,7 @@ impl ConstantPool {
         classfile: &ClassFile,
     ) -> Result<Self, ConstantPoolError> {
         let classfile_cp = classfile.constant_pool();
+        let bootstrap_methods = bootstrap_methods(classfile)?;
         let mut cp = ConstantPool::new(vec![]);
         for entry in classfile_cp.inner() {
             if let ClassfileConstantPoolEntry::Entry(ref entry) = entry {
@@ -119,29 +232,10 @@ impl ConstantPool {
                             .ok_or_else(|| ConstantPoolError::InvalidUtf8StringReference {
                                 index: info.string_index as usize,
                             })?;
-                        let char_array = CharArray::from_string(&string.to_string());
-                        let obj = match cm.get_class_by_name("java/lang/String") {
-                            Some(LoadedClass::Loaded(class)) => {
-                                let id = class.id.clone();
-                                Object::new_with_classmanager(cm, id)
-                            }
-                            Some(LoadedClass::Resolved(class)) => {
-                                Object::new_with_classfile(class.class_id, &class.classfile)
-                            }
-                            Some(LoadedClass::Loading(class)) => Object::new_with_classfile(
-                                class.class_id,
-                                class.classfile.as_ref().expect("unreachable!"),
-                            ),
-                            None => {
-                                unreachable!("java/lang/String class not loaded");
-                            }
-                        };
-                        let obj =
-                            obj.map_err(|err| ConstantPoolError::StringObjectCreationFailure {
-                                context: err.to_string(),
-                            })?;
-                        obj.set_field(0, Slot::ArrayReference(Gc::new(Array::Char(char_array))));
-                        cp.append(ConstantPoolEntry::StringReference(Gc::new(obj)));
+                        cp.append(ConstantPoolEntry::StringReference(StringConstant {
+                            value: string.into_owned(),
+                            object: OnceCell::new(),
+                        }));
                     }
                     ClassfileConstantPoolInfo::FieldRefInfo(info) => {
                         let class_name = classfile_cp
@@ -163,15 +257,14 @@ impl ConstantPool {
                                     context: Some(format!("FieldRefInfo (name: {}, descriptor: {}) at index {}", field_name, field_descriptor, info.name_and_type_index as usize))
                                 }
                             })?;
-                        let descriptor =
-                            descriptor::parse_field_descriptor(&field_descriptor.to_owned())
-                                .map_err(|err| ConstantPoolError::InvalidDescriptor {
-                                    index: info.name_and_type_index as usize,
-                                    source: err,
-                                })?;
+                        let descriptor = descriptor::parse_field_descriptor(&field_descriptor)
+                            .map_err(|err| ConstantPoolError::InvalidDescriptor {
+                                index: info.name_and_type_index as usize,
+                                source: err,
+                            })?;
 
                         cp.append(ConstantPoolEntry::FieldReference {
-                            field_name: field_name.to_string(),
+                            field_name: field_name.into_owned(),
                             field_descriptor: descriptor,
                             implementor,
                         });
@@ -197,14 +290,14 @@ impl ConstantPool {
                                 }
                             })?;
                         let descriptor =
-                            descriptor::parse_method_descriptor(&&method_descriptor.to_owned())
+                            cm.intern_method_descriptor(&method_descriptor)
                                 .map_err(|err| ConstantPoolError::InvalidDescriptor {
                                     index: info.name_and_type_index as usize,
                                     source: err,
                                 })?;
 
                         cp.append(ConstantPoolEntry::MethodReference {
-                            method_name: method_name.to_string(),
+                            method_name: method_name.into_owned(),
                             method_descriptor: descriptor,
                             implementor,
                         });
@@ -230,14 +323,14 @@ impl ConstantPool {
                                 }
                             })?;
                         let descriptor =
-                            descriptor::parse_method_descriptor(&&method_descriptor.to_owned())
+                            cm.intern_method_descriptor(&method_descriptor)
                                 .map_err(|err| ConstantPoolError::InvalidDescriptor {
                                     index: info.name_and_type_index as usize,
                                     source: err,
                                 })?;
 
                         cp.append(ConstantPoolEntry::InterfaceMethodReference {
-                            method_name: method_name.to_string(),
+                            method_name: method_name.into_owned(),
                             method_descriptor: descriptor,
                             implementor,
                         });
@@ -278,35 +371,153 @@ impl ConstantPool {
                         ));
                     }
                     ClassfileConstantPoolInfo::MethodTypeInfo(info) => {
-                        let descriptor = descriptor::parse_method_descriptor(
-                            &classfile_cp
-                                .get_utf8_string(info.descriptor_index as usize)
-                                .unwrap(),
-                        )
-                        .map_err(|err| {
-                            ConstantPoolError::InvalidDescriptor {
+                        let descriptor = cm
+                            .intern_method_descriptor(
+                                &classfile_cp
+                                    .get_utf8_string(info.descriptor_index as usize)
+                                    .ok_or(ConstantPoolError::InvalidUtf8StringReference {
+                                        index: info.descriptor_index as usize,
+                                    })?,
+                            )
+                            .map_err(|err| ConstantPoolError::InvalidDescriptor {
                                 index: info.descriptor_index as usize,
                                 source: err,
-                            }
-                        })?;
+                            })?;
                         cp.append(ConstantPoolEntry::MethodType(descriptor));
                     }
 
-                    // TODO: Implement DynamicConstant and DynamicCallSite.
+                    ClassfileConstantPoolInfo::DynamicInfo(info) => {
+                        let index = info.bootstrap_method_attr_index as usize;
+                        let bootstrap_method = bootstrap_methods
+                            .get(index)
+                            .ok_or(ConstantPoolError::InvalidBootstrapMethodReference { index })?;
+                        let (name, descriptor) = classfile_cp
+                            .get_name_and_type(info.name_and_type_index as usize)
+                            .ok_or(ConstantPoolError::InvalidConstantReference {
+                                index: info.name_and_type_index as usize,
+                            })?;
+                        let descriptor =
+                            descriptor::parse_field_descriptor(&descriptor).map_err(|err| {
+                                ConstantPoolError::InvalidDescriptor {
+                                    index: info.name_and_type_index as usize,
+                                    source: err,
+                                }
+                            })?;
+                        cp.append(ConstantPoolEntry::DynamicConstant(DynamicConstant {
+                            method_handle: bootstrap_method.bootstrap_method_ref as usize,
+                            arguments_ref: bootstrap_method
+                                .bootstrap_arguments
+                                .iter()
+                                .map(|index| *index as usize)
+                                .collect(),
+                            name: UnqualifiedName::new(&name),
+                            descriptor,
+                            value: OnceCell::new(),
+                        }));
+                    }
+
+                    ClassfileConstantPoolInfo::InvokeDynamicInfo(info) => {
+                        let index = info.bootstrap_method_attr_index as usize;
+                        let bootstrap_method = bootstrap_methods
+                            .get(index)
+                            .ok_or(ConstantPoolError::InvalidBootstrapMethodReference { index })?;
+                        let (name, descriptor) = classfile_cp
+                            .get_name_and_type(info.name_and_type_index as usize)
+                            .ok_or(ConstantPoolError::InvalidConstantReference {
+                                index: info.name_and_type_index as usize,
+                            })?;
+                        let descriptor =
+                            descriptor::parse_method_descriptor(&descriptor).map_err(|err| {
+                                ConstantPoolError::InvalidDescriptor {
+                                    index: info.name_and_type_index as usize,
+                                    source: err,
+                                }
+                            })?;
+                        cp.append(ConstantPoolEntry::DynamicCCallSite(DynamicCallSite {
+                            method_handle: bootstrap_method.bootstrap_method_ref as usize,
+                            arguments_ref: bootstrap_method
+                                .bootstrap_arguments
+                                .iter()
+                                .map(|index| *index as usize)
+                                .collect(),
+                            name: UnqualifiedName::new(&name),
+                            descriptor,
+                            target: OnceCell::new(),
+                        }));
+                    }
+
                     _ => {
                         log::trace!("Constant pool entry not necessary or unimplemented, ignored in RtConstantPool: {:?}", entry);
-                        cp.mappings.push(0);
+                        cp.ignore(entry.tag_name());
                     }
                 }
             } else {
                 // Tombstone, this entry is not used.
-                cp.mappings.push(0);
+                cp.mappings.push(NO_ENTRY);
             }
         }
         Ok(cp)
     }
 }
 
+/// Get the java/lang/String object of the string constant at the given index
+/// of the constant pool of a class, creating it on its first resolution.
+///
+/// The string constants are shared by all the classes (JLS 3.10.5), so equal
+/// literals are the same object.
+pub fn resolve_string_constant(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    index: usize,
+) -> Result<ObjectRef, InstructionError> {
+    let constant = string_constant(cm, class_id, index)?;
+    if let Some(obj) = constant.object.get() {
+        return Ok(obj.clone());
+    }
+    let value = constant.value.clone();
+    let obj = cm
+        .intern_string(&value)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: "java/lang/String".into(),
+            source: Box::new(err),
+        })?;
+    let constant = string_constant(cm, class_id, index)?;
+    Ok(constant.object.get_or_init(|| obj).clone())
+}
+
+fn string_constant(
+    cm: &ClassManager,
+    class_id: ClassId,
+    index: usize,
+) -> Result<&StringConstant, InstructionError> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found (or not loaded): ClassId({})", class_id.0),
+        });
+    };
+    match class.constant_pool.get(index) {
+        Some(ConstantPoolEntry::StringReference(constant)) => Ok(constant),
+        _ => Err(InstructionError::InvalidState {
+            context: format!("Invalid string constant at index {}", index),
+        }),
+    }
+}
+
+/// Read the BootstrapMethods attribute of a class file, if any.
+fn bootstrap_methods(classfile: &ClassFile) -> Result<Vec<BootstrapMethod>, ConstantPoolError> {
+    let classfile_cp = classfile.constant_pool();
+    for attribute in classfile.attributes() {
+        let name = classfile_cp.get_utf8_string(attribute.attribute_name_index as usize);
+        if name.as_deref() == Some("BootstrapMethods") {
+            let mut reader = Cursor::new(attribute.info.as_slice());
+            let attribute = BootstrapMethodsAttribute::read(&mut reader)
+                .map_err(|_| ConstantPoolError::InvalidBootstrapMethodsAttribute)?;
+            return Ok(attribute.bootstrap_methods);
+        }
+    }
+    Ok(Vec::new())
+}
+
 #[derive(Debug, Snafu)]
 pub enum ConstantPoolError {
     #[snafu(display("Invalid UTF-8 string reference, entry index: {}", index))]
@@ -327,8 +538,14 @@ pub enum ConstantPoolError {
     #[snafu(display("Invalid classname reference, entry index: {}", index))]
     InvalidClassNameReference { index: usize },
 
-    #[snafu(display("String object creation failed: {}", context))]
-    StringObjectCreationFailure { context: String },
+    #[snafu(display(
+        "Invalid bootstrap method reference, bootstrap method index: {}",
+        index
+    ))]
+    InvalidBootstrapMethodReference { index: usize },
+
+    #[snafu(display("Invalid BootstrapMethods attribute"))]
+    InvalidBootstrapMethodsAttribute,
 
     #[snafu(display("Loading failure of a class/interface reference, name: {}, context: {}", class_name, context.as_ref().unwrap_or(&"<unknown>".to_string())))]
     ClassLoadingFailure {
@@ -344,7 +561,7 @@ pub enum ConstantPoolEntry {
     FloatConstant(f32),
     LongConstant(i64),
     DoubleConstant(f64),
-    StringReference(ObjectRef),
+    StringReference(StringConstant),
     // TODO: Implement the rest of the constant pool entries, in particular
     // the symbolic references (class, field, method, interface method, ...).
     FieldReference {
@@ -354,12 +571,12 @@ pub enum ConstantPoolEntry {
     },
     MethodReference {
         method_name: String,
-        method_descriptor: MethodDescriptor,
+        method_descriptor: InternedMethodDescriptor,
         implementor: ClassId,
     },
     InterfaceMethodReference {
         method_name: String,
-        method_descriptor: MethodDescriptor,
+        method_descriptor: InternedMethodDescriptor,
         implementor: ClassId,
     },
     ClassReference(ClassId),
@@ -370,20 +587,91 @@ pub enum ConstantPoolEntry {
     /// field/method reference in the constant pool.
     MethodHandleReference(ReferenceKind, usize),
     /// A reference to a method type.
-    MethodType(MethodDescriptor),
+    MethodType(InternedMethodDescriptor),
     /// A reference to a dynamic constant.
     DynamicConstant(DynamicConstant),
     /// A reference to a dynamically-computed call site.
     DynamicCCallSite(DynamicCallSite),
 }
 
+impl ConstantPoolEntry {
+    /// Get the name of the tag of the class file constant of this entry, e.g.
+    /// `CONSTANT_Integer`.
+    pub fn tag_name(&self) -> &'static str {
+        match self {
+            ConstantPoolEntry::IntegerConstant(_) => "CONSTANT_Integer",
+            ConstantPoolEntry::FloatConstant(_) => "CONSTANT_Float",
+            ConstantPoolEntry::LongConstant(_) => "CONSTANT_Long",
+            ConstantPoolEntry::DoubleConstant(_) => "CONSTANT_Double",
+            ConstantPoolEntry::StringReference(_) => "CONSTANT_String",
+            ConstantPoolEntry::FieldReference { .. } => "CONSTANT_Fieldref",
+            ConstantPoolEntry::MethodReference { .. } => "CONSTANT_Methodref",
+            ConstantPoolEntry::InterfaceMethodReference { .. } => "CONSTANT_InterfaceMethodref",
+            ConstantPoolEntry::ClassReference(_) | ConstantPoolEntry::ArrayReference(_) => {
+                "CONSTANT_Class"
+            }
+            ConstantPoolEntry::MethodHandleReference(..) => "CONSTANT_MethodHandle",
+            ConstantPoolEntry::MethodType(_) => "CONSTANT_MethodType",
+            ConstantPoolEntry::DynamicConstant(_) => "CONSTANT_Dynamic",
+            ConstantPoolEntry::DynamicCCallSite(_) => "CONSTANT_InvokeDynamic",
+        }
+    }
+
+    /// Whether the entry can be loaded on the operand stack by the `ldc` family
+    /// instructions (JVMS 5.1).
+    pub fn is_loadable(&self) -> bool {
+        !matches!(
+            self,
+            ConstantPoolEntry::FieldReference { .. }
+                | ConstantPoolEntry::MethodReference { .. }
+                | ConstantPoolEntry::InterfaceMethodReference { .. }
+                | ConstantPoolEntry::DynamicCCallSite(_)
+        )
+    }
+}
+
+/// Typed view of a field reference of the constant pool.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldRefView<'a> {
+    pub name: &'a str,
+    pub descriptor: &'a FieldDescriptor,
+    /// The class named by the reference, not necessarily the one declaring the field.
+    pub implementor: ClassId,
+}
+
+/// Typed view of a method or interface method reference of the constant pool.
+#[derive(Debug, Clone, Copy)]
+pub struct MethodRefView<'a> {
+    pub name: &'a str,
+    pub descriptor: &'a InternedMethodDescriptor,
+    /// The class named by the reference, not necessarily the one declaring the method.
+    pub implementor: ClassId,
+    /// Whether this is an InterfaceMethodRef.
+    pub interface: bool,
+}
+
+/// Representation of a string constant.
+///
+/// Only the content of the string is kept when the class is loaded, its
+/// java/lang/String object is created on the first `ldc` of the constant.
+#[derive(Debug, Clone)]
+pub struct StringConstant {
+    pub value: String,
+    /// The interned java/lang/String object, once resolved.
+    pub object: OnceCell<ObjectRef>,
+}
+
 /// Representation of a symbolic reference to a dynamic constant.
 #[derive(Debug, Clone)]
 pub struct DynamicConstant {
+    /// Index of the method handle of the bootstrap method in the constant pool.
     pub method_handle: usize,
+    /// Indexes of the static arguments of the bootstrap method in the constant pool.
     pub arguments_ref: Vec<usize>,
     pub name: UnqualifiedName,
     pub descriptor: FieldDescriptor,
+    /// The value produced by the bootstrap method, once resolved.
+    pub value: OnceCell<Slot>,
 }
 
 /// Representation of a symbolic reference to a dynamically-computed call site.
@@ -393,4 +681,9 @@ pub struct DynamicCallSite {
     pub arguments_ref: Vec<usize>,
     pub name: UnqualifiedName,
     pub descriptor: MethodDescriptor,
+    /// The class of the objects produced by the call site, once linked.
+    ///
+    /// Only the call sites of the lambda metafactory are supported, see
+    /// [crate::lambda].
+    pub target: OnceCell<ClassId>,
 }
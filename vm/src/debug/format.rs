@@ -0,0 +1,382 @@
+use std::fmt;
+
+use reader::{base::constant_pool::ReferenceKind, descriptor::MethodDescriptor};
+
+use crate::{
+    alloc::{read_string, Array, Object},
+    class::ClassId,
+    class_manager::{ClassManager, LoadedClass},
+    constant_pool::ConstantPoolEntry,
+    thread::{Frame, Slot},
+    value::{type_descriptor, type_name},
+};
+
+/// Display of a value with its type, e.g. `int 3`, `java.lang.String "hi"`,
+/// `long[4]` or `null`.
+///
+/// The alternate format (`{:#}`) also displays the fields of the objects, see
+/// [display_object].
+#[derive(Clone, Copy)]
+pub struct SlotDisplay<'a> {
+    cm: &'a ClassManager,
+    slot: &'a Slot,
+}
+
+/// Display a value with its type, the classes of the references being resolved by name.
+pub fn display_slot<'a>(cm: &'a ClassManager, slot: &'a Slot) -> SlotDisplay<'a> {
+    SlotDisplay { cm, slot }
+}
+
+impl fmt::Display for SlotDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.slot {
+            Slot::Tombstone => write!(f, "top"),
+            Slot::Int(value) => write!(f, "int {}", value),
+            Slot::Long(value) => write!(f, "long {}", value),
+            Slot::Float(value) => write!(f, "float {:?}", value),
+            Slot::Double(value) => write!(f, "double {:?}", value),
+            Slot::ReturnAddress(pc) => write!(f, "returnAddress {}", pc),
+            Slot::UndefinedReference => write!(f, "null"),
+            Slot::ObjectReference(obj) => {
+                let class_name = class_name(self.cm, obj.class_id());
+                match read_string(obj).filter(|_| class_name == "java.lang.String") {
+                    Some(value) => write!(f, "{} {:?}", class_name, value),
+                    None if f.alternate() => write!(f, "{}", display_object(self.cm, obj)),
+                    None => write!(f, "{}", class_name),
+                }
+            }
+            Slot::ArrayReference(array) => {
+                let item_name = match array.as_ref() {
+                    Array::Int(_) => "int".to_string(),
+                    Array::Long(_) => "long".to_string(),
+                    Array::Float(_) => "float".to_string(),
+                    Array::Double(_) => "double".to_string(),
+                    Array::Byte(_) => "byte".to_string(),
+                    Array::Char(_) => "char".to_string(),
+                    Array::Short(_) => "short".to_string(),
+                    Array::Boolean(_) => "boolean".to_string(),
+                    Array::ObjectRef(array) => class_name(self.cm, array.class_id()),
+                    Array::ArrayRef(array) => format!("{}[]", type_name(array.item_type().item())),
+                };
+                // The length is the one of the first dimension, e.g. `int[3][]`.
+                let (item_name, dimensions) =
+                    item_name.split_at(item_name.find('[').unwrap_or(item_name.len()));
+                write!(f, "{}[{}]{}", item_name, array.len(), dimensions)
+            }
+        }
+    }
+}
+
+/// Display of an object with its instance fields, e.g.
+/// `Point { x: int 3, label: java.lang.String "origin" }`.
+///
+/// The fields are named after the declarations of the class of the object and
+/// its superclasses, the ones of the superclasses first. The objects they
+/// reference are only displayed by class name, the references may be cyclic.
+#[derive(Clone, Copy)]
+pub struct ObjectDisplay<'a> {
+    cm: &'a ClassManager,
+    object: &'a Object,
+}
+
+/// Display an object with its instance fields, the classes being resolved by id.
+pub fn display_object<'a>(cm: &'a ClassManager, object: &'a Object) -> ObjectDisplay<'a> {
+    ObjectDisplay { cm, object }
+}
+
+impl fmt::Display for ObjectDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut hierarchy = vec![];
+        let mut class_id = Some(self.object.class_id());
+        while let Some(id) = class_id {
+            let Some(LoadedClass::Loaded(class)) = self.cm.get_class_by_id(id) else {
+                // The layout is unknown, the fields cannot be named.
+                return write!(
+                    f,
+                    "{} {{ .. }}",
+                    class_name(self.cm, self.object.class_id())
+                );
+            };
+            hierarchy.push(class);
+            class_id = class.superclass;
+        }
+        let fields: Vec<String> = hierarchy
+            .iter()
+            .rev()
+            .flat_map(|class| {
+                class
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, field)| !field.is_static())
+                    .map(|(index, field)| (field, class.field_offset + index))
+            })
+            .map(|(field, index)| match self.object.get_field(index) {
+                Some(value) => format!("{}: {}", field.name, display_slot(self.cm, &value)),
+                None => format!("{}: ?", field.name),
+            })
+            .collect();
+        let class_name = class_name(self.cm, self.object.class_id());
+        if fields.is_empty() {
+            write!(f, "{} {{}}", class_name)
+        } else {
+            write!(f, "{} {{ {} }}", class_name, fields.join(", "))
+        }
+    }
+}
+
+/// Display of a frame: the method with the location of the execution, followed
+/// by a line for each local variable and the operand stack.
+#[derive(Clone, Copy)]
+pub struct FrameDisplay<'a> {
+    cm: &'a ClassManager,
+    frame: &'a Frame,
+    pc: usize,
+}
+
+/// Display a frame, the pc being the one of the next instruction to execute in it.
+///
+/// The local variables are named after the LocalVariableTable of the method, if any.
+pub fn display_frame<'a>(cm: &'a ClassManager, frame: &'a Frame, pc: usize) -> FrameDisplay<'a> {
+    FrameDisplay { cm, frame, pc }
+}
+
+impl fmt::Display for FrameDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class_name = class_name(self.cm, self.frame.class);
+        let method = match self.cm.get_class_by_id(self.frame.class) {
+            Some(LoadedClass::Loaded(class)) => class.get_method_by_index(self.frame.method),
+            _ => None,
+        };
+        match method {
+            Some(method) => write!(
+                f,
+                "{}.{}{}",
+                class_name,
+                method.name,
+                method.descriptor.as_str()
+            )?,
+            None => write!(f, "{}#{}", class_name, self.frame.method)?,
+        }
+        write!(f, " (pc {}", self.pc)?;
+        let line = method
+            .and_then(|method| method.get_code())
+            .and_then(|code| code.line_number(self.pc));
+        if let Some(line) = line {
+            write!(f, ", line {}", line)?;
+        }
+        writeln!(f, ")")?;
+        for local in local_variables(self.cm, self.frame, self.pc) {
+            writeln!(f, "  {}", local)?;
+        }
+        let stack: Vec<String> = self
+            .frame
+            .operand_stack
+            .iter()
+            .map(|slot| format!("{:#}", display_slot(self.cm, slot)))
+            .collect();
+        write!(f, "  stack: [{}]", stack.join(", "))
+    }
+}
+
+/// Describe the local variables of a frame holding a value, e.g. `1 count = int 3`.
+///
+/// The objects are displayed with their fields, e.g. `0 this = Point { x: int 3 }`.
+pub(crate) fn local_variables(cm: &ClassManager, frame: &Frame, pc: usize) -> Vec<String> {
+    let code = match cm.get_class_by_id(frame.class) {
+        Some(LoadedClass::Loaded(class)) => class
+            .get_method_by_index(frame.method)
+            .and_then(|method| method.get_code()),
+        _ => None,
+    };
+    frame
+        .local_variables
+        .iter()
+        .enumerate()
+        // The second half of a long or double, or a variable not assigned yet.
+        .filter(|(_, slot)| !matches!(slot, Slot::Tombstone))
+        .map(
+            |(index, slot)| match code.and_then(|code| code.local_variable(index, pc)) {
+                Some(variable) => {
+                    format!("{} {} = {:#}", index, variable.name, display_slot(cm, slot))
+                }
+                None => format!("{} = {:#}", index, display_slot(cm, slot)),
+            },
+        )
+        .collect()
+}
+
+/// Display of a constant pool entry, in the manner of `javap -v`, e.g.
+/// `Methodref java.io.PrintStream.println:(I)V`.
+#[derive(Clone, Copy)]
+pub struct ConstantDisplay<'a> {
+    cm: &'a ClassManager,
+    constant: &'a ConstantPoolEntry,
+}
+
+/// Display a constant pool entry, the classes it references being resolved by name.
+pub fn display_constant<'a>(
+    cm: &'a ClassManager,
+    constant: &'a ConstantPoolEntry,
+) -> ConstantDisplay<'a> {
+    ConstantDisplay { cm, constant }
+}
+
+impl fmt::Display for ConstantDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.constant {
+            ConstantPoolEntry::IntegerConstant(value) => write!(f, "Integer {}", value),
+            ConstantPoolEntry::FloatConstant(value) => write!(f, "Float {:?}f", value),
+            ConstantPoolEntry::LongConstant(value) => write!(f, "Long {}l", value),
+            ConstantPoolEntry::DoubleConstant(value) => write!(f, "Double {:?}d", value),
+            ConstantPoolEntry::StringReference(string) => write!(f, "String {:?}", string.value),
+            ConstantPoolEntry::FieldReference {
+                field_name,
+                field_descriptor,
+                implementor,
+            } => write!(
+                f,
+                "Fieldref {}.{}:{}",
+                class_name(self.cm, *implementor),
+                field_name,
+                type_descriptor(field_descriptor.field_type())
+            ),
+            ConstantPoolEntry::MethodReference {
+                method_name,
+                method_descriptor,
+                implementor,
+            } => write!(
+                f,
+                "Methodref {}.{}:{}",
+                class_name(self.cm, *implementor),
+                method_name,
+                method_descriptor.as_str()
+            ),
+            ConstantPoolEntry::InterfaceMethodReference {
+                method_name,
+                method_descriptor,
+                implementor,
+            } => write!(
+                f,
+                "InterfaceMethodref {}.{}:{}",
+                class_name(self.cm, *implementor),
+                method_name,
+                method_descriptor.as_str()
+            ),
+            ConstantPoolEntry::ClassReference(class_id) => {
+                write!(f, "Class {}", class_name(self.cm, *class_id))
+            }
+            ConstantPoolEntry::ArrayReference(array_type) => {
+                write!(f, "Class {}", type_name(array_type))
+            }
+            ConstantPoolEntry::MethodHandleReference(kind, index) => {
+                write!(f, "MethodHandle {} #{}", reference_kind_name(kind), index)
+            }
+            ConstantPoolEntry::MethodType(descriptor) => {
+                write!(f, "MethodType {}", descriptor.as_str())
+            }
+            ConstantPoolEntry::DynamicConstant(constant) => write!(
+                f,
+                "Dynamic {}:{} (bootstrap #{})",
+                constant.name,
+                type_descriptor(constant.descriptor.field_type()),
+                constant.method_handle
+            ),
+            ConstantPoolEntry::DynamicCCallSite(call_site) => write!(
+                f,
+                "InvokeDynamic {}:{} (bootstrap #{})",
+                call_site.name,
+                method_descriptor(&call_site.descriptor),
+                call_site.method_handle
+            ),
+        }
+    }
+}
+
+/// Get the Java name of a class, e.g. `java.lang.String`.
+fn class_name(cm: &ClassManager, class_id: ClassId) -> String {
+    match cm.get_class_by_id(class_id) {
+        Some(class) => class.name().replace('/', "."),
+        None => format!("ClassId({})", class_id.0),
+    }
+}
+
+fn method_descriptor(descriptor: &MethodDescriptor) -> String {
+    let parameters: String = descriptor.parameters.iter().map(type_descriptor).collect();
+    let return_type = descriptor
+        .return_type
+        .as_ref()
+        .map_or_else(|| "V".to_string(), type_descriptor);
+    format!("({}){}", parameters, return_type)
+}
+
+/// Get the name of a kind of method handle, as in the JVM specification (§5.4.3.5).
+fn reference_kind_name(kind: &ReferenceKind) -> &'static str {
+    match kind {
+        ReferenceKind::GetField => "REF_getField",
+        ReferenceKind::GetStatic => "REF_getStatic",
+        ReferenceKind::PutField => "REF_putField",
+        ReferenceKind::PutStatic => "REF_putStatic",
+        ReferenceKind::InvokeVirtual => "REF_invokeVirtual",
+        ReferenceKind::InvokeStatic => "REF_invokeStatic",
+        ReferenceKind::InvokeSpecial => "REF_invokeSpecial",
+        ReferenceKind::NewInvokeSpecial => "REF_newInvokeSpecial",
+        ReferenceKind::InvokeInterface => "REF_invokeInterface",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reader::descriptor;
+
+    use super::*;
+    use crate::{
+        alloc::{IntArray, ObjectRefArray},
+        test_util::test_class_manager,
+    };
+
+    #[test]
+    fn pretty_printing() {
+        let mut cm = test_class_manager();
+        let ints = Slot::ArrayReference(cm.alloc_array(IntArray::new(4).into()));
+        let objects =
+            Slot::ArrayReference(cm.alloc_array(ObjectRefArray::new(ClassId(42), 2).into()));
+        let slot = |slot: &Slot| display_slot(&cm, slot).to_string();
+        assert_eq!(slot(&Slot::Int(3)), "int 3");
+        assert_eq!(slot(&Slot::Double(2.0)), "double 2.0");
+        assert_eq!(slot(&Slot::UndefinedReference), "null");
+        assert_eq!(slot(&ints), "int[4]");
+        assert_eq!(slot(&objects), "ClassId(42)[2]");
+
+        let mut frame = Frame::new(ClassId(42), 1, 3, 2);
+        frame.set_local_variable(0, Slot::Long(7));
+        frame.set_local_variable(2, Slot::Float(1.5));
+        frame.operand_stack.push(Slot::Int(1));
+        assert_eq!(
+            display_frame(&cm, &frame, 8).to_string(),
+            "ClassId(42)#1 (pc 8)\n  0 = long 7\n  2 = float 1.5\n  stack: [int 1]"
+        );
+
+        let class_id = cm.get_or_resolve_class("Accessors").unwrap().id();
+        let object = Object::new_with_classmanager(&mut cm, class_id).unwrap();
+        object.set_field_by_name(&cm, "value", Slot::Int(5));
+        object.set_field_by_name(&cm, "big", Slot::Long(7));
+        let object = Slot::ObjectReference(cm.alloc_object(object));
+        assert_eq!(display_slot(&cm, &object).to_string(), "Accessors");
+        assert_eq!(
+            format!("{:#}", display_slot(&cm, &object)),
+            "Accessors { value: int 5, big: long 7, name: null }"
+        );
+
+        let array_type = descriptor::parse_field_descriptor("[[J")
+            .unwrap()
+            .field_type()
+            .clone();
+        let constant = |constant: &ConstantPoolEntry| display_constant(&cm, constant).to_string();
+        assert_eq!(constant(&ConstantPoolEntry::LongConstant(5)), "Long 5l");
+        assert_eq!(
+            constant(&ConstantPoolEntry::ArrayReference(array_type)),
+            "Class long[][]"
+        );
+    }
+}
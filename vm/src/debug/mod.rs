@@ -0,0 +1,16 @@
+//! Inspection of the state of the VM.
+//!
+//! The formatters are always available, for the logs, the thread dumps and the
+//! error messages. The read-only views of the frames of the threads, meant for
+//! debugger front-ends, need the `debug-api` feature.
+
+pub(crate) mod format;
+#[cfg(feature = "debug-api")]
+mod view;
+
+pub use format::{
+    display_constant, display_frame, display_object, display_slot, ConstantDisplay, FrameDisplay,
+    ObjectDisplay, SlotDisplay,
+};
+#[cfg(feature = "debug-api")]
+pub use view::{DebugValue, FrameView};
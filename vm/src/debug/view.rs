@@ -0,0 +1,191 @@
+use crate::{
+    alloc::{ArrayRef, ObjectRef},
+    class::{ClassId, Method},
+    class_manager::{ClassManager, LoadedClass},
+    thread::{Frame, Slot, Thread},
+};
+
+/// A value of a local variable or of the operand stack, as seen by a debugger.
+#[derive(Debug, Clone)]
+pub enum DebugValue {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// The address pushed by `jsr` and `jsr_w`.
+    ReturnAddress(u32),
+    Object(ObjectRef),
+    Array(ArrayRef),
+    Null,
+}
+
+impl DebugValue {
+    /// Get the value of a slot, None if it holds no value (the second half of
+    /// a long or double, or an internal detail of the VM).
+    pub fn from_slot(slot: &Slot) -> Option<Self> {
+        match slot {
+            Slot::Int(value) => Some(DebugValue::Int(*value)),
+            Slot::Long(value) => Some(DebugValue::Long(*value)),
+            Slot::Float(value) => Some(DebugValue::Float(*value)),
+            Slot::Double(value) => Some(DebugValue::Double(*value)),
+            Slot::ReturnAddress(address) => Some(DebugValue::ReturnAddress(*address)),
+            Slot::ObjectReference(obj) => Some(DebugValue::Object(obj.clone())),
+            Slot::ArrayReference(array) => Some(DebugValue::Array(array.clone())),
+            Slot::UndefinedReference => Some(DebugValue::Null),
+            Slot::Tombstone => None,
+        }
+    }
+}
+
+/// Read-only view of a frame of a thread.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameView<'a> {
+    frame: &'a Frame,
+    depth: usize,
+}
+
+impl<'a> FrameView<'a> {
+    /// Depth of the frame in the stack, 0 for the current frame.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Whether this is the frame being executed.
+    pub fn is_current(&self) -> bool {
+        self.depth == 0
+    }
+
+    pub fn class_id(&self) -> ClassId {
+        self.frame.class
+    }
+
+    /// Index of the method in the methods of its class.
+    pub fn method_index(&self) -> usize {
+        self.frame.method
+    }
+
+    /// The pc of the next instruction to execute in this frame.
+    ///
+    /// For the caller frames, this is the pc of the pending invocation.
+    pub fn pc(&self) -> usize {
+        self.frame.pc
+    }
+
+    /// Get the binary name of the class of the method.
+    pub fn class_name<'c>(&self, cm: &'c ClassManager) -> Option<&'c str> {
+        cm.get_class_by_id(self.frame.class)
+            .map(|class| class.name())
+    }
+
+    /// Get the method executed in this frame.
+    pub fn method<'c>(&self, cm: &'c ClassManager) -> Option<&'c Method> {
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(self.frame.class) else {
+            return None;
+        };
+        class.get_method_by_index(self.frame.method)
+    }
+
+    /// Get the source line of the instruction being executed, or of the pending
+    /// invocation for the callers, if the method has a LineNumberTable.
+    pub fn line(&self, cm: &ClassManager) -> Option<u16> {
+        self.method(cm)?.get_code()?.line_number(self.frame.pc)
+    }
+
+    /// Number of local variables of the frame, the long and double ones counting twice.
+    pub fn local_count(&self) -> usize {
+        self.frame.local_variables.len()
+    }
+
+    /// Get the value of a local variable, None if it has no value.
+    pub fn local(&self, index: usize) -> Option<DebugValue> {
+        DebugValue::from_slot(self.frame.local_variables.get(index)?)
+    }
+
+    /// Iterate over the local variables holding a value, with their index.
+    pub fn locals(&self) -> impl Iterator<Item = (usize, DebugValue)> + 'a {
+        self.frame
+            .local_variables
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, DebugValue::from_slot(slot)?)))
+    }
+
+    /// Get the values of the operand stack, from the bottom to the top.
+    pub fn operand_stack(&self) -> Vec<DebugValue> {
+        self.frame
+            .operand_stack
+            .iter()
+            .filter_map(DebugValue::from_slot)
+            .collect()
+    }
+
+    /// Get the value on top of the operand stack.
+    pub fn peek(&self) -> Option<DebugValue> {
+        self.frame
+            .operand_stack
+            .iter()
+            .rev()
+            .find_map(DebugValue::from_slot)
+    }
+}
+
+impl Thread {
+    /// Number of frames of the thread.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Iterate over the frames of the thread, the current one first.
+    pub fn frames(&self) -> impl Iterator<Item = FrameView<'_>> {
+        self.stack
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(depth, frame)| FrameView { frame, depth })
+    }
+
+    /// Get the frame at the given depth, 0 being the current frame.
+    pub fn frame(&self, depth: usize) -> Option<FrameView<'_>> {
+        self.frames().nth(depth)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frame_views() {
+        let mut thread = Thread::new();
+        let mut caller = Frame::new(ClassId(1), 2, 2, 2);
+        caller.set_local_variable(0, Slot::Long(7));
+        caller.set_local_variable(1, Slot::Tombstone);
+        caller.operand_stack.push(Slot::Int(3));
+        caller.pc = 12;
+        let mut callee = Frame::new(ClassId(4), 0, 2, 1);
+        callee.set_local_variable(1, Slot::UndefinedReference);
+        callee.operand_stack.push(Slot::Float(1.5));
+        callee.pc = 5;
+        thread.stack.push(caller);
+        thread.stack.push(callee);
+
+        let frames: Vec<_> = thread.frames().collect();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].is_current());
+        assert_eq!(frames[0].class_id(), ClassId(4));
+        assert_eq!(frames[0].pc(), 5);
+        assert!(frames[0].local(0).is_none());
+        assert!(matches!(frames[0].local(1), Some(DebugValue::Null)));
+        assert!(matches!(frames[0].peek(), Some(DebugValue::Float(value)) if value == 1.5));
+
+        let caller = thread.frame(1).unwrap();
+        assert_eq!(
+            (caller.depth(), caller.method_index(), caller.pc()),
+            (1, 2, 12)
+        );
+        let locals: Vec<_> = caller.locals().collect();
+        assert!(matches!(locals[..], [(0, DebugValue::Long(7))]));
+        assert!(matches!(caller.operand_stack()[..], [DebugValue::Int(3)]));
+        assert!(thread.frame(2).is_none());
+    }
+}
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, hash::Hash, ops::Deref, sync::Arc};
+
+use reader::descriptor::{self, DescriptorError, MethodDescriptor};
+
+/// Method descriptor interned in a [MethodDescriptorTable].
+///
+/// Cloning is cheap, and comparisons are made by id: two interned descriptors
+/// are equal if they come from the same entry of the table.
+#[derive(Debug, Clone)]
+pub struct InternedMethodDescriptor {
+    id: usize,
+    raw: Arc<str>,
+    descriptor: Arc<MethodDescriptor>,
+}
+
+impl InternedMethodDescriptor {
+    /// Get the id of the descriptor in its table
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Get the string representation of the descriptor
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Get the parsed descriptor
+    pub fn descriptor(&self) -> &MethodDescriptor {
+        &self.descriptor
+    }
+}
+
+impl PartialEq for InternedMethodDescriptor {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for InternedMethodDescriptor {}
+
+impl Hash for InternedMethodDescriptor {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl Deref for InternedMethodDescriptor {
+    type Target = MethodDescriptor;
+
+    fn deref(&self) -> &Self::Target {
+        &self.descriptor
+    }
+}
+
+/// Intern table of the method descriptors, keyed by their string representation.
+///
+/// Each descriptor is parsed only once, whatever the number of classes and
+/// constant pool entries referencing it.
+#[derive(Debug, Default)]
+pub struct MethodDescriptorTable {
+    entries: HashMap<String, InternedMethodDescriptor>,
+}
+
+impl MethodDescriptorTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Intern a method descriptor, parsing it if it has never been seen before.
+    pub fn intern(
+        &mut self,
+        descriptor: &str,
+    ) -> Result<InternedMethodDescriptor, DescriptorError> {
+        if let Some(interned) = self.entries.get(descriptor) {
+            return Ok(interned.clone());
+        }
+        let parsed = descriptor::parse_method_descriptor(descriptor)?;
+        let interned = InternedMethodDescriptor {
+            id: self.entries.len(),
+            raw: Arc::from(descriptor),
+            descriptor: Arc::new(parsed),
+        };
+        self.entries
+            .insert(descriptor.to_string(), interned.clone());
+        Ok(interned)
+    }
+
+    /// Get an already interned method descriptor.
+    pub fn get(&self, descriptor: &str) -> Option<&InternedMethodDescriptor> {
+        self.entries.get(descriptor)
+    }
+
+    /// Number of interned descriptors.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn intern_once() {
+        let mut table = MethodDescriptorTable::new();
+        let a = table.intern("(IJ)V").unwrap();
+        let b = table.intern("(IJ)V").unwrap();
+        let c = table.intern("()V").unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.args_count(), 2);
+        assert_eq!(table.len(), 2);
+        assert!(table.intern("(I").is_err());
+    }
+}
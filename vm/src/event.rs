@@ -0,0 +1,286 @@
+use std::{
+    fmt::{self, Debug, Write as _},
+    io::Write,
+};
+
+/// Events of the VM execution, reported to the [EventSink] of the class manager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmEvent {
+    /// A class has been loaded, and is about to be initialized.
+    ClassLoaded { class_name: String, class_id: usize },
+
+    /// A thread has been stopped by a Java exception it did not catch.
+    UncaughtException {
+        thread: usize,
+        thread_name: String,
+        /// Binary name of the exception class, e.g. `java/lang/NullPointerException`.
+        exception: String,
+        message: String,
+    },
+
+    /// A thread has been stopped by an error of the VM itself while
+    /// executing a method (unsupported instruction, invalid class file...).
+    InvocationError {
+        thread: usize,
+        thread_name: String,
+        /// The method being executed, as `class.name(descriptor)`, if known.
+        method: Option<String>,
+        message: String,
+    },
+
+    /// The weak references whose referent is no longer reachable have been cleared.
+    WeakRefsCollected { cleared: usize, live: usize },
+}
+
+impl VmEvent {
+    /// Name of the event, the `event` field of its JSON representation.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VmEvent::ClassLoaded { .. } => "class_loaded",
+            VmEvent::UncaughtException { .. } => "uncaught_exception",
+            VmEvent::InvocationError { .. } => "invocation_error",
+            VmEvent::WeakRefsCollected { .. } => "weak_refs_collected",
+        }
+    }
+
+    /// Format the event as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let mut json = JsonObject::new();
+        json.string("event", self.name());
+        match self {
+            VmEvent::ClassLoaded {
+                class_name,
+                class_id,
+            } => {
+                json.string("class_name", class_name);
+                json.number("class_id", class_id);
+            }
+            VmEvent::UncaughtException {
+                thread,
+                thread_name,
+                exception,
+                message,
+            } => {
+                json.number("thread", thread);
+                json.string("thread_name", thread_name);
+                json.string("exception", exception);
+                json.string("message", message);
+            }
+            VmEvent::InvocationError {
+                thread,
+                thread_name,
+                method,
+                message,
+            } => {
+                json.number("thread", thread);
+                json.string("thread_name", thread_name);
+                match method {
+                    Some(method) => json.string("method", method),
+                    None => json.raw("method", "null"),
+                }
+                json.string("message", message);
+            }
+            VmEvent::WeakRefsCollected { cleared, live } => {
+                json.number("cleared", cleared);
+                json.number("live", live);
+            }
+        }
+        json.finish()
+    }
+}
+
+impl fmt::Display for VmEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmEvent::ClassLoaded {
+                class_name,
+                class_id,
+            } => write!(f, "Class loaded: {} (ClassId({}))", class_name, class_id),
+            VmEvent::UncaughtException {
+                thread_name,
+                exception,
+                message,
+                ..
+            } => write!(
+                f,
+                "Exception in thread \"{}\" {}: {}",
+                thread_name,
+                exception.replace('/', "."),
+                message
+            ),
+            VmEvent::InvocationError {
+                thread_name,
+                method: Some(method),
+                message,
+                ..
+            } => write!(
+                f,
+                "Thread \"{}\" failed in {}: {}",
+                thread_name, method, message
+            ),
+            VmEvent::InvocationError {
+                thread_name,
+                message,
+                ..
+            } => write!(f, "Thread \"{}\" failed: {}", thread_name, message),
+            VmEvent::WeakRefsCollected { cleared, live } => write!(
+                f,
+                "Weak references collected: {} cleared, {} live",
+                cleared, live
+            ),
+        }
+    }
+}
+
+/// Receiver of the events of the VM.
+pub trait EventSink: Debug + Send {
+    fn emit(&mut self, event: &VmEvent);
+}
+
+/// Sink forwarding the events to the `log` crate, the default one.
+///
+/// The failures are logged as errors, the other events at the debug level.
+#[derive(Debug, Default)]
+pub struct LogSink;
+
+impl EventSink for LogSink {
+    fn emit(&mut self, event: &VmEvent) {
+        match event {
+            VmEvent::UncaughtException { .. } | VmEvent::InvocationError { .. } => {
+                log::error!("{}", event)
+            }
+            VmEvent::ClassLoaded { .. } | VmEvent::WeakRefsCollected { .. } => {
+                log::debug!("{}", event)
+            }
+        }
+    }
+}
+
+/// Sink writing the events as JSON Lines, one JSON object per line.
+pub struct JsonLinesSink<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Get back the writer of the sink.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W> Debug for JsonLinesSink<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLinesSink").finish_non_exhaustive()
+    }
+}
+
+impl<W: Write + Send> EventSink for JsonLinesSink<W> {
+    fn emit(&mut self, event: &VmEvent) {
+        // The events are best-effort telemetry, a broken output must not stop the VM.
+        let _ = writeln!(self.writer, "{}", event.to_json());
+        let _ = self.writer.flush();
+    }
+}
+
+/// Builder of a single-line JSON object.
+#[derive(Debug)]
+pub struct JsonObject {
+    json: String,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self {
+            json: String::from("{"),
+        }
+    }
+
+    /// Add a string member.
+    pub fn string(&mut self, key: &str, value: &str) {
+        self.key(key);
+        write_json_string(&mut self.json, value);
+    }
+
+    /// Add a number member.
+    pub fn number(&mut self, key: &str, value: impl fmt::Display) {
+        self.key(key);
+        let _ = write!(self.json, "{}", value);
+    }
+
+    /// Add a member whose value is already JSON.
+    pub fn raw(&mut self, key: &str, value: &str) {
+        self.key(key);
+        self.json.push_str(value);
+    }
+
+    pub fn finish(mut self) -> String {
+        self.json.push('}');
+        self.json
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.json.len() > 1 {
+            self.json.push(',');
+        }
+        write_json_string(&mut self.json, key);
+        self.json.push(':');
+    }
+}
+
+impl Default for JsonObject {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write a string as a JSON string literal, escaping it.
+fn write_json_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn json_lines() {
+        let mut sink = JsonLinesSink::new(Vec::new());
+        sink.emit(&VmEvent::ClassLoaded {
+            class_name: "java/lang/Object".into(),
+            class_id: 1,
+        });
+        sink.emit(&VmEvent::InvocationError {
+            thread: 0,
+            thread_name: "main".into(),
+            method: None,
+            message: "Invalid state: \"x\"\n\u{1}".into(),
+        });
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            concat!(
+                "{\"event\":\"class_loaded\",\"class_name\":\"java/lang/Object\",\"class_id\":1}\n",
+                "{\"event\":\"invocation_error\",\"thread\":0,\"thread_name\":\"main\",\"method\":null,",
+                "\"message\":\"Invalid state: \\\"x\\\"\\n\\u0001\"}\n"
+            )
+        );
+    }
+}
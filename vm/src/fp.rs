@@ -0,0 +1,266 @@
+use crate::class::{Class, Method};
+
+/// First class file major version where all the floating-point operations are
+/// FP-strict, whatever the ACC_STRICT flag (Java 17, JEP 306).
+pub const ALWAYS_STRICT_MAJOR_VERSION: u16 = 61;
+
+/// Floating-point semantics of a method (JVMS 2.8).
+///
+/// Non FP-strict methods were allowed to use extended exponent value sets for
+/// their intermediate results. This VM never does: all the float and double
+/// operations and conversions are evaluated in the float and double value sets,
+/// using IEEE-754 round-to-nearest (ties to even), which is a valid implementation
+/// of both. The strictness is kept on the frames so it can be reported, but the
+/// results never depend on it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FpStrictness {
+    #[default]
+    Strict,
+    /// FP-default, only for methods of class files older than Java 17 without
+    /// the ACC_STRICT flag.
+    Default,
+}
+
+impl FpStrictness {
+    /// Get the floating-point semantics of a method of the given class.
+    pub fn of_method(class: &Class, method: &Method) -> Self {
+        if class.major_version >= ALWAYS_STRICT_MAJOR_VERSION || method.is_strict() {
+            FpStrictness::Strict
+        } else {
+            FpStrictness::Default
+        }
+    }
+}
+
+const F32_MANTISSA_BITS: u32 = 23;
+const F64_MANTISSA_BITS: u32 = 52;
+const F32_QUIET_NAN: u32 = 0x7fc0_0000;
+const F64_QUIET_NAN: u64 = 0x7ff8_0000_0000_0000;
+
+/// `d2f`, rounds to the nearest float, ties to even.
+///
+/// A NaN is converted to a quiet NaN keeping the sign and the most significant
+/// bits of the payload, as HotSpot does on x86-64, whatever the host platform.
+pub fn d2f(value: f64) -> f32 {
+    if value.is_nan() {
+        let bits = value.to_bits();
+        let sign = ((bits >> 63) as u32) << 31;
+        let payload = ((bits & ((1 << F64_MANTISSA_BITS) - 1))
+            >> (F64_MANTISSA_BITS - F32_MANTISSA_BITS)) as u32;
+        return f32::from_bits(sign | F32_QUIET_NAN | payload);
+    }
+    value as f32
+}
+
+/// `f2d`, always exact.
+///
+/// A NaN is converted to a quiet NaN keeping the sign and the payload, as
+/// HotSpot does on x86-64, whatever the host platform.
+pub fn f2d(value: f32) -> f64 {
+    if value.is_nan() {
+        let bits = value.to_bits();
+        let sign = ((bits >> 31) as u64) << 63;
+        let payload = ((bits & ((1 << F32_MANTISSA_BITS) - 1)) as u64)
+            << (F64_MANTISSA_BITS - F32_MANTISSA_BITS);
+        return f64::from_bits(sign | F64_QUIET_NAN | payload);
+    }
+    value as f64
+}
+
+/// `f2i`, rounds toward zero, NaN gives 0 and out of range values saturate.
+pub fn f2i(value: f32) -> i32 {
+    value as i32
+}
+
+/// `f2l`, rounds toward zero, NaN gives 0 and out of range values saturate.
+pub fn f2l(value: f32) -> i64 {
+    value as i64
+}
+
+/// `d2i`, rounds toward zero, NaN gives 0 and out of range values saturate.
+pub fn d2i(value: f64) -> i32 {
+    value as i32
+}
+
+/// `d2l`, rounds toward zero, NaN gives 0 and out of range values saturate.
+pub fn d2l(value: f64) -> i64 {
+    value as i64
+}
+
+/// `frem`, the remainder of the truncating division (JVMS 6.5.frem), not the
+/// IEEE 754 remainder of `Math.IEEEremainder`.
+///
+/// The result is the one of Rust `%` (C `fmod`) for the finite operands, but the
+/// NaN produced by an invalid operation is left to the platform by Rust, e.g.
+/// the negative default NaN of x86-64. The JVM result is `Float.NaN`, whatever
+/// the NaN operands, and the dividend itself when the divisor is infinite or
+/// the dividend is a zero, keeping the sign of the zero.
+pub fn frem(dividend: f32, divisor: f32) -> f32 {
+    if dividend.is_nan() || divisor.is_nan() || dividend.is_infinite() || divisor == 0.0 {
+        return f32::from_bits(F32_QUIET_NAN);
+    }
+    if divisor.is_infinite() || dividend == 0.0 {
+        return dividend;
+    }
+    // Exact, with the sign of the dividend, including for a zero result.
+    dividend % divisor
+}
+
+/// `drem`, the remainder of the truncating division (JVMS 6.5.drem), see [frem].
+pub fn drem(dividend: f64, divisor: f64) -> f64 {
+    if dividend.is_nan() || divisor.is_nan() || dividend.is_infinite() || divisor == 0.0 {
+        return f64::from_bits(F64_QUIET_NAN);
+    }
+    if divisor.is_infinite() || dividend == 0.0 {
+        return dividend;
+    }
+    dividend % divisor
+}
+
+/// Conformance of the conversions against the results of HotSpot (OpenJDK 17, x86-64).
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn d2f_hotspot() {
+        let expected: &[(u64, u32)] = &[
+            // Denormals, underflow and rounding to the smallest float denormal
+            (0x0000000000000001, 0x00000000),
+            (0x000fffffffffffff, 0x00000000),
+            (0x3690000000000000, 0x00000000),
+            (0x36a0000000000000, 0x00000001),
+            (0x36a8000000000000, 0x00000002),
+            // Ties to even
+            (0x3ff0000010000000, 0x3f800000),
+            (0x3ff0000030000000, 0x3f800002),
+            // Overflow
+            (0x47efffffe0000000, 0x7f7fffff),
+            (0x47effffff0000000, 0x7f800000),
+            // NaN payloads
+            (0x7ff0000000000001, 0x7fc00000),
+            (0x7ff8000000000123, 0x7fc00000),
+            (0xfff4000000000000, 0xffe00000),
+            (0x8000000000000000, 0x80000000),
+            (0x7ff0000000000000, 0x7f800000),
+            (0x3fb999999999999a, 0x3dcccccd),
+        ];
+        for (value, result) in expected {
+            assert_eq!(
+                d2f(f64::from_bits(*value)).to_bits(),
+                *result,
+                "d2f({:#018x})",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn f2d_hotspot() {
+        let expected: &[(u32, u64)] = &[
+            (0x00000001, 0x36a0000000000000),
+            (0x007fffff, 0x380fffffc0000000),
+            (0x7f7fffff, 0x47efffffe0000000),
+            (0x7f800001, 0x7ff8000020000000),
+            (0x7fc00123, 0x7ff8002460000000),
+            (0xff800000, 0xfff0000000000000),
+            (0x80000000, 0x8000000000000000),
+            (0x3dcccccd, 0x3fb99999a0000000),
+        ];
+        for (value, result) in expected {
+            assert_eq!(
+                f2d(f32::from_bits(*value)).to_bits(),
+                *result,
+                "f2d({:#010x})",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn to_integer_hotspot() {
+        let doubles: &[(u64, i32, i64)] = &[
+            (0x7ff8000000000000, 0, 0),
+            (0x41e0000000000000, i32::MAX, 2147483648),
+            (0xc1e0000000200000, i32::MIN, -2147483649),
+            (0x43e0000000000000, i32::MAX, i64::MAX),
+            (0xc3e0000000000001, i32::MIN, i64::MIN),
+            (0x3fefffffffffffff, 0, 0),
+            (0xbfefffffffffffff, 0, 0),
+            (0x7ff0000000000000, i32::MAX, i64::MAX),
+        ];
+        for (value, int, long) in doubles {
+            let value = f64::from_bits(*value);
+            assert_eq!(d2i(value), *int, "d2i({})", value);
+            assert_eq!(d2l(value), *long, "d2l({})", value);
+        }
+
+        let floats: &[(u32, i32, i64)] = &[
+            (0x7fc00000, 0, 0),
+            (0x4f000000, i32::MAX, 2147483648),
+            (0xcf000001, i32::MIN, -2147483904),
+            (0x5f000000, i32::MAX, i64::MAX),
+            (0xdf000001, i32::MIN, i64::MIN),
+            (0x3f7fffff, 0, 0),
+            (0xff800000, i32::MIN, i64::MIN),
+        ];
+        for (value, int, long) in floats {
+            let value = f32::from_bits(*value);
+            assert_eq!(f2i(value), *int, "f2i({})", value);
+            assert_eq!(f2l(value), *long, "f2l({})", value);
+        }
+    }
+
+    #[test]
+    fn remainder_jvms() {
+        let floats: &[(u32, u32, u32)] = &[
+            // 5.5 % 2.0 = 1.5, -5.5 % 2.0 = -1.5, 5.5 % -2.0 = 1.5
+            (0x40b00000, 0x40000000, 0x3fc00000),
+            (0xc0b00000, 0x40000000, 0xbfc00000),
+            (0x40b00000, 0xc0000000, 0x3fc00000),
+            // -4.0 % 2.0 = -0.0
+            (0xc0800000, 0x40000000, 0x80000000),
+            // The zero dividends, the infinite divisors: the dividend
+            (0x80000000, 0x40000000, 0x80000000),
+            (0xc0b00000, 0x7f800000, 0xc0b00000),
+            (0x00000001, 0xff800000, 0x00000001),
+            // NaN, infinite dividend, zero divisor: Float.NaN
+            (0xffc00001, 0x40000000, 0x7fc00000),
+            (0x7f800000, 0x40000000, 0x7fc00000),
+            (0x40000000, 0x80000000, 0x7fc00000),
+            // Large quotients stay exact: 3.4028235e38 % 1.0e-45
+            (0x7f7fffff, 0x00000001, 0x00000000),
+        ];
+        for (dividend, divisor, result) in floats {
+            assert_eq!(
+                frem(f32::from_bits(*dividend), f32::from_bits(*divisor)).to_bits(),
+                *result,
+                "frem({:#010x}, {:#010x})",
+                dividend,
+                divisor
+            );
+        }
+
+        let doubles: &[(u64, u64, u64)] = &[
+            (0x4016000000000000, 0x4000000000000000, 0x3ff8000000000000),
+            (0xc016000000000000, 0x4000000000000000, 0xbff8000000000000),
+            (0xc010000000000000, 0x4000000000000000, 0x8000000000000000),
+            (0x8000000000000000, 0x7ff0000000000000, 0x8000000000000000),
+            (0x3ff0000000000000, 0xfff0000000000000, 0x3ff0000000000000),
+            (0xfff8000000000001, 0x3ff0000000000000, 0x7ff8000000000000),
+            (0xfff0000000000000, 0x3ff0000000000000, 0x7ff8000000000000),
+            (0x3ff0000000000000, 0x0000000000000000, 0x7ff8000000000000),
+            // 0.1 % 0.01 is 2^-58, not 0 as for the decimal values
+            (0x3fb999999999999a, 0x3f847ae147ae147b, 0x3c50000000000000),
+        ];
+        for (dividend, divisor, result) in doubles {
+            assert_eq!(
+                drem(f64::from_bits(*dividend), f64::from_bits(*divisor)).to_bits(),
+                *result,
+                "drem({:#018x}, {:#018x})",
+                dividend,
+                divisor
+            );
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Execution of raw bytecode, for the unit tests of the instructions.
+//!
+//! The bytecode is the code of a static method of a class defined on the fly,
+//! with an empty constant pool. It runs until it completes or until it falls
+//! off its end, the operand stack and the local variables being then reported.
+
+use std::sync::{Arc, OnceLock};
+
+use reader::{
+    base::classfile::{ClassAccessFlags, MethodAccessFlags},
+    descriptor::{self, BaseType, FieldType},
+};
+use snafu::Snafu;
+
+use crate::{
+    class::{self, ClassId, ExceptionTable, MethodAttribute, MethodCode},
+    class_loader::ClassLoadingError,
+    class_manager::{ClassManager, LoadingClass},
+    class_version,
+    constant_pool::ConstantPool,
+    opcode::{InstructionCache, Opcode},
+    slot::Slot,
+    thread::{ExecutionError, Frame, Thread},
+    value::{self, ValueError},
+};
+
+/// Maximum depth of the operand stack of the bytecode.
+pub const MAX_STACK: usize = 64;
+
+/// Maximum number of instructions executed, the bytecode possibly looping forever.
+pub const MAX_STEPS: usize = 1_000_000;
+
+#[derive(Debug, Snafu)]
+pub enum HarnessError {
+    #[snafu(display("Invalid bytecode, expected hexadecimal bytes: {}", input))]
+    InvalidBytecode { input: String },
+
+    #[snafu(context(false))]
+    #[snafu(display("{}", source))]
+    InvalidLocal { source: ValueError },
+
+    #[snafu(display(
+        "The initial locals take {} local variables, only {} available",
+        size,
+        max_locals
+    ))]
+    TooManyLocals { size: usize, max_locals: usize },
+
+    #[snafu(context(false))]
+    #[snafu(display("Cannot define the harness class: {}", source))]
+    Definition { source: ClassLoadingError },
+
+    #[snafu(context(false))]
+    #[snafu(display("{}", source))]
+    Execution { source: ExecutionError },
+
+    #[snafu(display("The bytecode did not complete after {} instructions", MAX_STEPS))]
+    StepLimit,
+}
+
+/// State of the bytecode once executed.
+#[derive(Debug, Clone)]
+pub struct BytecodeOutcome {
+    /// The operand stack, the top last, empty if the bytecode returned.
+    pub stack: Vec<Slot>,
+    /// The local variables, empty if the bytecode returned.
+    pub locals: Vec<Slot>,
+    /// Whether the bytecode ended with a return instruction.
+    pub returned: bool,
+    /// The value returned, if any.
+    pub result: Option<Slot>,
+}
+
+/// Parse bytecode written in hexadecimal, e.g. `1a 1b 60` or `1a1b60`.
+pub fn parse_bytecode(input: &str) -> Result<Vec<u8>, HarnessError> {
+    let digits: Vec<char> = input.chars().filter(|c| !c.is_whitespace()).collect();
+    let invalid = || HarnessError::InvalidBytecode {
+        input: input.to_string(),
+    };
+    if !digits.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let byte: String = pair.iter().collect();
+            u8::from_str_radix(&byte, 16).map_err(|_| invalid())
+        })
+        .collect()
+}
+
+/// Parse the initial value of a local variable, its type being given by the
+/// Java literal: `42`, `-1L`, `1.5f`, `2.0`, `'c'`, `true`, `"text"` or `null`.
+pub fn parse_local(cm: &mut ClassManager, input: &str) -> Result<Slot, HarnessError> {
+    let base = if input == "null" {
+        return Ok(Slot::UndefinedReference);
+    } else if input.starts_with('"') {
+        let string = descriptor::parse_field_descriptor("Ljava/lang/String;")
+            .expect("java/lang/String is a valid descriptor");
+        return Ok(value::parse_literal(cm, string.field_type(), input)?);
+    } else if input.starts_with('\'') {
+        BaseType::Char
+    } else if input == "true" || input == "false" {
+        BaseType::Boolean
+    } else if input.ends_with(['l', 'L']) {
+        BaseType::Long
+    } else if input.ends_with(['f', 'F']) {
+        BaseType::Float
+    } else if input.ends_with(['d', 'D']) || input.contains(['.', 'e', 'E']) {
+        BaseType::Double
+    } else {
+        BaseType::Int
+    };
+    Ok(value::parse_literal(cm, &FieldType::BaseType(base), input)?)
+}
+
+/// Execute bytecode with the given local variables, a long or a double taking
+/// two of them.
+///
+/// The instructions referring to the constant pool cannot be used, it is empty.
+/// The class of the bytecode is unloaded once it completes.
+pub fn run_bytecode(
+    cm: &mut ClassManager,
+    max_locals: usize,
+    locals: Vec<Slot>,
+    code: &[u8],
+) -> Result<BytecodeOutcome, HarnessError> {
+    let size: usize = locals.iter().map(Slot::size).sum();
+    if size > max_locals {
+        return Err(HarnessError::TooManyLocals { size, max_locals });
+    }
+
+    let class_id = cm.acquire_class_id();
+    let cache = Arc::new(InstructionCache::new(code.to_vec()));
+    let descriptor = run_descriptor(&cache);
+    let method = class::Method {
+        name: "run".to_string(),
+        descriptor: cm
+            .intern_method_descriptor(descriptor)
+            .expect("the run descriptors are valid"),
+        flags: MethodAccessFlags::Public | MethodAccessFlags::Static,
+        intrinsic: OnceLock::new(),
+        attributes: vec![MethodAttribute::Code(MethodCode {
+            max_stack: MAX_STACK as u16,
+            max_locals: max_locals as u16,
+            cache,
+            instructions: code.to_vec(),
+            line_numbers: Vec::new(),
+            local_variables: Vec::new(),
+            stack_map: Vec::new(),
+            exception_table: ExceptionTable::default(),
+        })],
+    };
+    cm.define_class(LoadingClass {
+        class_id,
+        class_name: format!("$BytecodeHarness${}", class_id.0),
+        super_class: Some("java/lang/Object".into()),
+        interfaces: vec![],
+        flags: ClassAccessFlags::Public | ClassAccessFlags::Super | ClassAccessFlags::Synthetic,
+        major_version: class_version::MAX_MAJOR_VERSION,
+        constant_pool: ConstantPool::new(vec![]),
+        fields: vec![],
+        methods: vec![method],
+        attributes: vec![],
+        classfile: None,
+    })?;
+
+    let outcome = execute(cm, class_id, max_locals, locals, code.len());
+    cm.unload_class(class_id);
+    outcome
+}
+
+/// Get the descriptor of the `run` method, its return type being the one of
+/// the first return instruction of the bytecode.
+fn run_descriptor(cache: &InstructionCache) -> &'static str {
+    let program = cache.program();
+    (0..program.len())
+        .find_map(|index| match program.get(index)? {
+            Opcode::IReturn => Some("()I"),
+            Opcode::LReturn => Some("()J"),
+            Opcode::FReturn => Some("()F"),
+            Opcode::DReturn => Some("()D"),
+            Opcode::AReturn => Some("()Ljava/lang/Object;"),
+            _ => None,
+        })
+        .unwrap_or("()V")
+}
+
+/// Step the `run` method of the harness class until it completes.
+fn execute(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    max_locals: usize,
+    locals: Vec<Slot>,
+    code_len: usize,
+) -> Result<BytecodeOutcome, HarnessError> {
+    let mut frame = Frame::new(class_id, 0, max_locals, MAX_STACK);
+    frame
+        .set_arguments(locals)
+        .map_err(|source| ExecutionError::InstructionExecutionError { source })?;
+    let mut thread = Thread::with_name("bytecode");
+    thread.push_frame(frame);
+    for _ in 0..MAX_STEPS {
+        if thread.stack.len() == 1 && thread.pc() >= code_len {
+            let frame = thread
+                .pop_frame()
+                .expect("the bytecode frame is on the stack");
+            return Ok(BytecodeOutcome {
+                stack: frame.operand_stack.into_vec(),
+                locals: frame.local_variables,
+                returned: false,
+                result: None,
+            });
+        }
+        if thread.step(cm)? {
+            return Ok(BytecodeOutcome {
+                stack: vec![],
+                locals: vec![],
+                returned: true,
+                result: thread.result,
+            });
+        }
+    }
+    Err(HarnessError::StepLimit)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::class_manager::LoadedClass;
+    use crate::opcode::InstructionError;
+    use crate::test_util::test_class_manager;
+
+    /// Run the bytecode and describe the operand stack, or the returned value.
+    fn run(cm: &mut ClassManager, code: &str, locals: &[&str]) -> String {
+        let locals = locals
+            .iter()
+            .map(|local| parse_local(cm, local).unwrap())
+            .collect();
+        let outcome = run_bytecode(cm, 4, locals, &parse_bytecode(code).unwrap()).unwrap();
+        let values = match outcome.result {
+            Some(result) => vec![result],
+            None => outcome.stack,
+        };
+        values
+            .iter()
+            .map(|value| crate::debug::display_slot(cm, value).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    #[test]
+    fn instructions() {
+        let mut cm = test_class_manager();
+        let cases: &[(&str, &[&str], &str)] = &[
+            ("1a 1b 60", &["2", "3"], "int 5"),
+            ("1a 1b 64", &["2", "3"], "int -1"),
+            ("1a 1b 6c", &["7", "2"], "int 3"),
+            ("1e 20 61", &["1L", "2L"], "long 3"),
+            ("22 23 62", &["1.5f", "2f"], "float 3.5"),
+            ("05 10 07 5f", &[], "int 7, int 2"),
+            ("84 00 ff 1a", &["1"], "int 0"),
+            ("1a ac", &["42"], "int 42"),
+            ("01", &[], "null"),
+            // tableswitch 0..=1, the padding depending on its pc
+            (
+                "1a aa 0000 00000018 00000000 00000001 00000017 00000019 04 05 06",
+                &["1"],
+                "int 3",
+            ),
+        ];
+        for (code, locals, expected) in cases {
+            assert_eq!(&run(&mut cm, code, locals), expected, "bytecode {}", code);
+        }
+        assert_eq!(cm.unloaded_class_count(), cases.len());
+        assert!(!cm
+            .name_map
+            .keys()
+            .any(|name| name.starts_with("$BytecodeHarness$")));
+    }
+
+    #[test]
+    fn harness_errors() {
+        let mut cm = test_class_manager();
+        assert!(matches!(
+            parse_bytecode("1a 1"),
+            Err(HarnessError::InvalidBytecode { .. })
+        ));
+        assert!(matches!(
+            run_bytecode(&mut cm, 1, vec![Slot::Long(1)], &[]),
+            Err(HarnessError::TooManyLocals {
+                size: 2,
+                max_locals: 1
+            })
+        ));
+        // goto 0
+        assert!(matches!(
+            run_bytecode(&mut cm, 0, vec![], &[0xa7, 0x00, 0x00]),
+            Err(HarnessError::StepLimit)
+        ));
+    }
+
+    #[test]
+    fn monitor_release() {
+        let mut cm = test_class_manager();
+        let lock = parse_local(&mut cm, "\"lock\"").unwrap();
+        let Slot::ObjectReference(obj) = &lock else {
+            panic!("Unexpected lock: {:?}", lock);
+        };
+        let obj = obj.clone();
+
+        // monitorenter, monitorexit, return
+        let code = parse_bytecode("2a c2 2a c3 b1").unwrap();
+        assert!(run_bytecode(&mut cm, 1, vec![lock.clone()], &code).is_ok());
+        assert_eq!(obj.monitor_count(), 0);
+
+        // monitorenter twice, return: the frame releases the monitor.
+        let code = parse_bytecode("2a c2 2a c2 b1").unwrap();
+        let err = run_bytecode(&mut cm, 1, vec![lock], &code).unwrap_err();
+        let HarnessError::Execution {
+            source: ExecutionError::InstructionExecutionError { source },
+        } = &err
+        else {
+            panic!("unexpected error: {}", err);
+        };
+        assert!(matches!(
+            source,
+            InstructionError::IllegalMonitorState { .. }
+        ));
+        assert!(err.to_string().contains("2 monitor(s) still held"));
+        assert_eq!(obj.monitor_count(), 0);
+    }
+
+    #[test]
+    fn contained_panics() {
+        let mut cm = test_class_manager();
+        // newarray on an empty operand stack
+        let err = run_bytecode(&mut cm, 4, vec![], &parse_bytecode("bc 0a").unwrap()).unwrap_err();
+        assert!(matches!(
+            err,
+            HarnessError::Execution {
+                source: ExecutionError::InstructionExecutionError {
+                    source: InstructionError::InvalidState { .. }
+                }
+            }
+        ));
+
+        // A native method panicking
+        cm.natives.register(
+            "java/lang/System",
+            "identityHashCode",
+            "(Ljava/lang/Object;)I",
+            |_, _, _| panic!("native failure"),
+        );
+        let class_id = cm.get_or_resolve_class("ObjectDefaults").unwrap().id();
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+            panic!("ObjectDefaults not loaded");
+        };
+        let method = class
+            .methods
+            .iter()
+            .position(|m| m.name == "identityHash")
+            .unwrap();
+        let err = cm.run_method(class_id, method, vec![]).unwrap_err();
+        assert!(matches!(err, ExecutionError::InstructionPanic { .. }));
+        assert!(err.to_string().contains("native failure"), "{}", err);
+
+        // The class manager is still usable.
+        assert_eq!(run(&mut cm, "1a 1b 60", &["2", "3"]), "int 5");
+    }
+
+    #[test]
+    fn return_validation() {
+        let mut cm = test_class_manager();
+        // The method returns int, as its first return instruction: ireturn on
+        // a non-zero argument, return otherwise.
+        let code = "1a 99 0005 1a ac b1";
+        assert_eq!(run(&mut cm, code, &["1"]), "int 1");
+        let code = parse_bytecode(code).unwrap();
+        let err = run_bytecode(&mut cm, 1, vec![Slot::Int(0)], &code).unwrap_err();
+        let HarnessError::Execution {
+            source: ExecutionError::InstructionExecutionError { source },
+        } = &err
+        else {
+            panic!("unexpected error: {}", err);
+        };
+        assert_eq!(source.java_exception(), Some("java/lang/VerifyError"));
+        assert!(err.to_string().contains("return in $BytecodeHarness$"));
+        assert!(err.to_string().ends_with("run()I, a method returning int"));
+    }
+}
@@ -0,0 +1,299 @@
+use std::mem::size_of;
+
+use super::{Intrinsic, IntrinsicRegistry};
+use crate::{
+    alloc::{ArrayRef, PrimitiveElement},
+    class_manager::ClassManager,
+    opcode::InstructionError,
+    slot::Slot,
+    thread::Thread,
+};
+
+const ARRAYS: &str = "java/util/Arrays";
+
+pub(super) fn register(registry: &mut IntrinsicRegistry) {
+    register_type::<i32>(registry, "I");
+    register_type::<i64>(registry, "J");
+    register_type::<f32>(registry, "F");
+    register_type::<f64>(registry, "D");
+    register_type::<i8>(registry, "B");
+    register_type::<u16>(registry, "C");
+    register_type::<i16>(registry, "S");
+    register_type::<bool>(registry, "Z");
+}
+
+/// Register the intrinsics of the arrays of a primitive type, given its descriptor.
+fn register_type<T: Elements>(registry: &mut IntrinsicRegistry, ty: &str) {
+    for (name, descriptor, intrinsic) in [
+        ("fill", format!("([{0}{0})V", ty), fill::<T> as Intrinsic),
+        ("fill", format!("([{0}II{0})V", ty), fill_range::<T>),
+        ("copyOf", format!("([{0}I)[{0}", ty), copy_of::<T>),
+        (
+            "copyOfRange",
+            format!("([{0}II)[{0}", ty),
+            copy_of_range::<T>,
+        ),
+        ("equals", format!("([{0}[{0})Z", ty), equals::<T>),
+    ] {
+        registry.register(ARRAYS, name, &descriptor, intrinsic);
+    }
+}
+
+/// The elements of the arrays of a primitive type, as the arguments of the intrinsics.
+trait Elements: PrimitiveElement + Default + PartialEq {
+    /// Get an element from its value on the operand stack.
+    fn from_slot(slot: &Slot) -> Option<Self>;
+
+    /// Compare two elements as `Arrays.equals` does.
+    fn same(a: &Self, b: &Self) -> bool {
+        a == b
+    }
+}
+
+macro_rules! elements {
+    ($ty:ty, $slot:ident($value:ident) => $element:expr) => {
+        impl Elements for $ty {
+            fn from_slot(slot: &Slot) -> Option<Self> {
+                match slot {
+                    Slot::$slot($value) => Some($element),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+elements!(i32, Int(value) => *value);
+elements!(i64, Long(value) => *value);
+elements!(i8, Int(value) => *value as i8);
+elements!(u16, Int(value) => *value as u16);
+elements!(i16, Int(value) => *value as i16);
+elements!(bool, Int(value) => *value & 1 != 0);
+
+/// The floating-point elements are equal if their bits are, all the NaNs being
+/// equal, as `Float.floatToIntBits` and `Double.doubleToLongBits` compare them.
+impl Elements for f32 {
+    fn from_slot(slot: &Slot) -> Option<Self> {
+        match slot {
+            Slot::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn same(a: &Self, b: &Self) -> bool {
+        (a.is_nan() && b.is_nan()) || a.to_bits() == b.to_bits()
+    }
+}
+
+impl Elements for f64 {
+    fn from_slot(slot: &Slot) -> Option<Self> {
+        match slot {
+            Slot::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn same(a: &Self, b: &Self) -> bool {
+        (a.is_nan() && b.is_nan()) || a.to_bits() == b.to_bits()
+    }
+}
+
+/// `public static void fill(T[] a, T val)`
+fn fill<T: Elements>(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let array = array_argument(&args, 0, "fill")?;
+    let value = element_argument::<T>(&args, 1, "fill")?;
+    array
+        .with_slice_mut(|data: &mut [T]| data.fill(value))
+        .ok_or_else(|| unexpected_type("fill"))?;
+    Ok(None)
+}
+
+/// `public static void fill(T[] a, int fromIndex, int toIndex, T val)`
+fn fill_range<T: Elements>(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let array = array_argument(&args, 0, "fill")?;
+    let from = int_argument(&args, 1, "fill")?;
+    let to = int_argument(&args, 2, "fill")?;
+    let value = element_argument::<T>(&args, 3, "fill")?;
+    let (from, to) = check_range(from, to, array.len(), "fill")?;
+    array
+        .with_slice_mut(|data: &mut [T]| data[from..to].fill(value))
+        .ok_or_else(|| unexpected_type("fill"))?;
+    Ok(None)
+}
+
+/// `public static T[] copyOf(T[] original, int newLength)`
+///
+/// The copy is truncated or padded with zeros to the new length.
+fn copy_of<T: Elements>(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let array = array_argument(&args, 0, "copyOf")?;
+    let length = int_argument(&args, 1, "copyOf")?;
+    if length < 0 {
+        return Err(InstructionError::InvalidState {
+            context: format!("Arrays.copyOf - length is negative: {}", length),
+        });
+    }
+    let length = length as usize;
+    cm.check_array_budget(length, size_of::<T>())?;
+    let copy = array
+        .with_slice(|data: &[T]| padded_copy(&data[..length.min(data.len())], length))
+        .ok_or_else(|| unexpected_type("copyOf"))?;
+    Ok(Some(Slot::ArrayReference(
+        cm.alloc_array(T::new_array(copy)),
+    )))
+}
+
+/// `public static T[] copyOfRange(T[] original, int from, int to)`
+///
+/// The range may end after the array, the copy being padded with zeros.
+fn copy_of_range<T: Elements>(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let array = array_argument(&args, 0, "copyOfRange")?;
+    let from = int_argument(&args, 1, "copyOfRange")?;
+    let to = int_argument(&args, 2, "copyOfRange")?;
+    if from > to {
+        return Err(InstructionError::IllegalArgument {
+            context: format!("Arrays.copyOfRange: {} > {}", from, to),
+        });
+    }
+    let length = (to as i64 - from as i64) as usize;
+    let (from, _) = check_range(from, from, array.len(), "copyOfRange")?;
+    cm.check_array_budget(length, size_of::<T>())?;
+    let copy = array
+        .with_slice(|data: &[T]| {
+            let end = data.len().min(from + length);
+            padded_copy(&data[from..end], length)
+        })
+        .ok_or_else(|| unexpected_type("copyOfRange"))?;
+    Ok(Some(Slot::ArrayReference(
+        cm.alloc_array(T::new_array(copy)),
+    )))
+}
+
+/// `public static boolean equals(T[] a, T[] a2)`
+///
+/// Two null references are equal.
+fn equals<T: Elements>(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let equal = match (args.first(), args.get(1)) {
+        (Some(Slot::ArrayReference(a)), Some(Slot::ArrayReference(b))) => {
+            if std::ptr::eq(a.as_ref(), b.as_ref()) {
+                true
+            } else {
+                a.with_slice(|a: &[T]| {
+                    b.with_slice(|b: &[T]| {
+                        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| T::same(a, b))
+                    })
+                })
+                .flatten()
+                .ok_or_else(|| unexpected_type("equals"))?
+            }
+        }
+        (Some(Slot::UndefinedReference), Some(Slot::UndefinedReference)) => true,
+        (Some(_), Some(_)) => false,
+        _ => return Err(missing_argument("equals", &args)),
+    };
+    Ok(Some(Slot::Int(equal as i32)))
+}
+
+/// Copy elements into a vector of the given length, padded with zeros.
+fn padded_copy<T: Elements>(elements: &[T], length: usize) -> Vec<T> {
+    let mut copy = Vec::with_capacity(length);
+    copy.extend_from_slice(elements);
+    copy.resize(length, T::default());
+    copy
+}
+
+/// Check a range of indices of an array of the given length, as `Arrays.fill` does.
+fn check_range(
+    from: i32,
+    to: i32,
+    len: usize,
+    method: &str,
+) -> Result<(usize, usize), InstructionError> {
+    if from > to {
+        return Err(InstructionError::IllegalArgument {
+            context: format!("Arrays.{}: fromIndex({}) > toIndex({})", method, from, to),
+        });
+    }
+    if from < 0 || to as usize > len {
+        return Err(InstructionError::InvalidState {
+            context: format!(
+                "Arrays.{}: index out of bounds, range {}..{} of length {}",
+                method, from, to, len
+            ),
+        });
+    }
+    Ok((from as usize, to as usize))
+}
+
+fn unexpected_type(method: &str) -> InstructionError {
+    InstructionError::InvalidState {
+        context: format!("Arrays.{}: unexpected array type", method),
+    }
+}
+
+/// Get an array argument, the null reference being a NullPointer error.
+fn array_argument(args: &[Slot], index: usize, method: &str) -> Result<ArrayRef, InstructionError> {
+    match args.get(index) {
+        Some(Slot::ArrayReference(array)) => Ok(array.clone()),
+        Some(Slot::UndefinedReference) => Err(InstructionError::NullPointer {
+            context: format!("Arrays.{}", method),
+        }),
+        _ => Err(missing_argument(method, args)),
+    }
+}
+
+fn element_argument<T: Elements>(
+    args: &[Slot],
+    index: usize,
+    method: &str,
+) -> Result<T, InstructionError> {
+    args.get(index)
+        .and_then(T::from_slot)
+        .ok_or_else(|| missing_argument(method, args))
+}
+
+fn int_argument(args: &[Slot], index: usize, method: &str) -> Result<i32, InstructionError> {
+    match args.get(index) {
+        Some(Slot::Int(value)) => Ok(*value),
+        _ => Err(missing_argument(method, args)),
+    }
+}
+
+fn missing_argument(method: &str, args: &[Slot]) -> InstructionError {
+    InstructionError::InvalidState {
+        context: format!("Arrays.{}: unexpected arguments {:?}", method, args),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn floating_point_equality() {
+        assert!(f64::same(&f64::NAN, &-f64::NAN));
+        assert!(!f64::same(&0.0, &-0.0));
+        assert!(f32::same(&f32::NAN, &f32::from_bits(0x7fc0_0001)));
+        assert!(!f32::same(&f32::NAN, &1.0));
+        assert!(f32::same(&1.5, &1.5));
+    }
+}
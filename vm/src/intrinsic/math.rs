@@ -0,0 +1,191 @@
+use super::IntrinsicRegistry;
+use crate::{class_manager::ClassManager, opcode::InstructionError, slot::Slot, thread::Thread};
+
+const MATH: &str = "java/lang/Math";
+
+pub(super) fn register(registry: &mut IntrinsicRegistry) {
+    for (name, descriptor, intrinsic) in [
+        ("sin", "(D)D", sin as super::Intrinsic),
+        ("cos", "(D)D", cos),
+        ("tan", "(D)D", tan),
+        ("asin", "(D)D", asin),
+        ("acos", "(D)D", acos),
+        ("atan", "(D)D", atan),
+        ("atan2", "(DD)D", atan2),
+        ("exp", "(D)D", exp),
+        ("log", "(D)D", log),
+        ("log10", "(D)D", log10),
+        ("pow", "(DD)D", pow),
+        ("sqrt", "(D)D", sqrt),
+        ("cbrt", "(D)D", cbrt),
+        ("floor", "(D)D", floor),
+        ("ceil", "(D)D", ceil),
+        ("abs", "(I)I", abs_int),
+        ("abs", "(J)J", abs_long),
+        ("abs", "(F)F", abs_float),
+        ("abs", "(D)D", abs_double),
+        ("min", "(II)I", min_int),
+        ("min", "(JJ)J", min_long),
+        ("min", "(FF)F", min_float),
+        ("min", "(DD)D", min_double),
+        ("max", "(II)I", max_int),
+        ("max", "(JJ)J", max_long),
+        ("max", "(FF)F", max_float),
+        ("max", "(DD)D", max_double),
+    ] {
+        registry.register(MATH, name, descriptor, intrinsic);
+    }
+}
+
+/// Define an intrinsic computing a value from its primitive arguments.
+macro_rules! math_intrinsic {
+    ($name:ident, $signature:literal, ($($arg:ident: $kind:ident),+) -> $ret:ident, $value:expr) => {
+        #[doc = concat!("`", $signature, "`")]
+        fn $name(
+            _thread: &mut Thread,
+            _cm: &mut ClassManager,
+            args: Vec<Slot>,
+        ) -> Result<Option<Slot>, InstructionError> {
+            let [$(Slot::$kind($arg)),+] = args[..] else {
+                return Err(InstructionError::InvalidState {
+                    context: format!("Math intrinsic `{}` got {:?}", $signature, args),
+                });
+            };
+            Ok(Some(Slot::$ret($value)))
+        }
+    };
+}
+
+math_intrinsic!(sin, "static double sin(double a)", (a: Double) -> Double, a.sin());
+math_intrinsic!(cos, "static double cos(double a)", (a: Double) -> Double, a.cos());
+math_intrinsic!(tan, "static double tan(double a)", (a: Double) -> Double, a.tan());
+math_intrinsic!(asin, "static double asin(double a)", (a: Double) -> Double, a.asin());
+math_intrinsic!(acos, "static double acos(double a)", (a: Double) -> Double, a.acos());
+math_intrinsic!(atan, "static double atan(double a)", (a: Double) -> Double, a.atan());
+math_intrinsic!(
+    atan2,
+    "static double atan2(double y, double x)",
+    (y: Double, x: Double) -> Double,
+    y.atan2(x)
+);
+math_intrinsic!(exp, "static double exp(double a)", (a: Double) -> Double, a.exp());
+math_intrinsic!(log, "static double log(double a)", (a: Double) -> Double, a.ln());
+math_intrinsic!(log10, "static double log10(double a)", (a: Double) -> Double, a.log10());
+math_intrinsic!(
+    pow,
+    "static double pow(double a, double b)",
+    (a: Double, b: Double) -> Double,
+    java_pow(a, b)
+);
+math_intrinsic!(sqrt, "static double sqrt(double a)", (a: Double) -> Double, a.sqrt());
+math_intrinsic!(cbrt, "static double cbrt(double a)", (a: Double) -> Double, a.cbrt());
+math_intrinsic!(floor, "static double floor(double a)", (a: Double) -> Double, a.floor());
+math_intrinsic!(ceil, "static double ceil(double a)", (a: Double) -> Double, a.ceil());
+
+// The absolute value of the most negative int or long is itself.
+math_intrinsic!(abs_int, "static int abs(int a)", (a: Int) -> Int, a.wrapping_abs());
+math_intrinsic!(abs_long, "static long abs(long a)", (a: Long) -> Long, a.wrapping_abs());
+math_intrinsic!(abs_float, "static float abs(float a)", (a: Float) -> Float, a.abs());
+math_intrinsic!(abs_double, "static double abs(double a)", (a: Double) -> Double, a.abs());
+
+math_intrinsic!(min_int, "static int min(int a, int b)", (a: Int, b: Int) -> Int, a.min(b));
+math_intrinsic!(
+    min_long,
+    "static long min(long a, long b)",
+    (a: Long, b: Long) -> Long,
+    a.min(b)
+);
+math_intrinsic!(
+    min_float,
+    "static float min(float a, float b)",
+    (a: Float, b: Float) -> Float,
+    java_min(a as f64, b as f64) as f32
+);
+math_intrinsic!(
+    min_double,
+    "static double min(double a, double b)",
+    (a: Double, b: Double) -> Double,
+    java_min(a, b)
+);
+math_intrinsic!(max_int, "static int max(int a, int b)", (a: Int, b: Int) -> Int, a.max(b));
+math_intrinsic!(
+    max_long,
+    "static long max(long a, long b)",
+    (a: Long, b: Long) -> Long,
+    a.max(b)
+);
+math_intrinsic!(
+    max_float,
+    "static float max(float a, float b)",
+    (a: Float, b: Float) -> Float,
+    java_max(a as f64, b as f64) as f32
+);
+math_intrinsic!(
+    max_double,
+    "static double max(double a, double b)",
+    (a: Double, b: Double) -> Double,
+    java_max(a, b)
+);
+
+/// `Math.min` of floating-point values: NaN if any value is NaN, and -0.0 is
+/// smaller than 0.0, unlike [f64::min].
+///
+/// The float values are exactly represented as double values, so the float
+/// version is computed on doubles too.
+fn java_min(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == 0.0 && b == 0.0 {
+        if a.is_sign_negative() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.min(b)
+    }
+}
+
+/// `Math.max` of floating-point values, see [java_min].
+fn java_max(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == 0.0 && b == 0.0 {
+        if a.is_sign_positive() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.max(b)
+    }
+}
+
+/// `Math.pow`, which differs from the C `pow` on two special cases: a NaN
+/// exponent always gives NaN, and so does 1.0 or -1.0 to an infinite power.
+fn java_pow(a: f64, b: f64) -> f64 {
+    if b.is_nan() || (a.abs() == 1.0 && b.is_infinite()) {
+        f64::NAN
+    } else {
+        a.powf(b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn floating_point_special_cases() {
+        assert!(java_min(1.0, f64::NAN).is_nan());
+        assert!(java_max(f64::NAN, 1.0).is_nan());
+        assert!(java_min(0.0, -0.0).is_sign_negative());
+        assert!(java_min(-0.0, 0.0).is_sign_negative());
+        assert!(java_max(-0.0, 0.0).is_sign_positive());
+        assert_eq!(java_max(-3.0, 2.0), 2.0);
+        assert!(java_pow(1.0, f64::NAN).is_nan());
+        assert!(java_pow(-1.0, f64::INFINITY).is_nan());
+        assert_eq!(java_pow(f64::NAN, 0.0), 1.0);
+        assert_eq!(java_pow(2.0, 10.0), 1024.0);
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use crate::{class_manager::ClassManager, opcode::InstructionError, slot::Slot, thread::Thread};
+
+mod arrays;
+mod math;
+mod object;
+mod resource;
+mod runtime;
+mod string;
+
+/// Signature of an intrinsic, a method of the class library implemented by the VM.
+///
+/// The arguments are given in order, starting with the receiver (`this`) for
+/// non-static methods. The returned slot, if any, is pushed onto the operand
+/// stack of the caller.
+pub type Intrinsic =
+    fn(&mut Thread, &mut ClassManager, Vec<Slot>) -> Result<Option<Slot>, InstructionError>;
+
+/// Registry of the intrinsics of the VM.
+///
+/// Unlike the native methods, the intrinsics replace methods having a Java
+/// implementation: they are called directly by the invoke instructions, without
+/// pushing a frame.
+#[derive(Debug)]
+pub struct IntrinsicRegistry {
+    /// The intrinsics by class name, then by method name and descriptor.
+    ///
+    /// The invoke instructions look up every method called, so the classes are
+    /// looked up first to avoid building a key for each call.
+    classes: HashMap<String, Vec<(String, String, Intrinsic)>>,
+}
+
+impl IntrinsicRegistry {
+    /// Create a registry with all the intrinsics of the VM.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        arrays::register(&mut registry);
+        math::register(&mut registry);
+        object::register(&mut registry);
+        resource::register(&mut registry);
+        runtime::register(&mut registry);
+        string::register(&mut registry);
+        registry
+    }
+
+    /// Create a registry without any intrinsic, every method running its Java implementation.
+    pub fn empty() -> Self {
+        Self {
+            classes: HashMap::new(),
+        }
+    }
+
+    /// Register an intrinsic, replacing the previous one of the method if any.
+    pub fn register(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+        intrinsic: Intrinsic,
+    ) {
+        let methods = self.classes.entry(class_name.to_string()).or_default();
+        methods.retain(|(name, desc, _)| name != method_name || desc != descriptor);
+        methods.push((method_name.to_string(), descriptor.to_string(), intrinsic));
+    }
+
+    /// Get the intrinsic of a method, if any.
+    pub fn get(&self, class_name: &str, method_name: &str, descriptor: &str) -> Option<Intrinsic> {
+        self.classes
+            .get(class_name)?
+            .iter()
+            .find(|(name, desc, _)| name == method_name && desc == descriptor)
+            .map(|(_, _, intrinsic)| *intrinsic)
+    }
+
+    /// Number of registered intrinsics.
+    pub fn len(&self) -> usize {
+        self.classes.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+}
+
+impl Default for IntrinsicRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
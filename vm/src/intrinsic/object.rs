@@ -0,0 +1,166 @@
+use super::{Intrinsic, IntrinsicRegistry};
+use crate::{
+    alloc::{identity_hash, ObjectRef},
+    class_manager::{ClassManager, LoadedClass},
+    opcode::InstructionError,
+    slot::Slot,
+    thread::{ExecutionError, Thread},
+};
+
+const OBJECT: &str = "java/lang/Object";
+
+pub(super) fn register(registry: &mut IntrinsicRegistry) {
+    for (name, descriptor, intrinsic) in [
+        ("equals", "(Ljava/lang/Object;)Z", equals as Intrinsic),
+        ("hashCode", "()I", hash_code),
+        ("toString", "()Ljava/lang/String;", to_string),
+    ] {
+        registry.register(OBJECT, name, descriptor, intrinsic);
+    }
+}
+
+/// `public boolean equals(Object obj)`
+///
+/// The identity of the objects, as `this == obj`.
+fn equals(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = Slot::ObjectReference(receiver(&args, "equals")?);
+    let equal = match args.get(1) {
+        Some(other) => this.same_reference(other).unwrap_or(false),
+        None => {
+            return Err(InstructionError::InvalidState {
+                context: format!("Object.equals: unexpected arguments {:?}", args),
+            })
+        }
+    };
+    Ok(Some(Slot::Int(equal as i32)))
+}
+
+/// `public int hashCode()`
+///
+/// The identity hash code of the object or array, see [identity_hash].
+fn hash_code(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    match args.first() {
+        Some(Slot::UndefinedReference) => Err(InstructionError::NullPointer {
+            context: "Object.hashCode".into(),
+        }),
+        Some(this) => match identity_hash(cm, this) {
+            Some(hash) => Ok(Some(Slot::Int(hash))),
+            None => Err(InstructionError::InvalidState {
+                context: format!("Object.hashCode: invalid receiver {:?}", this),
+            }),
+        },
+        None => Err(InstructionError::InvalidState {
+            context: "Object.hashCode: missing receiver".into(),
+        }),
+    }
+}
+
+/// `public String toString()`
+///
+/// The name of the class and the hash code of the object in hexadecimal, e.g.
+/// `com.example.Point@1b6d3586`, as an interned string. The hash code is the
+/// one of the `hashCode` method of the object, which may be overridden.
+fn to_string(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "toString")?;
+    let hash = virtual_hash_code(cm, &this)?;
+    let Some(class) = cm.get_class_by_id(this.class_id()) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Object.toString: unknown class of {:?}", this),
+        });
+    };
+    let value = format!("{}@{:x}", class.name().replace('/', "."), hash as u32);
+    let string = cm
+        .intern_string(&value)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: "java/lang/String".into(),
+            source: Box::new(err),
+        })?;
+    Ok(Some(Slot::ObjectReference(string)))
+}
+
+/// Get the hash code of an object, running its `hashCode` method if its class
+/// overrides the one of java/lang/Object.
+fn virtual_hash_code(cm: &mut ClassManager, this: &ObjectRef) -> Result<i32, InstructionError> {
+    let identity = Slot::ObjectReference(this.clone());
+    let resolved = cm.id_of_class(OBJECT).and_then(|object_id| {
+        let Some(LoadedClass::Loaded(object)) = cm.get_class_by_id(object_id) else {
+            return None;
+        };
+        let index = object
+            .methods
+            .iter()
+            .position(|method| method.name == "hashCode" && method.descriptor.as_str() == "()I")?;
+        Some((object_id, index))
+    });
+    let Some((object_id, index)) = resolved else {
+        return Ok(identity_hash(cm, &identity).unwrap_or_default());
+    };
+    let (declaring, method) = cm.select_method(this.class_id(), (object_id, index));
+    if declaring == object_id {
+        return Ok(identity_hash(cm, &identity).unwrap_or_default());
+    }
+    match cm.run_method(declaring, method, vec![identity]) {
+        Ok(Some(Slot::Int(hash))) => Ok(hash),
+        Ok(value) => Err(InstructionError::InvalidState {
+            context: format!("Object.toString: hashCode returned {:?}", value),
+        }),
+        Err(ExecutionError::InstructionExecutionError { source }) => Err(source),
+        Err(err) => Err(InstructionError::InvalidState {
+            context: format!("Object.toString: hashCode failed: {}", err),
+        }),
+    }
+}
+
+/// Get the receiver of a method, the null reference being a NullPointer error.
+fn receiver(args: &[Slot], method: &str) -> Result<ObjectRef, InstructionError> {
+    match args.first() {
+        Some(slot) => slot.clone().non_null_object(&format!("Object.{}", method)),
+        None => Err(InstructionError::InvalidState {
+            context: format!("Object.{}: missing receiver", method),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{alloc::read_string, test_util::test_vm};
+
+    #[test]
+    fn object_defaults() {
+        let mut vm = test_vm();
+        let intrinsics = &vm.class_manager().intrinsics;
+        assert!(intrinsics.get(OBJECT, "hashCode", "()I").is_some());
+        let mut call = |method: &str, descriptor: &str| {
+            vm.invoke_static("ObjectDefaults", method, descriptor, vec![])
+                .unwrap()
+                .unwrap()
+        };
+        for method in ["identityEquals", "identityHash", "interned"] {
+            assert!(matches!(call(method, "()Z"), Slot::Int(1)), "{}", method);
+        }
+        let string = |slot: Slot| match slot {
+            Slot::ObjectReference(obj) => read_string(&obj).unwrap(),
+            slot => panic!("Unexpected result: {:?}", slot),
+        };
+        assert_eq!(
+            string(call("hashed", "()Ljava/lang/String;")),
+            "ObjectDefaults$Hashed@beef"
+        );
+        let plain = string(call("plain", "()Ljava/lang/String;"));
+        let hash = plain.strip_prefix("ObjectDefaults$Plain@").unwrap();
+        assert!(u32::from_str_radix(hash, 16).is_ok());
+    }
+}
@@ -0,0 +1,178 @@
+use super::{Intrinsic, IntrinsicRegistry};
+use crate::{
+    alloc::{read_string, Array, ByteArray, Object},
+    class_manager::ClassManager,
+    opcode::InstructionError,
+    slot::Slot,
+    thread::Thread,
+};
+
+/// Class of the streams returned, the resources being read at once.
+const INPUT_STREAM: &str = "java/io/ByteArrayInputStream";
+
+pub(super) fn register(registry: &mut IntrinsicRegistry) {
+    registry.register(
+        "java/lang/Class",
+        "getResourceAsStream",
+        "(Ljava/lang/String;)Ljava/io/InputStream;",
+        class_get_resource_as_stream as Intrinsic,
+    );
+    registry.register(
+        "java/lang/ClassLoader",
+        "getResourceAsStream",
+        "(Ljava/lang/String;)Ljava/io/InputStream;",
+        loader_get_resource_as_stream,
+    );
+    registry.register(
+        "java/lang/ClassLoader",
+        "getSystemResourceAsStream",
+        "(Ljava/lang/String;)Ljava/io/InputStream;",
+        get_system_resource_as_stream,
+    );
+}
+
+/// `public InputStream getResourceAsStream(String name)` of java/lang/Class
+///
+/// A relative name is resolved against the package of the class, an absolute
+/// one (starting with `/`) against the class path.
+fn class_get_resource_as_stream(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let mirror = match args.first() {
+        Some(slot) => slot.clone().non_null_object("Class.getResourceAsStream")?,
+        None => return Err(missing_argument("Class.getResourceAsStream", &args)),
+    };
+    let class_name = cm
+        .class_of_mirror(&mirror)
+        .and_then(|class_id| cm.get_class_by_id(class_id))
+        .map(|class| class.name().to_string())
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Class.getResourceAsStream: the receiver is not a class mirror".into(),
+        })?;
+    let name = name_argument(&args, 1, "Class.getResourceAsStream")?;
+    resource_stream(cm, &resolve_name(&class_name, &name))
+}
+
+/// `public InputStream getResourceAsStream(String name)` of java/lang/ClassLoader
+///
+/// The VM has a single class loader, every instance reads from the class path.
+fn loader_get_resource_as_stream(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let name = name_argument(&args, 1, "ClassLoader.getResourceAsStream")?;
+    resource_stream(cm, &name)
+}
+
+/// `public static InputStream getSystemResourceAsStream(String name)`
+fn get_system_resource_as_stream(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let name = name_argument(&args, 0, "ClassLoader.getSystemResourceAsStream")?;
+    resource_stream(cm, &name)
+}
+
+/// Resolve the name of a resource relative to a class, as `Class.getResource`
+/// does, e.g. `app.properties` relative to `com/example/Main` is
+/// `com/example/app.properties`.
+fn resolve_name(class_name: &str, name: &str) -> String {
+    if let Some(absolute) = name.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    match class_name.rsplit_once('/') {
+        Some((package, _)) => format!("{}/{}", package, name),
+        None => name.to_string(),
+    }
+}
+
+/// Open a stream on a resource of the class path, or get null if it is missing.
+fn resource_stream(cm: &mut ClassManager, name: &str) -> Result<Option<Slot>, InstructionError> {
+    let Some(bytes) = cm.class_loader.get_resource(name) else {
+        return Ok(Some(Slot::UndefinedReference));
+    };
+    let loading_error = |err| InstructionError::ClassLoadingError {
+        class_name: INPUT_STREAM.to_string(),
+        source: Box::new(err),
+    };
+    let class_id = cm
+        .get_or_resolve_class(INPUT_STREAM)
+        .map(|class| class.id())
+        .map_err(loading_error)?;
+    let count = bytes.len() as i32;
+    let buf: Vec<i8> = bytes.into_iter().map(|byte| byte as i8).collect();
+    let buf = cm.alloc_array(Array::Byte(ByteArray::from(buf)));
+    // The fields of the stream are set as its constructor does, the position
+    // and the mark being 0.
+    let stream = Object::new_with_classmanager(cm, class_id).map_err(loading_error)?;
+    if !stream.set_field_by_name(cm, "buf", Slot::ArrayReference(buf))
+        || !stream.set_field_by_name(cm, "count", Slot::Int(count))
+    {
+        return Err(InstructionError::InvalidState {
+            context: format!("{} has no buf or count field", INPUT_STREAM),
+        });
+    }
+    Ok(Some(Slot::ObjectReference(cm.alloc_object(stream))))
+}
+
+/// Get the name of the resource, the null reference being a NullPointer error.
+fn name_argument(args: &[Slot], index: usize, method: &str) -> Result<String, InstructionError> {
+    let name = args
+        .get(index)
+        .cloned()
+        .ok_or_else(|| missing_argument(method, args))?
+        .non_null_object(method)?;
+    read_string(&name).ok_or_else(|| InstructionError::InvalidState {
+        context: format!("{}: the name is not a String", method),
+    })
+}
+
+fn missing_argument(method: &str, args: &[Slot]) -> InstructionError {
+    InstructionError::InvalidState {
+        context: format!("{}: unexpected arguments {:?}", method, args),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resource_names() {
+        assert_eq!(
+            resolve_name("com/example/Main", "app.properties"),
+            "com/example/app.properties"
+        );
+        assert_eq!(
+            resolve_name("com/example/Main", "/config/app.properties"),
+            "config/app.properties"
+        );
+        assert_eq!(resolve_name("Main", "app.properties"), "app.properties");
+    }
+
+    #[test]
+    fn resource_streams() {
+        use crate::{alloc::new_string, test_util::test_vm};
+
+        let mut vm = test_vm();
+        let mut sum = |method: &str, name: &str| {
+            let name = new_string(vm.class_manager_mut(), name).unwrap();
+            let name = Slot::ObjectReference(name);
+            match vm.invoke_static("Resources", method, "(Ljava/lang/String;)I", vec![name]) {
+                Ok(Some(Slot::Int(sum))) => sum,
+                result => panic!("Unexpected result: {:?}", result),
+            }
+        };
+        // The bytes of "hello".
+        assert_eq!(sum("classResource", "resources/greeting.txt"), 532);
+        assert_eq!(sum("classResource", "/resources/greeting.txt"), 532);
+        assert_eq!(sum("systemResource", "resources/greeting.txt"), 532);
+        assert_eq!(sum("systemResource", "resources/missing.txt"), -1);
+        assert_eq!(sum("systemResource", "resources"), -1);
+        assert_eq!(sum("systemResource", "../classes/Resources.java"), -1);
+    }
+}
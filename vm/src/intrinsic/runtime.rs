@@ -0,0 +1,70 @@
+use super::{Intrinsic, IntrinsicRegistry};
+use crate::{
+    alloc::{Object, ObjectRef},
+    class_manager::ClassManager,
+    opcode::InstructionError,
+    slot::Slot,
+    thread::Thread,
+};
+
+const RUNTIME: &str = "java/lang/Runtime";
+
+pub(super) fn register(registry: &mut IntrinsicRegistry) {
+    for (name, descriptor, intrinsic) in [
+        (
+            "addShutdownHook",
+            "(Ljava/lang/Thread;)V",
+            add_shutdown_hook as Intrinsic,
+        ),
+        (
+            "removeShutdownHook",
+            "(Ljava/lang/Thread;)Z",
+            remove_shutdown_hook,
+        ),
+    ] {
+        registry.register(RUNTIME, name, descriptor, intrinsic);
+    }
+}
+
+/// `public void addShutdownHook(Thread hook)`
+///
+/// The hooks are run by the VM, see [crate::Vm::run_shutdown_hooks]. A hook
+/// registered twice only runs once.
+fn add_shutdown_hook(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let hook = hook_argument(&args, "addShutdownHook")?;
+    if !cm
+        .shutdown_hooks
+        .iter()
+        .any(|registered| std::ptr::eq::<Object>(&**registered, &*hook))
+    {
+        cm.shutdown_hooks.push(hook);
+    }
+    Ok(None)
+}
+
+/// `public boolean removeShutdownHook(Thread hook)`
+fn remove_shutdown_hook(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let hook = hook_argument(&args, "removeShutdownHook")?;
+    let count = cm.shutdown_hooks.len();
+    cm.shutdown_hooks
+        .retain(|registered| !std::ptr::eq::<Object>(&**registered, &*hook));
+    Ok(Some(Slot::Int((cm.shutdown_hooks.len() < count) as i32)))
+}
+
+/// Get the hook argument, the null reference being a NullPointer error.
+fn hook_argument(args: &[Slot], method: &str) -> Result<ObjectRef, InstructionError> {
+    match args.get(1) {
+        Some(slot) => slot.clone().non_null_object(&format!("Runtime.{}", method)),
+        None => Err(InstructionError::InvalidState {
+            context: format!("Runtime.{}: unexpected arguments {:?}", method, args),
+        }),
+    }
+}
@@ -0,0 +1,323 @@
+use super::{Intrinsic, IntrinsicRegistry};
+use crate::{
+    alloc::{string::STRING_VALUE_FIELD, Array, ObjectRef},
+    class_manager::ClassManager,
+    opcode::InstructionError,
+    slot::Slot,
+    thread::Thread,
+};
+
+const STRING: &str = "java/lang/String";
+
+pub(super) fn register(registry: &mut IntrinsicRegistry) {
+    for (name, descriptor, intrinsic) in [
+        ("equals", "(Ljava/lang/Object;)Z", equals as Intrinsic),
+        ("length", "()I", length),
+        ("isEmpty", "()Z", is_empty),
+        ("charAt", "(I)C", char_at),
+        ("hashCode", "()I", hash_code),
+        ("compareTo", "(Ljava/lang/String;)I", compare_to),
+        ("startsWith", "(Ljava/lang/String;)Z", starts_with),
+        ("startsWith", "(Ljava/lang/String;I)Z", starts_with_offset),
+        ("indexOf", "(I)I", index_of_char),
+        ("indexOf", "(II)I", index_of_char_from),
+        ("indexOf", "(Ljava/lang/String;)I", index_of_string),
+        ("indexOf", "(Ljava/lang/String;I)I", index_of_string_from),
+    ] {
+        registry.register(STRING, name, descriptor, intrinsic);
+    }
+}
+
+/// `boolean equals(Object anObject)`
+fn equals(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "equals")?;
+    let equal = match args.get(1) {
+        Some(Slot::ObjectReference(other)) if other.class_id() == this.class_id() => {
+            with_chars(this, |chars| with_chars(other, |other| chars == other))??
+        }
+        Some(_) => false,
+        None => return Err(missing_argument("equals", &args)),
+    };
+    Ok(Some(Slot::Int(equal as i32)))
+}
+
+/// `int length()`
+fn length(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let len = with_chars(receiver(&args, "length")?, <[u16]>::len)?;
+    Ok(Some(Slot::Int(len as i32)))
+}
+
+/// `boolean isEmpty()`
+fn is_empty(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let empty = with_chars(receiver(&args, "isEmpty")?, <[u16]>::is_empty)?;
+    Ok(Some(Slot::Int(empty as i32)))
+}
+
+/// `char charAt(int index)`
+fn char_at(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "charAt")?;
+    let index = int_argument(&args, 1, "charAt")?;
+    with_chars(this, |chars| {
+        match usize::try_from(index)
+            .ok()
+            .and_then(|index| chars.get(index))
+        {
+            Some(c) => Ok(Some(Slot::Int(*c as i32))),
+            None => Err(InstructionError::StringIndexOutOfBounds {
+                context: format!("index {}, length {}", index, chars.len()),
+            }),
+        }
+    })?
+}
+
+/// `int hashCode()`
+///
+/// The hash is cached in the `hash` field of the string, as the Java
+/// implementation does, if the String class has one.
+fn hash_code(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "hashCode")?;
+    let cache = this.field_index(cm, "hash");
+    if let Some(Slot::Int(hash)) = cache.and_then(|index| this.get_field(index)) {
+        if hash != 0 {
+            return Ok(Some(Slot::Int(hash)));
+        }
+    }
+    let hash = with_chars(this, |chars| {
+        chars.iter().fold(0i32, |hash, c| {
+            hash.wrapping_mul(31).wrapping_add(*c as i32)
+        })
+    })?;
+    if let Some(index) = cache {
+        this.set_field(index, Slot::Int(hash));
+    }
+    Ok(Some(Slot::Int(hash)))
+}
+
+/// `int compareTo(String anotherString)`
+fn compare_to(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "compareTo")?;
+    let other = string_argument(&args, 1, "compareTo")?;
+    let result = with_chars(this, |chars| {
+        with_chars(&other, |other| {
+            match chars.iter().zip(other).find(|(a, b)| a != b) {
+                Some((a, b)) => *a as i32 - *b as i32,
+                None => chars.len() as i32 - other.len() as i32,
+            }
+        })
+    })??;
+    Ok(Some(Slot::Int(result)))
+}
+
+/// `boolean startsWith(String prefix)`
+fn starts_with(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "startsWith")?;
+    let prefix = string_argument(&args, 1, "startsWith")?;
+    let result = with_chars(this, |chars| {
+        with_chars(&prefix, |prefix| chars.starts_with(prefix))
+    })??;
+    Ok(Some(Slot::Int(result as i32)))
+}
+
+/// `boolean startsWith(String prefix, int toffset)`
+fn starts_with_offset(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "startsWith")?;
+    let prefix = string_argument(&args, 1, "startsWith")?;
+    let offset = int_argument(&args, 2, "startsWith")?;
+    let result = with_chars(this, |chars| {
+        with_chars(&prefix, |prefix| {
+            usize::try_from(offset)
+                .ok()
+                .and_then(|offset| chars.get(offset..))
+                .is_some_and(|chars| chars.starts_with(prefix))
+        })
+    })??;
+    Ok(Some(Slot::Int(result as i32)))
+}
+
+/// `int indexOf(int ch)`
+fn index_of_char(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "indexOf")?;
+    let ch = int_argument(&args, 1, "indexOf")?;
+    let index = with_chars(this, |chars| find_char(chars, ch, 0))?;
+    Ok(Some(Slot::Int(index)))
+}
+
+/// `int indexOf(int ch, int fromIndex)`
+fn index_of_char_from(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "indexOf")?;
+    let ch = int_argument(&args, 1, "indexOf")?;
+    let from = int_argument(&args, 2, "indexOf")?;
+    let index = with_chars(this, |chars| find_char(chars, ch, from))?;
+    Ok(Some(Slot::Int(index)))
+}
+
+/// `int indexOf(String str)`
+fn index_of_string(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "indexOf")?;
+    let needle = string_argument(&args, 1, "indexOf")?;
+    let index = with_chars(this, |chars| {
+        with_chars(&needle, |needle| find(chars, needle, 0))
+    })??;
+    Ok(Some(Slot::Int(index)))
+}
+
+/// `int indexOf(String str, int fromIndex)`
+fn index_of_string_from(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let this = receiver(&args, "indexOf")?;
+    let needle = string_argument(&args, 1, "indexOf")?;
+    let from = int_argument(&args, 2, "indexOf")?;
+    let index = with_chars(this, |chars| {
+        with_chars(&needle, |needle| find(chars, needle, from))
+    })??;
+    Ok(Some(Slot::Int(index)))
+}
+
+/// Index of the first occurrence of a code point from an index, -1 if not found.
+///
+/// The supplementary code points are searched as their surrogate pairs.
+fn find_char(chars: &[u16], ch: i32, from: i32) -> i32 {
+    let Some(ch) = u32::try_from(ch).ok().and_then(char::from_u32) else {
+        // Lone surrogates are not chars, but can be searched in a String.
+        return match u16::try_from(ch) {
+            Ok(unit) => find(chars, &[unit], from),
+            Err(_) => -1,
+        };
+    };
+    let mut units = [0; 2];
+    find(chars, ch.encode_utf16(&mut units), from)
+}
+
+/// Index of the first occurrence of `needle` from an index, -1 if not found.
+///
+/// As `String.indexOf`, a negative index is a search from the start, and an
+/// empty needle is found at the index, clamped to the length of the string.
+fn find(chars: &[u16], needle: &[u16], from: i32) -> i32 {
+    let from = (from.max(0) as usize).min(chars.len());
+    if needle.is_empty() {
+        return from as i32;
+    }
+    chars[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map_or(-1, |index| (from + index) as i32)
+}
+
+/// Run a function on the characters of a String object.
+fn with_chars<R>(string: &ObjectRef, f: impl FnOnce(&[u16]) -> R) -> Result<R, InstructionError> {
+    let Some(Slot::ArrayReference(array)) = string.get_field(STRING_VALUE_FIELD) else {
+        return Err(InstructionError::InvalidState {
+            context: "String intrinsic: the string has no char array value".into(),
+        });
+    };
+    let Array::Char(chars) = array.as_ref() else {
+        return Err(InstructionError::InvalidState {
+            context: "String intrinsic: the value of the string is not a char array".into(),
+        });
+    };
+    let data = chars
+        .data
+        .read()
+        .expect("rwlock has been poisoned, cannot read string value");
+    Ok(f(&data))
+}
+
+/// Get the string the method is called on.
+fn receiver<'a>(args: &'a [Slot], method: &str) -> Result<&'a ObjectRef, InstructionError> {
+    match args.first() {
+        Some(Slot::ObjectReference(this)) => Ok(this),
+        _ => Err(missing_argument(method, args)),
+    }
+}
+
+/// Get a String argument, the null reference being a NullPointer error.
+fn string_argument(
+    args: &[Slot],
+    index: usize,
+    method: &str,
+) -> Result<ObjectRef, InstructionError> {
+    args.get(index)
+        .cloned()
+        .ok_or_else(|| missing_argument(method, args))?
+        .non_null_object(&format!("String.{}", method))
+}
+
+fn int_argument(args: &[Slot], index: usize, method: &str) -> Result<i32, InstructionError> {
+    match args.get(index) {
+        Some(Slot::Int(value)) => Ok(*value),
+        _ => Err(missing_argument(method, args)),
+    }
+}
+
+fn missing_argument(method: &str, args: &[Slot]) -> InstructionError {
+    InstructionError::InvalidState {
+        context: format!("String.{}: unexpected arguments {:?}", method, args),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn index_of() {
+        let chars: Vec<u16> = "abcabc\u{1F600}".encode_utf16().collect();
+        let bc: Vec<u16> = "bc".encode_utf16().collect();
+        assert_eq!(find(&chars, &bc, 0), 1);
+        assert_eq!(find(&chars, &bc, 2), 4);
+        assert_eq!(find(&chars, &bc, 5), -1);
+        assert_eq!(find(&chars, &[], -3), 0);
+        assert_eq!(find(&chars, &[], 100), chars.len() as i32);
+        assert_eq!(find_char(&chars, 'c' as i32, -1), 2);
+        assert_eq!(find_char(&chars, 0x1F600, 0), 6);
+        assert_eq!(find_char(&chars, 0xD83D, 0), 6);
+        assert_eq!(find_char(&chars, -1, 0), -1);
+    }
+}
@@ -0,0 +1,633 @@
+use std::sync::{Arc, OnceLock};
+
+use reader::{
+    base::{
+        classfile::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags},
+        constant_pool::ReferenceKind,
+    },
+    descriptor::{self, BaseType, FieldType},
+};
+
+use crate::{
+    alloc::{Object, ObjectRef},
+    class::{self, ClassAttribute, ClassId, ExceptionTable, MethodAttribute, MethodCode},
+    class_manager::{ClassManager, LoadedClass, LoadingClass},
+    class_version,
+    constant_pool::{ConstantPool, ConstantPoolEntry, DynamicCallSite},
+    descriptor_table::InternedMethodDescriptor,
+    opcode::{InstructionCache, InstructionError},
+    slot::Slot,
+    value::type_descriptor,
+};
+
+/// Binary name of the class of the lambda metafactory bootstrap methods.
+pub const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+
+/// `altMetafactory` flag: marker interfaces are given after the flags.
+const FLAG_MARKERS: i32 = 2;
+/// `altMetafactory` flag: bridge method types are given after the markers.
+const FLAG_BRIDGES: i32 = 4;
+
+/// Check if a bootstrap method is one of the lambda metafactory, implemented by the VM.
+pub fn is_metafactory(class_name: &str, method_name: &str) -> bool {
+    class_name == LAMBDA_METAFACTORY && matches!(method_name, "metafactory" | "altMetafactory")
+}
+
+/// The method called by a lambda, target of the implementation method handle.
+#[derive(Debug)]
+struct Implementation {
+    kind: ReferenceKind,
+    class_name: String,
+    name: String,
+    descriptor: InternedMethodDescriptor,
+    interface: bool,
+}
+
+/// Description of a lambda class, read from the static arguments of its call site.
+#[derive(Debug)]
+struct LambdaSpec {
+    /// Name of the method of the functional interface.
+    method_name: String,
+    /// The functional interface, then the marker interfaces.
+    interfaces: Vec<String>,
+    /// Types of the values captured by the lambda, the parameters of the call site.
+    captured: Vec<FieldType>,
+    /// Erased descriptor of the method of the functional interface, then the
+    /// descriptors of its bridges.
+    descriptors: Vec<InternedMethodDescriptor>,
+    implementation: Implementation,
+}
+
+/// Define the class of the lambdas produced by a call site of the lambda
+/// metafactory (`metafactory` or `altMetafactory`), and get its ID.
+///
+/// The class implements the functional interface with a method forwarding the
+/// captured values, stored in the fields of the lambdas, and its arguments to
+/// the implementation method. The values are adapted as by the metafactory:
+/// casts, boxing, unboxing and primitive widening.
+pub fn define_lambda_class(
+    cm: &mut ClassManager,
+    caller: ClassId,
+    call_site: &DynamicCallSite,
+) -> Result<ClassId, InstructionError> {
+    let (spec, caller_name, nest_host) = {
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(caller) else {
+            return Err(InstructionError::InvalidState {
+                context: format!("Class not found (or not loaded): ClassId({})", caller.0),
+            });
+        };
+        (
+            read_spec(cm, &class.constant_pool, call_site)?,
+            class.name.clone(),
+            class.nest_host().to_string(),
+        )
+    };
+    for interface in spec.interfaces.iter() {
+        class_reference(cm, interface)?;
+    }
+
+    let class_id = cm.acquire_class_id();
+    let class_name = format!("{}$$Lambda${}", caller_name, class_id.0);
+    let mut constant_pool = ConstantPool::new(vec![]);
+    let mut fields = Vec::new();
+    let mut captured = Vec::new();
+    for (index, field_type) in spec.captured.iter().enumerate() {
+        let name = format!("arg${}", index + 1);
+        let descriptor = descriptor::parse_field_descriptor(&type_descriptor(field_type))
+            .map_err(|err| bootstrap_error(format!("invalid captured type: {}", err)))?;
+        captured.push(constant_pool.append(ConstantPoolEntry::FieldReference {
+            field_name: name.clone(),
+            field_descriptor: descriptor.clone(),
+            implementor: class_id,
+        }));
+        fields.push(class::Field {
+            name,
+            descriptor,
+            flags: FieldAccessFlags::Private | FieldAccessFlags::Final,
+            value: Slot::Tombstone,
+            attributes: vec![],
+        });
+    }
+
+    let mut methods = Vec::new();
+    for (index, descriptor) in spec.descriptors.iter().enumerate() {
+        let code = forwarding_code(cm, &mut constant_pool, &spec, &captured, descriptor)?;
+        let mut flags = MethodAccessFlags::Public.into();
+        if index > 0 {
+            flags |= MethodAccessFlags::Bridge | MethodAccessFlags::Synthetic;
+        }
+        methods.push(class::Method {
+            name: spec.method_name.clone(),
+            descriptor: descriptor.clone(),
+            flags,
+            attributes: vec![MethodAttribute::Code(code)],
+            intrinsic: OnceLock::new(),
+        });
+    }
+
+    let class = LoadingClass {
+        class_id,
+        class_name: class_name.clone(),
+        super_class: Some("java/lang/Object".into()),
+        interfaces: spec.interfaces,
+        flags: ClassAccessFlags::Final | ClassAccessFlags::Super | ClassAccessFlags::Synthetic,
+        major_version: class_version::MAX_MAJOR_VERSION,
+        constant_pool,
+        fields,
+        methods,
+        attributes: vec![ClassAttribute::NestHost(nest_host), ClassAttribute::Hidden],
+        classfile: None,
+    };
+    cm.define_class(class)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name,
+            source: Box::new(err),
+        })
+}
+
+/// Create a lambda, an instance of a lambda class holding the captured values.
+pub fn new_lambda(
+    cm: &mut ClassManager,
+    lambda_class: ClassId,
+    captured: Vec<Slot>,
+) -> Result<ObjectRef, InstructionError> {
+    let obj = Object::new_with_classmanager(cm, lambda_class).map_err(|err| {
+        InstructionError::ClassLoadingError {
+            class_name: cm
+                .get_class_by_id(lambda_class)
+                .map(|class| class.name().to_string())
+                .unwrap_or_default(),
+            source: Box::new(err),
+        }
+    })?;
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(lambda_class) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Lambda class not loaded: ClassId({})", lambda_class.0),
+        });
+    };
+    for (index, value) in captured.into_iter().enumerate() {
+        obj.set_field(class.field_offset + index, value);
+    }
+    Ok(cm.alloc_object(obj))
+}
+
+fn bootstrap_error(context: String) -> InstructionError {
+    InstructionError::BootstrapMethod { context }
+}
+
+/// Read the description of a lambda class from its call site.
+fn read_spec(
+    cm: &ClassManager,
+    cp: &ConstantPool,
+    call_site: &DynamicCallSite,
+) -> Result<LambdaSpec, InstructionError> {
+    let argument = |index: usize| {
+        call_site
+            .arguments_ref
+            .get(index)
+            .and_then(|cp_index| cp.get(*cp_index))
+    };
+    let method_type = |index: usize| match argument(index) {
+        Some(ConstantPoolEntry::MethodType(descriptor)) => Ok(descriptor.clone()),
+        other => Err(bootstrap_error(format!(
+            "static argument {} of the lambda metafactory is not a method type: {:?}",
+            index, other
+        ))),
+    };
+    let int = |index: usize| match argument(index) {
+        Some(ConstantPoolEntry::IntegerConstant(value)) => Ok(*value),
+        other => Err(bootstrap_error(format!(
+            "static argument {} of the lambda metafactory is not an int: {:?}",
+            index, other
+        ))),
+    };
+    let class_name = |class_id: ClassId| {
+        cm.get_class_by_id(class_id)
+            .map(|class| class.name().to_string())
+            .unwrap_or_default()
+    };
+
+    let Some(FieldType::ObjectType(interface)) = &call_site.descriptor.return_type else {
+        return Err(bootstrap_error(format!(
+            "the call site {} does not produce an object",
+            call_site.name
+        )));
+    };
+    let mut interfaces = vec![interface.class_name.as_binary_name()];
+    let mut descriptors = vec![method_type(0)?];
+
+    let implementation = match argument(1) {
+        Some(ConstantPoolEntry::MethodHandleReference(kind, index)) => {
+            let Some(method_ref) = cp.any_method_ref(*index) else {
+                return Err(bootstrap_error(format!(
+                    "the implementation of the lambda {} is not a method",
+                    call_site.name
+                )));
+            };
+            Implementation {
+                kind: kind.clone(),
+                class_name: class_name(method_ref.implementor),
+                name: method_ref.name.to_string(),
+                descriptor: method_ref.descriptor.clone(),
+                interface: method_ref.interface,
+            }
+        }
+        other => {
+            return Err(bootstrap_error(format!(
+                "static argument 1 of the lambda metafactory is not a method handle: {:?}",
+                other
+            )))
+        }
+    };
+    // The instantiated method type only restricts the accepted arguments, which
+    // are cast to the parameter types of the implementation anyway.
+    method_type(2)?;
+
+    if call_site.arguments_ref.len() > 3 {
+        let flags = int(3)?;
+        let mut next = 4;
+        if flags & FLAG_MARKERS != 0 {
+            let count = int(next)? as usize;
+            for index in next + 1..next + 1 + count {
+                match argument(index) {
+                    Some(ConstantPoolEntry::ClassReference(class_id)) => {
+                        interfaces.push(class_name(*class_id))
+                    }
+                    other => {
+                        return Err(bootstrap_error(format!(
+                            "marker interface {} of the lambda metafactory is not a class: {:?}",
+                            index, other
+                        )))
+                    }
+                }
+            }
+            next += 1 + count;
+        }
+        if flags & FLAG_BRIDGES != 0 {
+            let count = int(next)? as usize;
+            for index in next + 1..next + 1 + count {
+                let bridge = method_type(index)?;
+                if !descriptors.contains(&bridge) {
+                    descriptors.push(bridge);
+                }
+            }
+        }
+    }
+
+    Ok(LambdaSpec {
+        method_name: call_site.name.as_str().to_string(),
+        interfaces,
+        captured: call_site.descriptor.parameters.clone(),
+        descriptors,
+        implementation,
+    })
+}
+
+/// Get the ID of a class, resolving it if needed.
+fn class_reference(cm: &mut ClassManager, class_name: &str) -> Result<ClassId, InstructionError> {
+    if let Some(class_id) = cm.id_of_class(class_name) {
+        if let Some(LoadedClass::Loaded(_)) = cm.get_class_by_id(class_id) {
+            return Ok(class_id);
+        }
+    }
+    cm.get_or_resolve_class(class_name)
+        .map(|class| class.id())
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: class_name.to_string(),
+            source: Box::new(err),
+        })
+}
+
+/// Bytecode of a method, built instruction by instruction.
+struct CodeBuilder<'a> {
+    cm: &'a mut ClassManager,
+    cp: &'a mut ConstantPool,
+    code: Vec<u8>,
+}
+
+impl CodeBuilder<'_> {
+    fn emit(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    /// Emit an instruction taking a constant pool index.
+    fn emit_indexed(&mut self, opcode: u8, index: usize) -> Result<(), InstructionError> {
+        let index = u16::try_from(index)
+            .map_err(|_| bootstrap_error("too many constants in the lambda class".into()))?;
+        let [high, low] = index.to_be_bytes();
+        self.emit(&[opcode, high, low]);
+        Ok(())
+    }
+
+    /// Load a local variable of the given type.
+    fn load(&mut self, field_type: &FieldType, local: usize) -> Result<(), InstructionError> {
+        let opcode = match field_type {
+            FieldType::BaseType(BaseType::Long) => 0x16,
+            FieldType::BaseType(BaseType::Float) => 0x17,
+            FieldType::BaseType(BaseType::Double) => 0x18,
+            FieldType::BaseType(_) => 0x15,
+            FieldType::ObjectType(_) | FieldType::ArrayType(_) => 0x19,
+        };
+        let local = u8::try_from(local)
+            .map_err(|_| bootstrap_error("too many arguments for a lambda".into()))?;
+        self.emit(&[opcode, local]);
+        Ok(())
+    }
+
+    /// Return a value of the given type, or nothing.
+    fn return_value(&mut self, field_type: Option<&FieldType>) {
+        let opcode = match field_type {
+            None => 0xb1,
+            Some(FieldType::BaseType(BaseType::Long)) => 0xad,
+            Some(FieldType::BaseType(BaseType::Float)) => 0xae,
+            Some(FieldType::BaseType(BaseType::Double)) => 0xaf,
+            Some(FieldType::BaseType(_)) => 0xac,
+            Some(_) => 0xb0,
+        };
+        self.emit(&[opcode]);
+    }
+
+    fn class_entry(&mut self, field_type: &FieldType) -> Result<usize, InstructionError> {
+        let entry = match field_type {
+            FieldType::ObjectType(object) => ConstantPoolEntry::ClassReference(class_reference(
+                self.cm,
+                &object.class_name.as_binary_name(),
+            )?),
+            field_type => ConstantPoolEntry::ArrayReference(field_type.clone()),
+        };
+        Ok(self.cp.append(entry))
+    }
+
+    fn method_entry(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        interface: bool,
+    ) -> Result<usize, InstructionError> {
+        let implementor = class_reference(self.cm, class_name)?;
+        let method_descriptor = self
+            .cm
+            .intern_method_descriptor(descriptor)
+            .map_err(|err| bootstrap_error(format!("invalid method descriptor: {}", err)))?;
+        let method_name = name.to_string();
+        let entry = if interface {
+            ConstantPoolEntry::InterfaceMethodReference {
+                method_name,
+                method_descriptor,
+                implementor,
+            }
+        } else {
+            ConstantPoolEntry::MethodReference {
+                method_name,
+                method_descriptor,
+                implementor,
+            }
+        };
+        Ok(self.cp.append(entry))
+    }
+
+    /// Convert the value on top of the stack from a type to another (JLS 5.3).
+    fn adapt(&mut self, from: &FieldType, to: &FieldType) -> Result<(), InstructionError> {
+        if from == to {
+            return Ok(());
+        }
+        match (from, to) {
+            (FieldType::BaseType(from), FieldType::BaseType(to)) => self.widen(from, to),
+            (FieldType::BaseType(from), to) => {
+                let (wrapper, _) = wrapper(from);
+                let descriptor = format!("({})L{};", primitive_descriptor(from), wrapper);
+                let index = self.method_entry(wrapper, "valueOf", &descriptor, false)?;
+                self.emit_indexed(0xb8, index)?;
+                self.adapt(&object_type(wrapper), to)
+            }
+            (from, FieldType::BaseType(to)) => {
+                // Unbox with the wrapper of the source if it is known, then widen.
+                let unboxed = match from {
+                    FieldType::ObjectType(object) => {
+                        unwrapped(&object.class_name.as_binary_name()).unwrap_or(to.clone())
+                    }
+                    _ => to.clone(),
+                };
+                let (wrapper, unbox) = wrapper(&unboxed);
+                let index = self.class_entry(&object_type(wrapper))?;
+                self.emit_indexed(0xc0, index)?;
+                let descriptor = format!("(){}", primitive_descriptor(&unboxed));
+                let index = self.method_entry(wrapper, unbox, &descriptor, false)?;
+                self.emit_indexed(0xb6, index)?;
+                self.widen(&unboxed, to)
+            }
+            (_, FieldType::ObjectType(object))
+                if object.class_name.as_binary_name() == "java/lang/Object" =>
+            {
+                Ok(())
+            }
+            (_, to) => {
+                let index = self.class_entry(to)?;
+                self.emit_indexed(0xc0, index)
+            }
+        }
+    }
+
+    /// Widen a primitive value on top of the stack (JLS 5.1.2).
+    fn widen(&mut self, from: &BaseType, to: &BaseType) -> Result<(), InstructionError> {
+        use BaseType::*;
+        let opcode = match (from, to) {
+            (from, to) if from == to => return Ok(()),
+            (Byte | Short | Char, Int) | (Byte, Short) => return Ok(()),
+            (Byte | Short | Char | Int, Long) => 0x85,
+            (Byte | Short | Char | Int, Float) => 0x86,
+            (Byte | Short | Char | Int, Double) => 0x87,
+            (Long, Float) => 0x89,
+            (Long, Double) => 0x8a,
+            (Float, Double) => 0x8d,
+            (from, to) => {
+                return Err(bootstrap_error(format!(
+                    "cannot convert {:?} to {:?} in a lambda",
+                    from, to
+                )))
+            }
+        };
+        self.emit(&[opcode]);
+        Ok(())
+    }
+}
+
+/// Build the code of a method of a lambda class, forwarding to the implementation.
+fn forwarding_code(
+    cm: &mut ClassManager,
+    cp: &mut ConstantPool,
+    spec: &LambdaSpec,
+    captured: &[usize],
+    descriptor: &InternedMethodDescriptor,
+) -> Result<MethodCode, InstructionError> {
+    let implementation = &spec.implementation;
+    let receiver = object_type(&implementation.class_name);
+    let mut targets: Vec<FieldType> = Vec::new();
+    match implementation.kind {
+        ReferenceKind::InvokeStatic | ReferenceKind::NewInvokeSpecial => (),
+        ReferenceKind::InvokeVirtual
+        | ReferenceKind::InvokeInterface
+        | ReferenceKind::InvokeSpecial => targets.push(receiver.clone()),
+        ref kind => {
+            return Err(bootstrap_error(format!(
+                "unsupported implementation method handle kind for a lambda: {:?}",
+                kind
+            )))
+        }
+    }
+    targets.extend(implementation.descriptor.parameters.iter().cloned());
+    let sources: Vec<&FieldType> = spec
+        .captured
+        .iter()
+        .chain(descriptor.parameters.iter())
+        .collect();
+    if sources.len() != targets.len() {
+        return Err(bootstrap_error(format!(
+            "the lambda {}{} cannot call {}.{}{}: {} argument(s) for {} parameter(s)",
+            spec.method_name,
+            descriptor.as_str(),
+            implementation.class_name,
+            implementation.name,
+            implementation.descriptor.as_str(),
+            sources.len(),
+            targets.len()
+        )));
+    }
+
+    let mut builder = CodeBuilder {
+        cm,
+        cp,
+        code: Vec::new(),
+    };
+    if matches!(implementation.kind, ReferenceKind::NewInvokeSpecial) {
+        let index = builder.class_entry(&receiver)?;
+        builder.emit_indexed(0xbb, index)?;
+        builder.emit(&[0x59]);
+    }
+    let mut local = 1;
+    for (position, (source, target)) in sources.iter().zip(targets.iter()).enumerate() {
+        match captured.get(position) {
+            Some(field) => {
+                builder.emit(&[0x2a]);
+                builder.emit_indexed(0xb4, *field)?;
+            }
+            None => {
+                builder.load(source, local)?;
+                local += slot_count(source);
+            }
+        }
+        builder.adapt(source, target)?;
+    }
+
+    let index = builder.method_entry(
+        &implementation.class_name,
+        &implementation.name,
+        implementation.descriptor.as_str(),
+        implementation.interface,
+    )?;
+    let returned = match implementation.kind {
+        ReferenceKind::InvokeStatic => {
+            builder.emit_indexed(0xb8, index)?;
+            implementation.descriptor.return_type.clone()
+        }
+        ReferenceKind::InvokeVirtual => {
+            builder.emit_indexed(0xb6, index)?;
+            implementation.descriptor.return_type.clone()
+        }
+        ReferenceKind::InvokeSpecial => {
+            builder.emit_indexed(0xb7, index)?;
+            implementation.descriptor.return_type.clone()
+        }
+        ReferenceKind::NewInvokeSpecial => {
+            builder.emit_indexed(0xb7, index)?;
+            Some(receiver)
+        }
+        _ => {
+            let count: usize = targets.iter().map(slot_count).sum();
+            builder.emit_indexed(0xb9, index)?;
+            builder.emit(&[count as u8, 0]);
+            implementation.descriptor.return_type.clone()
+        }
+    };
+    match (&returned, &descriptor.return_type) {
+        (Some(returned), None) => builder.emit(&[if slot_count(returned) == 2 {
+            0x58
+        } else {
+            0x57
+        }]),
+        (Some(returned), Some(expected)) => builder.adapt(returned, expected)?,
+        (None, None) => (),
+        (None, Some(_)) => {
+            return Err(bootstrap_error(format!(
+                "the lambda {}{} returns a value, but {}.{} does not",
+                spec.method_name,
+                descriptor.as_str(),
+                implementation.class_name,
+                implementation.name
+            )))
+        }
+    }
+    builder.return_value(descriptor.return_type.as_ref());
+
+    // The new object and its copy, the arguments, and a boxed or widened value.
+    let arguments: usize = targets.iter().map(slot_count).sum();
+    let code = builder.code;
+    Ok(MethodCode {
+        max_stack: (arguments + 4) as u16,
+        max_locals: local as u16,
+        cache: Arc::new(InstructionCache::new(code.clone())),
+        instructions: code,
+        line_numbers: Vec::new(),
+        local_variables: Vec::new(),
+        stack_map: Vec::new(),
+        exception_table: ExceptionTable::default(),
+    })
+}
+
+fn object_type(class_name: &str) -> FieldType {
+    descriptor::parse_field_descriptor(&format!("L{};", class_name))
+        .map(|descriptor| descriptor.field_type().clone())
+        .expect("a class name is a valid field descriptor")
+}
+
+fn slot_count(field_type: &FieldType) -> usize {
+    match field_type {
+        FieldType::BaseType(BaseType::Long | BaseType::Double) => 2,
+        _ => 1,
+    }
+}
+
+fn primitive_descriptor(base: &BaseType) -> String {
+    type_descriptor(&FieldType::BaseType(base.clone()))
+}
+
+/// Get the wrapper class of a primitive type, and the name of its unboxing method.
+pub(crate) fn wrapper(base: &BaseType) -> (&'static str, &'static str) {
+    match base {
+        BaseType::Byte => ("java/lang/Byte", "byteValue"),
+        BaseType::Char => ("java/lang/Character", "charValue"),
+        BaseType::Double => ("java/lang/Double", "doubleValue"),
+        BaseType::Float => ("java/lang/Float", "floatValue"),
+        BaseType::Int => ("java/lang/Integer", "intValue"),
+        BaseType::Long => ("java/lang/Long", "longValue"),
+        BaseType::Short => ("java/lang/Short", "shortValue"),
+        BaseType::Boolean => ("java/lang/Boolean", "booleanValue"),
+    }
+}
+
+/// Get the primitive type wrapped by a class, if it is a wrapper class.
+pub(crate) fn unwrapped(class_name: &str) -> Option<BaseType> {
+    [
+        BaseType::Byte,
+        BaseType::Char,
+        BaseType::Double,
+        BaseType::Float,
+        BaseType::Int,
+        BaseType::Long,
+        BaseType::Short,
+        BaseType::Boolean,
+    ]
+    .into_iter()
+    .find(|base| wrapper(base).0 == class_name)
+}
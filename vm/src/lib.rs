@@ -1,12 +1,37 @@
 pub mod alloc;
+pub mod assertions;
+pub mod bootstrap;
+pub mod breakpoint;
+pub mod call_log;
 pub mod class;
+pub mod class_cache;
 pub mod class_loader;
 pub mod class_manager;
+pub mod class_stats;
+pub mod class_summary;
+pub mod class_version;
+pub mod clock;
 pub mod constant_pool;
+pub mod debug;
+pub mod descriptor_table;
+pub mod event;
+pub mod fp;
+pub mod harness;
+pub mod intrinsic;
+pub mod lambda;
+pub mod native;
 pub mod opcode;
+pub mod opcode_stats;
+pub mod operand_stack;
 pub mod slot;
+#[cfg(test)]
+mod test_util;
 pub mod thread;
+pub mod thread_dump;
 pub mod thread_manager;
+pub mod timing;
+pub mod types;
+pub mod value;
 pub mod vm;
 
-pub use vm::Vm;
+pub use vm::{Vm, VmBuilder, VmError};
@@ -0,0 +1,344 @@
+use std::mem::size_of;
+
+use reader::descriptor::{self, BaseType, FieldType};
+
+use super::NativeRegistry;
+use crate::{
+    alloc::{Array, ArrayRef, ArrayRefArray, Object, ObjectRef, ObjectRefArray},
+    class_manager::{ClassManager, LoadedClass},
+    lambda::{unwrapped, wrapper},
+    opcode::InstructionError,
+    slot::Slot,
+    thread::Thread,
+    types::{self, FieldTypeOrClassId},
+};
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register(
+        "java/lang/reflect/Array",
+        "newArray",
+        "(Ljava/lang/Class;I)Ljava/lang/Object;",
+        new_array,
+    );
+    registry.register(
+        "java/lang/reflect/Array",
+        "getLength",
+        "(Ljava/lang/Object;)I",
+        get_length,
+    );
+    registry.register(
+        "java/lang/reflect/Array",
+        "get",
+        "(Ljava/lang/Object;I)Ljava/lang/Object;",
+        get,
+    );
+    registry.register(
+        "java/lang/reflect/Array",
+        "set",
+        "(Ljava/lang/Object;ILjava/lang/Object;)V",
+        set,
+    );
+}
+
+/// `private static native Object newArray(Class<?> componentType, int length)`
+///
+/// The component type is a class, an array class or a primitive type but `void`.
+fn new_array(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let mirror = args
+        .first()
+        .cloned()
+        .ok_or_else(|| missing_argument("newArray", &args))?
+        .non_null_object("Array.newArray")?;
+    let length = match args.get(1) {
+        Some(Slot::Int(length)) => *length,
+        _ => return Err(missing_argument("newArray", &args)),
+    };
+    if length < 0 {
+        return Err(InstructionError::InvalidState {
+            context: format!("Array.newArray - length is negative: {}", length),
+        });
+    }
+    if let Some(primitive) = cm.primitive_of_mirror(&mirror) {
+        let Some(base) = primitive_type(primitive) else {
+            return Err(InstructionError::IllegalArgument {
+                context: "Array.newArray: void component type".into(),
+            });
+        };
+        cm.check_array_budget(length as usize, Array::primitive_item_size(&base))?;
+        let array = Array::new_primitive(&base, length as usize);
+        return Ok(Some(Slot::ArrayReference(cm.alloc_array(array))));
+    }
+    let Some(class_id) = cm.class_of_mirror(&mirror) else {
+        return Err(InstructionError::IllegalArgument {
+            context: "Array.newArray: unsupported component type".into(),
+        });
+    };
+    let Some(LoadedClass::Loaded(component)) = cm.get_class_by_id(class_id) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found: ClassId({})", class_id.0),
+        });
+    };
+    let array: Array = if component.name.starts_with('[') {
+        let item_ty = match descriptor::parse_field_descriptor(&component.name)
+            .map(|desc| desc.field_type().clone())
+        {
+            Ok(FieldType::ArrayType(item_ty)) => item_ty,
+            _ => {
+                return Err(InstructionError::InvalidState {
+                    context: format!("Invalid array class name: {}", component.name),
+                })
+            }
+        };
+        cm.check_array_budget(length as usize, size_of::<Option<ArrayRef>>())?;
+        ArrayRefArray::new(item_ty, length as usize).into()
+    } else {
+        cm.check_array_budget(length as usize, size_of::<Option<ObjectRef>>())?;
+        ObjectRefArray::new(class_id, length as usize).into()
+    };
+    Ok(Some(Slot::ArrayReference(cm.alloc_array(array))))
+}
+
+/// `public static native int getLength(Object array)`
+fn get_length(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let array = array_argument(&args, "getLength")?;
+    Ok(Some(Slot::Int(array.len() as i32)))
+}
+
+/// `public static native Object get(Object array, int index)`
+///
+/// The primitive elements are boxed in their wrapper class.
+fn get(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let array = array_argument(&args, "get")?;
+    let index = index_argument(&args, &array, "get")?;
+    let (base, value) = match array.as_ref() {
+        Array::ObjectRef(array) => {
+            let value = array.get(index).flatten();
+            return Ok(Some(
+                value.map_or(Slot::UndefinedReference, Slot::ObjectReference),
+            ));
+        }
+        Array::ArrayRef(array) => {
+            let value = array.get(index).flatten();
+            return Ok(Some(
+                value.map_or(Slot::UndefinedReference, Slot::ArrayReference),
+            ));
+        }
+        Array::Int(array) => (BaseType::Int, Slot::Int(array.get(index).unwrap())),
+        Array::Long(array) => (BaseType::Long, Slot::Long(array.get(index).unwrap())),
+        Array::Float(array) => (BaseType::Float, Slot::Float(array.get(index).unwrap())),
+        Array::Double(array) => (BaseType::Double, Slot::Double(array.get(index).unwrap())),
+        Array::Byte(array) => (BaseType::Byte, Slot::Int(array.get(index).unwrap() as i32)),
+        Array::Char(array) => (BaseType::Char, Slot::Int(array.get(index).unwrap() as i32)),
+        Array::Short(array) => (BaseType::Short, Slot::Int(array.get(index).unwrap() as i32)),
+        Array::Boolean(array) => (
+            BaseType::Boolean,
+            Slot::Int(array.get(index).unwrap() as i32),
+        ),
+    };
+    Ok(Some(Slot::ObjectReference(box_value(cm, &base, value)?)))
+}
+
+/// `public static native void set(Object array, int index, Object value)`
+///
+/// The value is unboxed into the primitive arrays, with a widening conversion
+/// if needed (e.g. an Integer stored into a long array).
+fn set(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let array = array_argument(&args, "set")?;
+    let index = index_argument(&args, &array, "set")?;
+    let value = args
+        .get(2)
+        .cloned()
+        .ok_or_else(|| missing_argument("set", &args))?;
+    match array.as_ref() {
+        Array::ObjectRef(array) => {
+            check_component_type(cm, &value, array.class_id().into())?;
+            array.set(index, value.nullable_object("Array.set")?);
+            return Ok(None);
+        }
+        Array::ArrayRef(array) => {
+            check_component_type(
+                cm,
+                &value,
+                FieldType::ArrayType(array.item_type().clone()).into(),
+            )?;
+            array.set(index, value.nullable_array("Array.set")?);
+            return Ok(None);
+        }
+        _ => (),
+    }
+    let value = value.non_null_object("Array.set")?;
+    let (base, value) = unbox_value(cm, &value)?;
+    let mismatch = || InstructionError::IllegalArgument {
+        context: "Array.set: argument type mismatch".into(),
+    };
+    if !widens(&base, element_type(&array).unwrap()) {
+        return Err(mismatch());
+    }
+    match (array.as_ref(), value) {
+        (Array::Int(array), Slot::Int(value)) => array.set(index, value),
+        (Array::Byte(array), Slot::Int(value)) => array.set(index, value as i8),
+        (Array::Short(array), Slot::Int(value)) => array.set(index, value as i16),
+        (Array::Char(array), Slot::Int(value)) => array.set(index, value as u16),
+        (Array::Boolean(array), Slot::Int(value)) => array.set(index, value != 0),
+        (Array::Long(array), Slot::Int(value)) => array.set(index, value as i64),
+        (Array::Long(array), Slot::Long(value)) => array.set(index, value),
+        (Array::Float(array), Slot::Int(value)) => array.set(index, value as f32),
+        (Array::Float(array), Slot::Long(value)) => array.set(index, value as f32),
+        (Array::Float(array), Slot::Float(value)) => array.set(index, value),
+        (Array::Double(array), Slot::Int(value)) => array.set(index, value as f64),
+        (Array::Double(array), Slot::Long(value)) => array.set(index, value as f64),
+        (Array::Double(array), Slot::Float(value)) => array.set(index, value as f64),
+        (Array::Double(array), Slot::Double(value)) => array.set(index, value),
+        _ => return Err(mismatch()),
+    }
+    Ok(None)
+}
+
+/// Get the array argument, an object not being an array is an IllegalArgument error.
+fn array_argument(args: &[Slot], method: &str) -> Result<ArrayRef, InstructionError> {
+    match args.first() {
+        Some(Slot::ArrayReference(array)) => Ok(array.clone()),
+        Some(Slot::ObjectReference(_)) => Err(InstructionError::IllegalArgument {
+            context: format!("Array.{}: argument is not an array", method),
+        }),
+        Some(Slot::UndefinedReference) => Err(InstructionError::NullPointer {
+            context: format!("Array.{}", method),
+        }),
+        _ => Err(missing_argument(method, args)),
+    }
+}
+
+fn index_argument(args: &[Slot], array: &Array, method: &str) -> Result<usize, InstructionError> {
+    match args.get(1) {
+        Some(Slot::Int(index)) if *index >= 0 && (*index as usize) < array.len() => {
+            Ok(*index as usize)
+        }
+        Some(Slot::Int(_)) => Err(InstructionError::InvalidState {
+            context: "Index out of bounds".into(),
+        }),
+        _ => Err(missing_argument(method, args)),
+    }
+}
+
+/// Check if a reference can be stored in an array of references, like `aastore`.
+fn check_component_type(
+    cm: &ClassManager,
+    value: &Slot,
+    component_ty: FieldTypeOrClassId,
+) -> Result<(), InstructionError> {
+    match types::type_of(cm, value) {
+        Some(value_ty) if !types::is_assignable(cm, &value_ty, &component_ty) => {
+            Err(InstructionError::IllegalArgument {
+                context: "Array.set: argument type mismatch".into(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn missing_argument(method: &str, args: &[Slot]) -> InstructionError {
+    InstructionError::InvalidState {
+        context: format!("Array.{}: invalid arguments {:?}", method, args),
+    }
+}
+
+/// Get the primitive type of the elements of an array, None for the arrays of references.
+fn element_type(array: &Array) -> Option<&'static BaseType> {
+    match array {
+        Array::Int(_) => Some(&BaseType::Int),
+        Array::Long(_) => Some(&BaseType::Long),
+        Array::Float(_) => Some(&BaseType::Float),
+        Array::Double(_) => Some(&BaseType::Double),
+        Array::Byte(_) => Some(&BaseType::Byte),
+        Array::Char(_) => Some(&BaseType::Char),
+        Array::Short(_) => Some(&BaseType::Short),
+        Array::Boolean(_) => Some(&BaseType::Boolean),
+        Array::ObjectRef(_) | Array::ArrayRef(_) => None,
+    }
+}
+
+/// Check if a primitive value can be stored as another primitive type, by
+/// identity or widening conversion (JLS 5.1.2).
+fn widens(from: &BaseType, to: &BaseType) -> bool {
+    use BaseType::*;
+    from == to
+        || matches!(
+            (from, to),
+            (Byte, Short | Int | Long | Float | Double)
+                | (Short | Char, Int | Long | Float | Double)
+                | (Int, Long | Float | Double)
+                | (Long, Float | Double)
+                | (Float, Double)
+        )
+}
+
+/// Box a primitive value in a new instance of its wrapper class.
+fn box_value(
+    cm: &mut ClassManager,
+    base: &BaseType,
+    value: Slot,
+) -> Result<ObjectRef, InstructionError> {
+    let (class_name, _) = wrapper(base);
+    let loading_error = |err| InstructionError::ClassLoadingError {
+        class_name: class_name.to_string(),
+        source: Box::new(err),
+    };
+    let class_id = cm
+        .get_or_resolve_class(class_name)
+        .map(|class| class.id())
+        .map_err(loading_error)?;
+    let obj = Object::new_with_classmanager(cm, class_id).map_err(loading_error)?;
+    if !obj.set_field_by_name(cm, "value", value) {
+        return Err(InstructionError::InvalidState {
+            context: format!("{} has no value field", class_name),
+        });
+    }
+    Ok(cm.alloc_object(obj))
+}
+
+/// Get the primitive type and value of a boxed value.
+fn unbox_value(cm: &ClassManager, obj: &Object) -> Result<(BaseType, Slot), InstructionError> {
+    let base = cm
+        .get_class_by_id(obj.class_id())
+        .and_then(|class| unwrapped(class.name()))
+        .ok_or_else(|| InstructionError::IllegalArgument {
+            context: "Array.set: argument type mismatch".into(),
+        })?;
+    let value =
+        obj.get_field_by_name(cm, "value")
+            .ok_or_else(|| InstructionError::InvalidState {
+                context: format!("{} has no value field", wrapper(&base).0),
+            })?;
+    Ok((base, value))
+}
+
+/// Get the primitive type of the name of a primitive mirror, None for `void`.
+fn primitive_type(name: &str) -> Option<BaseType> {
+    match name {
+        "boolean" => Some(BaseType::Boolean),
+        "byte" => Some(BaseType::Byte),
+        "char" => Some(BaseType::Char),
+        "short" => Some(BaseType::Short),
+        "int" => Some(BaseType::Int),
+        "long" => Some(BaseType::Long),
+        "float" => Some(BaseType::Float),
+        "double" => Some(BaseType::Double),
+        _ => None,
+    }
+}
@@ -0,0 +1,447 @@
+use super::NativeRegistry;
+use crate::{
+    alloc::{new_string, read_string, Array, ObjectRef, ObjectRefArray},
+    class::{Class, ClassId},
+    class_manager::{ClassManager, LoadedClass},
+    opcode::InstructionError,
+    slot::Slot,
+    thread::Thread,
+};
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register(
+        "java/lang/Class",
+        "registerNatives",
+        "()V",
+        register_natives,
+    );
+    registry.register(
+        "java/lang/Class",
+        "forName0",
+        "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;",
+        for_name0,
+    );
+    registry.register(
+        "java/lang/Class",
+        "getDeclaringClass0",
+        "()Ljava/lang/Class;",
+        get_declaring_class0,
+    );
+    registry.register(
+        "java/lang/Class",
+        "getEnclosingMethod0",
+        "()[Ljava/lang/Object;",
+        get_enclosing_method0,
+    );
+    registry.register(
+        "java/lang/Class",
+        "getDeclaredClasses0",
+        "()[Ljava/lang/Class;",
+        get_declared_classes0,
+    );
+    registry.register(
+        "java/lang/Class",
+        "getSimpleBinaryName0",
+        "()Ljava/lang/String;",
+        get_simple_binary_name0,
+    );
+    registry.register("java/lang/Class", "getModifiers", "()I", get_modifiers);
+    registry.register(
+        "java/lang/Class",
+        "getPrimitiveClass",
+        "(Ljava/lang/String;)Ljava/lang/Class;",
+        get_primitive_class,
+    );
+    registry.register("java/lang/Class", "isPrimitive", "()Z", is_primitive);
+    registry.register(
+        "java/lang/Class",
+        "desiredAssertionStatus0",
+        "(Ljava/lang/Class;)Z",
+        desired_assertion_status0,
+    );
+    registry.register(
+        "jdk/internal/reflect/Reflection",
+        "getCallerClass",
+        "()Ljava/lang/Class;",
+        get_caller_class,
+    );
+}
+
+/// `static native void registerNatives()`
+fn register_natives(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(None)
+}
+
+/// Load a class from its dotted name, initializing it if requested, and get its mirror.
+fn load_class_mirror(
+    cm: &mut ClassManager,
+    name: Option<&Slot>,
+    initialize: bool,
+) -> Result<Option<Slot>, InstructionError> {
+    let name = match name {
+        Some(slot) => slot.clone().non_null_object("Class.forName")?,
+        None => {
+            return Err(InstructionError::InvalidState {
+                context: "Class.forName: missing class name".into(),
+            })
+        }
+    };
+    let Some(name) = read_string(&name) else {
+        return Err(InstructionError::InvalidState {
+            context: "Class.forName: class name is not a String".into(),
+        });
+    };
+    let binary_name = name.replace('.', "/");
+    let mirror = cm
+        .get_or_resolve_class(&binary_name)
+        .map(|class| class.id())
+        .and_then(|class_id| {
+            if initialize {
+                cm.initialize_class(class_id)?;
+            }
+            cm.get_class_object(class_id)
+        })
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: name.clone(),
+            source: Box::new(err),
+        })?;
+    Ok(Some(Slot::ObjectReference(mirror)))
+}
+
+/// `private static native Class<?> forName0(String name, boolean initialize, ClassLoader loader, Class<?> caller)`
+fn for_name0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    // The class manager initializes classes as soon as they are loaded, so the
+    // `initialize` flag only matters for the interfaces.
+    let initialize = matches!(args.get(1), Some(Slot::Int(value)) if *value != 0);
+    load_class_mirror(cm, args.first(), initialize)
+}
+
+/// `static native Class<?> getCallerClass()`
+///
+/// Native methods do not have their own frame, so the current frame is the
+/// method calling `getCallerClass`, and the frame below it is its caller.
+fn get_caller_class(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let depth = thread.stack.len();
+    let Some(caller) = depth.checked_sub(2).and_then(|i| thread.stack.get(i)) else {
+        return Ok(Some(Slot::UndefinedReference));
+    };
+    let mirror =
+        cm.get_class_object(caller.class)
+            .map_err(|err| InstructionError::ClassLoadingError {
+                class_name: "java/lang/Class".into(),
+                source: Box::new(err),
+            })?;
+    Ok(Some(Slot::ObjectReference(mirror)))
+}
+
+/// Get the class of the mirror receiving a call to a native method of java/lang/Class.
+fn mirrored_class<'a>(
+    cm: &'a ClassManager,
+    args: &[Slot],
+    method: &str,
+) -> Result<&'a Class, InstructionError> {
+    let mirror = args
+        .first()
+        .cloned()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: format!("Class.{}: missing receiver", method),
+        })?
+        .non_null_object(&format!("Class.{}", method))?;
+    match cm
+        .class_of_mirror(&mirror)
+        .and_then(|class_id| cm.get_class_by_id(class_id))
+    {
+        Some(LoadedClass::Loaded(class)) => Ok(class),
+        _ => Err(InstructionError::InvalidState {
+            context: format!("Class.{}: the receiver is not a class mirror", method),
+        }),
+    }
+}
+
+/// Load a class from its binary name and get its mirror.
+fn class_mirror(cm: &mut ClassManager, name: &str) -> Result<ObjectRef, InstructionError> {
+    cm.get_or_resolve_class(name)
+        .map(|class| class.id())
+        .and_then(|class_id| cm.get_class_object(class_id))
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: name.to_string(),
+            source: Box::new(err),
+        })
+}
+
+/// Allocate an array of the given class holding the given objects.
+fn object_array(
+    cm: &mut ClassManager,
+    class_name: &str,
+    objects: Vec<ObjectRef>,
+) -> Result<Slot, InstructionError> {
+    let class_id: ClassId = cm
+        .get_or_resolve_class(class_name)
+        .map(|class| class.id())
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: class_name.to_string(),
+            source: Box::new(err),
+        })?;
+    let array = ObjectRefArray::new(class_id, objects.len());
+    for (index, obj) in objects.into_iter().enumerate() {
+        array.set(index, Some(obj));
+    }
+    Ok(Slot::ArrayReference(
+        cm.alloc_array(Array::ObjectRef(array)),
+    ))
+}
+
+/// `private native Class<?> getDeclaringClass0()`
+///
+/// Returns null for a top-level class, or a primitive type.
+fn get_declaring_class0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    if primitive_mirror(cm, &args, "getDeclaringClass0")?.is_some() {
+        return Ok(Some(Slot::UndefinedReference));
+    }
+    let class = mirrored_class(cm, &args, "getDeclaringClass0")?;
+    match class.declaring_class().map(str::to_string) {
+        Some(outer) => Ok(Some(Slot::ObjectReference(class_mirror(cm, &outer)?))),
+        None => Ok(Some(Slot::UndefinedReference)),
+    }
+}
+
+/// `private native Object[] getEnclosingMethod0()`
+///
+/// Returns the enclosing class, and the name and descriptor of the enclosing
+/// method (null in an initializer), or null if the class is not a local or
+/// anonymous class (e.g. a primitive type).
+fn get_enclosing_method0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    if primitive_mirror(cm, &args, "getEnclosingMethod0")?.is_some() {
+        return Ok(Some(Slot::UndefinedReference));
+    }
+    let class = mirrored_class(cm, &args, "getEnclosingMethod0")?;
+    let Some((outer, method)) = class
+        .enclosing_method()
+        .map(|(outer, method)| (outer.to_string(), method.cloned()))
+    else {
+        return Ok(Some(Slot::UndefinedReference));
+    };
+    let object_class = cm
+        .get_or_resolve_class("java/lang/Object")
+        .map(|class| class.id())
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: "java/lang/Object".into(),
+            source: Box::new(err),
+        })?;
+    let array = ObjectRefArray::new(object_class, 3);
+    array.set(0, Some(class_mirror(cm, &outer)?));
+    if let Some((name, descriptor)) = method {
+        for (index, value) in [(1, name), (2, descriptor)] {
+            let string =
+                new_string(cm, &value).map_err(|err| InstructionError::ClassLoadingError {
+                    class_name: "java/lang/String".into(),
+                    source: Box::new(err),
+                })?;
+            array.set(index, Some(string));
+        }
+    }
+    Ok(Some(Slot::ArrayReference(
+        cm.alloc_array(Array::ObjectRef(array)),
+    )))
+}
+
+/// `private native Class<?>[] getDeclaredClasses0()`
+fn get_declared_classes0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    if primitive_mirror(cm, &args, "getDeclaredClasses0")?.is_some() {
+        return object_array(cm, "java/lang/Class", vec![]).map(Some);
+    }
+    let class = mirrored_class(cm, &args, "getDeclaredClasses0")?;
+    let names: Vec<String> = class
+        .declared_inner_classes()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mirrors = names
+        .iter()
+        .map(|name| class_mirror(cm, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    object_array(cm, "java/lang/Class", mirrors).map(Some)
+}
+
+/// `private native String getSimpleBinaryName0()`
+///
+/// Returns null for a top-level class or a primitive type, and the empty string
+/// for an anonymous class.
+fn get_simple_binary_name0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    if primitive_mirror(cm, &args, "getSimpleBinaryName0")?.is_some() {
+        return Ok(Some(Slot::UndefinedReference));
+    }
+    let class = mirrored_class(cm, &args, "getSimpleBinaryName0")?;
+    if class.inner_class_info().is_none() {
+        return Ok(Some(Slot::UndefinedReference));
+    }
+    let name = class.simple_name().to_string();
+    let string = new_string(cm, &name).map_err(|err| InstructionError::ClassLoadingError {
+        class_name: "java/lang/String".into(),
+        source: Box::new(err),
+    })?;
+    Ok(Some(Slot::ObjectReference(string)))
+}
+
+/// `public native int getModifiers()`
+fn get_modifiers(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    if primitive_mirror(cm, &args, "getModifiers")?.is_some() {
+        // public, final and abstract, as in the JVM.
+        return Ok(Some(Slot::Int(0x0411)));
+    }
+    let class = mirrored_class(cm, &args, "getModifiers")?;
+    Ok(Some(Slot::Int(class.modifiers() as i32)))
+}
+
+/// `static native Class<?> getPrimitiveClass(String name)`
+///
+/// The mirrors of the primitive types, e.g. `Integer.TYPE`, are created by the
+/// class manager, see [ClassManager::get_primitive_class_object].
+fn get_primitive_class(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let name = args
+        .first()
+        .cloned()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Class.getPrimitiveClass: missing name".into(),
+        })?
+        .non_null_object("Class.getPrimitiveClass")?;
+    let name = read_string(&name).ok_or_else(|| InstructionError::InvalidState {
+        context: "Class.getPrimitiveClass: the name is not a String".into(),
+    })?;
+    let mirror = cm.get_primitive_class_object(&name).map_err(|err| {
+        InstructionError::ClassLoadingError {
+            class_name: "java/lang/Class".into(),
+            source: Box::new(err),
+        }
+    })?;
+    match mirror {
+        Some(mirror) => Ok(Some(Slot::ObjectReference(mirror))),
+        None => Err(InstructionError::IllegalArgument {
+            context: format!("Class.getPrimitiveClass: not a primitive type: {}", name),
+        }),
+    }
+}
+
+/// `public native boolean isPrimitive()`
+fn is_primitive(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let primitive = primitive_mirror(cm, &args, "isPrimitive")?.is_some();
+    Ok(Some(Slot::Int(primitive as i32)))
+}
+
+/// Get the primitive type of the mirror receiving a call to a native method of
+/// java/lang/Class, None for the mirror of a class.
+fn primitive_mirror(
+    cm: &ClassManager,
+    args: &[Slot],
+    method: &str,
+) -> Result<Option<&'static str>, InstructionError> {
+    let mirror = args
+        .first()
+        .cloned()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: format!("Class.{}: missing receiver", method),
+        })?
+        .non_null_object(&format!("Class.{}", method))?;
+    Ok(cm.primitive_of_mirror(&mirror))
+}
+
+/// `private static native boolean desiredAssertionStatus0(Class<?> clazz)`
+///
+/// The status is the one set by the `-ea` and `-da` options, see [crate::VmBuilder::assertions].
+fn desired_assertion_status0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let class = mirrored_class(cm, &args, "desiredAssertionStatus0")?;
+    let enabled = cm.assertions.desired(&class.name);
+    Ok(Some(Slot::Int(enabled as i32)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{test_class_manager, test_vm};
+
+    #[test]
+    fn class_lookups() {
+        let mut vm = test_vm();
+        for method in ["forName", "callerClass"] {
+            assert!(
+                matches!(
+                    vm.invoke_static("Reflective", method, "()Z", vec![]),
+                    Ok(Some(Slot::Int(1)))
+                ),
+                "Reflective.{} failed",
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn primitive_mirrors() {
+        let mut cm = test_class_manager();
+        let mut thread = Thread::new();
+        let int = cm.get_primitive_class_object("int").unwrap().unwrap();
+        let receiver = || vec![Slot::ObjectReference(int.clone())];
+
+        // No enclosing or declaring class, nor simple binary name.
+        for native in [
+            get_declaring_class0,
+            get_enclosing_method0,
+            get_simple_binary_name0,
+        ] {
+            let result = native(&mut thread, &mut cm, receiver());
+            assert!(
+                matches!(result, Ok(Some(Slot::UndefinedReference))),
+                "{:?}",
+                result
+            );
+        }
+        let result = get_declared_classes0(&mut thread, &mut cm, receiver());
+        let Ok(Some(Slot::ArrayReference(classes))) = result else {
+            panic!("not an array: {:?}", result);
+        };
+        assert_eq!(classes.len(), 0);
+    }
+}
@@ -0,0 +1,612 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+use super::NativeRegistry;
+use crate::{
+    alloc::{read_string, Array, ArrayRef, ObjectRef},
+    class_manager::ClassManager,
+    opcode::InstructionError,
+    slot::Slot,
+    thread::Thread,
+};
+
+pub const STDIN_FD: i32 = 0;
+pub const STDOUT_FD: i32 = 1;
+pub const STDERR_FD: i32 = 2;
+
+/// Handles behind the standard streams of the Java programs.
+///
+/// They are the standard streams of the VM process by default, an embedder can
+/// replace them, e.g. to capture the output of a program.
+pub struct StandardStreams {
+    pub stdin: Box<dyn Read + Send>,
+    pub stdout: Box<dyn Write + Send>,
+    pub stderr: Box<dyn Write + Send>,
+}
+
+impl Default for StandardStreams {
+    fn default() -> Self {
+        Self {
+            stdin: Box::new(io::stdin()),
+            stdout: Box::new(io::stdout()),
+            stderr: Box::new(io::stderr()),
+        }
+    }
+}
+
+impl fmt::Debug for StandardStreams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StandardStreams").finish_non_exhaustive()
+    }
+}
+
+/// Files opened by the java.io natives, by file descriptor.
+///
+/// The standard streams are not part of the table, they are always open and
+/// mapped to the [StandardStreams] of the VM.
+#[derive(Debug)]
+pub struct FileTable {
+    files: HashMap<i32, File>,
+    next_fd: i32,
+    streams: StandardStreams,
+}
+
+impl FileTable {
+    pub fn new() -> Self {
+        Self::with_streams(StandardStreams::default())
+    }
+
+    pub fn with_streams(streams: StandardStreams) -> Self {
+        Self {
+            files: HashMap::new(),
+            next_fd: STDERR_FD + 1,
+            streams,
+        }
+    }
+
+    /// Register an opened file, and get its file descriptor.
+    pub fn open(&mut self, file: File) -> i32 {
+        let fd = self.next_fd;
+        self.next_fd += 1;
+        self.files.insert(fd, file);
+        fd
+    }
+
+    /// Close a file, returns false if the file descriptor was not open.
+    pub fn close(&mut self, fd: i32) -> bool {
+        self.files.remove(&fd).is_some()
+    }
+
+    /// Get an opened file, the standard streams are not part of the table.
+    pub fn get_mut(&mut self, fd: i32) -> Option<&mut File> {
+        self.files.get_mut(&fd)
+    }
+
+    /// Read from a file descriptor, returns 0 at the end of the file.
+    pub fn read(&mut self, fd: i32, buf: &mut [u8]) -> io::Result<usize> {
+        match fd {
+            STDIN_FD => self.streams.stdin.read(buf),
+            _ => self.file(fd)?.read(buf),
+        }
+    }
+
+    /// Write all the bytes to a file descriptor.
+    ///
+    /// The standard output and error are flushed right away, as the Java
+    /// streams already do their own buffering.
+    pub fn write(&mut self, fd: i32, buf: &[u8]) -> io::Result<()> {
+        match fd {
+            STDOUT_FD => {
+                self.streams.stdout.write_all(buf)?;
+                self.streams.stdout.flush()
+            }
+            STDERR_FD => self.streams.stderr.write_all(buf),
+            _ => self.file(fd)?.write_all(buf),
+        }
+    }
+
+    fn file(&mut self, fd: i32) -> io::Result<&mut File> {
+        self.files
+            .get_mut(&fd)
+            .ok_or_else(|| io::Error::other(format!("Bad file descriptor: {}", fd)))
+    }
+}
+
+impl Default for FileTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register("java/io/FileDescriptor", "initIDs", "()V", init_ids);
+    registry.register("java/io/FileDescriptor", "getHandle", "(I)J", get_handle);
+    registry.register("java/io/FileDescriptor", "getAppend", "(I)Z", get_append);
+    registry.register("java/io/FileDescriptor", "close0", "()V", close0);
+
+    registry.register("java/io/FileInputStream", "initIDs", "()V", init_ids);
+    registry.register(
+        "java/io/FileInputStream",
+        "open0",
+        "(Ljava/lang/String;)V",
+        input_open0,
+    );
+    registry.register("java/io/FileInputStream", "read0", "()I", read0);
+    registry.register(
+        "java/io/FileInputStream",
+        "readBytes",
+        "([BII)I",
+        read_bytes,
+    );
+    registry.register("java/io/FileInputStream", "available0", "()I", available0);
+    registry.register("java/io/FileInputStream", "skip0", "(J)J", skip0);
+    registry.register("java/io/FileInputStream", "length0", "()J", length0);
+    registry.register("java/io/FileInputStream", "position0", "()J", position0);
+
+    registry.register("java/io/FileOutputStream", "initIDs", "()V", init_ids);
+    registry.register(
+        "java/io/FileOutputStream",
+        "open0",
+        "(Ljava/lang/String;Z)V",
+        output_open0,
+    );
+    registry.register("java/io/FileOutputStream", "write", "(IZ)V", write);
+    registry.register(
+        "java/io/FileOutputStream",
+        "writeBytes",
+        "([BIIZ)V",
+        write_bytes,
+    );
+}
+
+/// Get the receiver of a native method.
+fn this(args: &[Slot], context: &str) -> Result<ObjectRef, InstructionError> {
+    match args.first() {
+        Some(slot) => slot.clone().non_null_object(context),
+        None => Err(InstructionError::InvalidState {
+            context: format!("{}: missing receiver", context),
+        }),
+    }
+}
+
+fn int_arg(args: &[Slot], index: usize, context: &str) -> Result<i32, InstructionError> {
+    match args.get(index) {
+        Some(Slot::Int(value)) => Ok(*value),
+        other => Err(InstructionError::InvalidState {
+            context: format!("{}: expected an int argument, got {:?}", context, other),
+        }),
+    }
+}
+
+fn long_arg(args: &[Slot], index: usize, context: &str) -> Result<i64, InstructionError> {
+    match args.get(index) {
+        Some(Slot::Long(value)) => Ok(*value),
+        other => Err(InstructionError::InvalidState {
+            context: format!("{}: expected a long argument, got {:?}", context, other),
+        }),
+    }
+}
+
+/// Get the java/io/FileDescriptor of a file stream.
+fn descriptor_of(
+    cm: &ClassManager,
+    stream: &ObjectRef,
+    context: &str,
+) -> Result<ObjectRef, InstructionError> {
+    match stream.get_field_by_name(cm, "fd") {
+        Some(slot) => slot.non_null_object(context),
+        None => Err(InstructionError::InvalidState {
+            context: format!("{}: object has no file descriptor", context),
+        }),
+    }
+}
+
+/// Get the file descriptor number of a java/io/FileDescriptor, or of a file stream.
+fn fd_of(cm: &ClassManager, obj: &ObjectRef, context: &str) -> Result<i32, InstructionError> {
+    let descriptor = match obj.get_field_by_name(cm, "fd") {
+        Some(Slot::Int(fd)) => return Ok(fd),
+        _ => descriptor_of(cm, obj, context)?,
+    };
+    match descriptor.get_field_by_name(cm, "fd") {
+        Some(Slot::Int(fd)) => Ok(fd),
+        other => Err(InstructionError::InvalidState {
+            context: format!("{}: invalid file descriptor {:?}", context, other),
+        }),
+    }
+}
+
+/// Get the `(array, offset, length)` arguments of a bulk read or write, checking
+/// the bounds.
+fn byte_range(args: &[Slot], context: &str) -> Result<(ArrayRef, usize, usize), InstructionError> {
+    let array = match args.get(1) {
+        Some(slot) => slot.clone().non_null_array(context)?,
+        None => {
+            return Err(InstructionError::InvalidState {
+                context: format!("{}: missing byte array", context),
+            })
+        }
+    };
+    let offset = int_arg(args, 2, context)?;
+    let length = int_arg(args, 3, context)?;
+    if offset < 0 || length < 0 || offset as usize + length as usize > array.len() {
+        return Err(InstructionError::InvalidState {
+            context: format!(
+                "{}: range {}+{} out of bounds of an array of length {}",
+                context,
+                offset,
+                length,
+                array.len()
+            ),
+        });
+    }
+    Ok((array, offset as usize, length as usize))
+}
+
+/// Open a file and attach it to the file descriptor of a stream.
+fn open(
+    cm: &mut ClassManager,
+    args: &[Slot],
+    options: &OpenOptions,
+    context: &str,
+) -> Result<(), InstructionError> {
+    let stream = this(args, context)?;
+    let path = match args.get(1) {
+        Some(slot) => slot.clone().non_null_object(context)?,
+        None => {
+            return Err(InstructionError::InvalidState {
+                context: format!("{}: missing path", context),
+            })
+        }
+    };
+    let Some(path) = read_string(&path) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("{}: path is not a String", context),
+        });
+    };
+    let descriptor = descriptor_of(cm, &stream, context)?;
+    let file = options.open(&path)?;
+    let fd = cm.files.open(file);
+    log::debug!("Opened {} as file descriptor {}", path, fd);
+    descriptor.set_field_by_name(cm, "fd", Slot::Int(fd));
+    Ok(())
+}
+
+/// `private static native void initIDs()`
+fn init_ids(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(None)
+}
+
+/// `private static native long getHandle(int d)`, only meaningful on Windows.
+fn get_handle(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(Some(Slot::Long(-1)))
+}
+
+/// `private static native boolean getAppend(int fd)`, only meaningful on Windows.
+fn get_append(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(Some(Slot::Int(0)))
+}
+
+/// `private native void close0()`
+fn close0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let descriptor = this(&args, "FileDescriptor.close0")?;
+    let fd = fd_of(cm, &descriptor, "FileDescriptor.close0")?;
+    // The standard streams of the VM stay open
+    if fd > STDERR_FD {
+        cm.files.close(fd);
+    }
+    descriptor.set_field_by_name(cm, "fd", Slot::Int(-1));
+    Ok(None)
+}
+
+/// `private native void open0(String name)`
+fn input_open0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let mut options = OpenOptions::new();
+    options.read(true);
+    open(cm, &args, &options, "FileInputStream.open0")?;
+    Ok(None)
+}
+
+/// `private native int read0()`
+fn read0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let stream = this(&args, "FileInputStream.read0")?;
+    let fd = fd_of(cm, &stream, "FileInputStream.read0")?;
+    let mut buf = [0u8; 1];
+    let value = match cm.files.read(fd, &mut buf)? {
+        0 => -1,
+        _ => buf[0] as i32,
+    };
+    Ok(Some(Slot::Int(value)))
+}
+
+/// `private native int readBytes(byte[] b, int off, int len)`
+fn read_bytes(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let context = "FileInputStream.readBytes";
+    let stream = this(&args, context)?;
+    let fd = fd_of(cm, &stream, context)?;
+    let (array, offset, length) = byte_range(&args, context)?;
+    if length == 0 {
+        return Ok(Some(Slot::Int(0)));
+    }
+    let Array::Byte(bytes) = array.as_ref() else {
+        return Err(InstructionError::InvalidState {
+            context: format!("{}: expected a byte array", context),
+        });
+    };
+    let mut buf = vec![0u8; length];
+    let read = cm.files.read(fd, &mut buf)?;
+    if read == 0 {
+        return Ok(Some(Slot::Int(-1)));
+    }
+    let mut data = bytes
+        .data
+        .write()
+        .expect("rwlock has been poisoned, cannot get a mutable ref to array element");
+    for (dest, src) in data[offset..offset + read].iter_mut().zip(buf.iter()) {
+        *dest = *src as i8;
+    }
+    Ok(Some(Slot::Int(read as i32)))
+}
+
+/// `private native int available0()`
+fn available0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let stream = this(&args, "FileInputStream.available0")?;
+    let fd = fd_of(cm, &stream, "FileInputStream.available0")?;
+    let available = match cm.files.get_mut(fd) {
+        Some(file) => {
+            let length = file.metadata()?.len();
+            let position = file.stream_position()?;
+            length.saturating_sub(position).min(i32::MAX as u64) as i32
+        }
+        None => 0,
+    };
+    Ok(Some(Slot::Int(available)))
+}
+
+/// `private native long skip0(long n)`
+fn skip0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let stream = this(&args, "FileInputStream.skip0")?;
+    let fd = fd_of(cm, &stream, "FileInputStream.skip0")?;
+    let n = long_arg(&args, 1, "FileInputStream.skip0")?;
+    let skipped = match cm.files.get_mut(fd) {
+        Some(file) => {
+            let before = file.stream_position()?;
+            let after = file.seek(SeekFrom::Current(n))?;
+            after as i64 - before as i64
+        }
+        None => {
+            // Not seekable, read and drop the bytes
+            let mut buf = vec![0u8; n.clamp(0, 8192) as usize];
+            cm.files.read(fd, &mut buf)? as i64
+        }
+    };
+    Ok(Some(Slot::Long(skipped)))
+}
+
+/// `private native long length0()`
+fn length0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let stream = this(&args, "FileInputStream.length0")?;
+    let fd = fd_of(cm, &stream, "FileInputStream.length0")?;
+    let length = match cm.files.get_mut(fd) {
+        Some(file) => file.metadata()?.len() as i64,
+        None => 0,
+    };
+    Ok(Some(Slot::Long(length)))
+}
+
+/// `private native long position0()`
+fn position0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let stream = this(&args, "FileInputStream.position0")?;
+    let fd = fd_of(cm, &stream, "FileInputStream.position0")?;
+    let position = match cm.files.get_mut(fd) {
+        Some(file) => file.stream_position()? as i64,
+        None => 0,
+    };
+    Ok(Some(Slot::Long(position)))
+}
+
+/// `private native void open0(String name, boolean append)`
+fn output_open0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let append = int_arg(&args, 2, "FileOutputStream.open0")? != 0;
+    let mut options = OpenOptions::new();
+    options.write(true).create(true);
+    if append {
+        options.append(true);
+    } else {
+        options.truncate(true);
+    }
+    open(cm, &args, &options, "FileOutputStream.open0")?;
+    Ok(None)
+}
+
+/// `private native void write(int b, boolean append)`
+fn write(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let stream = this(&args, "FileOutputStream.write")?;
+    let fd = fd_of(cm, &stream, "FileOutputStream.write")?;
+    let value = int_arg(&args, 1, "FileOutputStream.write")?;
+    cm.files.write(fd, &[value as u8])?;
+    Ok(None)
+}
+
+/// `private native void writeBytes(byte[] b, int off, int len, boolean append)`
+fn write_bytes(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let context = "FileOutputStream.writeBytes";
+    let stream = this(&args, context)?;
+    let fd = fd_of(cm, &stream, context)?;
+    let (array, offset, length) = byte_range(&args, context)?;
+    let Array::Byte(bytes) = array.as_ref() else {
+        return Err(InstructionError::InvalidState {
+            context: format!("{}: expected a byte array", context),
+        });
+    };
+    let buf: Vec<u8> = bytes
+        .data
+        .read()
+        .expect("rwlock has been poisoned, cannot get a ref to array element")
+        [offset..offset + length]
+        .iter()
+        .map(|byte| *byte as u8)
+        .collect();
+    cm.files.write(fd, &buf)?;
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{alloc::new_string, test_util::test_vm, thread::ExecutionError, Vm, VmError};
+
+    /// Output kept in memory, shared with the test.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn redirected_streams() {
+        let (stdout, stderr) = (Captured::default(), Captured::default());
+        let mut files = FileTable::with_streams(StandardStreams {
+            stdin: Box::new(&b"input"[..]),
+            stdout: Box::new(stdout.clone()),
+            stderr: Box::new(stderr.clone()),
+        });
+        files.write(STDOUT_FD, b"Hello").unwrap();
+        files.write(STDERR_FD, b"Oops").unwrap();
+        let mut buf = [0u8; 8];
+        let read = files.read(STDIN_FD, &mut buf).unwrap();
+
+        assert_eq!(&buf[..read], b"input");
+        assert_eq!(*stdout.0.lock().unwrap(), b"Hello");
+        assert_eq!(*stderr.0.lock().unwrap(), b"Oops");
+        assert!(files.write(7, b"none").is_err());
+    }
+
+    /// Call a static method of FileIo with a path.
+    fn call(
+        vm: &mut Vm,
+        method: &str,
+        descriptor: &str,
+        path: &str,
+    ) -> Result<Option<Slot>, VmError> {
+        let path = new_string(vm.class_manager_mut(), path).unwrap();
+        vm.invoke_static(
+            "FileIo",
+            method,
+            descriptor,
+            vec![Slot::ObjectReference(path)],
+        )
+    }
+
+    fn io_error(result: Result<Option<Slot>, VmError>) -> io::Error {
+        match result {
+            Err(VmError::Execution {
+                source:
+                    ExecutionError::InstructionExecutionError {
+                        source: InstructionError::IOError { source },
+                    },
+            }) => source,
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn file_streams() {
+        let dir = std::env::temp_dir().join(format!("blazevm-io-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.bin");
+        let path = path.to_str().unwrap();
+        let mut vm = test_vm();
+
+        let checks = call(&mut vm, "roundTrip", "(Ljava/lang/String;)I", path);
+        assert!(matches!(checks, Ok(Some(Slot::Int(11111)))), "{:?}", checks);
+        assert_eq!(std::fs::read(path).unwrap(), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let missing = dir.join("missing.bin");
+        let err = io_error(call(
+            &mut vm,
+            "openMissing",
+            "(Ljava/lang/String;)V",
+            missing.to_str().unwrap(),
+        ));
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = io_error(call(&mut vm, "readClosed", "(Ljava/lang/String;)I", path));
+        assert!(err.to_string().contains("Bad file descriptor"), "{}", err);
+        let err = io_error(call(
+            &mut vm,
+            "seekBeforeStart",
+            "(Ljava/lang/String;)J",
+            path,
+        ));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use crate::{class_manager::ClassManager, opcode::InstructionError, slot::Slot, thread::Thread};
+
+mod array;
+mod class;
+mod io;
+mod object;
+mod runtime;
+mod shutdown;
+mod system;
+mod thread;
+
+pub use io::{FileTable, StandardStreams};
+
+/// Signature of a native method implementation.
+///
+/// The arguments are given in order, starting with the receiver (`this`) for
+/// non-static methods. The returned slot, if any, is pushed onto the operand
+/// stack of the caller.
+pub type NativeMethod =
+    fn(&mut Thread, &mut ClassManager, Vec<Slot>) -> Result<Option<Slot>, InstructionError>;
+
+/// Registry of the native methods implemented by the VM.
+///
+/// Native methods are identified by their class name, method name and method descriptor.
+#[derive(Debug)]
+pub struct NativeRegistry {
+    methods: HashMap<(String, String, String), NativeMethod>,
+}
+
+impl NativeRegistry {
+    /// Create a registry with all the native methods implemented by the VM.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        array::register(&mut registry);
+        class::register(&mut registry);
+        io::register(&mut registry);
+        object::register(&mut registry);
+        runtime::register(&mut registry);
+        shutdown::register(&mut registry);
+        system::register(&mut registry);
+        thread::register(&mut registry);
+        registry
+    }
+
+    /// Create a registry without any native method.
+    pub fn empty() -> Self {
+        Self {
+            methods: HashMap::new(),
+        }
+    }
+
+    /// Register a native method, replacing the previous implementation if any.
+    pub fn register(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+        method: NativeMethod,
+    ) {
+        self.methods.insert(
+            (
+                class_name.to_string(),
+                method_name.to_string(),
+                descriptor.to_string(),
+            ),
+            method,
+        );
+    }
+
+    /// Get the implementation of a native method.
+    pub fn get(
+        &self,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+    ) -> Option<NativeMethod> {
+        self.methods
+            .get(&(
+                class_name.to_string(),
+                method_name.to_string(),
+                descriptor.to_string(),
+            ))
+            .copied()
+    }
+
+    /// Number of registered native methods.
+    pub fn len(&self) -> usize {
+        self.methods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+}
+
+impl Default for NativeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
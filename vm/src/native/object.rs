@@ -0,0 +1,33 @@
+use super::NativeRegistry;
+use crate::{
+    alloc::identity_hash, class_manager::ClassManager, opcode::InstructionError, slot::Slot,
+    thread::Thread,
+};
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register("java/lang/Object", "hashCode", "()I", hash_code);
+}
+
+/// `public native int hashCode()`
+///
+/// The identity hash code of the object or array, see [identity_hash].
+fn hash_code(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    match args.first() {
+        Some(Slot::UndefinedReference) => Err(InstructionError::NullPointer {
+            context: "Object.hashCode".into(),
+        }),
+        Some(receiver) => match identity_hash(cm, receiver) {
+            Some(hash) => Ok(Some(Slot::Int(hash))),
+            None => Err(InstructionError::InvalidState {
+                context: format!("Object.hashCode: invalid receiver {:?}", receiver),
+            }),
+        },
+        None => Err(InstructionError::InvalidState {
+            context: "Object.hashCode: missing receiver".into(),
+        }),
+    }
+}
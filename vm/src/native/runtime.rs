@@ -0,0 +1,129 @@
+use super::NativeRegistry;
+use crate::{class_manager::ClassManager, opcode::InstructionError, slot::Slot, thread::Thread};
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register(
+        "java/lang/Runtime",
+        "availableProcessors",
+        "()I",
+        available_processors,
+    );
+    registry.register("java/lang/Runtime", "freeMemory", "()J", free_memory);
+    registry.register("java/lang/Runtime", "totalMemory", "()J", total_memory);
+    registry.register("java/lang/Runtime", "maxMemory", "()J", max_memory);
+    registry.register("java/lang/Runtime", "gc", "()V", gc);
+}
+
+/// `public native int availableProcessors()`
+fn available_processors(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let processors = std::thread::available_parallelism().map_or(1, |count| count.get());
+    Ok(Some(Slot::Int(processors.min(i32::MAX as usize) as i32)))
+}
+
+/// Get the used and the total memory of the heap, in bytes.
+///
+/// Only the elements of the live arrays are accounted, see
+/// [AllocationStats::live_array_bytes](crate::alloc::AllocationStats::live_array_bytes). Without a
+/// budget the heap grows as needed, so all of it is used.
+fn memory(cm: &ClassManager) -> (u64, u64) {
+    let used = cm.allocations.live_array_bytes() as u64;
+    let total = cm
+        .heap_budget
+        .max_bytes()
+        .map_or(used, |max| (max as u64).max(used));
+    (used, total)
+}
+
+/// `public native long freeMemory()`
+fn free_memory(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let (used, total) = memory(cm);
+    Ok(Some(Slot::Long((total - used) as i64)))
+}
+
+/// `public native long totalMemory()`
+fn total_memory(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let (_, total) = memory(cm);
+    Ok(Some(Slot::Long(total.min(i64::MAX as u64) as i64)))
+}
+
+/// `public native long maxMemory()`
+///
+/// `Long.MAX_VALUE` if the heap has no budget.
+fn max_memory(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let max = cm
+        .heap_budget
+        .max_bytes()
+        .map_or(i64::MAX, |max| max.min(i64::MAX as usize) as i64);
+    Ok(Some(Slot::Long(max)))
+}
+
+/// `public native void gc()`
+///
+/// Collect the unreachable cycles.
+fn gc(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    dumpster::sync::collect();
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{class_manager::LoadedClass, test_util::test_class_loader, Vm, VmBuilder};
+
+    /// Run `MemoryCalls.measure`, returning the max, total and free memory
+    /// before and after the allocation of an int array.
+    fn measure(vm: &mut Vm, size: i32) -> [i64; 4] {
+        vm.invoke_static("MemoryCalls", "measure", "(I)V", vec![Slot::Int(size)])
+            .unwrap();
+        let Some(LoadedClass::Loaded(class)) = vm.class_manager().get_class_by_name("MemoryCalls")
+        else {
+            panic!("MemoryCalls is not loaded");
+        };
+        ["max", "total", "freeBefore", "freeAfter"].map(|name| {
+            match class.get_field(name).and_then(|field| field.get_value()) {
+                Some(Slot::Long(value)) => *value,
+                value => panic!("{} is not a long: {:?}", name, value),
+            }
+        })
+    }
+
+    #[test]
+    fn memory_natives() {
+        let mut vm = VmBuilder::new(test_class_loader())
+            .max_heap(1 << 20)
+            .build();
+        let [max, total, free_before, free_after] = measure(&mut vm, 1000);
+        assert_eq!((max, total), (1 << 20, 1 << 20));
+        assert_eq!(free_before - free_after, 4000);
+        let used = vm.class_manager().allocations.live_array_bytes() as i64;
+        assert_eq!(free_after, total - used);
+
+        // Without budget, the heap is as large as the live arrays.
+        let mut vm = VmBuilder::new(test_class_loader()).build();
+        let [max, total, free_before, free_after] = measure(&mut vm, 1000);
+        assert_eq!(max, i64::MAX);
+        assert_eq!((free_before, free_after), (0, 0));
+        let used = vm.class_manager().allocations.live_array_bytes() as i64;
+        assert!(total <= used - 4000, "{} {}", total, used);
+    }
+}
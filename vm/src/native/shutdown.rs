@@ -0,0 +1,52 @@
+use super::NativeRegistry;
+use crate::{class_manager::ClassManager, opcode::InstructionError, slot::Slot, thread::Thread};
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register("java/lang/Shutdown", "beforeHalt", "()V", before_halt);
+    registry.register("java/lang/Shutdown", "halt0", "(I)V", halt0);
+}
+
+/// `static native void beforeHalt()`
+fn before_halt(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(None)
+}
+
+/// `static native void halt0(int status)`
+///
+/// End of `System.exit` and `Runtime.halt`, the exit request unwinds every
+/// thread up to the VM.
+fn halt0(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let Some(Slot::Int(status)) = args.first() else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Shutdown.halt0: expected an int status, got {:?}", args),
+        });
+    };
+    Err(InstructionError::Exit { status: *status })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{slot::Slot, test_util::test_vm, VmError};
+
+    #[test]
+    fn exit_requests() {
+        let mut vm = test_vm();
+        let result = vm.invoke_static("Exits", "exit", "(I)I", vec![Slot::Int(42)]);
+        assert!(matches!(result, Err(VmError::Exit { status: 42 })));
+        // From a class initializer, through the initialization error.
+        let result = vm.invoke_static("Exits", "initializer", "()I", vec![]);
+        assert!(matches!(result, Err(VmError::Exit { status: 3 })));
+
+        // The status of the program, given its main arguments.
+        let args = ["ab".to_string(), "c".to_string()];
+        assert!(matches!(vm.run_main("Exits", &args), Ok(22)));
+    }
+}
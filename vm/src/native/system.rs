@@ -0,0 +1,57 @@
+use super::NativeRegistry;
+use crate::{
+    alloc::identity_hash, class_manager::ClassManager, opcode::InstructionError, slot::Slot,
+    thread::Thread,
+};
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register(
+        "java/lang/System",
+        "currentTimeMillis",
+        "()J",
+        current_time_millis,
+    );
+    registry.register("java/lang/System", "nanoTime", "()J", nano_time);
+    registry.register(
+        "java/lang/System",
+        "identityHashCode",
+        "(Ljava/lang/Object;)I",
+        identity_hash_code,
+    );
+}
+
+/// `static native long currentTimeMillis()`
+fn current_time_millis(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(Some(Slot::Long(cm.clock.current_time_millis())))
+}
+
+/// `static native long nanoTime()`
+fn nano_time(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(Some(Slot::Long(cm.clock.nano_time())))
+}
+
+/// `static native int identityHashCode(Object x)`
+fn identity_hash_code(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let hash = args.first().and_then(|slot| identity_hash(cm, slot));
+    match hash {
+        Some(hash) => Ok(Some(Slot::Int(hash))),
+        None => Err(InstructionError::InvalidState {
+            context: format!(
+                "System.identityHashCode: expected a reference, got {:?}",
+                args
+            ),
+        }),
+    }
+}
@@ -0,0 +1,192 @@
+use super::NativeRegistry;
+use crate::{
+    alloc::{read_string, Object},
+    class_manager::ClassManager,
+    opcode::InstructionError,
+    slot::Slot,
+    thread::{Thread, MAX_PRIORITY, MIN_PRIORITY},
+};
+
+pub(super) fn register(registry: &mut NativeRegistry) {
+    registry.register(
+        "java/lang/Thread",
+        "registerNatives",
+        "()V",
+        register_natives,
+    );
+    registry.register(
+        "java/lang/Thread",
+        "currentThread",
+        "()Ljava/lang/Thread;",
+        current_thread,
+    );
+    registry.register(
+        "java/lang/Thread",
+        "setNativeName",
+        "(Ljava/lang/String;)V",
+        set_native_name,
+    );
+    registry.register("java/lang/Thread", "isAlive", "()Z", is_alive);
+    registry.register("java/lang/Thread", "start0", "()V", start0);
+    registry.register("java/lang/Thread", "setPriority0", "(I)V", set_priority0);
+}
+
+/// Check if the given slot is the mirror of the current thread.
+fn is_current_thread(thread: &Thread, slot: Option<&Slot>) -> bool {
+    match (slot, &thread.mirror) {
+        (Some(Slot::ObjectReference(obj)), Some(mirror)) => {
+            std::ptr::eq::<Object>(&**obj, &**mirror)
+        }
+        _ => false,
+    }
+}
+
+/// `static native void registerNatives()`
+fn register_natives(
+    _thread: &mut Thread,
+    _cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(None)
+}
+
+/// `static native Thread currentThread()`
+fn current_thread(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    _args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let mirror = thread
+        .mirror(cm)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: "java/lang/Thread".into(),
+            source: Box::new(err),
+        })?;
+    Ok(Some(Slot::ObjectReference(mirror)))
+}
+
+/// `private native void setNativeName(String name)`
+fn set_native_name(
+    thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    // Only the current thread is reachable from a native method
+    if !is_current_thread(thread, args.first()) {
+        return Ok(None);
+    }
+    match args.get(1) {
+        Some(Slot::ObjectReference(name)) => {
+            if let Some(name) = read_string(name) {
+                thread.name = name;
+            }
+            Ok(None)
+        }
+        _ => Err(InstructionError::InvalidState {
+            context: "Null object reference".into(),
+        }),
+    }
+}
+
+/// `public final native boolean isAlive()`
+fn is_alive(
+    thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    Ok(Some(Slot::Int(
+        is_current_thread(thread, args.first()) as i32
+    )))
+}
+
+/// `private native void start0()`
+///
+/// The thread is added to the threads of the VM once the current thread stops.
+fn start0(
+    _thread: &mut Thread,
+    cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    let Some(Slot::ObjectReference(mirror)) = args.first() else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Thread.start0: expected a thread, got {:?}", args),
+        });
+    };
+    let started = Thread::for_mirror(cm, mirror.clone())?;
+    cm.started_threads.push(started);
+    Ok(None)
+}
+
+/// `private native void setPriority0(int newPriority)`
+fn set_priority0(
+    thread: &mut Thread,
+    _cm: &mut ClassManager,
+    args: Vec<Slot>,
+) -> Result<Option<Slot>, InstructionError> {
+    // The threads not started yet take the priority of their object when started.
+    if !is_current_thread(thread, args.first()) {
+        return Ok(None);
+    }
+    match args.get(1) {
+        Some(Slot::Int(priority)) => {
+            thread.priority = (*priority).clamp(MIN_PRIORITY, MAX_PRIORITY);
+            Ok(None)
+        }
+        _ => Err(InstructionError::InvalidState {
+            context: format!("Thread.setPriority0: expected a priority, got {:?}", args),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        alloc::new_string,
+        test_util::{test_class_manager, test_vm},
+    };
+
+    #[test]
+    fn thread_mirrors() {
+        let mut cm = test_class_manager();
+        let mut thread = Thread::with_name("main");
+        let Ok(Some(Slot::ObjectReference(mirror))) = current_thread(&mut thread, &mut cm, vec![])
+        else {
+            panic!("no mirror for the current thread");
+        };
+        assert!(is_current_thread(
+            &thread,
+            Some(&Slot::ObjectReference(mirror.clone()))
+        ));
+        let name = |cm: &ClassManager| match mirror.get_field_by_name(cm, "name") {
+            Some(Slot::ObjectReference(name)) => read_string(&name),
+            _ => None,
+        };
+        assert_eq!(name(&cm).as_deref(), Some("main"));
+
+        // Renamed from the VM
+        thread.set_name(&mut cm, "renamed").unwrap();
+        assert_eq!(name(&cm).as_deref(), Some("renamed"));
+
+        // Renamed from Java
+        let worker = Slot::ObjectReference(new_string(&mut cm, "worker").unwrap());
+        let this = Slot::ObjectReference(mirror.clone());
+        set_native_name(&mut thread, &mut cm, vec![this, worker]).unwrap();
+        assert_eq!(thread.name, "worker");
+
+        let mut vm = test_vm();
+        let same = vm.invoke_static("ThreadMirrors", "sameMirror", "()Z", vec![]);
+        assert!(matches!(same, Ok(Some(Slot::Int(1)))));
+        let name = Slot::ObjectReference(new_string(vm.class_manager_mut(), "renamed").unwrap());
+        let renamed = vm.invoke_static(
+            "ThreadMirrors",
+            "rename",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            vec![name],
+        );
+        let Ok(Some(Slot::ObjectReference(renamed))) = renamed else {
+            panic!("Unexpected result: {:?}", renamed);
+        };
+        assert_eq!(read_string(&renamed).as_deref(), Some("renamed"));
+    }
+}
@@ -0,0 +1,418 @@
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use reader::descriptor::{BaseType, FieldType};
+
+use super::{read_instruction, InstructionError, Opcode};
+use crate::class::ClassId;
+
+/// Decoded instruction cache of a method.
+///
+/// The bytecode is decoded once, the first time the method is executed, into
+/// a flat [Program] of instructions. The cached instructions can then be
+/// rewritten to their quickened equivalents once they have been resolved.
+#[derive(Debug)]
+pub struct InstructionCache {
+    bytecode: Vec<u8>,
+    program: OnceLock<Program>,
+    accessor: OnceLock<Option<Accessor>>,
+    accessor_cache: AccessorCache,
+}
+
+/// A method whose code only gets or sets a field of its receiver, which the
+/// invoke instructions run without pushing a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accessor {
+    /// `aload_0; getfield #index; xreturn`
+    Getter(u16, ValueType),
+    /// `aload_0; xload_1; putfield #index; return`
+    Setter(u16, ValueType),
+}
+
+impl Accessor {
+    /// Recognize the code of a getter or a setter.
+    fn recognize(bytecode: &[u8]) -> Option<Self> {
+        match *bytecode {
+            // ireturn, lreturn, freturn, dreturn or areturn
+            [0x2a, 0xb4, high, low, opcode @ 0xac..=0xb0] => Some(Accessor::Getter(
+                u16::from_be_bytes([high, low]),
+                ValueType::ALL[(opcode - 0xac) as usize],
+            )),
+            // iload_1, lload_1, fload_1, dload_1 or aload_1
+            [0x2a, opcode @ (0x1b | 0x1f | 0x23 | 0x27 | 0x2b), 0xb5, high, low, 0xb1] => {
+                Some(Accessor::Setter(
+                    u16::from_be_bytes([high, low]),
+                    ValueType::ALL[((opcode - 0x1b) / 4) as usize],
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Inline cache of an [Accessor], holding the field it resolved for the last
+/// class of its receivers.
+///
+/// A receiver of the same class skips the resolution of the field, its access
+/// and type checks, a receiver of another class replacing the cached field
+/// once resolved.
+#[derive(Debug, Default)]
+pub struct AccessorCache {
+    field: Mutex<Option<CachedField>>,
+    hits: AtomicUsize,
+}
+
+/// Field resolved by an [Accessor] for the receivers of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedField {
+    /// The class of the receivers.
+    pub receiver: ClassId,
+    /// The index of the field in the receivers.
+    pub slot: usize,
+    /// The type of the field if it is primitive, the ints stored being narrowed to it.
+    pub base_type: Option<BaseType>,
+}
+
+impl AccessorCache {
+    /// Get the field resolved for the receivers of the given class, if cached.
+    pub fn get(&self, receiver: ClassId) -> Option<CachedField> {
+        let field = self.field.lock().expect("accessor cache lock poisoned");
+        let cached = field.as_ref().filter(|field| field.receiver == receiver)?;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(cached.clone())
+    }
+
+    /// Cache the field resolved for the receivers of a class, replacing the previous one.
+    pub fn set(&self, field: CachedField) {
+        *self.field.lock().expect("accessor cache lock poisoned") = Some(field);
+    }
+
+    /// Number of calls whose receiver was of the class of the cached field.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
+/// Type of the value got or set by an [Accessor], given by its return or load
+/// instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Int,
+    Long,
+    Float,
+    Double,
+    Reference,
+}
+
+impl ValueType {
+    /// In the order of the typed instructions, e.g. `ireturn` to `areturn`.
+    const ALL: [ValueType; 5] = [
+        ValueType::Int,
+        ValueType::Long,
+        ValueType::Float,
+        ValueType::Double,
+        ValueType::Reference,
+    ];
+
+    /// Check that a value of the given type (of a field, a parameter or a
+    /// return value) has this type on the operand stack.
+    pub fn holds(self, field_type: &FieldType) -> bool {
+        match field_type {
+            FieldType::BaseType(BaseType::Long) => self == ValueType::Long,
+            FieldType::BaseType(BaseType::Float) => self == ValueType::Float,
+            FieldType::BaseType(BaseType::Double) => self == ValueType::Double,
+            FieldType::BaseType(_) => self == ValueType::Int,
+            FieldType::ObjectType(_) | FieldType::ArrayType(_) => self == ValueType::Reference,
+        }
+    }
+}
+
+/// Flat list of the decoded instructions of a method.
+///
+/// Instructions are addressed by their index in the list, the pc of the next
+/// instruction is simply found at the next index.
+///
+/// Decoding stops at the first invalid instruction, the instructions after it
+/// are not part of the program and must be decoded on demand.
+#[derive(Debug)]
+pub struct Program {
+    opcodes: Vec<Opcode>,
+    /// The quickened instructions, replacing the decoded ones.
+    ///
+    /// An instruction can only be quickened once, which allows fetching
+    /// instructions without locking.
+    quickened: Vec<OnceLock<Opcode>>,
+    /// The pc of each instruction
+    pcs: Vec<usize>,
+    /// The index of the instruction starting at each pc, `usize::MAX` if no
+    /// instruction starts at this pc.
+    indices: Vec<usize>,
+    /// The pc where the decoding stopped, the length of the bytecode if all
+    /// the instructions are valid.
+    decoded_length: usize,
+}
+
+impl Program {
+    /// Decode the bytecode of a method, up to the first invalid instruction.
+    fn decode(bytecode: &[u8]) -> Self {
+        let mut opcodes = Vec::new();
+        let mut pcs = Vec::new();
+        let mut indices = vec![usize::MAX; bytecode.len()];
+        let mut reader = Cursor::new(bytecode);
+        let mut pc = 0;
+        while pc < bytecode.len() {
+            reader.set_position(pc as u64);
+            let Ok((size, opcode)) = read_instruction(&mut reader) else {
+                log::debug!("Bytecode decoding stopped at pc {}", pc);
+                break;
+            };
+            indices[pc] = opcodes.len();
+            opcodes.push(opcode);
+            pcs.push(pc);
+            pc += size;
+        }
+        Self {
+            quickened: opcodes.iter().map(|_| OnceLock::new()).collect(),
+            opcodes,
+            pcs,
+            indices,
+            decoded_length: pc.min(bytecode.len()),
+        }
+    }
+
+    /// Check that the branch targets of the decoded instructions are the pc of
+    /// an instruction of the method.
+    ///
+    /// Returns the pc of the first invalid branch and its target otherwise.
+    /// The targets at or after the first invalid instruction are rejected, as
+    /// no instruction has been decoded there.
+    pub fn check_branches(&self) -> Result<(), (usize, i64)> {
+        for (opcode, pc) in self.opcodes.iter().zip(&self.pcs) {
+            for target in opcode.branch_targets() {
+                let valid = match usize::try_from(target) {
+                    Ok(target) if target < self.decoded_length => {
+                        self.indices[target] != usize::MAX
+                    }
+                    _ => false,
+                };
+                if !valid {
+                    return Err((*pc, target));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the index of the instruction starting at the given pc.
+    pub fn index_of(&self, pc: usize) -> Option<usize> {
+        self.indices
+            .get(pc)
+            .copied()
+            .filter(|index| *index != usize::MAX)
+    }
+
+    /// Get the pc of the instruction at the given index.
+    pub fn pc_of(&self, index: usize) -> Option<usize> {
+        self.pcs.get(index).copied()
+    }
+
+    /// Get the instruction at the given index.
+    pub fn get(&self, index: usize) -> Option<&Opcode> {
+        match self.quickened.get(index)?.get() {
+            Some(quickened) => Some(quickened),
+            None => self.opcodes.get(index),
+        }
+    }
+
+    /// Replace the instruction at the given index.
+    ///
+    /// Only the first replacement of an instruction is taken into account.
+    pub fn rewrite(&self, index: usize, opcode: Opcode) {
+        if let Some(entry) = self.quickened.get(index) {
+            log::trace!("Quickening instruction #{}: {:?}", index, opcode);
+            let _ = entry.set(opcode);
+        }
+    }
+
+    /// Number of decoded instructions.
+    pub fn len(&self) -> usize {
+        self.pcs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pcs.is_empty()
+    }
+}
+
+impl InstructionCache {
+    /// Create an empty cache for the given bytecode
+    pub fn new(bytecode: Vec<u8>) -> Self {
+        Self {
+            bytecode,
+            program: OnceLock::new(),
+            accessor: OnceLock::new(),
+            accessor_cache: AccessorCache::default(),
+        }
+    }
+
+    /// Get the field accessed if the method is a getter or a setter, see [Accessor].
+    pub fn accessor(&self) -> Option<Accessor> {
+        *self
+            .accessor
+            .get_or_init(|| Accessor::recognize(&self.bytecode))
+    }
+
+    /// Get the inline cache of the accessor of the method, see [AccessorCache].
+    pub fn accessor_cache(&self) -> &AccessorCache {
+        &self.accessor_cache
+    }
+
+    /// Get the decoded program, decoding the bytecode if necessary
+    pub fn program(&self) -> &Program {
+        self.program.get_or_init(|| Program::decode(&self.bytecode))
+    }
+
+    /// Get the instruction at the given pc
+    pub fn get(&self, pc: usize) -> Result<Opcode, InstructionError> {
+        let program = self.program();
+        match program.index_of(pc).and_then(|index| program.get(index)) {
+            Some(opcode) => Ok(opcode.clone()),
+            None => self.decode_at(pc),
+        }
+    }
+
+    /// Get the original opcode of the instruction at the given pc, before any quickening
+    pub fn opcode_at(&self, pc: usize) -> Option<u8> {
+        self.bytecode.get(pc).copied()
+    }
+
+    /// Decode the instruction at the given pc, without caching it
+    ///
+    /// This is only needed for the instructions that are not part of the program.
+    pub fn decode_at(&self, pc: usize) -> Result<Opcode, InstructionError> {
+        let mut reader = Cursor::new(self.bytecode.as_slice());
+        reader.set_position(pc as u64);
+        let (_, opcode) = read_instruction(&mut reader)?;
+        Ok(opcode)
+    }
+
+    /// Replace the instruction at the given pc
+    ///
+    /// The new instruction must have the same behavior and the same length
+    /// as the original one.
+    pub fn rewrite(&self, pc: usize, opcode: Opcode) {
+        let program = self.program();
+        if let Some(index) = program.index_of(pc) {
+            program.rewrite(index, opcode);
+        }
+    }
+
+    /// Get the raw bytecode of the method
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn accessors() {
+        let accessor = |bytecode: &[u8]| InstructionCache::new(bytecode.to_vec()).accessor();
+        assert_eq!(
+            accessor(&[0x2a, 0xb4, 0x00, 0x07, 0xac]),
+            Some(Accessor::Getter(7, ValueType::Int))
+        );
+        assert_eq!(
+            accessor(&[0x2a, 0xb4, 0x01, 0x02, 0xb0]),
+            Some(Accessor::Getter(258, ValueType::Reference))
+        );
+        assert_eq!(
+            accessor(&[0x2a, 0x1f, 0xb5, 0x00, 0x03, 0xb1]),
+            Some(Accessor::Setter(3, ValueType::Long))
+        );
+        assert_eq!(
+            accessor(&[0x2a, 0x27, 0xb5, 0x00, 0x03, 0xb1]),
+            Some(Accessor::Setter(3, ValueType::Double))
+        );
+        // getstatic, a return without value, a setter of its second argument
+        assert_eq!(accessor(&[0x2a, 0xb2, 0x00, 0x07, 0xac]), None);
+        assert_eq!(accessor(&[0x2a, 0xb4, 0x00, 0x07, 0xb1]), None);
+        assert_eq!(accessor(&[0x2a, 0x1c, 0xb5, 0x00, 0x03, 0xb1]), None);
+        assert_eq!(accessor(&[0x2a, 0xb4, 0x00, 0x07, 0xac, 0x00]), None);
+    }
+
+    #[test]
+    fn decode_and_rewrite() {
+        // ldc #1; iconst_1; return
+        let cache = InstructionCache::new(vec![0x12, 0x01, 0x04, 0xb1]);
+        assert!(matches!(cache.get(0).unwrap(), Opcode::Ldc(1)));
+        assert!(matches!(cache.get(2).unwrap(), Opcode::IConst1));
+
+        cache.rewrite(0, Opcode::LdcQuickInt(42, 2));
+        assert!(matches!(cache.get(0).unwrap(), Opcode::LdcQuickInt(42, 2)));
+        assert!(matches!(cache.get(3).unwrap(), Opcode::Return));
+    }
+
+    #[test]
+    fn flat_program() {
+        // iconst_1; goto -1; <invalid>
+        let cache = InstructionCache::new(vec![0x04, 0xa7, 0xff, 0xff, 0xe0]);
+        let program = cache.program();
+        assert_eq!(program.len(), 2);
+        assert_eq!(program.index_of(1), Some(1));
+        assert_eq!(program.index_of(2), None);
+        assert_eq!(program.pc_of(1), Some(1));
+        assert!(cache.get(4).is_err());
+        assert!(program.check_branches().is_ok());
+    }
+
+    #[test]
+    fn branch_targets() {
+        // iconst_1; ifeq +6; goto -4; return
+        let program = InstructionCache::new(vec![0x04, 0x99, 0x00, 0x06, 0xa7, 0xff, 0xfc, 0xb1]);
+        assert!(program.program().check_branches().is_ok());
+        // iconst_1; goto +1 (operand of the goto); return
+        let program = InstructionCache::new(vec![0x04, 0xa7, 0x00, 0x01, 0xb1]);
+        assert_eq!(program.program().check_branches(), Err((1, 2)));
+        // goto -1 (before the code); return
+        let program = InstructionCache::new(vec![0xa7, 0xff, 0xff, 0xb1]);
+        assert_eq!(program.program().check_branches(), Err((0, -1)));
+        // goto +4 (after the code)
+        let program = InstructionCache::new(vec![0xa7, 0x00, 0x04]);
+        assert_eq!(program.program().check_branches(), Err((0, 4)));
+        // iconst_1; goto +3 (the invalid instruction); <invalid>; return
+        let program = InstructionCache::new(vec![0x04, 0xa7, 0x00, 0x03, 0xe0, 0xb1]);
+        assert_eq!(program.program().check_branches(), Err((1, 4)));
+        // iconst_1; goto +4 (after the invalid instruction); <invalid>; return
+        let program = InstructionCache::new(vec![0x04, 0xa7, 0x00, 0x04, 0xe0, 0xb1]);
+        assert_eq!(program.program().check_branches(), Err((1, 5)));
+    }
+
+    #[test]
+    fn switch_targets() {
+        // iconst_1; tableswitch (2 bytes of padding) 0..=1; iconst_0; iconst_1; iconst_2
+        let mut code = vec![0x04, 0xaa, 0, 0];
+        for value in [25, 0, 1, 23, 24] {
+            code.extend_from_slice(&i32::to_be_bytes(value));
+        }
+        code.extend_from_slice(&[0x03, 0x04, 0x05]);
+        let cache = InstructionCache::new(code);
+        let program = cache.program();
+        assert_eq!(program.pc_of(2), Some(24));
+        assert_eq!(program.get(1).unwrap().branch_targets(), vec![26, 24, 25]);
+        assert!(program.check_branches().is_ok());
+
+        // lookupswitch (3 bytes of padding) 5 => iconst_0, default => iconst_1
+        let mut code = vec![0xab, 0, 0, 0];
+        for value in [21, 1, 5, 20] {
+            code.extend_from_slice(&i32::to_be_bytes(value));
+        }
+        code.extend_from_slice(&[0x03, 0x04]);
+        let cache = InstructionCache::new(code);
+        assert_eq!(cache.program().pc_of(1), Some(20));
+        assert_eq!(cache.get(0).unwrap().branch_targets(), vec![21, 20]);
+    }
+}
@@ -1,4 +1,4 @@
-use super::{InstructionError, InstructionSuccess};
+use super::{BranchTarget, InstructionError, InstructionSuccess};
 use crate::thread::Slot;
 use crate::thread::Thread;
 use crate::{if_acmpx, if_icmpx, ifx};
@@ -23,9 +23,9 @@ if_acmpx!(if_acmpne, false);
 
 /// `lcmp` compares two longs and pushes the result onto the stack.
 pub fn lcmp(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let value2 = frame.operand_stack.pop().unwrap();
-    let value1 = frame.operand_stack.pop().unwrap();
+    let frame = thread.executing_frame()?;
+    let value2 = frame.pop()?;
+    let value1 = frame.pop()?;
     let result = match (value1, value2) {
         (Slot::Long(value1), Slot::Long(value2)) => {
             if value1 > value2 {
@@ -50,9 +50,9 @@ pub fn lcmp(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError>
 ///
 /// If either value is NaN, then -1 is pushed onto the stack.
 pub fn fcmpl(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let value2 = frame.operand_stack.pop().unwrap();
-    let value1 = frame.operand_stack.pop().unwrap();
+    let frame = thread.executing_frame()?;
+    let value2 = frame.pop()?;
+    let value1 = frame.pop()?;
     let result = match (value1, value2) {
         (Slot::Float(value1), Slot::Float(value2)) => {
             if value1 == f32::NAN || value2 == f32::NAN {
@@ -79,9 +79,9 @@ pub fn fcmpl(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError
 ///
 /// If either value is NaN, then 1 is pushed onto the stack.
 pub fn fcmpg(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let value2 = frame.operand_stack.pop().unwrap();
-    let value1 = frame.operand_stack.pop().unwrap();
+    let frame = thread.executing_frame()?;
+    let value2 = frame.pop()?;
+    let value1 = frame.pop()?;
     let result = match (value1, value2) {
         (Slot::Float(value1), Slot::Float(value2)) => {
             if value1 == f32::NAN || value2 == f32::NAN {
@@ -108,9 +108,9 @@ pub fn fcmpg(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError
 ///
 /// If either value is NaN, then -1 is pushed onto the stack.
 pub fn dcmpl(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let value2 = frame.operand_stack.pop().unwrap();
-    let value1 = frame.operand_stack.pop().unwrap();
+    let frame = thread.executing_frame()?;
+    let value2 = frame.pop()?;
+    let value1 = frame.pop()?;
     let result = match (value1, value2) {
         (Slot::Double(value1), Slot::Double(value2)) => {
             if value1 == f64::NAN || value2 == f64::NAN {
@@ -137,9 +137,9 @@ pub fn dcmpl(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError
 ///
 /// If either value is NaN, then 1 is pushed onto the stack.
 pub fn dcmpg(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let value2 = frame.operand_stack.pop().unwrap();
-    let value1 = frame.operand_stack.pop().unwrap();
+    let frame = thread.executing_frame()?;
+    let value2 = frame.pop()?;
+    let value1 = frame.pop()?;
     let result = match (value1, value2) {
         (Slot::Double(value1), Slot::Double(value2)) => {
             if value1 == f64::NAN || value2 == f64::NAN {
@@ -167,11 +167,11 @@ mod macros {
     macro_rules! ifx {
         ($name:ident, $cond:tt) => {
             /// Branch if top of stack comparison with zero succeeds.
-            pub fn $name(thread: &mut Thread, offset: i16) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
+            pub fn $name(thread: &mut Thread, target: BranchTarget) -> Result<InstructionSuccess, InstructionError> {
+                let frame = thread.executing_frame()?;
                 if let Some(Slot::Int(value)) = frame.operand_stack.pop() {
                     if value $cond 0 {
-                        Ok(InstructionSuccess::JumpRelative(offset as isize))
+                        Ok(InstructionSuccess::JumpAbsolute(target as usize))
                     } else {
                         Ok(InstructionSuccess::Next(3))
                     }
@@ -186,12 +186,12 @@ mod macros {
     macro_rules! if_icmpx {
         ($name:ident, $cond:tt) => {
             /// Branch if int comparison succeeds.
-            pub fn $name(thread: &mut Thread, offset: i16) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
+            pub fn $name(thread: &mut Thread, target: BranchTarget) -> Result<InstructionSuccess, InstructionError> {
+                let frame = thread.executing_frame()?;
                 if let Some(Slot::Int(value2)) = frame.operand_stack.pop() {
                     if let Some(Slot::Int(value1)) = frame.operand_stack.pop() {
                         if value1 $cond value2 {
-                            Ok(InstructionSuccess::JumpRelative(offset as isize))
+                            Ok(InstructionSuccess::JumpAbsolute(target as usize))
                         } else {
                             Ok(InstructionSuccess::Next(3))
                         }
@@ -211,40 +211,19 @@ mod macros {
             /// Branch if reference comparison succeeds.
             pub fn $name(
                 thread: &mut Thread,
-                offset: i16,
+                target: BranchTarget,
             ) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(value2) = frame.operand_stack.pop() {
-                    if let Some(value1) = frame.operand_stack.pop() {
-                        let eqcheck = match (value1, value2) {
-                            (Slot::UndefinedReference, Slot::UndefinedReference) => true,
-                            (Slot::ObjectReference(obj1), Slot::ObjectReference(obj2)) => {
-                                std::ptr::eq(obj1.as_ref(), obj2.as_ref())
-                            }
-                            (Slot::ArrayReference(arr1), Slot::ArrayReference(arr2)) => {
-                                std::ptr::eq(arr1.as_ref(), arr2.as_ref())
-                            }
-                            (x, y) if x.is_reference() && y.is_reference() => false,
-                            _ => {
-                                return Err(InstructionError::InvalidState {
-                                    context: "Expected reference on top of operand stack".into(),
-                                });
-                            }
-                        };
-                        if eqcheck == $on_eq {
-                            Ok(InstructionSuccess::JumpRelative(offset as isize))
-                        } else {
-                            Ok(InstructionSuccess::Next(3))
-                        }
-                    } else {
-                        Err(InstructionError::InvalidState {
-                            context: "Expected reference on top of operand stack".into(),
-                        })
-                    }
-                } else {
-                    Err(InstructionError::InvalidState {
+                let frame = thread.executing_frame()?;
+                let (value2, value1) = frame.pop2()?;
+                let Some(eqcheck) = value1.same_reference(&value2) else {
+                    return Err(InstructionError::InvalidState {
                         context: "Expected reference on top of operand stack".into(),
-                    })
+                    });
+                };
+                if eqcheck == $on_eq {
+                    Ok(InstructionSuccess::JumpAbsolute(target as usize))
+                } else {
+                    Ok(InstructionSuccess::Next(3))
                 }
             }
         };
@@ -1,8 +1,17 @@
-use super::{InstructionError, InstructionSuccess};
+use reader::descriptor::{BaseType, FieldType};
+
+use super::{InstructionError, InstructionSuccess, Opcode};
+use crate::alloc::ObjectRef;
+use crate::bootstrap::resolve_dynamic_constant;
+use crate::class::ClassId;
 use crate::class_manager::{ClassManager, LoadedClass};
-use crate::constant_pool::ConstantPoolEntry;
+use crate::constant_pool::{
+    resolve_string_constant, ConstantPool, ConstantPoolEntry, RuntimeConstantKind,
+};
+use crate::debug::display_constant;
 use crate::thread::Slot;
 use crate::thread::Thread;
+use crate::value::type_descriptor;
 use crate::xconst_i;
 
 pub fn nop(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
@@ -29,22 +38,22 @@ xconst_i!(dconst_1, Double, 1.0);
 
 /// `aconst_null` pushes a null reference onto the stack.
 pub fn aconst_null(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    frame.operand_stack.push(Slot::UndefinedReference);
+    let frame = thread.executing_frame()?;
+    frame.push_value(Slot::UndefinedReference)?;
     Ok(InstructionSuccess::Next(1))
 }
 
 /// `bipush` pushes a byte onto the stack as an integer.
 pub fn bipush(thread: &mut Thread, value: i8) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    frame.operand_stack.push(Slot::Int(value as i32));
+    let frame = thread.executing_frame()?;
+    frame.push_value(Slot::Int(value as i32))?;
     Ok(InstructionSuccess::Next(2))
 }
 
 /// `sipush` pushes a short onto the stack as an integer.
 pub fn sipush(thread: &mut Thread, value: i16) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    frame.operand_stack.push(Slot::Int(value as i32));
+    let frame = thread.executing_frame()?;
+    frame.push_value(Slot::Int(value as i32))?;
     Ok(InstructionSuccess::Next(3))
 }
 
@@ -54,15 +63,64 @@ pub fn ldc(
     cm: &mut ClassManager,
     value: u8,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let pc = thread.pc;
-    let frame = thread.current_frame_mut().unwrap();
+    load_constant(thread, cm, value as u16, LdcWidth::Narrow)
+}
+
+/// `ldc_w` pushes a constant from the constant pool onto the stack.
+///
+/// Same as `ldc`, but the index is 16 bits wide.
+pub fn ldc_w(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    value: u16,
+) -> Result<InstructionSuccess, InstructionError> {
+    load_constant(thread, cm, value, LdcWidth::Wide)
+}
+
+/// Width of the constant pool index of the instructions loading a constant
+/// taking a single slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LdcWidth {
+    /// `ldc`, with an 8 bits index
+    Narrow,
+    /// `ldc_w`, with a 16 bits index
+    Wide,
+}
+
+impl LdcWidth {
+    fn instruction(self) -> &'static str {
+        match self {
+            LdcWidth::Narrow => "ldc",
+            LdcWidth::Wide => "ldc_w",
+        }
+    }
+
+    /// Length of the instruction, its opcode included.
+    fn length(self) -> usize {
+        match self {
+            LdcWidth::Narrow => 2,
+            LdcWidth::Wide => 3,
+        }
+    }
+}
+
+/// Push the constant loaded by `ldc` or `ldc_w` onto the stack.
+fn load_constant(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    index: u16,
+    width: LdcWidth,
+) -> Result<InstructionSuccess, InstructionError> {
+    let instruction = width.instruction();
+    let pc = thread.pc();
+    let frame = thread.executing_frame()?;
     let class = frame.class;
-    let LoadedClass::Loaded(class) = cm.get_class_by_id(class).unwrap() else {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
         return Err(InstructionError::InvalidState {
             context: "Current class is not loaded!?".into(),
         });
     };
-    let constant = class.constant_pool.get(value as usize).unwrap();
+    let constant = loadable_constant(&class.constant_pool, index, instruction, false)?;
     match constant {
         ConstantPoolEntry::IntegerConstant(value) => {
             frame.operand_stack.push(Slot::Int(*value));
@@ -70,59 +128,64 @@ pub fn ldc(
         ConstantPoolEntry::FloatConstant(value) => {
             frame.operand_stack.push(Slot::Float(*value));
         }
-        ConstantPoolEntry::ClassReference(value) => {
-            let class_obj = cm.get_class_object(&value.clone()).unwrap();
+        ConstantPoolEntry::ClassReference(class_id) => {
+            let class_id = *class_id;
+            let class_obj = cm.get_class_object(class_id).map_err(|err| {
+                InstructionError::ClassLoadingError {
+                    class_name: cm.get_class_by_id(class_id).map_or_else(
+                        || format!("ClassId({})", class_id.0),
+                        |class| class.name().into(),
+                    ),
+                    source: Box::new(err),
+                }
+            })?;
+            frame.operand_stack.push(Slot::ObjectReference(class_obj));
+        }
+        ConstantPoolEntry::ArrayReference(array_type) => {
+            let array_type = array_type.clone();
+            let class_obj = array_class_object(cm, &array_type)?;
             frame.operand_stack.push(Slot::ObjectReference(class_obj));
         }
-        ConstantPoolEntry::StringReference(value) => {
-            frame.operand_stack.push(Slot::ObjectReference(value.clone()));
+        ConstantPoolEntry::StringReference(_) => {
+            let class_id = class.id;
+            let obj = resolve_string_constant(cm, class_id, index as usize)?;
+            frame.operand_stack.push(Slot::ObjectReference(obj));
+        }
+        ConstantPoolEntry::DynamicConstant(_) => {
+            let class_id = class.id;
+            let value = resolve_dynamic_constant(cm, class_id, index)?;
+            frame.operand_stack.push(value);
         }
-        // TODO: Implement String reference and Class reference.
         _ => {
             log::error!(
-                "ldc - invalid constant pool - running class {}, method {}, pc {}",
+                "{} - invalid constant pool - running class {}, method {}, pc {}",
+                instruction,
                 class.name,
                 frame.method,
                 pc
             );
             return Err(InstructionError::InvalidState {
-                context: format!("Invalid constant pool entry at {}: {:?}", value, constant),
+                context: format!(
+                    "Invalid constant pool entry at {}: {}",
+                    index,
+                    display_constant(cm, constant)
+                ),
             });
         }
     }
-    Ok(InstructionSuccess::Next(2))
+    Ok(InstructionSuccess::Next(width.length()))
 }
 
-/// `ldc_w` pushes a constant from the constant pool onto the stack.
-pub fn ldc_w(
-    thread: &mut Thread,
+/// Get the mirror of an array class constant.
+fn array_class_object(
     cm: &mut ClassManager,
-    value: u16,
-) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let class = frame.class;
-    let LoadedClass::Loaded(class) = cm.get_class_by_id(class).unwrap() else {
-        return Err(InstructionError::InvalidState {
-            context: "Current class is not loaded!?".into(),
-        });
-    };
-    let constant = class.constant_pool.get(value as usize).unwrap();
-
-    match constant {
-        ConstantPoolEntry::IntegerConstant(value) => {
-            frame.operand_stack.push(Slot::Int(*value));
-        }
-        ConstantPoolEntry::FloatConstant(value) => {
-            frame.operand_stack.push(Slot::Float(*value));
-        }
-        // TODO: Implement String reference and Class reference.
-        _ => {
-            return Err(InstructionError::InvalidState {
-                context: format!("Invalid constant pool entry at {}: {:?}", value, constant),
-            });
-        }
-    }
-    Ok(InstructionSuccess::Next(3))
+    array_type: &FieldType,
+) -> Result<ObjectRef, InstructionError> {
+    cm.get_array_class_object(array_type)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: type_descriptor(array_type),
+            source: Box::new(err),
+        })
 }
 
 /// `ldc2_w` pushes a long/double constant from the constant pool onto the stack.
@@ -131,14 +194,14 @@ pub fn ldc2_w(
     cm: &mut ClassManager,
     value: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
+    let frame = thread.executing_frame()?;
     let class = frame.class;
-    let LoadedClass::Loaded(class) = cm.get_class_by_id(class).unwrap() else {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
         return Err(InstructionError::InvalidState {
             context: "Current class is not loaded!?".into(),
         });
     };
-    let constant = class.constant_pool.get(value as usize).unwrap();
+    let constant = loadable_constant(&class.constant_pool, value, "ldc2_w", true)?;
 
     match constant {
         ConstantPoolEntry::LongConstant(value) => {
@@ -147,33 +210,284 @@ pub fn ldc2_w(
         ConstantPoolEntry::DoubleConstant(value) => {
             frame.operand_stack.push(Slot::Double(*value));
         }
-        ConstantPoolEntry::ClassReference(value) => {
-            let class_obj = cm.get_class_object(&value.clone()).unwrap();
-            frame.operand_stack.push(Slot::ObjectReference(class_obj));
-        }
-        ConstantPoolEntry::StringReference(value) => {
-            frame.operand_stack.push(Slot::ObjectReference(value.clone()));
+        ConstantPoolEntry::DynamicConstant(_) => {
+            let class_id = class.id;
+            let value = resolve_dynamic_constant(cm, class_id, value)?;
+            frame.operand_stack.push(value);
         }
-        // TODO: Implement dynamic reference.
         _ => {
             return Err(InstructionError::InvalidState {
-                context: format!("Invalid constant pool entry at {}: {:?}", value, constant),
+                context: format!(
+                    "Invalid constant pool entry at {}: {}",
+                    value,
+                    display_constant(cm, constant)
+                ),
             });
         }
     }
     Ok(InstructionSuccess::Next(3))
 }
 
+/// Quickened `ldc`, `ldc_w` and `ldc2_w`, pushes an already resolved constant onto the stack.
+pub fn ldc_quick(
+    thread: &mut Thread,
+    value: Slot,
+    size: usize,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    frame.operand_stack.push(value);
+    Ok(InstructionSuccess::Next(size))
+}
+
+/// Get the quickened equivalent of a `ldc` family instruction.
+///
+/// Only primitive constants are quickened, references are left as is.
+/// Constants of the wrong kind for the instruction are not quickened either,
+/// so that the instruction itself reports the error.
+pub(crate) fn quicken_ldc(
+    cm: &ClassManager,
+    class: ClassId,
+    index: u16,
+    size: usize,
+    category2: bool,
+) -> Option<Opcode> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
+        return None;
+    };
+    match loadable_constant(&class.constant_pool, index, "ldc", category2).ok()? {
+        ConstantPoolEntry::IntegerConstant(value) => Some(Opcode::LdcQuickInt(*value, size)),
+        ConstantPoolEntry::FloatConstant(value) => Some(Opcode::LdcQuickFloat(*value, size)),
+        ConstantPoolEntry::LongConstant(value) => Some(Opcode::LdcQuickLong(*value)),
+        ConstantPoolEntry::DoubleConstant(value) => Some(Opcode::LdcQuickDouble(*value)),
+        _ => None,
+    }
+}
+
+/// Get the constant loaded by a `ldc` family instruction, checking its kind.
+///
+/// Long and double constants (category 2) can only be loaded by `ldc2_w`,
+/// which cannot load any other constant.
+fn loadable_constant<'a>(
+    constant_pool: &'a ConstantPool,
+    index: u16,
+    instruction: &'static str,
+    category2: bool,
+) -> Result<&'a ConstantPoolEntry, InstructionError> {
+    let invalid = |context: String| InstructionError::InvalidConstant {
+        instruction,
+        index,
+        context,
+    };
+    let constant = match constant_pool.kind(index as usize) {
+        RuntimeConstantKind::Entry(constant) => constant,
+        RuntimeConstantKind::Ignored(tag) => {
+            return Err(invalid(format!("{} is not a loadable constant", tag)))
+        }
+        RuntimeConstantKind::Unusable => return Err(invalid("no constant at this index".into())),
+    };
+    if !constant.is_loadable() {
+        return Err(invalid(format!(
+            "{} is not a loadable constant",
+            constant.tag_name()
+        )));
+    }
+    let is_category2 = match constant {
+        ConstantPoolEntry::LongConstant(_) | ConstantPoolEntry::DoubleConstant(_) => true,
+        ConstantPoolEntry::DynamicConstant(constant) => matches!(
+            constant.descriptor.field_type(),
+            FieldType::BaseType(BaseType::Long | BaseType::Double)
+        ),
+        _ => false,
+    };
+    if is_category2 != category2 {
+        return Err(invalid(format!(
+            "{} cannot be loaded by this instruction",
+            constant.tag_name()
+        )));
+    }
+    Ok(constant)
+}
+
 mod macros {
     #[macro_export]
     macro_rules! xconst_i {
         ($name:ident, $sloty:ident, $value:expr) => {
             /// Push a constant value onto the stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                frame.operand_stack.push(Slot::$sloty($value));
+                let frame = thread.executing_frame()?;
+                frame.push_value(Slot::$sloty($value))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constant_pool(entries: Vec<ConstantPoolEntry>) -> ConstantPool {
+        let mut constant_pool = ConstantPool::new(entries);
+        for index in 0..constant_pool.entries.len() {
+            constant_pool.mappings.push(index);
+        }
+        constant_pool
+    }
+
+    #[test]
+    fn ldc_constant_kinds() {
+        let cp = constant_pool(vec![
+            ConstantPoolEntry::IntegerConstant(42),
+            ConstantPoolEntry::FloatConstant(1.5),
+            ConstantPoolEntry::LongConstant(7),
+            ConstantPoolEntry::DoubleConstant(2.5),
+        ]);
+        assert!(matches!(
+            loadable_constant(&cp, 1, "ldc", false),
+            Ok(ConstantPoolEntry::IntegerConstant(42))
+        ));
+        assert!(loadable_constant(&cp, 2, "ldc_w", false).is_ok());
+        assert!(loadable_constant(&cp, 3, "ldc", false).is_err());
+        assert!(loadable_constant(&cp, 4, "ldc_w", false).is_err());
+
+        assert!(loadable_constant(&cp, 1, "ldc2_w", true).is_err());
+        assert!(loadable_constant(&cp, 2, "ldc2_w", true).is_err());
+        assert!(matches!(
+            loadable_constant(&cp, 3, "ldc2_w", true),
+            Ok(ConstantPoolEntry::LongConstant(7))
+        ));
+        assert!(loadable_constant(&cp, 4, "ldc2_w", true).is_ok());
+
+        assert!(loadable_constant(&cp, 0, "ldc", false).is_err());
+        assert!(loadable_constant(&cp, 5, "ldc", false).is_err());
+    }
+
+    #[test]
+    fn loadable_constant_kinds() {
+        use std::cell::OnceCell;
+
+        use reader::{
+            base::constant_pool::ReferenceKind,
+            descriptor::{self, UnqualifiedName},
+        };
+
+        use crate::constant_pool::{DynamicConstant, StringConstant};
+        use crate::descriptor_table::MethodDescriptorTable;
+
+        let field_descriptor = |descriptor| descriptor::parse_field_descriptor(descriptor).unwrap();
+        let dynamic = |descriptor| {
+            ConstantPoolEntry::DynamicConstant(DynamicConstant {
+                method_handle: 7,
+                arguments_ref: vec![],
+                name: UnqualifiedName::new("constant"),
+                descriptor: field_descriptor(descriptor),
+                value: OnceCell::new(),
+            })
+        };
+        let mut cp = constant_pool(vec![
+            ConstantPoolEntry::IntegerConstant(42),
+            ConstantPoolEntry::FloatConstant(1.5),
+            ConstantPoolEntry::LongConstant(7),
+            ConstantPoolEntry::DoubleConstant(2.5),
+            ConstantPoolEntry::StringReference(StringConstant {
+                value: "text".into(),
+                object: OnceCell::new(),
+            }),
+            ConstantPoolEntry::ClassReference(ClassId(3)),
+            ConstantPoolEntry::ArrayReference(field_descriptor("[I").field_type().clone()),
+            ConstantPoolEntry::MethodHandleReference(ReferenceKind::InvokeStatic, 9),
+            ConstantPoolEntry::MethodType(MethodDescriptorTable::new().intern("()V").unwrap()),
+            dynamic("Ljava/lang/Object;"),
+            dynamic("J"),
+            ConstantPoolEntry::FieldReference {
+                field_name: "field".into(),
+                field_descriptor: field_descriptor("I"),
+                implementor: ClassId(3),
+            },
+        ]);
+        cp.ignore("CONSTANT_Utf8");
+
+        for index in [1, 2, 5, 6, 7, 8, 9, 10] {
+            assert!(loadable_constant(&cp, index, "ldc", false).is_ok());
+            assert!(loadable_constant(&cp, index, "ldc2_w", true).is_err());
+        }
+        for index in [3, 4, 11] {
+            assert!(loadable_constant(&cp, index, "ldc2_w", true).is_ok());
+            assert!(loadable_constant(&cp, index, "ldc_w", false).is_err());
+        }
+
+        let error = |index| {
+            loadable_constant(&cp, index, "ldc", false)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(12),
+            "Invalid constant #12 for ldc: CONSTANT_Fieldref is not a loadable constant"
+        );
+        assert_eq!(
+            error(13),
+            "Invalid constant #13 for ldc: CONSTANT_Utf8 is not a loadable constant"
+        );
+        assert_eq!(
+            error(3),
+            "Invalid constant #3 for ldc: CONSTANT_Long cannot be loaded by this instruction"
+        );
+        assert_eq!(
+            error(0),
+            "Invalid constant #0 for ldc: no constant at this index"
+        );
+    }
+
+    #[test]
+    fn wide_constant_indices() {
+        use crate::alloc::read_string;
+        use crate::test_util::test_vm;
+
+        let mut vm = test_vm();
+        assert!(vm.describe_class("WideConstants").unwrap().constants > 256);
+        let mut invoke = |method: &str, descriptor: &str| {
+            vm.invoke_static("WideConstants", method, descriptor, vec![])
+                .unwrap()
+                .unwrap()
+        };
+
+        assert!(matches!(invoke("ints", "()I"), Slot::Int(30044850)));
+        assert!(matches!(invoke("floats", "()F"), Slot::Float(value) if value == 3.75));
+        let string = |slot: Slot| read_string(slot.as_object().unwrap());
+        assert_eq!(
+            string(invoke("narrow", "()Ljava/lang/String;")).unwrap(),
+            "narrow"
+        );
+        let wide = invoke("wide", "()Ljava/lang/String;");
+        assert_eq!(string(wide.clone()).unwrap(), "wide");
+        // The resolved string constant is reused by the following loads.
+        let again = invoke("wide", "()Ljava/lang/String;");
+        assert_eq!(wide.same_reference(&again), Some(true));
+
+        let class = invoke("type", "()Ljava/lang/Class;");
+        let cm = vm.class_manager_mut();
+        let greeter = cm.id_of_class("Greeter").unwrap();
+        let mirror = Slot::ObjectReference(cm.get_class_object(greeter).unwrap());
+        assert_eq!(class.same_reference(&mirror), Some(true));
+    }
+
+    #[test]
+    fn class_constant_errors() {
+        use crate::test_util::test_vm;
+        use crate::{thread::ExecutionError, VmError};
+
+        let mut vm = test_vm();
+        let result = vm.invoke_static("ClassConstants", "broken", "()Ljava/lang/Object;", vec![]);
+        let Err(VmError::Execution {
+            source:
+                ExecutionError::InstructionExecutionError {
+                    source: InstructionError::ClassLoadingError { class_name, .. },
+                },
+        }) = result
+        else {
+            panic!("unexpected result: {:?}", result);
+        };
+        assert_eq!(class_name, "FinalChild");
+    }
+}
@@ -1,51 +1,60 @@
+use reader::descriptor::{BaseType, FieldType};
+
 use super::LookupSwitch;
 use super::TableSwitch;
-use super::{InstructionError, InstructionSuccess};
+use super::{BranchTarget, InstructionError, InstructionSuccess};
+use crate::class_manager::{ClassManager, LoadedClass};
 use crate::thread::Slot;
-use crate::thread::Thread;
+use crate::thread::{Frame, Thread};
 use crate::xreturn;
 
 /// `goto` jumps to another instruction.
-pub fn goto(thread: &mut Thread, offset: i16) -> Result<InstructionSuccess, InstructionError> {
-    Ok(InstructionSuccess::JumpRelative(offset as isize))
+pub fn goto(target: BranchTarget) -> Result<InstructionSuccess, InstructionError> {
+    Ok(InstructionSuccess::JumpAbsolute(target as usize))
 }
 
 /// `goto_w` (wide variant) jumps to another instruction.
-pub fn goto_w(thread: &mut Thread, offset: i32) -> Result<InstructionSuccess, InstructionError> {
-    Ok(InstructionSuccess::JumpRelative(offset as isize))
+pub fn goto_w(target: BranchTarget) -> Result<InstructionSuccess, InstructionError> {
+    Ok(InstructionSuccess::JumpAbsolute(target as usize))
 }
 
 /// `jsr` pushes the address of the next instruction onto the stack and jumps to another instruction.
 ///
 /// The address of the next instruction is pushed onto the stack as a return address, 32-bit value.
-pub fn jsr(thread: &mut Thread, offset: i16) -> Result<InstructionSuccess, InstructionError> {
-    let pc = thread.pc as u32;
-    let frame = thread.current_frame_mut().unwrap();
+pub fn jsr(
+    thread: &mut Thread,
+    target: BranchTarget,
+) -> Result<InstructionSuccess, InstructionError> {
+    let pc = thread.pc() as u32;
+    let frame = thread.executing_frame()?;
     frame
         .operand_stack
         .push(Slot::ReturnAddress((pc + 3) as u32));
-    Ok(InstructionSuccess::JumpRelative(offset as isize))
+    Ok(InstructionSuccess::JumpAbsolute(target as usize))
 }
 
 /// `jsr_w` (wide variant) pushes the address of the next instruction onto the
 /// stack and jumps to another instruction.
 ///
 /// The address of the next instruction is pushed onto the stack as a return address, 32-bit value.
-pub fn jsr_w(thread: &mut Thread, offset: i32) -> Result<InstructionSuccess, InstructionError> {
-    let pc = thread.pc as u32;
-    let frame = thread.current_frame_mut().unwrap();
+pub fn jsr_w(
+    thread: &mut Thread,
+    target: BranchTarget,
+) -> Result<InstructionSuccess, InstructionError> {
+    let pc = thread.pc() as u32;
+    let frame = thread.executing_frame()?;
     frame
         .operand_stack
         .push(Slot::ReturnAddress((pc + 5) as u32));
-    Ok(InstructionSuccess::JumpRelative(offset as isize))
+    Ok(InstructionSuccess::JumpAbsolute(target as usize))
 }
 
 /// `ret` returns from a subroutine.
 ///
 /// The index is an unsigned byte that must be an index into the local variable array of the current frame.
-pub fn ret(thread: &mut Thread, index: u8) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let Slot::ReturnAddress(address) = frame.local_variables[index as usize] else {
+pub fn ret(thread: &mut Thread, index: u16) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let Slot::ReturnAddress(address) = *frame.local(index as usize)? else {
         return Err(InstructionError::InvalidState {
             context: format!("Expected return address at index {}", index),
         });
@@ -58,14 +67,14 @@ pub fn tableswitch(
     thread: &mut Thread,
     table: &TableSwitch,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let index = frame.operand_stack.pop().unwrap();
-    let offset = match index {
+    let frame = thread.executing_frame()?;
+    let index = frame.pop()?;
+    let target = match index {
         Slot::Int(index) => {
             if index < table.low || index > table.high {
                 table.default
             } else {
-                table.jump_offsets[(index - table.low) as usize]
+                table.jump_targets[(index - table.low) as usize]
             }
         }
         _ => {
@@ -74,7 +83,7 @@ pub fn tableswitch(
             })
         }
     };
-    Ok(InstructionSuccess::JumpRelative(offset as isize))
+    Ok(InstructionSuccess::JumpAbsolute(target as usize))
 }
 
 /// `lookupswitch` accesses jump table by key match and jumps.
@@ -82,12 +91,12 @@ pub fn lookupswitch(
     thread: &mut Thread,
     table: &LookupSwitch,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let key = frame.operand_stack.pop().unwrap();
-    let offset = match key {
+    let frame = thread.executing_frame()?;
+    let key = frame.pop()?;
+    let target = match key {
         Slot::Int(key) => {
-            if let Ok(index) = table.match_offsets.binary_search_by_key(&key, |(k, _)| *k) {
-                table.match_offsets[index].1
+            if let Ok(index) = table.match_targets.binary_search_by_key(&key, |(k, _)| *k) {
+                table.match_targets[index].1
             } else {
                 table.default
             }
@@ -98,23 +107,68 @@ pub fn lookupswitch(
             })
         }
     };
-    Ok(InstructionSuccess::JumpRelative(offset as isize))
+    Ok(InstructionSuccess::JumpAbsolute(target as usize))
 }
 
-/// `return` returns void from a method.
-pub fn vreturn(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    thread.pop_frame();
-    // TODO: implement monitor strategy for synchronized methods
-    if let Some(frame) = thread.current_frame_mut() {
-        let Some(Slot::InvokationReturnAddress(pc)) = frame.operand_stack.pop() else {
-            return Err(InstructionError::InvalidState {
-                context: "Expected invokation return address on the operand stack".into(),
-            });
-        };
-        Ok(InstructionSuccess::FrameChange(pc as usize))
-    } else {
-        Ok(InstructionSuccess::Completed)
+/// Check that a return instruction matches the return type of the current
+/// method, e.g. no `ireturn` in a method returning long.
+fn check_return(
+    thread: &Thread,
+    cm: &ClassManager,
+    instruction: &'static str,
+) -> Result<(), InstructionError> {
+    let Some(frame) = thread.current_frame() else {
+        return Ok(());
+    };
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
+        return Ok(());
+    };
+    let Some(method) = class.get_method_by_index(frame.method) else {
+        return Ok(());
+    };
+    let return_type = &method.descriptor.return_type;
+    let expected = match return_type {
+        None => "return",
+        Some(FieldType::BaseType(BaseType::Long)) => "lreturn",
+        Some(FieldType::BaseType(BaseType::Float)) => "freturn",
+        Some(FieldType::BaseType(BaseType::Double)) => "dreturn",
+        Some(FieldType::BaseType(_)) => "ireturn",
+        Some(FieldType::ObjectType(_) | FieldType::ArrayType(_)) => "areturn",
+    };
+    if instruction == expected {
+        return Ok(());
     }
+    Err(InstructionError::Verify {
+        context: format!(
+            "{} in {}.{}{}, a method returning {}",
+            instruction,
+            class.name,
+            method.name,
+            method.descriptor.as_str(),
+            return_type
+                .as_ref()
+                .map_or_else(|| "void".to_string(), FieldType::java_syntax)
+        ),
+    })
+}
+
+/// Get the frame of a return instruction, its value being popped before the frame.
+fn returning_frame(thread: &mut Thread) -> Result<&mut Frame, InstructionError> {
+    thread
+        .current_frame_mut()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "No frame to return from".into(),
+        })
+}
+
+/// `return` returns void from a method.
+pub fn vreturn(
+    thread: &mut Thread,
+    cm: &ClassManager,
+) -> Result<InstructionSuccess, InstructionError> {
+    check_return(thread, cm, "return")?;
+    let returned = thread.return_frame()?;
+    thread.resume_caller(&returned, None)
 }
 
 // TODO: ireturn actually checks the method type to cast properly the returned value to the correct type
@@ -125,28 +179,14 @@ xreturn!(freturn, Float);
 xreturn!(dreturn, Double);
 
 /// `areturn` returns a reference from a method.
-pub fn areturn(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let prev_frame = thread.pop_frame().unwrap();
-    if let Some(slot) = prev_frame.operand_stack.last() {
-        if slot.is_reference() {
-            let frame = thread.current_frame_mut().unwrap();
-            let Some(Slot::InvokationReturnAddress(pc)) = frame.operand_stack.pop() else {
-                return Err(InstructionError::InvalidState {
-                    context: "Expected invokation return address on the operand stack".into(),
-                });
-            };
-            frame.operand_stack.push(slot.clone());
-            Ok(InstructionSuccess::FrameChange(pc as usize))
-        } else {
-            return Err(InstructionError::InvalidState {
-                context: format!("Expected reference but got {:?}", slot),
-            });
-        }
-    } else {
-        return Err(InstructionError::InvalidState {
-            context: "Expected reference on the operand stack".into(),
-        });
-    }
+pub fn areturn(
+    thread: &mut Thread,
+    cm: &ClassManager,
+) -> Result<InstructionSuccess, InstructionError> {
+    check_return(thread, cm, "areturn")?;
+    let value = returning_frame(thread)?.pop_ref()?;
+    let returned = thread.return_frame()?;
+    thread.resume_caller(&returned, Some(value))
 }
 
 mod macros {
@@ -154,25 +194,68 @@ mod macros {
     macro_rules! xreturn {
         ($name:ident, $ty:ident) => {
             /// Return a value from a method.
-            pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let prev_frame = thread.pop_frame().unwrap();
-                // TODO: implement monitor strategy for synchronized methods
-                if let Some(Slot::$ty(value)) = prev_frame.operand_stack.last() {
-                    let frame = thread.current_frame_mut().unwrap();
-                    let Some(Slot::InvokationReturnAddress(pc)) = frame.operand_stack.pop() else {
+            pub fn $name(
+                thread: &mut Thread,
+                cm: &ClassManager,
+            ) -> Result<InstructionSuccess, InstructionError> {
+                check_return(thread, cm, stringify!($name))?;
+                let value = match returning_frame(thread)?.pop()? {
+                    value @ Slot::$ty(_) => value,
+                    value => {
                         return Err(InstructionError::InvalidState {
-                            context: "Expected invokation return address on the operand stack"
-                                .into(),
-                        });
-                    };
-                    frame.operand_stack.push(Slot::$ty(*value));
-                    Ok(InstructionSuccess::FrameChange(pc as usize))
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: format!("Expected {:?} on the operand stack", stringify!($ty)),
-                    });
-                }
+                            context: format!("Expected {} but got {:?}", stringify!($ty), value),
+                        })
+                    }
+                };
+                let returned = thread.return_frame()?;
+                thread.resume_caller(&returned, Some(value))
             }
         };
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{class::ClassId, test_util::test_class_manager, thread::ReturnInfo};
+
+    #[test]
+    fn returned_values() {
+        let cm = test_class_manager();
+        let mut thread = Thread::new();
+        thread.push_frame(Frame::new(ClassId(usize::MAX), 0, 0, 1));
+        let mut callee = Frame::new(ClassId(usize::MAX), 0, 0, 2);
+        callee.return_info = Some(ReturnInfo { pc: 3 });
+        thread.push_frame(callee);
+
+        // The value is checked before the frame is popped.
+        let frame = thread.current_frame_mut().unwrap();
+        frame.push_value(7i64).unwrap();
+        assert!(ireturn(&mut thread, &cm).is_err());
+        assert_eq!(thread.stack.len(), 2);
+
+        let frame = thread.current_frame_mut().unwrap();
+        frame.push_value(7).unwrap();
+        let result = ireturn(&mut thread, &cm);
+        assert!(
+            matches!(result, Ok(InstructionSuccess::FrameChange)),
+            "{:?}",
+            result
+        );
+        assert_eq!(thread.stack.len(), 1);
+        assert_eq!(thread.pc(), 3);
+        let caller = thread.current_frame_mut().unwrap();
+        assert_eq!(caller.pop_value::<i32>().unwrap(), 7);
+
+        // Without caller, the value is the result of the thread.
+        caller.push_value(Slot::UndefinedReference).unwrap();
+        let result = areturn(&mut thread, &cm);
+        assert!(
+            matches!(result, Ok(InstructionSuccess::Completed)),
+            "{:?}",
+            result
+        );
+        assert!(thread.stack.is_empty());
+        assert!(thread.result.unwrap().is_null());
+    }
+}
@@ -1,4 +1,5 @@
 use super::{InstructionError, InstructionSuccess};
+use crate::fp;
 use crate::thread::Slot;
 use crate::thread::Thread;
 use crate::{i2truncate, x2y};
@@ -11,13 +12,13 @@ x2y!(l2i, Long, Int, i32);
 x2y!(l2f, Long, Float, f32);
 x2y!(l2d, Long, Double, f64);
 
-x2y!(f2i, Float, Int, i32);
-x2y!(f2l, Float, Long, i64);
-x2y!(f2d, Float, Double, f64);
+x2y!(f2i, Float, Int, fn fp::f2i);
+x2y!(f2l, Float, Long, fn fp::f2l);
+x2y!(f2d, Float, Double, fn fp::f2d);
 
-x2y!(d2i, Double, Int, i32);
-x2y!(d2l, Double, Long, i64);
-x2y!(d2f, Double, Float, f32);
+x2y!(d2i, Double, Int, fn fp::d2i);
+x2y!(d2l, Double, Long, fn fp::d2l);
+x2y!(d2f, Double, Float, fn fp::d2f);
 
 i2truncate!(i2b, i8);
 i2truncate!(i2c, u16);
@@ -26,15 +27,19 @@ i2truncate!(i2s, i16);
 mod macros {
     #[macro_export]
     macro_rules! x2y {
+        ($name:ident, $srcty:ident, $destty:ident, fn $conv:path) => {
+            $crate::x2y!($name, $srcty, $destty, |value| $conv(value));
+        };
         ($name:ident, $srcty:ident, $destty:ident, $real_destty:ty) => {
+            $crate::x2y!($name, $srcty, $destty, |value| value as $real_destty);
+        };
+        ($name:ident, $srcty:ident, $destty:ident, |$value:ident| $conv:expr) => {
             /// Convert the top value to another numeric form and push it back to the stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
+                let frame = thread.executing_frame()?;
                 if let Some(slot) = frame.operand_stack.pop() {
-                    if let Slot::$srcty(value) = slot {
-                        frame
-                            .operand_stack
-                            .push(Slot::$destty(value as $real_destty));
+                    if let Slot::$srcty($value) = slot {
+                        frame.operand_stack.push(Slot::$destty($conv));
                         Ok(InstructionSuccess::Next(1))
                     } else {
                         return Err(InstructionError::InvalidState {
@@ -59,13 +64,12 @@ mod macros {
         ($name:ident, $real_destty:ty) => {
             /// Convert the top value (int) to a byte/char/short form by truncation and push it back to the stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
+                let frame = thread.executing_frame()?;
                 if let Some(slot) = frame.operand_stack.pop() {
                     if let Slot::Int(value) = slot {
                         frame
                             .operand_stack
                             .push(Slot::Int((value as $real_destty) as i32));
-                        thread.pc += 1;
                         Ok(InstructionSuccess::Next(1))
                     } else {
                         return Err(InstructionError::InvalidState {
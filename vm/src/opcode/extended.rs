@@ -1,24 +1,199 @@
 use crate::thread::{Slot, Thread};
 
-use super::{InstructionError, InstructionSuccess};
+use super::{BranchTarget, InstructionError, InstructionSuccess, WideInstruction};
 
+/// Pop a reference (possibly null) from the operand stack.
+fn pop_reference(thread: &mut Thread, context: &str) -> Result<Slot, InstructionError> {
+    let frame = thread.executing_frame()?;
+    match frame.operand_stack.pop() {
+        Some(slot) if slot.is_reference() => Ok(slot),
+        Some(slot) => Err(InstructionError::InvalidState {
+            context: format!("{} - expected reference but got {:?}", context, slot),
+        }),
+        None => Err(InstructionError::InvalidState {
+            context: format!("{} - operand stack is empty", context),
+        }),
+    }
+}
 
 /// `ifnull` - Branch if reference is null
-pub fn ifnull(thread: &mut Thread, offset: i16) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let value = frame.operand_stack.pop().unwrap();
-    match value {
-        Slot::UndefinedReference => Ok(InstructionSuccess::JumpRelative(offset as isize)),
-        _ => Ok(InstructionSuccess::Next(3)),
+pub fn ifnull(
+    thread: &mut Thread,
+    target: BranchTarget,
+) -> Result<InstructionSuccess, InstructionError> {
+    if pop_reference(thread, "ifnull")?.is_null() {
+        Ok(InstructionSuccess::JumpAbsolute(target as usize))
+    } else {
+        Ok(InstructionSuccess::Next(3))
     }
 }
 
 /// `ifnonnull` - Branch if reference is not null
-pub fn ifnonnull(thread: &mut Thread, offset: i16) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let value = frame.operand_stack.pop().unwrap();
-    match value {
-        Slot::UndefinedReference => Ok(InstructionSuccess::Next(3)),
-        _ => Ok(InstructionSuccess::JumpRelative(offset as isize)),
-    }
-}
\ No newline at end of file
+pub fn ifnonnull(
+    thread: &mut Thread,
+    target: BranchTarget,
+) -> Result<InstructionSuccess, InstructionError> {
+    if pop_reference(thread, "ifnonnull")?.is_null() {
+        Ok(InstructionSuccess::Next(3))
+    } else {
+        Ok(InstructionSuccess::JumpAbsolute(target as usize))
+    }
+}
+
+/// `wide` - Load or store a local variable with a 16-bit index.
+///
+/// The other instructions modified by `wide`, `iinc` and `ret`, are dispatched
+/// to their own implementation.
+pub fn wide(
+    thread: &mut Thread,
+    instruction: &WideInstruction,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    match *instruction {
+        WideInstruction::ILoad(index) => {
+            let value = frame.load_value::<i32>(index as usize)?;
+            frame.push_value(value)?;
+        }
+        WideInstruction::LLoad(index) => {
+            let value = frame.load_value::<i64>(index as usize)?;
+            frame.push_value(value)?;
+        }
+        WideInstruction::FLoad(index) => {
+            let value = frame.load_value::<f32>(index as usize)?;
+            frame.push_value(value)?;
+        }
+        WideInstruction::DLoad(index) => {
+            let value = frame.load_value::<f64>(index as usize)?;
+            frame.push_value(value)?;
+        }
+        WideInstruction::ALoad(index) => {
+            let value = frame.load_ref(index as usize)?;
+            frame.push_value(value)?;
+        }
+        WideInstruction::IStore(index) => {
+            let value = frame.pop_value::<i32>()?;
+            frame.store_value(index as usize, value)?;
+        }
+        WideInstruction::LStore(index) => {
+            let value = frame.pop_value::<i64>()?;
+            frame.store_value(index as usize, value)?;
+        }
+        WideInstruction::FStore(index) => {
+            let value = frame.pop_value::<f32>()?;
+            frame.store_value(index as usize, value)?;
+        }
+        WideInstruction::DStore(index) => {
+            let value = frame.pop_value::<f64>()?;
+            frame.store_value(index as usize, value)?;
+        }
+        WideInstruction::AStore(index) => {
+            let value = frame.pop_ref()?;
+            frame.store_value(index as usize, value)?;
+        }
+        WideInstruction::IInc(..) | WideInstruction::Ret(_) => {
+            return Err(InstructionError::InvalidState {
+                context: format!("wide - {:?} is not a load or a store", instruction),
+            });
+        }
+    }
+    // The `wide` opcode, the modified opcode and the 2 bytes of the index.
+    Ok(InstructionSuccess::Next(4))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        class::ClassId,
+        opcode::{math, read_instruction, Opcode},
+        thread::Frame,
+    };
+
+    /// Decode a `wide` instruction, spanning all the given bytes.
+    fn decode(code: &[u8]) -> WideInstruction {
+        let (length, opcode) = read_instruction(Cursor::new(code)).unwrap();
+        assert_eq!(length, code.len());
+        match opcode {
+            Opcode::Wide(instruction) => instruction,
+            opcode => panic!("not a wide instruction: {:?}", opcode),
+        }
+    }
+
+    fn frame(thread: &mut Thread) -> &mut Frame {
+        thread.current_frame_mut().unwrap()
+    }
+
+    #[test]
+    fn wide_loads_and_stores() {
+        let mut thread = Thread::new();
+        thread.stack.push(Frame::new(ClassId(0), 0, 300, 2));
+        let execute = |thread: &mut Thread, code: &[u8]| {
+            let result = wide(thread, &decode(code));
+            assert!(
+                matches!(result, Ok(InstructionSuccess::Next(4))),
+                "{:?}",
+                result
+            );
+        };
+
+        // istore 260, iload 260
+        frame(&mut thread).push_value(-5).unwrap();
+        execute(&mut thread, &[0xc4, 0x36, 0x01, 0x04]);
+        assert_eq!(frame(&mut thread).load_value::<i32>(260).unwrap(), -5);
+        execute(&mut thread, &[0xc4, 0x15, 0x01, 0x04]);
+        assert_eq!(frame(&mut thread).pop_value::<i32>().unwrap(), -5);
+
+        // lstore 298, taking the last two local variables, dload 256
+        frame(&mut thread).push_value(1i64 << 40).unwrap();
+        execute(&mut thread, &[0xc4, 0x37, 0x01, 0x2a]);
+        assert_eq!(frame(&mut thread).load_value::<i64>(298).unwrap(), 1 << 40);
+        frame(&mut thread).store_value(256, 2.5f64).unwrap();
+        execute(&mut thread, &[0xc4, 0x18, 0x01, 0x00]);
+        assert_eq!(frame(&mut thread).pop_value::<f64>().unwrap(), 2.5);
+
+        // astore 511 of null, aload 511
+        frame(&mut thread)
+            .push_value(Slot::UndefinedReference)
+            .unwrap();
+        execute(&mut thread, &[0xc4, 0x3a, 0x01, 0x1f]);
+        execute(&mut thread, &[0xc4, 0x19, 0x01, 0x1f]);
+        assert!(frame(&mut thread).pop_ref().unwrap().is_null());
+
+        // Out of the local variables, or of a wrong type.
+        frame(&mut thread).push_value(1i64).unwrap();
+        let lstore_299 = decode(&[0xc4, 0x37, 0x01, 0x2b]);
+        assert!(wide(&mut thread, &lstore_299).is_err());
+        let fload_260 = decode(&[0xc4, 0x17, 0x01, 0x04]);
+        assert!(wide(&mut thread, &fload_260).is_err());
+    }
+
+    #[test]
+    fn wide_iinc() {
+        let mut thread = Thread::new();
+        thread.stack.push(Frame::new(ClassId(0), 0, 300, 0));
+        frame(&mut thread).store_value(258, i32::MIN + 1).unwrap();
+
+        // iinc 258 by -32768, wrapping around
+        let instruction = decode(&[0xc4, 0x84, 0x01, 0x02, 0x80, 0x00]);
+        let WideInstruction::IInc(index, increment) = instruction else {
+            panic!("not an iinc: {:?}", instruction);
+        };
+        assert_eq!((index, increment), (258, i16::MIN));
+        let result = math::wide_iinc(&mut thread, index, increment);
+        assert!(
+            matches!(result, Ok(InstructionSuccess::Next(6))),
+            "{:?}",
+            result
+        );
+        assert_eq!(
+            frame(&mut thread).load_value::<i32>(258).unwrap(),
+            i32::MAX - 32766
+        );
+
+        // iinc is not executed as a load or a store
+        assert!(wide(&mut thread, &instruction).is_err());
+        assert!(math::wide_iinc(&mut thread, 300, 1).is_err());
+    }
+}
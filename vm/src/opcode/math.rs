@@ -1,54 +1,56 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
 use super::{InstructionError, InstructionSuccess};
-use crate::thread::Slot;
-use crate::thread::Thread;
-use crate::{xadd, xand, xdiv, xmul, xneg1, xneg2, xor, xrem, xshl, xshr, xsub, xxor};
-
-xadd!(iadd, Int, i32, i32);
-xadd!(ladd, Long, i64, i64);
-xadd!(fadd, Float, f32, f32);
-xadd!(dadd, Double, f64, f64);
-
-xsub!(isub, Int, i32, i32);
-xsub!(lsub, Long, i64, i64);
-xsub!(fsub, Float, f32, f32);
-xsub!(dsub, Double, f64, f64);
-
-xmul!(imul, Int, i32, i32);
-xmul!(lmul, Long, i64, i64);
-xmul!(fmul, Float, f32, f32);
-xmul!(dmul, Double, f64, f64);
-
-xdiv!(idiv, Int, i32, i32);
-xdiv!(ldiv, Long, i64, i64);
-xdiv!(fdiv, Float, f32, f32);
-xdiv!(ddiv, Double, f64, f64);
-
-xrem!(irem, Int, i32, i32);
-xrem!(lrem, Long, i64, i64);
-xrem!(frem, Float, f32, f32);
-xrem!(drem, Double, f64, f64);
-
-xneg1!(ineg, Int);
-xneg1!(lneg, Long);
-xneg2!(fneg, Float, f32);
-xneg2!(dneg, Double, f64);
-
-xshl!(ishl, Int);
-xshl!(lshl, Long);
-
-xshr!(ishr, Int);
-xshr!(lshr, Long);
+use crate::{fp, thread::Thread};
+use crate::{xadd, xand, xdiv, xidiv, xirem, xmul, xneg, xor, xrem, xshl, xshr, xsub, xxor};
+
+// The integer operations wrap around on overflow, in two's complement.
+xadd!(iadd, i32, wrapping_add);
+xadd!(ladd, i64, wrapping_add);
+xadd!(fadd, f32, add);
+xadd!(dadd, f64, add);
+
+xsub!(isub, i32, wrapping_sub);
+xsub!(lsub, i64, wrapping_sub);
+xsub!(fsub, f32, sub);
+xsub!(dsub, f64, sub);
+
+xmul!(imul, i32, wrapping_mul);
+xmul!(lmul, i64, wrapping_mul);
+xmul!(fmul, f32, mul);
+xmul!(dmul, f64, mul);
+
+xidiv!(idiv, i32);
+xidiv!(ldiv, i64);
+xdiv!(fdiv, f32);
+xdiv!(ddiv, f64);
+
+xirem!(irem, i32);
+xirem!(lrem, i64);
+xrem!(frem, f32, fp::frem);
+xrem!(drem, f64, fp::drem);
+
+xneg!(ineg, i32, wrapping_neg);
+xneg!(lneg, i64, wrapping_neg);
+xneg!(fneg, f32, neg);
+xneg!(dneg, f64, neg);
+
+xshl!(ishl, i32);
+xshl!(lshl, i64);
+
+xshr!(ishr, i32);
+xshr!(lshr, i64);
 
 // TODO: implement ushr
 
-xand!(iand, Int);
-xand!(land, Long);
+xand!(iand, i32);
+xand!(land, i64);
 
-xor!(ior, Int);
-xor!(lor, Long);
+xor!(ior, i32);
+xor!(lor, i64);
 
-xxor!(ixor, Int);
-xxor!(lxor, Long);
+xxor!(ixor, i32);
+xxor!(lxor, i64);
 
 /// `iinc` - Increment local variable by constant.
 pub fn iinc(
@@ -56,21 +58,10 @@ pub fn iinc(
     index: u8,
     increment: i8,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    if let Some(slot) = frame.local_variables.get_mut(index as usize) {
-        if let Slot::Int(value) = slot {
-            *value += increment as i32;
-            Ok(InstructionSuccess::Next(3))
-        } else {
-            return Err(InstructionError::InvalidState {
-                context: "Expected Int".into(),
-            });
-        }
-    } else {
-        return Err(InstructionError::InvalidState {
-            context: "Local variable not found".into(),
-        });
-    }
+    let frame = thread.executing_frame()?;
+    let value = frame.load_value::<i32>(index as usize)?;
+    frame.store_value(index as usize, value.wrapping_add(increment as i32))?;
+    Ok(InstructionSuccess::Next(3))
 }
 
 /// `iinc` (wide variation) - Increment local variable by constant.
@@ -79,52 +70,23 @@ pub fn wide_iinc(
     index: u16,
     increment: i16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    if let Some(slot) = frame.local_variables.get_mut(index as usize) {
-        if let Slot::Int(value) = slot {
-            *value += increment as i32;
-            Ok(InstructionSuccess::Next(5))
-        } else {
-            return Err(InstructionError::InvalidState {
-                context: "Expected Int".into(),
-            });
-        }
-    } else {
-        return Err(InstructionError::InvalidState {
-            context: "Local variable not found".into(),
-        });
-    }
+    let frame = thread.executing_frame()?;
+    let value = frame.load_value::<i32>(index as usize)?;
+    frame.store_value(index as usize, value.wrapping_add(increment as i32))?;
+    // The `wide` opcode, the `iinc` opcode, and the 4 bytes of the operands.
+    Ok(InstructionSuccess::Next(6))
 }
 
 mod macros {
     #[macro_export]
     macro_rules! xadd {
-        ($name:ident, $ty:ident, $real_ty:ty, $final_ty:ty) => {
+        ($name:ident, $ty:ty, $op:ident) => {
             /// Add two values from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot1) = frame.operand_stack.pop() {
-                    if let Some(slot2) = frame.operand_stack.pop() {
-                        if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                            frame.operand_stack.push(Slot::$ty(
-                                ((value1 as $real_ty) + (value2 as $real_ty)) as $final_ty,
-                            ));
-                        } else {
-                            return Err(InstructionError::InvalidState {
-                                context: format!("Expected {:?}", stringify!($ty)),
-                            });
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: "Operand stack is len 1, expected as least two elements."
-                                .into(),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value2.$op(value1))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -132,32 +94,13 @@ mod macros {
 
     #[macro_export]
     macro_rules! xsub {
-        ($name:ident, $ty:ident, $real_ty:ty, $final_ty:ty) => {
+        ($name:ident, $ty:ty, $op:ident) => {
             /// Substract two values from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot1) = frame.operand_stack.pop() {
-                    if let Some(slot2) = frame.operand_stack.pop() {
-                        if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                            frame.operand_stack.push(Slot::$ty(
-                                ((value2 as $real_ty) - (value1 as $real_ty)) as $final_ty,
-                            ));
-                        } else {
-                            return Err(InstructionError::InvalidState {
-                                context: format!("Expected {:?}", stringify!($ty)),
-                            });
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: "Operand stack is len 1, expected as least two elements."
-                                .into(),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value2.$op(value1))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -165,32 +108,13 @@ mod macros {
 
     #[macro_export]
     macro_rules! xmul {
-        ($name:ident, $ty:ident, $real_ty:ty, $final_ty:ty) => {
+        ($name:ident, $ty:ty, $op:ident) => {
             /// Multiply two values from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot1) = frame.operand_stack.pop() {
-                    if let Some(slot2) = frame.operand_stack.pop() {
-                        if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                            frame.operand_stack.push(Slot::$ty(
-                                ((value1 as $real_ty) * (value2 as $real_ty)) as $final_ty,
-                            ));
-                        } else {
-                            return Err(InstructionError::InvalidState {
-                                context: format!("Expected {:?}", stringify!($ty)),
-                            });
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: "Operand stack is len 1, expected as least two elements."
-                                .into(),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value2.$op(value1))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -198,32 +122,13 @@ mod macros {
 
     #[macro_export]
     macro_rules! xdiv {
-        ($name:ident, $ty:ident, $real_ty:ty, $final_ty:ty) => {
+        ($name:ident, $ty:ty) => {
             /// Divide a value by another from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot1) = frame.operand_stack.pop() {
-                    if let Some(slot2) = frame.operand_stack.pop() {
-                        if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                            frame.operand_stack.push(Slot::$ty(
-                                ((value1 as $real_ty) / (value2 as $real_ty)) as $final_ty,
-                            ));
-                        } else {
-                            return Err(InstructionError::InvalidState {
-                                context: format!("Expected {:?}", stringify!($ty)),
-                            });
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: "Operand stack is len 1, expected as least two elements."
-                                .into(),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value2 / value1)?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -231,90 +136,71 @@ mod macros {
 
     #[macro_export]
     macro_rules! xrem {
-        ($name:ident, $ty:ident, $real_ty:ty, $final_ty:ty) => {
+        ($name:ident, $ty:ty, $op:path) => {
             /// The reminder of a value by another from the operand stack and push the result onto the operand stack.
+            ///
+            /// The remainder is the one of the truncating division, see [crate::fp::frem].
+            pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value($op(value2, value1))?;
+                Ok(InstructionSuccess::Next(1))
+            }
+        };
+    }
+
+    #[macro_export]
+    macro_rules! xidiv {
+        ($name:ident, $ty:ty) => {
+            /// Divide an integer by another from the operand stack and push the result onto the operand stack.
+            ///
+            /// The division of the smallest value by -1 overflows, giving the smallest value.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot1) = frame.operand_stack.pop() {
-                    if let Some(slot2) = frame.operand_stack.pop() {
-                        if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                            frame.operand_stack.push(Slot::$ty(
-                                ((value1 as $real_ty) % (value2 as $real_ty)) as $final_ty,
-                            ));
-                        } else {
-                            return Err(InstructionError::InvalidState {
-                                context: format!("Expected {:?}", stringify!($ty)),
-                            });
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: "Operand stack is len 1, expected as least two elements."
-                                .into(),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                if value1 == 0 {
+                    return Err(InstructionError::Arithmetic {
+                        context: "/ by zero".into(),
                     });
                 }
+                frame.push_value(value2.wrapping_div(value1))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
     }
 
     #[macro_export]
-    macro_rules! xneg1 {
-        ($name:ident, $ty:ident) => {
-            /// Negate a value from the operand stack and push the result onto the operand stack.
+    macro_rules! xirem {
+        ($name:ident, $ty:ty) => {
+            /// The remainder of an integer by another from the operand stack and push the result onto the operand stack.
+            ///
+            /// The sign of the result is the one of the dividend, and the remainder of the
+            /// smallest value by -1 is 0.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot) = frame.operand_stack.pop() {
-                    if let Slot::$ty(value) = slot {
-                        frame.operand_stack.push(Slot::$ty(-value));
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: format!("Expected {:?}", stringify!($ty)),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                if value1 == 0 {
+                    return Err(InstructionError::Arithmetic {
+                        context: "/ by zero".into(),
                     });
                 }
+                frame.push_value(value2.wrapping_rem(value1))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
     }
 
     #[macro_export]
-    macro_rules! xneg2 {
-        ($name:ident, $ty:ident, $real_ty:ty) => {
+    macro_rules! xneg {
+        ($name:ident, $ty:ty, $op:ident) => {
             /// Negate a value from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot) = frame.operand_stack.pop() {
-                    if let Slot::$ty(value) = slot {
-                        match value {
-                            0.0 => frame.operand_stack.push(Slot::$ty(-0.0)),
-                            -0.0 => frame.operand_stack.push(Slot::$ty(0.0)),
-                            <$real_ty>::INFINITY => frame
-                                .operand_stack
-                                .push(Slot::$ty(<$real_ty>::NEG_INFINITY)),
-                            <$real_ty>::NEG_INFINITY => {
-                                frame.operand_stack.push(Slot::$ty(<$real_ty>::INFINITY))
-                            }
-                            x => frame.operand_stack.push(Slot::$ty(-x)),
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: format!("Expected {:?}", stringify!($ty)),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value = frame.pop_value::<$ty>()?;
+                frame.push_value(value.$op())?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -322,32 +208,13 @@ mod macros {
 
     #[macro_export]
     macro_rules! xshl {
-        ($name:ident, $ty:ident) => {
+        ($name:ident, $ty:ty) => {
             /// Shift left a value from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot1) = frame.operand_stack.pop() {
-                    if let Some(slot2) = frame.operand_stack.pop() {
-                        if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                            frame
-                                .operand_stack
-                                .push(Slot::$ty(value1 << (value2 & 0x1f)));
-                        } else {
-                            return Err(InstructionError::InvalidState {
-                                context: format!("Expected {:?}", stringify!($ty)),
-                            });
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: "Operand stack is len 1, expected as least two elements."
-                                .into(),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value1 << (value2 & 0x1f))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -355,32 +222,13 @@ mod macros {
 
     #[macro_export]
     macro_rules! xshr {
-        ($name:ident, $ty:ident) => {
+        ($name:ident, $ty:ty) => {
             /// Shift right a value from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let Some(slot1) = frame.operand_stack.pop() {
-                    if let Some(slot2) = frame.operand_stack.pop() {
-                        if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                            frame
-                                .operand_stack
-                                .push(Slot::$ty(value1 >> (value2 & 0x1f)));
-                        } else {
-                            return Err(InstructionError::InvalidState {
-                                context: format!("Expected {:?}", stringify!($ty)),
-                            });
-                        }
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: "Operand stack is len 1, expected as least two elements."
-                                .into(),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty".into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value1 >> (value2 & 0x1f))?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -388,26 +236,13 @@ mod macros {
 
     #[macro_export]
     macro_rules! xand {
-        ($name:ident, $ty:ident) => {
+        ($name:ident, $ty:ty) => {
             /// Bitwise and a value from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let (Some(slot1), Some(slot2)) =
-                    (frame.operand_stack.pop(), frame.operand_stack.pop())
-                {
-                    if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                        frame.operand_stack.push(Slot::$ty(value1 & value2));
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: format!("Expected {:?}", stringify!($ty)),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty or len 1, expected as least two elements."
-                            .into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value1 & value2)?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -415,26 +250,13 @@ mod macros {
 
     #[macro_export]
     macro_rules! xor {
-        ($name:ident, $ty:ident) => {
+        ($name:ident, $ty:ty) => {
             /// Bitwise or a value from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let (Some(slot1), Some(slot2)) =
-                    (frame.operand_stack.pop(), frame.operand_stack.pop())
-                {
-                    if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                        frame.operand_stack.push(Slot::$ty(value1 | value2));
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: format!("Expected {:?}", stringify!($ty)),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty or len 1, expected as least two elements."
-                            .into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value1 | value2)?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
@@ -442,28 +264,90 @@ mod macros {
 
     #[macro_export]
     macro_rules! xxor {
-        ($name:ident, $ty:ident) => {
+        ($name:ident, $ty:ty) => {
             /// Bitwise xor a value from the operand stack and push the result onto the operand stack.
             pub fn $name(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-                let frame = thread.current_frame_mut().unwrap();
-                if let (Some(slot1), Some(slot2)) =
-                    (frame.operand_stack.pop(), frame.operand_stack.pop())
-                {
-                    if let (Slot::$ty(value1), Slot::$ty(value2)) = (slot1, slot2) {
-                        frame.operand_stack.push(Slot::$ty(value1 ^ value2));
-                    } else {
-                        return Err(InstructionError::InvalidState {
-                            context: format!("Expected {:?}", stringify!($ty)),
-                        });
-                    }
-                } else {
-                    return Err(InstructionError::InvalidState {
-                        context: "Operand stack is empty or len 1, expected as least two elements."
-                            .into(),
-                    });
-                }
+                let frame = thread.executing_frame()?;
+                let value1 = frame.pop_value::<$ty>()?;
+                let value2 = frame.pop_value::<$ty>()?;
+                frame.push_value(value1 ^ value2)?;
                 Ok(InstructionSuccess::Next(1))
             }
         };
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{class::ClassId, slot::PrimitiveValue, thread::Frame};
+
+    /// Execute a binary instruction on `value2 op value1`.
+    fn binary<T: PrimitiveValue>(
+        instruction: fn(&mut Thread) -> Result<InstructionSuccess, InstructionError>,
+        value2: T,
+        value1: T,
+    ) -> Result<T, InstructionError> {
+        let mut thread = Thread::new();
+        thread.stack.push(Frame::new(ClassId(0), 0, 0, 4));
+        let frame = thread.current_frame_mut().unwrap();
+        frame.push_value(value2)?;
+        frame.push_value(value1)?;
+        instruction(&mut thread)?;
+        thread.current_frame_mut().unwrap().pop_value::<T>()
+    }
+
+    #[test]
+    fn integer_division() {
+        assert_eq!(binary(idiv, 7, -2).unwrap(), -3);
+        assert_eq!(binary(irem, 7, -2).unwrap(), 1);
+        assert_eq!(binary(irem, -7, 2).unwrap(), -1);
+        assert_eq!(binary(idiv, i32::MIN, -1).unwrap(), i32::MIN);
+        assert_eq!(binary(irem, i32::MIN, -1).unwrap(), 0);
+        assert_eq!(binary(ldiv, i64::MIN, -1).unwrap(), i64::MIN);
+        assert_eq!(binary(lrem, i64::MIN, -1).unwrap(), 0);
+        for result in [binary(idiv, 1, 0), binary(irem, 1, 0)] {
+            assert!(matches!(result, Err(InstructionError::Arithmetic { .. })));
+        }
+        for result in [binary(ldiv, 1i64, 0), binary(lrem, 1i64, 0)] {
+            assert!(matches!(result, Err(InstructionError::Arithmetic { .. })));
+        }
+        // The floating-point divisions do not fail.
+        assert!(binary(ddiv, 1.0f64, 0.0).unwrap().is_infinite());
+        assert!(binary(frem, 1.0f32, 0.0).unwrap().is_nan());
+    }
+
+    #[test]
+    fn operand_order() {
+        // The divisor is on top of the operand stack, the dividend below it.
+        assert_eq!(binary(idiv, 20, 6).unwrap(), 3);
+        assert_eq!(binary(irem, 20, 6).unwrap(), 2);
+        assert_eq!(binary(ldiv, 20i64, 6).unwrap(), 3);
+        assert_eq!(binary(lrem, 20i64, 6).unwrap(), 2);
+        assert_eq!(binary(fdiv, 1.0f32, 4.0).unwrap(), 0.25);
+        assert_eq!(binary(drem, 7.5f64, 2.0).unwrap(), 1.5);
+        assert_eq!(binary(isub, 20, 6).unwrap(), 14);
+        assert_eq!(binary(dsub, 1.0f64, 4.0).unwrap(), -3.0);
+    }
+
+    #[test]
+    fn integer_overflow() {
+        assert_eq!(binary(iadd, i32::MAX, 1).unwrap(), i32::MIN);
+        assert_eq!(binary(isub, i32::MIN, 1).unwrap(), i32::MAX);
+        assert_eq!(binary(imul, i32::MAX, 2).unwrap(), -2);
+        assert_eq!(binary(ladd, i64::MAX, 1).unwrap(), i64::MIN);
+        assert_eq!(binary(lmul, i64::MIN, -1).unwrap(), i64::MIN);
+
+        let mut thread = Thread::new();
+        thread.stack.push(Frame::new(ClassId(0), 0, 1, 2));
+        let frame = thread.current_frame_mut().unwrap();
+        frame.push_value(i32::MIN).unwrap();
+        ineg(&mut thread).unwrap();
+        let frame = thread.current_frame_mut().unwrap();
+        assert_eq!(frame.pop_value::<i32>().unwrap(), i32::MIN);
+        frame.store_value(0, i32::MAX).unwrap();
+        iinc(&mut thread, 0, 1).unwrap();
+        let frame = thread.current_frame_mut().unwrap();
+        assert_eq!(frame.load_value::<i32>(0).unwrap(), i32::MIN);
+    }
+}
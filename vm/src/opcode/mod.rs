@@ -1,11 +1,15 @@
+use crate::class::ClassId;
 use crate::class_manager::ClassManager;
-use crate::thread::Thread;
-use crate::{opcode_with_operand1, opcode_with_operand2};
+use crate::thread::{Slot, Thread};
+use crate::{branch_opcode, opcode_with_operand1, opcode_with_operand2};
 use binrw::{BinRead, BinReaderExt};
 use reader::base::ParsingError;
 use snafu::Snafu;
 use std::io::{Read, Seek};
 
+pub use cache::{Accessor, AccessorCache, CachedField, InstructionCache, Program, ValueType};
+
+mod cache;
 mod comparison;
 mod constant;
 mod control;
@@ -172,22 +176,22 @@ pub enum Opcode {
     FCmpG,
     DCmpL,
     DCmpG,
-    IfEq(i16),
-    IfNe(i16),
-    IfLt(i16),
-    IfGe(i16),
-    IfGt(i16),
-    IfLe(i16),
-    IfICmpEq(i16),
-    IfICmpNe(i16),
-    IfICmpLt(i16),
-    IfICmpGe(i16),
-    IfICmpGt(i16),
-    IfICmpLe(i16),
-    IfACmpEq(i16),
-    IfACmpNe(i16),
-    Goto(i16),
-    Jsr(i16),
+    IfEq(BranchTarget),
+    IfNe(BranchTarget),
+    IfLt(BranchTarget),
+    IfGe(BranchTarget),
+    IfGt(BranchTarget),
+    IfLe(BranchTarget),
+    IfICmpEq(BranchTarget),
+    IfICmpNe(BranchTarget),
+    IfICmpLt(BranchTarget),
+    IfICmpGe(BranchTarget),
+    IfICmpGt(BranchTarget),
+    IfICmpLe(BranchTarget),
+    IfACmpEq(BranchTarget),
+    IfACmpNe(BranchTarget),
+    Goto(BranchTarget),
+    Jsr(BranchTarget),
     Ret(u8),
     TableSwitch(TableSwitch),
     LookupSwitch(LookupSwitch),
@@ -215,37 +219,103 @@ pub enum Opcode {
     InstanceOf(u16),
     MonitorEnter,
     MonitorExit,
-    Wide,
+    Wide(WideInstruction),
     MultiANewArray(u16, u8),
-    IfNull(i16),
-    IfNonNull(i16),
-    GotoW(i32),
-    JsrW(i32),
+    IfNull(BranchTarget),
+    IfNonNull(BranchTarget),
+    GotoW(BranchTarget),
+    JsrW(BranchTarget),
     Breakpoint,
     ImpDep1,
     ImpDep2,
+
+    // Quickened opcodes, never read from a classfile.
+    // They replace resolved instructions in the InstructionCache.
+    /// `getstatic` with a resolved field: implementor class and field index
+    GetStaticQuick(ClassId, usize),
+    /// `ldc`/`ldc_w` of an int constant: value and instruction length
+    LdcQuickInt(i32, usize),
+    /// `ldc`/`ldc_w` of a float constant: value and instruction length
+    LdcQuickFloat(f32, usize),
+    /// `ldc2_w` of a long constant
+    LdcQuickLong(i64),
+    /// `ldc2_w` of a double constant
+    LdcQuickDouble(f64),
+}
+
+/// An instruction modified by `wide`, with its 16-bit local variable index.
+#[derive(Debug, Clone)]
+pub enum WideInstruction {
+    ILoad(u16),
+    LLoad(u16),
+    FLoad(u16),
+    DLoad(u16),
+    ALoad(u16),
+    IStore(u16),
+    LStore(u16),
+    FStore(u16),
+    DStore(u16),
+    AStore(u16),
+    Ret(u16),
+    /// Index and 16-bit increment
+    IInc(u16, i16),
 }
 
+/// Absolute pc of the target of a branch, computed when the instruction is decoded.
+///
+/// It is signed, as the target of an invalid branch can be before the start
+/// of the code, such branches being rejected when the class is loaded.
+pub type BranchTarget = i64;
+
+/// Operands of a `tableswitch`, read after its padding.
+///
+/// The offsets are turned into the pc of their targets, given the pc of the
+/// instruction.
 #[derive(Debug, Clone, BinRead)]
-#[br(big)]
+#[br(big, import(pc: BranchTarget))]
 pub struct TableSwitch {
-    default: i32,
+    #[br(map = |offset: i32| pc + offset as BranchTarget)]
+    default: BranchTarget,
     low: i32,
     high: i32,
-    #[br(count = high - low + 1)]
-    jump_offsets: Vec<i32>,
+    #[br(count = high - low + 1, map = |offsets: Vec<i32>| branch_targets(pc, offsets))]
+    jump_targets: Vec<BranchTarget>,
 }
 
+/// Operands of a `lookupswitch`, read after its padding.
+///
+/// The offsets are turned into the pc of their targets, given the pc of the
+/// instruction.
 #[derive(Debug, Clone, BinRead)]
-#[br(big)]
+#[br(big, import(pc: BranchTarget))]
 pub struct LookupSwitch {
-    default: i32,
+    #[br(map = |offset: i32| pc + offset as BranchTarget)]
+    default: BranchTarget,
     npairs: i32,
-    #[br(count = npairs)]
-    match_offsets: Vec<(i32, i32)>,
+    #[br(count = npairs, map = |pairs: Vec<(i32, i32)>| match_targets(pc, pairs))]
+    match_targets: Vec<(i32, BranchTarget)>,
+}
+
+/// Get the pc of the targets of branch offsets, relative to the given pc.
+fn branch_targets(pc: BranchTarget, offsets: Vec<i32>) -> Vec<BranchTarget> {
+    offsets
+        .into_iter()
+        .map(|offset| pc + offset as BranchTarget)
+        .collect()
+}
+
+/// Get the pc of the targets of `lookupswitch` pairs, relative to the given pc.
+fn match_targets(pc: BranchTarget, pairs: Vec<(i32, i32)>) -> Vec<(i32, BranchTarget)> {
+    pairs
+        .into_iter()
+        .map(|(key, offset)| (key, pc + offset as BranchTarget))
+        .collect()
 }
 
 pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode), InstructionError> {
+    // The reader is positioned at the pc of the instruction, the padding of the
+    // switches and the targets of the branches depending on it.
+    let pc = reader.stream_position()? as BranchTarget;
     let mut buf = [0u8; 1];
     reader.read_exact(&mut buf)?;
     match buf[0] {
@@ -402,22 +472,22 @@ pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode),
         0x96 => Ok((1, Opcode::FCmpG)),
         0x97 => Ok((1, Opcode::DCmpL)),
         0x98 => Ok((1, Opcode::DCmpG)),
-        0x99 => opcode_with_operand2!(reader, IfEq, i16),
-        0x9a => opcode_with_operand2!(reader, IfNe, i16),
-        0x9b => opcode_with_operand2!(reader, IfLt, i16),
-        0x9c => opcode_with_operand2!(reader, IfGe, i16),
-        0x9d => opcode_with_operand2!(reader, IfGt, i16),
-        0x9e => opcode_with_operand2!(reader, IfLe, i16),
-        0x9f => opcode_with_operand2!(reader, IfICmpEq, i16),
-        0xa0 => opcode_with_operand2!(reader, IfICmpNe, i16),
-        0xa1 => opcode_with_operand2!(reader, IfICmpLt, i16),
-        0xa2 => opcode_with_operand2!(reader, IfICmpGe, i16),
-        0xa3 => opcode_with_operand2!(reader, IfICmpGt, i16),
-        0xa4 => opcode_with_operand2!(reader, IfICmpLe, i16),
-        0xa5 => opcode_with_operand2!(reader, IfACmpEq, i16),
-        0xa6 => opcode_with_operand2!(reader, IfACmpNe, i16),
-        0xa7 => opcode_with_operand2!(reader, Goto, i16),
-        0xa8 => opcode_with_operand2!(reader, Jsr, i16),
+        0x99 => branch_opcode!(reader, pc, IfEq, i16),
+        0x9a => branch_opcode!(reader, pc, IfNe, i16),
+        0x9b => branch_opcode!(reader, pc, IfLt, i16),
+        0x9c => branch_opcode!(reader, pc, IfGe, i16),
+        0x9d => branch_opcode!(reader, pc, IfGt, i16),
+        0x9e => branch_opcode!(reader, pc, IfLe, i16),
+        0x9f => branch_opcode!(reader, pc, IfICmpEq, i16),
+        0xa0 => branch_opcode!(reader, pc, IfICmpNe, i16),
+        0xa1 => branch_opcode!(reader, pc, IfICmpLt, i16),
+        0xa2 => branch_opcode!(reader, pc, IfICmpGe, i16),
+        0xa3 => branch_opcode!(reader, pc, IfICmpGt, i16),
+        0xa4 => branch_opcode!(reader, pc, IfICmpLe, i16),
+        0xa5 => branch_opcode!(reader, pc, IfACmpEq, i16),
+        0xa6 => branch_opcode!(reader, pc, IfACmpNe, i16),
+        0xa7 => branch_opcode!(reader, pc, Goto, i16),
+        0xa8 => branch_opcode!(reader, pc, Jsr, i16),
         0xa9 => opcode_with_operand1!(reader, Ret),
         0xaa => {
             // tableswitch
@@ -426,13 +496,13 @@ pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode),
             reader.seek(std::io::SeekFrom::Current(padding as i64))?;
             let ts: TableSwitch =
                 reader
-                    .read_be()
+                    .read_be_args((pc,))
                     .map_err(|e| InstructionError::CorruptedOpcode {
                         opcode: 0xaa,
                         source: e,
                     })?;
             Ok((
-                1 + (padding as usize) + (4 * 3) + 4 * ts.jump_offsets.len(),
+                1 + (padding as usize) + (4 * 3) + 4 * ts.jump_targets.len(),
                 Opcode::TableSwitch(ts),
             ))
         }
@@ -443,13 +513,13 @@ pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode),
             reader.seek(std::io::SeekFrom::Current(padding as i64))?;
             let ls: LookupSwitch =
                 reader
-                    .read_be()
+                    .read_be_args((pc,))
                     .map_err(|e| InstructionError::CorruptedOpcode {
                         opcode: 0xab,
                         source: e,
                     })?;
             Ok((
-                1 + (padding as usize) + (4 * 2) + 8 * ls.match_offsets.len(),
+                1 + (padding as usize) + (4 * 2) + 8 * ls.match_targets.len(),
                 Opcode::LookupSwitch(ls),
             ))
         }
@@ -477,7 +547,14 @@ pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode),
                 Opcode::InvokeInterface(u16::from_be_bytes([buf[0], buf[1]])),
             ))
         }
-        0xba => opcode_with_operand2!(reader, InvokeDynamic),
+        0xba => {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            Ok((
+                5,
+                Opcode::InvokeDynamic(u16::from_be_bytes([buf[0], buf[1]])),
+            ))
+        }
         0xbb => opcode_with_operand2!(reader, New),
         0xbc => opcode_with_operand1!(reader, NewArray),
         0xbd => opcode_with_operand2!(reader, ANewArray),
@@ -487,7 +564,7 @@ pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode),
         0xc1 => opcode_with_operand2!(reader, InstanceOf),
         0xc2 => Ok((1, Opcode::MonitorEnter)),
         0xc3 => Ok((1, Opcode::MonitorExit)),
-        // TODO: 0xc4 - wide (special instruction that modifies the next instruction behavior)
+        0xc4 => read_wide_instruction(&mut reader),
         0xc5 => {
             let mut buf = [0u8; 3];
             reader.read_exact(&mut buf)?;
@@ -496,18 +573,10 @@ pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode),
                 Opcode::MultiANewArray(u16::from_be_bytes([buf[0], buf[1]]), buf[2]),
             ))
         }
-        0xc6 => opcode_with_operand2!(reader, IfNull, i16),
-        0xc7 => opcode_with_operand2!(reader, IfNonNull, i16),
-        0xc8 => {
-            let mut buf = [0u8; 4];
-            reader.read_exact(&mut buf)?;
-            Ok((5, Opcode::GotoW(i32::from_be_bytes(buf))))
-        }
-        0xc9 => {
-            let mut buf = [0u8; 4];
-            reader.read_exact(&mut buf)?;
-            Ok((5, Opcode::JsrW(i32::from_be_bytes(buf))))
-        }
+        0xc6 => branch_opcode!(reader, pc, IfNull, i16),
+        0xc7 => branch_opcode!(reader, pc, IfNonNull, i16),
+        0xc8 => branch_opcode!(reader, pc, GotoW, i32),
+        0xc9 => branch_opcode!(reader, pc, JsrW, i32),
         0xca => Ok((1, Opcode::Breakpoint)),
         0xfe => Ok((1, Opcode::ImpDep1)),
         0xff => Ok((1, Opcode::ImpDep2)),
@@ -515,7 +584,85 @@ pub fn read_instruction(mut reader: impl Read + Seek) -> Result<(usize, Opcode),
     }
 }
 
+/// Read the instruction modified by `wide`, the `wide` opcode being already read.
+fn read_wide_instruction(mut reader: impl Read) -> Result<(usize, Opcode), InstructionError> {
+    let mut buf = [0u8; 3];
+    reader.read_exact(&mut buf)?;
+    let index = u16::from_be_bytes([buf[1], buf[2]]);
+    let instruction = match buf[0] {
+        0x15 => WideInstruction::ILoad(index),
+        0x16 => WideInstruction::LLoad(index),
+        0x17 => WideInstruction::FLoad(index),
+        0x18 => WideInstruction::DLoad(index),
+        0x19 => WideInstruction::ALoad(index),
+        0x36 => WideInstruction::IStore(index),
+        0x37 => WideInstruction::LStore(index),
+        0x38 => WideInstruction::FStore(index),
+        0x39 => WideInstruction::DStore(index),
+        0x3a => WideInstruction::AStore(index),
+        0xa9 => WideInstruction::Ret(index),
+        0x84 => {
+            let mut buf = [0u8; 2];
+            reader.read_exact(&mut buf)?;
+            let increment = i16::from_be_bytes(buf);
+            return Ok((6, Opcode::Wide(WideInstruction::IInc(index, increment))));
+        }
+        invalid => return Err(InstructionError::InvalidOpcode { opcode: invalid }),
+    };
+    Ok((4, Opcode::Wide(instruction)))
+}
+
 impl Opcode {
+    /// Get the pc of the branch targets of this instruction, empty if it is not
+    /// a branch.
+    ///
+    /// `ret` is not included, its target being only known at runtime.
+    pub fn branch_targets(&self) -> Vec<BranchTarget> {
+        match self {
+            Opcode::IfEq(target)
+            | Opcode::IfNe(target)
+            | Opcode::IfLt(target)
+            | Opcode::IfGe(target)
+            | Opcode::IfGt(target)
+            | Opcode::IfLe(target)
+            | Opcode::IfICmpEq(target)
+            | Opcode::IfICmpNe(target)
+            | Opcode::IfICmpLt(target)
+            | Opcode::IfICmpGe(target)
+            | Opcode::IfICmpGt(target)
+            | Opcode::IfICmpLe(target)
+            | Opcode::IfACmpEq(target)
+            | Opcode::IfACmpNe(target)
+            | Opcode::IfNull(target)
+            | Opcode::IfNonNull(target)
+            | Opcode::Goto(target)
+            | Opcode::Jsr(target)
+            | Opcode::GotoW(target)
+            | Opcode::JsrW(target) => vec![*target],
+            Opcode::TableSwitch(ts) => std::iter::once(ts.default)
+                .chain(ts.jump_targets.iter().copied())
+                .collect(),
+            Opcode::LookupSwitch(ls) => std::iter::once(ls.default)
+                .chain(ls.match_targets.iter().map(|(_, target)| *target))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the quickened equivalent of this instruction, if any.
+    ///
+    /// This must be called after a successful execution of the instruction, so
+    /// the constant pool entries it refers to are already resolved.
+    pub fn quicken(&self, cm: &ClassManager, class: ClassId) -> Option<Opcode> {
+        match self {
+            Opcode::Ldc(index) => constant::quicken_ldc(cm, class, *index as u16, 2, false),
+            Opcode::LdcW(index) => constant::quicken_ldc(cm, class, *index, 3, false),
+            Opcode::Ldc2W(index) => constant::quicken_ldc(cm, class, *index, 3, true),
+            Opcode::GetStatic(index) => reference::quicken_getstatic(cm, class, *index),
+            _ => None,
+        }
+    }
+
     pub fn execute(
         &self,
         thread: &mut Thread,
@@ -605,7 +752,7 @@ impl Opcode {
             Opcode::LAStore => store::lastore(thread),
             Opcode::FAStore => store::fastore(thread),
             Opcode::DAStore => store::dastore(thread),
-            Opcode::AAStore => store::aastore(thread),
+            Opcode::AAStore => store::aastore(thread, cm),
             Opcode::BAStore => store::bastore(thread),
             Opcode::CAStore => store::castore(thread),
             Opcode::SAStore => store::sastore(thread),
@@ -688,17 +835,17 @@ impl Opcode {
             Opcode::IfICmpLe(value) => comparison::if_icmple(thread, *value),
             Opcode::IfACmpEq(value) => comparison::if_acmpeq(thread, *value),
             Opcode::IfACmpNe(value) => comparison::if_acmpne(thread, *value),
-            Opcode::Goto(value) => control::goto(thread, *value),
+            Opcode::Goto(value) => control::goto(*value),
             Opcode::Jsr(value) => control::jsr(thread, *value),
-            Opcode::Ret(value) => control::ret(thread, *value),
+            Opcode::Ret(value) => control::ret(thread, *value as u16),
             Opcode::TableSwitch(ts) => control::tableswitch(thread, ts),
             Opcode::LookupSwitch(ls) => control::lookupswitch(thread, ls),
-            Opcode::IReturn => control::ireturn(thread),
-            Opcode::LReturn => control::lreturn(thread),
-            Opcode::FReturn => control::freturn(thread),
-            Opcode::DReturn => control::dreturn(thread),
-            Opcode::AReturn => control::areturn(thread),
-            Opcode::Return => control::vreturn(thread),
+            Opcode::IReturn => control::ireturn(thread, cm),
+            Opcode::LReturn => control::lreturn(thread, cm),
+            Opcode::FReturn => control::freturn(thread, cm),
+            Opcode::DReturn => control::dreturn(thread, cm),
+            Opcode::AReturn => control::areturn(thread, cm),
+            Opcode::Return => control::vreturn(thread, cm),
             Opcode::GetStatic(index) => reference::getstatic(thread, cm, *index),
             Opcode::PutStatic(index) => reference::putstatic(thread, cm, *index),
             Opcode::GetField(index) => reference::getfield(thread, cm, *index),
@@ -706,19 +853,42 @@ impl Opcode {
             Opcode::InvokeVirtual(index) => reference::invokevirtual(thread, cm, *index),
             Opcode::InvokeSpecial(index) => reference::invokespecial(thread, cm, *index),
             Opcode::InvokeInterface(index) => reference::invokeinterface(thread, cm, *index),
-            // TODO: Implement InvokeDynamic
+            Opcode::InvokeDynamic(index) => reference::invokedynamic(thread, cm, *index),
             Opcode::InvokeStatic(index) => reference::invokestatic(thread, cm, *index),
             Opcode::New(index) => reference::new(thread, cm, *index),
-            Opcode::NewArray(atype) => reference::newarray(thread, *atype),
+            Opcode::NewArray(atype) => reference::newarray(thread, cm, *atype),
             Opcode::ANewArray(index) => reference::anewarray(thread, cm, *index),
             Opcode::ArrayLength => reference::arraylength(thread),
-            // TODO: Implement AThrow, CheckCast, InstanceOf, MonitorEnter, MonitorExit
-            // TODO: Implement Wide
-            // TODO: Implement MultiANewArray
+            // TODO: Implement AThrow
+            Opcode::CheckCast(index) => reference::checkcast(thread, cm, *index),
+            Opcode::InstanceOf(index) => reference::instanceof(thread, cm, *index),
+            Opcode::MonitorEnter => reference::monitorenter(thread),
+            Opcode::MonitorExit => reference::monitorexit(thread),
+            // The breakpoints are handled by the interpreter, before the execution.
+            Opcode::Breakpoint => constant::nop(thread),
+            Opcode::Wide(WideInstruction::IInc(index, value)) => {
+                math::wide_iinc(thread, *index, *value)
+            }
+            Opcode::Wide(WideInstruction::Ret(index)) => control::ret(thread, *index),
+            Opcode::Wide(instruction) => extended::wide(thread, instruction),
+            Opcode::MultiANewArray(index, dimensions) => {
+                reference::multianewarray(thread, cm, *index, *dimensions)
+            }
             Opcode::IfNull(value) => extended::ifnull(thread, *value),
             Opcode::IfNonNull(value) => extended::ifnonnull(thread, *value),
-            Opcode::GotoW(value) => control::goto_w(thread, *value),
+            Opcode::GotoW(value) => control::goto_w(*value),
             Opcode::JsrW(value) => control::jsr_w(thread, *value),
+            Opcode::GetStaticQuick(class, field) => {
+                reference::getstatic_quick(thread, cm, *class, *field)
+            }
+            Opcode::LdcQuickInt(value, size) => {
+                constant::ldc_quick(thread, Slot::Int(*value), *size)
+            }
+            Opcode::LdcQuickFloat(value, size) => {
+                constant::ldc_quick(thread, Slot::Float(*value), *size)
+            }
+            Opcode::LdcQuickLong(value) => constant::ldc_quick(thread, Slot::Long(*value), 3),
+            Opcode::LdcQuickDouble(value) => constant::ldc_quick(thread, Slot::Double(*value), 3),
             x => Err(InstructionError::UnimplementedInstruction { opcode: x.clone() }),
         }
     }
@@ -735,6 +905,66 @@ pub enum InstructionError {
     #[snafu(display("Invalid state: {}", context))]
     InvalidState { context: String },
 
+    #[snafu(display("Invalid constant #{} for {}: {}", index, instruction, context))]
+    InvalidConstant {
+        instruction: &'static str,
+        index: u16,
+        context: String,
+    },
+
+    #[snafu(display(
+        "Operand stack overflow: {} values for a max_stack of {}",
+        depth,
+        max_stack
+    ))]
+    OperandStackOverflow { depth: usize, max_stack: usize },
+
+    #[snafu(display("Bootstrap method error: {}", context))]
+    BootstrapMethod { context: String },
+
+    #[snafu(display("Null pointer: {}", context))]
+    NullPointer { context: String },
+
+    #[snafu(display("String index out of bounds: {}", context))]
+    StringIndexOutOfBounds { context: String },
+
+    /// An integer division or remainder by zero.
+    #[snafu(display("Arithmetic exception: {}", context))]
+    Arithmetic { context: String },
+
+    #[snafu(display("Illegal monitor state: {}", context))]
+    IllegalMonitorState { context: String },
+
+    #[snafu(display("Class cast error: {}", context))]
+    ClassCast { context: String },
+
+    #[snafu(display("Array store error: {}", context))]
+    ArrayStore { context: String },
+
+    #[snafu(display("Instantiation error: {}", context))]
+    Instantiation { context: String },
+
+    #[snafu(display("Abstract method error: {}", context))]
+    AbstractMethod { context: String },
+
+    #[snafu(display("Incompatible class change: {}", context))]
+    IncompatibleClassChange { context: String },
+
+    #[snafu(display("Illegal argument: {}", context))]
+    IllegalArgument { context: String },
+
+    /// A malformed method, rejected by the verifier of a JVM.
+    #[snafu(display("Verify error: {}", context))]
+    Verify { context: String },
+
+    #[snafu(context(false))]
+    #[snafu(display("Out of memory: {}", source))]
+    OutOfMemory { source: crate::alloc::AllocError },
+
+    /// The VM has been requested to exit, e.g. by `System.exit`.
+    #[snafu(display("Exit requested with status {}", status))]
+    Exit { status: i32 },
+
     #[snafu(display("Unimplemented instruction, opcode: {:?}", opcode))]
     UnimplementedInstruction { opcode: Opcode },
 
@@ -749,6 +979,49 @@ pub enum InstructionError {
     CorruptedOpcode { opcode: u8, source: ParsingError },
 }
 
+impl InstructionError {
+    /// Get the status of the requested exit, if the error is an exit request,
+    /// possibly raised by a class initializer.
+    pub fn exit_status(&self) -> Option<i32> {
+        match self {
+            InstructionError::Exit { status } => Some(*status),
+            InstructionError::ClassLoadingError { source, .. } => match source.as_ref() {
+                crate::class_loader::ClassLoadingError::InitializerError { source } => {
+                    source.exit_status()
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the binary name of the Java exception or error thrown for this error,
+    /// if it is not an error of the VM itself.
+    pub fn java_exception(&self) -> Option<&'static str> {
+        match self {
+            InstructionError::NullPointer { .. } => Some("java/lang/NullPointerException"),
+            InstructionError::Arithmetic { .. } => Some("java/lang/ArithmeticException"),
+            InstructionError::StringIndexOutOfBounds { .. } => {
+                Some("java/lang/StringIndexOutOfBoundsException")
+            }
+            InstructionError::IllegalMonitorState { .. } => {
+                Some("java/lang/IllegalMonitorStateException")
+            }
+            InstructionError::ClassCast { .. } => Some("java/lang/ClassCastException"),
+            InstructionError::ArrayStore { .. } => Some("java/lang/ArrayStoreException"),
+            InstructionError::Instantiation { .. } => Some("java/lang/InstantiationError"),
+            InstructionError::AbstractMethod { .. } => Some("java/lang/AbstractMethodError"),
+            InstructionError::IncompatibleClassChange { .. } => {
+                Some("java/lang/IncompatibleClassChangeError")
+            }
+            InstructionError::IllegalArgument { .. } => Some("java/lang/IllegalArgumentException"),
+            InstructionError::Verify { .. } => Some("java/lang/VerifyError"),
+            InstructionError::OutOfMemory { .. } => Some("java/lang/OutOfMemoryError"),
+            _ => None,
+        }
+    }
+}
+
 /// The result of executing an instruction.
 ///
 /// Indicate where the next instruction should be read from.
@@ -759,20 +1032,15 @@ pub enum InstructionSuccess {
     /// The offset is the number of bytes to skip to get to the next instruction.
     Next(usize),
 
-    /// Jump relatively to the address.
-    ///
-    /// The offset is the number of bytes to add/remove to get to the next instruction.
-    JumpRelative(isize),
-
     /// Jump absolutely to the address.
     JumpAbsolute(usize),
 
-    /// Frame has been added/removed. The PC is set to the value given.
+    /// Frame has been added/removed.
     ///
-    /// A frame has been pushed or popped from the stack. The PC is set to the value given.
-    /// In case of the return of a method invokation, the PC is generally set to the InvokationReturnAddress of the last frame.
-    /// In case of the invokation of a method, the PC is generally set to the first instruction of the method, ie 0.
-    FrameChange(usize),
+    /// A frame has been pushed or popped from the stack, the execution continues
+    /// at the pc of the new current frame: 0 for an invoked method, the
+    /// [ReturnInfo](crate::thread::ReturnInfo) of the returned frame for its caller.
+    FrameChange,
 
     /// The execution of the thread has completed.
     ///
@@ -797,6 +1065,17 @@ mod macros {
         }};
     }
 
+    /// Read a branch instruction, its offset being turned into the pc of its target.
+    #[macro_export]
+    macro_rules! branch_opcode {
+        ($reader:expr, $pc:expr, $name:ident, $ty:ty) => {{
+            let mut buf = [0u8; std::mem::size_of::<$ty>()];
+            $reader.read_exact(&mut buf)?;
+            let target = $pc + <$ty>::from_be_bytes(buf) as BranchTarget;
+            Ok((1 + buf.len(), Opcode::$name(target)))
+        }};
+    }
+
     #[macro_export]
     macro_rules! opcode_with_operand2 {
         ($reader:expr, $name:ident) => {{
@@ -1,33 +1,92 @@
-use dumpster::sync::Gc;
-use reader::descriptor::{class, FieldType};
-
-use super::{InstructionError, InstructionSuccess};
-use crate::alloc::{array::*, Object, ObjectRef};
-use crate::class::{Class, ClassId, Field, Method};
+use std::mem::size_of;
+use std::sync::Arc;
+
+use reader::descriptor::{ArrayType, FieldType, MethodDescriptor};
+
+use super::{
+    Accessor, CachedField, InstructionCache, InstructionError, InstructionSuccess, Opcode,
+};
+use crate::alloc::{array::*, AllocError, Object, ObjectRef};
+use crate::bootstrap;
+use crate::call_log;
+use crate::class::{narrow_int, ClassId, ConstantValue, Field, FieldAttribute};
+use crate::class_loader::ClassLoadingError;
 use crate::class_manager::{ClassManager, LoadedClass, LoadingClass};
-use crate::constant_pool::ConstantPoolEntry;
-use crate::thread::{Frame, Slot, Thread};
+use crate::constant_pool::{ConstantPool, ConstantPoolEntry, MethodRefView};
+use crate::descriptor_table::InternedMethodDescriptor;
+use crate::fp::FpStrictness;
+use crate::lambda;
+use crate::thread::{Frame, ReturnInfo, Slot, Thread};
+use crate::types::{self, FieldTypeOrClassId};
+
+/// Get the name of a class for an error message, its id if it is unknown.
+fn class_name(cm: &ClassManager, class_id: ClassId) -> String {
+    cm.get_class_by_id(class_id).map_or_else(
+        || format!("ClassId({})", class_id.0),
+        |class| class.name().into(),
+    )
+}
+
+/// Internal helper to ensure a field of the implementor class is accessible
+/// from the given class.
+fn check_field_access(
+    cm: &ClassManager,
+    class: ClassId,
+    implementor: ClassId,
+    field: &Field,
+) -> Result<(), InstructionError> {
+    let access = field.access();
+    if cm.is_member_accessible(class, implementor, access) {
+        return Ok(());
+    }
+    let class_name = cm
+        .get_class_by_id(implementor)
+        .map(|class| class.name().to_string())
+        .unwrap_or_default();
+    let context = format!(
+        "{} field {}.{} is not accessible from ClassId({})",
+        access, class_name, field.name, class.0
+    );
+    Err(InstructionError::ClassLoadingError {
+        class_name,
+        source: Box::new(ClassLoadingError::IllegalAccess { context }),
+    })
+}
+
+/// Initialize the class or interface declaring a static member on its first use,
+/// see [ClassManager::initialize_class].
+fn initialize_declaring(cm: &mut ClassManager, declaring: ClassId) -> Result<(), InstructionError> {
+    cm.initialize_class(declaring)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: cm
+                .get_class_by_id(declaring)
+                .map(|class| class.name().to_string())
+                .unwrap_or_default(),
+            source: Box::new(err),
+        })
+}
 
 /// Internal helper to get a field from a ClassId and a constant pool index.
-fn intern_get_field(
-    cm: &mut ClassManager,
+///
+/// The field is resolved from the referenced class, and can be declared by one of
+/// its superclasses or superinterfaces. Returns the declaring class, the field and
+/// its slot: the index of the field in the declaring class for static fields, or
+/// its index in the instances for instance fields.
+///
+/// If a receiver object is given, it must be an instance of the declaring class.
+fn intern_get_field<'a>(
+    cm: &'a mut ClassManager,
     class: ClassId,
     cp_index: u16,
-) -> Result<(ClassId, &Field, usize), InstructionError> {
+    receiver: Option<&ObjectRef>,
+) -> Result<(ClassId, &'a Field, usize), InstructionError> {
+    let class_id = class;
     let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
         return Err(InstructionError::InvalidState {
             context: format!("Class not found: ClassId({})", class.0),
         });
     };
-    let Some(ConstantPoolEntry::FieldReference {
-        field_name,
-        field_descriptor,
-        implementor,
-    }) = class
-        .constant_pool
-        .get_field_ref(cp_index as usize)
-        .cloned()
-    else {
+    let Some(field_ref) = class.constant_pool.field_ref(cp_index as usize) else {
         return Err(InstructionError::InvalidState {
             context: format!(
                 "FieldRef not found: ClassId({}), constant pool index {}",
@@ -35,34 +94,52 @@ fn intern_get_field(
             ),
         });
     };
-    cm.request_class_load(implementor.clone()).map_err(|err| {
-        InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
+    let (field_name, field_descriptor, implementor) = (
+        field_ref.name.to_string(),
+        field_ref.descriptor.clone(),
+        field_ref.implementor,
+    );
+    cm.request_class_load(implementor)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: class_name(cm, implementor),
             source: Box::new(err),
-        }
-    })?;
-    let Some(LoadedClass::Loaded(impl_class)) = cm.get_class_by_id(implementor.clone()) else {
+        })?;
+    let Some((declaring, field_id)) = cm.resolve_field(implementor, &field_name, &field_descriptor)
+    else {
         return Err(InstructionError::InvalidState {
             context: format!(
-                "Implementor class not found / not initialized: ClassId({})",
-                implementor.0
+                "Field not found: ClassId({}), field name {}, field descriptor {:?}",
+                implementor.0, field_name, field_descriptor
             ),
         });
     };
-    let Some(field) = impl_class.get_field(&field_name) else {
+    initialize_declaring(cm, declaring)?;
+    let Some(LoadedClass::Loaded(decl_class)) = cm.get_class_by_id(declaring) else {
         return Err(InstructionError::InvalidState {
             context: format!(
-                "Field not found: ClassId({}), field name {}, field descriptor {:?}",
-                implementor.0, field_name, field_descriptor
+                "Declaring class not found / not initialized: ClassId({})",
+                declaring.0
             ),
         });
     };
-    let field_id = impl_class.index_of_field(&field_name).unwrap();
-    Ok((implementor, field, field_id))
+    let field = &decl_class.fields[field_id];
+    check_field_access(cm, class_id, declaring, field)?;
+    if let Some(objref) = receiver {
+        if !cm.is_superclass_of(objref.class_id(), declaring) {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "Object is not an instance of the field class: ClassId({}) is not a subclass of ClassId({})",
+                    objref.class_id().0,
+                    declaring.0
+                ),
+            });
+        }
+    }
+    if field.is_static() {
+        Ok((declaring, field, field_id))
+    } else {
+        Ok((declaring, field, decl_class.field_offset + field_id))
+    }
 }
 
 /// `getstatic` gets a static field value of a class, where the field is identified
@@ -72,9 +149,9 @@ pub fn getstatic(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
+    let frame = thread.executing_frame()?;
     let class = frame.class;
-    let (implementor, field, _) = intern_get_field(cm, class, index)?;
+    let (implementor, field, _) = intern_get_field(cm, class, index, None)?;
 
     if !field.is_static() {
         return Err(InstructionError::InvalidState {
@@ -97,6 +174,71 @@ pub fn getstatic(
     Ok(InstructionSuccess::Next(3))
 }
 
+/// Quickened `getstatic`, gets a static field value from an already resolved field.
+pub fn getstatic_quick(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    implementor: ClassId,
+    field_id: usize,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let Some(LoadedClass::Loaded(impl_class)) = cm.get_class_by_id(implementor) else {
+        return Err(InstructionError::InvalidState {
+            context: format!(
+                "Implementor class not found / not initialized: ClassId({})",
+                implementor.0
+            ),
+        });
+    };
+    let Some(value) = impl_class
+        .get_field_by_index(field_id)
+        .and_then(|field| field.get_value())
+    else {
+        return Err(InstructionError::InvalidState {
+            context: format!(
+                "Field not found: ClassId({}), field index {}",
+                implementor.0, field_id
+            ),
+        });
+    };
+    frame.operand_stack.push(value.clone());
+    Ok(InstructionSuccess::Next(3))
+}
+
+/// Get the quickened equivalent of a `getstatic` instruction.
+///
+/// Static final fields with a ConstantValue attribute are folded into a
+/// constant, other static fields are accessed directly by their index.
+pub(crate) fn quicken_getstatic(cm: &ClassManager, class: ClassId, index: u16) -> Option<Opcode> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
+        return None;
+    };
+    let field_ref = class.constant_pool.field_ref(index as usize)?;
+    let (declaring, field_id) =
+        cm.resolve_field(field_ref.implementor, field_ref.name, field_ref.descriptor)?;
+    let Some(LoadedClass::Loaded(decl_class)) = cm.get_class_by_id(declaring) else {
+        return None;
+    };
+    let field = decl_class.get_field_by_index(field_id)?;
+    let constant = field.attributes.iter().find_map(|attr| match attr {
+        FieldAttribute::ConstantValue { value } => Some(value),
+        _ => None,
+    });
+    match constant {
+        Some(ConstantValue::Integer(value)) if field.is_final() => {
+            Some(Opcode::LdcQuickInt(*value, 3))
+        }
+        Some(ConstantValue::Float(value)) if field.is_final() => {
+            Some(Opcode::LdcQuickFloat(*value, 3))
+        }
+        Some(ConstantValue::Long(value)) if field.is_final() => Some(Opcode::LdcQuickLong(*value)),
+        Some(ConstantValue::Double(value)) if field.is_final() => {
+            Some(Opcode::LdcQuickDouble(*value))
+        }
+        _ => Some(Opcode::GetStaticQuick(declaring, field_id)),
+    }
+}
+
 /// `putstatic` sets static field to a value in a class, where the field is identified
 /// by field reference in the constant pool index.
 pub fn putstatic(
@@ -104,7 +246,8 @@ pub fn putstatic(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
+    let frame = thread.executing_frame()?;
+    let class_id = frame.class;
     let (field_name, field_descriptor, implementor) = {
         let class = frame.class;
         let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
@@ -112,12 +255,7 @@ pub fn putstatic(
                 context: format!("Class not found: ClassId({})", class.0),
             });
         };
-        let Some(ConstantPoolEntry::FieldReference {
-            field_name,
-            field_descriptor,
-            implementor,
-        }) = class.constant_pool.get_field_ref(index as usize)
-        else {
+        let Some(field_ref) = class.constant_pool.field_ref(index as usize) else {
             return Err(InstructionError::InvalidState {
                 context: format!(
                     "FieldRef not found: ClassId({}), constant pool index {}",
@@ -126,42 +264,42 @@ pub fn putstatic(
             });
         };
         (
-            field_name.clone(),
-            field_descriptor.clone(),
-            implementor.clone(),
+            field_ref.name.to_string(),
+            field_ref.descriptor.clone(),
+            field_ref.implementor,
         )
     };
-    cm.request_class_load(implementor.clone()).map_err(|err| {
-        InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
+    cm.request_class_load(implementor)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: class_name(cm, implementor),
             source: Box::new(err),
-        }
-    })?;
-    let Some(LoadedClass::Loaded(impl_class)) = cm.get_mut_class_by_id(implementor.clone()) else {
+        })?;
+    let Some((declaring, field_id)) = cm.resolve_field(implementor, &field_name, &field_descriptor)
+    else {
         return Err(InstructionError::InvalidState {
             context: format!(
-                "Implementor class not found / not initialized: ClassId({})",
-                implementor.0
+                "Field not found: ClassId({}), field name {}, field descriptor {:?}",
+                implementor.0, field_name, field_descriptor
             ),
         });
     };
-
-    let class_initialized =
-        impl_class.initialized.get().is_some() && impl_class.initialized.get().cloned().unwrap();
-
-    let Some(field) = impl_class.get_mut_field(&field_name) else {
+    if let Some(LoadedClass::Loaded(decl_class)) = cm.get_class_by_id(declaring) {
+        check_field_access(cm, class_id, declaring, &decl_class.fields[field_id])?;
+    }
+    initialize_declaring(cm, declaring)?;
+    let Some(LoadedClass::Loaded(decl_class)) = cm.get_mut_class_by_id(declaring) else {
         return Err(InstructionError::InvalidState {
             context: format!(
-                "Field not found: ClassId({}), field name {}, field descriptor {:?}",
-                implementor.0, field_name, field_descriptor
+                "Declaring class not found / not initialized: ClassId({})",
+                declaring.0
             ),
         });
     };
 
+    let class_initialized = decl_class.initialized.get().copied().unwrap_or(false);
+
+    let field = &mut decl_class.fields[field_id];
+
     if !field.is_static() {
         return Err(InstructionError::InvalidState {
             context: format!(
@@ -185,7 +323,7 @@ pub fn putstatic(
             context: format!("Operand stack is empty"),
         });
     };
-    field.value = value;
+    field.value = field.narrow(value);
     Ok(InstructionSuccess::Next(3))
 }
 
@@ -196,35 +334,17 @@ pub fn getfield(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let objref = match frame.operand_stack.pop() {
-        Some(Slot::ObjectReference(objref)) => objref,
-        Some(Slot::UndefinedReference) => {
-            return Err(InstructionError::InvalidState {
-                context: "Null object reference".into(),
-            });
-        }
-        _ => {
-            return Err(InstructionError::InvalidState {
-                context: format!("Invalid object reference: {:?}", frame.operand_stack),
-            });
-        }
-    };
-
-    let (implementor, field, field_id) = intern_get_field(cm, frame.class, index)?;
+    let frame = thread.executing_frame()?;
+    let objref = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected object reference".into(),
+        })?
+        .non_null_object("getfield")?;
 
-    // Check if the type is coherent
-    if &implementor != objref.class_id() {
-        return Err(InstructionError::InvalidState {
-            context: format!(
-                "Field implementor class does not match object class: ClassId({}) != ClassId({})",
-                implementor.0,
-                objref.class_id().0
-            ),
-        });
-    }
+    let (implementor, field, field_id) = intern_get_field(cm, frame.class, index, Some(&objref))?;
 
-    // TODO: Check if the field is accessible
     // Ensure the field is not static
     if field.is_static() {
         return Err(InstructionError::InvalidState {
@@ -257,29 +377,23 @@ pub fn putfield(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
+    let frame = thread.executing_frame()?;
     let value = frame
         .operand_stack
         .pop()
         .ok_or_else(|| InstructionError::InvalidState {
             context: "Operand stack is empty".into(),
         })?;
-    let objref = match frame.operand_stack.pop() {
-        Some(Slot::ObjectReference(objref)) => objref,
-        Some(Slot::UndefinedReference) => {
-            return Err(InstructionError::InvalidState {
-                context: "Null object reference".into(),
-            });
-        }
-        _ => {
-            return Err(InstructionError::InvalidState {
-                context: format!("Invalid object reference: {:?}", frame.operand_stack),
-            });
-        }
-    };
+    let objref = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected object reference".into(),
+        })?
+        .non_null_object("putfield")?;
 
-    // Check if we are currently running the initializer of this class
-    let is_initializer = {
+    // Check if we are currently running an instance initializer
+    let in_init = {
         let Some(LoadedClass::Loaded(cur_class)) = cm.get_class_by_id(frame.class) else {
             return Err(InstructionError::InvalidState {
                 context: format!("Class not found: ClassId({})", frame.class.0),
@@ -293,23 +407,14 @@ pub fn putfield(
                 ),
             });
         };
-        &cur_method.name == "<init>" && objref.class_id() == &frame.class
+        &cur_method.name == "<init>"
     };
 
-    let (implementor, field, field_id) = intern_get_field(cm, frame.class, index)?;
-
-    // Check if the type is coherent
-    if &implementor != objref.class_id() {
-        return Err(InstructionError::InvalidState {
-            context: format!(
-                "Field implementor class does not match object class: ClassId({}) != ClassId({})",
-                implementor.0,
-                objref.class_id().0
-            ),
-        });
-    }
+    let (implementor, field, field_id) = intern_get_field(cm, frame.class, index, Some(&objref))?;
+    // A final field is set by the initializers of its own class, the object
+    // being possibly an instance of a subclass.
+    let is_initializer = in_init && implementor == frame.class;
 
-    // TODO: Check if the field is accessible
     // Ensure the field is not static
     if field.is_static() {
         return Err(InstructionError::InvalidState {
@@ -333,55 +438,91 @@ pub fn putfield(
     // TODO: Ensure the field type is coherent
 
     // Set the field value
-    objref.set_field(field_id, value);
+    objref.set_field(field_id, field.narrow(value));
 
     Ok(InstructionSuccess::Next(3))
 }
 
-/// `invokestatic` invokes a static method and puts the result on the operand stack.
-pub fn invokestatic(
-    thread: &mut Thread,
+/// Internal helper to get a method reference from a ClassId and a constant pool index,
+/// loading the referenced class.
+///
+/// The accessor selects the kind of reference accepted by the instruction.
+fn intern_get_method_ref(
     cm: &mut ClassManager,
-    index: u16,
-) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let (method_name, method_descriptor, implementor) = {
-        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
-            return Err(InstructionError::InvalidState {
-                context: format!(
-                    "Class not found (or not loaded): ClassId({})",
-                    frame.class.0
-                ),
-            });
-        };
-
-        let Some(ConstantPoolEntry::MethodReference {
-            method_name,
-            method_descriptor,
-            implementor,
-        }) = class.constant_pool.get_method_ref(index as usize).cloned()
-        else {
-            return Err(InstructionError::InvalidState {
+    class: ClassId,
+    cp_index: u16,
+    accessor: for<'a> fn(&'a ConstantPool, usize) -> Option<MethodRefView<'a>>,
+) -> Result<(String, InternedMethodDescriptor, ClassId), InstructionError> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found (or not loaded): ClassId({})", class.0),
+        });
+    };
+    let Some(method_ref) = accessor(&class.constant_pool, cp_index as usize) else {
+        return Err(InstructionError::InvalidState {
+            context: format!(
+                "MethodRef not found: ClassId({}), constant pool index {}",
+                class.id.0, cp_index
+            ),
+        });
+    };
+    let (method_name, method_descriptor, implementor, interface) = (
+        method_ref.name.to_string(),
+        method_ref.descriptor.clone(),
+        method_ref.implementor,
+        method_ref.interface,
+    );
+    cm.request_class_load(implementor)
+        .map_err(|err| InstructionError::ClassLoadingError {
+            class_name: class_name(cm, implementor),
+            source: Box::new(err),
+        })?;
+    // A Methodref must reference a class, and an InterfaceMethodref an interface
+    // (JVMS 5.4.3.3 and 5.4.3.4).
+    if let Some(LoadedClass::Loaded(impl_class)) = cm.get_class_by_id(implementor) {
+        if impl_class.is_interface() != interface {
+            let (expected, reference) = if interface {
+                ("an interface", "InterfaceMethodref")
+            } else {
+                ("a class", "Methodref")
+            };
+            return Err(InstructionError::IncompatibleClassChange {
                 context: format!(
-                    "MethodRef not found: ClassId({}), constant pool index {}",
-                    class.id.0, index
+                    "{} is not {}, as expected by the {} of {}",
+                    impl_class.name, expected, reference, method_name
                 ),
             });
-        };
-
-        (method_name, method_descriptor, implementor)
-    };
+        }
+    }
+    Ok((method_name, method_descriptor, implementor))
+}
 
-    cm.request_class_load(implementor.clone()).map_err(|err| {
-        InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
-            source: Box::new(err),
+/// Accessor of the method reference of an `invokestatic` or `invokespecial`.
+///
+/// Since classfile version 52 (Java 8), both instructions may also name an
+/// InterfaceMethodref, to invoke the static and private methods of interfaces.
+fn static_method_ref_accessor(
+    cm: &ClassManager,
+    class: ClassId,
+) -> for<'a> fn(&'a ConstantPool, usize) -> Option<MethodRefView<'a>> {
+    match cm.get_class_by_id(class) {
+        Some(LoadedClass::Loaded(class)) if class.major_version >= 52 => {
+            ConstantPool::any_method_ref
         }
-    })?;
+        _ => ConstantPool::method_ref,
+    }
+}
+
+/// `invokestatic` invokes a static method and puts the result on the operand stack.
+pub fn invokestatic(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    index: u16,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let accessor = static_method_ref_accessor(cm, frame.class);
+    let (method_name, method_descriptor, implementor) =
+        intern_get_method_ref(cm, frame.class, index, accessor)?;
     let Some(LoadedClass::Loaded(impl_class)) = cm.get_class_by_id(implementor) else {
         return Err(InstructionError::InvalidState {
             context: format!(
@@ -400,21 +541,19 @@ pub fn invokestatic(
         });
     };
 
-    let mut args = Vec::new();
-    for _ in 0..method_descriptor.args_count() {
-        let arg = frame.operand_stack.pop().unwrap();
-        args.push(arg);
-    }
-    args.reverse();
-
     if !method.is_static() {
-        return Err(InstructionError::InvalidState {
+        return Err(InstructionError::IncompatibleClassChange {
             context: format!(
-                "Method is not static: ClassId({}), method name {}, method descriptor {:?}",
-                implementor.0, method_name, method_descriptor
+                "Expected static method {}.{}{}",
+                impl_class.name,
+                method_name,
+                method_descriptor.as_str()
             ),
         });
     }
+    initialize_declaring(cm, implementor)?;
+
+    let args = pop_arguments(frame, &method_descriptor, "invokestatic")?;
 
     invoke(thread, cm, implementor, method_id, args, 3)
 }
@@ -426,60 +565,22 @@ pub fn invokespecial(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
+    let frame = thread.executing_frame()?;
     let this_class = frame.class;
 
-    let (method_name, method_descriptor, implementor) = {
-        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
-            return Err(InstructionError::InvalidState {
-                context: format!(
-                    "Class not found (or not loaded): ClassId({})",
-                    frame.class.0
-                ),
-            });
-        };
-
-        let Some(ConstantPoolEntry::MethodReference {
-            method_name,
-            method_descriptor,
-            implementor,
-        }) = class.constant_pool.get_method_ref(index as usize).cloned()
-        else {
-            return Err(InstructionError::InvalidState {
-                context: format!(
-                    "MethodRef not found: ClassId({}), constant pool index {}",
-                    class.id.0, index
-                ),
-            });
-        };
-
-        (method_name, method_descriptor, implementor)
-    };
-
-    cm.request_class_load(implementor.clone()).map_err(|err| {
-        InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
-            source: Box::new(err),
-        }
-    })?;
+    let accessor = static_method_ref_accessor(cm, frame.class);
+    let (method_name, method_descriptor, implementor) =
+        intern_get_method_ref(cm, frame.class, index, accessor)?;
     let Some((real_impl, method_id)) = cm
         .resolve_method(
-            &this_class,
-            &implementor,
+            this_class,
+            implementor,
             &method_name,
             &method_descriptor,
             true,
         )
         .map_err(|err| InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
+            class_name: class_name(cm, implementor),
             source: Box::new(err),
         })?
     else {
@@ -491,32 +592,16 @@ pub fn invokespecial(
         });
     };
 
-    let mut args = Vec::new();
-    for _ in 0..method_descriptor.args_count() {
-        let arg = frame
-            .operand_stack
-            .pop()
-            .ok_or_else(|| InstructionError::InvalidState {
-                context: format!("Operand stack is empty"),
-            })?;
-        args.push(arg);
-    }
-    let objref = match frame.operand_stack.pop() {
-        Some(Slot::ObjectReference(objref)) => objref,
-        Some(Slot::UndefinedReference) => {
-            return Err(InstructionError::InvalidState {
-                context: "Null object reference".into(),
-            });
-        }
-        _ => {
-            return Err(InstructionError::InvalidState {
-                context: format!("Operand stack is empty, expected object reference"),
-            });
-        }
-    };
+    let mut args = pop_arguments(frame, &method_descriptor, "invokespecial")?;
+    let objref = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected object reference".into(),
+        })?
+        .non_null_object("invokespecial")?;
     // TODO: Check if the type is coherent
-    args.push(Slot::ObjectReference(objref));
-    args.reverse();
+    args.insert(0, Slot::ObjectReference(objref));
 
     invoke(thread, cm, real_impl, method_id, args, 3)
 }
@@ -527,60 +612,21 @@ pub fn invokevirtual(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
+    let frame = thread.executing_frame()?;
     let this_class = frame.class;
 
-    let (method_name, method_descriptor, implementor) = {
-        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
-            return Err(InstructionError::InvalidState {
-                context: format!(
-                    "Class not found (or not loaded): ClassId({})",
-                    frame.class.0
-                ),
-            });
-        };
-
-        let Some(ConstantPoolEntry::MethodReference {
-            method_name,
-            method_descriptor,
-            implementor,
-        }) = class.constant_pool.get_method_ref(index as usize).cloned()
-        else {
-            return Err(InstructionError::InvalidState {
-                context: format!(
-                    "MethodRef not found: ClassId({}), constant pool index {}",
-                    class.id.0, index
-                ),
-            });
-        };
-
-        (method_name, method_descriptor, implementor)
-    };
-
-    cm.request_class_load(implementor.clone()).map_err(|err| {
-        InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
-            source: Box::new(err),
-        }
-    })?;
+    let (method_name, method_descriptor, implementor) =
+        intern_get_method_ref(cm, frame.class, index, ConstantPool::method_ref)?;
     let Some((real_impl, method_id)) = cm
         .resolve_method(
-            &this_class,
-            &implementor,
+            this_class,
+            implementor,
             &method_name,
             &method_descriptor,
             false,
         )
         .map_err(|err| InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
+            class_name: class_name(cm, implementor),
             source: Box::new(err),
         })?
     else {
@@ -592,33 +638,18 @@ pub fn invokevirtual(
         });
     };
 
-    let mut args = Vec::new();
-    for _ in 0..method_descriptor.args_count() {
-        let arg = frame
-            .operand_stack
-            .pop()
-            .ok_or_else(|| InstructionError::InvalidState {
-                context: format!("Operand stack is empty"),
-            })?;
-        args.push(arg);
-    }
-    let objref = match frame.operand_stack.pop() {
-        Some(Slot::ObjectReference(objref)) => objref,
-        Some(Slot::UndefinedReference) => {
-            return Err(InstructionError::InvalidState {
-                context: "Null object reference".into(),
-            });
-        }
-        _ => {
-            return Err(InstructionError::InvalidState {
-                context: format!("Operand stack is empty, expected object reference"),
-            });
-        }
-    };
-    // TODO: Check if the type is coherent
-    args.push(Slot::ObjectReference(objref));
-    args.reverse();
-
+    let mut args = pop_arguments(frame, &method_descriptor, "invokevirtual")?;
+    let objref = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected object reference".into(),
+        })?
+        .non_null_object("invokevirtual")?;
+    // TODO: Check if the type is coherent
+    let (real_impl, method_id) = cm.select_method(objref.class_id(), (real_impl, method_id));
+    args.insert(0, Slot::ObjectReference(objref));
+
     invoke(thread, cm, real_impl, method_id, args, 3)
 }
 
@@ -628,60 +659,21 @@ pub fn invokeinterface(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
+    let frame = thread.executing_frame()?;
     let this_class = frame.class;
 
-    let (method_name, method_descriptor, implementor) = {
-        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
-            return Err(InstructionError::InvalidState {
-                context: format!(
-                    "Class not found (or not loaded): ClassId({})",
-                    frame.class.0
-                ),
-            });
-        };
-
-        let Some(ConstantPoolEntry::InterfaceMethodReference {
-            method_name,
-            method_descriptor,
-            implementor,
-        }) = class.constant_pool.get_method_ref(index as usize).cloned()
-        else {
-            return Err(InstructionError::InvalidState {
-                context: format!(
-                    "MethodRef not found: ClassId({}), constant pool index {}",
-                    class.id.0, index
-                ),
-            });
-        };
-
-        (method_name, method_descriptor, implementor)
-    };
-
-    cm.request_class_load(implementor.clone()).map_err(|err| {
-        InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
-            source: Box::new(err),
-        }
-    })?;
+    let (method_name, method_descriptor, implementor) =
+        intern_get_method_ref(cm, frame.class, index, ConstantPool::interface_method_ref)?;
     let Some((real_impl, method_id)) = cm
         .resolve_method(
-            &this_class,
-            &implementor,
+            this_class,
+            implementor,
             &method_name,
             &method_descriptor,
             false,
         )
         .map_err(|err| InstructionError::ClassLoadingError {
-            class_name: cm
-                .get_class_by_id(implementor.clone())
-                .unwrap()
-                .name()
-                .into(),
+            class_name: class_name(cm, implementor),
             source: Box::new(err),
         })?
     else {
@@ -693,34 +685,58 @@ pub fn invokeinterface(
         });
     };
 
-    let mut args = Vec::new();
-    for _ in 0..method_descriptor.args_count() {
-        let arg = frame
-            .operand_stack
-            .pop()
-            .ok_or_else(|| InstructionError::InvalidState {
-                context: format!("Operand stack is empty"),
-            })?;
-        args.push(arg);
-    }
-    let objref = match frame.operand_stack.pop() {
-        Some(Slot::ObjectReference(objref)) => objref,
-        Some(Slot::UndefinedReference) => {
+    let mut args = pop_arguments(frame, &method_descriptor, "invokeinterface")?;
+    let objref = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected object reference".into(),
+        })?
+        .non_null_object("invokeinterface")?;
+    // TODO: Check if the type is coherent
+    let (real_impl, method_id) = cm.select_method(objref.class_id(), (real_impl, method_id));
+    args.insert(0, Slot::ObjectReference(objref));
+
+    invoke(thread, cm, real_impl, method_id, args, 5)
+}
+
+/// Pop the arguments of a method from the operand stack, in declaration order.
+///
+/// Each value is checked against the type of its parameter, so that a value
+/// of the wrong category cannot shift the local variables of the callee.
+fn pop_arguments(
+    frame: &mut Frame,
+    descriptor: &MethodDescriptor,
+    instruction: &str,
+) -> Result<Vec<Slot>, InstructionError> {
+    let mut args = Vec::with_capacity(descriptor.args_count() + 1);
+    for (index, parameter) in descriptor.parameters.iter().enumerate().rev() {
+        let Some(arg) = frame.operand_stack.pop() else {
             return Err(InstructionError::InvalidState {
-                context: "Null object reference".into(),
+                context: format!(
+                    "{}: operand stack is empty, expected argument {} of {}",
+                    instruction,
+                    index,
+                    descriptor.java_syntax()
+                ),
             });
-        }
-        _ => {
+        };
+        if !arg.matches_type(parameter) {
             return Err(InstructionError::InvalidState {
-                context: format!("Operand stack is empty, expected object reference"),
+                context: format!(
+                    "{}: argument {} of {} should be a {}, got {:?}",
+                    instruction,
+                    index,
+                    descriptor.java_syntax(),
+                    parameter.java_syntax(),
+                    arg
+                ),
             });
         }
-    };
-    // TODO: Check if the type is coherent
-    args.push(Slot::ObjectReference(objref));
+        args.push(arg);
+    }
     args.reverse();
-
-    invoke(thread, cm, real_impl, method_id, args, 5)
+    Ok(args)
 }
 
 fn invoke(
@@ -731,6 +747,17 @@ fn invoke(
     args: Vec<Slot>,
     next_instruction: usize,
 ) -> Result<InstructionSuccess, InstructionError> {
+    // The getters and setters run without a frame.
+    if let Some((accessor, cache)) = accessor_of(cm, class_id, method_id) {
+        if let Some(value) = run_accessor(cm, class_id, accessor, &cache, &args) {
+            if let Some(value) = value {
+                let frame = thread.executing_frame()?;
+                frame.operand_stack.push(value);
+            }
+            return Ok(InstructionSuccess::Next(next_instruction));
+        }
+    }
+
     let Some(LoadedClass::Loaded(impl_class)) = cm.get_class_by_id(class_id) else {
         return Err(InstructionError::InvalidState {
             context: format!(
@@ -749,6 +776,34 @@ fn invoke(
         });
     };
 
+    if method.is_abstract() {
+        return Err(InstructionError::AbstractMethod {
+            context: format!(
+                "{}.{}{}",
+                impl_class.name,
+                method.name,
+                method.descriptor.as_str()
+            ),
+        });
+    }
+
+    if cm.call_log.is_some() {
+        call_log::log_entry(cm, class_id, method_id, &args, thread.stack.len());
+    }
+
+    if let Some(intrinsic) = method.intrinsic(&cm.intrinsics, &impl_class.name) {
+        let value = intrinsic(thread, cm, args)?;
+        if cm.call_log.is_some() {
+            let depth = thread.stack.len();
+            call_log::log_exit(cm, class_id, method_id, value.as_ref(), depth);
+        }
+        if let Some(value) = value {
+            let frame = thread.executing_frame()?;
+            frame.operand_stack.push(value);
+        }
+        return Ok(InstructionSuccess::Next(next_instruction));
+    }
+
     if method.is_native() {
         log::debug!(
             "Call to native method: {}::{}, {:?}, with args:\n{:?}",
@@ -757,51 +812,232 @@ fn invoke(
             method.descriptor,
             args
         );
-        log::warn!("Native methods are not implemented yet, skipping the invokation");
+        let Some(native) =
+            cm.natives
+                .get(&impl_class.name, &method.name, method.descriptor.as_str())
+        else {
+            log::warn!(
+                "Native method {}::{}{} is not implemented, skipping the invokation",
+                impl_class.name,
+                method.name,
+                method.descriptor.as_str()
+            );
+            return Ok(InstructionSuccess::Next(next_instruction));
+        };
+        let value = native(thread, cm, args)?;
+        if cm.call_log.is_some() {
+            let depth = thread.stack.len();
+            call_log::log_exit(cm, class_id, method_id, value.as_ref(), depth);
+        }
+        if let Some(value) = value {
+            let frame = thread.executing_frame()?;
+            frame.operand_stack.push(value);
+        }
         Ok(InstructionSuccess::Next(next_instruction))
     } else {
-        let code = method
-            .get_code()
-            .expect("A non-native method has no code attribute, THIS IS WRONG!");
-        let frame = Frame::new(class_id, method_id, code.max_locals as usize);
-
-        // TODO: synchronized - implement monitorenter/monitorexit
-
-        // Push the "return address" onto the stack
-        let old_pc = thread.pc + next_instruction;
+        let Some(code) = method.get_code() else {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "{}{} is neither native nor has a Code attribute",
+                    method.name,
+                    method.descriptor.as_str()
+                ),
+            });
+        };
+        let max_locals = code.max_locals as usize;
+        let max_stack = code.max_stack as usize;
+        let is_static = method.is_static();
+        let mut frame = Frame::new(class_id, method_id, max_locals, max_stack);
+        frame.fp_strictness = FpStrictness::of_method(impl_class, method);
+        let args_size = method.descriptor.slot_count() + usize::from(!is_static);
+        if args_size > max_locals {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "{}.{}{} takes {} local variables for its arguments, but has max_locals {}",
+                    impl_class.name,
+                    method.name,
+                    method.descriptor.java_syntax(),
+                    args_size,
+                    max_locals
+                ),
+            });
+        }
+        frame.set_method_arguments(&method.descriptor, is_static, args)?;
+
+        if method.is_synchronized() {
+            let monitor = if is_static {
+                cm.get_class_object(class_id).map_err(|err| {
+                    InstructionError::ClassLoadingError {
+                        class_name: "java/lang/Class".into(),
+                        source: Box::new(err),
+                    }
+                })?
+            } else {
+                let Some(Slot::ObjectReference(objref)) = frame.local_variables.first() else {
+                    return Err(InstructionError::InvalidState {
+                        context: "Expected object reference for synchronized method".into(),
+                    });
+                };
+                objref.clone()
+            };
+            monitor.monitor_enter();
+            frame.synchronized_on = Some(monitor);
+        }
 
-        let cur_frame = thread.current_frame_mut().unwrap();
-        cur_frame
-            .operand_stack
-            .push(Slot::InvokationReturnAddress(old_pc as u32));
+        // The caller resumes after the invocation, once the method returns.
+        frame.return_info = Some(ReturnInfo {
+            pc: thread.pc() + next_instruction,
+        });
 
         // Push the new frame onto the stack, with the arguments in the local variables.
         thread.push_frame(frame);
-        let frame = thread.current_frame_mut().unwrap();
-        let mut arg_pos = 0;
-        for arg in args.into_iter() {
-            match arg {
-                Slot::Int(_)
-                | Slot::Float(_)
-                | Slot::UndefinedReference
-                | Slot::ArrayReference(_)
-                | Slot::ObjectReference(_)
-                | Slot::ReturnAddress(_) => {
-                    frame.local_variables[arg_pos] = arg;
-                    arg_pos += 1;
-                }
-                Slot::Long(_) | Slot::Double(_) => {
-                    frame.local_variables[arg_pos] = arg;
-                    frame.local_variables[arg_pos + 1] = Slot::Tombstone;
-                    arg_pos += 2;
-                }
-                _ => {
-                    panic!("Invalid argument type");
-                }
-            }
+        Ok(InstructionSuccess::FrameChange)
+    }
+}
+
+/// Get the field accessed by a method if it is a getter or a setter that can
+/// run without a frame: an instance method, neither synchronized nor replaced
+/// by an intrinsic, where no breakpoint is set and whose calls are not logged.
+///
+/// The instruction cache of the method is given with it, for its inline cache.
+fn accessor_of(
+    cm: &ClassManager,
+    class_id: ClassId,
+    method_id: usize,
+) -> Option<(Accessor, Arc<InstructionCache>)> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return None;
+    };
+    let method = class.get_method_by_index(method_id)?;
+    let cache = &method.get_code()?.cache;
+    let accessor = cache.accessor()?;
+    // The value must have the type of the descriptor, else the return or the
+    // load instruction fails in a frame.
+    let (value_type, descriptor_type) = match accessor {
+        Accessor::Getter(_, value_type) => (value_type, method.descriptor.return_type.as_ref()),
+        Accessor::Setter(_, value_type) => (value_type, method.descriptor.parameters.first()),
+    };
+    if !descriptor_type.is_some_and(|field_type| value_type.holds(field_type)) {
+        return None;
+    }
+    if method.is_static()
+        || method.is_synchronized()
+        || cm.breakpoints.in_method(class_id, method_id)
+        || cm
+            .call_log
+            .as_ref()
+            .is_some_and(|filter| filter.matches(&class.name))
+        || method.intrinsic(&cm.intrinsics, &class.name).is_some()
+    {
+        return None;
+    }
+    Some((accessor, cache.clone()))
+}
+
+/// Run a getter or a setter of the given class on its arguments, the receiver first.
+///
+/// The field is looked up in the inline cache of the method, and only resolved
+/// for the receivers of another class than the cached one.
+///
+/// Returns the value got, if any, or None if the method must run in its own
+/// frame, see [resolve_accessor_field].
+fn run_accessor(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    accessor: Accessor,
+    cache: &InstructionCache,
+    args: &[Slot],
+) -> Option<Option<Slot>> {
+    let Some(Slot::ObjectReference(objref)) = args.first() else {
+        return None;
+    };
+    let field = match cache.accessor_cache().get(objref.class_id()) {
+        Some(field) => field,
+        None => {
+            let field = resolve_accessor_field(cm, class_id, accessor, objref)?;
+            cache.accessor_cache().set(field.clone());
+            field
+        }
+    };
+    match accessor {
+        Accessor::Getter(..) => Some(Some(objref.get_field(field.slot)?)),
+        Accessor::Setter(..) => {
+            // Narrowed as putfield does, see Field::narrow.
+            let value = match (args.get(1)?.clone(), &field.base_type) {
+                (Slot::Int(value), Some(base)) => Slot::Int(narrow_int(value, base)),
+                (value, _) => value,
+            };
+            objref.set_field(field.slot, value);
+            Some(None)
+        }
+    }
+}
+
+/// Resolve the field of a getter or a setter of the given class for a receiver.
+///
+/// Returns None if the method must run in its own frame: the field cannot be
+/// resolved, is static, is not of the type of the value, or is final for a
+/// setter. The errors are then reported by the instructions of the method.
+fn resolve_accessor_field(
+    cm: &mut ClassManager,
+    class_id: ClassId,
+    accessor: Accessor,
+    objref: &ObjectRef,
+) -> Option<CachedField> {
+    let (index, value_type) = match accessor {
+        Accessor::Getter(index, value_type) | Accessor::Setter(index, value_type) => {
+            (index, value_type)
         }
-        Ok(InstructionSuccess::FrameChange(0))
+    };
+    let (_, field, slot) = intern_get_field(cm, class_id, index, Some(objref)).ok()?;
+    let field_type = field.descriptor.field_type();
+    if field.is_static() || !value_type.holds(field_type) {
+        return None;
+    }
+    if matches!(accessor, Accessor::Setter(..)) && field.is_final() {
+        return None;
+    }
+    let base_type = match field_type {
+        FieldType::BaseType(base) => Some(base.clone()),
+        _ => None,
+    };
+    Some(CachedField {
+        receiver: objref.class_id(),
+        slot,
+        base_type,
+    })
+}
+
+/// `invokedynamic` links a dynamically-computed call site and puts the object it
+/// produces on the operand stack.
+///
+/// Only the call sites of the lambda metafactory are supported: the produced
+/// object is a lambda holding the arguments of the call site.
+pub fn invokedynamic(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    index: u16,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let lambda_class = bootstrap::resolve_call_site(cm, frame.class, index)?;
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(lambda_class) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Lambda class not loaded: ClassId({})", lambda_class.0),
+        });
+    };
+    let mut captured = Vec::with_capacity(class.fields.len());
+    for _ in 0..class.fields.len() {
+        let Some(value) = frame.operand_stack.pop() else {
+            return Err(InstructionError::InvalidState {
+                context: "Missing captured value of a lambda on the operand stack".into(),
+            });
+        };
+        captured.push(value);
     }
+    captured.reverse();
+    let obj = lambda::new_lambda(cm, lambda_class, captured)?;
+    frame.operand_stack.push(Slot::ObjectReference(obj));
+    Ok(InstructionSuccess::Next(5))
 }
 
 /// `new` creates a new object of a given class and pushes a reference to it onto the operand stack.
@@ -810,15 +1046,13 @@ pub fn new(
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
+    let current_class = thread.executing_frame()?.class;
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(current_class) else {
         return Err(InstructionError::InvalidState {
-            context: format!("Class not found: ClassId({})", frame.class.0),
+            context: format!("Class not found: ClassId({})", current_class.0),
         });
     };
-    let Some(ConstantPoolEntry::ClassReference(class_id)) =
-        class.constant_pool.get_class_ref(index as usize).cloned()
-    else {
+    let Some(class_id) = class.constant_pool.class_ref(index as usize) else {
         return Err(InstructionError::InvalidState {
             context: format!(
                 "ClassRef not found: ClassId({}), constant pool index {}",
@@ -829,21 +1063,36 @@ pub fn new(
 
     let obj = Object::new_with_classmanager(cm, class_id).map_err(|err| {
         InstructionError::ClassLoadingError {
-            class_name: cm.get_class_by_id(class_id).unwrap().name().into(),
+            class_name: class_name(cm, class_id),
             source: Box::new(err),
         }
     })?;
 
-    frame
-        .operand_stack
-        .push(Slot::ObjectReference(Gc::new(obj)));
+    // Interfaces and abstract classes cannot be instantiated
+    if let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) {
+        if class.is_interface() || class.is_abstract() {
+            return Err(InstructionError::Instantiation {
+                context: format!("{} is an interface or an abstract class", class.name),
+            });
+        }
+    }
+
+    let obj = cm.alloc_object(obj);
+    let frame = thread.executing_frame()?;
+    frame.operand_stack.push(Slot::ObjectReference(obj));
     Ok(InstructionSuccess::Next(3))
 }
 
 /// `newarray` creates a new array of a given primitive type and size.
-pub fn newarray(thread: &mut Thread, atype: u8) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let count = frame.operand_stack.pop().unwrap();
+///
+/// The array must fit in the heap budget of the VM.
+pub fn newarray(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    atype: u8,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let count = frame.pop()?;
     let count = match count {
         Slot::Int(count) => count,
         _ => {
@@ -857,38 +1106,54 @@ pub fn newarray(thread: &mut Thread, atype: u8) -> Result<InstructionSuccess, In
             context: format!("newarray - count is negative: {}", count),
         });
     }
+    let item_size = match atype {
+        4 => size_of::<bool>(),
+        5 => size_of::<u16>(),
+        6 => size_of::<f32>(),
+        7 => size_of::<f64>(),
+        8 => size_of::<i8>(),
+        9 => size_of::<i16>(),
+        10 => size_of::<i32>(),
+        11 => size_of::<i64>(),
+        _ => {
+            return Err(InstructionError::InvalidState {
+                context: format!("newarray - invalid atype: {}", atype),
+            });
+        }
+    };
+    cm.check_array_budget(count as usize, item_size)?;
     let array = match atype {
         4 => {
             let array = BoolArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         5 => {
             let array = CharArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         6 => {
             let array = FloatArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         7 => {
             let array = DoubleArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         8 => {
             let array = ByteArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         9 => {
             let array = ShortArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         10 => {
             let array = IntArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         11 => {
             let array = LongArray::new(count as usize);
-            Slot::ArrayReference(Gc::new(array.into()))
+            Slot::ArrayReference(cm.alloc_array(array.into()))
         }
         _ => {
             return Err(InstructionError::InvalidState {
@@ -901,13 +1166,15 @@ pub fn newarray(thread: &mut Thread, atype: u8) -> Result<InstructionSuccess, In
 }
 
 /// `anewarray` creates a new array of a given reference type and size.
+///
+/// The array must fit in the heap budget of the VM.
 pub fn anewarray(
     thread: &mut Thread,
     cm: &mut ClassManager,
     index: u16,
 ) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let count = frame.operand_stack.pop().unwrap();
+    let frame = thread.executing_frame()?;
+    let count = frame.pop()?;
     let count = match count {
         Slot::Int(count) => count,
         _ => {
@@ -922,28 +1189,27 @@ pub fn anewarray(
         });
     }
 
-    let class = cm.get_class_by_id(frame.class).unwrap();
     let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
         return Err(InstructionError::InvalidState {
             context: format!("Class not found: ClassId({})", frame.class.0),
         });
     };
-    if let Some(ConstantPoolEntry::ClassReference(class_id)) =
-        class.constant_pool.get_class_ref(index as usize)
-    {
+    if let Some(class_id) = class.constant_pool.class_ref(index as usize) {
         // It is an object reference
-        let arr = ObjectRefArray::new(class_id.clone(), count as usize);
+        cm.check_array_budget(count as usize, size_of::<Option<ObjectRef>>())?;
+        let arr = ObjectRefArray::new(class_id, count as usize);
         frame
             .operand_stack
-            .push(Slot::ArrayReference(Gc::new(arr.into())));
+            .push(Slot::ArrayReference(cm.alloc_array(arr.into())));
     } else if let Some(ConstantPoolEntry::ArrayReference(FieldType::ArrayType(item_ty))) =
         class.constant_pool.get_array_ref(index as usize)
     {
         // It is an array reference
+        cm.check_array_budget(count as usize, size_of::<Option<ArrayRef>>())?;
         let arr = ArrayRefArray::new(item_ty.clone(), count as usize);
         frame
             .operand_stack
-            .push(Slot::ArrayReference(Gc::new(arr.into())));
+            .push(Slot::ArrayReference(cm.alloc_array(arr.into())));
     } else {
         return Err(InstructionError::InvalidState {
             context: format!(
@@ -955,18 +1221,278 @@ pub fn anewarray(
     Ok(InstructionSuccess::Next(3))
 }
 
-/// `arraylength` gets the length of an array and pushes it onto the operand stack.
-pub fn arraylength(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
-    let frame = thread.current_frame_mut().unwrap();
-    let array_ref = frame.operand_stack.pop().unwrap();
-    let len = match array_ref {
-        Slot::ArrayReference(array_ref) => array_ref.len(),
-        _ => {
+/// `multianewarray` creates a new multidimensional array, of the given array
+/// type and with `dimensions` dimensions, whose lengths are popped from the
+/// operand stack.
+///
+/// All the nested arrays must fit in the heap budget of the VM.
+pub fn multianewarray(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    index: u16,
+    dimensions: u8,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found: ClassId({})", frame.class.0),
+        });
+    };
+    let Some(ConstantPoolEntry::ArrayReference(FieldType::ArrayType(array_ty))) =
+        class.constant_pool.get_array_ref(index as usize).cloned()
+    else {
+        return Err(InstructionError::InvalidState {
+            context: format!(
+                "multianewarray - ArrayRef not found: ClassId({}), constant pool index {}",
+                class.id.0, index
+            ),
+        });
+    };
+    let depth = std::iter::successors(Some(&array_ty), |ty| match ty.item.as_ref() {
+        FieldType::ArrayType(item) => Some(item),
+        _ => None,
+    })
+    .count();
+    if dimensions == 0 || dimensions as usize > depth {
+        return Err(InstructionError::InvalidState {
+            context: format!(
+                "multianewarray - {} dimensions for an array of depth {}",
+                dimensions, depth
+            ),
+        });
+    }
+
+    let mut counts = vec![0; dimensions as usize];
+    for count in counts.iter_mut().rev() {
+        let value = frame.pop_int()?;
+        if value < 0 {
             return Err(InstructionError::InvalidState {
-                context: format!("arraylength - invalid array reference: {:?}", array_ref),
+                context: format!("multianewarray - count is negative: {}", value),
             });
         }
+        *count = value as usize;
+    }
+    let size = multi_array_size(&array_ty, &counts).ok_or(AllocError::SizeOverflow {
+        count: counts
+            .iter()
+            .fold(1, |total, count| total.saturating_mul(*count)),
+        item_size: item_size(&array_ty.item),
+    })?;
+    cm.check_array_budget(size, 1)?;
+    let array = new_multi_array(cm, &array_ty, &counts)?;
+    let frame = thread.executing_frame()?;
+    frame.operand_stack.push(Slot::ArrayReference(array));
+    Ok(InstructionSuccess::Next(4))
+}
+
+/// Size (in bytes) of an element of an array holding the given type.
+fn item_size(item: &FieldType) -> usize {
+    match item {
+        FieldType::BaseType(base) => Array::primitive_item_size(base),
+        FieldType::ObjectType(_) => size_of::<Option<ObjectRef>>(),
+        FieldType::ArrayType(_) => size_of::<Option<ArrayRef>>(),
+    }
+}
+
+/// Size (in bytes) of the elements of a multidimensional array and of its
+/// nested arrays, None on overflow.
+fn multi_array_size(array_ty: &ArrayType, counts: &[usize]) -> Option<usize> {
+    let (count, nested) = counts.split_first()?;
+    let size = count.checked_mul(item_size(&array_ty.item))?;
+    match (array_ty.item.as_ref(), nested.is_empty()) {
+        (FieldType::ArrayType(item_ty), false) => {
+            size.checked_add(count.checked_mul(multi_array_size(item_ty, nested)?)?)
+        }
+        _ => Some(size),
+    }
+}
+
+/// Create a multidimensional array, the arrays of the last dimension being
+/// filled with default values.
+fn new_multi_array(
+    cm: &mut ClassManager,
+    array_ty: &ArrayType,
+    counts: &[usize],
+) -> Result<ArrayRef, InstructionError> {
+    let Some((&count, nested)) = counts.split_first() else {
+        return Err(InstructionError::Verify {
+            context: "multianewarray - an array of no dimension".into(),
+        });
+    };
+    let array: Array = match array_ty.item.as_ref() {
+        FieldType::ArrayType(item_ty) => {
+            let array = ArrayRefArray::new(item_ty.clone(), count);
+            if !nested.is_empty() {
+                for index in 0..count {
+                    array.set(index, Some(new_multi_array(cm, item_ty, nested)?));
+                }
+            }
+            array.into()
+        }
+        FieldType::ObjectType(object) => {
+            let class_name = object.class_name.as_binary_name();
+            let class_id = cm
+                .get_or_resolve_class(&class_name)
+                .map_err(|err| InstructionError::ClassLoadingError {
+                    class_name: class_name.to_string(),
+                    source: Box::new(err),
+                })?
+                .id();
+            ObjectRefArray::new(class_id, count).into()
+        }
+        FieldType::BaseType(base) => Array::new_primitive(base, count),
     };
+    Ok(cm.alloc_array(array))
+}
+
+/// `arraylength` gets the length of an array and pushes it onto the operand stack.
+pub fn arraylength(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let len = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected array reference".into(),
+        })?
+        .non_null_array("arraylength")?
+        .len();
     frame.operand_stack.push(Slot::Int(len as i32));
     Ok(InstructionSuccess::Next(1))
 }
+
+/// `monitorenter` enters the monitor associated with an object.
+///
+/// The monitor is recorded in the current frame, so it can be released when
+/// the frame is popped.
+pub fn monitorenter(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    // TODO: Arrays do not have a monitor yet
+    let objref = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected object reference".into(),
+        })?
+        .non_null_object("monitorenter")?;
+    objref.monitor_enter();
+    frame.monitors.push(objref);
+    Ok(InstructionSuccess::Next(1))
+}
+
+/// `monitorexit` exits the monitor associated with an object.
+///
+/// Only monitors entered by the current frame can be exited, any other
+/// attempt is reported as an IllegalMonitorState error.
+pub fn monitorexit(thread: &mut Thread) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let objref = frame
+        .operand_stack
+        .pop()
+        .ok_or_else(|| InstructionError::InvalidState {
+            context: "Operand stack is empty, expected object reference".into(),
+        })?
+        .non_null_object("monitorexit")?;
+    let Some(pos) = frame
+        .monitors
+        .iter()
+        .rposition(|monitor| std::ptr::eq::<Object>(&**monitor, &*objref))
+    else {
+        return Err(InstructionError::IllegalMonitorState {
+            context: "monitorexit - monitor not held by the current frame".into(),
+        });
+    };
+    let monitor = frame.monitors.remove(pos);
+    if !monitor.monitor_exit() {
+        return Err(InstructionError::IllegalMonitorState {
+            context: "monitorexit - monitor count mismatch".into(),
+        });
+    }
+    Ok(InstructionSuccess::Next(1))
+}
+
+/// Internal helper to get the type referenced by a ClassRef/ArrayRef in the constant pool.
+fn intern_get_type(
+    cm: &ClassManager,
+    class: ClassId,
+    cp_index: u16,
+) -> Result<FieldTypeOrClassId, InstructionError> {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class) else {
+        return Err(InstructionError::InvalidState {
+            context: format!("Class not found: ClassId({})", class.0),
+        });
+    };
+    match class.constant_pool.get(cp_index as usize) {
+        Some(ConstantPoolEntry::ClassReference(class_id)) => Ok((*class_id).into()),
+        Some(ConstantPoolEntry::ArrayReference(field_type)) => Ok(field_type.clone().into()),
+        _ => Err(InstructionError::InvalidState {
+            context: format!(
+                "ClassRef/ArrayRef not found: ClassId({}), constant pool index {}",
+                class.id.0, cp_index
+            ),
+        }),
+    }
+}
+
+/// `checkcast` checks whether an object is of a given type.
+///
+/// The object reference is left untouched on the operand stack if the check
+/// succeeds, null references always pass the check.
+pub fn checkcast(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    index: u16,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let Some(objref) = frame.operand_stack.last() else {
+        return Err(InstructionError::InvalidState {
+            context: "Expected reference on the operand stack".into(),
+        });
+    };
+    if !objref.is_reference() {
+        return Err(InstructionError::InvalidState {
+            context: format!("checkcast - expected reference but got {:?}", objref),
+        });
+    }
+    let Some(from) = types::type_of(cm, objref) else {
+        // null can be cast to any type
+        return Ok(InstructionSuccess::Next(3));
+    };
+    let to = intern_get_type(cm, frame.class, index)?;
+    if !types::is_assignable(cm, &from, &to) {
+        return Err(InstructionError::ClassCast {
+            context: format!("{:?} cannot be cast to {:?}", from, to),
+        });
+    }
+    Ok(InstructionSuccess::Next(3))
+}
+
+/// `instanceof` determines if an object is of a given type.
+///
+/// Pushes 1 onto the operand stack if the object is an instance of the type,
+/// 0 otherwise (or if the reference is null).
+pub fn instanceof(
+    thread: &mut Thread,
+    cm: &mut ClassManager,
+    index: u16,
+) -> Result<InstructionSuccess, InstructionError> {
+    let frame = thread.executing_frame()?;
+    let Some(objref) = frame.operand_stack.pop() else {
+        return Err(InstructionError::InvalidState {
+            context: "Expected reference on the operand stack".into(),
+        });
+    };
+    if !objref.is_reference() {
+        return Err(InstructionError::InvalidState {
+            context: format!("instanceof - expected reference but got {:?}", objref),
+        });
+    }
+    let result = match types::type_of(cm, &objref) {
+        Some(from) => {
+            let to = intern_get_type(cm, frame.class, index)?;
+            types::is_assignable(cm, &from, &to)
+        }
+        None => false,
+    };
+    frame.operand_stack.push(Slot::Int(result as i32));
+    Ok(InstructionSuccess::Next(3))
+}
@@ -0,0 +1,313 @@
+use std::fmt;
+
+/// Mnemonics of the instructions of the JVM, by opcode.
+const MNEMONICS: [&str; 0xcb] = [
+    "nop",
+    "aconst_null",
+    "iconst_m1",
+    "iconst_0",
+    "iconst_1",
+    "iconst_2",
+    "iconst_3",
+    "iconst_4",
+    "iconst_5",
+    "lconst_0",
+    "lconst_1",
+    "fconst_0",
+    "fconst_1",
+    "fconst_2",
+    "dconst_0",
+    "dconst_1",
+    "bipush",
+    "sipush",
+    "ldc",
+    "ldc_w",
+    "ldc2_w",
+    "iload",
+    "lload",
+    "fload",
+    "dload",
+    "aload",
+    "iload_0",
+    "iload_1",
+    "iload_2",
+    "iload_3",
+    "lload_0",
+    "lload_1",
+    "lload_2",
+    "lload_3",
+    "fload_0",
+    "fload_1",
+    "fload_2",
+    "fload_3",
+    "dload_0",
+    "dload_1",
+    "dload_2",
+    "dload_3",
+    "aload_0",
+    "aload_1",
+    "aload_2",
+    "aload_3",
+    "iaload",
+    "laload",
+    "faload",
+    "daload",
+    "aaload",
+    "baload",
+    "caload",
+    "saload",
+    "istore",
+    "lstore",
+    "fstore",
+    "dstore",
+    "astore",
+    "istore_0",
+    "istore_1",
+    "istore_2",
+    "istore_3",
+    "lstore_0",
+    "lstore_1",
+    "lstore_2",
+    "lstore_3",
+    "fstore_0",
+    "fstore_1",
+    "fstore_2",
+    "fstore_3",
+    "dstore_0",
+    "dstore_1",
+    "dstore_2",
+    "dstore_3",
+    "astore_0",
+    "astore_1",
+    "astore_2",
+    "astore_3",
+    "iastore",
+    "lastore",
+    "fastore",
+    "dastore",
+    "aastore",
+    "bastore",
+    "castore",
+    "sastore",
+    "pop",
+    "pop2",
+    "dup",
+    "dup_x1",
+    "dup_x2",
+    "dup2",
+    "dup2_x1",
+    "dup2_x2",
+    "swap",
+    "iadd",
+    "ladd",
+    "fadd",
+    "dadd",
+    "isub",
+    "lsub",
+    "fsub",
+    "dsub",
+    "imul",
+    "lmul",
+    "fmul",
+    "dmul",
+    "idiv",
+    "ldiv",
+    "fdiv",
+    "ddiv",
+    "irem",
+    "lrem",
+    "frem",
+    "drem",
+    "ineg",
+    "lneg",
+    "fneg",
+    "dneg",
+    "ishl",
+    "lshl",
+    "ishr",
+    "lshr",
+    "iushr",
+    "lushr",
+    "iand",
+    "land",
+    "ior",
+    "lor",
+    "ixor",
+    "lxor",
+    "iinc",
+    "i2l",
+    "i2f",
+    "i2d",
+    "l2i",
+    "l2f",
+    "l2d",
+    "f2i",
+    "f2l",
+    "f2d",
+    "d2i",
+    "d2l",
+    "d2f",
+    "i2b",
+    "i2c",
+    "i2s",
+    "lcmp",
+    "fcmpl",
+    "fcmpg",
+    "dcmpl",
+    "dcmpg",
+    "ifeq",
+    "ifne",
+    "iflt",
+    "ifge",
+    "ifgt",
+    "ifle",
+    "if_icmpeq",
+    "if_icmpne",
+    "if_icmplt",
+    "if_icmpge",
+    "if_icmpgt",
+    "if_icmple",
+    "if_acmpeq",
+    "if_acmpne",
+    "goto",
+    "jsr",
+    "ret",
+    "tableswitch",
+    "lookupswitch",
+    "ireturn",
+    "lreturn",
+    "freturn",
+    "dreturn",
+    "areturn",
+    "return",
+    "getstatic",
+    "putstatic",
+    "getfield",
+    "putfield",
+    "invokevirtual",
+    "invokespecial",
+    "invokestatic",
+    "invokeinterface",
+    "invokedynamic",
+    "new",
+    "newarray",
+    "anewarray",
+    "arraylength",
+    "athrow",
+    "checkcast",
+    "instanceof",
+    "monitorenter",
+    "monitorexit",
+    "wide",
+    "multianewarray",
+    "ifnull",
+    "ifnonnull",
+    "goto_w",
+    "jsr_w",
+    "breakpoint",
+];
+
+/// Get the mnemonic of an opcode, e.g. `iadd` for 0x60.
+///
+/// The opcodes reserved for the implementations are named after the JVM
+/// specification, there is no mnemonic for the unassigned ones.
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    match opcode {
+        0xfe => Some("impdep1"),
+        0xff => Some("impdep2"),
+        _ => MNEMONICS.get(opcode as usize).copied(),
+    }
+}
+
+/// Number of executions of each instruction, by opcode.
+///
+/// The instructions are counted before being executed, the unimplemented
+/// ones and the ones failing included. The quickened instructions are
+/// accounted to their original opcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpcodeHistogram {
+    counts: [u64; 256],
+}
+
+impl OpcodeHistogram {
+    pub fn new() -> Self {
+        Self { counts: [0; 256] }
+    }
+
+    /// Record an execution of an instruction.
+    pub fn record(&mut self, opcode: u8) {
+        self.counts[opcode as usize] += 1;
+    }
+
+    /// Get the number of executions of an opcode.
+    pub fn count(&self, opcode: u8) -> u64 {
+        self.counts[opcode as usize]
+    }
+
+    /// Get the total number of executed instructions.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Get the executed opcodes with their count, the most executed first.
+    pub fn sorted(&self) -> Vec<(u8, u64)> {
+        let mut executed: Vec<(u8, u64)> = (0..=u8::MAX)
+            .map(|opcode| (opcode, self.count(opcode)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        executed.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        executed
+    }
+}
+
+impl Default for OpcodeHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for OpcodeHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        writeln!(
+            f,
+            "{:<20}{:>8}{:>14}{:>9}",
+            "instruction", "opcode", "count", "%"
+        )?;
+        for (opcode, count) in self.sorted() {
+            let name = match mnemonic(opcode) {
+                Some(name) => name.to_string(),
+                None => format!("<0x{:02x}>", opcode),
+            };
+            writeln!(
+                f,
+                "{:<20}{:>8}{:>14}{:>9.2}",
+                name,
+                format!("0x{:02x}", opcode),
+                count,
+                count as f64 * 100.0 / total as f64
+            )?;
+        }
+        write!(f, "{:<20}{:>8}{:>14}", "total", "", total)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn opcode_histogram() {
+        let mut histogram = OpcodeHistogram::new();
+        histogram.record(0x60);
+        histogram.record(0x1a);
+        histogram.record(0x60);
+
+        assert_eq!(mnemonic(0x60), Some("iadd"));
+        assert_eq!(mnemonic(0xc9), Some("jsr_w"));
+        assert_eq!(mnemonic(0xe0), None);
+        assert_eq!(histogram.count(0x60), 2);
+        assert_eq!(histogram.total(), 3);
+        assert_eq!(histogram.sorted(), vec![(0x60, 2), (0x1a, 1)]);
+    }
+}
@@ -0,0 +1,246 @@
+//! The operand stack of the frames, and of the stacks simulated from the
+//! stack map frames.
+//!
+//! The values of category 2 (long and double) are a single entry of the stack,
+//! but take two units of its depth. The stack instructions (`pop2`, `dup2`,
+//! `dup_x2`...) work on units, they are implemented once for both kinds of
+//! stacks by [OperandStack::dup_x] and [OperandStack::pop_units].
+
+use std::{ops::Deref, slice};
+
+use crate::{class::VerificationType, opcode::InstructionError, slot::Slot};
+
+/// A value held by an operand stack.
+pub trait StackValue: Clone {
+    /// Get the number of units of depth taken by the value: 2 for a long or a
+    /// double, 1 otherwise.
+    fn category(&self) -> usize;
+}
+
+impl StackValue for Slot {
+    fn category(&self) -> usize {
+        match self {
+            Slot::Long(_) | Slot::Double(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl StackValue for VerificationType {
+    fn category(&self) -> usize {
+        match self {
+            VerificationType::Long | VerificationType::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// An operand stack, tracking its depth in units along its values.
+///
+/// The values are read as a slice, from the bottom to the top of the stack,
+/// but only modified through the methods keeping the depth up to date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperandStack<T: StackValue = Slot> {
+    values: Vec<T>,
+    /// The depth of the stack, the values of category 2 counting twice.
+    depth: usize,
+}
+
+impl<T: StackValue> OperandStack<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Create an empty stack, with room for `capacity` values.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: Vec::with_capacity(capacity),
+            depth: 0,
+        }
+    }
+
+    /// Get the depth of the stack, in units: the values of category 2 count twice.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.depth += value.category();
+        self.values.push(value);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.values.pop()?;
+        self.depth -= value.category();
+        Some(value)
+    }
+
+    /// Remove all the values of the stack.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.depth = 0;
+    }
+
+    /// Get the values, from the bottom to the top of the stack.
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    /// Pop `units` units of depth (1 or 2), e.g. `pop2` pops a long or two ints.
+    ///
+    /// Fails without modifying the stack if the units would split a value of
+    /// category 2.
+    pub fn pop_units(&mut self, units: usize, instruction: &str) -> Result<(), InstructionError> {
+        let count = self.values_in_units(units, 0, instruction)?;
+        let rest = self.values.len() - count;
+        self.values.truncate(rest);
+        self.depth -= units;
+        Ok(())
+    }
+
+    /// Duplicate the `units` top units of depth (1 or 2), and insert the copy
+    /// under the `under` units below them (0, 1 or 2).
+    ///
+    /// This is the semantics of the `dup` family: `dup` is `dup_x(1, 0)`,
+    /// `dup_x2` is `dup_x(1, 2)`, `dup2_x1` is `dup_x(2, 1)`... Fails without
+    /// modifying the stack if the units would split a value of category 2.
+    pub fn dup_x(
+        &mut self,
+        units: usize,
+        under: usize,
+        instruction: &str,
+    ) -> Result<(), InstructionError> {
+        let count = self.values_in_units(units, 0, instruction)?;
+        let under_count = self.values_in_units(under, count, instruction)?;
+        let top = self.values.len() - count;
+        let copy: Vec<T> = self.values[top..].to_vec();
+        let at = top - under_count;
+        self.values.splice(at..at, copy);
+        self.depth += units;
+        Ok(())
+    }
+
+    /// Swap the two top values, both of category 1.
+    pub fn swap(&mut self, instruction: &str) -> Result<(), InstructionError> {
+        let len = self.values.len();
+        self.values_in_units(1, 0, instruction)?;
+        self.values_in_units(1, 1, instruction)?;
+        self.values.swap(len - 1, len - 2);
+        Ok(())
+    }
+
+    /// Get the number of values taking exactly `units` units of depth, below
+    /// the `skip` top values.
+    fn values_in_units(
+        &self,
+        units: usize,
+        skip: usize,
+        instruction: &str,
+    ) -> Result<usize, InstructionError> {
+        let mut taken = 0;
+        let mut count = 0;
+        for value in self.values.iter().rev().skip(skip) {
+            if taken >= units {
+                break;
+            }
+            taken += value.category();
+            count += 1;
+        }
+        if taken < units {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "Illegal operation, {} on a stack of depth {}",
+                    instruction, self.depth
+                ),
+            });
+        }
+        if taken > units {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "Illegal operation, {} would split a long/double slot",
+                    instruction
+                ),
+            });
+        }
+        Ok(count)
+    }
+}
+
+impl<T: StackValue> Default for OperandStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: StackValue> Deref for OperandStack<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.values
+    }
+}
+
+impl<T: StackValue> Extend<T> for OperandStack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        for value in values {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: StackValue> From<Vec<T>> for OperandStack<T> {
+    fn from(values: Vec<T>) -> Self {
+        let mut stack = Self::with_capacity(values.len());
+        stack.extend(values);
+        stack
+    }
+}
+
+impl<'a, T: StackValue> IntoIterator for &'a OperandStack<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unit_accounting() {
+        let mut stack = OperandStack::from(vec![Slot::Int(1), Slot::Long(2), Slot::Int(3)]);
+        assert_eq!((stack.len(), stack.depth()), (3, 4));
+
+        // dup_x2, form 2: the int goes under the long.
+        stack.dup_x(1, 2, "dup_x2").unwrap();
+        assert!(matches!(
+            stack[..],
+            [Slot::Int(1), Slot::Int(3), Slot::Long(2), Slot::Int(3)]
+        ));
+        assert_eq!(stack.depth(), 5);
+
+        // dup2 on a long duplicates it, pop cannot take half of it.
+        stack.pop();
+        assert!(stack.dup_x(2, 0, "dup2").is_ok());
+        assert!(stack.pop_units(1, "pop").is_err());
+        assert_eq!(stack.depth(), 6);
+        stack.pop_units(2, "pop2").unwrap();
+        stack.pop_units(2, "pop2").unwrap();
+        assert!(matches!(stack[..], [Slot::Int(1), Slot::Int(3)]));
+        stack.swap("swap").unwrap();
+        assert!(matches!(stack[..], [Slot::Int(3), Slot::Int(1)]));
+        assert!(stack.dup_x(1, 2, "dup_x2").is_err());
+        assert_eq!(stack.depth(), 2);
+
+        // The simulated stacks of the verifier follow the same rules.
+        let mut types =
+            OperandStack::from(vec![VerificationType::Double, VerificationType::Integer]);
+        assert!(types.swap("swap").is_err());
+        types.dup_x(1, 2, "dup_x2").unwrap();
+        assert_eq!(types.depth(), 4);
+        assert_eq!(types[0], VerificationType::Integer);
+    }
+}
@@ -5,6 +5,7 @@ use reader::descriptor::{ArrayType, BaseType, FieldDescriptor, FieldType};
 use crate::{
     alloc::{Array, ArrayRef, ObjectRef},
     class::ConstantValue,
+    opcode::InstructionError,
 };
 
 #[derive(Debug, Clone, Collectable)]
@@ -19,10 +20,6 @@ pub enum Slot {
     Float(f32),
     Double(f64),
     ReturnAddress(u32),
-    /// This item is used to know the new instruction when returning from a method.
-    ///
-    /// It is an internal implementation detail and should not be used by the user.
-    InvokationReturnAddress(u32),
     ArrayReference(ArrayRef),
     ObjectReference(ObjectRef),
     /// Basically the Null Pointer representation
@@ -36,7 +33,6 @@ impl Slot {
             Slot::Int(_)
             | Slot::Float(_)
             | Slot::ReturnAddress(_)
-            | Slot::InvokationReturnAddress(_)
             | Slot::ArrayReference(_)
             | Slot::ObjectReference(_)
             | Slot::UndefinedReference => 1,
@@ -64,6 +60,99 @@ impl Slot {
             _ => false,
         }
     }
+
+    /// Check if the slot holds a value of the given type, the booleans, bytes,
+    /// chars and shorts being ints. The classes of the references are not checked.
+    pub fn matches_type(&self, field_type: &FieldType) -> bool {
+        match field_type {
+            FieldType::BaseType(BaseType::Float) => matches!(self, Slot::Float(_)),
+            FieldType::BaseType(BaseType::Long) => matches!(self, Slot::Long(_)),
+            FieldType::BaseType(BaseType::Double) => matches!(self, Slot::Double(_)),
+            FieldType::BaseType(_) => matches!(self, Slot::Int(_)),
+            FieldType::ObjectType(_) | FieldType::ArrayType(_) => self.is_reference(),
+        }
+    }
+
+    /// Check if the slot is the null reference.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Slot::UndefinedReference)
+    }
+
+    /// Check if two references are the same, i.e. both null or both referencing
+    /// the same object or array.
+    ///
+    /// Returns None if any of the slots is not a reference.
+    pub fn same_reference(&self, other: &Slot) -> Option<bool> {
+        match (self, other) {
+            (Slot::UndefinedReference, Slot::UndefinedReference) => Some(true),
+            (Slot::ObjectReference(obj1), Slot::ObjectReference(obj2)) => {
+                Some(std::ptr::eq(obj1.as_ref(), obj2.as_ref()))
+            }
+            (Slot::ArrayReference(arr1), Slot::ArrayReference(arr2)) => {
+                Some(std::ptr::eq(arr1.as_ref(), arr2.as_ref()))
+            }
+            (x, y) if x.is_reference() && y.is_reference() => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Get the referenced object, if the slot is a non-null object reference.
+    pub fn as_object(&self) -> Option<&ObjectRef> {
+        match self {
+            Slot::ObjectReference(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Get the referenced array, if the slot is a non-null array reference.
+    pub fn as_array(&self) -> Option<&ArrayRef> {
+        match self {
+            Slot::ArrayReference(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// Get the referenced object, or None for the null reference.
+    ///
+    /// Any value that is not an object reference is an InvalidState error.
+    pub fn nullable_object(self, context: &str) -> Result<Option<ObjectRef>, InstructionError> {
+        match self {
+            Slot::ObjectReference(obj) => Ok(Some(obj)),
+            Slot::UndefinedReference => Ok(None),
+            slot => Err(InstructionError::InvalidState {
+                context: format!("{} - expected object reference but got {:?}", context, slot),
+            }),
+        }
+    }
+
+    /// Get the referenced array, or None for the null reference.
+    ///
+    /// Any value that is not an array reference is an InvalidState error.
+    pub fn nullable_array(self, context: &str) -> Result<Option<ArrayRef>, InstructionError> {
+        match self {
+            Slot::ArrayReference(array) => Ok(Some(array)),
+            Slot::UndefinedReference => Ok(None),
+            slot => Err(InstructionError::InvalidState {
+                context: format!("{} - expected array reference but got {:?}", context, slot),
+            }),
+        }
+    }
+
+    /// Get the referenced object, the null reference being a NullPointer error.
+    pub fn non_null_object(self, context: &str) -> Result<ObjectRef, InstructionError> {
+        self.nullable_object(context)?
+            .ok_or_else(|| InstructionError::NullPointer {
+                context: context.to_string(),
+            })
+    }
+
+    /// Get the referenced array, the null reference being a NullPointer error.
+    pub fn non_null_array(self, context: &str) -> Result<ArrayRef, InstructionError> {
+        self.nullable_array(context)?
+            .ok_or_else(|| InstructionError::NullPointer {
+                context: context.to_string(),
+            })
+    }
 }
 
 impl From<ConstantValue> for Slot {
@@ -73,6 +162,67 @@ impl From<ConstantValue> for Slot {
             ConstantValue::Long(value) => Slot::Long(value),
             ConstantValue::Float(value) => Slot::Float(value),
             ConstantValue::Double(value) => Slot::Double(value),
+            // The String constants are interned when the class is initialized.
+            ConstantValue::String(_) => Slot::UndefinedReference,
         }
     }
 }
+
+/// A primitive value, held by a slot of the matching variant.
+pub trait PrimitiveValue: Into<Slot> + Sized {
+    /// The Java name of the type, for the error messages
+    const TYPE_NAME: &'static str;
+
+    /// Get the value of a slot, if it is of this type.
+    fn from_slot(slot: &Slot) -> Option<Self>;
+}
+
+macro_rules! primitive_value {
+    ($real_ty:ty, $ty:ident, $type_name:expr) => {
+        impl From<$real_ty> for Slot {
+            fn from(value: $real_ty) -> Self {
+                Slot::$ty(value)
+            }
+        }
+
+        impl PrimitiveValue for $real_ty {
+            const TYPE_NAME: &'static str = $type_name;
+
+            fn from_slot(slot: &Slot) -> Option<Self> {
+                match slot {
+                    Slot::$ty(value) => Some(*value),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+primitive_value!(i32, Int, "int");
+primitive_value!(i64, Long, "long");
+primitive_value!(f32, Float, "float");
+primitive_value!(f64, Double, "double");
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn null_semantics() {
+        assert!(Slot::UndefinedReference.is_null());
+        assert!(!Slot::Int(0).is_null());
+        assert!(Slot::UndefinedReference.as_object().is_none());
+        assert!(matches!(
+            Slot::UndefinedReference.nullable_array("test"),
+            Ok(None)
+        ));
+        assert!(matches!(
+            Slot::UndefinedReference.non_null_object("test"),
+            Err(InstructionError::NullPointer { .. })
+        ));
+        assert!(matches!(
+            Slot::Int(0).nullable_object("test"),
+            Err(InstructionError::InvalidState { .. })
+        ));
+    }
+}
@@ -0,0 +1,27 @@
+//! Fixtures shared by the unit tests.
+
+use crate::{
+    class_loader::{ClassLoader, ClassPathDirEntry},
+    class_manager::ClassManager,
+    Vm,
+};
+
+/// Class loader of the classes of `tests/classes`, with a minimal java/lang.
+pub(crate) fn test_class_loader() -> ClassLoader {
+    let mut class_loader = ClassLoader::new();
+    class_loader.add_class_path_entry(Box::new(ClassPathDirEntry::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/classes"
+    ))));
+    class_loader
+}
+
+/// Class manager loading the classes of `tests/classes`.
+pub(crate) fn test_class_manager() -> ClassManager {
+    ClassManager::new(test_class_loader())
+}
+
+/// VM loading the classes of `tests/classes`.
+pub(crate) fn test_vm() -> Vm {
+    Vm::new(test_class_loader())
+}
@@ -1,34 +1,251 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use reader::descriptor::{FieldDescriptor, FieldType, MethodDescriptor};
 use snafu::Snafu;
 
 use crate::{
+    alloc::{new_string, read_string, Array, CharArray, Object, ObjectRef, RootProvider, RootSet},
+    breakpoint::{Breakpoint, BreakpointAction},
+    call_log,
     class::ClassId,
-    class_manager::{self, LoadedClass},
-    opcode::InstructionSuccess,
+    class_loader::ClassLoadingError,
+    class_manager::{self, ClassManager, LoadedClass},
+    debug,
+    fp::FpStrictness,
+    opcode::{InstructionError, InstructionSuccess, Opcode},
+    operand_stack::OperandStack,
 };
-use std::io::Cursor;
 
-pub use crate::slot::Slot;
+pub use crate::slot::{PrimitiveValue, Slot};
+
+/// Lowest priority of a thread, `Thread.MIN_PRIORITY`.
+pub const MIN_PRIORITY: i32 = 1;
+/// Default priority of a thread, `Thread.NORM_PRIORITY`.
+pub const NORM_PRIORITY: i32 = 5;
+/// Highest priority of a thread, `Thread.MAX_PRIORITY`.
+pub const MAX_PRIORITY: i32 = 10;
 
 #[derive(Debug, Clone)]
 pub struct Thread {
-    pub pc: usize,
     pub stack: Vec<Frame>,
+    /// Name of the thread
+    pub name: String,
+    /// The java/lang/Thread object representing this thread, created on first use
+    pub mirror: Option<ObjectRef>,
+    /// The value returned by the first method of the thread, once completed
+    pub result: Option<Slot>,
+    /// Daemon threads do not keep the VM running, see [crate::Vm::execute_until_shutdown]
+    pub daemon: bool,
+    /// Priority of the thread, between [MIN_PRIORITY] and [MAX_PRIORITY]
+    pub priority: i32,
 }
 
 impl Thread {
     pub fn new() -> Self {
+        Self::with_name("main")
+    }
+
+    pub fn with_name(name: impl Into<String>) -> Self {
         Self {
-            pc: 0,
             stack: vec![],
+            name: name.into(),
+            mirror: None,
+            result: None,
+            daemon: false,
+            priority: NORM_PRIORITY,
         }
     }
 
+    /// Create a thread executing the `run` method of a java/lang/Thread object,
+    /// with its name, priority and daemon status.
+    pub fn for_mirror(cm: &mut ClassManager, mirror: ObjectRef) -> Result<Self, InstructionError> {
+        let class_id = mirror.class_id();
+        let descriptor =
+            cm.intern_method_descriptor("()V")
+                .map_err(|err| InstructionError::InvalidState {
+                    context: format!("Invalid run descriptor: {}", err),
+                })?;
+        let run = cm
+            .resolve_method(class_id, class_id, "run", &descriptor, false)
+            .map_err(|err| InstructionError::ClassLoadingError {
+                class_name: "java/lang/Thread".into(),
+                source: Box::new(err),
+            })?;
+        let code = run.and_then(|(declaring, index)| {
+            let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(declaring) else {
+                return None;
+            };
+            let code = class.get_method_by_index(index)?.get_code()?;
+            Some((declaring, index, code.max_locals, code.max_stack))
+        });
+        let Some((declaring, index, max_locals, max_stack)) = code else {
+            return Err(InstructionError::AbstractMethod {
+                context: "The thread has no run method to execute".into(),
+            });
+        };
+
+        let mut thread = Self::with_name("");
+        thread.name = match mirror.get_field_by_name(cm, "name") {
+            Some(Slot::ArrayReference(array)) => match array.as_ref() {
+                Array::Char(chars) => String::from_utf16_lossy(
+                    &chars
+                        .data
+                        .read()
+                        .expect("rwlock has been poisoned, cannot read thread name"),
+                ),
+                _ => String::new(),
+            },
+            Some(Slot::ObjectReference(name)) => read_string(&name).unwrap_or_default(),
+            _ => String::new(),
+        };
+        if let Some(Slot::Int(daemon)) = mirror.get_field_by_name(cm, "daemon") {
+            thread.daemon = daemon != 0;
+        }
+        if let Some(Slot::Int(priority)) = mirror.get_field_by_name(cm, "priority") {
+            thread.priority = priority.clamp(MIN_PRIORITY, MAX_PRIORITY);
+        }
+        let mut frame = Frame::new(declaring, index, max_locals as usize, max_stack as usize);
+        frame.set_arguments(vec![Slot::ObjectReference(mirror.clone())])?;
+        thread.push_frame(frame);
+        thread.mirror = Some(mirror);
+        Ok(thread)
+    }
+
+    /// Get the java/lang/Thread object representing this thread, creating it if needed.
+    pub fn mirror(&mut self, cm: &mut ClassManager) -> Result<ObjectRef, ClassLoadingError> {
+        if let Some(mirror) = &self.mirror {
+            return Ok(mirror.clone());
+        }
+        let class_id = cm.get_or_resolve_class("java/lang/Thread")?.id();
+        let mirror = Object::new_with_classmanager(cm, class_id)?;
+        let mirror = cm.alloc_object(mirror);
+        self.mirror = Some(mirror.clone());
+        self.update_mirror_name(cm)?;
+        mirror.set_field_by_name(cm, "priority", Slot::Int(self.priority));
+        mirror.set_field_by_name(cm, "daemon", Slot::Int(self.daemon as i32));
+        Ok(mirror)
+    }
+
+    /// Rename the thread, and its java/lang/Thread object if it exists.
+    pub fn set_name(
+        &mut self,
+        cm: &mut ClassManager,
+        name: impl Into<String>,
+    ) -> Result<(), ClassLoadingError> {
+        self.name = name.into();
+        self.update_mirror_name(cm)
+    }
+
+    fn update_mirror_name(&self, cm: &mut ClassManager) -> Result<(), ClassLoadingError> {
+        let Some(mirror) = &self.mirror else {
+            return Ok(());
+        };
+        // The name is either a char[] (up to Java 8) or a String.
+        let resolved = ["Ljava/lang/String;", "[C"].iter().find_map(|descriptor| {
+            let (_, descriptor) = FieldDescriptor::parse(descriptor).ok()?;
+            cm.resolve_field(mirror.class_id(), "name", &descriptor)
+        });
+        let Some((declaring, index)) = resolved else {
+            return Ok(());
+        };
+        let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(declaring) else {
+            return Ok(());
+        };
+        let slot = class.field_offset + index;
+        let value = match class.fields[index].descriptor.field_type() {
+            FieldType::ArrayType(_) => Slot::ArrayReference(
+                cm.alloc_array(Array::Char(CharArray::from_string(&self.name))),
+            ),
+            _ => Slot::ObjectReference(new_string(cm, &self.name)?),
+        };
+        mirror.set_field(slot, value);
+        Ok(())
+    }
+
     pub fn execute(
         &mut self,
         class_manager: &mut class_manager::ClassManager,
     ) -> Result<(), ExecutionError> {
-        while let Some(frame) = self.current_frame_mut() {
-            let LoadedClass::Loaded(class) = class_manager.get_class_by_id(frame.class).unwrap()
+        self.execute_until(class_manager, &AtomicBool::new(false))
+            .map(|_| ())
+    }
+
+    /// Execute the thread until its completion, or until `suspend` is set.
+    ///
+    /// The flag is checked before each instruction, a suspended thread is resumed
+    /// by calling this method again.
+    /// Returns false if the thread has been suspended before its completion.
+    pub fn execute_until(
+        &mut self,
+        class_manager: &mut class_manager::ClassManager,
+        suspend: &AtomicBool,
+    ) -> Result<bool, ExecutionError> {
+        self.run(class_manager, suspend, 0)
+    }
+
+    /// Execute exactly one instruction, stepping into the invoked methods.
+    ///
+    /// Returns true if the thread has completed.
+    pub fn step(
+        &mut self,
+        class_manager: &mut class_manager::ClassManager,
+    ) -> Result<bool, ExecutionError> {
+        self.step_with(class_manager, Step::Instruction)
+    }
+
+    /// Execute the current instruction, and the whole method it invokes if any.
+    ///
+    /// Returns true if the thread has completed.
+    pub fn step_over(
+        &mut self,
+        class_manager: &mut class_manager::ClassManager,
+    ) -> Result<bool, ExecutionError> {
+        self.step_with(class_manager, Step::Over)
+    }
+
+    /// Execute the rest of the current method, until the return to its caller.
+    ///
+    /// Returns true if the thread has completed.
+    pub fn step_out(
+        &mut self,
+        class_manager: &mut class_manager::ClassManager,
+    ) -> Result<bool, ExecutionError> {
+        self.step_with(class_manager, Step::Out)
+    }
+
+    /// Execute the thread by one step, see [Step].
+    ///
+    /// Returns true if the thread has completed.
+    pub fn step_with(
+        &mut self,
+        class_manager: &mut class_manager::ClassManager,
+        step: Step,
+    ) -> Result<bool, ExecutionError> {
+        let depth = self.stack.len();
+        let stop_depth = match step {
+            Step::Instruction => usize::MAX,
+            Step::Over => depth,
+            Step::Out => depth.saturating_sub(1),
+        };
+        self.run(class_manager, &AtomicBool::new(false), stop_depth)
+    }
+
+    /// Execute the thread until its completion, until `suspend` is set, or until
+    /// an instruction is about to be executed with at most `stop_depth` frames on
+    /// the stack, the first instruction excepted (0 to never stop).
+    fn run(
+        &mut self,
+        class_manager: &mut class_manager::ClassManager,
+        suspend: &AtomicBool,
+        stop_depth: usize,
+    ) -> Result<bool, ExecutionError> {
+        let mut started = false;
+        while let Some(frame) = self.stack.last_mut() {
+            let Some(LoadedClass::Loaded(class)) = class_manager.get_class_by_id(frame.class)
             else {
                 return Err(ExecutionError::ClassNotLoaded);
             };
@@ -37,52 +254,221 @@ impl Thread {
             };
 
             log::debug!("Executing method: {}#{}", class.name, method.name);
-            log::debug!("Current local vars: {:?}", frame.local_variables);
+            log::debug!(
+                "Current frame: {}",
+                debug::display_frame(class_manager, frame, frame.pc)
+            );
 
-            // TODO: Native methods
-            let code = method
-                .get_code()
-                .expect("Code attribute not found, probably a native method");
+            let Some(code) = method.get_code() else {
+                return Err(ExecutionError::NoCode {
+                    method: format!(
+                        "{}.{}{}",
+                        class.name,
+                        method.name,
+                        method.descriptor.as_str()
+                    ),
+                });
+            };
 
-            let mut inst_reader = Cursor::new(code.instructions.clone());
+            let class_id = frame.class;
+            let method_index = frame.method;
+            let mut has_breakpoints = class_manager.breakpoints.in_method(class_id, method_index);
+            let cache = code.cache.clone();
+            let program = cache.program();
+            let mut pc = frame.pc;
+            let mut index = program.index_of(pc);
             loop {
-                inst_reader.set_position(self.pc as u64);
-                let inst = match crate::opcode::read_instruction(&mut inst_reader) {
-                    Ok((_, inst)) => inst,
-                    Err(e) => {
-                        return Err(ExecutionError::InstructionParseError { source: e });
-                    }
+                if suspend.load(Ordering::Relaxed) {
+                    return Ok(false);
+                }
+                if started && self.stack.len() <= stop_depth {
+                    return Ok(false);
+                }
+                started = true;
+                let decoded;
+                let inst = match index.and_then(|index| program.get(index)) {
+                    Some(inst) => inst,
+                    // Not part of the decoded program, e.g. after an invalid instruction
+                    None => match cache.decode_at(pc) {
+                        Ok(inst) => {
+                            decoded = inst;
+                            &decoded
+                        }
+                        Err(e) => {
+                            return Err(ExecutionError::InstructionParseError { source: e });
+                        }
+                    },
                 };
+                if let Some(histogram) = class_manager.opcode_histogram.as_mut() {
+                    if let Some(opcode) = cache.opcode_at(pc) {
+                        histogram.record(opcode);
+                    }
+                }
+                if has_breakpoints || matches!(inst, Opcode::Breakpoint) {
+                    let breakpoint = Breakpoint {
+                        class: class_id,
+                        method: method_index,
+                        pc,
+                    };
+                    if matches!(inst, Opcode::Breakpoint)
+                        || class_manager.breakpoints.contains(&breakpoint)
+                    {
+                        self.break_at(class_manager, breakpoint);
+                        has_breakpoints =
+                            class_manager.breakpoints.in_method(class_id, method_index);
+                    }
+                }
+                if class_manager.collector.is_requested() {
+                    self.stress_collect(class_manager);
+                }
                 log::trace!(
                     "Executing instruction: {:?} with current stack: {:?}",
                     inst,
                     self.current_frame()
                 );
-                match crate::opcode::Opcode::execute(&inst, self, class_manager) {
-                    Ok(InstructionSuccess::Next(n)) => {
-                        self.pc += n;
+                // A panic of the interpreter fails this thread instead of the
+                // process, see ExecutionError::InstructionPanic.
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    crate::opcode::Opcode::execute(inst, self, class_manager)
+                }))
+                .map_err(|payload| ExecutionError::InstructionPanic {
+                    instruction: format!("{:?}", inst),
+                    location: method_location(class_manager, class_id, method_index, pc),
+                    message: panic_message(payload.as_ref()),
+                })?
+                .and_then(|success| match self.current_frame() {
+                    Some(frame) => frame.check_operand_stack().map(|_| success),
+                    None => Ok(success),
+                });
+                if result.is_ok() {
+                    if let (Some(index), Some(quickened)) =
+                        (index, inst.quicken(class_manager, class_id))
+                    {
+                        program.rewrite(index, quickened);
                     }
-                    Ok(InstructionSuccess::JumpRelative(offset)) => {
-                        self.pc = ((self.pc as isize) + offset) as usize;
+                }
+                match result {
+                    Ok(InstructionSuccess::Next(n)) => {
+                        // The next instruction directly follows in the program
+                        let next = index.map(|index| index + 1);
+                        match next.and_then(|next| program.pc_of(next)) {
+                            Some(next_pc) => {
+                                pc = next_pc;
+                                index = next;
+                            }
+                            None => {
+                                pc += n;
+                                index = program.index_of(pc);
+                            }
+                        }
+                        self.set_pc(pc);
                     }
-                    Ok(InstructionSuccess::JumpAbsolute(offset)) => {
-                        self.pc = offset;
+                    Ok(InstructionSuccess::JumpAbsolute(target)) => {
+                        pc = target;
+                        index = program.index_of(pc);
+                        self.set_pc(pc);
                     }
-                    Ok(InstructionSuccess::FrameChange(pc)) => {
-                        self.pc = pc;
+                    Ok(InstructionSuccess::FrameChange) => {
+                        if class_manager.call_log.is_some() {
+                            self.log_return(class_manager, inst, class_id, method_index);
+                        }
                         break;
                     }
                     Ok(InstructionSuccess::Completed) => {
+                        if class_manager.call_log.is_some() {
+                            self.log_return(class_manager, inst, class_id, method_index);
+                        }
                         break;
                     }
                     Err(e) => {
+                        if let Some(status) = e.exit_status() {
+                            self.reset();
+                            return Err(ExecutionError::Exit { status });
+                        }
                         return Err(ExecutionError::InstructionExecutionError { source: e });
                     }
                 }
             }
         }
+        Ok(true)
+    }
 
-        Ok(())
+    /// Run the collection requested by the stress mode of the collector, then
+    /// check the roots of the thread and of the class manager.
+    fn stress_collect(&self, class_manager: &mut ClassManager) {
+        class_manager.collector.collect();
+        let mut roots = RootSet::new();
+        roots.add_provider(self);
+        roots.add_provider(&*class_manager);
+        class_manager.collector.check_roots(&roots);
+    }
+
+    /// Log the exit of a method, if the instruction executed was a return.
+    ///
+    /// The returned value is on the operand stack of the caller, or is the
+    /// result of the thread if there is none.
+    fn log_return(
+        &self,
+        class_manager: &ClassManager,
+        inst: &Opcode,
+        class_id: ClassId,
+        method_index: usize,
+    ) {
+        let value = match inst {
+            Opcode::Return => None,
+            Opcode::IReturn
+            | Opcode::LReturn
+            | Opcode::FReturn
+            | Opcode::DReturn
+            | Opcode::AReturn => match self.current_frame() {
+                Some(caller) => caller.operand_stack.last(),
+                None => self.result.as_ref(),
+            },
+            _ => return,
+        };
+        call_log::log_exit(
+            class_manager,
+            class_id,
+            method_index,
+            value,
+            self.stack.len(),
+        );
+    }
+
+    /// Pause the thread on a breakpoint, until its hook returns.
+    fn break_at(&self, class_manager: &mut ClassManager, breakpoint: Breakpoint) {
+        let action = match class_manager.breakpoints.take_hook() {
+            Some(mut hook) => {
+                let action = hook(&breakpoint, self, class_manager);
+                class_manager.breakpoints.set_hook(hook);
+                action
+            }
+            None => {
+                log::info!(
+                    "Thread \"{}\" reached a breakpoint: ClassId({}), method index {}, pc {}",
+                    self.name,
+                    breakpoint.class.0,
+                    breakpoint.method,
+                    breakpoint.pc
+                );
+                BreakpointAction::Continue
+            }
+        };
+        if action == BreakpointAction::Remove {
+            class_manager.breakpoints.remove(&breakpoint);
+        }
+    }
+
+    /// The pc of the next instruction to execute in the current frame, 0 if
+    /// the thread has no frame.
+    pub fn pc(&self) -> usize {
+        self.current_frame().map_or(0, |frame| frame.pc)
+    }
+
+    fn set_pc(&mut self, pc: usize) {
+        if let Some(frame) = self.current_frame_mut() {
+            frame.pc = pc;
+        }
     }
 
     pub(crate) fn push_frame(&mut self, frame: Frame) {
@@ -93,6 +479,82 @@ impl Thread {
         self.stack.pop()
     }
 
+    /// Pop the current frame on a normal method completion.
+    ///
+    /// The monitors held by the frame are released. If some monitors entered
+    /// with `monitorenter` were still held, the locking was not structured and
+    /// an IllegalMonitorState error is reported.
+    pub(crate) fn return_frame(&mut self) -> Result<Frame, InstructionError> {
+        let Some(mut frame) = self.pop_frame() else {
+            return Err(InstructionError::InvalidState {
+                context: "No frame to return from".into(),
+            });
+        };
+        let unbalanced = frame.release_monitors();
+        if unbalanced > 0 {
+            return Err(InstructionError::IllegalMonitorState {
+                context: format!(
+                    "{} monitor(s) still held on return from ClassId({}), method index {}",
+                    unbalanced, frame.class.0, frame.method
+                ),
+            });
+        }
+        Ok(frame)
+    }
+
+    /// Resume the caller of a frame popped by [Thread::return_frame], the
+    /// returned value being pushed on its operand stack.
+    ///
+    /// Without caller, the thread completes with this value as result.
+    pub(crate) fn resume_caller(
+        &mut self,
+        returned: &Frame,
+        value: Option<Slot>,
+    ) -> Result<InstructionSuccess, InstructionError> {
+        let Some(caller) = self.current_frame_mut() else {
+            self.result = value;
+            return Ok(InstructionSuccess::Completed);
+        };
+        let Some(return_info) = returned.return_info else {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "ClassId({}), method index {} returned without return information",
+                    returned.class.0, returned.method
+                ),
+            });
+        };
+        caller.pc = return_info.pc;
+        caller.operand_stack.extend(value);
+        Ok(InstructionSuccess::FrameChange)
+    }
+
+    /// Pop the current frame on an abrupt method completion.
+    ///
+    /// Every monitor held by the frame is released, as the frame will never
+    /// get a chance to exit them.
+    pub(crate) fn unwind_frame(&mut self) -> Option<Frame> {
+        let mut frame = self.pop_frame()?;
+        let unbalanced = frame.release_monitors();
+        if unbalanced > 0 {
+            log::debug!(
+                "Released {} monitor(s) while unwinding ClassId({}), method index {}",
+                unbalanced,
+                frame.class.0,
+                frame.method
+            );
+        }
+        Some(frame)
+    }
+
+    /// Get the frame of the instruction being executed, a malformed call
+    /// without frame being an InvalidState error.
+    pub(crate) fn executing_frame(&mut self) -> Result<&mut Frame, InstructionError> {
+        self.current_frame_mut()
+            .ok_or_else(|| InstructionError::InvalidState {
+                context: "No frame to execute the instruction".into(),
+            })
+    }
+
     pub(crate) fn current_frame(&self) -> Option<&Frame> {
         self.stack.last()
     }
@@ -102,27 +564,333 @@ impl Thread {
     }
 
     pub fn reset(&mut self) {
-        self.pc = 0;
-        self.stack.clear();
+        self.result = None;
+        while self.unwind_frame().is_some() {}
     }
 }
 
+/// The frames of the thread, its java/lang/Thread object and its result.
+impl RootProvider for Thread {
+    fn add_roots(&self, roots: &mut RootSet) {
+        for frame in &self.stack {
+            roots.add_provider(frame);
+        }
+        if let Some(mirror) = &self.mirror {
+            roots.add_object(mirror);
+        }
+        if let Some(value) = &self.result {
+            roots.add_slot(value);
+        }
+    }
+}
+
+/// How far a thread is executed by [Thread::step_with].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Exactly one instruction.
+    Instruction,
+    /// One instruction of the current method, the invoked methods being executed
+    /// until their completion.
+    Over,
+    /// Until the current method returns (or completes abruptly).
+    Out,
+}
+
+/// Where the execution of the caller resumes once an invoked method returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReturnInfo {
+    /// The pc of the instruction following the invocation in the caller.
+    pub pc: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct Frame {
+    /// The pc of the next instruction to execute in the method.
+    ///
+    /// While a method invoked by this frame runs, it stays on the invoke instruction.
+    pub pc: usize,
+    /// Where the caller resumes on return, None for the first frame of a thread.
+    pub return_info: Option<ReturnInfo>,
     pub local_variables: Vec<Slot>,
-    pub operand_stack: Vec<Slot>,
+    pub operand_stack: OperandStack,
+    /// Maximum depth of the operand stack, from the Code attribute
+    pub max_stack: usize,
     pub class: ClassId,
     pub method: usize,
+    /// Monitor acquired on invokation of a synchronized method
+    pub synchronized_on: Option<ObjectRef>,
+    /// Monitors entered by `monitorenter`, in acquisition order
+    pub monitors: Vec<ObjectRef>,
+    /// Floating-point semantics of the method
+    pub fp_strictness: FpStrictness,
 }
 
 impl Frame {
-    pub fn new(class: ClassId, method: usize, varlen: usize) -> Self {
+    pub fn new(class: ClassId, method: usize, varlen: usize, max_stack: usize) -> Self {
         Self {
+            pc: 0,
+            return_info: None,
             local_variables: vec![Slot::Tombstone; varlen],
-            operand_stack: vec![],
+            operand_stack: OperandStack::with_capacity(max_stack),
+            max_stack,
             class,
             method,
+            synchronized_on: None,
+            monitors: vec![],
+            fp_strictness: FpStrictness::Strict,
+        }
+    }
+
+    /// Push a value on the operand stack, failing if it does not fit in
+    /// `max_stack`, a long or a double taking two units.
+    pub fn push_value(&mut self, value: impl Into<Slot>) -> Result<(), InstructionError> {
+        let value = value.into();
+        let depth = self.operand_stack.depth() + value.size();
+        if depth > self.max_stack {
+            return Err(InstructionError::OperandStackOverflow {
+                depth,
+                max_stack: self.max_stack,
+            });
+        }
+        self.operand_stack.push(value);
+        Ok(())
+    }
+
+    /// Pop the top value of the operand stack.
+    pub fn pop(&mut self) -> Result<Slot, InstructionError> {
+        self.operand_stack
+            .pop()
+            .ok_or_else(|| InstructionError::InvalidState {
+                context: "Operand stack is empty".into(),
+            })
+    }
+
+    /// Pop the two top values of the operand stack, the top one first.
+    pub fn pop2(&mut self) -> Result<(Slot, Slot), InstructionError> {
+        if self.operand_stack.len() < 2 {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "Operand stack is len {}, expected at least two elements",
+                    self.operand_stack.len()
+                ),
+            });
         }
+        let first = self.pop()?;
+        let second = self.pop()?;
+        Ok((first, second))
+    }
+
+    /// Duplicate the `units` top units of the operand stack and insert them
+    /// `under` units down, see [OperandStack::dup_x].
+    ///
+    /// Fails without modifying the stack if the copy does not fit in `max_stack`.
+    pub fn dup_x(
+        &mut self,
+        units: usize,
+        under: usize,
+        instruction: &str,
+    ) -> Result<(), InstructionError> {
+        let depth = self.operand_stack.depth() + units;
+        if depth > self.max_stack {
+            return Err(InstructionError::OperandStackOverflow {
+                depth,
+                max_stack: self.max_stack,
+            });
+        }
+        self.operand_stack.dup_x(units, under, instruction)
+    }
+
+    /// Pop a primitive value of the given type from the operand stack.
+    pub fn pop_value<T: PrimitiveValue>(&mut self) -> Result<T, InstructionError> {
+        let slot = self.pop()?;
+        T::from_slot(&slot).ok_or_else(|| InstructionError::InvalidState {
+            context: format!("Expected {} but got {:?}", T::TYPE_NAME, slot),
+        })
+    }
+
+    pub fn pop_int(&mut self) -> Result<i32, InstructionError> {
+        self.pop_value()
+    }
+
+    pub fn pop_long(&mut self) -> Result<i64, InstructionError> {
+        self.pop_value()
+    }
+
+    pub fn pop_float(&mut self) -> Result<f32, InstructionError> {
+        self.pop_value()
+    }
+
+    pub fn pop_double(&mut self) -> Result<f64, InstructionError> {
+        self.pop_value()
+    }
+
+    /// Pop a reference, possibly null, from the operand stack.
+    pub fn pop_ref(&mut self) -> Result<Slot, InstructionError> {
+        let slot = self.pop()?;
+        if !slot.is_reference() {
+            return Err(InstructionError::InvalidState {
+                context: format!("Expected reference but got {:?}", slot),
+            });
+        }
+        Ok(slot)
+    }
+
+    /// Get a local variable.
+    pub fn local(&self, index: usize) -> Result<&Slot, InstructionError> {
+        self.local_variables
+            .get(index)
+            .ok_or_else(|| InstructionError::InvalidState {
+                context: format!("Local variable {} not found", index),
+            })
+    }
+
+    /// Get a primitive value of the given type from the local variables.
+    pub fn load_value<T: PrimitiveValue>(&self, index: usize) -> Result<T, InstructionError> {
+        let slot = self.local(index)?;
+        T::from_slot(slot).ok_or_else(|| InstructionError::InvalidState {
+            context: format!("Expected {} but got {:?}", T::TYPE_NAME, slot),
+        })
+    }
+
+    /// Get a reference, possibly null, from the local variables.
+    pub fn load_ref(&self, index: usize) -> Result<Slot, InstructionError> {
+        let slot = self.local(index)?;
+        if !slot.is_reference() {
+            return Err(InstructionError::InvalidState {
+                context: format!("Expected reference but got {:?}", slot),
+            });
+        }
+        Ok(slot.clone())
+    }
+
+    /// Store a value into the local variables, a long or a double taking two of them.
+    pub fn store_value(
+        &mut self,
+        index: usize,
+        value: impl Into<Slot>,
+    ) -> Result<(), InstructionError> {
+        let value = value.into();
+        let size = value.size().max(1);
+        if self.local_variables.len() < index + size {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "Index out of bound, the local variable array is len: {}, index given is: {}.",
+                    self.local_variables.len(),
+                    index
+                ),
+            });
+        }
+        self.local_variables[index] = value;
+        if size == 2 {
+            self.local_variables[index + 1] = Slot::Tombstone;
+        }
+        Ok(())
+    }
+
+    /// Check that the operand stack does not exceed its maximum depth.
+    pub fn check_operand_stack(&self) -> Result<(), InstructionError> {
+        if self.operand_stack.depth() > self.max_stack {
+            return Err(InstructionError::OperandStackOverflow {
+                depth: self.operand_stack.depth(),
+                max_stack: self.max_stack,
+            });
+        }
+        Ok(())
+    }
+
+    /// Store the arguments of the method in the local variables, a long or a
+    /// double taking two of them.
+    ///
+    /// Fails if the arguments take more local variables than the frame has.
+    pub fn set_arguments(&mut self, args: Vec<Slot>) -> Result<(), InstructionError> {
+        let size: usize = args.iter().map(Slot::size).sum();
+        if size > self.local_variables.len() {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "{} argument(s) taking {} local variable(s) given, with {} local variables",
+                    args.len(),
+                    size,
+                    self.local_variables.len()
+                ),
+            });
+        }
+        let mut pos = 0;
+        for arg in args {
+            let size = arg.size();
+            self.local_variables[pos] = arg;
+            if size > 1 {
+                self.local_variables[pos + 1] = Slot::Tombstone;
+            }
+            pos += size;
+        }
+        Ok(())
+    }
+
+    /// Store the arguments of a method in the local variables, laid out by its
+    /// descriptor: the receiver of an instance method first, then each parameter
+    /// taking the width of its declared type.
+    ///
+    /// Fails if the arguments do not match the parameters, in number or width.
+    pub fn set_method_arguments(
+        &mut self,
+        descriptor: &MethodDescriptor,
+        is_static: bool,
+        args: Vec<Slot>,
+    ) -> Result<(), InstructionError> {
+        let receiver = (!is_static).then_some(1);
+        let widths: Vec<usize> = receiver
+            .into_iter()
+            .chain(descriptor.parameters.iter().map(FieldType::width))
+            .collect();
+        if args.len() != widths.len() || widths.iter().sum::<usize>() > self.local_variables.len() {
+            return Err(InstructionError::InvalidState {
+                context: format!(
+                    "{} argument(s) given for {}{}, with {} local variables",
+                    args.len(),
+                    descriptor.java_syntax(),
+                    if is_static { "" } else { " and its receiver" },
+                    self.local_variables.len()
+                ),
+            });
+        }
+        let mut pos = 0;
+        for (arg, width) in args.into_iter().zip(widths) {
+            if arg.size() != width {
+                return Err(InstructionError::InvalidState {
+                    context: format!(
+                        "The argument at local variable {} of {} takes {} slot(s), got {:?}",
+                        pos,
+                        descriptor.java_syntax(),
+                        width,
+                        arg
+                    ),
+                });
+            }
+            self.local_variables[pos] = arg;
+            if width == 2 {
+                self.local_variables[pos + 1] = Slot::Tombstone;
+            }
+            pos += width;
+        }
+        Ok(())
+    }
+
+    /// Release every monitor held by this frame.
+    ///
+    /// Returns the number of monitors entered by `monitorenter` that were not
+    /// exited, or that could not be exited properly.
+    pub fn release_monitors(&mut self) -> usize {
+        let mut unbalanced = 0;
+        while let Some(monitor) = self.monitors.pop() {
+            monitor.monitor_exit();
+            unbalanced += 1;
+        }
+        if let Some(monitor) = self.synchronized_on.take() {
+            if !monitor.monitor_exit() {
+                unbalanced += 1;
+            }
+        }
+        unbalanced
     }
 
     pub fn get_local_variable(&self, index: usize) -> Option<&Slot> {
@@ -138,6 +906,18 @@ impl Frame {
     }
 }
 
+/// The local variables and the operand stack of the frame, and the monitors it holds.
+impl RootProvider for Frame {
+    fn add_roots(&self, roots: &mut RootSet) {
+        for value in self.local_variables.iter().chain(&self.operand_stack) {
+            roots.add_slot(value);
+        }
+        for monitor in self.synchronized_on.iter().chain(&self.monitors) {
+            roots.add_object(monitor);
+        }
+    }
+}
+
 /// Errors that can occur during execution of a thread
 #[derive(Debug, Snafu)]
 pub enum ExecutionError {
@@ -149,6 +929,32 @@ pub enum ExecutionError {
     #[snafu(display("Method not loaded"))]
     MethodNotLoaded,
 
+    /// The current method has no code, being abstract or native
+    #[snafu(display("No code to execute in {}", method))]
+    NoCode { method: String },
+
+    /// No live thread has this id, it has never existed or has been stopped
+    #[snafu(display("Thread not found: {}", thread_id))]
+    ThreadNotFound { thread_id: usize },
+
+    /// The interpreter panicked while executing an instruction
+    ///
+    /// The frames of the thread are left as they were when the panic occured.
+    /// The panic is still reported by the panic hook, and the locks held by the
+    /// instruction (e.g. the fields of an object) are poisoned: the objects and
+    /// classes it was updating may be left unusable for the other threads.
+    #[snafu(display(
+        "Internal error executing {} at {}: {}",
+        instruction,
+        location,
+        message
+    ))]
+    InstructionPanic {
+        instruction: String,
+        location: String,
+        message: String,
+    },
+
     /// Impossible to parse the current instruction
     #[snafu(display("Error parsing instruction, source: {}", source))]
     InstructionParseError {
@@ -160,4 +966,307 @@ pub enum ExecutionError {
     InstructionExecutionError {
         source: crate::opcode::InstructionError,
     },
+
+    /// The VM has been requested to exit, e.g. by `System.exit`
+    ///
+    /// The stack of the thread has been unwound.
+    #[snafu(display("Exit requested with status {}", status))]
+    Exit { status: i32 },
+}
+
+/// Describe an instruction of a method, e.g. `Main.run()V pc 4`.
+fn method_location(cm: &ClassManager, class_id: ClassId, method_index: usize, pc: usize) -> String {
+    let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(class_id) else {
+        return format!("ClassId({})#{} pc {}", class_id.0, method_index, pc);
+    };
+    match class.get_method_by_index(method_index) {
+        Some(method) => format!(
+            "{}.{}{} pc {}",
+            class.name,
+            method.name,
+            method.descriptor.as_str(),
+            pc
+        ),
+        None => format!("{}#{} pc {}", class.name, method_index, pc),
+    }
+}
+
+/// Get the message of a panic, given to `panic!` as a string.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".into()
+    }
+}
+
+impl ExecutionError {
+    /// Get the status of the requested exit, if the error is an exit request.
+    pub fn exit_status(&self) -> Option<i32> {
+        match self {
+            ExecutionError::Exit { status } => Some(*status),
+            ExecutionError::InstructionExecutionError { source } => source.exit_status(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reader::descriptor;
+
+    use super::*;
+
+    #[test]
+    fn operand_stack_bound() {
+        let mut frame = Frame::new(ClassId(0), 0, 0, 2);
+        assert!(frame.push_value(Slot::Int(1)).is_ok());
+        // A long takes two units of depth.
+        assert!(matches!(
+            frame.push_value(Slot::Long(2)),
+            Err(InstructionError::OperandStackOverflow {
+                depth: 3,
+                max_stack: 2
+            })
+        ));
+        assert!(frame.push_value(Slot::Int(3)).is_ok());
+        assert!(frame.check_operand_stack().is_ok());
+        frame.operand_stack.pop();
+        frame.operand_stack.push(Slot::Double(3.0));
+        assert!(frame.check_operand_stack().is_err());
+    }
+
+    #[test]
+    fn dup_bound() {
+        let mut frame = Frame::new(ClassId(0), 0, 0, 3);
+        frame.push_value(Slot::Long(1)).unwrap();
+        // dup2 of a long needs two more units.
+        assert!(matches!(
+            frame.dup_x(2, 0, "dup2"),
+            Err(InstructionError::OperandStackOverflow {
+                depth: 4,
+                max_stack: 3
+            })
+        ));
+        assert_eq!(frame.operand_stack.depth(), 2);
+        frame.pop().unwrap();
+        frame.push_value(Slot::Int(1)).unwrap();
+        frame.push_value(Slot::Int(2)).unwrap();
+        assert!(frame.dup_x(1, 1, "dup_x1").is_ok());
+        assert_eq!(frame.operand_stack.depth(), 3);
+    }
+
+    #[test]
+    fn method_arguments_layout() {
+        let descriptor = descriptor::parse_method_descriptor("(JI)V").unwrap();
+        let mut frame = Frame::new(ClassId(0), 0, 4, 0);
+        frame
+            .set_method_arguments(
+                &descriptor,
+                false,
+                vec![Slot::UndefinedReference, Slot::Long(1), Slot::Int(2)],
+            )
+            .unwrap();
+        assert!(matches!(
+            frame.local_variables[..],
+            [
+                Slot::UndefinedReference,
+                Slot::Long(1),
+                Slot::Tombstone,
+                Slot::Int(2)
+            ]
+        ));
+
+        // An int given for the long would shift the following arguments.
+        let mut frame = Frame::new(ClassId(0), 0, 3, 0);
+        assert!(frame
+            .set_method_arguments(&descriptor, true, vec![Slot::Int(1), Slot::Int(2)])
+            .is_err());
+        assert!(frame
+            .set_method_arguments(&descriptor, true, vec![Slot::Long(1)])
+            .is_err());
+        assert!(frame
+            .set_method_arguments(&descriptor, true, vec![Slot::Long(1), Slot::Int(2)])
+            .is_ok());
+    }
+
+    #[test]
+    fn typed_operands() {
+        let mut frame = Frame::new(ClassId(0), 0, 3, 4);
+        frame.push_value(1i32).unwrap();
+        frame.push_value(2.5f64).unwrap();
+        assert!(matches!(
+            frame.pop_int(),
+            Err(InstructionError::InvalidState { .. })
+        ));
+        frame.push_value(Slot::UndefinedReference).unwrap();
+        assert!(frame.pop_ref().unwrap().is_null());
+        assert_eq!(frame.pop_int().unwrap(), 1);
+        assert!(frame.pop().is_err());
+
+        frame.store_value(1, 7i64).unwrap();
+        assert!(matches!(frame.local_variables[2], Slot::Tombstone));
+        assert_eq!(frame.load_value::<i64>(1).unwrap(), 7);
+        assert!(frame.load_value::<i32>(1).is_err());
+        assert!(frame.store_value(2, 1.0f64).is_err());
+    }
+
+    #[test]
+    fn stepping() {
+        use crate::test_util::test_vm;
+
+        let mut vm = test_vm();
+        let Ok(LoadedClass::Loaded(class)) =
+            vm.class_manager_mut().get_or_resolve_class("Stepping")
+        else {
+            panic!("Stepping not loaded");
+        };
+        let class_id = class.id;
+        let method = class
+            .methods
+            .iter()
+            .position(|method| method.name == "sumOfSquares")
+            .unwrap();
+        let args = vec![Slot::Int(3), Slot::Int(4)];
+        let thread_id = vm.create_thread(class_id, method, args).unwrap();
+        let mut step = |step| {
+            let completed = vm.step_thread(thread_id, step).unwrap();
+            let thread = vm.thread_manager().get_thread(thread_id).unwrap();
+            (completed, thread.stack.len(), thread.pc())
+        };
+
+        assert_eq!(step(Step::Instruction), (false, 1, 1));
+        // Into square, then back to the caller after the invocation.
+        assert_eq!(step(Step::Instruction), (false, 2, 0));
+        assert_eq!(step(Step::Out), (false, 1, 4));
+        assert_eq!(step(Step::Over), (false, 1, 5));
+        assert_eq!(step(Step::Over), (false, 1, 8));
+        assert!(step(Step::Out).0);
+        let thread = vm.thread_manager().get_thread(thread_id).unwrap();
+        assert!(matches!(thread.result, Some(Slot::Int(25))));
+    }
+
+    #[test]
+    fn return_addresses() {
+        use crate::test_util::test_vm;
+
+        let mut vm = test_vm();
+        let Ok(LoadedClass::Loaded(class)) = vm
+            .class_manager_mut()
+            .get_or_resolve_class("ReturnAddresses")
+        else {
+            panic!("ReturnAddresses not loaded");
+        };
+        let class_id = class.id;
+        let method = class.methods.iter().position(|m| m.name == "run").unwrap();
+        let thread_id = vm.create_thread(class_id, method, vec![]).unwrap();
+
+        // The pcs of the invocations of run, and of the instructions following them.
+        let mut invocations = Vec::new();
+        let mut returns = Vec::new();
+        loop {
+            let thread = vm.thread_manager().get_thread(thread_id).unwrap();
+            let (depth, pc) = (thread.stack.len(), thread.pc());
+            if vm.step_thread(thread_id, Step::Instruction).unwrap() {
+                break;
+            }
+            let thread = vm.thread_manager().get_thread(thread_id).unwrap();
+            if thread.stack.len() > depth {
+                // The caller stays at the invocation while the callee runs.
+                let caller = &thread.stack[depth - 1];
+                assert_eq!(caller.pc, pc);
+                assert_eq!(thread.pc(), 0);
+                let return_info = thread.current_frame().unwrap().return_info.unwrap();
+                returns.push((depth, return_info.pc));
+                if depth == 1 {
+                    invocations.push((pc, return_info.pc));
+                }
+            } else if thread.stack.len() < depth {
+                let (caller_depth, return_pc) = returns.pop().unwrap();
+                assert_eq!(thread.stack.len(), caller_depth);
+                assert_eq!(thread.pc(), return_pc);
+            }
+        }
+        assert!(returns.is_empty());
+        // invokespecial, invokestatic and invokeinterface (5 bytes long)
+        assert_eq!(invocations, [(4, 7), (9, 12), (15, 20)]);
+        let thread = vm.thread_manager().get_thread(thread_id).unwrap();
+        assert!(matches!(thread.result, Some(Slot::Int(1011))));
+    }
+
+    #[test]
+    fn non_daemon_shutdown() {
+        use crate::test_util::test_vm;
+
+        let mut vm = test_vm();
+        let Ok(LoadedClass::Loaded(class)) = vm.class_manager_mut().get_or_resolve_class("Threads")
+        else {
+            panic!("Threads not loaded");
+        };
+        let class_id = class.id;
+        let method = class
+            .methods
+            .iter()
+            .position(|method| method.name == "start")
+            .unwrap();
+        let thread_id = vm.create_thread(class_id, method, vec![]).unwrap();
+        assert!(matches!(vm.execute_until_shutdown(thread_id), Ok(None)));
+
+        let Some(LoadedClass::Loaded(class)) = vm.class_manager().get_class_by_id(class_id) else {
+            panic!("Threads not loaded");
+        };
+        let rank = |name| match class.get_field(name).and_then(|field| field.get_value()) {
+            Some(Slot::Int(rank)) => *rank,
+            _ => panic!("{} is not an int", name),
+        };
+        // The started threads run after the main one, the highest priority first,
+        // and the daemon thread is abandoned.
+        assert_eq!(
+            (rank("main"), rank("high"), rank("low"), rank("daemon")),
+            (1, 2, 3, 0)
+        );
+        let threads = vm.thread_manager();
+        assert_eq!(threads.len(), 4);
+        let thread = |id| threads.get_thread(id).unwrap();
+        assert!(thread(2).daemon && !thread(2).stack.is_empty());
+        assert_eq!(
+            (thread(1).priority, thread(3).priority),
+            (MIN_PRIORITY, MAX_PRIORITY)
+        );
+    }
+
+    #[test]
+    fn runtime_and_shutdown_hooks() {
+        use crate::test_util::test_vm;
+
+        let mut vm = test_vm();
+        let Ok(LoadedClass::Loaded(class)) =
+            vm.class_manager_mut().get_or_resolve_class("RuntimeCalls")
+        else {
+            panic!("RuntimeCalls not loaded");
+        };
+        let class_id = class.id;
+        let method = class
+            .methods
+            .iter()
+            .position(|method| method.name == "start")
+            .unwrap();
+        let thread_id = vm.create_thread(class_id, method, vec![]).unwrap();
+        assert!(matches!(vm.execute_until_shutdown(thread_id), Ok(None)));
+        assert!(vm.class_manager().shutdown_hooks.is_empty());
+
+        let Some(LoadedClass::Loaded(class)) = vm.class_manager().get_class_by_id(class_id) else {
+            panic!("RuntimeCalls not loaded");
+        };
+        let int = |name| match class.get_field(name).and_then(|field| field.get_value()) {
+            Some(Slot::Int(value)) => *value,
+            _ => panic!("{} is not an int", name),
+        };
+        assert!(int("processors") >= 1);
+        assert_eq!((int("memory"), int("removed")), (1, 1));
+        // The hook registered twice runs once, the removed one never runs.
+        assert_eq!(int("hookRuns"), 1);
+    }
 }
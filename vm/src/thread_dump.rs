@@ -0,0 +1,114 @@
+use std::fmt;
+
+use crate::{
+    class_manager::{ClassManager, LoadedClass},
+    debug::format::local_variables,
+    thread::Thread,
+    thread_manager::ThreadId,
+};
+
+/// Snapshot of the stacks of the live threads of a VM, as printed by jstack.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadDump {
+    pub threads: Vec<ThreadStack>,
+}
+
+/// Stack of a thread, the current frame first.
+#[derive(Debug, Clone)]
+pub struct ThreadStack {
+    pub id: ThreadId,
+    pub name: String,
+    pub frames: Vec<FrameInfo>,
+}
+
+/// Location of the execution in a frame.
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    pub class_name: String,
+    pub method_name: String,
+    pub descriptor: String,
+    /// The pc of the next instruction to execute in this frame.
+    ///
+    /// For the caller frames, this is the pc of the pending invocation.
+    pub pc: usize,
+    /// The source line of the current instruction, if the method has a LineNumberTable.
+    pub line: Option<u16>,
+    /// The local variables holding a value, e.g. `1 count = int 3`.
+    ///
+    /// They are only printed by the alternate format (`{:#}`) of the stacks.
+    pub locals: Vec<String>,
+}
+
+impl ThreadStack {
+    /// Take a snapshot of the stack of a thread.
+    pub fn of(cm: &ClassManager, id: ThreadId, thread: &Thread) -> Self {
+        let mut frames = Vec::with_capacity(thread.stack.len());
+        for frame in thread.stack.iter().rev() {
+            let Some(LoadedClass::Loaded(class)) = cm.get_class_by_id(frame.class) else {
+                continue;
+            };
+            let Some(method) = class.get_method_by_index(frame.method) else {
+                continue;
+            };
+            frames.push(FrameInfo {
+                class_name: class.name.replace('/', "."),
+                method_name: method.name.clone(),
+                descriptor: method.descriptor.as_str().to_string(),
+                pc: frame.pc,
+                line: method
+                    .get_code()
+                    .and_then(|code| code.line_number(frame.pc)),
+                locals: local_variables(cm, frame, frame.pc),
+            });
+        }
+        Self {
+            id,
+            name: thread.name.clone(),
+            frames,
+        }
+    }
+}
+
+impl fmt::Display for FrameInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "at {}.{}{} (pc {}",
+            self.class_name, self.method_name, self.descriptor, self.pc
+        )?;
+        if let Some(line) = self.line {
+            write!(f, ", line {}", line)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for ThreadStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\"{}\" #{}", self.name, self.id)?;
+        for frame in self.frames.iter() {
+            writeln!(f, "\t{}", frame)?;
+            if f.alternate() {
+                for local in frame.locals.iter() {
+                    writeln!(f, "\t\t{}", local)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ThreadDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Full thread dump:")?;
+        for thread in self.threads.iter() {
+            writeln!(f)?;
+            if f.alternate() {
+                write!(f, "{:#}", thread)?;
+            } else {
+                write!(f, "{}", thread)?;
+            }
+        }
+        Ok(())
+    }
+}
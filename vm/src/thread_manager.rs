@@ -1,13 +1,17 @@
 use crate::{
     class::{Class, ClassId},
+    opcode::InstructionError,
     thread::{Frame, Slot, Thread},
 };
 
 pub type ThreadId = usize;
 
+/// The threads of a VM, by id.
+///
+/// The id of a thread is stable: it is not reused once the thread is stopped.
 #[derive(Debug, Clone)]
 pub struct ThreadManager {
-    pub threads: Vec<Thread>,
+    threads: Vec<Option<Thread>>,
 }
 
 impl ThreadManager {
@@ -17,39 +21,106 @@ impl ThreadManager {
 
     pub fn create_thread<'a>(
         &'a mut self,
-        class: &ClassId,
+        class: ClassId,
         method: usize,
         max_locals: usize,
+        max_stack: usize,
         args: Vec<Slot>,
-    ) -> ThreadId {
-        let mut thread = Thread::new();
-
-        thread.push_frame(Frame::new(class.clone(), method, max_locals));
-        let mut pos = 0;
-        for arg in args {
-            if arg.size() > 1 {
-                pos += 1;
-            }
-            *thread
-                .current_frame_mut()
-                .unwrap()
-                .get_local_variable_mut(pos)
-                .unwrap() = arg;
-            pos += 1;
+    ) -> Result<ThreadId, InstructionError> {
+        let mut thread = if self.threads.is_empty() {
+            Thread::new()
+        } else {
+            Thread::with_name(format!("Thread-{}", self.threads.len()))
+        };
+
+        let mut frame = Frame::new(class, method, max_locals, max_stack);
+        frame.set_arguments(args)?;
+        thread.push_frame(frame);
+        self.threads.push(Some(thread));
+        Ok(self.threads.len() - 1)
+    }
+
+    /// Add a thread started by the program, see [Thread::for_mirror].
+    ///
+    /// An unnamed thread is named after its id.
+    pub fn add_thread(&mut self, mut thread: Thread) -> ThreadId {
+        if thread.name.is_empty() {
+            thread.name = format!("Thread-{}", self.threads.len());
         }
-        self.threads.push(thread);
-        return self.threads.len() - 1;
+        self.threads.push(Some(thread));
+        self.threads.len() - 1
+    }
+
+    /// Get the next thread to execute until its completion: the live non-daemon
+    /// thread with the highest priority, the oldest one first.
+    ///
+    /// The failed threads are skipped, their stack being kept for the thread dumps.
+    pub fn next_non_daemon(&self, failed: &[ThreadId]) -> Option<ThreadId> {
+        self.iter()
+            .filter(|(id, thread)| {
+                !thread.daemon && !thread.stack.is_empty() && !failed.contains(id)
+            })
+            .min_by_key(|(id, thread)| (-thread.priority, *id))
+            .map(|(id, _)| id)
     }
 
     pub fn get_thread(&self, index: usize) -> Option<&Thread> {
-        self.threads.get(index)
+        self.threads.get(index)?.as_ref()
     }
 
     pub fn get_thread_mut(&mut self, index: usize) -> Option<&mut Thread> {
-        self.threads.get_mut(index)
+        self.threads.get_mut(index)?.as_mut()
     }
 
+    /// Enumerate the threads with their id, the stopped ones excluded.
+    pub fn iter(&self) -> impl Iterator<Item = (ThreadId, &Thread)> {
+        self.threads
+            .iter()
+            .enumerate()
+            .filter_map(|(id, thread)| Some((id, thread.as_ref()?)))
+    }
+
+    /// Enumerate mutably the threads with their id, the stopped ones excluded.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ThreadId, &mut Thread)> {
+        self.threads
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(id, thread)| Some((id, thread.as_mut()?)))
+    }
+
+    /// Get the number of threads, the stopped ones excluded.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Stop a thread and drop it, the ids of the other threads being kept.
     pub fn stop_thread(&mut self, index: usize) {
-        self.threads.remove(index);
+        if let Some(thread) = self.threads.get_mut(index) {
+            *thread = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stable_thread_ids() {
+        let mut threads = ThreadManager::new();
+        for name in ["a", "b", "c"] {
+            threads.add_thread(Thread::with_name(name));
+        }
+        threads.stop_thread(1);
+        assert!(threads.get_thread(1).is_none());
+        assert_eq!(threads.get_thread(2).unwrap().name, "c");
+        let ids: Vec<_> = threads.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, [0, 2]);
+        assert_eq!(threads.add_thread(Thread::with_name("d")), 3);
+        assert_eq!(threads.len(), 3);
     }
 }
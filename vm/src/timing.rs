@@ -0,0 +1,177 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Phases of the VM execution that are timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Reading and parsing classfiles from the classpath
+    ClassRead,
+    /// Resolution of the class dependencies
+    Resolution,
+    /// Construction of the runtime constant pool, fields and methods
+    Linking,
+    /// Execution of the class initializers (`<clinit>`)
+    ClassInit,
+    /// Interpretation of the bytecode
+    Interpretation,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 5] = [
+        Phase::ClassRead,
+        Phase::Resolution,
+        Phase::Linking,
+        Phase::ClassInit,
+        Phase::Interpretation,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Human readable name of the phase
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::ClassRead => "class read",
+            Phase::Resolution => "resolution",
+            Phase::Linking => "linking",
+            Phase::ClassInit => "<clinit>",
+            Phase::Interpretation => "interpretation",
+        }
+    }
+}
+
+/// Time spent in each phase of the VM execution.
+///
+/// Phases can be nested (e.g. a class is read while interpreting), the time
+/// is then only accounted to the innermost phase.
+#[derive(Debug, Default)]
+pub struct Timings {
+    durations: [Duration; 5],
+    counts: [u64; 5],
+    stack: Vec<Phase>,
+    last: Option<Instant>,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enter a new phase, suspending the current one.
+    pub fn enter(&mut self, phase: Phase) {
+        self.checkpoint();
+        self.counts[phase.index()] += 1;
+        self.stack.push(phase);
+    }
+
+    /// Exit the given phase, resuming the previous one.
+    ///
+    /// Phases entered after the given one and not exited yet are exited too.
+    pub fn exit(&mut self, phase: Phase) {
+        self.checkpoint();
+        while let Some(current) = self.stack.pop() {
+            if current == phase {
+                break;
+            }
+        }
+        if self.stack.is_empty() {
+            self.last = None;
+        }
+    }
+
+    /// Account the elapsed time to the current phase.
+    fn checkpoint(&mut self) {
+        let now = Instant::now();
+        if let (Some(phase), Some(last)) = (self.stack.last(), self.last) {
+            self.durations[phase.index()] += now - last;
+        }
+        self.last = Some(now);
+    }
+
+    /// Get the total time spent in a phase
+    pub fn duration(&self, phase: Phase) -> Duration {
+        self.durations[phase.index()]
+    }
+
+    /// Get the number of times a phase has been entered
+    pub fn count(&self, phase: Phase) -> u64 {
+        self.counts[phase.index()]
+    }
+
+    /// Get the total time spent in all phases
+    pub fn total(&self) -> Duration {
+        self.durations.iter().sum()
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16}{:>12}{:>10}", "phase", "time (ms)", "count")?;
+        for phase in Phase::ALL {
+            writeln!(
+                f,
+                "{:<16}{:>12.3}{:>10}",
+                phase.name(),
+                self.duration(phase).as_secs_f64() * 1000.0,
+                self.count(phase)
+            )?;
+        }
+        write!(
+            f,
+            "{:<16}{:>12.3}",
+            "total",
+            self.total().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{slot::Slot, test_util::test_vm};
+
+    #[test]
+    fn nested_phases() {
+        let mut timings = Timings::new();
+        timings.enter(Phase::Interpretation);
+        timings.enter(Phase::ClassRead);
+        timings.exit(Phase::ClassRead);
+        timings.enter(Phase::ClassRead);
+        timings.enter(Phase::Linking);
+        // Exiting the interpretation exits the phases nested in it.
+        timings.exit(Phase::Interpretation);
+        assert!(timings.stack.is_empty());
+        assert!(timings.last.is_none());
+
+        assert_eq!(timings.count(Phase::Interpretation), 1);
+        assert_eq!(timings.count(Phase::ClassRead), 2);
+        assert_eq!(timings.count(Phase::Linking), 1);
+        assert_eq!(timings.count(Phase::Resolution), 0);
+        assert_eq!(timings.duration(Phase::Resolution), Duration::ZERO);
+        let sum: Duration = Phase::ALL
+            .iter()
+            .map(|phase| timings.duration(*phase))
+            .sum();
+        assert_eq!(timings.total(), sum);
+    }
+
+    #[test]
+    fn vm_phases() {
+        let mut vm = test_vm();
+        vm.invoke_static(
+            "Parameters",
+            "add",
+            "(II)I",
+            vec![Slot::Int(1), Slot::Int(2)],
+        )
+        .unwrap();
+        let timings = vm.timing_report();
+        assert_eq!(timings.count(Phase::Interpretation), 1);
+        assert!(timings.count(Phase::ClassRead) > 0);
+        assert!(timings.count(Phase::Linking) > 0);
+        assert!(timings.stack.is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+use std::{cell::RefCell, collections::HashMap, str::FromStr};
+
+use reader::descriptor::{self, ArrayType, BaseType, ClassName, FieldType, ObjectType};
+
+use crate::{
+    alloc::Array,
+    class::ClassId,
+    class_manager::{ClassManager, LoadedClass},
+    slot::Slot,
+};
+
+/// A reference type, either a class already known by the class manager, or a
+/// type described by a descriptor (arrays in particular).
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldTypeOrClassId {
+    ClassId(ClassId),
+    FieldType(FieldType),
+}
+
+impl From<ClassId> for FieldTypeOrClassId {
+    fn from(class_id: ClassId) -> Self {
+        FieldTypeOrClassId::ClassId(class_id)
+    }
+}
+
+impl From<FieldType> for FieldTypeOrClassId {
+    fn from(field_type: FieldType) -> Self {
+        FieldTypeOrClassId::FieldType(field_type)
+    }
+}
+
+/// Normalized form of a type, used to compare them.
+enum RefType {
+    Class(ClassId),
+    /// A class that is unknown to the class manager, hence without any instance.
+    Unloaded(String),
+    Primitive(BaseType),
+    Array(Box<RefType>),
+}
+
+impl RefType {
+    fn from_field_type(cm: &ClassManager, field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::BaseType(base) => RefType::Primitive(base.clone()),
+            FieldType::ObjectType(object) => {
+                let name = object.class_name.as_binary_name();
+                match cm.id_of_class(&name) {
+                    Some(class_id) => RefType::Class(class_id),
+                    None => RefType::Unloaded(name),
+                }
+            }
+            FieldType::ArrayType(array) => {
+                RefType::Array(Box::new(RefType::from_field_type(cm, array.item())))
+            }
+        }
+    }
+
+    fn from_class_id(cm: &ClassManager, class_id: ClassId) -> Self {
+        // Array classes are registered under their descriptor
+        if let Some(class) = cm.get_class_by_id(class_id) {
+            if class.name().starts_with('[') {
+                if let Ok(desc) = descriptor::parse_field_descriptor(class.name()) {
+                    return RefType::from_field_type(cm, desc.field_type());
+                }
+            }
+        }
+        RefType::Class(class_id)
+    }
+
+    fn new(cm: &ClassManager, ty: &FieldTypeOrClassId) -> Self {
+        match ty {
+            FieldTypeOrClassId::ClassId(class_id) => RefType::from_class_id(cm, *class_id),
+            FieldTypeOrClassId::FieldType(field_type) => RefType::from_field_type(cm, field_type),
+        }
+    }
+}
+
+/// Check if a value of type `from` can be assigned to a variable of type `to`.
+///
+/// This implements the rules of `checkcast`, `instanceof` and `aastore`:
+/// - a class is assignable to its superclasses and the interfaces it implements,
+/// - an array is assignable to Object, Cloneable and Serializable,
+/// - an array of primitives is only assignable to an array of the same primitive,
+/// - an array of references is assignable to an array whose component type is
+///   assignable from its own component type.
+pub fn is_assignable(
+    cm: &ClassManager,
+    from: &FieldTypeOrClassId,
+    to: &FieldTypeOrClassId,
+) -> bool {
+    is_ref_assignable(cm, &RefType::new(cm, from), &RefType::new(cm, to))
+}
+
+fn is_ref_assignable(cm: &ClassManager, from: &RefType, to: &RefType) -> bool {
+    match (from, to) {
+        (RefType::Unloaded(from), RefType::Unloaded(to)) => from == to,
+        (RefType::Unloaded(_), _) | (_, RefType::Unloaded(_)) => false,
+        (RefType::Primitive(from), RefType::Primitive(to)) => from == to,
+        (RefType::Primitive(_), _) | (_, RefType::Primitive(_)) => false,
+        (RefType::Class(from), RefType::Class(to)) => is_subtype_of(cm, *from, *to),
+        (RefType::Array(_), RefType::Class(to)) => matches!(
+            cm.get_class_by_id(*to).map(|class| class.name()),
+            Some("java/lang/Object" | "java/lang/Cloneable" | "java/io/Serializable")
+        ),
+        (RefType::Class(_), RefType::Array(_)) => false,
+        (RefType::Array(from), RefType::Array(to)) => is_ref_assignable(cm, from, to),
+    }
+}
+
+/// Check if a class is the same as, a subclass of, or an implementation of another class.
+///
+/// The results are memoized by the [SubtypeCache] of the class manager.
+pub fn is_subtype_of(cm: &ClassManager, class_id: ClassId, other: ClassId) -> bool {
+    if class_id == other {
+        return true;
+    }
+    if let Some(result) = cm.subtype_cache.get(class_id, other) {
+        return result;
+    }
+    let mut stack = vec![class_id];
+    // A hierarchy still being loaded may gain supertypes, its result is not kept.
+    let mut loaded = true;
+    while let Some(cur) = stack.pop() {
+        if cur == other {
+            cm.subtype_cache.insert(class_id, other, true);
+            return true;
+        }
+        match cm.get_class_by_id(cur) {
+            Some(LoadedClass::Loaded(class)) => {
+                stack.extend(class.superclass);
+                stack.extend(class.interfaces.iter().copied());
+            }
+            _ => loaded = false,
+        }
+    }
+    if loaded {
+        cm.subtype_cache.insert(class_id, other, false);
+    }
+    false
+}
+
+/// Memoization of the subtype checks of `checkcast`, `instanceof`... keyed by
+/// the subtype and the supertype, with both the positive and negative results.
+///
+/// The hierarchy of a loaded class never changes, so the results are only
+/// dropped when a class is unloaded or (re)defined, or when the table is full.
+#[derive(Debug, Default)]
+pub struct SubtypeCache {
+    results: RefCell<HashMap<(ClassId, ClassId), bool>>,
+}
+
+impl SubtypeCache {
+    /// Maximum number of results kept, the table being cleared once reached.
+    pub const CAPACITY: usize = 4096;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the result of a check, if memoized.
+    pub fn get(&self, class_id: ClassId, other: ClassId) -> Option<bool> {
+        self.results.borrow().get(&(class_id, other)).copied()
+    }
+
+    fn insert(&self, class_id: ClassId, other: ClassId, result: bool) {
+        let mut results = self.results.borrow_mut();
+        if results.len() >= Self::CAPACITY {
+            results.clear();
+        }
+        results.insert((class_id, other), result);
+    }
+
+    /// Drop all the results.
+    pub fn invalidate(&self) {
+        self.results.borrow_mut().clear();
+    }
+
+    /// Number of results kept.
+    pub fn len(&self) -> usize {
+        self.results.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Get the runtime type of a reference.
+///
+/// Returns None for null references and primitive values.
+pub fn type_of(cm: &ClassManager, slot: &Slot) -> Option<FieldTypeOrClassId> {
+    match slot {
+        Slot::ObjectReference(obj) => Some(FieldTypeOrClassId::ClassId(obj.class_id())),
+        Slot::ArrayReference(array) => {
+            let item = match array.as_ref() {
+                Array::Int(_) => FieldType::BaseType(BaseType::Int),
+                Array::Long(_) => FieldType::BaseType(BaseType::Long),
+                Array::Float(_) => FieldType::BaseType(BaseType::Float),
+                Array::Double(_) => FieldType::BaseType(BaseType::Double),
+                Array::Byte(_) => FieldType::BaseType(BaseType::Byte),
+                Array::Char(_) => FieldType::BaseType(BaseType::Char),
+                Array::Short(_) => FieldType::BaseType(BaseType::Short),
+                Array::Boolean(_) => FieldType::BaseType(BaseType::Boolean),
+                Array::ObjectRef(array) => {
+                    let name = cm.get_class_by_id(array.class_id())?.name();
+                    FieldType::ObjectType(ObjectType::new(ClassName::from_str(name).ok()?))
+                }
+                Array::ArrayRef(array) => FieldType::ArrayType(array.item_type().clone()),
+            };
+            Some(FieldTypeOrClassId::FieldType(FieldType::ArrayType(
+                ArrayType::new(item),
+            )))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{test_class_manager, test_vm};
+
+    #[test]
+    fn array_assignability() {
+        let mut cm = test_class_manager();
+        for class_name in [
+            "java/lang/String",
+            "java/lang/Integer",
+            "java/lang/Cloneable",
+        ] {
+            cm.get_or_resolve_class(class_name).unwrap();
+        }
+        let ty = |descriptor: &str| {
+            let descriptor = descriptor::parse_field_descriptor(descriptor).unwrap();
+            FieldTypeOrClassId::FieldType(descriptor.field_type().clone())
+        };
+        let object = FieldTypeOrClassId::ClassId(cm.id_of_class("java/lang/Object").unwrap());
+        let cases = [
+            ("[Ljava/lang/String;", "[Ljava/lang/Comparable;", true),
+            ("[Ljava/lang/Comparable;", "[Ljava/lang/String;", false),
+            ("[Ljava/lang/String;", "[Ljava/lang/Integer;", false),
+            ("[[Ljava/lang/String;", "[[Ljava/lang/Comparable;", true),
+            ("[[Ljava/lang/String;", "[Ljava/lang/Object;", true),
+            ("[[Ljava/lang/String;", "[Ljava/lang/Cloneable;", true),
+            ("[[Ljava/lang/String;", "[Ljava/lang/Comparable;", false),
+            ("[[Ljava/lang/String;", "[[[Ljava/lang/Object;", false),
+            ("[[I", "[Ljava/lang/Object;", true),
+            ("[[I", "[[J", false),
+            ("[I", "[Ljava/lang/Object;", false),
+            ("[I", "[I", true),
+        ];
+        for (from, to, expected) in cases {
+            assert_eq!(
+                is_assignable(&cm, &ty(from), &ty(to)),
+                expected,
+                "{} to {}",
+                from,
+                to
+            );
+        }
+        assert!(is_assignable(&cm, &ty("[[I"), &object));
+        assert!(!is_assignable(&cm, &object, &ty("[I")));
+    }
+
+    #[test]
+    fn array_instanceof() {
+        let mut vm = test_vm();
+        for method in ["stringsAreComparable", "nestedArrays", "primitiveArrays"] {
+            let result = vm.invoke_static("ArrayTypes", method, "()Z", vec![]);
+            assert!(
+                matches!(result, Ok(Some(Slot::Int(1)))),
+                "{} {:?}",
+                method,
+                result
+            );
+        }
+    }
+}
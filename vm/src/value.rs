@@ -0,0 +1,290 @@
+use reader::descriptor::{BaseType, FieldType};
+use snafu::Snafu;
+
+use crate::{
+    alloc::{new_string, read_string, Array},
+    class_loader::ClassLoadingError,
+    class_manager::ClassManager,
+    slot::Slot,
+};
+
+#[derive(Debug, Snafu)]
+pub enum ValueError {
+    #[snafu(display("Invalid {} literal: {}", type_name, input))]
+    InvalidLiteral { type_name: String, input: String },
+
+    #[snafu(display("No literal for {} values, only null", type_name))]
+    UnsupportedType { type_name: String },
+
+    #[snafu(context(false))]
+    #[snafu(display("Cannot create the string: {}", source))]
+    StringCreation { source: ClassLoadingError },
+}
+
+/// Get the Java name of a type, e.g. `int`, `java.lang.String` or `long[]`.
+pub fn type_name(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::BaseType(base) => base.java_syntax().to_string(),
+        FieldType::ObjectType(object) => object.class_name.as_source_name(),
+        FieldType::ArrayType(array) => format!("{}[]", type_name(array.item())),
+    }
+}
+
+/// Get the descriptor of a type, e.g. `I`, `Ljava/lang/String;` or `[J`.
+pub fn type_descriptor(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::BaseType(base) => match base {
+            BaseType::Byte => "B",
+            BaseType::Char => "C",
+            BaseType::Double => "D",
+            BaseType::Float => "F",
+            BaseType::Int => "I",
+            BaseType::Long => "J",
+            BaseType::Short => "S",
+            BaseType::Boolean => "Z",
+        }
+        .to_string(),
+        FieldType::ObjectType(object) => format!("L{};", object.class_name.as_binary_name()),
+        FieldType::ArrayType(array) => format!("[{}", type_descriptor(array.item())),
+    }
+}
+
+/// Parse the literal of a value of the given type.
+///
+/// Primitive values are written as in Java (`42`, `-1L`, `1.5f`, `'c'`, `true`),
+/// strings between double quotes, and any reference can be `null`.
+pub fn parse_literal(
+    cm: &mut ClassManager,
+    field_type: &FieldType,
+    input: &str,
+) -> Result<Slot, ValueError> {
+    match field_type {
+        FieldType::BaseType(base) => parse_primitive(base, input),
+        _ if input == "null" => Ok(Slot::UndefinedReference),
+        FieldType::ObjectType(object)
+            if object.class_name.as_binary_name() == "java/lang/String" =>
+        {
+            let Some(value) = input
+                .strip_prefix('"')
+                .and_then(|input| input.strip_suffix('"'))
+                .filter(|_| input.len() >= 2)
+            else {
+                return Err(ValueError::InvalidLiteral {
+                    type_name: type_name(field_type),
+                    input: input.to_string(),
+                });
+            };
+            Ok(Slot::ObjectReference(new_string(cm, &unescape(value))?))
+        }
+        _ => Err(ValueError::UnsupportedType {
+            type_name: type_name(field_type),
+        }),
+    }
+}
+
+/// Parse the literal of a primitive value.
+pub fn parse_primitive(base: &BaseType, input: &str) -> Result<Slot, ValueError> {
+    let invalid = || ValueError::InvalidLiteral {
+        type_name: type_name(&FieldType::BaseType(base.clone())),
+        input: input.to_string(),
+    };
+    let slot = match base {
+        BaseType::Boolean => match input {
+            "true" => Slot::Int(1),
+            "false" => Slot::Int(0),
+            _ => return Err(invalid()),
+        },
+        BaseType::Char => {
+            let quoted = input
+                .strip_prefix('\'')
+                .and_then(|input| input.strip_suffix('\''))
+                .map(unescape);
+            match quoted {
+                Some(value) if value.encode_utf16().count() == 1 => {
+                    Slot::Int(value.encode_utf16().next().unwrap() as i32)
+                }
+                Some(_) => return Err(invalid()),
+                None => Slot::Int(input.parse::<u16>().map_err(|_| invalid())? as i32),
+            }
+        }
+        BaseType::Byte => Slot::Int(input.parse::<i8>().map_err(|_| invalid())? as i32),
+        BaseType::Short => Slot::Int(input.parse::<i16>().map_err(|_| invalid())? as i32),
+        BaseType::Int => Slot::Int(input.parse().map_err(|_| invalid())?),
+        BaseType::Long => Slot::Long(
+            input
+                .strip_suffix(['l', 'L'])
+                .unwrap_or(input)
+                .parse()
+                .map_err(|_| invalid())?,
+        ),
+        BaseType::Float => Slot::Float(
+            input
+                .strip_suffix(['f', 'F'])
+                .unwrap_or(input)
+                .parse()
+                .map_err(|_| invalid())?,
+        ),
+        BaseType::Double => Slot::Double(
+            input
+                .strip_suffix(['d', 'D'])
+                .unwrap_or(input)
+                .parse()
+                .map_err(|_| invalid())?,
+        ),
+    };
+    Ok(slot)
+}
+
+/// Format a value of the given type as a Java literal, when possible.
+///
+/// The objects other than strings are only described by their class.
+pub fn format_value(cm: &ClassManager, field_type: &FieldType, value: &Slot) -> String {
+    match (field_type, value) {
+        (FieldType::BaseType(BaseType::Boolean), Slot::Int(value)) => (*value != 0).to_string(),
+        (FieldType::BaseType(BaseType::Char), Slot::Int(value)) => {
+            match char::from_u32(*value as u16 as u32) {
+                Some(c) => format!("{:?}", c),
+                None => format!("'\\u{:04x}'", *value as u16),
+            }
+        }
+        (_, Slot::Int(value)) => value.to_string(),
+        (_, Slot::Long(value)) => format!("{}L", value),
+        (_, Slot::Float(value)) => format!("{:?}f", value),
+        (_, Slot::Double(value)) => format!("{:?}", value),
+        (_, Slot::UndefinedReference) => "null".to_string(),
+        (_, Slot::ObjectReference(obj)) => {
+            let class_name = cm
+                .get_class_by_id(obj.class_id())
+                .map(|class| class.name().replace('/', "."))
+                .unwrap_or_default();
+            match read_string(obj).filter(|_| class_name == "java.lang.String") {
+                Some(value) => format!("{:?}", value),
+                None => format!("{} instance", class_name),
+            }
+        }
+        (_, Slot::ArrayReference(array)) => {
+            let item_name = match (field_type, array.as_ref()) {
+                (FieldType::ArrayType(array), _) => type_name(array.item()),
+                (_, Array::Int(_)) => "int".to_string(),
+                (_, Array::Long(_)) => "long".to_string(),
+                (_, Array::Float(_)) => "float".to_string(),
+                (_, Array::Double(_)) => "double".to_string(),
+                (_, Array::Byte(_)) => "byte".to_string(),
+                (_, Array::Char(_)) => "char".to_string(),
+                (_, Array::Short(_)) => "short".to_string(),
+                (_, Array::Boolean(_)) => "boolean".to_string(),
+                (_, Array::ObjectRef(_) | Array::ArrayRef(_)) => "java.lang.Object".to_string(),
+            };
+            format!("{}[{}]", item_name, array.len())
+        }
+        (_, slot) => format!("{:?}", slot),
+    }
+}
+
+/// Replace the escape sequences of a Java string literal.
+fn unescape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('r') => output.push('\r'),
+            Some('0') => output.push('\0'),
+            Some(other) => output.push(other),
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::test_class_manager;
+
+    #[test]
+    fn primitive_literals() {
+        let parse = |base, input| parse_primitive(&base, input).ok();
+        assert!(matches!(parse(BaseType::Int, "-42"), Some(Slot::Int(-42))));
+        assert!(matches!(parse(BaseType::Byte, "127"), Some(Slot::Int(127))));
+        assert!(parse(BaseType::Byte, "128").is_none());
+        assert!(matches!(
+            parse(BaseType::Boolean, "true"),
+            Some(Slot::Int(1))
+        ));
+        assert!(matches!(parse(BaseType::Char, "'a'"), Some(Slot::Int(97))));
+        assert!(matches!(
+            parse(BaseType::Char, "'\\n'"),
+            Some(Slot::Int(10))
+        ));
+        assert!(matches!(parse(BaseType::Long, "5L"), Some(Slot::Long(5))));
+        assert!(matches!(parse(BaseType::Float, "1.5f"), Some(Slot::Float(v)) if v == 1.5));
+        assert!(matches!(parse(BaseType::Double, "2"), Some(Slot::Double(v)) if v == 2.0));
+        assert!(parse(BaseType::Int, "1.5").is_none());
+        assert!(parse(BaseType::Long, "5LL").is_none());
+        assert!(parse(BaseType::Double, "1.5f").is_none());
+        assert!(parse(BaseType::Boolean, "1").is_none());
+        assert!(matches!(parse(BaseType::Char, "65"), Some(Slot::Int(65))));
+        assert!(parse(BaseType::Char, "'ab'").is_none());
+        assert!(parse(BaseType::Char, "-1").is_none());
+    }
+
+    #[test]
+    fn reference_literals() {
+        let mut cm = test_class_manager();
+        let field_type = |descriptor| FieldType::parse_field_type(descriptor).unwrap().1;
+        let string = field_type("Ljava/lang/String;");
+        let value = parse_literal(&mut cm, &string, "\"a\\tb \\\"c\\\"\"").unwrap();
+        let Slot::ObjectReference(obj) = &value else {
+            panic!("not a string: {:?}", value);
+        };
+        assert_eq!(read_string(obj).as_deref(), Some("a\tb \"c\""));
+        assert_eq!(format_value(&cm, &string, &value), "\"a\\tb \\\"c\\\"\"");
+
+        assert!(matches!(
+            parse_literal(&mut cm, &string, "null"),
+            Ok(Slot::UndefinedReference)
+        ));
+        assert!(matches!(
+            parse_literal(&mut cm, &field_type("[I"), "null"),
+            Ok(Slot::UndefinedReference)
+        ));
+        for input in ["\"", "text", "\"open"] {
+            assert!(matches!(
+                parse_literal(&mut cm, &string, input),
+                Err(ValueError::InvalidLiteral { .. })
+            ));
+        }
+        assert!(matches!(
+            parse_literal(&mut cm, &field_type("Ljava/lang/Object;"), "\"x\""),
+            Err(ValueError::UnsupportedType { .. })
+        ));
+    }
+
+    #[test]
+    fn formatted_values() {
+        let cm = test_class_manager();
+        let format = |descriptor, value| {
+            format_value(
+                &cm,
+                &FieldType::parse_field_type(descriptor).unwrap().1,
+                &value,
+            )
+        };
+        assert_eq!(format("Z", Slot::Int(1)), "true");
+        assert_eq!(format("C", Slot::Int(10)), "'\\n'");
+        assert_eq!(format("I", Slot::Int(-3)), "-3");
+        assert_eq!(format("J", Slot::Long(5)), "5L");
+        assert_eq!(format("F", Slot::Float(1.5)), "1.5f");
+        assert_eq!(format("D", Slot::Double(2.0)), "2.0");
+        assert_eq!(
+            format("Ljava/lang/Object;", Slot::UndefinedReference),
+            "null"
+        );
+    }
+}
@@ -1,24 +1,133 @@
+use std::{
+    fmt,
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use reader::descriptor::DescriptorError;
+use snafu::{ResultExt, Snafu};
+
 use crate::{
+    alloc::{
+        new_string_array,
+        weak::{self, Reachability, ReferenceClearedHook},
+        AllocError, ArrayRef, Collector, HeapBudget, IdentityHashGenerator, PrimitiveElement,
+        RootSet,
+    },
+    assertions::AssertionStatus,
+    breakpoint::{Breakpoint, BreakpointHook},
+    call_log::{self, CallFilter},
     class::ClassId,
-    class_loader::ClassLoader,
+    class_loader::{ClassLoader, ClassLoadingError},
     class_manager::{ClassManager, LoadedClass},
-    thread::{ExecutionError, Slot},
+    class_stats::ClassStats,
+    class_summary::ClassSummary,
+    class_version::ClassVersionPolicy,
+    clock::Clock,
+    event::{EventSink, VmEvent},
+    native::{FileTable, StandardStreams},
+    opcode_stats::OpcodeHistogram,
+    thread::{ExecutionError, Slot, Step, Thread},
+    thread_dump::{ThreadDump, ThreadStack},
     thread_manager::ThreadManager,
+    timing::{Phase, Timings},
 };
 
-#[derive(Debug)]
+/// Descriptor of the main method of a program, `public static void main(String[] args)`.
+pub const MAIN_DESCRIPTOR: &str = "([Ljava/lang/String;)V";
+/// Descriptor of a main method without arguments, still accepted.
+pub const MAIN_NO_ARGS_DESCRIPTOR: &str = "()V";
+
+#[derive(Debug, Snafu)]
+pub enum VmError {
+    #[snafu(display("Cannot load class {}: {}", class_name, source))]
+    ClassLoading {
+        class_name: String,
+        source: ClassLoadingError,
+    },
+
+    #[snafu(context(false))]
+    #[snafu(display("Bad descriptor: {}", source))]
+    BadDescriptor { source: DescriptorError },
+
+    #[snafu(display("Static method not found: {}.{}{}", class_name, name, descriptor))]
+    MethodNotFound {
+        class_name: String,
+        name: String,
+        descriptor: String,
+    },
+
+    #[snafu(display("Method not found: {}.{}", class_name, name))]
+    UnknownMethod { class_name: String, name: String },
+
+    #[snafu(display(
+        "Method {}.{} is overloaded, its descriptor is required",
+        class_name,
+        name
+    ))]
+    AmbiguousMethod { class_name: String, name: String },
+
+    #[snafu(display("No instruction at pc {} of {}.{}", pc, class_name, name))]
+    InvalidPc {
+        class_name: String,
+        name: String,
+        pc: usize,
+    },
+
+    #[snafu(display("Method {}.{} has no bytecode to execute", class_name, name))]
+    NoCode { class_name: String, name: String },
+
+    #[snafu(display(
+        "{}{} expects {} argument(s), got {}",
+        name,
+        signature,
+        expected,
+        actual
+    ))]
+    ArgumentCount {
+        name: String,
+        /// The descriptor of the method in Java syntax, e.g. `(int, long) -> void`.
+        signature: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[snafu(context(false))]
+    #[snafu(display("{}", source))]
+    Execution { source: ExecutionError },
+
+    #[snafu(display("Exit requested with status {}", status))]
+    Exit { status: i32 },
+}
+
+/// Hook receiving the thread dumps requested with [Vm::dump_request].
+pub type ThreadDumpHook = Box<dyn FnMut(&ThreadDump) + Send>;
+
 pub struct Vm {
     class_manager: ClassManager,
 
     thread_manager: ThreadManager,
+
+    dump_request: Arc<AtomicBool>,
+    dump_hook: Option<ThreadDumpHook>,
+}
+
+impl fmt::Debug for Vm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vm")
+            .field("class_manager", &self.class_manager)
+            .field("thread_manager", &self.thread_manager)
+            .field("dump_request", &self.dump_request)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Vm {
     pub fn new(cl: ClassLoader) -> Self {
-        Self {
-            class_manager: ClassManager::new(cl),
-            thread_manager: ThreadManager::new(),
-        }
+        VmBuilder::new(cl).build()
     }
 
     pub fn class_manager(&self) -> &ClassManager {
@@ -37,25 +146,859 @@ impl Vm {
         &mut self.thread_manager
     }
 
-    pub fn create_thread(&mut self, class_id: &ClassId, method: usize, args: Vec<Slot>) -> usize {
-        let Some(LoadedClass::Loaded(class)) = self.class_manager.get_class_by_id(class_id.clone())
-        else {
-            panic!("Class not loaded: {:?}", class_id);
+    /// Get the roots of the heap: the references held by the threads and by
+    /// the class manager (static fields, interned strings...).
+    pub fn gc_roots(&self) -> RootSet {
+        let mut roots = RootSet::new();
+        for (_, thread) in self.thread_manager.iter() {
+            roots.add_provider(thread);
+        }
+        roots.add_provider(&self.class_manager);
+        roots
+    }
+
+    /// Get the time spent in each phase of the execution (class loading, interpretation, ...)
+    pub fn timing_report(&self) -> &Timings {
+        &self.class_manager.timings
+    }
+
+    /// Get the memory statistics of the loaded classes (instance sizes, allocations, ...)
+    pub fn class_stats(&self) -> ClassStats {
+        self.class_manager.class_stats()
+    }
+
+    /// Describe a class (superclass, interfaces, members, ...), loading it if needed.
+    pub fn describe_class(&mut self, class_name: &str) -> Result<ClassSummary, VmError> {
+        let class_id = self
+            .class_manager
+            .get_or_resolve_class(class_name)
+            .context(ClassLoadingSnafu { class_name })?
+            .id();
+        let Some(LoadedClass::Loaded(class)) = self.class_manager.get_class_by_id(class_id) else {
+            return Err(VmError::ClassLoading {
+                class_name: class_name.to_string(),
+                source: ClassLoadingError::Unknown,
+            });
+        };
+        Ok(ClassSummary::of(&self.class_manager, class))
+    }
+
+    /// Get the number of classes removed from the VM.
+    ///
+    /// Only the temporary classes of [crate::harness::run_bytecode] are removed,
+    /// explicitly once their bytecode completes. The classes of the class path
+    /// are never unloaded, whatever the reachability of their instances.
+    pub fn unloaded_class_count(&self) -> usize {
+        self.class_manager.unloaded_class_count()
+    }
+
+    /// Get the number of executions of each instruction, if counting them has
+    /// been enabled (see [VmBuilder::opcode_histogram]).
+    pub fn opcode_histogram(&self) -> Option<&OpcodeHistogram> {
+        self.class_manager.opcode_histogram.as_ref()
+    }
+
+    /// Create a thread executing a method with the given arguments.
+    ///
+    /// Fails if the method has no code (native or abstract methods cannot be
+    /// an entry point), or if the arguments do not fit in its local variables.
+    pub fn create_thread(
+        &mut self,
+        class_id: ClassId,
+        method: usize,
+        args: Vec<Slot>,
+    ) -> Result<usize, ExecutionError> {
+        let Some(LoadedClass::Loaded(class)) = self.class_manager.get_class_by_id(class_id) else {
+            return Err(ExecutionError::ClassNotLoaded);
+        };
+        let Some(m) = class.get_method_by_index(method) else {
+            return Err(ExecutionError::MethodNotLoaded);
+        };
+        let Some(code) = m.get_code() else {
+            return Err(ExecutionError::NoCode {
+                method: format!("{}.{}{}", class.name, m.name, m.descriptor.as_str()),
+            });
         };
-        let m = class.get_method_by_index(method).unwrap();
-        let code = m.get_code().expect(
-            "Code attribute not found, probably a native method, unsupported as thread entry point",
-        );
         let max_locals = code.max_locals as usize;
+        let max_stack = code.max_stack as usize;
+        if self.class_manager.call_log.is_some() {
+            call_log::log_entry(&self.class_manager, class_id, method, &args, 0);
+        }
 
         self.thread_manager
-            .create_thread(&class_id, method, max_locals, args)
+            .create_thread(class_id, method, max_locals, max_stack, args)
+            .map_err(|source| ExecutionError::InstructionExecutionError { source })
     }
 
-    pub fn execute_thread(&mut self, thread_id: usize) -> Result<(), ExecutionError> {
-        let thread = self.thread_manager.get_thread_mut(thread_id).unwrap();
-        let x = thread.execute(&mut self.class_manager);
+    /// Execute a thread until its completion.
+    ///
+    /// Returns the exit status if the VM has been requested to exit (e.g. by
+    /// `System.exit`), in which case the stacks of all the threads are unwound.
+    pub fn execute_thread(&mut self, thread_id: usize) -> Result<Option<i32>, ExecutionError> {
+        self.class_manager.current_thread = thread_id;
+        self.class_manager.timings.enter(Phase::Interpretation);
+        let x = loop {
+            let Some(thread) = self.thread_manager.get_thread_mut(thread_id) else {
+                break Err(ExecutionError::ThreadNotFound { thread_id });
+            };
+            match thread.execute_until(&mut self.class_manager, &self.dump_request) {
+                Ok(false) => {
+                    self.dump_request.store(false, Ordering::Relaxed);
+                    let dump = self.thread_dump();
+                    match &mut self.dump_hook {
+                        Some(hook) => hook(&dump),
+                        None => log::info!("{}", dump),
+                    }
+                }
+                Ok(true) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+        };
+        self.class_manager.timings.exit(Phase::Interpretation);
+        self.add_started_threads();
         log::debug!("Classes loaded: {}", self.class_manager.classes_by_id.len());
-        x
+        if let Err(err) = &x {
+            self.report_failure(thread_id, err);
+        }
+        match x {
+            Ok(()) => Ok(None),
+            Err(ExecutionError::Exit { status }) => {
+                log::debug!("Exit requested with status {}", status);
+                for (_, thread) in self.thread_manager.iter_mut() {
+                    thread.reset();
+                }
+                Ok(Some(status))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Execute the main thread, then the threads it started until the last
+    /// non-daemon thread completes, as a JVM does before shutting down.
+    ///
+    /// The threads are not preempted, each one runs until its completion: the
+    /// started threads run once the main thread completes, the highest priority
+    /// first, and the daemon threads never run as nothing waits for them.
+    /// The failures of the started threads are only reported, the result is the
+    /// one of the main thread unless the VM has been requested to exit.
+    ///
+    /// The shutdown hooks are run last, see [Vm::run_shutdown_hooks].
+    pub fn execute_until_shutdown(
+        &mut self,
+        main_thread: usize,
+    ) -> Result<Option<i32>, ExecutionError> {
+        let mut result = self.execute_thread(main_thread);
+        let mut failed = Vec::new();
+        if result.is_err() {
+            failed.push(main_thread);
+        }
+        if !matches!(result, Ok(Some(_))) {
+            while let Some(thread_id) = self.thread_manager.next_non_daemon(&failed) {
+                match self.execute_thread(thread_id) {
+                    Ok(Some(status)) => {
+                        result = Ok(Some(status));
+                        break;
+                    }
+                    Ok(None) => (),
+                    // Already reported to the event sink
+                    Err(_) => failed.push(thread_id),
+                }
+            }
+        }
+        self.run_shutdown_hooks();
+        result
+    }
+
+    /// Run the shutdown hooks registered by `Runtime.addShutdownHook`, one after
+    /// the other until their completion, as the VM shuts down.
+    ///
+    /// The failures of the hooks are reported, and their exit requests ignored.
+    pub fn run_shutdown_hooks(&mut self) {
+        for hook in std::mem::take(&mut self.class_manager.shutdown_hooks) {
+            let thread = match Thread::for_mirror(&mut self.class_manager, hook) {
+                Ok(thread) => thread,
+                Err(err) => {
+                    log::warn!("Cannot run a shutdown hook: {}", err);
+                    continue;
+                }
+            };
+            let thread_id = self.thread_manager.add_thread(thread);
+            let _ = self.execute_thread(thread_id);
+        }
+    }
+
+    /// Add the threads started by the program to the threads of the VM.
+    fn add_started_threads(&mut self) {
+        for thread in std::mem::take(&mut self.class_manager.started_threads) {
+            let thread_id = self.thread_manager.add_thread(thread);
+            log::debug!("Thread {} started", thread_id);
+        }
+    }
+
+    /// Execute a thread by one step (an instruction, over an invocation, or out
+    /// of the current method), see [Step].
+    ///
+    /// Returns true if the thread has completed. The failures are reported as by
+    /// [Vm::execute_thread], but the exit requests are returned as errors.
+    pub fn step_thread(&mut self, thread_id: usize, step: Step) -> Result<bool, ExecutionError> {
+        let Some(thread) = self.thread_manager.get_thread_mut(thread_id) else {
+            return Err(ExecutionError::ThreadNotFound { thread_id });
+        };
+        self.class_manager.current_thread = thread_id;
+        self.class_manager.timings.enter(Phase::Interpretation);
+        let result = thread.step_with(&mut self.class_manager, step);
+        self.class_manager.timings.exit(Phase::Interpretation);
+        self.add_started_threads();
+        if let Err(err) = &result {
+            self.report_failure(thread_id, err);
+        }
+        result
+    }
+
+    /// Report the failure of a thread to the event sink, exit requests excluded.
+    fn report_failure(&mut self, thread_id: usize, err: &ExecutionError) {
+        if err.exit_status().is_some() {
+            return;
+        }
+        let Some(thread) = self.thread_manager.get_thread(thread_id) else {
+            return;
+        };
+        let thread_name = thread.name.clone();
+        let exception = match err {
+            ExecutionError::InstructionExecutionError { source } => source.java_exception(),
+            _ => None,
+        };
+        let event = match exception {
+            Some(exception) => VmEvent::UncaughtException {
+                thread: thread_id,
+                thread_name,
+                exception: exception.to_string(),
+                message: err.to_string(),
+            },
+            None => VmEvent::InvocationError {
+                thread: thread_id,
+                thread_name,
+                method: ThreadStack::of(&self.class_manager, thread_id, thread)
+                    .frames
+                    .first()
+                    .map(|frame| {
+                        format!(
+                            "{}.{}{}",
+                            frame.class_name, frame.method_name, frame.descriptor
+                        )
+                    }),
+                message: err.to_string(),
+            },
+        };
+        self.class_manager.events.emit(&event);
+    }
+
+    /// Invoke a static method in a new thread, and get its returned value.
+    ///
+    /// The class is loaded and initialized if needed. The thread is removed once
+    /// the method has completed.
+    pub fn invoke_static(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        args: Vec<Slot>,
+    ) -> Result<Option<Slot>, VmError> {
+        let descriptor = self.class_manager.intern_method_descriptor(descriptor)?;
+        let class = self
+            .class_manager
+            .get_or_resolve_class(class_name)
+            .context(ClassLoadingSnafu { class_name })?;
+        let LoadedClass::Loaded(class) = class else {
+            return Err(VmError::ClassLoading {
+                class_name: class_name.to_string(),
+                source: ClassLoadingError::Unknown,
+            });
+        };
+        let Some((method_id, method)) = class
+            .get_method(name, &descriptor)
+            .filter(|(_, method)| method.is_static())
+        else {
+            return Err(VmError::MethodNotFound {
+                class_name: class_name.to_string(),
+                name: name.to_string(),
+                descriptor: descriptor.as_str().to_string(),
+            });
+        };
+        if method.get_code().is_none() {
+            return Err(VmError::NoCode {
+                class_name: class_name.to_string(),
+                name: name.to_string(),
+            });
+        }
+        if args.len() != descriptor.args_count() {
+            return Err(VmError::ArgumentCount {
+                name: name.to_string(),
+                signature: descriptor.java_syntax(),
+                expected: descriptor.args_count(),
+                actual: args.len(),
+            });
+        }
+        let class_id = class.id;
+        self.class_manager
+            .initialize_class(class_id)
+            .context(ClassLoadingSnafu { class_name })?;
+
+        let thread_id = self.create_thread(class_id, method_id, args)?;
+        let status = self.execute_thread(thread_id);
+        let result = self
+            .thread_manager
+            .get_thread_mut(thread_id)
+            .and_then(|thread| thread.result.take());
+        self.thread_manager.stop_thread(thread_id);
+        match status? {
+            Some(status) => Err(VmError::Exit { status }),
+            None => Ok(result),
+        }
+    }
+
+    /// Create the main thread of a program, running the `main` method of a class
+    /// with the given arguments.
+    ///
+    /// The class is loaded and initialized if needed, its name being either the
+    /// binary name (`com/example/Main`) or the qualified one (`com.example.Main`).
+    /// The main method takes the arguments as a String array, or nothing for the
+    /// legacy `static void main()`. Returns the id of the thread.
+    pub fn create_main_thread(
+        &mut self,
+        class_name: &str,
+        args: &[String],
+    ) -> Result<usize, VmError> {
+        let class_name = class_name.replace('.', "/");
+        let class_name = class_name.as_str();
+        let main_descriptor = self
+            .class_manager
+            .intern_method_descriptor(MAIN_DESCRIPTOR)?;
+        let no_args_descriptor = self
+            .class_manager
+            .intern_method_descriptor(MAIN_NO_ARGS_DESCRIPTOR)?;
+        let class = self
+            .class_manager
+            .get_or_resolve_class(class_name)
+            .context(ClassLoadingSnafu { class_name })?;
+        let LoadedClass::Loaded(class) = class else {
+            return Err(VmError::ClassLoading {
+                class_name: class_name.to_string(),
+                source: ClassLoadingError::Unknown,
+            });
+        };
+        let main = |descriptor| {
+            class
+                .get_method("main", descriptor)
+                .filter(|(_, method)| method.is_static())
+        };
+        let (method_id, method, with_args) = match main(&main_descriptor) {
+            Some((method_id, method)) => (method_id, method, true),
+            None => match main(&no_args_descriptor) {
+                Some((method_id, method)) => (method_id, method, false),
+                None => {
+                    return Err(VmError::MethodNotFound {
+                        class_name: class_name.to_string(),
+                        name: "main".to_string(),
+                        descriptor: MAIN_DESCRIPTOR.to_string(),
+                    })
+                }
+            },
+        };
+        if method.get_code().is_none() {
+            return Err(VmError::NoCode {
+                class_name: class_name.to_string(),
+                name: "main".to_string(),
+            });
+        }
+        let class_id = class.id;
+        self.class_manager
+            .initialize_class(class_id)
+            .context(ClassLoadingSnafu { class_name })?;
+        let mut main_args = vec![];
+        if with_args {
+            let array =
+                new_string_array(&mut self.class_manager, args).context(ClassLoadingSnafu {
+                    class_name: "java/lang/String",
+                })?;
+            main_args.push(Slot::ArrayReference(array));
+        }
+        Ok(self.create_thread(class_id, method_id, main_args)?)
+    }
+
+    /// Run a program: the `main` method of a class in the main thread, then the
+    /// threads it started until the VM shuts down, see [Vm::create_main_thread]
+    /// and [Vm::execute_until_shutdown].
+    ///
+    /// Returns the exit status, 0 unless the program requested to exit (e.g. by
+    /// `System.exit`). The failure of the main thread is an [VmError::Execution]
+    /// error, reported as by [Vm::execute_thread].
+    pub fn run_main(&mut self, class_name: &str, args: &[String]) -> Result<i32, VmError> {
+        let thread_id = self.create_main_thread(class_name, args)?;
+        log::info!("Starting main thread: {}", thread_id);
+        Ok(self.execute_until_shutdown(thread_id)?.unwrap_or(0))
+    }
+
+    /// Allocate an array of a primitive type holding the given elements, e.g. to
+    /// pass an `int[]` to [Vm::invoke_static].
+    pub fn new_array<T: PrimitiveElement>(&mut self, data: Vec<T>) -> Result<ArrayRef, AllocError> {
+        self.class_manager
+            .check_array_budget(data.len(), std::mem::size_of::<T>())?;
+        Ok(self.class_manager.alloc_array(T::new_array(data)))
+    }
+
+    /// Copy the elements of an array of a primitive type, e.g. the `int[]`
+    /// returned by [Vm::invoke_static].
+    ///
+    /// Returns None if the slot is not a reference to an array of the type `T`.
+    pub fn read_array<T: PrimitiveElement>(&self, array: &Slot) -> Option<Vec<T>> {
+        array.as_array()?.to_vec()
+    }
+
+    /// Copy elements at the start of an array of a primitive type.
+    ///
+    /// Returns false if the slot is not a reference to an array of the type `T`,
+    /// or if the array is shorter than the elements.
+    pub fn write_array<T: PrimitiveElement>(&self, array: &Slot, data: &[T]) -> bool {
+        let Some(array) = array.as_array() else {
+            return false;
+        };
+        array
+            .with_slice_mut(|elements: &mut [T]| match elements.get_mut(..data.len()) {
+                Some(elements) => {
+                    elements.copy_from_slice(data);
+                    true
+                }
+                None => false,
+            })
+            .unwrap_or(false)
+    }
+
+    /// Take a snapshot of the stacks of the live threads.
+    ///
+    /// The threads whose stack is empty (not started, or completed) are omitted.
+    pub fn thread_dump(&self) -> ThreadDump {
+        ThreadDump {
+            threads: self
+                .thread_manager
+                .iter()
+                .filter(|(_, thread)| !thread.stack.is_empty())
+                .map(|(id, thread)| ThreadStack::of(&self.class_manager, id, thread))
+                .collect(),
+        }
+    }
+
+    /// Get the flag requesting a thread dump of the running VM.
+    ///
+    /// The flag can be set from another thread (e.g. a signal handler), the dump
+    /// is then taken before the next instruction of the executing thread and
+    /// given to the thread dump hook, before resuming the execution.
+    pub fn dump_request(&self) -> Arc<AtomicBool> {
+        self.dump_request.clone()
+    }
+
+    /// Set the hook receiving the requested thread dumps, logged by default.
+    pub fn set_thread_dump_hook(&mut self, hook: ThreadDumpHook) {
+        self.dump_hook = Some(hook);
+    }
+
+    /// Set a breakpoint on an instruction of a method, the class being loaded if needed.
+    ///
+    /// Without descriptor, the method must be the only one of its class with this name.
+    pub fn set_breakpoint(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: Option<&str>,
+        pc: usize,
+    ) -> Result<Breakpoint, VmError> {
+        let descriptor = match descriptor {
+            Some(descriptor) => Some(self.class_manager.intern_method_descriptor(descriptor)?),
+            None => None,
+        };
+        let class = self
+            .class_manager
+            .get_or_resolve_class(class_name)
+            .context(ClassLoadingSnafu { class_name })?;
+        let LoadedClass::Loaded(class) = class else {
+            return Err(VmError::ClassLoading {
+                class_name: class_name.to_string(),
+                source: ClassLoadingError::Unknown,
+            });
+        };
+        let mut methods = class.methods.iter().enumerate().filter(|(_, method)| {
+            method.name == name
+                && match &descriptor {
+                    Some(descriptor) => method.descriptor == *descriptor,
+                    None => true,
+                }
+        });
+        let Some((method_index, method)) = methods.next() else {
+            return Err(VmError::UnknownMethod {
+                class_name: class_name.to_string(),
+                name: name.to_string(),
+            });
+        };
+        if methods.next().is_some() {
+            return Err(VmError::AmbiguousMethod {
+                class_name: class_name.to_string(),
+                name: name.to_string(),
+            });
+        }
+        let Some(code) = method.get_code() else {
+            return Err(VmError::NoCode {
+                class_name: class_name.to_string(),
+                name: name.to_string(),
+            });
+        };
+        if code.cache.program().index_of(pc).is_none() {
+            return Err(VmError::InvalidPc {
+                class_name: class_name.to_string(),
+                name: name.to_string(),
+                pc,
+            });
+        }
+        let breakpoint = Breakpoint {
+            class: class.id,
+            method: method_index,
+            pc,
+        };
+        self.class_manager.breakpoints.insert(breakpoint);
+        Ok(breakpoint)
+    }
+
+    /// Remove a breakpoint, returns false if it was not set.
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        self.class_manager.breakpoints.remove(breakpoint)
+    }
+
+    /// Set the hook called when a thread reaches a breakpoint, the breakpoints
+    /// being logged by default.
+    pub fn set_breakpoint_hook(&mut self, hook: BreakpointHook) {
+        self.class_manager.breakpoints.set_hook(hook);
+    }
+
+    /// Register a hook called for each java/lang/ref/Reference object whose
+    /// referent is cleared by [Vm::collect_weak_refs].
+    ///
+    /// This is the extension point for enqueuing references in their ReferenceQueue.
+    pub fn add_reference_cleared_hook(&mut self, hook: ReferenceClearedHook) {
+        self.class_manager.weak_refs.add_cleared_hook(hook);
+    }
+
+    /// Clear the weak references whose referent is no longer reachable.
+    ///
+    /// The roots are the thread stacks and mirrors, the static fields, the class
+    /// objects and the string constants of the loaded classes.
+    /// Returns the number of cleared weak references.
+    pub fn collect_weak_refs(&mut self) -> usize {
+        let mut roots = Vec::new();
+        for (_, thread) in self.thread_manager.iter() {
+            roots.extend(thread.mirror.clone().map(Slot::ObjectReference));
+            for frame in thread.stack.iter() {
+                roots.extend(frame.local_variables.iter().cloned());
+                roots.extend(frame.operand_stack.iter().cloned());
+                roots.extend(frame.synchronized_on.clone().map(Slot::ObjectReference));
+                roots.extend(frame.monitors.iter().cloned().map(Slot::ObjectReference));
+            }
+        }
+        for class in self.class_manager.classes_by_id.values() {
+            let LoadedClass::Loaded(class) = class else {
+                continue;
+            };
+            roots.extend(class.fields.iter().map(|field| field.value.clone()));
+            roots.extend(class.class_object.get().cloned().map(Slot::ObjectReference));
+        }
+        roots.extend(
+            self.class_manager
+                .string_constants
+                .values()
+                .cloned()
+                .map(Slot::ObjectReference),
+        );
+
+        let mut reachability = Reachability::new();
+        reachability.mark(&self.class_manager, roots.iter());
+        let cleared = weak::sweep(&mut self.class_manager, &reachability);
+        let live = self.class_manager.weak_refs.len();
+        self.class_manager
+            .events
+            .emit(&VmEvent::WeakRefsCollected { cleared, live });
+        cleared
+    }
+}
+
+/// Builder of a [Vm], to configure it before the bootstrap classes are loaded.
+#[derive(Debug)]
+pub struct VmBuilder {
+    class_loader: ClassLoader,
+    version_policy: ClassVersionPolicy,
+    heap_budget: HeapBudget,
+    gc_stress: bool,
+    deterministic_seed: Option<u64>,
+    event_sink: Option<Box<dyn EventSink>>,
+    streams: StandardStreams,
+    opcode_histogram: bool,
+    call_log: Option<CallFilter>,
+    assertions: AssertionStatus,
+}
+
+impl VmBuilder {
+    pub fn new(class_loader: ClassLoader) -> Self {
+        Self {
+            class_loader,
+            version_policy: ClassVersionPolicy::default(),
+            heap_budget: HeapBudget::unlimited(),
+            gc_stress: false,
+            deterministic_seed: None,
+            event_sink: None,
+            streams: StandardStreams::default(),
+            opcode_histogram: false,
+            call_log: None,
+            assertions: AssertionStatus::new(),
+        }
+    }
+
+    /// Set the newest class file major version accepted by the VM.
+    pub fn max_class_version(mut self, major: u16) -> Self {
+        self.version_policy.max_major = major;
+        self
+    }
+
+    /// Only accept the class files of the given Java release and older (e.g. 17).
+    pub fn release_compat(mut self, release: u16) -> Self {
+        self.version_policy.max_major = ClassVersionPolicy::for_release(release).max_major;
+        self
+    }
+
+    /// Accept the class files depending on the preview features of the newest accepted release.
+    pub fn enable_preview(mut self, enable: bool) -> Self {
+        self.version_policy.enable_preview = enable;
+        self
+    }
+
+    /// Limit the memory used by the arrays to `max_bytes`, further allocations
+    /// failing with an OutOfMemory error.
+    pub fn max_heap(mut self, max_bytes: usize) -> Self {
+        self.heap_budget = HeapBudget::new(max_bytes);
+        self
+    }
+
+    /// Collect the garbage after every allocation, to find the references
+    /// the VM holds out of the roots of the heap (see [crate::alloc::roots]).
+    ///
+    /// Very slow.
+    pub fn gc_stress(mut self, enable: bool) -> Self {
+        self.gc_stress = enable;
+        self
+    }
+
+    /// Make the runs reproducible: the identity hash codes are drawn from a
+    /// sequence seeded with `seed`, and the time is simulated (see [Clock::fixed]).
+    pub fn deterministic(mut self, seed: u64) -> Self {
+        self.deterministic_seed = Some(seed);
+        self
+    }
+
+    /// Send the events of the VM to the given sink, instead of logging them.
+    ///
+    /// The sink also receives the events of the bootstrap classes loading.
+    pub fn event_sink(mut self, sink: Box<dyn EventSink>) -> Self {
+        self.event_sink = Some(sink);
+        self
+    }
+
+    /// Count the executions of each instruction, see [Vm::opcode_histogram].
+    ///
+    /// Disabled by default, as it slows the interpretation down.
+    pub fn opcode_histogram(mut self, enable: bool) -> Self {
+        self.opcode_histogram = enable;
+        self
+    }
+
+    /// Log the entries and exits of the methods of the classes selected by the
+    /// filter, see [crate::call_log].
+    pub fn log_calls(mut self, filter: CallFilter) -> Self {
+        self.call_log = Some(filter);
+        self
+    }
+
+    /// Redirect the standard input of the programs, the one of the process by default.
+    pub fn stdin(mut self, stdin: Box<dyn Read + Send>) -> Self {
+        self.streams.stdin = stdin;
+        self
+    }
+
+    /// Redirect the standard output of the programs, the one of the process by default.
+    ///
+    /// It is flushed after each write of the program.
+    pub fn stdout(mut self, stdout: Box<dyn Write + Send>) -> Self {
+        self.streams.stdout = stdout;
+        self
+    }
+
+    /// Set the classes whose assertions are enabled, see the `-ea` and `-da`
+    /// options of java. They are all disabled by default.
+    pub fn assertions(mut self, assertions: AssertionStatus) -> Self {
+        self.assertions = assertions;
+        self
+    }
+
+    /// Redirect the standard error of the programs, the one of the process by default.
+    pub fn stderr(mut self, stderr: Box<dyn Write + Send>) -> Self {
+        self.streams.stderr = stderr;
+        self
+    }
+
+    pub fn build(self) -> Vm {
+        let mut class_manager =
+            ClassManager::without_preloading(self.class_loader, self.version_policy);
+        class_manager.heap_budget = self.heap_budget;
+        if self.gc_stress {
+            class_manager.collector = Collector::stress();
+        }
+        if let Some(seed) = self.deterministic_seed {
+            class_manager.identity_hashes = IdentityHashGenerator::seeded(seed);
+            class_manager.clock = Clock::fixed();
+        }
+        if let Some(sink) = self.event_sink {
+            class_manager.events = sink;
+        }
+        class_manager.files = FileTable::with_streams(self.streams);
+        class_manager.assertions = self.assertions;
+        class_manager.call_log = self.call_log;
+        if self.opcode_histogram {
+            class_manager.opcode_histogram = Some(OpcodeHistogram::new());
+        }
+        class_manager.preload();
+        Vm {
+            class_manager,
+            thread_manager: ThreadManager::new(),
+            dump_request: Arc::new(AtomicBool::new(false)),
+            dump_hook: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::test_util::test_vm;
+
+    #[test]
+    fn thread_dumps() {
+        let mut vm = test_vm();
+        let dumps = Arc::new(Mutex::new(Vec::new()));
+        let hook_dumps = dumps.clone();
+        vm.set_thread_dump_hook(Box::new(move |dump| {
+            hook_dumps.lock().unwrap().push(dump.clone())
+        }));
+        vm.dump_request().store(true, Ordering::Relaxed);
+        let args = vec![Slot::Int(3), Slot::Int(4)];
+        let result = vm.invoke_static("Stepping", "sumOfSquares", "(II)I", args);
+        assert!(matches!(result, Ok(Some(Slot::Int(25)))), "{:?}", result);
+        assert!(!vm.dump_request().load(Ordering::Relaxed));
+        assert!(vm.thread_dump().threads.is_empty());
+        let dumps = dumps.lock().unwrap();
+        assert_eq!(dumps.len(), 1);
+        assert_eq!(dumps[0].threads.len(), 1);
+        let frame = &dumps[0].threads[0].frames[0];
+        assert_eq!(frame.class_name, "Stepping");
+        assert_eq!(frame.method_name, "sumOfSquares");
+        assert_eq!((frame.pc, frame.line), (0, Some(8)));
+
+        // Stop in the first invocation of square.
+        let class = vm
+            .class_manager()
+            .get_class_by_name("Stepping")
+            .unwrap()
+            .id();
+        let Some(LoadedClass::Loaded(stepping)) = vm.class_manager().get_class_by_id(class) else {
+            panic!("Stepping is not loaded");
+        };
+        let method = stepping
+            .methods
+            .iter()
+            .position(|m| m.name == "sumOfSquares");
+        let args = vec![Slot::Int(3), Slot::Int(4)];
+        let thread = vm.create_thread(class, method.unwrap(), args).unwrap();
+        for _ in 0..2 {
+            assert!(!vm.step_thread(thread, Step::Instruction).unwrap());
+        }
+        let dump = vm.thread_dump();
+        let frames = &dump.threads[0].frames;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            (frames[0].method_name.as_str(), frames[0].pc),
+            ("square", 0)
+        );
+        assert_eq!(
+            (frames[1].method_name.as_str(), frames[1].pc),
+            ("sumOfSquares", 1)
+        );
+        let text = dump.to_string();
+        assert!(text.starts_with("Full thread dump:"), "{}", text);
+        assert!(
+            text.contains("\tat Stepping.square(I)I (pc 0, line 4)"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("\tat Stepping.sumOfSquares(II)I (pc 1, line 8)"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn unknown_threads() {
+        let mut vm = test_vm();
+        let args = vec![Slot::Int(3), Slot::Int(4)];
+        vm.invoke_static("Stepping", "sumOfSquares", "(II)I", args)
+            .unwrap();
+        // The thread of the invocation has been stopped.
+        assert!(matches!(
+            vm.execute_thread(0),
+            Err(ExecutionError::ThreadNotFound { thread_id: 0 })
+        ));
+        assert!(matches!(
+            vm.step_thread(42, Step::Instruction),
+            Err(ExecutionError::ThreadNotFound { thread_id: 42 })
+        ));
+    }
+
+    #[test]
+    fn main_methods() {
+        let mut vm = test_vm();
+        let args = ["hello".to_string(), "world".to_string()];
+        assert!(matches!(vm.run_main("MainMethods", &args), Ok(0)));
+        assert!(matches!(vm.run_main("MainMethods$Legacy", &args), Ok(0)));
+        assert!(matches!(
+            vm.run_main("MainMethods$NoMain", &[]),
+            Err(VmError::MethodNotFound { .. })
+        ));
+        assert!(matches!(
+            vm.run_main("Missing", &[]),
+            Err(VmError::ClassLoading { .. })
+        ));
+
+        let int = |class_name, field_name| {
+            let Some(LoadedClass::Loaded(class)) = vm.class_manager().get_class_by_name(class_name)
+            else {
+                panic!("{} not loaded", class_name);
+            };
+            match class
+                .get_field(field_name)
+                .and_then(|field| field.get_value())
+            {
+                Some(Slot::Int(value)) => *value,
+                _ => panic!("{} is not an int", field_name),
+            }
+        };
+        assert_eq!(int("MainMethods", "argCount"), 2);
+        assert_eq!(int("MainMethods", "firstLength"), 5);
+        assert_eq!(int("MainMethods$Legacy", "runs"), 1);
     }
 }
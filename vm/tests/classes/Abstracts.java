@@ -0,0 +1,18 @@
+// Instantiation and invocation through an interface, the implementation being
+// made abstract by the tests.
+public class Abstracts {
+    interface Shape {
+        int area();
+    }
+
+    static class Square implements Shape {
+        public int area() {
+            return 4;
+        }
+    }
+
+    public static int area() {
+        Shape shape = new Square();
+        return shape.area();
+    }
+}
@@ -0,0 +1,69 @@
+// Getters and setters, run without a frame by the invoke instructions.
+public class Accessors {
+    private int value;
+    private long big;
+    private final String name = "final";
+
+    int getValue() {
+        return value;
+    }
+
+    void setValue(int value) {
+        this.value = value;
+    }
+
+    long getBig() {
+        return big;
+    }
+
+    void setBig(long big) {
+        this.big = big;
+    }
+
+    String getName() {
+        return name;
+    }
+
+    static class Sub extends Accessors {}
+
+    static int run() {
+        Accessors accessors = new Accessors();
+        accessors.setValue(5);
+        accessors.setBig(7L);
+        int length = accessors.getName().length();
+        return accessors.getValue() * 100 + (int) accessors.getBig() * 10 + length;
+    }
+
+    // The inline cache of getValue holds the field for one class of receivers.
+    static int receivers() {
+        Accessors accessors = new Accessors();
+        Accessors sub = new Sub();
+        accessors.setValue(3);
+        sub.setValue(4);
+        return accessors.getValue() + accessors.getValue() * 10 + sub.getValue() * 100;
+    }
+
+    static int monomorphic(int count) {
+        Accessors accessors = new Accessors();
+        accessors.setValue(1);
+        int sum = 0;
+        for (int i = 0; i < count; i += 2) {
+            sum += accessors.getValue();
+            sum += accessors.getValue();
+        }
+        return sum;
+    }
+
+    static int polymorphic(int count) {
+        Accessors accessors = new Accessors();
+        Accessors sub = new Sub();
+        accessors.setValue(1);
+        sub.setValue(1);
+        int sum = 0;
+        for (int i = 0; i < count; i += 2) {
+            sum += accessors.getValue();
+            sum += sub.getValue();
+        }
+        return sum;
+    }
+}
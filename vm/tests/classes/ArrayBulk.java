@@ -0,0 +1,50 @@
+import java.util.Arrays;
+
+public class ArrayBulk {
+    static int filled;
+    static int ranged;
+    static int copied;
+    static int padded;
+    static int sliced;
+    static int equal;
+    static int nans;
+    static int bytes;
+
+    static {
+        int[] ints = new int[5];
+        Arrays.fill(ints, 3);
+        filled = ints[0] + ints[4];
+        Arrays.fill(ints, 1, 3, 7);
+        ranged = ints[0] * 100 + ints[1] * 10 + ints[3];
+
+        int[] copy = Arrays.copyOf(ints, 3);
+        copied = copy.length * 10 + copy[2];
+        int[] longer = Arrays.copyOf(ints, 7);
+        padded = longer.length * 10 + longer[6];
+        int[] slice = Arrays.copyOfRange(ints, 2, 6);
+        sliced = slice.length * 100 + slice[0] * 10 + slice[3];
+
+        equal = (Arrays.equals(ints, Arrays.copyOf(ints, 5)) ? 1 : 0)
+            + (Arrays.equals(ints, copy) ? 10 : 0)
+            + (Arrays.equals((int[]) null, null) ? 100 : 0)
+            + (Arrays.equals(ints, null) ? 1000 : 0);
+
+        double[] a = new double[2];
+        double[] b = new double[2];
+        Arrays.fill(a, 0.0 / 0.0);
+        Arrays.fill(b, 0.0 / 0.0);
+        nans = Arrays.equals(a, b) ? 1 : 0;
+
+        byte[] raw = new byte[3];
+        Arrays.fill(raw, (byte) -2);
+        bytes = raw[0] + raw[2];
+    }
+
+    static int[] reversed(int[] values) {
+        int[] reversed = new int[values.length];
+        for (int i = 0; i < values.length; i++) {
+            reversed[values.length - 1 - i] = values[i];
+        }
+        return reversed;
+    }
+}
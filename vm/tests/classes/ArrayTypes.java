@@ -0,0 +1,26 @@
+// instanceof and checkcast on arrays of arrays and on interface components.
+public class ArrayTypes {
+    static boolean stringsAreComparable() {
+        Object strings = new String[1];
+        return strings instanceof Comparable[] && !(strings instanceof Integer[]);
+    }
+
+    static boolean nestedArrays() {
+        String[][] rows = new String[2][];
+        rows[0] = new String[1];
+        Object matrix = rows;
+        return matrix instanceof Comparable[][]
+            && matrix instanceof Object[]
+            && !(matrix instanceof Comparable[])
+            && ((Object[]) matrix)[0] instanceof Comparable[];
+    }
+
+    static boolean primitiveArrays() {
+        int[][] rows = new int[2][];
+        rows[0] = new int[1];
+        Object ints = rows;
+        return ints instanceof Object[]
+            && !(ints instanceof long[][])
+            && !(((Object[]) ints)[0] instanceof Object[]);
+    }
+}
@@ -0,0 +1,8 @@
+// Assert statements, checked only if the assertions of the class are enabled.
+public class Assertions {
+    static boolean enabled() {
+        boolean enabled = false;
+        assert enabled = true;
+        return enabled;
+    }
+}
@@ -0,0 +1,6 @@
+// Class constants, of a class failing to load.
+public class ClassConstants {
+    static Object broken() {
+        return FinalChild.class;
+    }
+}
@@ -0,0 +1,3 @@
+// Implements ClassInterface, which has become a class, failing with an
+// IncompatibleClassChangeError.
+public class ClassImplementor implements ClassInterface {}
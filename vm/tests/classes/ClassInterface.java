@@ -0,0 +1,3 @@
+// Compiled as an interface first, ClassImplementor implementing it, then
+// recompiled as this class.
+public class ClassInterface {}
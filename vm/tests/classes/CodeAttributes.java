@@ -0,0 +1,31 @@
+// Compiled with -g, for the LocalVariableTable and LocalVariableTypeTable.
+public class CodeAttributes {
+    static int sum(int[] values) {
+        int total = 0;
+        for (int value : values) {
+            total += value;
+        }
+        return total;
+    }
+
+    static <T> T first(T[] values) {
+        T value = values[0];
+        return value;
+    }
+
+    static int cleanups;
+
+    static int guarded(int[] values, int index) {
+        try {
+            try {
+                return values[index];
+            } catch (Error e) {
+                return -1;
+            }
+        } catch (Exception e) {
+            return -2;
+        } finally {
+            cleanups++;
+        }
+    }
+}
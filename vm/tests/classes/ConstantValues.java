@@ -0,0 +1,14 @@
+public class ConstantValues {
+    static final String GREETING = "hello";
+    static final boolean FLAG = true;
+    static final char LETTER = 'x';
+    static final byte SMALL = -3;
+    static final short MEDIUM = 1234;
+    static final long BIG = 1L << 40;
+
+    static boolean same;
+
+    static {
+        same = GREETING == "hello";
+    }
+}
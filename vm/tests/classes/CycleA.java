@@ -0,0 +1,4 @@
+// Static initializers depending on each other, see CycleB.
+public class CycleA {
+    static int a = CycleB.b + 1;
+}
@@ -0,0 +1,3 @@
+public class CycleB {
+    static int b = CycleA.a + 10;
+}
@@ -0,0 +1,19 @@
+public class Determinism {
+    static int first;
+    static int second;
+    static int stable;
+    static int arrayHash;
+    static int elapsed;
+
+    static {
+        Object a = new Object();
+        Object b = new Object();
+        first = System.identityHashCode(a);
+        second = System.identityHashCode(b);
+        stable = a.hashCode() == first && System.identityHashCode(null) == 0 ? 1 : 0;
+        arrayHash = System.identityHashCode(new int[4]);
+        long start = System.nanoTime();
+        long end = System.nanoTime();
+        elapsed = (int) (end - start);
+    }
+}
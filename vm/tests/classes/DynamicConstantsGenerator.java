@@ -0,0 +1,78 @@
+import java.nio.file.Files;
+import java.nio.file.Path;
+
+import jdk.internal.org.objectweb.asm.ClassWriter;
+import jdk.internal.org.objectweb.asm.ConstantDynamic;
+import jdk.internal.org.objectweb.asm.Handle;
+import jdk.internal.org.objectweb.asm.MethodVisitor;
+import jdk.internal.org.objectweb.asm.Opcodes;
+
+// Generates DynamicConstants.class, javac never emitting CONSTANT_Dynamic entries:
+//   javac --add-exports java.base/jdk.internal.org.objectweb.asm=ALL-UNNAMED -d /tmp DynamicConstantsGenerator.java
+//   java --add-exports java.base/jdk.internal.org.objectweb.asm=ALL-UNNAMED -cp /tmp DynamicConstantsGenerator
+//
+// public class DynamicConstants {
+//     static int calls;
+//     static int answer(Object lookup, String name, Object type, int half) { calls++; return half * 2; }
+//     static long wide(Object lookup, String name, Object type) { return 1L << 40; }
+//     static int answer() { return ldc Dynamic answer:I [answer, 21]; }
+//     static long wide() { return ldc2_w Dynamic wide:J [wide]; }
+// }
+public class DynamicConstantsGenerator {
+    private static final String CLASS = "DynamicConstants";
+
+    public static void main(String[] args) throws Exception {
+        ClassWriter cw = new ClassWriter(ClassWriter.COMPUTE_FRAMES | ClassWriter.COMPUTE_MAXS);
+        cw.visit(Opcodes.V17, Opcodes.ACC_PUBLIC | Opcodes.ACC_SUPER, CLASS, null, "java/lang/Object", null);
+        cw.visitField(Opcodes.ACC_STATIC, "calls", "I", null, null).visitEnd();
+
+        MethodVisitor init = cw.visitMethod(Opcodes.ACC_PUBLIC, "<init>", "()V", null, null);
+        init.visitCode();
+        init.visitVarInsn(Opcodes.ALOAD, 0);
+        init.visitMethodInsn(Opcodes.INVOKESPECIAL, "java/lang/Object", "<init>", "()V", false);
+        init.visitInsn(Opcodes.RETURN);
+        init.visitMaxs(0, 0);
+        init.visitEnd();
+
+        String answerDescriptor = "(Ljava/lang/Object;Ljava/lang/String;Ljava/lang/Object;I)I";
+        MethodVisitor bsm = cw.visitMethod(Opcodes.ACC_STATIC, "answer", answerDescriptor, null, null);
+        bsm.visitCode();
+        bsm.visitFieldInsn(Opcodes.GETSTATIC, CLASS, "calls", "I");
+        bsm.visitInsn(Opcodes.ICONST_1);
+        bsm.visitInsn(Opcodes.IADD);
+        bsm.visitFieldInsn(Opcodes.PUTSTATIC, CLASS, "calls", "I");
+        bsm.visitVarInsn(Opcodes.ILOAD, 3);
+        bsm.visitInsn(Opcodes.ICONST_2);
+        bsm.visitInsn(Opcodes.IMUL);
+        bsm.visitInsn(Opcodes.IRETURN);
+        bsm.visitMaxs(0, 0);
+        bsm.visitEnd();
+
+        String wideDescriptor = "(Ljava/lang/Object;Ljava/lang/String;Ljava/lang/Object;)J";
+        MethodVisitor wide = cw.visitMethod(Opcodes.ACC_STATIC, "wide", wideDescriptor, null, null);
+        wide.visitCode();
+        wide.visitLdcInsn(1L << 40);
+        wide.visitInsn(Opcodes.LRETURN);
+        wide.visitMaxs(0, 0);
+        wide.visitEnd();
+
+        Handle answerHandle = new Handle(Opcodes.H_INVOKESTATIC, CLASS, "answer", answerDescriptor, false);
+        MethodVisitor answer = cw.visitMethod(Opcodes.ACC_STATIC, "answer", "()I", null, null);
+        answer.visitCode();
+        answer.visitLdcInsn(new ConstantDynamic("answer", "I", answerHandle, 21));
+        answer.visitInsn(Opcodes.IRETURN);
+        answer.visitMaxs(0, 0);
+        answer.visitEnd();
+
+        Handle wideHandle = new Handle(Opcodes.H_INVOKESTATIC, CLASS, "wide", wideDescriptor, false);
+        MethodVisitor getWide = cw.visitMethod(Opcodes.ACC_STATIC, "wide", "()J", null, null);
+        getWide.visitCode();
+        getWide.visitLdcInsn(new ConstantDynamic("wide", "J", wideHandle));
+        getWide.visitInsn(Opcodes.LRETURN);
+        getWide.visitMaxs(0, 0);
+        getWide.visitEnd();
+
+        cw.visitEnd();
+        Files.write(Path.of(CLASS + ".class"), cw.toByteArray());
+    }
+}
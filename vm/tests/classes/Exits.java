@@ -0,0 +1,19 @@
+// Exit requests, from a method and from a class initializer.
+public class Exits {
+    public static void main(String[] args) {
+        System.exit(args.length * 10 + args[0].length());
+    }
+
+    public static int exit(int status) {
+        System.exit(status);
+        return -1;
+    }
+
+    static class Initializer {
+        static int value = exit(3);
+    }
+
+    public static int initializer() {
+        return Initializer.value;
+    }
+}
@@ -0,0 +1,41 @@
+import java.io.FileInputStream;
+import java.io.FileOutputStream;
+
+// Files written and read back through the java.io natives.
+public class FileIo {
+    // Returns the sum of the checks passed.
+    public static int roundTrip(String path) {
+        FileOutputStream out = new FileOutputStream(path, false);
+        byte[] bytes = {1, 2, 3, 4, 5, 6};
+        out.write(bytes, 0, 6);
+        out.write(7);
+        out.close();
+        FileOutputStream append = new FileOutputStream(path, true);
+        append.write(8);
+        append.close();
+
+        FileInputStream in = new FileInputStream(path);
+        int checks = in.available() == 8 ? 1 : 0;
+        checks += in.read() == 1 ? 10 : 0;
+        checks += in.skip(2) == 2 ? 100 : 0;
+        byte[] buffer = new byte[8];
+        checks += in.read(buffer, 1, 8 - 1) == 5 && buffer[1] == 4 && buffer[5] == 8 ? 1000 : 0;
+        checks += in.read() == -1 && in.position() == 8 ? 10000 : 0;
+        in.close();
+        return checks;
+    }
+
+    public static void openMissing(String path) {
+        new FileInputStream(path);
+    }
+
+    public static int readClosed(String path) {
+        FileInputStream in = new FileInputStream(path);
+        in.close();
+        return in.read();
+    }
+
+    public static long seekBeforeStart(String path) {
+        return new FileInputStream(path).skip(-1);
+    }
+}
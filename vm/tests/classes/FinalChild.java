@@ -0,0 +1,2 @@
+// Extends FinalParent, which has become final, failing with a VerifyError.
+public class FinalChild extends FinalParent {}
@@ -0,0 +1,33 @@
+// Final fields set by the constructors, on instances of subclasses.
+public class FinalFields {
+    static class Base {
+        final int value;
+        int counter;
+
+        Base(int value) {
+            this.value = value;
+        }
+
+        void bump() {
+            counter++;
+        }
+    }
+
+    static class Derived extends Base {
+        Derived() {
+            super(7);
+            counter = 3;
+        }
+    }
+
+    static int derived() {
+        Derived derived = new Derived();
+        return derived.value * 10 + derived.counter;
+    }
+
+    static int bumped() {
+        Base base = new Base(1);
+        base.bump();
+        return base.counter;
+    }
+}
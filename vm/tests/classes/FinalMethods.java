@@ -0,0 +1,11 @@
+// Compiled without the final modifiers first, FinalOverride and HiddenOverride
+// extending it, then recompiled with them.
+public class FinalMethods {
+    public final int locked() {
+        return 1;
+    }
+
+    private final int hidden() {
+        return 1;
+    }
+}
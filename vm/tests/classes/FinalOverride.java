@@ -0,0 +1,6 @@
+// Overrides FinalMethods.locked, which has become final, failing with a VerifyError.
+public class FinalOverride extends FinalMethods {
+    public int locked() {
+        return 2;
+    }
+}
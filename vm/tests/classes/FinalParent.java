@@ -0,0 +1,3 @@
+// Compiled without the final modifier first, FinalChild extending it, then
+// recompiled as this final class.
+public final class FinalParent {}
@@ -0,0 +1,45 @@
+public class GcStress {
+    static class Node {
+        Node next;
+        int value;
+
+        Node(int value) {
+            this.value = value;
+        }
+    }
+
+    static Node ring;
+
+    // A ring only reachable from a static field, and garbage cycles
+    static int run() {
+        Node first = new Node(0);
+        Node last = first;
+        for (int i = 1; i < 100; i++) {
+            Node node = new Node(i);
+            last.next = node;
+            last = node;
+        }
+        last.next = first;
+        ring = first;
+        for (int i = 0; i < 50; i++) {
+            Node a = new Node(i);
+            Node b = new Node(i);
+            a.next = b;
+            b.next = a;
+        }
+        int[][] arrays = new int[10][];
+        for (int i = 0; i < arrays.length; i++) {
+            arrays[i] = new int[] { i };
+        }
+        int sum = 0;
+        Node node = ring;
+        do {
+            sum += node.value;
+            node = node.next;
+        } while (node != ring);
+        for (int[] array : arrays) {
+            sum += array[0];
+        }
+        return sum;
+    }
+}
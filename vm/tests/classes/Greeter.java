@@ -0,0 +1,16 @@
+// Interface with static, private and default methods (Java 9+).
+public interface Greeter {
+    static int twice(int value) {
+        return value * 2;
+    }
+
+    private int offset() {
+        return 100;
+    }
+
+    default int greet() {
+        return offset() + name();
+    }
+
+    int name();
+}
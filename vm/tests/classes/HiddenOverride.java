@@ -0,0 +1,9 @@
+// Declares the same method as the private final FinalMethods.hidden, which is
+// not an override.
+public class HiddenOverride extends FinalMethods {
+    static int value = new HiddenOverride().hidden();
+
+    int hidden() {
+        return 2;
+    }
+}
@@ -0,0 +1,5 @@
+// Invocation of a static method of a class that has become an interface,
+// failing with an IncompatibleClassChangeError.
+public class IncompatibleCalls {
+    static int value = IncompatibleTarget.value();
+}
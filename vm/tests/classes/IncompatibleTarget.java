@@ -0,0 +1,7 @@
+// Compiled as a class first, IncompatibleCalls referencing it with a Methodref,
+// then recompiled as this interface.
+public interface IncompatibleTarget {
+    static int value() {
+        return 1;
+    }
+}
@@ -0,0 +1,23 @@
+interface FieldSource {
+    Object MARK = new Object();
+}
+
+class FieldBase {
+    static int total = 3;
+    int value = 5;
+}
+
+public class InheritedFields extends FieldBase implements FieldSource {
+    long value = 7;
+
+    public static int read() {
+        InheritedFields fields = new InheritedFields();
+        InheritedFields.total += 1;
+        int result = ((FieldBase) fields).value * 10 + (int) fields.value;
+        result += InheritedFields.total * 100;
+        if (InheritedFields.MARK != null) {
+            result += 1000;
+        }
+        return result;
+    }
+}
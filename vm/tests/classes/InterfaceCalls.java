@@ -0,0 +1,6 @@
+// Invocations of interface methods, run by the static initializer.
+public class InterfaceCalls {
+    static int staticCall = Greeter.twice(21);
+    static int virtualCall = ((Greeter) new LoudGreeter()).name();
+    static int defaultCall = new LoudGreeter().greet();
+}
@@ -0,0 +1,3 @@
+// Extends InterfaceParent, which has become an interface, failing with an
+// IncompatibleClassChangeError.
+public class InterfaceChild extends InterfaceParent {}
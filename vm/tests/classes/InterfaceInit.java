@@ -0,0 +1,48 @@
+// Initialization of the interfaces (JVMS 5.5), each initializer appending its digit to the trace.
+public class InterfaceInit {
+    static int trace;
+
+    static int record(int digit) {
+        trace = trace * 10 + digit;
+        return digit;
+    }
+
+    interface Constants {
+        int FIRST = record(1);
+    }
+
+    interface Defaults extends Constants {
+        int SECOND = record(2);
+
+        default int answer() {
+            return 42;
+        }
+    }
+
+    interface Plain extends Constants {
+        int THIRD = record(3);
+
+        int get();
+    }
+
+    static class Impl implements Plain, Defaults {
+        static int fourth = record(4);
+
+        public int get() {
+            return 0;
+        }
+    }
+
+    static int afterImpl;
+    static int afterPlain;
+    static int afterInherited;
+
+    static {
+        new Impl();
+        afterImpl = trace;
+        int third = Plain.THIRD;
+        afterPlain = trace;
+        int first = Defaults.FIRST;
+        afterInherited = trace;
+    }
+}
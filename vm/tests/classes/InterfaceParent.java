@@ -0,0 +1,3 @@
+// Compiled as a class first, InterfaceChild extending it, then recompiled as
+// this interface.
+public interface InterfaceParent {}
@@ -0,0 +1,54 @@
+// Lambdas and method references, linked by the lambda metafactory.
+public class Lambdas {
+    interface IntOp {
+        int apply(int a, int b);
+    }
+
+    interface Getter<T> {
+        T get();
+    }
+
+    interface Transformer<T, R> {
+        R apply(T value);
+    }
+
+    static int sum;
+    static int captured;
+    static int instance;
+    static int bound;
+    static int constructed;
+    static int boxed;
+    static int adapted;
+
+    private int base = 40;
+
+    static {
+        IntOp add = (a, b) -> a + b;
+        sum = add.apply(3, 4);
+        int offset = 10;
+        IntOp shifted = (a, b) -> a + b + offset;
+        captured = shifted.apply(1, 2);
+        Lambdas lambdas = new Lambdas();
+        instance = lambdas.withBase().apply(1, 1);
+        Getter<Integer> getter = lambdas::base;
+        bound = getter.get();
+        Getter<Lambdas> maker = Lambdas::new;
+        constructed = maker.get().base + 2;
+        Transformer<Integer, Integer> twice = x -> x * 2;
+        boxed = twice.apply(21);
+        Transformer<Integer, Integer> negate = Lambdas::negate;
+        adapted = negate.apply(5);
+    }
+
+    IntOp withBase() {
+        return (a, b) -> base + a + b;
+    }
+
+    Integer base() {
+        return base;
+    }
+
+    static int negate(int value) {
+        return -value;
+    }
+}
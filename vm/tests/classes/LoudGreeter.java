@@ -0,0 +1,5 @@
+public class LoudGreeter implements Greeter {
+    public int name() {
+        return 7;
+    }
+}
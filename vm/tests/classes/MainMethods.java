@@ -0,0 +1,24 @@
+// Entry points for Vm::run_main: the standard main, and the legacy one in Legacy.
+public class MainMethods {
+    static int argCount = -1;
+    static int firstLength = -1;
+
+    public static void main(String[] args) {
+        argCount = args.length;
+        if (args.length > 0) {
+            firstLength = args[0].length();
+        }
+    }
+
+    static class Legacy {
+        static int runs;
+
+        static void main() {
+            runs++;
+        }
+    }
+
+    static class NoMain {
+        static void main(int value) {}
+    }
+}
@@ -0,0 +1,28 @@
+public class MathCalls {
+    static int sine;
+    static int cosine;
+    static int root;
+    static int floor;
+    static int ceil;
+    static int power;
+    static int absMin;
+    static int minLong;
+    static int maxInt;
+    static boolean negativeZero;
+    static boolean nan;
+
+    static {
+        sine = (int) (Math.sin(Math.PI / 2) * 1000);
+        cosine = (int) Math.cos(0.0);
+        root = (int) Math.sqrt(144.0);
+        floor = (int) Math.floor(-1.5);
+        ceil = (int) Math.ceil(-1.5);
+        power = (int) Math.pow(2.0, 10.0);
+        absMin = Math.abs(-2147483648);
+        minLong = (int) Math.min(-5L, 2L);
+        maxInt = Math.max(3, 7);
+        negativeZero = Math.pow(Math.min(0.0, -0.0), -1.0) < 0;
+        double max = Math.max(1.0, 0.0 / 0.0);
+        nan = max != max;
+    }
+}
@@ -0,0 +1,16 @@
+public class MemoryCalls {
+    static long max;
+    static long total;
+    static long freeBefore;
+    static long freeAfter;
+    static int[] kept;
+
+    static void measure(int size) {
+        Runtime runtime = Runtime.getRuntime();
+        max = runtime.maxMemory();
+        total = runtime.totalMemory();
+        freeBefore = runtime.freeMemory();
+        kept = new int[size];
+        freeAfter = runtime.freeMemory();
+    }
+}
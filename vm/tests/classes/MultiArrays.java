@@ -0,0 +1,18 @@
+// Multidimensional arrays, created by multianewarray.
+public class MultiArrays {
+    public static int grid(int rows, int columns) {
+        int[][] grid = new int[rows][columns];
+        grid[rows - 1][columns - 1] = 7;
+        return grid.length * 100 + grid[0].length * 10 + grid[rows - 1][columns - 1];
+    }
+
+    // Only the first two dimensions are created.
+    public static boolean partial() {
+        String[][][] cube = new String[2][3][];
+        return cube[1].length == 3 && cube[1][2] == null;
+    }
+
+    public static Object large(int count) {
+        return new long[count][count];
+    }
+}
@@ -0,0 +1,45 @@
+// Setters of byte and char fields, the tests removing the casts of the values
+// given to them.
+public class NarrowedAccessors {
+    private byte small;
+    private char letter;
+
+    byte getSmall() {
+        return small;
+    }
+
+    void setSmall(byte small) {
+        this.small = small;
+    }
+
+    // Not run without a frame.
+    synchronized void storeSmall(byte small) {
+        this.small = small;
+    }
+
+    char getLetter() {
+        return letter;
+    }
+
+    void setLetter(char letter) {
+        this.letter = letter;
+    }
+
+    static int narrowSmall(int value) {
+        NarrowedAccessors accessors = new NarrowedAccessors();
+        accessors.setSmall((byte) value);
+        return accessors.getSmall();
+    }
+
+    static int narrowStoredSmall(int value) {
+        NarrowedAccessors accessors = new NarrowedAccessors();
+        accessors.storeSmall((byte) value);
+        return accessors.getSmall();
+    }
+
+    static int narrowLetter(int value) {
+        NarrowedAccessors accessors = new NarrowedAccessors();
+        accessors.setLetter((char) value);
+        return accessors.getLetter();
+    }
+}
@@ -0,0 +1,37 @@
+// Nested, inner, local and anonymous classes, described by the InnerClasses
+// and EnclosingMethod attributes.
+public class Nesting {
+    static class Member {}
+
+    class Inner {}
+
+    private interface Hidden {}
+
+    static Object anonymous = new Object() {};
+
+    static int declaring;
+    static int declaredCount;
+    static int localDeclaring;
+    static int localEnclosing;
+    static int localName;
+    static int hiddenModifiers;
+    static int methodEnclosing;
+
+    static {
+        class Local {}
+        declaring = Member.class.getDeclaringClass() == Nesting.class ? 1 : 0;
+        declaredCount = Nesting.class.getDeclaredClasses().length;
+        localDeclaring = Local.class.getDeclaringClass() == null ? 1 : 0;
+        Object[] info = Local.class.getEnclosingMethodInfo();
+        localEnclosing = info[0] == Nesting.class && info[1] == null ? 1 : 0;
+        localName = Local.class.getSimpleBinaryName().equals("Local") ? 1 : 0;
+        hiddenModifiers = Hidden.class.getModifiers();
+        methodEnclosing = inMethod();
+    }
+
+    static int inMethod() {
+        class Counter {}
+        Object[] info = Counter.class.getEnclosingMethodInfo();
+        return "inMethod".equals(info[1]) && "()I".equals(info[2]) ? 1 : 0;
+    }
+}
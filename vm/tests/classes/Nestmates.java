@@ -0,0 +1,19 @@
+// Private members accessed by nestmates, without synthetic accessors (Java 11).
+public class Nestmates {
+    static int outer = new Inner().secret() + 1;
+    static int inner = Inner.call();
+
+    private int value() {
+        return 40;
+    }
+
+    static class Inner {
+        private int secret() {
+            return 41;
+        }
+
+        static int call() {
+            return new Nestmates().value() + 2;
+        }
+    }
+}
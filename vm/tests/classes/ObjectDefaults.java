@@ -0,0 +1,32 @@
+public class ObjectDefaults {
+    static class Plain {}
+
+    static class Hashed {
+        public int hashCode() {
+            return 0xbeef;
+        }
+    }
+
+    static boolean identityEquals() {
+        Object a = new Plain();
+        return a.equals(a) && !a.equals(new Plain()) && !a.equals(null);
+    }
+
+    static boolean identityHash() {
+        Object a = new Plain();
+        return a.hashCode() == System.identityHashCode(a) && a.hashCode() == a.hashCode();
+    }
+
+    static String hashed() {
+        return new Hashed().toString();
+    }
+
+    static String plain() {
+        return new Plain().toString();
+    }
+
+    static boolean interned() {
+        Object a = new Plain();
+        return a.toString() == a.toString();
+    }
+}
@@ -0,0 +1,6 @@
+// Compiled with -parameters, to keep the names of the method parameters.
+public class Parameters {
+    static int add(int left, final int right) {
+        return left + right;
+    }
+}
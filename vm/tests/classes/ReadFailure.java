@@ -0,0 +1,2 @@
+// Checked exception declared by Throws.
+public class ReadFailure extends Exception {}
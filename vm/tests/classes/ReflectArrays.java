@@ -0,0 +1,34 @@
+import java.lang.reflect.Array;
+
+public class ReflectArrays {
+    static int length;
+    static int stored;
+    static int boxed;
+    static int widened;
+    static int nested;
+    static int primitive;
+
+    static {
+        Object strings = Array.newInstance(String.class, 3);
+        length = Array.getLength(strings);
+        Array.set(strings, 1, "b");
+        stored = ((String[]) strings)[1] == "b" && Array.get(strings, 0) == null ? 1 : 0;
+
+        int[] ints = {4, 5};
+        boxed = ((Integer) Array.get(ints, 1)).intValue();
+
+        // An Integer is widened when stored into a long array.
+        long[] longs = new long[1];
+        Array.set(longs, 0, Integer.valueOf(7));
+        widened = (int) longs[0];
+
+        int[][] matrix = (int[][]) Array.newInstance(int[].class, 2);
+        matrix[1] = ints;
+        nested = Array.getLength(matrix) * 10 + Array.getLength(Array.get(matrix, 1));
+
+        // int.class is Integer.TYPE, the mirror of the primitive type.
+        int[] zeros = (int[]) Array.newInstance(int.class, 4);
+        boolean mirrors = int.class.isPrimitive() && !int[].class.isPrimitive();
+        primitive = mirrors && int.class.getModifiers() == 0x411 ? zeros.length : 0;
+    }
+}
@@ -0,0 +1,18 @@
+import jdk.internal.reflect.Reflection;
+
+// Class lookups by name and by caller.
+public class Reflective {
+    public static boolean forName() {
+        return Class.forName("Greeter") == Greeter.class;
+    }
+
+    public static boolean callerClass() {
+        return Callee.caller() == Reflective.class;
+    }
+
+    static class Callee {
+        static Class<?> caller() {
+            return Reflection.getCallerClass();
+        }
+    }
+}
@@ -0,0 +1,24 @@
+import java.io.InputStream;
+
+// Resources of the class path, read through a class and the system class loader.
+public class Resources {
+    // The sum of the bytes of the resource, or -1 if it is missing.
+    static int sum(InputStream stream) {
+        if (stream == null) {
+            return -1;
+        }
+        int sum = 0;
+        for (int b = stream.read(); b != -1; b = stream.read()) {
+            sum += b;
+        }
+        return sum;
+    }
+
+    static int classResource(String name) {
+        return sum(Resources.class.getResourceAsStream(name));
+    }
+
+    static int systemResource(String name) {
+        return sum(ClassLoader.getSystemResourceAsStream(name));
+    }
+}
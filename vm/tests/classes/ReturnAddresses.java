@@ -0,0 +1,23 @@
+// Invocations of different lengths, each resuming its caller after it.
+public class ReturnAddresses {
+    interface Op {
+        int apply(int x);
+    }
+
+    static class Inc implements Op {
+        public int apply(int x) {
+            return x + 1;
+        }
+    }
+
+    static int twice(int x) {
+        return x * 2;
+    }
+
+    static int run() {
+        Op op = new Inc();
+        int a = twice(5);
+        int b = op.apply(a);
+        return a * 100 + b;
+    }
+}
@@ -0,0 +1,24 @@
+public class RuntimeCalls implements Runnable {
+    static int processors;
+    static boolean memory;
+    static boolean removed;
+    static int hookRuns;
+
+    public void run() {
+        hookRuns++;
+    }
+
+    static void start() {
+        Runtime runtime = Runtime.getRuntime();
+        processors = runtime.availableProcessors();
+        memory = runtime.maxMemory() == 9223372036854775807L
+                && runtime.totalMemory() >= runtime.freeMemory();
+        Thread kept = new Thread(new RuntimeCalls());
+        Thread dropped = new Thread(new RuntimeCalls());
+        runtime.addShutdownHook(kept);
+        runtime.addShutdownHook(kept);
+        runtime.addShutdownHook(dropped);
+        removed = runtime.removeShutdownHook(dropped);
+        runtime.gc();
+    }
+}
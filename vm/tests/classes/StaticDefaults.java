@@ -0,0 +1,15 @@
+// Static fields without initializer, holding the default value of their type,
+// and constants given by a ConstantValue attribute.
+public class StaticDefaults {
+    static boolean flag;
+    static char letter;
+    static int integer;
+    static long wide;
+    static float single;
+    static double precise;
+    static Object reference;
+    static int[] array;
+
+    static final int CONSTANT = 7;
+    static final long WIDE_CONSTANT = 1L << 40;
+}
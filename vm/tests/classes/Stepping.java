@@ -0,0 +1,10 @@
+// Nested invocations, to walk through them step by step.
+public class Stepping {
+    static int square(int value) {
+        return value * value;
+    }
+
+    static int sumOfSquares(int a, int b) {
+        return square(a) + square(b);
+    }
+}
@@ -0,0 +1,27 @@
+public class StringCalls {
+    static int length;
+    static int letter;
+    static int hash;
+    static int cachedHash;
+    static boolean equal;
+    static boolean notEqual;
+    static int order;
+    static boolean prefix;
+    static int charIndex;
+    static int stringIndex;
+
+    static {
+        String hello = "hello";
+        String copy = new String(new char[] {'h', 'e', 'l', 'l', 'o'});
+        length = hello.length();
+        letter = hello.charAt(1);
+        hash = hello.hashCode();
+        cachedHash = hello.hashCode();
+        equal = hello.equals(copy);
+        notEqual = hello.equals("help") || hello.equals(null);
+        order = hello.compareTo("help");
+        prefix = hello.startsWith("he");
+        charIndex = hello.indexOf('l');
+        stringIndex = hello.indexOf("lo", 1);
+    }
+}
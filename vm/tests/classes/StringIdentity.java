@@ -0,0 +1,19 @@
+// Identity of the string constants, shared with StringIdentityOther.
+public class StringIdentity {
+    static int sameClass;
+    static int otherClass;
+    static int newObject;
+
+    static {
+        String a = "hello";
+        String b = "hello";
+        sameClass = a == b ? 1 : 0;
+        otherClass = a == StringIdentityOther.hello() ? 1 : 0;
+        newObject = a == new String(new char[] {'h'}) ? 1 : 0;
+    }
+
+    // Never called, its literal must not be materialized.
+    static String unused() {
+        return "unused";
+    }
+}
@@ -0,0 +1,6 @@
+// The same string constant as StringIdentity, in another class.
+public class StringIdentityOther {
+    static String hello() {
+        return "hello";
+    }
+}
@@ -0,0 +1,35 @@
+// Methods declared only by an indirect superinterface, or abstract in a superinterface.
+public class SuperinterfaceMethods {
+    interface Greeter {
+        default int greet() {
+            return 5;
+        }
+    }
+
+    interface LoudGreeter extends Greeter {}
+
+    interface Sized {
+        int size();
+    }
+
+    interface Measured extends Sized {}
+
+    static class Plain implements LoudGreeter {}
+
+    static abstract class Shape implements Measured {}
+
+    static class Square extends Shape {
+        public int size() {
+            return 4;
+        }
+    }
+
+    static int greet() {
+        return new Plain().greet();
+    }
+
+    static int size() {
+        Shape shape = new Square();
+        return shape.size();
+    }
+}
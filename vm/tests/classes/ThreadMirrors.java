@@ -0,0 +1,11 @@
+// The java/lang/Thread mirror of the current thread.
+public class ThreadMirrors {
+    static boolean sameMirror() {
+        return Thread.currentThread() == Thread.currentThread();
+    }
+
+    static String rename(String name) {
+        Thread.currentThread().setName(name);
+        return Thread.currentThread().getName();
+    }
+}
@@ -0,0 +1,37 @@
+public class Threads implements Runnable {
+    static int next = 1;
+    static int main;
+    static int low;
+    static int high;
+    static int daemon;
+
+    private final int kind;
+
+    Threads(int kind) {
+        this.kind = kind;
+    }
+
+    public void run() {
+        int rank = next++;
+        if (kind == 0) {
+            low = rank;
+        } else if (kind == 1) {
+            high = rank;
+        } else {
+            daemon = rank;
+        }
+    }
+
+    static void start() {
+        Thread low = new Thread(new Threads(0));
+        low.setPriority(Thread.MIN_PRIORITY);
+        low.start();
+        Thread daemon = new Thread(new Threads(2));
+        daemon.setDaemon(true);
+        daemon.start();
+        Thread high = new Thread(new Threads(1));
+        high.setPriority(Thread.MAX_PRIORITY);
+        high.start();
+        main = next++;
+    }
+}
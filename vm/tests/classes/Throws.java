@@ -0,0 +1,6 @@
+// Methods declaring checked exceptions.
+public class Throws {
+    static void read() throws ReadFailure, Exception {}
+
+    static void run() {}
+}
@@ -0,0 +1,15 @@
+import java.lang.ref.WeakReference;
+
+public class WeakRefs {
+    static Object kept = new Object();
+    static WeakReference<Object> keptRef = new WeakReference<Object>(kept);
+    static WeakReference<Object> droppedRef = new WeakReference<Object>(new Object());
+
+    public static boolean cleared() {
+        return droppedRef.get() == null;
+    }
+
+    public static boolean alive() {
+        return keptRef.get() == kept;
+    }
+}
@@ -0,0 +1,329 @@
+/**
+ * Loads constants with ldc_w: the 300 int constants of ints() come first, the
+ * constants of the following methods do not fit in a byte.
+ */
+public class WideConstants {
+    static String narrow() {
+        return "narrow";
+    }
+
+    static int ints() {
+        int sum = 0;
+        sum += 100000;
+        sum += 100001;
+        sum += 100002;
+        sum += 100003;
+        sum += 100004;
+        sum += 100005;
+        sum += 100006;
+        sum += 100007;
+        sum += 100008;
+        sum += 100009;
+        sum += 100010;
+        sum += 100011;
+        sum += 100012;
+        sum += 100013;
+        sum += 100014;
+        sum += 100015;
+        sum += 100016;
+        sum += 100017;
+        sum += 100018;
+        sum += 100019;
+        sum += 100020;
+        sum += 100021;
+        sum += 100022;
+        sum += 100023;
+        sum += 100024;
+        sum += 100025;
+        sum += 100026;
+        sum += 100027;
+        sum += 100028;
+        sum += 100029;
+        sum += 100030;
+        sum += 100031;
+        sum += 100032;
+        sum += 100033;
+        sum += 100034;
+        sum += 100035;
+        sum += 100036;
+        sum += 100037;
+        sum += 100038;
+        sum += 100039;
+        sum += 100040;
+        sum += 100041;
+        sum += 100042;
+        sum += 100043;
+        sum += 100044;
+        sum += 100045;
+        sum += 100046;
+        sum += 100047;
+        sum += 100048;
+        sum += 100049;
+        sum += 100050;
+        sum += 100051;
+        sum += 100052;
+        sum += 100053;
+        sum += 100054;
+        sum += 100055;
+        sum += 100056;
+        sum += 100057;
+        sum += 100058;
+        sum += 100059;
+        sum += 100060;
+        sum += 100061;
+        sum += 100062;
+        sum += 100063;
+        sum += 100064;
+        sum += 100065;
+        sum += 100066;
+        sum += 100067;
+        sum += 100068;
+        sum += 100069;
+        sum += 100070;
+        sum += 100071;
+        sum += 100072;
+        sum += 100073;
+        sum += 100074;
+        sum += 100075;
+        sum += 100076;
+        sum += 100077;
+        sum += 100078;
+        sum += 100079;
+        sum += 100080;
+        sum += 100081;
+        sum += 100082;
+        sum += 100083;
+        sum += 100084;
+        sum += 100085;
+        sum += 100086;
+        sum += 100087;
+        sum += 100088;
+        sum += 100089;
+        sum += 100090;
+        sum += 100091;
+        sum += 100092;
+        sum += 100093;
+        sum += 100094;
+        sum += 100095;
+        sum += 100096;
+        sum += 100097;
+        sum += 100098;
+        sum += 100099;
+        sum += 100100;
+        sum += 100101;
+        sum += 100102;
+        sum += 100103;
+        sum += 100104;
+        sum += 100105;
+        sum += 100106;
+        sum += 100107;
+        sum += 100108;
+        sum += 100109;
+        sum += 100110;
+        sum += 100111;
+        sum += 100112;
+        sum += 100113;
+        sum += 100114;
+        sum += 100115;
+        sum += 100116;
+        sum += 100117;
+        sum += 100118;
+        sum += 100119;
+        sum += 100120;
+        sum += 100121;
+        sum += 100122;
+        sum += 100123;
+        sum += 100124;
+        sum += 100125;
+        sum += 100126;
+        sum += 100127;
+        sum += 100128;
+        sum += 100129;
+        sum += 100130;
+        sum += 100131;
+        sum += 100132;
+        sum += 100133;
+        sum += 100134;
+        sum += 100135;
+        sum += 100136;
+        sum += 100137;
+        sum += 100138;
+        sum += 100139;
+        sum += 100140;
+        sum += 100141;
+        sum += 100142;
+        sum += 100143;
+        sum += 100144;
+        sum += 100145;
+        sum += 100146;
+        sum += 100147;
+        sum += 100148;
+        sum += 100149;
+        sum += 100150;
+        sum += 100151;
+        sum += 100152;
+        sum += 100153;
+        sum += 100154;
+        sum += 100155;
+        sum += 100156;
+        sum += 100157;
+        sum += 100158;
+        sum += 100159;
+        sum += 100160;
+        sum += 100161;
+        sum += 100162;
+        sum += 100163;
+        sum += 100164;
+        sum += 100165;
+        sum += 100166;
+        sum += 100167;
+        sum += 100168;
+        sum += 100169;
+        sum += 100170;
+        sum += 100171;
+        sum += 100172;
+        sum += 100173;
+        sum += 100174;
+        sum += 100175;
+        sum += 100176;
+        sum += 100177;
+        sum += 100178;
+        sum += 100179;
+        sum += 100180;
+        sum += 100181;
+        sum += 100182;
+        sum += 100183;
+        sum += 100184;
+        sum += 100185;
+        sum += 100186;
+        sum += 100187;
+        sum += 100188;
+        sum += 100189;
+        sum += 100190;
+        sum += 100191;
+        sum += 100192;
+        sum += 100193;
+        sum += 100194;
+        sum += 100195;
+        sum += 100196;
+        sum += 100197;
+        sum += 100198;
+        sum += 100199;
+        sum += 100200;
+        sum += 100201;
+        sum += 100202;
+        sum += 100203;
+        sum += 100204;
+        sum += 100205;
+        sum += 100206;
+        sum += 100207;
+        sum += 100208;
+        sum += 100209;
+        sum += 100210;
+        sum += 100211;
+        sum += 100212;
+        sum += 100213;
+        sum += 100214;
+        sum += 100215;
+        sum += 100216;
+        sum += 100217;
+        sum += 100218;
+        sum += 100219;
+        sum += 100220;
+        sum += 100221;
+        sum += 100222;
+        sum += 100223;
+        sum += 100224;
+        sum += 100225;
+        sum += 100226;
+        sum += 100227;
+        sum += 100228;
+        sum += 100229;
+        sum += 100230;
+        sum += 100231;
+        sum += 100232;
+        sum += 100233;
+        sum += 100234;
+        sum += 100235;
+        sum += 100236;
+        sum += 100237;
+        sum += 100238;
+        sum += 100239;
+        sum += 100240;
+        sum += 100241;
+        sum += 100242;
+        sum += 100243;
+        sum += 100244;
+        sum += 100245;
+        sum += 100246;
+        sum += 100247;
+        sum += 100248;
+        sum += 100249;
+        sum += 100250;
+        sum += 100251;
+        sum += 100252;
+        sum += 100253;
+        sum += 100254;
+        sum += 100255;
+        sum += 100256;
+        sum += 100257;
+        sum += 100258;
+        sum += 100259;
+        sum += 100260;
+        sum += 100261;
+        sum += 100262;
+        sum += 100263;
+        sum += 100264;
+        sum += 100265;
+        sum += 100266;
+        sum += 100267;
+        sum += 100268;
+        sum += 100269;
+        sum += 100270;
+        sum += 100271;
+        sum += 100272;
+        sum += 100273;
+        sum += 100274;
+        sum += 100275;
+        sum += 100276;
+        sum += 100277;
+        sum += 100278;
+        sum += 100279;
+        sum += 100280;
+        sum += 100281;
+        sum += 100282;
+        sum += 100283;
+        sum += 100284;
+        sum += 100285;
+        sum += 100286;
+        sum += 100287;
+        sum += 100288;
+        sum += 100289;
+        sum += 100290;
+        sum += 100291;
+        sum += 100292;
+        sum += 100293;
+        sum += 100294;
+        sum += 100295;
+        sum += 100296;
+        sum += 100297;
+        sum += 100298;
+        sum += 100299;
+        return sum;
+    }
+
+    static float floats() {
+        float sum = 0;
+        sum += 1.25f;
+        sum += 2.5f;
+        return sum;
+    }
+
+    static String wide() {
+        return "wide";
+    }
+
+    static Class<?> type() {
+        return Greeter.class;
+    }
+}
@@ -0,0 +1,18 @@
+package java.io;
+
+// Minimal java/io/ByteArrayInputStream, with the fields of the JDK one.
+public class ByteArrayInputStream extends InputStream {
+    protected byte[] buf;
+    protected int pos;
+    protected int mark;
+    protected int count;
+
+    public ByteArrayInputStream(byte[] buf) {
+        this.buf = buf;
+        this.count = buf.length;
+    }
+
+    public int read() {
+        return (pos < count) ? (buf[pos++] & 0xff) : -1;
+    }
+}
@@ -0,0 +1,16 @@
+package java.io;
+
+// Minimal java/io/FileDescriptor, to run the tests without a JDK.
+public final class FileDescriptor {
+    private int fd;
+
+    public FileDescriptor() {
+        fd = -1;
+    }
+
+    private native void close0();
+
+    void close() {
+        close0();
+    }
+}
@@ -0,0 +1,47 @@
+package java.io;
+
+// Minimal java/io/FileInputStream, to run the tests without a JDK.
+public class FileInputStream extends InputStream {
+    private final FileDescriptor fd;
+
+    public FileInputStream(String name) {
+        fd = new FileDescriptor();
+        open0(name);
+    }
+
+    private native void open0(String name);
+
+    private native int read0();
+
+    private native int readBytes(byte[] b, int off, int len);
+
+    private native long skip0(long n);
+
+    private native int available0();
+
+    private native long position0();
+
+    public int read() {
+        return read0();
+    }
+
+    public int read(byte[] b, int off, int len) {
+        return readBytes(b, off, len);
+    }
+
+    public long skip(long n) {
+        return skip0(n);
+    }
+
+    public int available() {
+        return available0();
+    }
+
+    public long position() {
+        return position0();
+    }
+
+    public void close() {
+        fd.close();
+    }
+}
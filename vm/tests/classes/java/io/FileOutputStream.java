@@ -0,0 +1,29 @@
+package java.io;
+
+// Minimal java/io/FileOutputStream, to run the tests without a JDK.
+public class FileOutputStream {
+    private final FileDescriptor fd;
+
+    public FileOutputStream(String name, boolean append) {
+        fd = new FileDescriptor();
+        open0(name, append);
+    }
+
+    private native void open0(String name, boolean append);
+
+    private native void write(int b, boolean append);
+
+    private native void writeBytes(byte[] b, int off, int len, boolean append);
+
+    public void write(int b) {
+        write(b, false);
+    }
+
+    public void write(byte[] b, int off, int len) {
+        writeBytes(b, off, len, false);
+    }
+
+    public void close() {
+        fd.close();
+    }
+}
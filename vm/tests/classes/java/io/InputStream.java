@@ -0,0 +1,6 @@
+package java.io;
+
+// Minimal java/io/InputStream, to run the tests without a JDK.
+public abstract class InputStream {
+    public abstract int read();
+}
@@ -0,0 +1,4 @@
+package java.io;
+
+// Minimal java/io/Serializable, implemented by the array classes.
+public interface Serializable {}
@@ -0,0 +1,6 @@
+package java.lang;
+
+// Minimal java/lang/AssertionError, thrown by the assert statements.
+public class AssertionError extends Error {
+    public AssertionError() {}
+}
@@ -0,0 +1,56 @@
+package java.lang;
+
+// Minimal java/lang/Class, to run the tests without a JDK.
+public final class Class<T> {
+    private Class() {}
+
+    private native Class<?> getDeclaringClass0();
+
+    private native Object[] getEnclosingMethod0();
+
+    private native Class<?>[] getDeclaredClasses0();
+
+    private native String getSimpleBinaryName0();
+
+    public native int getModifiers();
+
+    public native boolean isPrimitive();
+
+    static native Class<?> getPrimitiveClass(String name);
+
+    private static native boolean desiredAssertionStatus0(Class<?> clazz);
+
+    private static native Class<?> forName0(String name, boolean initialize, ClassLoader loader, Class<?> caller);
+
+    // Without class loaders, the caller is not needed.
+    public static Class<?> forName(String className) {
+        return forName0(className, true, null, null);
+    }
+
+    public Class<?> getDeclaringClass() {
+        return getDeclaringClass0();
+    }
+
+    public Class<?>[] getDeclaredClasses() {
+        return getDeclaredClasses0();
+    }
+
+    // Enclosing class, method name and method descriptor, as in the JDK.
+    public Object[] getEnclosingMethodInfo() {
+        return getEnclosingMethod0();
+    }
+
+    public String getSimpleBinaryName() {
+        return getSimpleBinaryName0();
+    }
+
+    // Without class loaders, the status only depends on the options of the VM.
+    public boolean desiredAssertionStatus() {
+        return desiredAssertionStatus0(this);
+    }
+
+    // Replaced by an intrinsic reading the class path.
+    public java.io.InputStream getResourceAsStream(String name) {
+        return null;
+    }
+}
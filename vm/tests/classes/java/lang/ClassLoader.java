@@ -0,0 +1,9 @@
+package java.lang;
+
+// Minimal java/lang/ClassLoader, to run the tests without a JDK.
+public abstract class ClassLoader {
+    // Replaced by an intrinsic reading the class path.
+    public static java.io.InputStream getSystemResourceAsStream(String name) {
+        return null;
+    }
+}
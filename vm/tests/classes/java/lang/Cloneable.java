@@ -0,0 +1,4 @@
+package java.lang;
+
+// Minimal java/lang/Cloneable, implemented by the array classes.
+public interface Cloneable {}
@@ -0,0 +1,6 @@
+package java.lang;
+
+// Minimal java/lang/Comparable, implemented by java/lang/String.
+public interface Comparable<T> {
+    int compareTo(T o);
+}
@@ -0,0 +1,6 @@
+package java.lang;
+
+// Minimal java/lang/Error, to declare errors without a JDK.
+public class Error extends Throwable {
+    public Error() {}
+}
@@ -0,0 +1,6 @@
+package java.lang;
+
+// Minimal java/lang/Exception, to declare exceptions without a JDK.
+public class Exception extends Throwable {
+    public Exception() {}
+}
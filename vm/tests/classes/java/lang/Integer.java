@@ -0,0 +1,20 @@
+package java.lang;
+
+// Minimal java/lang/Integer, to run the tests without a JDK.
+public final class Integer {
+    public static final Class<Integer> TYPE = (Class<Integer>) Class.getPrimitiveClass("int");
+
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int i) {
+        return new Integer(i);
+    }
+
+    public int intValue() {
+        return value;
+    }
+}
@@ -0,0 +1,20 @@
+package java.lang;
+
+// Stub of java.lang.Math, only implemented by the intrinsics of the VM.
+public final class Math {
+    public static final double PI = 3.141592653589793;
+
+    private Math() {}
+
+    public static native double sin(double a);
+    public static native double cos(double a);
+    public static native double sqrt(double a);
+    public static native double floor(double a);
+    public static native double ceil(double a);
+    public static native double pow(double a, double b);
+    public static native int abs(int a);
+    public static native long min(long a, long b);
+    public static native int max(int a, int b);
+    public static native double min(double a, double b);
+    public static native double max(double a, double b);
+}
@@ -0,0 +1,11 @@
+package java.lang;
+
+// Minimal java/lang/Object, to run the tests without a JDK.
+public class Object {
+    public Object() {}
+
+    // Implemented by the intrinsics of the VM.
+    public native boolean equals(Object obj);
+    public native int hashCode();
+    public native String toString();
+}
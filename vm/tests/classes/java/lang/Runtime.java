@@ -0,0 +1,22 @@
+package java.lang;
+
+// Minimal java/lang/Runtime, to run the tests without a JDK.
+public class Runtime {
+    private static final Runtime currentRuntime = new Runtime();
+
+    private Runtime() {}
+
+    public static Runtime getRuntime() {
+        return currentRuntime;
+    }
+
+    public native int availableProcessors();
+    public native long freeMemory();
+    public native long totalMemory();
+    public native long maxMemory();
+    public native void gc();
+
+    // Implemented by the intrinsics of the VM.
+    public native void addShutdownHook(Thread hook);
+    public native boolean removeShutdownHook(Thread hook);
+}
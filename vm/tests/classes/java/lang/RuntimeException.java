@@ -0,0 +1,6 @@
+package java.lang;
+
+// Minimal java/lang/RuntimeException, required by javac to compile the catch clauses.
+public class RuntimeException extends Exception {
+    public RuntimeException() {}
+}
@@ -0,0 +1,8 @@
+package java.lang;
+
+// Minimal java/lang/Shutdown, to run the tests without a JDK.
+class Shutdown {
+    static native void beforeHalt();
+
+    static native void halt0(int status);
+}
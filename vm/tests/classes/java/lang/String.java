@@ -0,0 +1,21 @@
+package java.lang;
+
+// Minimal java/lang/String, to run the tests without a JDK.
+public final class String implements Comparable<String> {
+    private final char[] value;
+    private int hash;
+
+    public String(char[] value) {
+        this.value = value;
+    }
+
+    // Implemented by the intrinsics of the VM.
+    public native boolean equals(Object anObject);
+    public native int length();
+    public native char charAt(int index);
+    public native int hashCode();
+    public native int compareTo(String anotherString);
+    public native boolean startsWith(String prefix);
+    public native int indexOf(int ch);
+    public native int indexOf(String str, int fromIndex);
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+// Minimal java/lang/System, to run the tests without a JDK.
+public final class System {
+    private System() {}
+
+    public static native long currentTimeMillis();
+
+    public static native long nanoTime();
+
+    public static native int identityHashCode(Object x);
+
+    // Without shutdown hooks, as Runtime.halt.
+    public static void exit(int status) {
+        Shutdown.beforeHalt();
+        Shutdown.halt0(status);
+    }
+}
@@ -0,0 +1,50 @@
+package java.lang;
+
+// Minimal java/lang/Thread, to run the tests without a JDK.
+public class Thread implements Runnable {
+    public static final int MIN_PRIORITY = 1;
+    public static final int NORM_PRIORITY = 5;
+    public static final int MAX_PRIORITY = 10;
+
+    private volatile String name;
+    private int priority = NORM_PRIORITY;
+    private boolean daemon;
+    private Runnable target;
+
+    public Thread(Runnable target) {
+        this.target = target;
+    }
+
+    public void run() {
+        if (target != null) {
+            target.run();
+        }
+    }
+
+    public void start() {
+        start0();
+    }
+
+    public static native Thread currentThread();
+
+    public final void setName(String name) {
+        this.name = name;
+        setNativeName(name);
+    }
+
+    public final String getName() {
+        return name;
+    }
+
+    public final void setPriority(int newPriority) {
+        setPriority0(priority = newPriority);
+    }
+
+    public final void setDaemon(boolean on) {
+        daemon = on;
+    }
+
+    private native void setNativeName(String name);
+    private native void start0();
+    private native void setPriority0(int newPriority);
+}
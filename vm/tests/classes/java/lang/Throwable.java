@@ -0,0 +1,6 @@
+package java.lang;
+
+// Minimal java/lang/Throwable, to declare exceptions without a JDK.
+public class Throwable {
+    public Throwable() {}
+}
@@ -0,0 +1,6 @@
+package java.lang.invoke;
+
+// Minimal java/lang/invoke/LambdaMetafactory, to run the tests without a JDK.
+// Its bootstrap methods are implemented by the VM.
+public final class LambdaMetafactory {
+}
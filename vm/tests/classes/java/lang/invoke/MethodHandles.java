@@ -0,0 +1,7 @@
+package java.lang.invoke;
+
+// Minimal java/lang/invoke/MethodHandles, to run the tests without a JDK.
+public final class MethodHandles {
+    public static final class Lookup {
+    }
+}
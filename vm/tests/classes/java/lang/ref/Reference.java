@@ -0,0 +1,17 @@
+package java.lang.ref;
+
+public abstract class Reference<T> {
+    private T referent;
+
+    Reference(T referent) {
+        this.referent = referent;
+    }
+
+    public T get() {
+        return this.referent;
+    }
+
+    public void clear() {
+        this.referent = null;
+    }
+}
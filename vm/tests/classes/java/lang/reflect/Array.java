@@ -0,0 +1,18 @@
+package java.lang.reflect;
+
+// Minimal java/lang/reflect/Array, to run the tests without a JDK.
+public final class Array {
+    private Array() {}
+
+    public static Object newInstance(Class<?> componentType, int length) {
+        return newArray(componentType, length);
+    }
+
+    private static native Object newArray(Class<?> componentType, int length);
+
+    public static native int getLength(Object array);
+
+    public static native Object get(Object array, int index);
+
+    public static native void set(Object array, int index, Object value);
+}
@@ -0,0 +1,75 @@
+package java.util;
+
+// Minimal java/util/Arrays, to run the tests without a JDK.
+//
+// The methods are plain loops, replaced by the intrinsics of the VM.
+public class Arrays {
+    public static void fill(int[] a, int val) {
+        fill(a, 0, a.length, val);
+    }
+
+    public static void fill(int[] a, int fromIndex, int toIndex, int val) {
+        for (int i = fromIndex; i < toIndex; i++) {
+            a[i] = val;
+        }
+    }
+
+    public static void fill(double[] a, double val) {
+        for (int i = 0; i < a.length; i++) {
+            a[i] = val;
+        }
+    }
+
+    public static void fill(byte[] a, byte val) {
+        for (int i = 0; i < a.length; i++) {
+            a[i] = val;
+        }
+    }
+
+    public static int[] copyOf(int[] original, int newLength) {
+        int[] copy = new int[newLength];
+        for (int i = 0; i < newLength && i < original.length; i++) {
+            copy[i] = original[i];
+        }
+        return copy;
+    }
+
+    public static int[] copyOfRange(int[] original, int from, int to) {
+        int[] copy = new int[to - from];
+        for (int i = from; i < to && i < original.length; i++) {
+            copy[i - from] = original[i];
+        }
+        return copy;
+    }
+
+    public static boolean equals(int[] a, int[] a2) {
+        if (a == a2) {
+            return true;
+        }
+        if (a == null || a2 == null || a.length != a2.length) {
+            return false;
+        }
+        for (int i = 0; i < a.length; i++) {
+            if (a[i] != a2[i]) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public static boolean equals(double[] a, double[] a2) {
+        if (a == a2) {
+            return true;
+        }
+        if (a == null || a2 == null || a.length != a2.length) {
+            return false;
+        }
+        for (int i = 0; i < a.length; i++) {
+            // NaN equals NaN, as Double.doubleToLongBits compares them.
+            if (a[i] != a2[i] && !(a[i] != a[i] && a2[i] != a2[i])) {
+                return false;
+            }
+        }
+        return true;
+    }
+}
@@ -0,0 +1,6 @@
+package jdk.internal.reflect;
+
+// Minimal jdk/internal/reflect/Reflection, to run the tests without a JDK.
+public class Reflection {
+    public static native Class<?> getCallerClass();
+}